@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::codecs::base::TxFieldKey;
+use crate::codecs::errors::ParserError;
+use crate::domain::money::{self, AmountOverflow};
+use crate::domain::tx::*;
+
+const DAY_KEY: &str = "DAY";
+const AGGREGATE_COLUMNS: [&str; 4] = ["COUNT", "SUM", "MIN", "MAX"];
+
+/// Dimension records are grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// `TX_TYPE`.
+    Kind,
+    /// `STATUS`.
+    Status,
+    /// `FROM_USER_ID`.
+    FromAccount,
+    /// `TO_USER_ID`.
+    ToAccount,
+    /// Local date of `TIMESTAMP` at the offset.
+    Day(UtcOffset),
+}
+
+impl GroupBy {
+    pub(crate) fn value(&self, tx: &TxRecord) -> GroupValue {
+        match self {
+            GroupBy::Kind => GroupValue::Kind(tx.kind),
+            GroupBy::Status => GroupValue::Status(tx.status),
+            GroupBy::FromAccount => GroupValue::Account(tx.from.clone()),
+            GroupBy::ToAccount => GroupValue::Account(tx.to.clone()),
+            GroupBy::Day(offset) => GroupValue::Day(tx.ts.at_offset(*offset).local_date()),
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = ParserError;
+
+    /// Parses `TX_TYPE`, `STATUS`, `FROM_USER_ID`, `TO_USER_ID` or `DAY`, days are UTC ones.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if DAY_KEY == s {
+            return Ok(GroupBy::Day(UtcOffset::UTC));
+        }
+        match s.parse()? {
+            TxFieldKey::TxKind => Ok(GroupBy::Kind),
+            TxFieldKey::Status => Ok(GroupBy::Status),
+            TxFieldKey::FromUserId => Ok(GroupBy::FromAccount),
+            TxFieldKey::ToUserId => Ok(GroupBy::ToAccount),
+            other => Err(ParserError::UnparsableKey(other.to_string())),
+        }
+    }
+}
+
+impl Display for GroupBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupBy::Kind => write!(f, "{}", TxFieldKey::TxKind),
+            GroupBy::Status => write!(f, "{}", TxFieldKey::Status),
+            GroupBy::FromAccount => write!(f, "{}", TxFieldKey::FromUserId),
+            GroupBy::ToAccount => write!(f, "{}", TxFieldKey::ToUserId),
+            GroupBy::Day(_) => write!(f, "{}", DAY_KEY),
+        }
+    }
+}
+
+/// Value of a group-by dimension.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING_SNAKE_CASE"))]
+pub enum GroupValue {
+    /// Kind of records.
+    Kind(TxKind),
+    /// Status of records.
+    Status(TxStatus),
+    /// Source or destination account of records.
+    Account(AccountType),
+    /// Local date of records, e.g. `2024-01-31`.
+    Day(String),
+}
+
+impl Display for GroupValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupValue::Kind(kind) => write!(f, "{}", kind),
+            GroupValue::Status(status) => write!(f, "{}", status),
+            GroupValue::Account(account) => write!(f, "{}", account),
+            GroupValue::Day(day) => write!(f, "{}", day),
+        }
+    }
+}
+
+/// Records of one group and aggregates of their amounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Group {
+    /// Values of group-by dimensions, in their order.
+    pub key: Vec<GroupValue>,
+    /// Number of records.
+    pub count: usize,
+    /// Sum of amounts at the finest scale of them.
+    pub sum: Money,
+    /// Smallest amount.
+    pub min: Money,
+    /// Largest amount.
+    pub max: Money,
+}
+
+impl Group {
+    fn new(key: Vec<GroupValue>, amount: Money) -> Self {
+        Self {
+            key,
+            count: 1,
+            sum: amount,
+            min: amount,
+            max: amount,
+        }
+    }
+
+    fn add(&mut self, amount: Money) -> Result<(), AmountOverflow> {
+        self.sum = money::add(self.sum, amount)?;
+        self.count += 1;
+        self.min = self.min.min(amount);
+        self.max = self.max.max(amount);
+        Ok(())
+    }
+}
+
+/// Result of [`aggregate`], groups ordered by their keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aggregation {
+    /// Group-by dimensions.
+    pub dimensions: Vec<GroupBy>,
+    /// Groups of records, only non-empty ones.
+    pub groups: Vec<Group>,
+}
+
+impl Aggregation {
+    /// Group of the key, `None` for groups without records.
+    pub fn get(&self, key: &[GroupValue]) -> Option<&Group> {
+        self.groups.iter().find(|group| group.key == key)
+    }
+
+    /// Writes groups as CSV with a column per dimension followed by `COUNT,SUM,MIN,MAX`.
+    pub fn write_csv<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        let header: Vec<String> = self
+            .dimensions
+            .iter()
+            .map(|dimension| dimension.to_string())
+            .chain(AGGREGATE_COLUMNS.iter().map(|column| column.to_string()))
+            .collect();
+        writeln!(w, "{}", header.join(","))?;
+        for group in &self.groups {
+            let key: Vec<String> = group.key.iter().map(|value| value.to_string()).collect();
+            writeln!(
+                w,
+                "{},{},{},{},{}",
+                key.join(","),
+                group.count,
+                group.sum,
+                group.min,
+                group.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Groups records by `dimensions` and aggregates amounts of every group, no dimensions put
+/// all records in one group; fails if a sum overflows.
+pub fn aggregate(data: &[TxRecord], dimensions: &[GroupBy]) -> Result<Aggregation, AmountOverflow> {
+    let mut groups: BTreeMap<Vec<GroupValue>, Group> = BTreeMap::new();
+    for tx in data {
+        let key: Vec<GroupValue> = dimensions
+            .iter()
+            .map(|dimension| dimension.value(tx))
+            .collect();
+        match groups.get_mut(&key) {
+            Some(group) => group.add(tx.amount)?,
+            None => {
+                groups.insert(key.clone(), Group::new(key, tx.amount));
+            }
+        }
+    }
+    Ok(Aggregation {
+        dimensions: dimensions.to_vec(),
+        groups: groups.into_values().collect(),
+    })
+}
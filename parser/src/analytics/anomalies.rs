@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::domain::key::{RecordIdentity, RecordKey};
+use crate::domain::tx::*;
+
+/// Thresholds of [`detect`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnomalyOptions {
+    /// Moment timestamps may not be after, current time by default.
+    pub now: TxTimestamp,
+    /// Milliseconds timestamps may be ahead of `now`, e.g. for clock skew between systems.
+    pub future_tolerance_ms: u64,
+    /// Amounts below are flagged.
+    pub min_amount: Option<Money>,
+    /// Amounts above are flagged.
+    pub max_amount: Option<Money>,
+}
+
+/// Suspicious trait of a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Anomaly {
+    /// Id was already used by a record with other fields, flagged at the first record of
+    /// every other payload.
+    ConflictingId {
+        /// Position of the first record of the id.
+        first: usize,
+    },
+    /// Deposit of zero or negative amount.
+    NonPositiveDeposit,
+    /// Timestamp is after current time.
+    FutureTimestamp,
+    /// Funds move from an account to itself.
+    SelfTransfer,
+    /// Amount is below [`AnomalyOptions::min_amount`].
+    AmountBelowMinimum,
+    /// Amount is above [`AnomalyOptions::max_amount`].
+    AmountAboveMaximum,
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anomaly::ConflictingId { first } => {
+                write!(f, "id is used by record #{} with other fields", first)
+            }
+            Anomaly::NonPositiveDeposit => write!(f, "deposit of non-positive amount"),
+            Anomaly::FutureTimestamp => write!(f, "timestamp is in the future"),
+            Anomaly::SelfTransfer => write!(f, "funds move to the same account"),
+            Anomaly::AmountBelowMinimum => write!(f, "amount is below minimum"),
+            Anomaly::AmountAboveMaximum => write!(f, "amount is above maximum"),
+        }
+    }
+}
+
+/// Anomaly of the record at `position` of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Position of the record, starting from 0.
+    pub position: usize,
+    /// Id of the record.
+    pub id: TxIdType,
+    /// The anomaly.
+    pub anomaly: Anomaly,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "record #{} (TX_ID {}): {}",
+            self.position, self.id, self.anomaly
+        )
+    }
+}
+
+/// Flags suspicious records, findings are ordered by position, then by anomaly.
+///
+/// Exact duplicates are not flagged, see [`dedup`](crate::transform::dedup) for them.
+pub fn detect(data: &[TxRecord], options: &AnomalyOptions) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    // identities of records seen for every id, with position of the first record of the id
+    let mut ids: HashMap<TxIdType, (usize, Vec<RecordIdentity>)> = HashMap::new();
+    let future = options
+        .now
+        .millis()
+        .saturating_add(options.future_tolerance_ms);
+    for (position, tx) in data.iter().enumerate() {
+        let mut flag = |anomaly| {
+            findings.push(Finding {
+                position,
+                id: tx.id,
+                anomaly,
+            })
+        };
+        let identity = RecordKey::FullRecord.identity(tx);
+        let (first, identities) = ids.entry(tx.id).or_insert_with(|| (position, Vec::new()));
+        if !identities.is_empty() && !identities.contains(&identity) {
+            flag(Anomaly::ConflictingId { first: *first });
+        }
+        if !identities.contains(&identity) {
+            identities.push(identity);
+        }
+        if TxKind::Deposit == tx.kind && tx.amount <= Money::default() {
+            flag(Anomaly::NonPositiveDeposit);
+        }
+        if tx.ts.millis() > future {
+            flag(Anomaly::FutureTimestamp);
+        }
+        if tx.source().is_some() && tx.source() == tx.destination() {
+            flag(Anomaly::SelfTransfer);
+        }
+        if options.min_amount.is_some_and(|min| tx.amount < min) {
+            flag(Anomaly::AmountBelowMinimum);
+        }
+        if options.max_amount.is_some_and(|max| tx.amount > max) {
+            flag(Anomaly::AmountAboveMaximum);
+        }
+    }
+    findings
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use crate::domain::money;
+use crate::domain::tx::*;
+
+/// Record the ledger refused to apply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LedgerError {
+    /// Record would take the account below zero.
+    Overdraft {
+        /// Id of the record.
+        id: TxIdType,
+        /// Debited account.
+        account: AccountType,
+        /// Balance of the account before the record.
+        balance: Money,
+        /// Amount of the record.
+        amount: Money,
+    },
+    /// Record of a kind moving funds between accounts has the same account on both sides.
+    SameAccount {
+        /// Id of the record.
+        id: TxIdType,
+        /// Kind of the record.
+        kind: TxKind,
+    },
+    /// Amount of the record is negative, kind tells the direction of funds.
+    NegativeAmount {
+        /// Id of the record.
+        id: TxIdType,
+    },
+    /// Balance does not fit 64-bit minor units.
+    Overflow {
+        /// Id of the record.
+        id: TxIdType,
+    },
+}
+
+impl std::error::Error for LedgerError {}
+
+impl Display for LedgerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LedgerError::Overdraft {
+                id,
+                account,
+                balance,
+                amount,
+            } => write!(
+                f,
+                "#{} overdraws account {}: balance {}, amount {}",
+                id, account, balance, amount
+            ),
+            LedgerError::SameAccount { id, kind } => {
+                write!(f, "#{} is {} to the same account", id, kind)
+            }
+            LedgerError::NegativeAmount { id } => write!(f, "#{} has negative amount", id),
+            LedgerError::Overflow { id } => {
+                write!(f, "#{} overflows balance of 64-bit minor units", id)
+            }
+        }
+    }
+}
+
+/// Balances of accounts built by applying records in order.
+///
+/// Only successful records move funds: deposits credit `to` account, withdrawals and fees
+/// debit `from` one, other kinds move amount from `from` to `to`, see
+/// [`TxRecord::source`] and [`TxRecord::destination`]. Records of other statuses are skipped.
+/// Fees of records are not taken into account.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ledger {
+    balances: BTreeMap<AccountType, Money>,
+    /// Accept records taking accounts below zero.
+    pub allow_overdraft: bool,
+    applied: usize,
+    skipped: usize,
+}
+
+impl Ledger {
+    /// Empty ledger rejecting overdrafts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets opening balance of the account.
+    pub fn open(&mut self, account: AccountType, balance: Money) {
+        self.balances.insert(account, balance);
+    }
+
+    /// Applies the record; a rejected record leaves balances as they were.
+    pub fn apply(&mut self, tx: &TxRecord) -> Result<(), LedgerError> {
+        if TxStatus::Success != tx.status {
+            self.skipped += 1;
+            return Ok(());
+        }
+        if tx.amount < Money::default() {
+            return Err(LedgerError::NegativeAmount { id: tx.id });
+        }
+        let (source, destination) = (tx.source(), tx.destination());
+        if source.is_some() && source == destination {
+            return Err(LedgerError::SameAccount {
+                id: tx.id,
+                kind: tx.kind,
+            });
+        }
+        let overflow = |_| LedgerError::Overflow { id: tx.id };
+        let debited = match source {
+            Some(account) => {
+                let balance = self.balance(account);
+                let after = money::sub(balance, tx.amount).map_err(overflow)?;
+                if after < Money::default() && !self.allow_overdraft {
+                    return Err(LedgerError::Overdraft {
+                        id: tx.id,
+                        account: account.clone(),
+                        balance,
+                        amount: tx.amount,
+                    });
+                }
+                Some((account, after))
+            }
+            None => None,
+        };
+        let credited = match destination {
+            Some(account) => Some((
+                account,
+                money::add(self.balance(account), tx.amount).map_err(overflow)?,
+            )),
+            None => None,
+        };
+        for (account, balance) in debited.into_iter().chain(credited) {
+            self.balances.insert(account.clone(), balance);
+        }
+        self.applied += 1;
+        Ok(())
+    }
+
+    /// Applies records in order, returns errors of rejected ones.
+    pub fn apply_all<'a, I: IntoIterator<Item = &'a TxRecord>>(
+        &mut self,
+        records: I,
+    ) -> Vec<LedgerError> {
+        records
+            .into_iter()
+            .filter_map(|tx| self.apply(tx).err())
+            .collect()
+    }
+
+    /// Balance of the account, zero for accounts without records.
+    pub fn balance(&self, account: &AccountType) -> Money {
+        self.balances.get(account).copied().unwrap_or_default()
+    }
+
+    /// Balances of all accounts ordered by account.
+    pub fn balances(&self) -> impl Iterator<Item = (&AccountType, &Money)> {
+        self.balances.iter()
+    }
+
+    /// Number of records which moved funds.
+    pub fn applied(&self) -> usize {
+        self.applied
+    }
+
+    /// Number of records skipped for their status.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+}
@@ -0,0 +1,14 @@
+/// Group-by aggregation of amounts.
+pub mod aggregate;
+/// Detection of suspicious records.
+pub mod anomalies;
+/// Account balances computed from records.
+pub mod balance;
+/// Overview statistics of datasets.
+pub mod summary;
+/// Top-N rankings of records and accounts.
+pub mod top;
+/// Bucketing of records into time windows.
+pub mod window;
+
+pub use summary::{Summary, summarize};
@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use crate::domain::money;
+use crate::domain::tx::*;
+use crate::json::JsonValue;
+
+/// Statistics of record amounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AmountStats {
+    /// Smallest amount.
+    pub min: Money,
+    /// Largest amount.
+    pub max: Money,
+    /// Mean amount at the scale of total, rounded half away from zero; `None` if total
+    /// does not fit.
+    pub mean: Option<Money>,
+    /// Sum of amounts at the finest scale of them, `None` if it does not fit 64-bit
+    /// minor units.
+    pub total: Option<Money>,
+}
+
+/// Overview of a dataset for sanity checks, e.g. after conversion.
+///
+/// Displayed as lines of text, [`Summary::to_json`] gives the same as a JSON object.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Summary {
+    /// Number of records.
+    pub records: usize,
+    /// Number of records of every kind present.
+    pub kinds: BTreeMap<TxKind, usize>,
+    /// Number of records of every status present.
+    pub statuses: BTreeMap<TxStatus, usize>,
+    /// Amount statistics, `None` without records.
+    pub amounts: Option<AmountStats>,
+    /// Earliest timestamp.
+    pub first_timestamp: Option<TxTimestamp>,
+    /// Latest timestamp.
+    pub last_timestamp: Option<TxTimestamp>,
+    /// Number of distinct accounts on either side, outside ones not counted, see
+    /// [`TxRecord::source`] and [`TxRecord::destination`].
+    pub accounts: usize,
+    /// Number of distinct accounts funds are taken from.
+    pub sources: usize,
+    /// Number of distinct accounts funds go to.
+    pub destinations: usize,
+}
+
+/// Summarizes records.
+pub fn summarize(data: &[TxRecord]) -> Summary {
+    let mut summary = Summary {
+        records: data.len(),
+        ..Default::default()
+    };
+    let mut sources = BTreeSet::new();
+    let mut destinations = BTreeSet::new();
+    for tx in data {
+        *summary.kinds.entry(tx.kind).or_default() += 1;
+        *summary.statuses.entry(tx.status).or_default() += 1;
+        summary.first_timestamp = Some(summary.first_timestamp.map_or(tx.ts, |ts| ts.min(tx.ts)));
+        summary.last_timestamp = Some(summary.last_timestamp.map_or(tx.ts, |ts| ts.max(tx.ts)));
+        sources.extend(tx.source());
+        destinations.extend(tx.destination());
+    }
+    summary.sources = sources.len();
+    summary.destinations = destinations.len();
+    summary.accounts = sources.union(&destinations).count();
+    summary.amounts = amount_stats(data);
+    summary
+}
+
+fn amount_stats(data: &[TxRecord]) -> Option<AmountStats> {
+    let min = data.iter().map(|tx| tx.amount).min()?;
+    let max = data.iter().map(|tx| tx.amount).max()?;
+    let total = money::sum_amounts(data).ok();
+    let mean = total.map(|total| {
+        let (units, count) = (i128::from(total.minor_units), data.len() as i128);
+        let rounded = (2 * units + units.signum() * count) / (2 * count);
+        // mean lies between min and max, so it fits
+        Money::new(rounded as i64, total.scale)
+    });
+    Some(AmountStats {
+        min,
+        max,
+        mean,
+        total,
+    })
+}
+
+impl Summary {
+    /// Summary as compact JSON object, amounts are decimal numbers and timestamps RFC 3339
+    /// strings.
+    pub fn to_json(&self) -> String {
+        let counts = |counts: Vec<(String, usize)>| {
+            JsonValue::Object(
+                counts
+                    .into_iter()
+                    .map(|(key, count)| (key, number(count)))
+                    .collect(),
+            )
+        };
+        let amount = |amount: Option<Money>| amount.map_or(JsonValue::Null, number);
+        let timestamp = |ts: Option<TxTimestamp>| {
+            ts.map_or(JsonValue::Null, |ts| JsonValue::String(ts.to_rfc3339()))
+        };
+        let amounts = match &self.amounts {
+            Some(stats) => JsonValue::Object(vec![
+                ("min".into(), number(stats.min)),
+                ("max".into(), number(stats.max)),
+                ("mean".into(), amount(stats.mean)),
+                ("total".into(), amount(stats.total)),
+            ]),
+            None => JsonValue::Null,
+        };
+        JsonValue::Object(vec![
+            ("records".into(), number(self.records)),
+            (
+                "kinds".into(),
+                counts(
+                    self.kinds
+                        .iter()
+                        .map(|(k, n)| (k.to_string(), *n))
+                        .collect(),
+                ),
+            ),
+            (
+                "statuses".into(),
+                counts(
+                    self.statuses
+                        .iter()
+                        .map(|(s, n)| (s.to_string(), *n))
+                        .collect(),
+                ),
+            ),
+            ("amounts".into(), amounts),
+            ("first_timestamp".into(), timestamp(self.first_timestamp)),
+            ("last_timestamp".into(), timestamp(self.last_timestamp)),
+            ("accounts".into(), number(self.accounts)),
+            ("sources".into(), number(self.sources)),
+            ("destinations".into(), number(self.destinations)),
+        ])
+        .to_string()
+    }
+}
+
+fn number<T: Display>(value: T) -> JsonValue {
+    JsonValue::Number(value.to_string())
+}
+
+fn counts_line<K: Display>(counts: &BTreeMap<K, usize>) -> String {
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(key, count)| format!("{} {}", key, count))
+        .collect();
+    counts.join(", ")
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let or_dash = |amount: Option<Money>| amount.map_or("-".to_string(), |a| a.to_string());
+        writeln!(f, "records: {}", self.records)?;
+        writeln!(f, "kinds: {}", counts_line(&self.kinds))?;
+        writeln!(f, "statuses: {}", counts_line(&self.statuses))?;
+        if let Some(stats) = &self.amounts {
+            writeln!(
+                f,
+                "amounts: min {}, max {}, mean {}, total {}",
+                stats.min,
+                stats.max,
+                or_dash(stats.mean),
+                or_dash(stats.total)
+            )?;
+        }
+        if let (Some(first), Some(last)) = (self.first_timestamp, self.last_timestamp) {
+            writeln!(
+                f,
+                "timestamps: {} .. {}",
+                first.to_rfc3339(),
+                last.to_rfc3339()
+            )?;
+        }
+        writeln!(
+            f,
+            "accounts: {} ({} sources, {} destinations)",
+            self.accounts, self.sources, self.destinations
+        )
+    }
+}
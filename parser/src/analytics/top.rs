@@ -0,0 +1,215 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::domain::money::{self, AmountOverflow};
+use crate::domain::tx::*;
+
+// record ranked by amount, the earlier one ranks higher on ties
+struct Ranked {
+    seq: usize,
+    tx: TxRecord,
+}
+
+impl Ranked {
+    fn key(&self) -> (Money, Reverse<usize>) {
+        (self.tx.amount, Reverse(self.seq))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// The `n` records of the largest amounts among records pushed, e.g. read from a stream;
+/// at most `n` records are held.
+pub struct LargestRecords {
+    n: usize,
+    pushed: usize,
+    // the smallest kept record on top
+    heap: BinaryHeap<Reverse<Ranked>>,
+}
+
+impl LargestRecords {
+    /// Keeps `n` largest records.
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            pushed: 0,
+            heap: BinaryHeap::with_capacity(n.min(1024) + 1),
+        }
+    }
+
+    /// Takes the record into account.
+    pub fn push(&mut self, tx: &TxRecord) {
+        self.pushed += 1;
+        if self.n == 0 {
+            return;
+        }
+        if self.heap.len() == self.n
+            && self
+                .heap
+                .peek()
+                .is_some_and(|Reverse(smallest)| tx.amount <= smallest.tx.amount)
+        {
+            return;
+        }
+        self.heap.push(Reverse(Ranked {
+            seq: self.pushed,
+            tx: tx.clone(),
+        }));
+        if self.heap.len() > self.n {
+            self.heap.pop();
+        }
+    }
+
+    /// Records by amount, the largest first; records of equal amounts keep order they were
+    /// pushed in.
+    pub fn into_vec(self) -> Vec<TxRecord> {
+        // ascending order of `Reverse` is descending order of records
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(ranked)| ranked.tx)
+            .collect()
+    }
+}
+
+/// Side of records account volumes are counted on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Funds leaving the account, see [`TxRecord::source`].
+    Outgoing,
+    /// Funds coming to the account, see [`TxRecord::destination`].
+    Incoming,
+}
+
+/// Records of an account on one side and sum of their amounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountVolume {
+    /// The account.
+    pub account: AccountType,
+    /// Number of records.
+    pub count: usize,
+    /// Sum of amounts at the finest scale of them.
+    pub volume: Money,
+}
+
+/// Volumes of all accounts on one side of records pushed, e.g. read from a stream.
+#[derive(Debug, Clone)]
+pub struct AccountVolumes {
+    flow: Flow,
+    volumes: HashMap<AccountType, (usize, Money)>,
+}
+
+impl AccountVolumes {
+    /// Volumes on the side of `flow`.
+    pub fn new(flow: Flow) -> Self {
+        Self {
+            flow,
+            volumes: HashMap::new(),
+        }
+    }
+
+    /// Adds the record to the volume of its account, records without account on the side,
+    /// e.g. deposits for [`Flow::Outgoing`], are skipped. Fails if the volume overflows.
+    pub fn push(&mut self, tx: &TxRecord) -> Result<(), AmountOverflow> {
+        let account = match self.flow {
+            Flow::Outgoing => tx.source(),
+            Flow::Incoming => tx.destination(),
+        };
+        let Some(account) = account else {
+            return Ok(());
+        };
+        match self.volumes.get_mut(account) {
+            Some((count, volume)) => {
+                *volume = money::add(*volume, tx.amount)?;
+                *count += 1;
+            }
+            None => {
+                self.volumes.insert(account.clone(), (1, tx.amount));
+            }
+        }
+        Ok(())
+    }
+
+    /// Volume of the account, `None` for accounts without records.
+    pub fn get(&self, account: &AccountType) -> Option<AccountVolume> {
+        self.volumes
+            .get(account)
+            .map(|&(count, volume)| AccountVolume {
+                account: account.clone(),
+                count,
+                volume,
+            })
+    }
+
+    /// `n` accounts of the largest volumes, the largest first, ties ordered by account.
+    pub fn top(&self, n: usize) -> Vec<AccountVolume> {
+        self.ranked(n, |a, b| b.volume.cmp(&a.volume))
+    }
+
+    /// `n` accounts of the most records, the busiest first, ties ordered by account.
+    pub fn top_by_count(&self, n: usize) -> Vec<AccountVolume> {
+        self.ranked(n, |a, b| b.count.cmp(&a.count))
+    }
+
+    fn ranked<F>(&self, n: usize, compare: F) -> Vec<AccountVolume>
+    where
+        F: Fn(&AccountVolume, &AccountVolume) -> Ordering,
+    {
+        let mut volumes: Vec<AccountVolume> = self
+            .volumes
+            .iter()
+            .map(|(account, &(count, volume))| AccountVolume {
+                account: account.clone(),
+                count,
+                volume,
+            })
+            .collect();
+        volumes.sort_by(|a, b| compare(a, b).then_with(|| a.account.cmp(&b.account)));
+        volumes.truncate(n);
+        volumes
+    }
+}
+
+/// `n` records of the largest amounts, the largest first, ties in input order.
+///
+/// All records are ranked whatever their kind or status, filter them first if needed.
+pub fn largest(data: &[TxRecord], n: usize) -> Vec<TxRecord> {
+    let mut largest = LargestRecords::new(n);
+    data.iter().for_each(|tx| largest.push(tx));
+    largest.into_vec()
+}
+
+/// `n` accounts of the largest volumes on the side of `flow`, the largest first.
+///
+/// All records are counted whatever their status, filter them first if needed. Fails if a
+/// volume overflows.
+pub fn top_accounts(
+    data: &[TxRecord],
+    flow: Flow,
+    n: usize,
+) -> Result<Vec<AccountVolume>, AmountOverflow> {
+    let mut volumes = AccountVolumes::new(flow);
+    for tx in data {
+        volumes.push(tx)?;
+    }
+    Ok(volumes.top(n))
+}
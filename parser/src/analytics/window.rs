@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::codecs::errors::ParserError;
+use crate::codecs::utils::{civil_from_days, days_from_civil};
+use crate::domain::money::{self, AmountOverflow};
+use crate::domain::tx::*;
+
+/// Name of the extra field holding number of records of a bucket record.
+pub const COUNT_KEY: &str = "COUNT";
+/// Name of the extra field holding the smallest amount of a bucket record.
+pub const MIN_KEY: &str = "MIN";
+/// Name of the extra field holding the largest amount of a bucket record.
+pub const MAX_KEY: &str = "MAX";
+/// Name of the extra field holding end of the window of a bucket record, in millis.
+pub const WINDOW_END_KEY: &str = "WINDOW_END";
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+/// Length of time windows records are bucketed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    /// Local hour.
+    Hour,
+    /// Local day.
+    Day,
+    /// Local calendar month.
+    Month,
+}
+
+impl Window {
+    /// Start and end of the window holding the moment, windows follow local time at the offset.
+    pub fn bounds(&self, ts: TxTimestamp, offset: UtcOffset) -> (TxTimestamp, TxTimestamp) {
+        let offset_ms = i64::from(offset.minutes()) * 60_000;
+        let local = i64::try_from(ts.millis())
+            .unwrap_or(i64::MAX)
+            .saturating_add(offset_ms);
+        let (start, end) = match self {
+            Window::Hour => {
+                let start = local - local.rem_euclid(HOUR_MS);
+                (start, start.saturating_add(HOUR_MS))
+            }
+            Window::Day => {
+                let start = local - local.rem_euclid(DAY_MS);
+                (start, start.saturating_add(DAY_MS))
+            }
+            Window::Month => {
+                let (year, month, _) = civil_from_days(local.div_euclid(DAY_MS));
+                let (next_year, next_month) = match month {
+                    12 => (year + 1, 1),
+                    _ => (year, month + 1),
+                };
+                (
+                    days_from_civil(year, month, 1) * DAY_MS,
+                    days_from_civil(next_year, next_month, 1) * DAY_MS,
+                )
+            }
+        };
+        // moments before Unix epoch are clamped to it
+        let utc =
+            |local: i64| TxTimestamp::from_millis(local.saturating_sub(offset_ms).max(0) as u64);
+        (utc(start), utc(end))
+    }
+}
+
+impl FromStr for Window {
+    type Err = ParserError;
+
+    /// Parses `HOUR`, `DAY` or `MONTH`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "HOUR" => Ok(Window::Hour),
+            "DAY" => Ok(Window::Day),
+            "MONTH" => Ok(Window::Month),
+            other => Err(ParserError::UnparsableValue(other.into())),
+        }
+    }
+}
+
+impl Display for Window {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Window::Hour => write!(f, "HOUR"),
+            Window::Day => write!(f, "DAY"),
+            Window::Month => write!(f, "MONTH"),
+        }
+    }
+}
+
+/// Records of one time window and aggregates of their amounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bucket {
+    /// First moment of the window.
+    pub start: TxTimestamp,
+    /// First moment after the window.
+    pub end: TxTimestamp,
+    /// Number of records.
+    pub count: usize,
+    /// Sum of amounts at the finest scale of them.
+    pub sum: Money,
+    /// Smallest amount.
+    pub min: Money,
+    /// Largest amount.
+    pub max: Money,
+}
+
+/// Result of [`bucket`], buckets ordered by their windows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Buckets {
+    /// Length of windows.
+    pub window: Window,
+    /// Offset of local time windows follow.
+    pub offset: UtcOffset,
+    /// Buckets of records, only non-empty ones.
+    pub buckets: Vec<Bucket>,
+}
+
+impl Buckets {
+    /// Bucket of the window holding the moment, `None` for windows without records.
+    pub fn get(&self, ts: TxTimestamp) -> Option<&Bucket> {
+        let (start, _) = self.window.bounds(ts, self.offset);
+        self.buckets.iter().find(|bucket| bucket.start == start)
+    }
+
+    /// A record of every bucket, to be written by any codec.
+    ///
+    /// `TX_ID` and `TIMESTAMP` are start of the window, `AMOUNT` is the sum, `DESCRIPTION` is
+    /// local start of the window, e.g. `2024-01-31T10:00:00.000+01:00`; records are successful
+    /// and keep defaults of other fields. Count, smallest and largest amounts and end of the
+    /// window are in extra fields [`COUNT_KEY`], [`MIN_KEY`], [`MAX_KEY`] and
+    /// [`WINDOW_END_KEY`], written as columns or keys by CSV and text codecs.
+    pub fn records(&self) -> Vec<TxRecord> {
+        self.buckets
+            .iter()
+            .map(|bucket| {
+                let mut tx = TxRecord {
+                    id: TxIdType::Numeric(bucket.start.millis()),
+                    amount: bucket.sum,
+                    ts: bucket.start,
+                    status: TxStatus::Success,
+                    description: Some(bucket.start.at_offset(self.offset).to_rfc3339()),
+                    ..Default::default()
+                };
+                tx.extras
+                    .insert(COUNT_KEY.to_string(), bucket.count.to_string());
+                tx.extras
+                    .insert(MIN_KEY.to_string(), bucket.min.to_string());
+                tx.extras
+                    .insert(MAX_KEY.to_string(), bucket.max.to_string());
+                tx.extras
+                    .insert(WINDOW_END_KEY.to_string(), bucket.end.to_string());
+                tx
+            })
+            .collect()
+    }
+}
+
+/// Buckets records into time windows following local time at `offset` and aggregates amounts
+/// of every window; fails if a sum overflows.
+pub fn bucket(
+    data: &[TxRecord],
+    window: Window,
+    offset: UtcOffset,
+) -> Result<Buckets, AmountOverflow> {
+    let mut buckets: BTreeMap<TxTimestamp, Bucket> = BTreeMap::new();
+    for tx in data {
+        let (start, end) = window.bounds(tx.ts, offset);
+        match buckets.get_mut(&start) {
+            Some(bucket) => {
+                bucket.sum = money::add(bucket.sum, tx.amount)?;
+                bucket.count += 1;
+                bucket.min = bucket.min.min(tx.amount);
+                bucket.max = bucket.max.max(tx.amount);
+            }
+            None => {
+                buckets.insert(
+                    start,
+                    Bucket {
+                        start,
+                        end,
+                        count: 1,
+                        sum: tx.amount,
+                        min: tx.amount,
+                        max: tx.amount,
+                    },
+                );
+            }
+        }
+    }
+    Ok(Buckets {
+        window,
+        offset,
+        buckets: buckets.into_values().collect(),
+    })
+}
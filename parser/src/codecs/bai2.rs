@@ -0,0 +1,289 @@
+use std::io::{BufRead, BufReader, Read, Write};
+
+use super::errors::{IoCtxBehavior, ParserContext, ParserCtxBehavior, ParserError};
+use super::options::ParseLimits;
+use super::traits::{
+    DataParser, DataWriter, RecordSink, RecordStream, StreamingParser, StreamingWriter,
+};
+use super::utils::parse_iso8601;
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+const FIELD_DELIMITER: char = ',';
+const RECORD_TERMINATOR: char = '/';
+
+const FILE_HEADER: &str = "01";
+const GROUP_HEADER: &str = "02";
+const ACCOUNT_IDENTIFIER: &str = "03";
+const TRANSACTION_DETAIL: &str = "16";
+const CONTINUATION: &str = "88";
+
+// BAI2 type codes 100-399 are credits, 400-699 are debits
+const CREDIT_TYPE_CODES: std::ops::RangeInclusive<u32> = 100..=399;
+const DEBIT_TYPE_CODES: std::ops::RangeInclusive<u32> = 400..=699;
+// currency of groups and accounts without currency code, amounts are in its cents
+const DEFAULT_CURRENCY: &str = "USD";
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Reader of BAI2 cash management files.
+///
+/// Every transaction detail (`16`) record becomes a record: credits are deposits to the
+/// current account (`03`), debits are withdrawals from it, timestamp is the group as-of date/time.
+/// Amounts are cents of US dollars, groups and accounts in other currencies are rejected.
+#[derive(Default)]
+pub(crate) struct Bai2Codec {
+    limits: ParseLimits,
+}
+
+#[derive(Default)]
+struct Bai2State {
+    as_of_ms: u64,
+    account: AccountType,
+    seq_no: u64,
+}
+
+impl Bai2Codec {
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // `YYMMDD` + optional `HHMM` into milliseconds since Unix epoch
+    fn parse_date_time(&self, date: &str, time: &str) -> Result<u64, ParserError> {
+        let invalid = || ParserError::UnparsableValue(format!("{} {}", date, time));
+        if 6 != date.len() || !date.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        // end of day is reported as 2400 or 9999, it is midnight of the next day
+        let (time, days) = match time {
+            "" => ("0000", 0),
+            "9999" | "2400" => ("0000", 1),
+            t => (t, 0),
+        };
+        if 4 != time.len() {
+            return Err(invalid());
+        }
+        let iso = format!(
+            "20{}-{}-{}T{}:{}:00Z",
+            &date[..2],
+            &date[2..4],
+            &date[4..],
+            &time[..2],
+            &time[2..]
+        );
+        parse_iso8601(&iso)
+            .map(|ms| ms + days * MILLIS_PER_DAY)
+            .map_err(|_| invalid())
+    }
+
+    // empty currency code stands for the default one
+    fn check_currency(&self, currency: &str) -> Result<(), ParserError> {
+        if !currency.is_empty() && DEFAULT_CURRENCY != currency {
+            return Err(ParserError::UnparsableValue(format!(
+                "currency {}, only {} is supported",
+                currency, DEFAULT_CURRENCY
+            )));
+        }
+        Ok(())
+    }
+
+    // slash terminates records, but text of transaction detail runs to the end of record and
+    // may end with slash itself, so there slash is a terminator only after empty text
+    fn strip_terminator<'a>(&self, line: &'a str, detail: bool) -> &'a str {
+        match line.strip_suffix(RECORD_TERMINATOR) {
+            Some(rest) if !detail || rest.ends_with(FIELD_DELIMITER) => rest,
+            _ => line,
+        }
+    }
+
+    fn parse_record(
+        &self,
+        record: &str,
+        state: &mut Bai2State,
+    ) -> Result<Option<TxRecord>, ParserError> {
+        let fields: Vec<&str> = record.split(FIELD_DELIMITER).collect();
+        let field = |i: usize| fields.get(i).copied().unwrap_or_default().trim();
+        match field(0) {
+            FILE_HEADER if 0 == state.as_of_ms => {
+                state.as_of_ms = self.parse_date_time(field(3), field(4))?;
+            }
+            GROUP_HEADER => {
+                self.check_currency(field(6))?;
+                state.as_of_ms = self.parse_date_time(field(4), field(5))?;
+            }
+            ACCOUNT_IDENTIFIER => {
+                self.check_currency(field(2))?;
+                state.account = field(1).parse()?;
+            }
+            TRANSACTION_DETAIL => return self.parse_transaction(&fields, state).map(Some),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn parse_transaction(
+        &self,
+        fields: &[&str],
+        state: &mut Bai2State,
+    ) -> Result<TxRecord, ParserError> {
+        if fields.len() < 4 {
+            return Err(ParserError::IncompleteRecord);
+        }
+        let field = |i: usize| fields.get(i).copied().unwrap_or_default().trim();
+        let type_code: u32 = field(1).parse()?;
+        let (kind, from, to) = if CREDIT_TYPE_CODES.contains(&type_code) {
+            (
+                TxKind::Deposit,
+                AccountType::default(),
+                state.account.clone(),
+            )
+        } else if DEBIT_TYPE_CODES.contains(&type_code) {
+            (
+                TxKind::Withdrawal,
+                state.account.clone(),
+                AccountType::default(),
+            )
+        } else {
+            return Err(ParserError::UnparsableValue(type_code.to_string()));
+        };
+        let amount: i64 = if field(2).is_empty() {
+            0
+        } else {
+            field(2).parse()?
+        };
+
+        // funds type may be followed by availability details
+        let mut i = 4 + match fields[3] {
+            "S" => 3,
+            "V" => 2,
+            "D" => {
+                1 + 2 * fields
+                    .get(4)
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .unwrap_or(0)
+            }
+            _ => 0,
+        };
+        let mut next = || {
+            let value = fields.get(i).copied().unwrap_or_default();
+            i += 1;
+            value
+        };
+        let bank_ref = next();
+        let customer_ref = next();
+        // text is the rest of the record and may contain delimiters
+        let text = fields
+            .get(i..)
+            .map(|rest| rest.join(","))
+            .filter(|text| !text.is_empty());
+
+        state.seq_no += 1;
+        let id = bank_ref
+            .parse()
+            .or_else(|_| customer_ref.parse())
+            .unwrap_or(TxIdType::Numeric(state.seq_no));
+
+        Ok(TxRecord {
+            id,
+            kind,
+            from,
+            to,
+            amount: Money::from_minor_units(amount),
+            fee: None,
+            original_amount: None,
+            exchange_rate: None,
+            ts: TxTimestamp::from_millis(state.as_of_ms),
+            status: TxStatus::Success,
+            description: text,
+            tenant: None,
+            reference: [bank_ref, customer_ref]
+                .into_iter()
+                .find(|r| !r.is_empty())
+                .map(str::to_string),
+            extras: Default::default(),
+        })
+    }
+}
+
+impl DataParser for Bai2Codec {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        // join continuation records with the records they continue, all of them are held
+        // until the file is read
+        let mut records: Vec<(usize, String)> = Vec::new();
+        let mut held = 0;
+        for (line_num, line_res) in BufReader::new(r).lines().enumerate() {
+            let input_line = line_res.map_err(AppError::ReadError)?;
+            let line = input_line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            held += line.len();
+            ParseLimits::check_size("file", held as u64, self.limits.max_block_size)
+                .add_parser_ctx(ParserContext::with_line_number_and_line(
+                    line_num,
+                    input_line.clone(),
+                ))?;
+            let continued = records.last().filter(|_| line.starts_with(CONTINUATION));
+            let detail = continued
+                .map_or(line, |(_, previous)| previous.as_str())
+                .starts_with(TRANSACTION_DETAIL);
+            let line = self.strip_terminator(line, detail);
+            match (line.strip_prefix(CONTINUATION), records.last_mut()) {
+                (Some(rest), Some((_, previous))) => {
+                    previous.push_str(rest);
+                    let size = previous.len() as u64;
+                    ParseLimits::check_size("record", size, self.limits.max_record_size)
+                        .add_parser_ctx(ParserContext::with_line_number_and_line(
+                            line_num,
+                            input_line.clone(),
+                        ))?;
+                }
+                (Some(_), None) => {
+                    return Err(ParserError::IncompleteRecord).add_parser_ctx(
+                        ParserContext::with_line_number_and_line(line_num, input_line.clone()),
+                    );
+                }
+                (None, _) => records.push((line_num, line.to_string())),
+            }
+        }
+
+        let mut state = Bai2State::default();
+        let mut result = Vec::new();
+        for (line_num, record) in records {
+            if let Some(tx) = self.parse_record(&record, &mut state).add_parser_ctx(
+                ParserContext::with_line_number_and_line(line_num, record.clone()),
+            )? {
+                self.limits.check_record(&tx, result.len())?;
+                result.push(tx);
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl StreamingParser for Bai2Codec {
+    // continuation records are joined before records are decoded
+    fn parse_stream<'a, R: Read + 'a>(&self, mut r: R) -> RecordStream<'a> {
+        RecordStream::from_parsed(self.parse(&mut r))
+    }
+}
+
+impl DataWriter for Bai2Codec {
+    fn write(&self, _: &mut dyn Write, _: &[TxRecord]) -> Result<(), AppError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "BAI2 format is read only",
+        ))
+        .add_write_ctx()
+    }
+}
+
+impl StreamingWriter for Bai2Codec {
+    fn open_sink<'a, W: Write + 'a>(&self, _: W) -> Result<RecordSink<'a>, AppError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "BAI2 format is read only",
+        ))
+        .add_write_ctx()
+    }
+}
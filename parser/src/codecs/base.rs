@@ -1,26 +1,76 @@
 use std::fmt::Display;
-use std::io::{Read, Write};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::str::FromStr;
 
+use crate::domain::batch::{BatchHeader, TxBatch};
 use crate::domain::tx::*;
 use crate::errors::AppError;
 
+use super::bai2::Bai2Codec;
 use super::binary::BinaryCodec;
-use super::csv::CsvCodec;
+use super::binary_v2::BinaryV2Codec;
+#[cfg(feature = "bincode")]
+use super::bincode::BincodeCodec;
+use super::camt::CamtCodec;
+#[cfg(feature = "capnp")]
+use super::capnp::CapnpCodec;
+use super::columnar::ColumnarBinaryCodec;
+use super::compression::{CompressedReader, CompressedWriter, Compression};
+use super::csv::{CsvCodec, CsvDialect};
 use super::dummy::DummyCodec;
-use super::errors::ParserError;
+#[cfg(feature = "encryption")]
+use super::encryption::{EncryptedReader, EncryptedWriter, EncryptionKey};
+use super::errors::IoCtxBehavior;
+use super::errors::{ParserContext, ParserError};
+use super::fix::FixCodec;
+use super::ledger::LedgerCodec;
+use super::markdown::MarkdownCodec;
+use super::options::{ParseOptions, TrailingNewline, WriteOptions};
+use super::quarantine::{LenientParse, QuarantineWriter, RejectedInput};
+use super::report::ReportCodec;
+use super::signing::{SignedReader, SignedWriter, SigningKey};
 use super::text::TextCodec;
 use super::traits::*;
+use super::utils::{LineLimited, TrailingNewlineTrimmer, limit_error};
+#[cfg(feature = "xlsx")]
+use super::xlsx::XlsxCodec;
 
 /// Supported Codecs factory.
 #[derive(Clone, Debug)]
 pub enum Codec {
     /// Codec for Binary format.
     BinaryCodec,
+    /// Codec for delta-encoded binary format (YPB2).
+    BinaryV2Codec,
+    /// Codec for column-wise binary format (YPBN-C).
+    ColumnarBinaryCodec,
     /// Codec for Text format.
     TextCodec,
+    /// Codec for compact bincode layout dumps.
+    #[cfg(feature = "bincode")]
+    BincodeCodec,
+    /// Codec for Cap'n Proto messages.
+    #[cfg(feature = "capnp")]
+    CapnpCodec,
     /// Codec for CSV format.
     CsvCodec,
+    /// Codec for tab-separated format, CSV preset.
+    TsvCodec,
+    /// Codec for ISO 20022 camt.053 XML statements (read only).
+    CamtCodec,
+    /// Codec for FIX execution reports (read only).
+    FixCodec,
+    /// Codec for BAI2 cash management files (read only).
+    Bai2Codec,
+    /// Codec for Excel workbooks.
+    #[cfg(feature = "xlsx")]
+    XlsxCodec,
+    /// Codec for GitHub-flavored markdown tables (write only).
+    MarkdownCodec,
+    /// Codec for ledger-cli journals (write only).
+    LedgerCodec,
+    /// Codec for human-readable reports (write only).
+    ReportCodec,
     /// Dummy format used for no-op behavior.
     DummyCodec,
 }
@@ -28,22 +78,508 @@ pub enum Codec {
 impl Codec {
     /// Parses records from input stream using selected codec.
     pub fn parse<R: Read>(&self, r: R) -> Result<Vec<TxRecord>, AppError> {
-        match self {
-            Codec::BinaryCodec => BinaryCodec::default().parse(r),
-            Codec::TextCodec => TextCodec::default().parse(r),
-            Codec::CsvCodec => CsvCodec::default().parse(r),
-            Codec::DummyCodec => DummyCodec::default().parse(r),
+        self.parse_with(r, &ParseOptions::default())
+    }
+    /// Parses records from input stream using selected codec and options.
+    pub fn parse_with<R: Read>(
+        &self,
+        r: R,
+        options: &ParseOptions,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        // codecs keep their own small line buffers, this one sets the size of reads from the stream
+        let mut r = BufReader::with_capacity(options.buffer_size.bytes(), r);
+        let max_line = options.limits.max_record_size;
+        let records = match self {
+            Codec::BinaryCodec => BinaryCodec::new(options.binary.clone())
+                .with_limits(options.limits)
+                .strict(options.is_strict())
+                .parse(&mut r),
+            Codec::BinaryV2Codec => BinaryV2Codec::default()
+                .with_limits(options.limits)
+                .parse(&mut r),
+            Codec::ColumnarBinaryCodec => ColumnarBinaryCodec::default()
+                .with_limits(options.limits)
+                .parse(&mut r),
+            Codec::TextCodec => TextCodec::new(options.text.clone())
+                .with_limits(options.limits)
+                .parse(&mut options.text_input(r)),
+            #[cfg(feature = "bincode")]
+            Codec::BincodeCodec => BincodeCodec::default()
+                .with_limits(options.limits)
+                .parse(&mut r),
+            #[cfg(feature = "capnp")]
+            Codec::CapnpCodec => CapnpCodec::default()
+                .with_limits(options.limits)
+                .parse(&mut r),
+            Codec::CsvCodec => CsvCodec::new(options.csv_dialect())
+                .with_limits(options.limits)
+                .parse(&mut options.text_input(r)),
+            Codec::TsvCodec => CsvCodec::new(options.tsv_dialect())
+                .with_limits(options.limits)
+                .parse(&mut options.text_input(r)),
+            Codec::CamtCodec => CamtCodec::default()
+                .with_limits(options.limits)
+                .parse(&mut r),
+            Codec::FixCodec => FixCodec::new(options.fix.clone())
+                .with_limits(options.limits)
+                .parse(&mut LineLimited::new(r, max_line)),
+            Codec::Bai2Codec => Bai2Codec::default()
+                .with_limits(options.limits)
+                .parse(&mut LineLimited::new(r, max_line)),
+            #[cfg(feature = "xlsx")]
+            Codec::XlsxCodec => XlsxCodec::default()
+                .with_limits(options.limits)
+                .parse(&mut r),
+            Codec::MarkdownCodec => MarkdownCodec.parse(&mut r),
+            Codec::LedgerCodec => LedgerCodec.parse(&mut r),
+            Codec::ReportCodec => ReportCodec::default().parse(&mut r),
+            Codec::DummyCodec => DummyCodec::default().parse(&mut r),
+        }
+        .map_err(limit_error)?;
+        self.check_parsed(records, options)
+    }
+    /// Parses records from input stream decompressing it first if compression is detected.
+    ///
+    /// Decompressed input over `options.limits.max_block_size` fails with `LimitExceeded`.
+    pub fn parse_compressed<R: Read>(
+        &self,
+        r: R,
+        options: &ParseOptions,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_with(
+            CompressedReader::with_limits(r, &options.compression, options.limits)?,
+            options,
+        )
+    }
+    /// Parses records from input sealed in encrypted container with `key`.
+    #[cfg(feature = "encryption")]
+    pub fn parse_encrypted<R: Read>(
+        &self,
+        r: R,
+        options: &ParseOptions,
+        key: &EncryptionKey,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_with(EncryptedReader::new(r, key)?, options)
+    }
+    /// Parses records from input signed with `key`, verifying its trailer first.
+    pub fn parse_signed<R: Read>(
+        &self,
+        r: R,
+        options: &ParseOptions,
+        key: &SigningKey,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_with(SignedReader::new(r, key)?, options)
+    }
+    /// Parses records, writing malformed ones to `quarantine` instead of failing on them.
+    ///
+    /// Broken framing and IO errors still abort parsing, as do errors of camt.053, BAI2,
+    /// binary v2, Cap'n Proto and bincode inputs which have no record-level recovery.
+    pub fn parse_quarantined<R: Read, Q: Write>(
+        &self,
+        r: R,
+        options: &ParseOptions,
+        quarantine: &mut QuarantineWriter<Q>,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_skipping(r, options, &mut |rejected| quarantine.write(&rejected))
+    }
+    /// Parses records, skipping malformed ones and collecting their errors.
+    ///
+    /// Recovers from the same errors as [`Codec::parse_quarantined`].
+    pub fn parse_lenient<R: Read>(
+        &self,
+        r: R,
+        options: &ParseOptions,
+    ) -> Result<LenientParse, AppError> {
+        let mut errors = Vec::new();
+        let records = self.parse_skipping(r, options, &mut |rejected| {
+            errors.push((rejected.context, rejected.error));
+            Ok(())
+        })?;
+        Ok(LenientParse { records, errors })
+    }
+    fn parse_skipping<R: Read>(
+        &self,
+        r: R,
+        options: &ParseOptions,
+        on_reject: &mut dyn FnMut(RejectedInput) -> Result<(), AppError>,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        let r = BufReader::with_capacity(options.buffer_size.bytes(), r);
+        let max_line = options.limits.max_record_size;
+        let records = match self {
+            Codec::BinaryCodec => BinaryCodec::new(options.binary.clone())
+                .with_limits(options.limits)
+                .strict(options.is_strict())
+                .parse_recovering(r, on_reject),
+            Codec::ColumnarBinaryCodec => ColumnarBinaryCodec::default()
+                .with_limits(options.limits)
+                .parse_recovering(r, on_reject),
+            Codec::TextCodec => TextCodec::new(options.text.clone())
+                .with_limits(options.limits)
+                .parse_recovering(options.text_input(r), on_reject),
+            Codec::CsvCodec => CsvCodec::new(options.csv_dialect())
+                .with_limits(options.limits)
+                .parse_recovering(options.text_input(r), on_reject),
+            Codec::TsvCodec => CsvCodec::new(options.tsv_dialect())
+                .with_limits(options.limits)
+                .parse_recovering(options.text_input(r), on_reject),
+            Codec::FixCodec => FixCodec::new(options.fix.clone())
+                .with_limits(options.limits)
+                .parse_recovering(LineLimited::new(r, max_line), on_reject),
+            #[cfg(feature = "xlsx")]
+            Codec::XlsxCodec => XlsxCodec::default()
+                .with_limits(options.limits)
+                .parse_recovering(r, on_reject),
+            Codec::BinaryV2Codec => return self.parse_with(r, options),
+            #[cfg(feature = "bincode")]
+            Codec::BincodeCodec => return self.parse_with(r, options),
+            #[cfg(feature = "capnp")]
+            Codec::CapnpCodec => return self.parse_with(r, options),
+            Codec::CamtCodec
+            | Codec::Bai2Codec
+            | Codec::MarkdownCodec
+            | Codec::LedgerCodec
+            | Codec::ReportCodec
+            | Codec::DummyCodec => {
+                return self.parse_with(r, options);
+            }
+        }
+        .map_err(limit_error)?;
+        self.check_parsed(records, options)
+    }
+    /// Parses records along with file-level metadata of their batch.
+    ///
+    /// CSV and TSV metadata lines and binary v2 batch header are read into [`TxBatch::header`],
+    /// records count declared there is verified; other formats read the default header.
+    pub fn parse_batch<R: Read>(&self, r: R, options: &ParseOptions) -> Result<TxBatch, AppError> {
+        let mut r = BufReader::with_capacity(options.buffer_size.bytes(), r);
+        let batch = match self {
+            Codec::BinaryV2Codec => BinaryV2Codec::default()
+                .with_limits(options.limits)
+                .parse_batch(&mut r),
+            Codec::CsvCodec => CsvCodec::new(options.csv_dialect())
+                .with_limits(options.limits)
+                .parse_batch(&mut options.text_input(r)),
+            Codec::TsvCodec => CsvCodec::new(options.tsv_dialect())
+                .with_limits(options.limits)
+                .parse_batch(&mut options.text_input(r)),
+            _ => {
+                return Ok(TxBatch {
+                    header: BatchHeader::default(),
+                    records: self.parse_with(r, options)?,
+                });
+            }
         }
+        .map_err(limit_error)?;
+        Ok(TxBatch {
+            records: self.check_parsed(batch.records, options)?,
+            header: batch.header,
+        })
+    }
+    /// Parses records lazily, one at a time, using selected codec and options.
+    ///
+    /// Formats decoded as a whole (camt.053, BAI2, xlsx) are read completely, up to
+    /// [`ParseLimits::max_block_size`], before the first record is returned. In strict mode
+    /// stream without records ends with [`ParserError::EmptyInput`] error and the first record
+    /// violating a record rule of [`ParseOptions::validation`] ends it with
+    /// [`ParserError::RuleViolation`] error; dataset rules, such as [`Rule::unique_ids`], need
+    /// all records and are enforced by [`Codec::parse_with`] only.
+    ///
+    /// [`ParseLimits::max_block_size`]: super::options::ParseLimits::max_block_size
+    /// [`Rule::unique_ids`]: crate::validation::Rule::unique_ids
+    pub fn parse_stream<'a, R: Read + 'a>(&self, r: R, options: &ParseOptions) -> RecordStream<'a> {
+        let r = BufReader::with_capacity(options.buffer_size.bytes(), r);
+        let max_line = options.limits.max_record_size;
+        let stream = match self {
+            Codec::BinaryCodec => BinaryCodec::new(options.binary.clone())
+                .with_limits(options.limits)
+                .strict(options.is_strict())
+                .parse_stream(r),
+            Codec::BinaryV2Codec => BinaryV2Codec::default()
+                .with_limits(options.limits)
+                .parse_stream(r),
+            Codec::ColumnarBinaryCodec => ColumnarBinaryCodec::default()
+                .with_limits(options.limits)
+                .parse_stream(r),
+            Codec::TextCodec => {
+                TextCodec::new(options.text.clone()).parse_stream(options.text_input(r))
+            }
+            #[cfg(feature = "bincode")]
+            Codec::BincodeCodec => BincodeCodec::default()
+                .with_limits(options.limits)
+                .parse_stream(r),
+            #[cfg(feature = "capnp")]
+            Codec::CapnpCodec => CapnpCodec::default()
+                .with_limits(options.limits)
+                .parse_stream(r),
+            Codec::CsvCodec => {
+                CsvCodec::new(options.csv_dialect()).parse_stream(options.text_input(r))
+            }
+            Codec::TsvCodec => {
+                CsvCodec::new(options.tsv_dialect()).parse_stream(options.text_input(r))
+            }
+            Codec::CamtCodec => CamtCodec::default()
+                .with_limits(options.limits)
+                .parse_stream(r),
+            Codec::FixCodec => {
+                FixCodec::new(options.fix.clone()).parse_stream(LineLimited::new(r, max_line))
+            }
+            Codec::Bai2Codec => Bai2Codec::default()
+                .with_limits(options.limits)
+                .parse_stream(LineLimited::new(r, max_line)),
+            #[cfg(feature = "xlsx")]
+            Codec::XlsxCodec => XlsxCodec::default()
+                .with_limits(options.limits)
+                .parse_stream(r),
+            Codec::MarkdownCodec => MarkdownCodec.parse_stream(r),
+            Codec::LedgerCodec => LedgerCodec.parse_stream(r),
+            Codec::ReportCodec => ReportCodec::default().parse_stream(r),
+            Codec::DummyCodec => DummyCodec::default().parse_stream(r),
+        }
+        .limited(options.limits);
+        if options.is_strict() {
+            stream
+                .require_records()
+                .validated(options.validation.clone())
+        } else {
+            stream
+        }
+    }
+    // strict mode rejects input without records and records violating validation rules
+    fn check_parsed(
+        &self,
+        records: Vec<TxRecord>,
+        options: &ParseOptions,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        if !options.is_strict() {
+            return Ok(records);
+        }
+        if records.is_empty() {
+            return Err(AppError::ParsingError {
+                context: ParserContext::with_position(0),
+                source: ParserError::EmptyInput,
+            });
+        }
+        options.validation.enforce(&records)?;
+        Ok(records)
     }
     /// Writes records to output stream using selected codec.
     pub fn write<W: Write>(&self, w: &mut W, data: &[TxRecord]) -> Result<(), AppError> {
+        self.write_with(w, data, &WriteOptions::default())
+    }
+    /// Writes records to output stream using selected codec and options.
+    pub fn write_with<W: Write>(
+        &self,
+        w: &mut W,
+        data: &[TxRecord],
+        options: &WriteOptions,
+    ) -> Result<(), AppError> {
+        self.write_with_header(w, None, data, options)
+    }
+    /// Writes batch with its header using selected codec and options.
+    ///
+    /// CSV and TSV write the header as metadata lines, binary v2 in its file header; other
+    /// formats write records only. Declared records count must match the records, the count
+    /// written is the one of records left after applying `options`.
+    pub fn write_batch<W: Write>(
+        &self,
+        w: &mut W,
+        batch: &TxBatch,
+        options: &WriteOptions,
+    ) -> Result<(), AppError> {
+        batch
+            .verify()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))
+            .add_write_ctx()?;
+        self.write_with_header(w, Some(&batch.header), &batch.records, options)
+    }
+    fn write_with_header<W: Write>(
+        &self,
+        w: &mut W,
+        header: Option<&BatchHeader>,
+        data: &[TxRecord],
+        options: &WriteOptions,
+    ) -> Result<(), AppError> {
+        let data = options.records(data)?;
+        let header = header.map(|header| BatchHeader {
+            record_count: header.record_count.map(|_| data.len() as u64),
+            ..header.clone()
+        });
+        let header = header.as_ref();
+        let mut w = BufWriter::with_capacity(options.buffer_size.bytes(), w);
+        if TrailingNewline::Trim == options.trailing_newline && self.is_textual() {
+            self.write_records(
+                &mut TrailingNewlineTrimmer::new(&mut w),
+                header,
+                &data,
+                options,
+            )?;
+        } else {
+            self.write_records(&mut w, header, &data, options)?;
+        }
+        w.flush().add_write_ctx()
+    }
+    // formats written as lines of text
+    fn is_textual(&self) -> bool {
+        matches!(
+            self,
+            Codec::TextCodec
+                | Codec::CsvCodec
+                | Codec::TsvCodec
+                | Codec::MarkdownCodec
+                | Codec::LedgerCodec
+                | Codec::ReportCodec
+        )
+    }
+    fn write_records<W: Write>(
+        &self,
+        w: &mut W,
+        header: Option<&BatchHeader>,
+        data: &[TxRecord],
+        options: &WriteOptions,
+    ) -> Result<(), AppError> {
+        match (self, header) {
+            (Codec::BinaryV2Codec, Some(header)) => {
+                return BinaryV2Codec::new(options.binary_v2.clone()).write_batch(w, header, data);
+            }
+            (Codec::CsvCodec, Some(header)) => {
+                return CsvCodec::default()
+                    .with_write_options(options.csv.clone())
+                    .write_batch(w, header, data);
+            }
+            (Codec::TsvCodec, Some(header)) => {
+                return CsvCodec::new(CsvDialect::tsv())
+                    .with_write_options(options.csv.clone())
+                    .write_batch(w, header, data);
+            }
+            _ => {}
+        }
         match self {
-            Codec::BinaryCodec => BinaryCodec::default().write(w, data),
-            Codec::TextCodec => TextCodec::default().write(w, data),
-            Codec::CsvCodec => CsvCodec::default().write(w, data),
+            Codec::BinaryCodec => BinaryCodec::default()
+                .with_write_options(options.binary.clone())
+                .write(w, data),
+            Codec::BinaryV2Codec => BinaryV2Codec::new(options.binary_v2.clone()).write(w, data),
+            Codec::ColumnarBinaryCodec => ColumnarBinaryCodec::default().write(w, data),
+            Codec::TextCodec => TextCodec::default()
+                .with_write_options(options.text.clone())
+                .write(w, data),
+            #[cfg(feature = "bincode")]
+            Codec::BincodeCodec => BincodeCodec::default().write(w, data),
+            #[cfg(feature = "capnp")]
+            Codec::CapnpCodec => CapnpCodec::default().write(w, data),
+            Codec::CsvCodec => CsvCodec::default()
+                .with_write_options(options.csv.clone())
+                .write(w, data),
+            Codec::TsvCodec => CsvCodec::new(CsvDialect::tsv())
+                .with_write_options(options.csv.clone())
+                .write(w, data),
+            Codec::CamtCodec => CamtCodec::default().write(w, data),
+            Codec::FixCodec => FixCodec::default().write(w, data),
+            Codec::Bai2Codec => Bai2Codec::default().write(w, data),
+            #[cfg(feature = "xlsx")]
+            Codec::XlsxCodec => XlsxCodec::default().write(w, data),
+            Codec::MarkdownCodec => MarkdownCodec.write(w, data),
+            Codec::LedgerCodec => LedgerCodec.write(w, data),
+            Codec::ReportCodec => ReportCodec::new(options.report.clone()).write(w, data),
             Codec::DummyCodec => DummyCodec::default().write(w, data),
         }
     }
+    /// Writes records compressed with `compression` using selected codec and options.
+    pub fn write_compressed<W: Write>(
+        &self,
+        w: &mut W,
+        data: &[TxRecord],
+        options: &WriteOptions,
+        compression: Compression,
+    ) -> Result<(), AppError> {
+        let mut w = CompressedWriter::with_options(w, compression, options.compression.clone())?;
+        self.write_with(&mut w, data, options)?;
+        w.finish().map(|_| ())
+    }
+    /// Writes records sealed in encrypted container with `key` using selected codec and options.
+    #[cfg(feature = "encryption")]
+    pub fn write_encrypted<W: Write>(
+        &self,
+        w: &mut W,
+        data: &[TxRecord],
+        options: &WriteOptions,
+        key: &EncryptionKey,
+    ) -> Result<(), AppError> {
+        let mut w = EncryptedWriter::new(w, key);
+        self.write_with(&mut w, data, options)?;
+        w.finish().map(|_| ())
+    }
+    /// Writes records followed by HMAC-SHA256 trailer signed with `key` using selected codec.
+    pub fn write_signed<W: Write>(
+        &self,
+        w: &mut W,
+        data: &[TxRecord],
+        options: &WriteOptions,
+        key: &SigningKey,
+    ) -> Result<(), AppError> {
+        let mut w = SignedWriter::new(w, key);
+        self.write_with(&mut w, data, options)?;
+        w.finish().map(|_| ())
+    }
+    /// Opens sink writing records to output stream one at a time using selected codec and options.
+    ///
+    /// Header of the format is written at once. CSV, TSV and markdown sinks always emit TENANT
+    /// column; bincode and xlsx outputs are written by [`RecordSink::finish`] as a whole.
+    /// Description limit is applied to every pushed record, records are not reordered.
+    /// Output is flushed as [`WriteOptions::flush`] says; formats written by blocks or as a
+    /// whole have nothing to flush before their block is complete.
+    pub fn open_sink<'a, W: Write + 'a>(
+        &self,
+        w: W,
+        options: &WriteOptions,
+    ) -> Result<RecordSink<'a>, AppError> {
+        let w = BufWriter::with_capacity(options.buffer_size.bytes(), w);
+        let sink = match self {
+            Codec::BinaryCodec => BinaryCodec::default()
+                .with_write_options(options.binary.clone())
+                .open_sink(w),
+            Codec::BinaryV2Codec => BinaryV2Codec::new(options.binary_v2.clone()).open_sink(w),
+            Codec::ColumnarBinaryCodec => ColumnarBinaryCodec::default().open_sink(w),
+            Codec::TextCodec => TextCodec::default()
+                .with_write_options(options.text.clone())
+                .open_sink(w),
+            #[cfg(feature = "bincode")]
+            Codec::BincodeCodec => BincodeCodec::default().open_sink(w),
+            #[cfg(feature = "capnp")]
+            Codec::CapnpCodec => CapnpCodec::default().open_sink(w),
+            Codec::CsvCodec => CsvCodec::default()
+                .with_write_options(options.csv.clone())
+                .open_sink(w),
+            Codec::TsvCodec => CsvCodec::new(CsvDialect::tsv())
+                .with_write_options(options.csv.clone())
+                .open_sink(w),
+            Codec::CamtCodec => CamtCodec::default().open_sink(w),
+            Codec::FixCodec => FixCodec::default().open_sink(w),
+            Codec::Bai2Codec => Bai2Codec::default().open_sink(w),
+            #[cfg(feature = "xlsx")]
+            Codec::XlsxCodec => XlsxCodec::default().open_sink(w),
+            Codec::MarkdownCodec => MarkdownCodec.open_sink(w),
+            Codec::LedgerCodec => LedgerCodec.open_sink(w),
+            Codec::ReportCodec => ReportCodec::new(options.report.clone()).open_sink(w),
+            Codec::DummyCodec => DummyCodec::default().open_sink(w),
+        }?;
+        Ok(sink
+            .limited(options.description_limit)
+            .flushing(options.flush))
+    }
+}
+
+/// Codecs selected at runtime parse with default options behind `dyn DataParser`.
+impl DataParser for Codec {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_with(r, &ParseOptions::default())
+    }
+}
+
+/// Codecs selected at runtime write with default options behind `dyn DataWriter`.
+impl DataWriter for Codec {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        self.write_with(&mut &mut *w, data, &WriteOptions::default())
+    }
 }
 
 //
@@ -51,17 +587,25 @@ impl Codec {
 //
 impl FromStr for TxIdType {
     type Err = ParserError;
+    /// Parses numeric id, values of canonical UUID length are parsed as UUID.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value = s.parse::<u64>()?;
-        Ok(TxIdType(value))
+        match s.parse::<u64>() {
+            Ok(value) => Ok(TxIdType::Numeric(value)),
+            Err(_) if TxIdType::UUID_LEN == s.len() => TxIdType::parse_uuid(s),
+            Err(e) => Err(e.into()),
+        }
     }
 }
 
 impl FromStr for AccountType {
     type Err = ParserError;
+    /// Parses numeric account id, values with letters are parsed as IBAN.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value = s.parse::<u64>()?;
-        Ok(AccountType(value))
+        match s.parse::<u64>() {
+            Ok(value) => Ok(AccountType::Numeric(value)),
+            Err(_) if s.bytes().any(|b| b.is_ascii_alphabetic()) => AccountType::parse_iban(s),
+            Err(e) => Err(e.into()),
+        }
     }
 }
 
@@ -72,6 +616,9 @@ impl FromStr for TxKind {
             "DEPOSIT" => Ok(TxKind::Deposit),
             "TRANSFER" => Ok(TxKind::Transfer),
             "WITHDRAWAL" => Ok(TxKind::Withdrawal),
+            "REFUND" => Ok(TxKind::Refund),
+            "FEE" => Ok(TxKind::Fee),
+            "REVERSAL" => Ok(TxKind::Reversal),
             _ => Err(ParserError::UnparsableValue(s.into())),
         }
     }
@@ -91,6 +638,9 @@ impl FromStr for TxStatus {
             "SUCCESS" => Ok(TxStatus::Success),
             "FAILURE" => Ok(TxStatus::Failure),
             "PENDING" => Ok(TxStatus::Pending),
+            "CANCELLED" => Ok(TxStatus::Cancelled),
+            "REVERSED" => Ok(TxStatus::Reversed),
+            "EXPIRED" => Ok(TxStatus::Expired),
             _ => Err(ParserError::UnparsableValue(s.to_string())),
         }
     }
@@ -99,7 +649,7 @@ impl FromStr for TxStatus {
 //
 // Transaction fields composite types and display/parse for them
 //
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 /// Field names shared between text and csv formats.
 pub enum TxFieldKey {
     /// `TX_ID` field.
@@ -118,6 +668,16 @@ pub enum TxFieldKey {
     Status,
     /// `DESCRIPTION` field.
     Description,
+    /// Optional `TENANT` field.
+    Tenant,
+    /// Optional `FEE` field.
+    Fee,
+    /// Optional `REFERENCE` field.
+    Reference,
+    /// Optional `ORIGINAL_AMOUNT` field.
+    OriginalAmount,
+    /// Optional `EXCHANGE_RATE` field.
+    ExchangeRate,
 }
 impl Display for TxFieldKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -130,9 +690,49 @@ impl Display for TxFieldKey {
             TxFieldKey::Timestamp => write!(f, "TIMESTAMP"),
             TxFieldKey::Status => write!(f, "STATUS"),
             TxFieldKey::Description => write!(f, "DESCRIPTION"),
+            TxFieldKey::Tenant => write!(f, "TENANT"),
+            TxFieldKey::Fee => write!(f, "FEE"),
+            TxFieldKey::Reference => write!(f, "REFERENCE"),
+            TxFieldKey::OriginalAmount => write!(f, "ORIGINAL_AMOUNT"),
+            TxFieldKey::ExchangeRate => write!(f, "EXCHANGE_RATE"),
         }
     }
 }
+impl TxFieldKey {
+    /// All field keys, in the order of text and CSV output.
+    pub const ALL: [TxFieldKey; 13] = [
+        TxFieldKey::Id,
+        TxFieldKey::TxKind,
+        TxFieldKey::FromUserId,
+        TxFieldKey::ToUserId,
+        TxFieldKey::Amount,
+        TxFieldKey::Timestamp,
+        TxFieldKey::Status,
+        TxFieldKey::Description,
+        TxFieldKey::Tenant,
+        TxFieldKey::Fee,
+        TxFieldKey::Reference,
+        TxFieldKey::OriginalAmount,
+        TxFieldKey::ExchangeRate,
+    ];
+
+    /// Parses key ignoring case, underscores, dashes and spaces: `tx_id`, `TxId` are `TX_ID`.
+    pub fn from_str_lenient(s: &str) -> Result<Self, ParserError> {
+        let key = Self::folded(s);
+        Self::ALL
+            .into_iter()
+            .find(|k| Self::folded(&k.to_string()) == key)
+            .ok_or_else(|| ParserError::UnparsableKey(s.into()))
+    }
+
+    // key with separators dropped and letters uppercased
+    pub(crate) fn folded(s: &str) -> String {
+        s.chars()
+            .filter(|c| !matches!(c, '_' | '-' | ' '))
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    }
+}
 impl FromStr for TxFieldKey {
     type Err = ParserError;
 
@@ -146,6 +746,11 @@ impl FromStr for TxFieldKey {
             "TIMESTAMP" => Ok(TxFieldKey::Timestamp),
             "STATUS" => Ok(TxFieldKey::Status),
             "DESCRIPTION" => Ok(TxFieldKey::Description),
+            "TENANT" => Ok(TxFieldKey::Tenant),
+            "FEE" => Ok(TxFieldKey::Fee),
+            "REFERENCE" => Ok(TxFieldKey::Reference),
+            "ORIGINAL_AMOUNT" => Ok(TxFieldKey::OriginalAmount),
+            "EXCHANGE_RATE" => Ok(TxFieldKey::ExchangeRate),
             _ => Err(ParserError::UnparsableKey(s.into())),
         }
     }
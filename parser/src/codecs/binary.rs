@@ -1,54 +1,764 @@
-use std::io::{Read, Write};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
 
 use super::errors::IoCtxBehavior;
 use super::errors::{ParserContext, ParserCtxBehavior, ParserError};
+use super::options::ParseLimits;
+use super::quarantine::RejectedInput;
 use super::traits::*;
+use super::utils::{read_varint, write_varint, zigzag_decode, zigzag_encode};
 use crate::codecs::base::TxFieldKey;
+use crate::digest::Crc32;
 use crate::domain::tx::*;
 use crate::errors::AppError;
 
 const RECORD_MAGIC: [u8; 4] = *b"YPBN";
+// file header: magic, version (u16), records count (u64)
+const FILE_MAGIC: [u8; 4] = *b"YPBF";
+const FILE_HEADER_SIZE: usize = 4 + 2 + 8;
+const RECORDS_COUNT_OFFSET: usize = 4 + 2;
 const MINIMUM_RECORD_SIZE: u32 = 8 + 1 + 8 + 8 + 8 + 8 + 1 + 4;
+// a byte per field when integers are varints
+const MINIMUM_VARINT_RECORD_SIZE: u32 = 8;
+const MAX_VARINT_SIZE: usize = 10;
+const RECORD_HEADER_SIZE: u64 = 4 + 4;
+const KIND_OFFSET: usize = 8;
+const STATUS_OFFSET: usize = 8 + 1 + 8 + 8 + 8 + 8;
+const DESCRIPTION_LEN_OFFSET: usize = STATUS_OFFSET + 1;
 
-#[derive(Default)]
-pub(crate) struct BinaryCodec {}
+// Optional fields are stored after the description as TLV extensions:
+// tag (u8), value length (u32), value bytes. Unknown tags are skipped by the parser,
+// records without optional fields have no extensions and keep the original layout.
+const EXTENSION_HEADER_SIZE: usize = 1 + 4;
+const EXTENSION_TENANT: u8 = 1;
+// CRC32 of body bytes preceding the extension, written last
+const EXTENSION_CRC32: u8 = 2;
+// IBAN accounts have 0 in from/to fields and IBAN in extension
+const EXTENSION_FROM_IBAN: u8 = 3;
+const EXTENSION_TO_IBAN: u8 = 4;
+// UUID ids have 0 in id field and 16 big-endian bytes of UUID in extension
+const EXTENSION_ID_UUID: u8 = 5;
+const UUID_SIZE: usize = 16;
+const EXTENSION_REFERENCE: u8 = 7;
+// fee, original amount and exchange rate have 8 big-endian bytes of their minor units
+// (mantissa) followed by their scale byte in extension
+const EXTENSION_FEE: u8 = 6;
+const EXTENSION_ORIGINAL_AMOUNT: u8 = 8;
+const EXTENSION_EXCHANGE_RATE: u8 = 9;
+const SCALED_SIZE: usize = 8 + 1;
+// amounts of other scale than Money::DEFAULT_SCALE have their scale byte in extension
+const EXTENSION_AMOUNT_SCALE: u8 = 10;
+
+// Optional footer index follows the records: magic, entries count (u64), id and offset from
+// stream start (u64 both) of every record, then offset of the footer (u64) and magic again,
+// so the footer is found from the end of the stream.
+const INDEX_MAGIC: [u8; 4] = *b"YPIX";
+const INDEX_HEADER_SIZE: u64 = 4 + 8;
+const INDEX_ENTRY_SIZE: u64 = 8 + 8;
+const INDEX_TRAILER_SIZE: u64 = 8 + 4;
+
+/// Byte order of integers in binary stream, signatures are the same in both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endianness {
+    /// Most significant byte first, the layout written by this crate.
+    #[default]
+    Big,
+    /// Least significant byte first, the layout of C tools on x86 and ARM.
+    Little,
+}
+
+impl Endianness {
+    fn u16_from(&self, b: [u8; 2]) -> u16 {
+        match self {
+            Endianness::Big => u16::from_be_bytes(b),
+            Endianness::Little => u16::from_le_bytes(b),
+        }
+    }
+    fn u32_from(&self, b: [u8; 4]) -> u32 {
+        match self {
+            Endianness::Big => u32::from_be_bytes(b),
+            Endianness::Little => u32::from_le_bytes(b),
+        }
+    }
+    fn u64_from(&self, b: [u8; 8]) -> u64 {
+        match self {
+            Endianness::Big => u64::from_be_bytes(b),
+            Endianness::Little => u64::from_le_bytes(b),
+        }
+    }
+    fn u16_bytes(&self, v: u16) -> [u8; 2] {
+        match self {
+            Endianness::Big => v.to_be_bytes(),
+            Endianness::Little => v.to_le_bytes(),
+        }
+    }
+    fn u32_bytes(&self, v: u32) -> [u8; 4] {
+        match self {
+            Endianness::Big => v.to_be_bytes(),
+            Endianness::Little => v.to_le_bytes(),
+        }
+    }
+    fn u64_bytes(&self, v: u64) -> [u8; 8] {
+        match self {
+            Endianness::Big => v.to_be_bytes(),
+            Endianness::Little => v.to_le_bytes(),
+        }
+    }
+}
+
+/// Recovery and byte order of binary input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BinaryParseOptions {
+    /// Scan forward for the next record signature instead of failing on a corrupt record.
+    ///
+    /// Skipped bytes are rejected as a whole with [`ParserContext::ByteRange`] context, so
+    /// they are reported by lenient, quarantined and streaming parsing, batch parsing still
+    /// fails on them.
+    pub resync: bool,
+    /// Byte order of integers, it is not detected.
+    pub endianness: Endianness,
+}
+
+/// Layout version of written binary stream, all of them are accepted by the parser.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BinaryVersion {
+    /// Headerless stream of records.
+    #[default]
+    V1,
+    /// Records preceded by file header with layout version and records count.
+    V2,
+    /// File header as in V2, integers of records are LEB128 varints, amounts zigzag encoded.
+    V3,
+    /// Layout of V3, kinds and statuses include ones unknown to older parsers: refund, fee and
+    /// reversal kinds, cancelled, reversed and expired statuses.
+    V4,
+}
+
+impl BinaryVersion {
+    /// Latest layout version understood by the parser.
+    pub const LATEST: BinaryVersion = BinaryVersion::V4;
+
+    fn number(&self) -> u16 {
+        match self {
+            BinaryVersion::V1 => 1,
+            BinaryVersion::V2 => 2,
+            BinaryVersion::V3 => 3,
+            BinaryVersion::V4 => 4,
+        }
+    }
+
+    // kinds and statuses added in version 4 are not written to streams of older versions
+    fn has_kind(&self, kind: TxKind) -> bool {
+        match kind {
+            TxKind::Deposit | TxKind::Transfer | TxKind::Withdrawal => true,
+            TxKind::Refund | TxKind::Fee | TxKind::Reversal => self.number() >= 4,
+        }
+    }
+    fn has_status(&self, status: TxStatus) -> bool {
+        match status {
+            TxStatus::Success | TxStatus::Failure | TxStatus::Pending => true,
+            TxStatus::Cancelled | TxStatus::Reversed | TxStatus::Expired => self.number() >= 4,
+        }
+    }
+
+    fn integers(&self) -> Integers {
+        Integers::of_version(self.number())
+    }
+
+    // version 1 has no file header, so headers of later versions only are valid
+    fn is_supported_in_header(version: u16) -> bool {
+        Self::from_header(version).is_some()
+    }
+
+    fn from_header(version: u16) -> Option<Self> {
+        [BinaryVersion::V2, BinaryVersion::V3, BinaryVersion::V4]
+            .into_iter()
+            .find(|v| version == v.number())
+    }
+}
+
+// integer layout of record bodies, set by file header version
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Integers {
+    #[default]
+    Fixed,
+    Varint,
+}
+
+impl Integers {
+    fn of_version(version: u16) -> Self {
+        if BinaryVersion::V3.number() <= version {
+            Integers::Varint
+        } else {
+            Integers::Fixed
+        }
+    }
+
+    fn minimum_record_size(&self) -> u32 {
+        match self {
+            Integers::Fixed => MINIMUM_RECORD_SIZE,
+            Integers::Varint => MINIMUM_VARINT_RECORD_SIZE,
+        }
+    }
+}
+
+// offsets within record body
+struct BodyLayout {
+    kind: usize,
+    status: usize,
+    extensions: usize,
+}
+
+// footer index entry of a record, records with UUID ids have entries of id 0
+#[derive(Debug, Clone, Copy)]
+struct IndexEntry {
+    id: u64,
+    offset: u64,
+}
+
+// framing of existing stream found by `walk_frames`
+struct Frames {
+    version: BinaryVersion,
+    records: u64,
+    // footer offset and its entries
+    index: Option<(u64, Vec<IndexEntry>)>,
+}
+
+/// Layout options of records written in binary format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BinaryWriteOptions {
+    /// Layout version, streams without file header stay readable by older parsers.
+    pub version: BinaryVersion,
+    /// Append CRC32 checksum to every record, verified by the parser when present.
+    pub checksum: bool,
+    /// Byte order of integers, streams are read back with the same [`BinaryParseOptions::endianness`].
+    pub endianness: Endianness,
+    /// Follow records with footer index of their ids and offsets, see [`IndexedReader`].
+    pub index: bool,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct BinaryCodec {
+    options: BinaryParseOptions,
+    write_options: BinaryWriteOptions,
+    limits: ParseLimits,
+    strict: bool,
+}
 impl BinaryCodec {
+    pub(crate) fn new(options: BinaryParseOptions) -> Self {
+        Self {
+            options,
+            write_options: BinaryWriteOptions::default(),
+            limits: ParseLimits::default(),
+            strict: false,
+        }
+    }
+
+    pub(crate) fn with_write_options(mut self, write_options: BinaryWriteOptions) -> Self {
+        self.write_options = write_options;
+        self
+    }
+
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // strict parser rejects partial record signature trailing the last record
+    pub(crate) fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    // garbage size field is rejected before record body is allocated
+    fn check_record_size(&self, record_size: u32) -> Result<(), ParserError> {
+        ParseLimits::check_size("record", record_size.into(), self.limits.max_record_size)
+    }
+
     fn bytes_to_hex(&self, bytes: &[u8]) -> String {
         bytes.iter().map(|b| format!("{:02X}", b)).collect()
     }
 
-    fn read_u32_be<R: Read>(&self, r: &mut R) -> Result<u32, AppError> {
+    // reads until buffer is full or EOF, returns number of bytes read
+    fn read_all_or_eof<R: Read>(&self, r: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match r.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+
+    fn decode_utf8(
+        &self,
+        bytes: Vec<u8>,
+        pos: usize,
+        field_key: TxFieldKey,
+    ) -> Result<String, AppError> {
+        String::from_utf8(bytes)
+            .map_err(|_| ParserError::UnparsableValue("non utf-8 string".into()))
+            .add_parser_ctx(ParserContext::with_position_and_field_key(pos, field_key))
+    }
+
+    // minor units (mantissa) and scale of scaled extension value
+    fn decode_scaled(
+        bytes: Vec<u8>,
+        pos: usize,
+        field_key: TxFieldKey,
+    ) -> Result<(i64, u8), AppError> {
+        match bytes.as_slice() {
+            [units @ .., scale] if units.len() == 8 && *scale <= Money::MAX_SCALE => Ok((
+                i64::from_be_bytes(units.try_into().expect("8 bytes")),
+                *scale,
+            )),
+            _ => Err(ParserError::UnparsableValue(
+                "scaled value must be 8 bytes and scale up to 18".into(),
+            ))
+            .add_parser_ctx(ParserContext::with_position_and_field_key(pos, field_key)),
+        }
+    }
+
+    fn encode_scaled(units: i64, scale: u8) -> [u8; SCALED_SIZE] {
+        let mut bytes = [scale; SCALED_SIZE];
+        bytes[..8].copy_from_slice(&units.to_be_bytes());
+        bytes
+    }
+
+    // consumes leading ASCII whitespace, returns up to 4 skipped bytes and whether EOF was reached
+    fn skip_whitespace<R: BufRead>(&self, r: &mut R) -> std::io::Result<(Vec<u8>, bool)> {
+        let mut skipped = Vec::new();
+        loop {
+            let buf = r.fill_buf()?;
+            if buf.is_empty() {
+                return Ok((skipped, true));
+            }
+            let blank = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+            let keep = blank.min(RECORD_MAGIC.len() - skipped.len());
+            skipped.extend_from_slice(&buf[..keep]);
+            let at_data = blank < buf.len();
+            r.consume(blank);
+            if at_data {
+                return Ok((skipped, false));
+            }
+        }
+    }
+
+    // validates field codes and description length of a record body without building the record
+    fn check_record_body(&self, body: &[u8], integers: Integers) -> Result<(), ParserError> {
+        self.verify_checksum(body, integers)?;
+        let layout = self
+            .body_layout(body, integers)
+            .ok_or(ParserError::IncompleteRecord)?;
+        self.parse_kind_from_u8(body[layout.kind])?;
+        self.parse_status_from_u8(body[layout.status])?;
+        if body.len() < layout.extensions {
+            return Err(ParserError::IncompleteRecord);
+        }
+        Ok(())
+    }
+
+    // offsets of field codes and of extensions following description, `None` if body ends first
+    fn body_layout(&self, body: &[u8], integers: Integers) -> Option<BodyLayout> {
+        match integers {
+            Integers::Fixed => {
+                let len_bytes = body.get(DESCRIPTION_LEN_OFFSET..DESCRIPTION_LEN_OFFSET + 4)?;
+                Some(BodyLayout {
+                    kind: KIND_OFFSET,
+                    status: STATUS_OFFSET,
+                    extensions: MINIMUM_RECORD_SIZE as usize + self.u32_at(len_bytes) as usize,
+                })
+            }
+            Integers::Varint => {
+                let next = |at: &mut usize| {
+                    let (value, len) = read_varint(&mut body.get(*at..)?).ok()?;
+                    *at += len;
+                    Some(value)
+                };
+                let mut at = 0;
+                next(&mut at)?;
+                let kind = at;
+                at += 1;
+                // from, to, amount and timestamp
+                for _ in 0..4 {
+                    next(&mut at)?;
+                }
+                let status = at;
+                at += 1;
+                let desc_len = next(&mut at)?;
+                Some(BodyLayout {
+                    kind,
+                    status,
+                    extensions: at.saturating_add(usize::try_from(desc_len).ok()?),
+                })
+            }
+        }
+    }
+
+    // parses record body, record size is already validated against minimum of its layout
+    fn parse_record_body(
+        &self,
+        record_body: &[u8],
+        start: usize,
+        integers: Integers,
+    ) -> Result<TxRecord, AppError> {
+        self.verify_checksum(record_body, integers)
+            .add_parser_ctx(ParserContext::with_position(start))?;
+        let mut buf = std::io::Cursor::new(record_body);
+        let at = |buf: &std::io::Cursor<&[u8]>| start + buf.position() as usize;
+
+        // read and parse TXID
+        let tx_id = self.read_uint(&mut buf, start, integers)?;
+
+        // read and parse TXTYPE aka TXKIND
+        let tx_kind = self.read_code(&mut buf, start)?;
+        let tx_kind = self.parse_kind_from_u8(tx_kind).add_parser_ctx(
+            ParserContext::with_position_and_field_key(at(&buf), TxFieldKey::TxKind),
+        )?;
+
+        // read and parse FROM
+        let from = self.read_uint(&mut buf, start, integers)?;
+
+        // read and parse TO
+        let to = self.read_uint(&mut buf, start, integers)?;
+
+        // read and parse AMOUNT
+        let amount = match integers {
+            Integers::Fixed => self.read_i64(&mut buf)?,
+            Integers::Varint => zigzag_decode(self.read_uint(&mut buf, start, integers)?),
+        };
+
+        // read and parse TIMESTAMP
+        let ts_miliseconds = self.read_uint(&mut buf, start, integers)?;
+        let ts = TxTimestamp::from_millis(ts_miliseconds);
+
+        // read and parse STATUS
+        let status = self.read_code(&mut buf, start)?;
+        let status = self.parse_status_from_u8(status).add_parser_ctx(
+            ParserContext::with_position_and_field_key(at(&buf), TxFieldKey::Status),
+        )?;
+
+        // read and parse DESCRIPTION
+        let desc_len = match integers {
+            Integers::Fixed => u64::from(self.read_u32(&mut buf)?),
+            Integers::Varint => self.read_uint(&mut buf, start, integers)?,
+        };
+        if desc_len > (buf.get_ref().len() - buf.position() as usize) as u64 {
+            return Err(ParserError::IncompleteRecord)
+                .add_parser_ctx(ParserContext::with_position(at(&buf)));
+        }
+        let description = if 0 < desc_len {
+            let mut desc_bytes = vec![0u8; desc_len as usize];
+            buf.read_exact(&mut desc_bytes).add_read_ctx()?;
+            Some(self.decode_utf8(desc_bytes, at(&buf), TxFieldKey::Description)?)
+        } else {
+            None
+        };
+
+        // read and parse optional TLV extensions
+        let mut tenant = None;
+        let (mut from_iban, mut to_iban) = (None, None);
+        let mut uuid = None;
+        let mut fee = None;
+        let mut reference = None;
+        let (mut original_amount, mut exchange_rate) = (None, None);
+        let mut amount_scale = Money::DEFAULT_SCALE;
+        let mut b = [0u8; 1];
+        while (buf.position() as usize) < buf.get_ref().len() {
+            if EXTENSION_HEADER_SIZE > buf.get_ref().len() - buf.position() as usize {
+                return Err(ParserError::IncompleteRecord)
+                    .add_parser_ctx(ParserContext::with_position(at(&buf)));
+            }
+            buf.read_exact(&mut b).add_read_ctx()?;
+            let value_len = self.read_u32(&mut buf)? as usize;
+            let remaining = buf.get_ref().len() - buf.position() as usize;
+            if value_len > remaining {
+                return Err(ParserError::IncompleteRecord)
+                    .add_parser_ctx(ParserContext::with_position(at(&buf)));
+            }
+            let mut value = vec![0u8; value_len];
+            buf.read_exact(&mut value).add_read_ctx()?;
+            match b[0] {
+                EXTENSION_TENANT => {
+                    tenant = Some(self.decode_utf8(value, at(&buf), TxFieldKey::Tenant)?)
+                }
+                EXTENSION_FROM_IBAN => {
+                    from_iban = Some(self.decode_utf8(value, at(&buf), TxFieldKey::FromUserId)?)
+                }
+                EXTENSION_TO_IBAN => {
+                    to_iban = Some(self.decode_utf8(value, at(&buf), TxFieldKey::ToUserId)?)
+                }
+                EXTENSION_ID_UUID => {
+                    let bytes: [u8; UUID_SIZE] =
+                        value.try_into().map_err(|_| AppError::ParsingError {
+                            context: ParserContext::with_position_and_field_key(
+                                at(&buf),
+                                TxFieldKey::Id,
+                            ),
+                            source: ParserError::UnparsableValue("UUID must be 16 bytes".into()),
+                        })?;
+                    uuid = Some(u128::from_be_bytes(bytes));
+                }
+                EXTENSION_REFERENCE => {
+                    reference = Some(self.decode_utf8(value, at(&buf), TxFieldKey::Reference)?)
+                }
+                EXTENSION_FEE => {
+                    let (units, scale) = Self::decode_scaled(value, at(&buf), TxFieldKey::Fee)?;
+                    fee = Some(Money::new(units, scale));
+                }
+                EXTENSION_ORIGINAL_AMOUNT => {
+                    let (units, scale) =
+                        Self::decode_scaled(value, at(&buf), TxFieldKey::OriginalAmount)?;
+                    original_amount = Some(Money::new(units, scale));
+                }
+                EXTENSION_EXCHANGE_RATE => {
+                    let (mantissa, scale) =
+                        Self::decode_scaled(value, at(&buf), TxFieldKey::ExchangeRate)?;
+                    exchange_rate = Some(ExchangeRate::new(mantissa, scale));
+                }
+                EXTENSION_AMOUNT_SCALE => {
+                    amount_scale = match value.as_slice() {
+                        [scale] if *scale <= Money::MAX_SCALE => *scale,
+                        _ => {
+                            return Err(ParserError::UnparsableValue(
+                                "amount scale must be a byte up to 18".into(),
+                            ))
+                            .add_parser_ctx(
+                                ParserContext::with_position_and_field_key(
+                                    at(&buf),
+                                    TxFieldKey::Amount,
+                                ),
+                            );
+                        }
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        // assemble transaction record
+        Ok(TxRecord {
+            id: TxIdType::from_parts(tx_id, uuid),
+            kind: tx_kind,
+            from: AccountType::from_parts(from, from_iban),
+            to: AccountType::from_parts(to, to_iban),
+            amount: Money::new(amount, amount_scale),
+            fee,
+            original_amount,
+            exchange_rate,
+            ts,
+            status,
+            description,
+            tenant,
+            reference,
+            extras: Default::default(),
+        })
+    }
+
+    fn write_record_body(&self, w: &mut dyn Write, rec: &TxRecord) -> Result<(), AppError> {
+        let version = self.write_options.version;
+        let unsupported = if !version.has_kind(rec.kind) {
+            Some(format!("{} kind", rec.kind))
+        } else if !version.has_status(rec.status) {
+            Some(format!("{} status", rec.status))
+        } else {
+            None
+        };
+        if let Some(unsupported) = unsupported {
+            return Err(AppError::WriteError(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "{} of record id {} needs binary format version 4, stream is version {}",
+                    unsupported,
+                    rec.id,
+                    version.number()
+                ),
+            )));
+        }
+        let integers = version.integers();
+        let desc_bytes = rec.description.as_deref().unwrap_or_default().as_bytes();
+        self.write_uint(w, rec.id.as_numeric().unwrap_or(0), integers)?;
+        w.write_all(&[self.kind_to_u8(rec.kind)]).add_write_ctx()?;
+        self.write_uint(w, rec.from.as_numeric().unwrap_or(0), integers)?;
+        self.write_uint(w, rec.to.as_numeric().unwrap_or(0), integers)?;
+        match integers {
+            Integers::Fixed => self.write_i64(w, rec.amount.minor_units)?,
+            Integers::Varint => {
+                self.write_uint(w, zigzag_encode(rec.amount.minor_units), integers)?
+            }
+        }
+        self.write_uint(w, rec.ts.millis(), integers)?;
+        w.write_all(&[self.status_to_u8(rec.status)])
+            .add_write_ctx()?;
+        match integers {
+            Integers::Fixed => self.write_u32(w, desc_bytes.len() as u32)?,
+            Integers::Varint => self.write_uint(w, desc_bytes.len() as u64, integers)?,
+        }
+        w.write_all(desc_bytes).add_write_ctx()?;
+        let uuid = rec.id.as_uuid().map(u128::to_be_bytes);
+        let fee = rec
+            .fee
+            .map(|fee| Self::encode_scaled(fee.minor_units, fee.scale));
+        let original_amount = rec
+            .original_amount
+            .map(|amount| Self::encode_scaled(amount.minor_units, amount.scale));
+        let exchange_rate = rec
+            .exchange_rate
+            .map(|rate| Self::encode_scaled(rate.mantissa, rate.scale));
+        let amount_scale = [rec.amount.scale];
+        let extensions = [
+            (EXTENSION_TENANT, rec.tenant.as_deref().map(str::as_bytes)),
+            (EXTENSION_FROM_IBAN, rec.from.as_iban().map(str::as_bytes)),
+            (EXTENSION_TO_IBAN, rec.to.as_iban().map(str::as_bytes)),
+            (EXTENSION_ID_UUID, uuid.as_ref().map(|uuid| uuid.as_slice())),
+            (EXTENSION_FEE, fee.as_ref().map(|fee| fee.as_slice())),
+            (
+                EXTENSION_REFERENCE,
+                rec.reference.as_deref().map(str::as_bytes),
+            ),
+            (
+                EXTENSION_ORIGINAL_AMOUNT,
+                original_amount.as_ref().map(|amount| amount.as_slice()),
+            ),
+            (
+                EXTENSION_EXCHANGE_RATE,
+                exchange_rate.as_ref().map(|rate| rate.as_slice()),
+            ),
+            (
+                EXTENSION_AMOUNT_SCALE,
+                Some(amount_scale.as_slice()).filter(|_| Money::DEFAULT_SCALE != rec.amount.scale),
+            ),
+        ];
+        for (tag, value) in extensions {
+            let Some(value) = value else { continue };
+            w.write_all(&[tag]).add_write_ctx()?;
+            self.write_u32(w, value.len() as u32)?;
+            w.write_all(value).add_write_ctx()?;
+        }
+        Ok(())
+    }
+
+    // checksum extension covers body bytes preceding it; bodies too broken to walk
+    // through extensions are left to field parsing to report
+    fn verify_checksum(&self, body: &[u8], integers: Integers) -> Result<(), ParserError> {
+        let Some(layout) = self.body_layout(body, integers) else {
+            return Ok(());
+        };
+        let mut offset = layout.extensions;
+        while offset + EXTENSION_HEADER_SIZE <= body.len() {
+            let tag = body[offset];
+            let len = &body[offset + 1..offset + EXTENSION_HEADER_SIZE];
+            let value_len = self.u32_at(len) as usize;
+            let value_start = offset + EXTENSION_HEADER_SIZE;
+            if value_len > body.len() - value_start {
+                break;
+            }
+            if EXTENSION_CRC32 == tag && 4 == value_len {
+                let value = &body[value_start..value_start + 4];
+                let stored = self.u32_at(value);
+                if Crc32::checksum(&body[..offset]) != stored {
+                    return Err(ParserError::ChecksumMismatch);
+                }
+            }
+            offset = value_start + value_len;
+        }
+        Ok(())
+    }
+
+    // varint records may end inside any field, fixed ones are long enough for all of them
+    fn body_error(&self, e: std::io::Error, position: usize) -> AppError {
+        let source = match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => ParserError::IncompleteRecord,
+            std::io::ErrorKind::InvalidData => ParserError::UnparsableValue(e.to_string()),
+            _ => return AppError::ReadError(e),
+        };
+        AppError::ParsingError {
+            context: ParserContext::with_position(position),
+            source,
+        }
+    }
+
+    fn read_code(&self, buf: &mut std::io::Cursor<&[u8]>, start: usize) -> Result<u8, AppError> {
+        let mut b = [0u8; 1];
+        buf.read_exact(&mut b)
+            .map_err(|e| self.body_error(e, start + buf.position() as usize))?;
+        Ok(b[0])
+    }
+
+    fn read_uint(
+        &self,
+        buf: &mut std::io::Cursor<&[u8]>,
+        start: usize,
+        integers: Integers,
+    ) -> Result<u64, AppError> {
+        match integers {
+            Integers::Fixed => self.read_u64(buf),
+            Integers::Varint => read_varint(buf)
+                .map(|(value, _)| value)
+                .map_err(|e| self.body_error(e, start + buf.position() as usize)),
+        }
+    }
+
+    fn write_uint(&self, w: &mut dyn Write, v: u64, integers: Integers) -> Result<(), AppError> {
+        match integers {
+            Integers::Fixed => self.write_u64(w, v),
+            Integers::Varint => {
+                let mut bytes = Vec::with_capacity(MAX_VARINT_SIZE);
+                write_varint(&mut bytes, v);
+                w.write_all(&bytes).add_write_ctx()
+            }
+        }
+    }
+
+    // integer of the first 4 bytes in input byte order
+    fn u32_at(&self, b: &[u8]) -> u32 {
+        self.options.endianness.u32_from([b[0], b[1], b[2], b[3]])
+    }
+    fn u64_at(&self, b: &[u8]) -> u64 {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&b[..8]);
+        self.options.endianness.u64_from(bytes)
+    }
+    fn read_u32<R: Read>(&self, r: &mut R) -> Result<u32, AppError> {
         let mut b = [0u8; 4];
         r.read_exact(&mut b).add_read_ctx()?;
-        Ok(u32::from_be_bytes(b))
+        Ok(self.options.endianness.u32_from(b))
     }
-    fn read_u64_be<R: Read>(&self, r: &mut R) -> Result<u64, AppError> {
+    fn read_u64<R: Read>(&self, r: &mut R) -> Result<u64, AppError> {
         let mut b = [0u8; 8];
         r.read_exact(&mut b).add_read_ctx()?;
-        Ok(u64::from_be_bytes(b))
+        Ok(self.options.endianness.u64_from(b))
     }
-    fn read_i64_be<R: Read>(&self, r: &mut R) -> Result<i64, AppError> {
-        let mut b = [0u8; 8];
-        r.read_exact(&mut b).add_read_ctx()?;
-        Ok(i64::from_be_bytes(b))
+    fn read_i64<R: Read>(&self, r: &mut R) -> Result<i64, AppError> {
+        Ok(self.read_u64(r)? as i64)
     }
-    fn write_u32_be<W: Write>(&self, w: &mut W, v: u32) -> Result<(), AppError> {
-        w.write_all(&v.to_be_bytes()).add_write_ctx()?;
-        Ok(())
+    fn write_u16(&self, w: &mut dyn Write, v: u16) -> Result<(), AppError> {
+        w.write_all(&self.write_options.endianness.u16_bytes(v))
+            .add_write_ctx()
     }
-    fn write_u64_be<W: Write>(&self, w: &mut W, v: u64) -> Result<(), AppError> {
-        w.write_all(&v.to_be_bytes()).add_write_ctx()?;
-        Ok(())
+    fn write_u32(&self, w: &mut dyn Write, v: u32) -> Result<(), AppError> {
+        w.write_all(&self.write_options.endianness.u32_bytes(v))
+            .add_write_ctx()
     }
-    fn write_i64_be<W: Write>(&self, w: &mut W, v: i64) -> Result<(), AppError> {
-        w.write_all(&v.to_be_bytes()).add_write_ctx()?;
-        Ok(())
+    fn write_u64(&self, w: &mut dyn Write, v: u64) -> Result<(), AppError> {
+        w.write_all(&self.write_options.endianness.u64_bytes(v))
+            .add_write_ctx()
+    }
+    fn write_i64(&self, w: &mut dyn Write, v: i64) -> Result<(), AppError> {
+        self.write_u64(w, v as u64)
     }
     fn parse_kind_from_u8(&self, v: u8) -> Result<TxKind, ParserError> {
         match v {
             0 => Ok(TxKind::Deposit),
             1 => Ok(TxKind::Transfer),
             2 => Ok(TxKind::Withdrawal),
+            3 => Ok(TxKind::Refund),
+            4 => Ok(TxKind::Fee),
+            5 => Ok(TxKind::Reversal),
             _ => Err(ParserError::UnparsableValue(v.to_string())),
         }
     }
@@ -57,6 +767,9 @@ impl BinaryCodec {
             TxKind::Deposit => 0,
             TxKind::Transfer => 1,
             TxKind::Withdrawal => 2,
+            TxKind::Refund => 3,
+            TxKind::Fee => 4,
+            TxKind::Reversal => 5,
         }
     }
 
@@ -65,6 +778,9 @@ impl BinaryCodec {
             0 => Ok(TxStatus::Success),
             1 => Ok(TxStatus::Failure),
             2 => Ok(TxStatus::Pending),
+            3 => Ok(TxStatus::Cancelled),
+            4 => Ok(TxStatus::Reversed),
+            5 => Ok(TxStatus::Expired),
             _ => Err(ParserError::UnparsableValue(v.to_string())),
         }
     }
@@ -73,130 +789,944 @@ impl BinaryCodec {
             TxStatus::Success => 0,
             TxStatus::Failure => 1,
             TxStatus::Pending => 2,
+            TxStatus::Cancelled => 3,
+            TxStatus::Reversed => 4,
+            TxStatus::Expired => 5,
         }
     }
 }
 impl DataParser for BinaryCodec {
-    fn parse<R: Read>(&self, mut r: R) -> Result<Vec<TxRecord>, AppError> {
-        let mut pos: usize = 0;
-        let mut result = Vec::new();
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_recovering(r, &mut |rejected| Err(rejected.into_error()))
+    }
+}
 
-        loop {
-            // reading record signature, distinct EOF or io::Error
-            let mut magic = [0u8; 4];
-            match r.read_exact(&mut magic) {
-                Ok(()) => pos += 4,
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
-                Err(e) => return Err(AppError::ReadError(e)),
-            }
-            if RECORD_MAGIC != magic {
-                return Err(ParserError::InvalidRecordHeader(self.bytes_to_hex(&magic)))
-                    .add_parser_ctx(ParserContext::with_position(pos));
+impl RecoveringParser for BinaryCodec {
+    fn parse_recovering<R: Read>(
+        &self,
+        r: R,
+        on_reject: &mut dyn FnMut(RejectedInput) -> Result<(), AppError>,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        collect_recovering(
+            BinaryRecordReader::new(self.clone(), r),
+            self.limits,
+            on_reject,
+        )
+    }
+}
+
+impl StreamingParser for BinaryCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, r: R) -> RecordStream<'a> {
+        RecordStream::new(BinaryRecordReader::new(self.clone(), r))
+    }
+}
+
+struct BinaryRecordReader<R> {
+    codec: BinaryCodec,
+    r: BufReader<R>,
+    pos: usize,
+    started: bool,
+    // bytes given back to the stream by resynchronization, read before `r`
+    pushback: VecDeque<u8>,
+    // bytes of the record being read
+    consumed: Vec<u8>,
+    // records count declared by file header, checked once the stream is over
+    declared_records: Option<u64>,
+    records: u64,
+    integers: Integers,
+}
+
+impl<R: Read> BinaryRecordReader<R> {
+    fn new(codec: BinaryCodec, r: R) -> Self {
+        Self {
+            codec,
+            r: BufReader::new(r),
+            pos: 0,
+            started: false,
+            pushback: VecDeque::new(),
+            consumed: Vec::new(),
+            declared_records: None,
+            records: 0,
+            integers: Integers::Fixed,
+        }
+    }
+
+    // whitespace-only input carries no records, same as in text formats
+    fn start(&mut self) -> Result<bool, AppError> {
+        let (skipped, at_eof) = self.codec.skip_whitespace(&mut self.r).add_read_ctx()?;
+        if !at_eof && !skipped.is_empty() {
+            return Err(ParserError::InvalidRecordHeader(
+                self.codec.bytes_to_hex(&skipped),
+            ))
+            .add_parser_ctx(ParserContext::with_position(self.pos));
+        }
+        if !at_eof {
+            self.read_file_header()?;
+        }
+        Ok(!at_eof)
+    }
+
+    // headerless v1 stream starts with record signature, which is given back untouched
+    fn read_file_header(&mut self) -> Result<(), AppError> {
+        let mut magic = [0u8; 4];
+        let n = self.fill(&mut magic)?;
+        if n < magic.len() || FILE_MAGIC != magic {
+            for &b in magic[..n].iter().rev() {
+                self.pushback.push_front(b);
             }
+            self.pos -= n;
+            self.consumed.clear();
+            return Ok(());
+        }
+        let mut header = [0u8; FILE_HEADER_SIZE - 4];
+        if self.fill(&mut header)? < header.len() {
+            return Err(ParserError::InvalidFileHeader)
+                .add_parser_ctx(ParserContext::with_position(self.pos));
+        }
+        let endianness = self.codec.options.endianness;
+        let version = endianness.u16_from([header[0], header[1]]);
+        if !BinaryVersion::is_supported_in_header(version) {
+            return Err(ParserError::UnparsableValue(format!(
+                "binary format version {}",
+                version
+            )))
+            .add_parser_ctx(ParserContext::with_position(4));
+        }
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&header[2..]);
+        self.declared_records = Some(endianness.u64_from(count));
+        self.integers = Integers::of_version(version);
+        self.consumed.clear();
+        Ok(())
+    }
 
-            let record_size = self.read_u32_be(&mut r)?;
-            pos += 4;
-            if MINIMUM_RECORD_SIZE > record_size {
-                return Err(ParserError::IncompleteRecord)
-                    .add_parser_ctx(ParserContext::with_position(pos));
+    // number of records read must match the file header, unless framing was lost on the way
+    fn check_declared_records(&mut self) -> Result<(), AppError> {
+        match self.declared_records.take() {
+            Some(declared) if declared != self.records => Err(ParserError::InvalidFileHeader)
+                .add_parser_ctx(ParserContext::with_position(self.pos)),
+            _ => Ok(()),
+        }
+    }
+
+    // reads until buffer is full or EOF, returns number of bytes read
+    fn fill(&mut self, buf: &mut [u8]) -> Result<usize, AppError> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.pushback.pop_front() {
+                Some(b) => buf[filled] = b,
+                None => break,
             }
+            filled += 1;
+        }
+        filled += self
+            .codec
+            .read_all_or_eof(&mut self.r, &mut buf[filled..])
+            .add_read_ctx()?;
+        self.consumed.extend_from_slice(&buf[..filled]);
+        self.pos += filled;
+        Ok(filled)
+    }
 
-            // Read record body into buffer at once
-            let mut record_body = vec![0u8; record_size as usize];
-            r.read_exact(&mut record_body).add_read_ctx()?;
-            let mut buf = std::io::Cursor::new(record_body);
+    fn fill_exact(&mut self, buf: &mut [u8]) -> Result<(), AppError> {
+        if self.fill(buf)? < buf.len() {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof)).add_read_ctx();
+        }
+        Ok(())
+    }
 
-            // read and parse TXID
-            let tx_id = self.read_u64_be(&mut buf)?;
-            pos += 8;
-            let mut b = [0u8; 1];
+    fn read_record(&mut self) -> Result<Option<Result<TxRecord, RejectedInput>>, AppError> {
+        self.consumed.clear();
+        let start = self.pos;
+        // reading record signature, distinct EOF or io::Error
+        let mut magic = [0u8; 4];
+        let n = self.fill(&mut magic)?;
+        if n < magic.len() {
+            if self.codec.strict && magic[..n].iter().any(|b| !b.is_ascii_whitespace()) {
+                return self.lost_framing(start, ParserError::IncompleteRecord);
+            }
+            self.check_declared_records()?;
+            return Ok(None);
+        }
+        if magic.iter().all(u8::is_ascii_whitespace) && self.at_whitespace_tail()? {
+            self.check_declared_records()?;
+            return Ok(None);
+        }
+        if INDEX_MAGIC == magic {
+            self.skip_index(start)?;
+            self.check_declared_records()?;
+            return Ok(None);
+        }
+        if RECORD_MAGIC != magic {
+            let error = ParserError::InvalidRecordHeader(self.codec.bytes_to_hex(&magic));
+            return self.lost_framing(start, error);
+        }
 
-            // read and parse TXTYPE aka TXKIND
-            buf.read_exact(&mut b).add_read_ctx()?;
-            pos += 1;
-            let tx_kind = self.parse_kind_from_u8(b[0]).add_parser_ctx(
-                ParserContext::with_position_and_field_key(pos, TxFieldKey::TxKind),
-            )?;
+        let mut size = [0u8; 4];
+        if let Err(e) = self.fill_exact(&mut size) {
+            return self.lost_framing_on(start, e);
+        }
+        let record_size = self.codec.u32_at(&size);
+        if self.integers.minimum_record_size() > record_size {
+            return self.lost_framing(start, ParserError::IncompleteRecord);
+        }
+        if let Err(error) = self.codec.check_record_size(record_size) {
+            return self.lost_framing(start, error);
+        }
 
-            // read and parse FROM
-            let from = self.read_u64_be(&mut buf)?;
-            pos += 8;
+        // Read record body into buffer at once
+        let mut record_body = vec![0u8; record_size as usize];
+        let body_pos = self.pos;
+        if let Err(e) = self.fill_exact(&mut record_body) {
+            return self.lost_framing_on(start, e);
+        }
+        self.records += 1;
+        match self
+            .codec
+            .parse_record_body(&record_body, body_pos, self.integers)
+        {
+            Ok(tx) => Ok(Some(Ok(tx))),
+            // framing is intact, malformed record can be stepped over
+            Err(AppError::ParsingError { context, source }) => {
+                let raw = std::mem::take(&mut self.consumed);
+                Ok(Some(Err(RejectedInput::new(context, source, raw))))
+            }
+            Err(e) => Err(e),
+        }
+    }
 
-            // read and parse TO
-            let to = self.read_u64_be(&mut buf)?;
-            pos += 8;
+    // true if only ASCII whitespace is left till EOF, bytes read are given back otherwise
+    fn at_whitespace_tail(&mut self) -> Result<bool, AppError> {
+        let mut read = Vec::new();
+        let mut b = [0u8; 1];
+        while 0 < self.fill(&mut b)? {
+            read.push(b[0]);
+            if !b[0].is_ascii_whitespace() {
+                for &b in read.iter().rev() {
+                    self.pushback.push_front(b);
+                }
+                self.pos -= read.len();
+                self.consumed.truncate(self.consumed.len() - read.len());
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
 
-            // read and parse AMOUNT
-            let amount = self.read_i64_be(&mut buf)?;
-            pos += 8;
+    // footer index at `start` ends the stream, its entries are not needed for sequential reading
+    fn skip_index(&mut self, start: usize) -> Result<(), AppError> {
+        let invalid =
+            || Err(ParserError::InvalidIndex).add_parser_ctx(ParserContext::with_position(start));
+        let mut count = [0u8; 8];
+        if self.fill(&mut count)? < count.len() {
+            return invalid();
+        }
+        let Some(mut left) = self.codec.u64_at(&count).checked_mul(INDEX_ENTRY_SIZE) else {
+            return invalid();
+        };
+        let mut chunk = [0u8; 4096];
+        while 0 < left {
+            let n = left.min(chunk.len() as u64) as usize;
+            let read = self.fill(&mut chunk[..n])?;
+            self.consumed.clear();
+            if read < n {
+                return invalid();
+            }
+            left -= n as u64;
+        }
+        let mut trailer = [0u8; INDEX_TRAILER_SIZE as usize];
+        if self.fill(&mut trailer)? < trailer.len()
+            || INDEX_MAGIC != trailer[8..]
+            || start as u64 != self.codec.u64_at(&trailer)
+            || 0 < self.fill(&mut [0u8; 1])?
+        {
+            return invalid();
+        }
+        Ok(())
+    }
 
-            // read and parse TIMESTAMP
-            let ts_miliseconds = self.read_u64_be(&mut buf)?;
-            let ts = TxTimestamp::from_millis(ts_miliseconds);
-            pos += 8;
+    fn lost_framing(
+        &mut self,
+        start: usize,
+        error: ParserError,
+    ) -> Result<Option<Result<TxRecord, RejectedInput>>, AppError> {
+        let e = AppError::ParsingError {
+            context: ParserContext::with_position(self.pos),
+            source: error,
+        };
+        self.lost_framing_on(start, e)
+    }
 
-            // read and parse STATUS
-            buf.read_exact(&mut b).add_read_ctx()?;
-            pos += 1;
-            let status = self.parse_status_from_u8(b[0]).add_parser_ctx(
-                ParserContext::with_position_and_field_key(pos, TxFieldKey::Status),
-            )?;
-
-            // read and parse DESCRIPTION
-            let desc_len = self.read_u32_be(&mut buf)? as usize;
-            pos += 4;
-            let description = if 0 < desc_len {
-                let mut desc_bytes = vec![0u8; desc_len];
-                buf.read_exact(&mut desc_bytes).add_read_ctx()?;
-                pos += desc_len;
-                String::from_utf8(desc_bytes)
-                    .map_err(|_| ParserError::UnparsableValue("non utf-8 string".into()))
-                    .add_parser_ctx(ParserContext::with_position_and_field_key(
-                        pos,
-                        TxFieldKey::Description,
-                    ))?
-            } else {
-                "".into()
-            };
+    // without resynchronization the rest of the stream can't be read
+    fn lost_framing_on(
+        &mut self,
+        start: usize,
+        e: AppError,
+    ) -> Result<Option<Result<TxRecord, RejectedInput>>, AppError> {
+        let error = match e {
+            _ if !self.codec.options.resync => return Err(e),
+            AppError::ParsingError { source, .. } => source,
+            AppError::ReadError(e) if std::io::ErrorKind::UnexpectedEof == e.kind() => {
+                ParserError::IncompleteRecord
+            }
+            e => return Err(e),
+        };
+        self.declared_records = None;
+        let skipped = self.skip_to_magic(start)?;
+        let end = start + skipped.len();
+        Ok(Some(Err(RejectedInput::new(
+            ParserContext::with_byte_range(start, end),
+            error,
+            skipped,
+        ))))
+    }
 
-            // assemble transaction record
-            result.push(TxRecord {
-                id: TxIdType(tx_id),
-                kind: tx_kind,
-                from: AccountType(from),
-                to: AccountType(to),
-                amount,
-                ts,
-                status,
-                description,
-            });
+    // steps over bytes of corrupt record at `start` till the next signature or EOF,
+    // bytes read after the first one may hold the next record and are scanned again
+    fn skip_to_magic(&mut self, start: usize) -> Result<Vec<u8>, AppError> {
+        let consumed = std::mem::take(&mut self.consumed);
+        for &b in consumed[1..].iter().rev() {
+            self.pushback.push_front(b);
         }
+        self.pos = start + 1;
+        let mut skipped = vec![consumed[0]];
+        let mut b = [0u8; 1];
+        while 0 < self.fill(&mut b)? {
+            skipped.push(b[0]);
+            if RECORD_MAGIC.len() < skipped.len() && skipped.ends_with(&RECORD_MAGIC) {
+                skipped.truncate(skipped.len() - RECORD_MAGIC.len());
+                for &b in RECORD_MAGIC.iter().rev() {
+                    self.pushback.push_front(b);
+                }
+                self.pos -= RECORD_MAGIC.len();
+                break;
+            }
+        }
+        self.consumed.clear();
+        Ok(skipped)
+    }
+}
 
-        Ok(result)
+impl<R: Read> RecordReader for BinaryRecordReader<R> {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        if !self.started {
+            self.started = true;
+            match self.start() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        self.read_record().transpose()
     }
 }
 
 impl DataWriter for BinaryCodec {
-    fn write<W: Write>(&self, w: &mut W, data: &[TxRecord]) -> Result<(), AppError> {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        let version = self.write_options.version;
+        let mut offset = 0;
+        if BinaryVersion::V1 != version {
+            w.write_all(&FILE_MAGIC).add_write_ctx()?;
+            self.write_u16(w, version.number())?;
+            self.write_u64(w, data.len() as u64)?;
+            offset = FILE_HEADER_SIZE as u64;
+        }
+        let mut index = Vec::new();
+        let end = self.write_records(w, data, offset, &mut index)?;
+        if self.write_options.index {
+            self.write_index(w, &index, end)?;
+        }
+        Ok(())
+    }
+}
+
+impl BinaryCodec {
+    // writes records starting at `offset` of the stream, returns offset past the last one;
+    // their index entries are collected into `index` when footer index is written
+    fn write_records(
+        &self,
+        w: &mut dyn Write,
+        data: &[TxRecord],
+        mut offset: u64,
+        index: &mut Vec<IndexEntry>,
+    ) -> Result<u64, AppError> {
+        let mut body = Vec::new();
         for rec in data {
-            // pre-compute sizes
-            let desc_bytes = rec.description.as_bytes();
-            let record_bites = (8 + 1 + 8 + 8 + 8 + 8 + 1 + 4 + desc_bytes.len()) as u32;
+            body.clear();
+            self.write_record_body(&mut body, rec)?;
+            if self.write_options.checksum {
+                let crc = Crc32::checksum(&body);
+                body.push(EXTENSION_CRC32);
+                self.write_u32(&mut body, 4)?;
+                self.write_u32(&mut body, crc)?;
+            }
+            if self.write_options.index {
+                index.push(IndexEntry {
+                    id: rec.id.as_numeric().unwrap_or(0),
+                    offset,
+                });
+            }
             // write record header
             w.write_all(&RECORD_MAGIC).add_write_ctx()?;
-            self.write_u32_be(w, record_bites)?;
-            // write record body
-            self.write_u64_be(w, rec.id.0)?;
-            w.write_all(&[self.kind_to_u8(rec.kind)]).add_write_ctx()?;
-            self.write_u64_be(w, rec.from.0)?;
-            self.write_u64_be(w, rec.to.0)?;
-            self.write_i64_be(w, rec.amount)?;
-            self.write_u64_be(w, rec.ts.millis())?;
-            w.write_all(&[self.status_to_u8(rec.status)])
+            self.write_u32(w, body.len() as u32)?;
+            w.write_all(&body).add_write_ctx()?;
+            offset += RECORD_HEADER_SIZE + body.len() as u64;
+        }
+        Ok(offset)
+    }
+
+    // writes footer index starting at offset `start` of the stream
+    fn write_index(
+        &self,
+        w: &mut dyn Write,
+        index: &[IndexEntry],
+        start: u64,
+    ) -> Result<(), AppError> {
+        w.write_all(&INDEX_MAGIC).add_write_ctx()?;
+        self.write_u64(w, index.len() as u64)?;
+        for entry in index {
+            self.write_u64(w, entry.id)?;
+            self.write_u64(w, entry.offset)?;
+        }
+        self.write_u64(w, start)?;
+        w.write_all(&INDEX_MAGIC).add_write_ctx()
+    }
+
+    // appends records in layout of the stream after validating its framing; records are
+    // encoded before anything is written, so a record failing to encode leaves `f` untouched
+    pub(crate) fn append<F: Read + Write + Seek>(
+        &self,
+        f: &mut F,
+        data: &[TxRecord],
+    ) -> Result<u64, AppError> {
+        let len = f.seek(SeekFrom::End(0)).add_read_ctx()?;
+        if 0 == len {
+            let mut bytes = Vec::new();
+            self.write(&mut bytes, data)?;
+            f.write_all(&bytes).add_write_ctx()?;
+            f.flush().add_write_ctx()?;
+            return Ok(data.len() as u64);
+        }
+        f.rewind().add_read_ctx()?;
+        let frames = self.walk_frames(f, len)?;
+
+        let codec = Self {
+            write_options: BinaryWriteOptions {
+                version: frames.version,
+                index: frames.index.is_some(),
+                ..self.write_options.clone()
+            },
+            ..self.clone()
+        };
+        // footer index is overwritten, the new one is longer as it gets entries of new records
+        let (end, mut index) = frames.index.unwrap_or((len, Vec::new()));
+        let mut tail = Vec::new();
+        let tail_end = codec.write_records(&mut tail, data, end, &mut index)?;
+        if codec.write_options.index {
+            codec.write_index(&mut tail, &index, tail_end)?;
+        }
+        let records = frames.records + data.len() as u64;
+        let mut count = Vec::new();
+        self.write_u64(&mut count, records)?;
+
+        f.seek(SeekFrom::Start(end)).add_write_ctx()?;
+        f.write_all(&tail).add_write_ctx()?;
+        if BinaryVersion::V1 != frames.version {
+            f.seek(SeekFrom::Start(RECORDS_COUNT_OFFSET as u64))
                 .add_write_ctx()?;
-            self.write_u32_be(w, desc_bytes.len() as u32)?;
-            w.write_all(desc_bytes).add_write_ctx()?;
+            f.write_all(&count).add_write_ctx()?;
         }
-        Ok(())
+        f.flush().add_write_ctx()?;
+        Ok(records)
+    }
+
+    // reads file header of stream positioned at its start, returns offset of the first record,
+    // layout version and declared records count
+    fn read_stream_header<F: Read>(
+        &self,
+        f: &mut F,
+    ) -> Result<(u64, BinaryVersion, Option<u64>), AppError> {
+        let mut header = [0u8; FILE_HEADER_SIZE];
+        let n = self.read_all_or_eof(f, &mut header).add_read_ctx()?;
+        if n < 4 || FILE_MAGIC != header[..4] {
+            return Ok((0, BinaryVersion::V1, None));
+        }
+        if n < FILE_HEADER_SIZE {
+            return Err(ParserError::InvalidFileHeader)
+                .add_parser_ctx(ParserContext::with_position(0));
+        }
+        let number = self.options.endianness.u16_from([header[4], header[5]]);
+        let version = BinaryVersion::from_header(number)
+            .ok_or_else(|| {
+                ParserError::UnparsableValue(format!("binary format version {}", number))
+            })
+            .add_parser_ctx(ParserContext::with_position(4))?;
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&header[RECORDS_COUNT_OFFSET..]);
+        let declared = self.options.endianness.u64_from(count);
+        Ok((FILE_HEADER_SIZE as u64, version, Some(declared)))
+    }
+
+    // steps over record bodies of `len` bytes long stream up to its end or footer index
+    fn walk_frames<F: Read + Seek>(&self, f: &mut F, len: u64) -> Result<Frames, AppError> {
+        let (mut pos, version, declared) = self.read_stream_header(f)?;
+        let minimum = u64::from(version.integers().minimum_record_size());
+        let mut records = 0;
+        let mut index = None;
+        while pos < len {
+            let incomplete = || {
+                Err(ParserError::IncompleteRecord)
+                    .add_parser_ctx(ParserContext::with_position(pos as usize))
+            };
+            if len - pos < RECORD_HEADER_SIZE {
+                return incomplete();
+            }
+            let mut record_header = [0u8; RECORD_HEADER_SIZE as usize];
+            f.seek(SeekFrom::Start(pos)).add_read_ctx()?;
+            f.read_exact(&mut record_header).add_read_ctx()?;
+            if INDEX_MAGIC == record_header[..4] {
+                let entries = self.read_index(f, pos, len)?;
+                if records != entries.len() as u64 {
+                    return Err(ParserError::InvalidIndex)
+                        .add_parser_ctx(ParserContext::with_position(pos as usize));
+                }
+                index = Some((pos, entries));
+                break;
+            }
+            if RECORD_MAGIC != record_header[..4] {
+                return Err(ParserError::InvalidRecordHeader(
+                    self.bytes_to_hex(&record_header[..4]),
+                ))
+                .add_parser_ctx(ParserContext::with_position(pos as usize));
+            }
+            let size = u64::from(self.u32_at(&record_header[4..]));
+            if size < minimum || len - pos - RECORD_HEADER_SIZE < size {
+                return incomplete();
+            }
+            pos += RECORD_HEADER_SIZE + size;
+            records += 1;
+        }
+        if let Some(declared) = declared
+            && declared != records
+        {
+            return Err(ParserError::InvalidFileHeader)
+                .add_parser_ctx(ParserContext::with_position(RECORDS_COUNT_OFFSET));
+        }
+        Ok(Frames {
+            version,
+            records,
+            index,
+        })
+    }
+
+    // reads footer index at offset `start` of `len` bytes long stream, it has to end the stream
+    fn read_index<F: Read + Seek>(
+        &self,
+        f: &mut F,
+        start: u64,
+        len: u64,
+    ) -> Result<Vec<IndexEntry>, AppError> {
+        let invalid = || {
+            Err(ParserError::InvalidIndex)
+                .add_parser_ctx(ParserContext::with_position(start as usize))
+        };
+        let mut header = [0u8; INDEX_HEADER_SIZE as usize];
+        f.seek(SeekFrom::Start(start)).add_read_ctx()?;
+        if self.read_all_or_eof(f, &mut header).add_read_ctx()? < header.len()
+            || INDEX_MAGIC != header[..4]
+        {
+            return invalid();
+        }
+        let count = self.u64_at(&header[4..]);
+        let size = count
+            .checked_mul(INDEX_ENTRY_SIZE)
+            .and_then(|entries| entries.checked_add(INDEX_HEADER_SIZE + INDEX_TRAILER_SIZE));
+        if Some(len) != size.and_then(|size| size.checked_add(start)) {
+            return invalid();
+        }
+
+        let mut footer = vec![0u8; (count * INDEX_ENTRY_SIZE + INDEX_TRAILER_SIZE) as usize];
+        f.read_exact(&mut footer).add_read_ctx()?;
+        let (entries, trailer) = footer.split_at(footer.len() - INDEX_TRAILER_SIZE as usize);
+        if INDEX_MAGIC != trailer[8..] || start != self.u64_at(trailer) {
+            return invalid();
+        }
+        Ok(entries
+            .chunks_exact(INDEX_ENTRY_SIZE as usize)
+            .map(|entry| IndexEntry {
+                id: self.u64_at(entry),
+                offset: self.u64_at(&entry[8..]),
+            })
+            .collect())
+    }
+}
+
+impl StreamingWriter for BinaryCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        match self.write_options.version {
+            BinaryVersion::V1 if !self.write_options.index => {
+                Ok(RecordSink::new(EachRecord::new(self.clone(), w)))
+            }
+            // records count in file header precedes the records, footer index follows all of them
+            _ => Ok(RecordSink::new(Blocks::whole(self.clone(), w))),
+        }
+    }
+}
+
+/// Appends records to binary stream `f` without rewriting it, returns number of records it holds.
+///
+/// Framing of the records already in the stream is validated first, nothing is written after
+/// a truncated or corrupt tail. Appended records take the layout of the stream and records count
+/// of its file header is updated; `options.version` is used for empty streams only, byte order
+/// of the stream has to be `options.endianness`.
+pub fn append<F: Read + Write + Seek>(
+    mut f: F,
+    data: &[TxRecord],
+    options: &BinaryWriteOptions,
+) -> Result<u64, AppError> {
+    let parse_options = BinaryParseOptions {
+        endianness: options.endianness,
+        ..Default::default()
+    };
+    BinaryCodec::new(parse_options)
+        .with_write_options(options.clone())
+        .append(&mut f, data)
+}
+
+/// Random access to records of binary stream with footer index, see [`BinaryWriteOptions::index`].
+///
+/// File header and footer index are read on open, every fetched record costs a seek and a read
+/// of its own bytes only.
+pub struct IndexedReader<F> {
+    codec: BinaryCodec,
+    f: F,
+    integers: Integers,
+    entries: Vec<IndexEntry>,
+    // position of the first record of every id
+    positions: HashMap<TxIdType, usize>,
+    // records end where footer index starts
+    index_start: u64,
+}
+
+impl<F: Read + Seek> IndexedReader<F> {
+    /// Reads footer index of `f`, streams without it fail with [`ParserError::InvalidIndex`].
+    pub fn open(mut f: F, options: &BinaryParseOptions) -> Result<Self, AppError> {
+        let codec = BinaryCodec::new(options.clone());
+        let len = f.seek(SeekFrom::End(0)).add_read_ctx()?;
+        if len < INDEX_HEADER_SIZE + INDEX_TRAILER_SIZE {
+            return Err(ParserError::InvalidIndex).add_parser_ctx(ParserContext::with_position(0));
+        }
+        let mut trailer = [0u8; INDEX_TRAILER_SIZE as usize];
+        f.seek(SeekFrom::End(-(INDEX_TRAILER_SIZE as i64)))
+            .add_read_ctx()?;
+        f.read_exact(&mut trailer).add_read_ctx()?;
+        let index_start = codec.u64_at(&trailer);
+        if INDEX_MAGIC != trailer[8..] || len - INDEX_TRAILER_SIZE < index_start {
+            return Err(ParserError::InvalidIndex)
+                .add_parser_ctx(ParserContext::with_position(len as usize));
+        }
+
+        f.rewind().add_read_ctx()?;
+        let (first_record, version, declared) = codec.read_stream_header(&mut f)?;
+        let entries = codec.read_index(&mut f, index_start, len)?;
+        if let Some(declared) = declared
+            && declared != entries.len() as u64
+        {
+            return Err(ParserError::InvalidFileHeader)
+                .add_parser_ctx(ParserContext::with_position(RECORDS_COUNT_OFFSET));
+        }
+        let mut positions = HashMap::new();
+        for (n, entry) in entries.iter().enumerate() {
+            if entry.offset < first_record || index_start < entry.offset + RECORD_HEADER_SIZE {
+                return Err(ParserError::InvalidIndex)
+                    .add_parser_ctx(ParserContext::with_position(index_start as usize));
+            }
+            positions.entry(TxIdType::Numeric(entry.id)).or_insert(n);
+        }
+        Ok(Self {
+            codec,
+            f,
+            integers: version.integers(),
+            entries,
+            positions,
+            index_start,
+        })
+    }
+
+    /// Number of records in the stream.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if the stream has no records.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Reads record at position `n` of the stream, `None` past the last one.
+    pub fn get(&mut self, n: usize) -> Result<Option<TxRecord>, AppError> {
+        match self.entries.get(n) {
+            Some(entry) => self.read_at(entry.offset).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the first record with `id`, `None` if the stream has none.
+    ///
+    /// UUID ids share index entries of id 0, records of such entries are read until one matches.
+    pub fn find(&mut self, id: TxIdType) -> Result<Option<TxRecord>, AppError> {
+        let key = TxIdType::Numeric(id.as_numeric().unwrap_or(0));
+        let Some(&first) = self.positions.get(&key) else {
+            return Ok(None);
+        };
+        if TxIdType::Numeric(0) != key {
+            return self.get(first);
+        }
+        for n in first..self.entries.len() {
+            let entry = self.entries[n];
+            if 0 == entry.id {
+                let tx = self.read_at(entry.offset)?;
+                if tx.id == id {
+                    return Ok(Some(tx));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_at(&mut self, offset: u64) -> Result<TxRecord, AppError> {
+        let position = ParserContext::with_position(offset as usize);
+        let mut header = [0u8; RECORD_HEADER_SIZE as usize];
+        self.f.seek(SeekFrom::Start(offset)).add_read_ctx()?;
+        self.f.read_exact(&mut header).add_read_ctx()?;
+        if RECORD_MAGIC != header[..4] {
+            return Err(ParserError::InvalidRecordHeader(
+                self.codec.bytes_to_hex(&header[..4]),
+            ))
+            .add_parser_ctx(position);
+        }
+        let size = self.codec.u32_at(&header[4..]);
+        let body_pos = offset + RECORD_HEADER_SIZE;
+        if self.integers.minimum_record_size() > size
+            || self.index_start - body_pos < u64::from(size)
+        {
+            return Err(ParserError::IncompleteRecord).add_parser_ctx(position);
+        }
+        let mut body = vec![0u8; size as usize];
+        self.f.read_exact(&mut body).add_read_ctx()?;
+        self.codec
+            .parse_record_body(&body, body_pos as usize, self.integers)
+    }
+}
+
+/// Structural problem found while scanning binary stream.
+#[derive(Debug)]
+pub struct ScanIssue {
+    /// Offset of the record the issue belongs to.
+    pub offset: u64,
+    /// Problem found.
+    pub error: ParserError,
+}
+
+/// Outcome of a structural scan of binary stream, see [`scan`].
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    /// Number of records with valid framing.
+    pub records: usize,
+    /// Number of bytes walked through.
+    pub bytes: u64,
+    /// Time spent scanning.
+    pub elapsed: Duration,
+    /// First structural issues found (up to requested maximum).
+    pub issues: Vec<ScanIssue>,
+    /// Number of structural issues found, including ones not kept in `issues`.
+    pub issues_found: usize,
+    /// Framing was lost, rest of the stream was not scanned.
+    pub aborted: bool,
+}
+
+impl ScanReport {
+    /// True if no structural issues were found.
+    pub fn is_healthy(&self) -> bool {
+        0 == self.issues_found && !self.aborted
+    }
+    /// Scan throughput in bytes per second.
+    pub fn bytes_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if 0.0 < seconds {
+            self.bytes as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+    fn push(&mut self, offset: u64, error: ParserError, max_issues: usize) {
+        self.issues_found += 1;
+        if self.issues.len() < max_issues {
+            self.issues.push(ScanIssue { offset, error });
+        }
+    }
+    fn push_fatal(&mut self, offset: u64, error: ParserError, max_issues: usize) {
+        self.push(offset, error, max_issues);
+        self.aborted = true;
+    }
+}
+
+// offsets of records walked, matched against footer index entries without keeping them:
+// offsets only grow, so equal counts, last offsets and folds mean equal sequences
+#[derive(Default)]
+struct OffsetsFold {
+    count: u64,
+    last: Option<u64>,
+    fold: u64,
+}
+
+impl OffsetsFold {
+    const FOLD_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    // false if offset does not follow the previous one
+    fn push(&mut self, offset: u64) -> bool {
+        let increases = self.last.is_none_or(|last| last < offset);
+        self.count += 1;
+        self.last = Some(offset);
+        self.fold = (self.fold ^ offset).wrapping_mul(Self::FOLD_PRIME);
+        increases
+    }
+}
+
+/// Walks binary stream verifying record framing, field codes and footer index without
+/// materializing records.
+///
+/// The whole stream is walked unless framing is lost (bad magic, bad size, truncated body),
+/// at most `max_issues` issues are kept in the report, all of them are counted.
+/// Footer index offsets have to increase and match offsets of the records walked.
+/// Stream is read in byte order of `options`, record sizes over the default
+/// [`ParseLimits::max_record_size`] are taken for lost framing.
+pub fn scan<R: Read>(
+    mut r: R,
+    options: &BinaryParseOptions,
+    max_issues: usize,
+) -> Result<ScanReport, AppError> {
+    let codec = BinaryCodec::new(options.clone());
+    let started = Instant::now();
+    let mut report = ScanReport::default();
+    let mut body = Vec::new();
+    let mut integers = Integers::Fixed;
+    let mut offsets = OffsetsFold::default();
+
+    loop {
+        let offset = report.bytes;
+        let mut header = [0u8; RECORD_HEADER_SIZE as usize];
+        match codec.read_all_or_eof(&mut r, &mut header).add_read_ctx()? {
+            0 => break,
+            n if n < header.len() => {
+                report.bytes += n as u64;
+                report.push_fatal(offset, ParserError::IncompleteRecord, max_issues);
+                break;
+            }
+            _ => report.bytes += RECORD_HEADER_SIZE,
+        }
+        if 0 == offset && FILE_MAGIC == header[..4] {
+            let mut rest = [0u8; FILE_HEADER_SIZE - RECORD_HEADER_SIZE as usize];
+            let n = codec.read_all_or_eof(&mut r, &mut rest).add_read_ctx()?;
+            report.bytes += n as u64;
+            let version = codec.options.endianness.u16_from([header[4], header[5]]);
+            if n < rest.len() {
+                report.push_fatal(offset, ParserError::InvalidFileHeader, max_issues);
+                break;
+            }
+            if !BinaryVersion::is_supported_in_header(version) {
+                let error =
+                    ParserError::UnparsableValue(format!("binary format version {}", version));
+                report.push_fatal(offset, error, max_issues);
+                break;
+            }
+            integers = Integers::of_version(version);
+            continue;
+        }
+        // footer index ends the stream, its entries hold no records
+        if INDEX_MAGIC == header[..4] {
+            if !scan_index(&codec, &mut r, &mut report, &header[4..], offsets)? {
+                report.push_fatal(offset, ParserError::InvalidIndex, max_issues);
+            }
+            break;
+        }
+        if RECORD_MAGIC != header[..4] {
+            let error = ParserError::InvalidRecordHeader(codec.bytes_to_hex(&header[..4]));
+            report.push_fatal(offset, error, max_issues);
+            break;
+        }
+        let record_size = codec.u32_at(&header[4..]);
+        if integers.minimum_record_size() > record_size {
+            report.push_fatal(offset, ParserError::IncompleteRecord, max_issues);
+            break;
+        }
+        if let Err(error) = codec.check_record_size(record_size) {
+            report.push_fatal(offset, error, max_issues);
+            break;
+        }
+
+        body.resize(record_size as usize, 0);
+        let n = codec.read_all_or_eof(&mut r, &mut body).add_read_ctx()?;
+        report.bytes += n as u64;
+        if n < body.len() {
+            report.push_fatal(offset, ParserError::IncompleteRecord, max_issues);
+            break;
+        }
+
+        report.records += 1;
+        offsets.push(offset);
+        if let Err(error) = codec.check_record_body(&body, integers) {
+            report.push(offset, error, max_issues);
+        }
+    }
+
+    report.elapsed = started.elapsed();
+    Ok(report)
+}
+
+// walks footer index following its magic, false if it is truncated, is followed by other bytes,
+// or its offsets do not increase or differ from offsets of the records walked
+fn scan_index<R: Read>(
+    codec: &BinaryCodec,
+    r: &mut R,
+    report: &mut ScanReport,
+    count: &[u8],
+    records: OffsetsFold,
+) -> Result<bool, AppError> {
+    let start = report.bytes - RECORD_HEADER_SIZE;
+    let mut rest = [0u8; INDEX_HEADER_SIZE as usize - RECORD_HEADER_SIZE as usize];
+    let n = codec.read_all_or_eof(r, &mut rest).add_read_ctx()?;
+    report.bytes += n as u64;
+    if n < rest.len() {
+        return Ok(false);
+    }
+    let mut count_bytes = [0u8; 8];
+    count_bytes[..count.len()].copy_from_slice(count);
+    count_bytes[count.len()..].copy_from_slice(&rest);
+    let count = codec.u64_at(&count_bytes);
+
+    let mut entries = OffsetsFold::default();
+    let mut increasing = true;
+    let mut entry = [0u8; INDEX_ENTRY_SIZE as usize];
+    for _ in 0..count {
+        let n = codec.read_all_or_eof(r, &mut entry).add_read_ctx()?;
+        report.bytes += n as u64;
+        if n < entry.len() {
+            return Ok(false);
+        }
+        increasing &= entries.push(codec.u64_at(&entry[8..]));
     }
+    let mut trailer = [0u8; INDEX_TRAILER_SIZE as usize];
+    let n = codec.read_all_or_eof(r, &mut trailer).add_read_ctx()?;
+    report.bytes += n as u64;
+    let tail = std::io::copy(r, &mut std::io::sink()).add_read_ctx()?;
+    report.bytes += tail;
+    Ok(n == trailer.len()
+        && INDEX_MAGIC == trailer[8..]
+        && start == codec.u64_at(&trailer)
+        && 0 == tail
+        && increasing
+        && records.count == entries.count
+        && records.last == entries.last
+        && records.fold == entries.fold)
 }
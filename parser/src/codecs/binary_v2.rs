@@ -0,0 +1,750 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use super::errors::IoCtxBehavior;
+use super::errors::{ParserContext, ParserCtxBehavior, ParserError};
+use super::options::ParseLimits;
+use super::traits::*;
+use super::utils::{read_varint, write_varint, zigzag_decode, zigzag_encode};
+use crate::codecs::base::TxFieldKey;
+use crate::domain::batch::{BatchHeader, TxBatch};
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+// Stream starts with magic and flags byte, with more flags flag set it is followed by second
+// flags byte holding flags from 256 on. With batch header flag set flags are followed by
+// source (as tenant), creation timestamp and record count (varints, 0 for absent value or
+// value + 1). Records follow till the end of stream:
+// id delta (zigzag varint), kind and status (u8, kind in high nibble), from (varint),
+// to (varint), amount (zigzag varint), timestamp delta (zigzag varint), description, tenant.
+// Deltas are taken against previous record, first record is compared with zeros.
+// Description is length prefixed, with deduplication it is a varint reference instead:
+// 0 for new description followed by length prefixed bytes, n for n-th description met.
+// Tenant is a varint, 0 for absent tenant or length + 1 followed by bytes.
+// With IBAN accounts flag set, tenant is followed by IBANs of from and to accounts stored
+// as tenant, from/to of IBAN account is 0.
+// With UUID ids flag set, record ends with a byte: 0 for numeric id or 1 followed by 16
+// big-endian bytes of UUID, id delta of UUID record is 0.
+// With fees flag set, record ends with a byte: 0 for absent fee or scale + 1 followed by
+// minor units of fee (zigzag varint).
+// With references flag set, record ends with reference stored as tenant.
+// With exchange rates flag set, record ends with original amount and exchange rate, each
+// a byte: 0 for absent value or scale + 1 followed by minor units (mantissa, zigzag varint).
+// With amount scales flag set, record ends with scale of amount (u8), amounts have
+// Money::DEFAULT_SCALE otherwise.
+const FILE_MAGIC: [u8; 4] = *b"YPB2";
+const FLAG_DEDUP_DESCRIPTIONS: u16 = 1;
+const FLAG_IBAN_ACCOUNTS: u16 = 2;
+const FLAG_UUID_IDS: u16 = 4;
+const FLAG_BATCH_HEADER: u16 = 8;
+const FLAG_FEES: u16 = 16;
+const FLAG_REFERENCES: u16 = 32;
+const FLAG_EXCHANGE_RATES: u16 = 64;
+const FLAG_MORE_FLAGS: u16 = 128;
+const FLAG_AMOUNT_SCALES: u16 = 256;
+const KNOWN_FLAGS: u16 = FLAG_DEDUP_DESCRIPTIONS
+    | FLAG_IBAN_ACCOUNTS
+    | FLAG_UUID_IDS
+    | FLAG_BATCH_HEADER
+    | FLAG_FEES
+    | FLAG_REFERENCES
+    | FLAG_EXCHANGE_RATES
+    | FLAG_MORE_FLAGS
+    | FLAG_AMOUNT_SCALES;
+
+/// Layout options of records written in binary v2 format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BinaryV2Options {
+    /// Store every distinct description once and refer to it from repeating records.
+    ///
+    /// Reader and writer keep all distinct descriptions in memory.
+    pub dedup_descriptions: bool,
+    /// Store IBANs of accounts, records without them are rejected by the writer otherwise.
+    ///
+    /// Batch writes set it when records have IBAN accounts, streaming writers need it up front.
+    pub iban_accounts: bool,
+    /// Store UUID ids, records with them are rejected by the writer otherwise.
+    ///
+    /// Batch writes set it when records have UUID ids, streaming writers need it up front.
+    pub uuid_ids: bool,
+    /// Store fees, records with them are rejected by the writer otherwise.
+    ///
+    /// Batch writes set it when records have fees, streaming writers need it up front.
+    pub fees: bool,
+    /// Store references, records with them are rejected by the writer otherwise.
+    ///
+    /// Batch writes set it when records have references, streaming writers need it up front.
+    pub references: bool,
+    /// Store original amounts and exchange rates, records with them are rejected by the writer
+    /// otherwise.
+    ///
+    /// Batch writes set it when records have either, streaming writers need it up front.
+    pub exchange_rates: bool,
+    /// Store scales of amounts, records with amounts of other scale than
+    /// [`Money::DEFAULT_SCALE`] are rejected by the writer otherwise.
+    ///
+    /// Batch writes set it when records have such amounts, streaming writers need it up front.
+    pub amount_scales: bool,
+}
+
+/// Codec for delta-encoded binary format (YPB2).
+///
+/// Ids and timestamps are stored as differences with previous record, so monotonically
+/// increasing values take one or two bytes instead of eight.
+#[derive(Clone, Default)]
+pub(crate) struct BinaryV2Codec {
+    options: BinaryV2Options,
+    limits: ParseLimits,
+}
+
+// byte reader keeping stream position for error contexts
+struct V2Reader<R> {
+    r: R,
+    pos: usize,
+    // longest string accepted before its bytes are read
+    max_string: usize,
+}
+
+impl<R: BufRead> V2Reader<R> {
+    fn map_io_error(&self, e: std::io::Error) -> AppError {
+        let source = match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => ParserError::IncompleteRecord,
+            std::io::ErrorKind::InvalidData => ParserError::UnparsableValue(e.to_string()),
+            _ => return AppError::ReadError(e),
+        };
+        AppError::ParsingError {
+            context: ParserContext::with_position(self.pos),
+            source,
+        }
+    }
+
+    fn at_eof(&mut self) -> Result<bool, AppError> {
+        Ok(self.r.fill_buf().add_read_ctx()?.is_empty())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, AppError> {
+        let mut b = [0u8; 1];
+        self.r
+            .read_exact(&mut b)
+            .map_err(|e| self.map_io_error(e))?;
+        self.pos += 1;
+        Ok(b[0])
+    }
+
+    fn read_varint(&mut self) -> Result<u64, AppError> {
+        let (value, len) = read_varint(&mut self.r).map_err(|e| self.map_io_error(e))?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    fn read_string(&mut self, len: u64, field_key: TxFieldKey) -> Result<String, AppError> {
+        let start = self.pos;
+        ParseLimits::check_size("string", len, self.max_string)
+            .add_parser_ctx(ParserContext::with_position_and_field_key(start, field_key))?;
+        let mut bytes = Vec::new();
+        let read = (&mut self.r)
+            .take(len)
+            .read_to_end(&mut bytes)
+            .add_read_ctx()?;
+        self.pos += read;
+        if (read as u64) < len {
+            return Err(ParserError::IncompleteRecord)
+                .add_parser_ctx(ParserContext::with_position(self.pos));
+        }
+        String::from_utf8(bytes)
+            .map_err(|_| ParserError::UnparsableValue("non utf-8 string".into()))
+            .add_parser_ctx(ParserContext::with_position_and_field_key(start, field_key))
+    }
+
+    // varint 0 for absent string, length + 1 followed by bytes otherwise
+    fn read_optional_string(&mut self, field_key: TxFieldKey) -> Result<Option<String>, AppError> {
+        match self.read_varint()? {
+            0 => Ok(None),
+            len => self.read_string(len - 1, field_key).map(Some),
+        }
+    }
+
+    // varint 0 for absent value, value + 1 otherwise
+    fn read_optional_varint(&mut self) -> Result<Option<u64>, AppError> {
+        Ok(self.read_varint()?.checked_sub(1))
+    }
+
+    // 0 for numeric id, 1 followed by big-endian UUID otherwise
+    fn read_optional_uuid(&mut self) -> Result<Option<u128>, AppError> {
+        let tag_pos = self.pos;
+        match self.read_u8()? {
+            0 => Ok(None),
+            1 => {
+                let mut bytes = [0u8; 16];
+                self.r
+                    .read_exact(&mut bytes)
+                    .map_err(|e| self.map_io_error(e))?;
+                self.pos += bytes.len();
+                Ok(Some(u128::from_be_bytes(bytes)))
+            }
+            tag => Err(ParserError::UnparsableValue(tag.to_string())).add_parser_ctx(
+                ParserContext::with_position_and_field_key(tag_pos, TxFieldKey::Id),
+            ),
+        }
+    }
+
+    fn read_scale(&mut self, field_key: TxFieldKey) -> Result<u8, AppError> {
+        let scale_pos = self.pos;
+        match self.read_u8()? {
+            scale if scale <= Money::MAX_SCALE => Ok(scale),
+            scale => Err(ParserError::UnparsableValue(format!("scale {}", scale))).add_parser_ctx(
+                ParserContext::with_position_and_field_key(scale_pos, field_key),
+            ),
+        }
+    }
+
+    // 0 for absent value, scale + 1 followed by minor units (zigzag varint) otherwise
+    fn read_optional_scaled(
+        &mut self,
+        field_key: TxFieldKey,
+    ) -> Result<Option<(i64, u8)>, AppError> {
+        let tag_pos = self.pos;
+        match self.read_u8()? {
+            0 => Ok(None),
+            tag if tag - 1 <= Money::MAX_SCALE => {
+                Ok(Some((zigzag_decode(self.read_varint()?), tag - 1)))
+            }
+            tag => Err(ParserError::UnparsableValue(format!("scale {}", tag - 1))).add_parser_ctx(
+                ParserContext::with_position_and_field_key(tag_pos, field_key),
+            ),
+        }
+    }
+}
+
+impl BinaryV2Codec {
+    pub(crate) fn new(options: BinaryV2Options) -> Self {
+        Self {
+            options,
+            limits: ParseLimits::default(),
+        }
+    }
+
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn parse_kind_from_u8(&self, v: u8) -> Result<TxKind, ParserError> {
+        match v {
+            0 => Ok(TxKind::Deposit),
+            1 => Ok(TxKind::Transfer),
+            2 => Ok(TxKind::Withdrawal),
+            3 => Ok(TxKind::Refund),
+            4 => Ok(TxKind::Fee),
+            5 => Ok(TxKind::Reversal),
+            _ => Err(ParserError::UnparsableValue(v.to_string())),
+        }
+    }
+    fn kind_to_u8(&self, v: TxKind) -> u8 {
+        match v {
+            TxKind::Deposit => 0,
+            TxKind::Transfer => 1,
+            TxKind::Withdrawal => 2,
+            TxKind::Refund => 3,
+            TxKind::Fee => 4,
+            TxKind::Reversal => 5,
+        }
+    }
+    fn parse_status_from_u8(&self, v: u8) -> Result<TxStatus, ParserError> {
+        match v {
+            0 => Ok(TxStatus::Success),
+            1 => Ok(TxStatus::Failure),
+            2 => Ok(TxStatus::Pending),
+            3 => Ok(TxStatus::Cancelled),
+            4 => Ok(TxStatus::Reversed),
+            5 => Ok(TxStatus::Expired),
+            _ => Err(ParserError::UnparsableValue(v.to_string())),
+        }
+    }
+    fn status_to_u8(&self, v: TxStatus) -> u8 {
+        match v {
+            TxStatus::Success => 0,
+            TxStatus::Failure => 1,
+            TxStatus::Pending => 2,
+            TxStatus::Cancelled => 3,
+            TxStatus::Reversed => 4,
+            TxStatus::Expired => 5,
+        }
+    }
+
+    fn write_bytes(&self, out: &mut Vec<u8>, bytes: &[u8]) {
+        write_varint(out, bytes.len() as u64);
+        out.extend_from_slice(bytes);
+    }
+
+    fn write_optional_varint(&self, out: &mut Vec<u8>, value: Option<u64>) {
+        write_varint(out, value.map_or(0, |value| value.saturating_add(1)));
+    }
+
+    fn write_optional_string(&self, out: &mut Vec<u8>, value: Option<&str>) {
+        match value {
+            None => write_varint(out, 0),
+            Some(value) => {
+                write_varint(out, value.len() as u64 + 1);
+                out.extend_from_slice(value.as_bytes());
+            }
+        }
+    }
+}
+
+impl DataParser for BinaryV2Codec {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        collect_recovering(V2RecordReader::new(self, r), self.limits, &mut |rejected| {
+            Err(rejected.into_error())
+        })
+    }
+}
+
+impl StreamingParser for BinaryV2Codec {
+    fn parse_stream<'a, R: Read + 'a>(&self, r: R) -> RecordStream<'a> {
+        RecordStream::new(V2RecordReader::new(self, r))
+    }
+}
+
+// records depend on the previous ones, so every error is fatal
+struct V2RecordReader<R> {
+    codec: BinaryV2Codec,
+    r: V2Reader<BufReader<R>>,
+    // flags of the file header, `None` until it is read
+    flags: Option<u16>,
+    batch_header: BatchHeader,
+    records: u64,
+    descriptions: Vec<String>,
+    prev_id: u64,
+    prev_ts: u64,
+}
+
+impl<R: Read> V2RecordReader<R> {
+    fn new(codec: &BinaryV2Codec, r: R) -> Self {
+        Self {
+            codec: codec.clone(),
+            r: V2Reader {
+                r: BufReader::new(r),
+                pos: 0,
+                max_string: codec.limits.max_record_size,
+            },
+            flags: None,
+            batch_header: BatchHeader::default(),
+            records: 0,
+            descriptions: Vec::new(),
+            prev_id: 0,
+            prev_ts: 0,
+        }
+    }
+
+    fn read_header(&mut self) -> Result<u16, AppError> {
+        let r = &mut self.r;
+        let mut magic = [0u8; 4];
+        for b in magic.iter_mut() {
+            *b = r.read_u8()?;
+        }
+        if FILE_MAGIC != magic {
+            let hex: String = magic.iter().map(|b| format!("{:02X}", b)).collect();
+            return Err(ParserError::InvalidRecordHeader(hex))
+                .add_parser_ctx(ParserContext::with_position(0));
+        }
+        let mut flags = u16::from(r.read_u8()?);
+        if 0 != flags & FLAG_MORE_FLAGS {
+            flags |= u16::from(r.read_u8()?) << 8;
+        }
+        if 0 != flags & !KNOWN_FLAGS {
+            return Err(ParserError::InvalidFileHeader)
+                .add_parser_ctx(ParserContext::with_position(r.pos));
+        }
+        if 0 != flags & FLAG_BATCH_HEADER {
+            self.batch_header = BatchHeader {
+                source: r.read_optional_string(TxFieldKey::Tenant)?,
+                created: r.read_optional_varint()?.map(TxTimestamp::from_millis),
+                record_count: r.read_optional_varint()?,
+            };
+        }
+        Ok(flags)
+    }
+
+    fn read_next(&mut self) -> Result<Option<TxRecord>, AppError> {
+        let flags = match self.flags {
+            Some(flags) => flags,
+            None if self.r.at_eof()? => return Ok(None),
+            None => {
+                let flags = self.read_header()?;
+                self.flags = Some(flags);
+                flags
+            }
+        };
+        if self.r.at_eof()? {
+            return match self.batch_header.record_count {
+                Some(declared) if declared != self.records => {
+                    Err(ParserError::RecordCountMismatch {
+                        declared,
+                        actual: self.records,
+                    })
+                    .add_parser_ctx(ParserContext::with_position(self.r.pos))
+                }
+                _ => Ok(None),
+            };
+        }
+        self.records += 1;
+        self.read_record(flags).map(Some)
+    }
+
+    fn read_record(&mut self, flags: u16) -> Result<TxRecord, AppError> {
+        let dedup = 0 != flags & FLAG_DEDUP_DESCRIPTIONS;
+        let r = &mut self.r;
+        let id = self
+            .prev_id
+            .wrapping_add(zigzag_decode(r.read_varint()?) as u64);
+        let codes_pos = r.pos;
+        let codes = r.read_u8()?;
+        let kind = self.codec.parse_kind_from_u8(codes >> 4).add_parser_ctx(
+            ParserContext::with_position_and_field_key(codes_pos, TxFieldKey::TxKind),
+        )?;
+        let status = self
+            .codec
+            .parse_status_from_u8(codes & 0x0F)
+            .add_parser_ctx(ParserContext::with_position_and_field_key(
+                codes_pos,
+                TxFieldKey::Status,
+            ))?;
+        let from = r.read_varint()?;
+        let to = r.read_varint()?;
+        let amount = zigzag_decode(r.read_varint()?);
+        let ts = self
+            .prev_ts
+            .wrapping_add(zigzag_decode(r.read_varint()?) as u64);
+
+        let description_pos = r.pos;
+        let description = match (dedup, r.read_varint()?) {
+            (false, len) => r.read_string(len, TxFieldKey::Description)?,
+            (true, 0) => {
+                let len = r.read_varint()?;
+                let description = r.read_string(len, TxFieldKey::Description)?;
+                self.descriptions.push(description.clone());
+                description
+            }
+            (true, reference) => self
+                .descriptions
+                .get(reference as usize - 1)
+                .cloned()
+                .ok_or_else(|| ParserError::UnparsableValue(format!("description #{}", reference)))
+                .add_parser_ctx(ParserContext::with_position_and_field_key(
+                    description_pos,
+                    TxFieldKey::Description,
+                ))?,
+        };
+        let tenant = r.read_optional_string(TxFieldKey::Tenant)?;
+        let (from_iban, to_iban) = if 0 != flags & FLAG_IBAN_ACCOUNTS {
+            (
+                r.read_optional_string(TxFieldKey::FromUserId)?,
+                r.read_optional_string(TxFieldKey::ToUserId)?,
+            )
+        } else {
+            (None, None)
+        };
+        let uuid = if 0 != flags & FLAG_UUID_IDS {
+            r.read_optional_uuid()?
+        } else {
+            None
+        };
+        let fee = if 0 != flags & FLAG_FEES {
+            r.read_optional_scaled(TxFieldKey::Fee)?
+                .map(|(units, scale)| Money::new(units, scale))
+        } else {
+            None
+        };
+        let reference = if 0 != flags & FLAG_REFERENCES {
+            r.read_optional_string(TxFieldKey::Reference)?
+        } else {
+            None
+        };
+        let (original_amount, exchange_rate) = if 0 != flags & FLAG_EXCHANGE_RATES {
+            (
+                r.read_optional_scaled(TxFieldKey::OriginalAmount)?
+                    .map(|(units, scale)| Money::new(units, scale)),
+                r.read_optional_scaled(TxFieldKey::ExchangeRate)?
+                    .map(|(mantissa, scale)| ExchangeRate::new(mantissa, scale)),
+            )
+        } else {
+            (None, None)
+        };
+        let amount_scale = if 0 != flags & FLAG_AMOUNT_SCALES {
+            r.read_scale(TxFieldKey::Amount)?
+        } else {
+            Money::DEFAULT_SCALE
+        };
+
+        self.prev_id = id;
+        self.prev_ts = ts;
+        Ok(TxRecord {
+            id: TxIdType::from_parts(id, uuid),
+            kind,
+            from: AccountType::from_parts(from, from_iban),
+            to: AccountType::from_parts(to, to_iban),
+            amount: Money::new(amount, amount_scale),
+            fee,
+            original_amount,
+            exchange_rate,
+            ts: TxTimestamp::from_millis(ts),
+            status,
+            description: Some(description).filter(|description| !description.is_empty()),
+            tenant,
+            reference,
+            extras: Default::default(),
+        })
+    }
+}
+
+impl<R: Read> RecordReader for V2RecordReader<R> {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        self.read_next().map(|tx| tx.map(Ok)).transpose()
+    }
+}
+
+impl DataWriter for BinaryV2Codec {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        let mut writer = V2RecordWriter::open(self.for_records(data), w, None)?;
+        for tx in data {
+            writer.push(tx)?;
+        }
+        Ok(())
+    }
+}
+
+impl StreamingWriter for BinaryV2Codec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        Ok(RecordSink::new(V2RecordWriter::open(
+            self.clone(),
+            w,
+            None,
+        )?))
+    }
+}
+
+impl BinaryV2Codec {
+    // batch header is read from the file header and its record count is verified
+    pub(crate) fn parse_batch<R: Read>(&self, r: R) -> Result<TxBatch, AppError> {
+        let mut reader = V2RecordReader::new(self, r);
+        let records = collect_recovering(&mut reader, self.limits, &mut |rejected| {
+            Err(rejected.into_error())
+        })?;
+        Ok(TxBatch {
+            header: reader.batch_header,
+            records,
+        })
+    }
+
+    pub(crate) fn write_batch(
+        &self,
+        w: &mut dyn Write,
+        header: &BatchHeader,
+        data: &[TxRecord],
+    ) -> Result<(), AppError> {
+        let mut writer = V2RecordWriter::open(self.for_records(data), w, Some(header))?;
+        for tx in data {
+            writer.push(tx)?;
+        }
+        Ok(())
+    }
+
+    // batch writes turn on flags of optional fields their records have
+    fn for_records(&self, data: &[TxRecord]) -> Self {
+        let mut codec = self.clone();
+        codec.options.iban_accounts |= data
+            .iter()
+            .any(|tx| tx.from.as_iban().is_some() || tx.to.as_iban().is_some());
+        codec.options.uuid_ids |= data.iter().any(|tx| tx.id.as_uuid().is_some());
+        codec.options.fees |= data.iter().any(|tx| tx.fee.is_some());
+        codec.options.references |= data.iter().any(|tx| tx.reference.is_some());
+        codec.options.exchange_rates |= data
+            .iter()
+            .any(|tx| tx.original_amount.is_some() || tx.exchange_rate.is_some());
+        codec.options.amount_scales |= data
+            .iter()
+            .any(|tx| Money::DEFAULT_SCALE != tx.amount.scale);
+        codec
+    }
+}
+
+// keeps previous record and met descriptions the next records are encoded against
+struct V2RecordWriter<W> {
+    codec: BinaryV2Codec,
+    w: W,
+    descriptions: HashMap<String, u64>,
+    prev_id: u64,
+    prev_ts: u64,
+    out: Vec<u8>,
+}
+
+impl<W: Write> V2RecordWriter<W> {
+    fn open(
+        codec: BinaryV2Codec,
+        mut w: W,
+        batch_header: Option<&BatchHeader>,
+    ) -> Result<Self, AppError> {
+        let mut flags = 0;
+        if codec.options.dedup_descriptions {
+            flags |= FLAG_DEDUP_DESCRIPTIONS;
+        }
+        if codec.options.iban_accounts {
+            flags |= FLAG_IBAN_ACCOUNTS;
+        }
+        if codec.options.uuid_ids {
+            flags |= FLAG_UUID_IDS;
+        }
+        if codec.options.fees {
+            flags |= FLAG_FEES;
+        }
+        if codec.options.references {
+            flags |= FLAG_REFERENCES;
+        }
+        if codec.options.exchange_rates {
+            flags |= FLAG_EXCHANGE_RATES;
+        }
+        if codec.options.amount_scales {
+            flags |= FLAG_AMOUNT_SCALES;
+        }
+        if batch_header.is_some() {
+            flags |= FLAG_BATCH_HEADER;
+        }
+        if flags > 0xFF {
+            flags |= FLAG_MORE_FLAGS;
+        }
+        let mut header = vec![flags as u8];
+        if 0 != flags & FLAG_MORE_FLAGS {
+            header.push((flags >> 8) as u8);
+        }
+        if let Some(batch_header) = batch_header {
+            codec.write_optional_string(&mut header, batch_header.source.as_deref());
+            codec.write_optional_varint(&mut header, batch_header.created.map(|ts| ts.millis()));
+            codec.write_optional_varint(&mut header, batch_header.record_count);
+        }
+        w.write_all(&FILE_MAGIC).add_write_ctx()?;
+        w.write_all(&header).add_write_ctx()?;
+        Ok(Self {
+            codec,
+            w,
+            descriptions: HashMap::new(),
+            prev_id: 0,
+            prev_ts: 0,
+            out: Vec::new(),
+        })
+    }
+}
+
+impl<W: Write> RecordEncoder for V2RecordWriter<W> {
+    fn push(&mut self, tx: &TxRecord) -> Result<(), AppError> {
+        let codec = &self.codec;
+        let ibans = (tx.from.as_iban(), tx.to.as_iban());
+        let unsupported = if !codec.options.iban_accounts && ibans != (None, None) {
+            Some("IBAN accounts")
+        } else if !codec.options.uuid_ids && tx.id.as_uuid().is_some() {
+            Some("UUID ids")
+        } else if !codec.options.fees && tx.fee.is_some() {
+            Some("fees")
+        } else if !codec.options.references && tx.reference.is_some() {
+            Some("references")
+        } else if !codec.options.exchange_rates
+            && (tx.original_amount.is_some() || tx.exchange_rate.is_some())
+        {
+            Some("exchange rates")
+        } else if !codec.options.amount_scales && Money::DEFAULT_SCALE != tx.amount.scale {
+            Some("amount scales")
+        } else {
+            None
+        };
+        if let Some(unsupported) = unsupported {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "record id {} needs {}, binary v2 stream is opened without them",
+                    tx.id, unsupported
+                ),
+            ))
+            .add_write_ctx();
+        }
+        let id = tx.id.as_numeric().unwrap_or(self.prev_id);
+        let out = &mut self.out;
+        out.clear();
+        write_varint(out, zigzag_encode(id.wrapping_sub(self.prev_id) as i64));
+        out.push((codec.kind_to_u8(tx.kind) << 4) | codec.status_to_u8(tx.status));
+        write_varint(out, tx.from.as_numeric().unwrap_or(0));
+        write_varint(out, tx.to.as_numeric().unwrap_or(0));
+        write_varint(out, zigzag_encode(tx.amount.minor_units));
+        write_varint(
+            out,
+            zigzag_encode(tx.ts.millis().wrapping_sub(self.prev_ts) as i64),
+        );
+
+        let description = tx.description.as_deref().unwrap_or_default();
+        if !codec.options.dedup_descriptions {
+            codec.write_bytes(out, description.as_bytes());
+        } else if let Some(&reference) = self.descriptions.get(description) {
+            write_varint(out, reference);
+        } else {
+            write_varint(out, 0);
+            codec.write_bytes(out, description.as_bytes());
+            let reference = self.descriptions.len() as u64 + 1;
+            self.descriptions.insert(description.to_string(), reference);
+        }
+        codec.write_optional_string(out, tx.tenant.as_deref());
+        if codec.options.iban_accounts {
+            codec.write_optional_string(out, ibans.0);
+            codec.write_optional_string(out, ibans.1);
+        }
+        if codec.options.uuid_ids {
+            match tx.id.as_uuid() {
+                None => out.push(0),
+                Some(uuid) => {
+                    out.push(1);
+                    out.extend_from_slice(&uuid.to_be_bytes());
+                }
+            }
+        }
+        if codec.options.fees {
+            match tx.fee {
+                None => out.push(0),
+                Some(fee) => {
+                    out.push(fee.scale + 1);
+                    write_varint(out, zigzag_encode(fee.minor_units));
+                }
+            }
+        }
+        if codec.options.references {
+            codec.write_optional_string(out, tx.reference.as_deref());
+        }
+        if codec.options.exchange_rates {
+            let original_amount = tx
+                .original_amount
+                .map(|amount| (amount.minor_units, amount.scale));
+            let exchange_rate = tx.exchange_rate.map(|rate| (rate.mantissa, rate.scale));
+            for scaled in [original_amount, exchange_rate] {
+                match scaled {
+                    None => out.push(0),
+                    Some((units, scale)) => {
+                        out.push(scale + 1);
+                        write_varint(out, zigzag_encode(units));
+                    }
+                }
+            }
+        }
+        if codec.options.amount_scales {
+            out.push(tx.amount.scale);
+        }
+
+        self.prev_id = id;
+        self.prev_ts = tx.ts.millis();
+        self.w.write_all(out).add_write_ctx()
+    }
+
+    fn flush(&mut self) -> Result<(), AppError> {
+        self.w.flush().add_write_ctx()
+    }
+
+    fn finish(&mut self) -> Result<(), AppError> {
+        self.w.flush().add_write_ctx()
+    }
+}
@@ -0,0 +1,394 @@
+use std::io::{BufRead, BufReader, Read, Write};
+
+use super::base::TxFieldKey;
+use super::errors::{IoCtxBehavior, ParserContext, ParserCtxBehavior, ParserError};
+use super::options::ParseLimits;
+use super::traits::*;
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+/// Codec of compact dumps in bincode 1.x layout of `Vec<TxRecord>`.
+///
+/// Integers are fixed-size little-endian, enums are `u32` variant indexes in declaration order,
+/// strings are `u64` length prefixed and `Option` has a one byte tag. Records round-trip exactly,
+/// the format has no framing or recovery and is meant for intermediate results only.
+#[derive(Clone, Default)]
+pub(crate) struct BincodeCodec {
+    limits: ParseLimits,
+}
+
+struct BincodeReader<R> {
+    r: R,
+    pos: usize,
+    // longest string accepted before its bytes are read
+    max_string: usize,
+}
+
+impl<R: Read> BincodeReader<R> {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), AppError> {
+        self.r.read_exact(buf).map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => AppError::ParsingError {
+                context: ParserContext::with_position(self.pos),
+                source: ParserError::IncompleteRecord,
+            },
+            _ => AppError::ReadError(e),
+        })?;
+        self.pos += buf.len();
+        Ok(())
+    }
+
+    fn read_u64(&mut self) -> Result<u64, AppError> {
+        let mut bytes = [0u8; 8];
+        self.read_bytes(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    // index of enum variant, rejected unless it is below number of `variants`
+    fn read_variant(&mut self, field_key: TxFieldKey, variants: u32) -> Result<u32, AppError> {
+        let mut bytes = [0u8; 4];
+        self.read_bytes(&mut bytes)?;
+        let variant = u32::from_le_bytes(bytes);
+        if variant >= variants {
+            return Err(ParserError::UnparsableValue(variant.to_string())).add_parser_ctx(
+                ParserContext::with_position_and_field_key(self.pos, field_key),
+            );
+        }
+        Ok(variant)
+    }
+
+    fn read_string(&mut self, field_key: TxFieldKey) -> Result<String, AppError> {
+        let len = self.read_u64()?;
+        ParseLimits::check_size("string", len, self.max_string).add_parser_ctx(
+            ParserContext::with_position_and_field_key(self.pos, field_key),
+        )?;
+        let mut bytes = Vec::new();
+        let read = (&mut self.r)
+            .take(len)
+            .read_to_end(&mut bytes)
+            .add_read_ctx()?;
+        self.pos += read;
+        if (read as u64) < len {
+            return Err(ParserError::IncompleteRecord)
+                .add_parser_ctx(ParserContext::with_position(self.pos));
+        }
+        String::from_utf8(bytes)
+            .map_err(|_| ParserError::UnparsableValue("non utf-8 string".into()))
+            .add_parser_ctx(ParserContext::with_position_and_field_key(
+                self.pos, field_key,
+            ))
+    }
+
+    fn read_id(&mut self) -> Result<TxIdType, AppError> {
+        match self.read_variant(TxFieldKey::Id, 2)? {
+            0 => Ok(TxIdType::Numeric(self.read_u64()?)),
+            _ => {
+                let mut bytes = [0u8; 16];
+                self.read_bytes(&mut bytes)?;
+                Ok(TxIdType::Uuid(u128::from_le_bytes(bytes)))
+            }
+        }
+    }
+
+    // tag of `Option`, true for present value
+    fn read_tag(&mut self, field_key: TxFieldKey) -> Result<bool, AppError> {
+        let mut tag = [0u8; 1];
+        self.read_bytes(&mut tag)?;
+        match tag[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(ParserError::UnparsableValue(other.to_string())).add_parser_ctx(
+                ParserContext::with_position_and_field_key(self.pos, field_key),
+            ),
+        }
+    }
+
+    // minor units (mantissa) followed by scale, rejected above `Money::MAX_SCALE`
+    fn read_scaled(&mut self, field_key: TxFieldKey) -> Result<(i64, u8), AppError> {
+        let units = self.read_u64()? as i64;
+        let mut scale = [0u8; 1];
+        self.read_bytes(&mut scale)?;
+        if scale[0] > Money::MAX_SCALE {
+            return Err(ParserError::UnparsableValue(format!("scale {}", scale[0])))
+                .add_parser_ctx(ParserContext::with_position_and_field_key(
+                    self.pos, field_key,
+                ));
+        }
+        Ok((units, scale[0]))
+    }
+
+    fn read_account(&mut self, field_key: TxFieldKey) -> Result<AccountType, AppError> {
+        match self.read_variant(field_key, 2)? {
+            0 => Ok(AccountType::Numeric(self.read_u64()?)),
+            _ => Ok(AccountType::Iban(self.read_string(field_key)?)),
+        }
+    }
+
+    fn read_record(&mut self) -> Result<TxRecord, AppError> {
+        let id = self.read_id()?;
+        let kind = match self.read_variant(TxFieldKey::TxKind, 6)? {
+            0 => TxKind::Deposit,
+            1 => TxKind::Transfer,
+            2 => TxKind::Withdrawal,
+            3 => TxKind::Refund,
+            4 => TxKind::Fee,
+            _ => TxKind::Reversal,
+        };
+        let from = self.read_account(TxFieldKey::FromUserId)?;
+        let to = self.read_account(TxFieldKey::ToUserId)?;
+        let (amount, amount_scale) = self.read_scaled(TxFieldKey::Amount)?;
+        let fee = match self.read_tag(TxFieldKey::Fee)? {
+            false => None,
+            true => {
+                let (units, scale) = self.read_scaled(TxFieldKey::Fee)?;
+                Some(Money::new(units, scale))
+            }
+        };
+        let original_amount = match self.read_tag(TxFieldKey::OriginalAmount)? {
+            false => None,
+            true => {
+                let (units, scale) = self.read_scaled(TxFieldKey::OriginalAmount)?;
+                Some(Money::new(units, scale))
+            }
+        };
+        let exchange_rate = match self.read_tag(TxFieldKey::ExchangeRate)? {
+            false => None,
+            true => {
+                let (mantissa, scale) = self.read_scaled(TxFieldKey::ExchangeRate)?;
+                Some(ExchangeRate::new(mantissa, scale))
+            }
+        };
+        let ts = TxTimestamp::from_millis(self.read_u64()?);
+        let status = match self.read_variant(TxFieldKey::Status, 6)? {
+            0 => TxStatus::Success,
+            1 => TxStatus::Failure,
+            2 => TxStatus::Pending,
+            3 => TxStatus::Cancelled,
+            4 => TxStatus::Reversed,
+            _ => TxStatus::Expired,
+        };
+        let description = self.read_string(TxFieldKey::Description)?;
+        let tenant = match self.read_tag(TxFieldKey::Tenant)? {
+            false => None,
+            true => Some(self.read_string(TxFieldKey::Tenant)?),
+        };
+        let reference = match self.read_tag(TxFieldKey::Reference)? {
+            false => None,
+            true => Some(self.read_string(TxFieldKey::Reference)?),
+        };
+        Ok(TxRecord {
+            id,
+            kind,
+            from,
+            to,
+            amount: Money::new(amount, amount_scale),
+            fee,
+            original_amount,
+            exchange_rate,
+            ts,
+            status,
+            description: Some(description).filter(|description| !description.is_empty()),
+            tenant,
+            reference,
+            extras: Default::default(),
+        })
+    }
+}
+
+impl BincodeCodec {
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn write_string(&self, w: &mut dyn Write, value: &str) -> Result<(), AppError> {
+        w.write_all(&(value.len() as u64).to_le_bytes())
+            .add_write_ctx()?;
+        w.write_all(value.as_bytes()).add_write_ctx()
+    }
+
+    fn write_optional_string(
+        &self,
+        w: &mut dyn Write,
+        value: Option<&str>,
+    ) -> Result<(), AppError> {
+        match value {
+            None => w.write_all(&[0]).add_write_ctx(),
+            Some(value) => {
+                w.write_all(&[1]).add_write_ctx()?;
+                self.write_string(w, value)
+            }
+        }
+    }
+
+    fn write_scaled(&self, w: &mut dyn Write, units: i64, scale: u8) -> Result<(), AppError> {
+        w.write_all(&units.to_le_bytes()).add_write_ctx()?;
+        w.write_all(&[scale]).add_write_ctx()
+    }
+
+    fn write_optional_scaled(
+        &self,
+        w: &mut dyn Write,
+        value: Option<(i64, u8)>,
+    ) -> Result<(), AppError> {
+        match value {
+            None => w.write_all(&[0]).add_write_ctx(),
+            Some((units, scale)) => {
+                w.write_all(&[1]).add_write_ctx()?;
+                self.write_scaled(w, units, scale)
+            }
+        }
+    }
+
+    fn write_id(&self, w: &mut dyn Write, id: TxIdType) -> Result<(), AppError> {
+        match id {
+            TxIdType::Numeric(id) => {
+                w.write_all(&0u32.to_le_bytes()).add_write_ctx()?;
+                w.write_all(&id.to_le_bytes()).add_write_ctx()
+            }
+            TxIdType::Uuid(uuid) => {
+                w.write_all(&1u32.to_le_bytes()).add_write_ctx()?;
+                w.write_all(&uuid.to_le_bytes()).add_write_ctx()
+            }
+        }
+    }
+
+    fn write_account(&self, w: &mut dyn Write, account: &AccountType) -> Result<(), AppError> {
+        match account {
+            AccountType::Numeric(id) => {
+                w.write_all(&0u32.to_le_bytes()).add_write_ctx()?;
+                w.write_all(&id.to_le_bytes()).add_write_ctx()
+            }
+            AccountType::Iban(iban) => {
+                w.write_all(&1u32.to_le_bytes()).add_write_ctx()?;
+                self.write_string(w, iban)
+            }
+        }
+    }
+
+    fn write_record(&self, w: &mut dyn Write, tx: &TxRecord) -> Result<(), AppError> {
+        let kind: u32 = match tx.kind {
+            TxKind::Deposit => 0,
+            TxKind::Transfer => 1,
+            TxKind::Withdrawal => 2,
+            TxKind::Refund => 3,
+            TxKind::Fee => 4,
+            TxKind::Reversal => 5,
+        };
+        let status: u32 = match tx.status {
+            TxStatus::Success => 0,
+            TxStatus::Failure => 1,
+            TxStatus::Pending => 2,
+            TxStatus::Cancelled => 3,
+            TxStatus::Reversed => 4,
+            TxStatus::Expired => 5,
+        };
+        self.write_id(w, tx.id)?;
+        w.write_all(&kind.to_le_bytes()).add_write_ctx()?;
+        self.write_account(w, &tx.from)?;
+        self.write_account(w, &tx.to)?;
+        self.write_scaled(w, tx.amount.minor_units, tx.amount.scale)?;
+        self.write_optional_scaled(w, tx.fee.map(|fee| (fee.minor_units, fee.scale)))?;
+        self.write_optional_scaled(
+            w,
+            tx.original_amount
+                .map(|amount| (amount.minor_units, amount.scale)),
+        )?;
+        self.write_optional_scaled(w, tx.exchange_rate.map(|rate| (rate.mantissa, rate.scale)))?;
+        w.write_all(&tx.ts.millis().to_le_bytes()).add_write_ctx()?;
+        w.write_all(&status.to_le_bytes()).add_write_ctx()?;
+        self.write_string(w, tx.description.as_deref().unwrap_or_default())?;
+        self.write_optional_string(w, tx.tenant.as_deref())?;
+        self.write_optional_string(w, tx.reference.as_deref())
+    }
+}
+
+impl DataParser for BincodeCodec {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        collect_recovering(
+            BincodeRecordReader::new(self, r),
+            self.limits,
+            &mut |rejected| Err(rejected.into_error()),
+        )
+    }
+}
+
+impl StreamingParser for BincodeCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, r: R) -> RecordStream<'a> {
+        RecordStream::new(BincodeRecordReader::new(self, r))
+    }
+}
+
+// no framing, every error is fatal
+struct BincodeRecordReader<R> {
+    r: BincodeReader<BufReader<R>>,
+    // `None` until the records count is read
+    remaining: Option<u64>,
+}
+
+impl<R: Read> BincodeRecordReader<R> {
+    fn new(codec: &BincodeCodec, r: R) -> Self {
+        Self {
+            r: BincodeReader {
+                r: BufReader::new(r),
+                pos: 0,
+                max_string: codec.limits.max_record_size,
+            },
+            remaining: None,
+        }
+    }
+
+    // empty input is an empty dump rather than truncated length prefix
+    fn read_count(&mut self) -> Result<Option<u64>, AppError> {
+        if self.remaining.is_none() {
+            self.remaining = Some(if self.r.r.fill_buf().add_read_ctx()?.is_empty() {
+                0
+            } else {
+                self.r.read_u64()?
+            });
+        }
+        Ok(self.remaining)
+    }
+
+    fn read_next(&mut self) -> Result<Option<TxRecord>, AppError> {
+        match self.read_count()? {
+            Some(0) | None => {
+                if !self.r.r.fill_buf().add_read_ctx()?.is_empty() {
+                    return Err(ParserError::UnparsableValue(
+                        "trailing bytes after records".into(),
+                    ))
+                    .add_parser_ctx(ParserContext::with_position(self.r.pos));
+                }
+                Ok(None)
+            }
+            Some(remaining) => {
+                let tx = self.r.read_record()?;
+                self.remaining = Some(remaining - 1);
+                Ok(Some(tx))
+            }
+        }
+    }
+}
+
+impl<R: Read> RecordReader for BincodeRecordReader<R> {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        self.read_next().map(|tx| tx.map(Ok)).transpose()
+    }
+}
+
+impl DataWriter for BincodeCodec {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        w.write_all(&(data.len() as u64).to_le_bytes())
+            .add_write_ctx()?;
+        for tx in data {
+            self.write_record(w, tx)?;
+        }
+        Ok(())
+    }
+}
+
+impl StreamingWriter for BincodeCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        // records count precedes the records
+        Ok(RecordSink::new(Blocks::whole(self.clone(), w)))
+    }
+}
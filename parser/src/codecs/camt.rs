@@ -0,0 +1,201 @@
+use std::io::{Read, Write};
+
+use super::errors::{IoCtxBehavior, ParserContext, ParserCtxBehavior, ParserError};
+use super::options::ParseLimits;
+use super::traits::{
+    DataParser, DataWriter, RecordSink, RecordStream, StreamingParser, StreamingWriter,
+};
+use super::utils::{parse_decimal_minor_units, parse_iso8601, read_limited};
+use super::xml::XmlElement;
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+const CREDIT: &str = "CRDT";
+const DEBIT: &str = "DBIT";
+const NOT_PROVIDED: &str = "NOTPROVIDED";
+
+/// Reader of ISO 20022 camt.053 bank-to-customer statements.
+///
+/// Every `Ntry` becomes a record: credits are deposits to the statement account,
+/// debits are withdrawals from it.
+#[derive(Default)]
+pub(crate) struct CamtCodec {
+    limits: ParseLimits,
+}
+impl CamtCodec {
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // `IBAN` or numeric `Othr/Id` of statement account
+    fn parse_statement_account(&self, stmt: &XmlElement) -> AccountType {
+        stmt.path_text(&["Acct", "Id", "IBAN"])
+            .and_then(|iban| AccountType::parse_iban(iban).ok())
+            .or_else(|| {
+                stmt.path_text(&["Acct", "Id", "Othr", "Id"])
+                    .and_then(|id| id.parse().ok())
+                    .map(AccountType::Numeric)
+            })
+            .unwrap_or_default()
+    }
+
+    fn parse_entry(
+        &self,
+        entry: &XmlElement,
+        account: &AccountType,
+        seq_no: u64,
+    ) -> Result<TxRecord, ParserError> {
+        let amount_element = entry
+            .child("Amt")
+            .ok_or(ParserError::UnparsableValue("Ntry/Amt is missing".into()))?;
+        let scale = currency_scale(amount_element.attribute("Ccy").unwrap_or_default());
+        let amount = parse_decimal_minor_units(amount_element.text.trim(), u32::from(scale))?;
+
+        let (kind, from, to) = match entry.path_text(&["CdtDbtInd"]) {
+            Some(CREDIT) => (TxKind::Deposit, AccountType::default(), account.clone()),
+            Some(DEBIT) => (TxKind::Withdrawal, account.clone(), AccountType::default()),
+            other => {
+                return Err(ParserError::UnparsableValue(
+                    other.unwrap_or("CdtDbtInd is missing").into(),
+                ));
+            }
+        };
+
+        // camt.053.001.02 has status code as text, newer versions nest it into `Cd`
+        let status_code = entry
+            .path_text(&["Sts", "Cd"])
+            .or_else(|| entry.path_text(&["Sts"]))
+            .unwrap_or_default();
+        let status = match status_code {
+            "BOOK" => TxStatus::Success,
+            "PDNG" | "INFO" | "FUTR" => TxStatus::Pending,
+            _ => return Err(ParserError::UnparsableValue(status_code.into())),
+        };
+
+        let booking = entry
+            .path_text(&["BookgDt", "DtTm"])
+            .or_else(|| entry.path_text(&["BookgDt", "Dt"]))
+            .or_else(|| entry.path_text(&["ValDt", "DtTm"]))
+            .or_else(|| entry.path_text(&["ValDt", "Dt"]))
+            .ok_or(ParserError::UnparsableValue("BookgDt is missing".into()))?;
+        let ts = TxTimestamp::from_millis(parse_iso8601(booking)?);
+
+        // numeric or UUID entry reference is used as id, otherwise position in statement
+        let id = entry
+            .path_text(&["NtryRef"])
+            .or_else(|| entry.path_text(&["AcctSvcrRef"]))
+            .and_then(|r| r.parse().ok())
+            .unwrap_or(TxIdType::Numeric(seq_no));
+
+        // end-to-end id set by originator, otherwise the one of account servicer
+        let mut end_to_end = Vec::new();
+        entry.descendants("EndToEndId", &mut end_to_end);
+        let reference = end_to_end
+            .iter()
+            .map(|e| e.text.trim())
+            .find(|r| !r.is_empty() && *r != NOT_PROVIDED)
+            .or_else(|| entry.path_text(&["AcctSvcrRef"]))
+            .map(str::to_string);
+
+        let mut unstructured = Vec::new();
+        entry.descendants("Ustrd", &mut unstructured);
+        let description = entry
+            .path_text(&["AddtlNtryInf"])
+            .map(str::to_string)
+            .or_else(|| {
+                let text = unstructured
+                    .iter()
+                    .map(|e| e.text.trim())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (!text.is_empty()).then_some(text)
+            });
+
+        Ok(TxRecord {
+            id,
+            kind,
+            from,
+            to,
+            amount: Money::new(amount, scale),
+            fee: None,
+            original_amount: None,
+            exchange_rate: None,
+            ts,
+            status,
+            description,
+            tenant: None,
+            reference,
+            extras: Default::default(),
+        })
+    }
+}
+
+// minor unit exponent per ISO 4217
+fn currency_scale(currency: &str) -> u8 {
+    match currency {
+        "JPY" | "KRW" | "ISK" | "CLP" | "VND" => 0,
+        "BHD" | "KWD" | "OMR" | "JOD" | "TND" | "IQD" | "LYD" => 3,
+        _ => 2,
+    }
+}
+
+impl DataParser for CamtCodec {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        let input = String::from_utf8(read_limited(r, self.limits.max_block_size)?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            .add_read_ctx()?;
+        if input.trim().is_empty() {
+            return Ok(vec![]);
+        }
+        let document = XmlElement::parse_document(&input).map_err(|(position, source)| {
+            AppError::ParsingError {
+                context: ParserContext::with_position(position),
+                source,
+            }
+        })?;
+
+        let mut statements = Vec::new();
+        document.descendants("Stmt", &mut statements);
+        let mut result = Vec::new();
+        for stmt in statements {
+            let account = self.parse_statement_account(stmt);
+            for entry in stmt.children("Ntry") {
+                let seq_no = result.len() as u64 + 1;
+                let tx = self
+                    .parse_entry(entry, &account, seq_no)
+                    .add_parser_ctx(ParserContext::with_position(seq_no as usize))?;
+                self.limits.check_record(&tx, result.len())?;
+                result.push(tx);
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl StreamingParser for CamtCodec {
+    // statement is decoded as a whole
+    fn parse_stream<'a, R: Read + 'a>(&self, mut r: R) -> RecordStream<'a> {
+        RecordStream::from_parsed(self.parse(&mut r))
+    }
+}
+
+impl DataWriter for CamtCodec {
+    fn write(&self, _: &mut dyn Write, _: &[TxRecord]) -> Result<(), AppError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "camt.053 format is read only",
+        ))
+        .add_write_ctx()
+    }
+}
+
+impl StreamingWriter for CamtCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, _: W) -> Result<RecordSink<'a>, AppError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "camt.053 format is read only",
+        ))
+        .add_write_ctx()
+    }
+}
@@ -0,0 +1,680 @@
+use std::io::{BufRead, BufReader, Read, Write};
+
+use super::base::TxFieldKey;
+use super::errors::{IoCtxBehavior, ParserContext, ParserCtxBehavior, ParserError};
+use super::options::ParseLimits;
+use super::traits::*;
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+/// Cap'n Proto schema the codec reads and writes.
+pub const SCHEMA: &str = include_str!("../../schema/tx.capnp");
+
+// large inputs are split into messages so neither side holds a huge segment
+const RECORDS_PER_MESSAGE: usize = 1 << 16;
+const MAX_SEGMENTS: usize = 512;
+const WORD_SIZE: usize = 8;
+
+// TxRecord layout: five 64-bit fields, then kind and status as 16-bit enums, fee presence
+// bit and scales of original amount, exchange rate and amount in the sixth word, then fee,
+// original amount and exchange rate, then fee scale
+const RECORD_DATA_WORDS: usize = 10;
+const RECORD_POINTERS: usize = 6;
+const RECORD_WORDS: usize = RECORD_DATA_WORDS + RECORD_POINTERS;
+const ID_WORD: usize = 0;
+const FROM_WORD: usize = 1;
+const TO_WORD: usize = 2;
+const AMOUNT_WORD: usize = 3;
+const TIMESTAMP_WORD: usize = 4;
+const KIND_U16: usize = 20;
+const STATUS_U16: usize = 21;
+const FEE_WORD: usize = 6;
+const HAS_FEE_BIT: usize = 5 * 64 + 32;
+const ORIGINAL_AMOUNT_WORD: usize = 7;
+const EXCHANGE_RATE_WORD: usize = 8;
+// scale + 1, 0 for absent value
+const ORIGINAL_AMOUNT_SCALE_U8: usize = 5 * 8 + 5;
+const EXCHANGE_RATE_SCALE_U8: usize = 5 * 8 + 6;
+// scale + 1, 0 for Money::DEFAULT_SCALE
+const AMOUNT_SCALE_U8: usize = 5 * 8 + 7;
+const FEE_SCALE_U8: usize = 9 * 8;
+const DESCRIPTION_POINTER: usize = 0;
+const TENANT_POINTER: usize = 1;
+const FROM_IBAN_POINTER: usize = 2;
+const TO_IBAN_POINTER: usize = 3;
+const UUID_POINTER: usize = 4;
+const REFERENCE_POINTER: usize = 5;
+const UUID_SIZE: usize = 16;
+
+const POINTER_STRUCT: u64 = 0;
+const POINTER_LIST: u64 = 1;
+const ELEMENT_SIZE_BYTE: u8 = 2;
+const ELEMENT_SIZE_COMPOSITE: u8 = 7;
+
+// pointer offsets are 30-bit and list sizes 29-bit signed/unsigned integers
+const MAX_SEGMENT_WORDS: usize = (1 << 29) - 1;
+
+/// Codec of Cap'n Proto messages carrying `TxBatch` roots, see [`SCHEMA`].
+///
+/// Fields are decoded in place from the read segment. Only single-segment messages are
+/// supported, records of multi-segment messages are rejected on the first far pointer.
+#[derive(Clone, Default)]
+pub(crate) struct CapnpCodec {
+    limits: ParseLimits,
+}
+
+enum Pointer {
+    Null,
+    Struct {
+        start: usize,
+        data_words: usize,
+        pointers: usize,
+    },
+    List {
+        start: usize,
+        element_size: u8,
+        count: usize,
+    },
+}
+
+struct StructView<'a> {
+    segment: &'a Segment<'a>,
+    start: usize,
+    data_words: usize,
+    pointers: usize,
+}
+
+struct Segment<'a> {
+    bytes: &'a [u8],
+}
+
+impl Segment<'_> {
+    fn len_words(&self) -> usize {
+        self.bytes.len() / WORD_SIZE
+    }
+
+    fn word(&self, index: usize) -> Result<u64, ParserError> {
+        let bytes = self
+            .bytes
+            .get(index * WORD_SIZE..(index + 1) * WORD_SIZE)
+            .ok_or(ParserError::IncompleteRecord)?;
+        Ok(u64::from_le_bytes(
+            bytes.try_into().expect("word sized slice"),
+        ))
+    }
+
+    fn check_range(&self, start: usize, words: usize) -> Result<(), ParserError> {
+        match start.checked_add(words) {
+            Some(end) if end <= self.len_words() => Ok(()),
+            _ => Err(ParserError::IncompleteRecord),
+        }
+    }
+
+    fn pointer(&self, at: usize) -> Result<Pointer, ParserError> {
+        let value = self.word(at)?;
+        if 0 == value {
+            return Ok(Pointer::Null);
+        }
+        // offset is counted in words from the end of the pointer
+        let offset = i64::from((value as u32 as i32) >> 2);
+        let start = usize::try_from(at as i64 + 1 + offset)
+            .map_err(|_| ParserError::UnparsableValue("capnp pointer out of segment".into()))?;
+        match value & 3 {
+            POINTER_STRUCT => {
+                let data_words = ((value >> 32) & 0xFFFF) as usize;
+                let pointers = (value >> 48) as usize;
+                self.check_range(start, data_words + pointers)?;
+                Ok(Pointer::Struct {
+                    start,
+                    data_words,
+                    pointers,
+                })
+            }
+            POINTER_LIST => Ok(Pointer::List {
+                start,
+                element_size: ((value >> 32) & 7) as u8,
+                count: (value >> 35) as usize,
+            }),
+            2 => Err(ParserError::UnparsableValue(
+                "capnp far pointers are not supported".into(),
+            )),
+            _ => Err(ParserError::UnparsableValue(
+                "capnp capabilities are not supported".into(),
+            )),
+        }
+    }
+
+    fn struct_at(&self, at: usize) -> Result<Option<StructView<'_>>, ParserError> {
+        match self.pointer(at)? {
+            Pointer::Null => Ok(None),
+            Pointer::Struct {
+                start,
+                data_words,
+                pointers,
+            } => Ok(Some(StructView {
+                segment: self,
+                start,
+                data_words,
+                pointers,
+            })),
+            Pointer::List { .. } => Err(ParserError::UnparsableValue(
+                "capnp struct pointer expected".into(),
+            )),
+        }
+    }
+
+    // elements of a composite list, tag word describes the layout of every element
+    fn struct_list(&self, at: usize) -> Result<Vec<StructView<'_>>, ParserError> {
+        let (start, words) = match self.pointer(at)? {
+            Pointer::Null => return Ok(Vec::new()),
+            Pointer::List {
+                start,
+                element_size: ELEMENT_SIZE_COMPOSITE,
+                count,
+            } => (start, count),
+            _ => {
+                return Err(ParserError::UnparsableValue(
+                    "capnp list of structs expected".into(),
+                ));
+            }
+        };
+        self.check_range(start, words + 1)?;
+        let tag = self.word(start)?;
+        let count = ((tag as u32) >> 2) as usize;
+        let data_words = ((tag >> 32) & 0xFFFF) as usize;
+        let pointers = (tag >> 48) as usize;
+        let element_words = data_words + pointers;
+        if POINTER_STRUCT != tag & 3 || count.saturating_mul(element_words) > words {
+            return Err(ParserError::UnparsableValue(
+                "malformed capnp list tag".into(),
+            ));
+        }
+        Ok((0..count)
+            .map(|i| StructView {
+                segment: self,
+                start: start + 1 + i * element_words,
+                data_words,
+                pointers,
+            })
+            .collect())
+    }
+}
+
+impl StructView<'_> {
+    // fields past the written data section keep their default zero value
+    fn u64_field(&self, index: usize) -> Result<u64, ParserError> {
+        if index < self.data_words {
+            self.segment.word(self.start + index)
+        } else {
+            Ok(0)
+        }
+    }
+
+    fn u16_field(&self, index: usize) -> Result<u16, ParserError> {
+        let word = self.u64_field(index / 4)?;
+        Ok((word >> ((index % 4) * 16)) as u16)
+    }
+
+    fn u8_field(&self, index: usize) -> Result<u8, ParserError> {
+        let word = self.u64_field(index / 8)?;
+        Ok((word >> ((index % 8) * 8)) as u8)
+    }
+
+    // value of `word` with scale stored as scale + 1 at `scale_index`, absent for 0 scale
+    fn scaled_field(
+        &self,
+        word: usize,
+        scale_index: usize,
+    ) -> Result<Option<(i64, u8)>, ParserError> {
+        match self.u8_field(scale_index)? {
+            0 => Ok(None),
+            scale if scale - 1 <= Money::MAX_SCALE => {
+                Ok(Some((self.u64_field(word)? as i64, scale - 1)))
+            }
+            scale => Err(ParserError::UnparsableValue(format!("scale {}", scale - 1))),
+        }
+    }
+
+    // scale stored as scale + 1 at `index`, `Money::DEFAULT_SCALE` for 0
+    fn scale_field(&self, index: usize) -> Result<u8, ParserError> {
+        match self.u8_field(index)? {
+            0 => Ok(Money::DEFAULT_SCALE),
+            scale if scale - 1 <= Money::MAX_SCALE => Ok(scale - 1),
+            scale => Err(ParserError::UnparsableValue(format!("scale {}", scale - 1))),
+        }
+    }
+
+    fn bool_field(&self, index: usize) -> Result<bool, ParserError> {
+        let word = self.u64_field(index / 64)?;
+        Ok(0 != (word >> (index % 64)) & 1)
+    }
+
+    fn data_field(&self, index: usize) -> Result<Option<&[u8]>, ParserError> {
+        if index >= self.pointers {
+            return Ok(None);
+        }
+        let at = self.start + self.data_words + index;
+        match self.segment.pointer(at)? {
+            Pointer::Null => Ok(None),
+            Pointer::List {
+                start,
+                element_size: ELEMENT_SIZE_BYTE,
+                count,
+            } => {
+                self.segment.check_range(start, count.div_ceil(WORD_SIZE))?;
+                Ok(Some(
+                    &self.segment.bytes[start * WORD_SIZE..start * WORD_SIZE + count],
+                ))
+            }
+            _ => Err(ParserError::UnparsableValue(
+                "capnp byte list expected".into(),
+            )),
+        }
+    }
+
+    fn text_field(&self, index: usize) -> Result<Option<&str>, ParserError> {
+        let Some(bytes) = self.data_field(index)? else {
+            return Ok(None);
+        };
+        // text is NUL terminated, the terminator is part of the list
+        match bytes.split_last() {
+            Some((0, text)) => std::str::from_utf8(text)
+                .map(Some)
+                .map_err(|_| ParserError::UnparsableValue("non utf-8 string".into())),
+            Some(_) => Err(ParserError::UnparsableValue(
+                "capnp text is not NUL terminated".into(),
+            )),
+            None => Err(ParserError::UnparsableValue("capnp text expected".into())),
+        }
+    }
+}
+
+impl CapnpCodec {
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn parse_kind(&self, v: u16) -> Result<TxKind, ParserError> {
+        match v {
+            0 => Ok(TxKind::Deposit),
+            1 => Ok(TxKind::Transfer),
+            2 => Ok(TxKind::Withdrawal),
+            3 => Ok(TxKind::Refund),
+            4 => Ok(TxKind::Fee),
+            5 => Ok(TxKind::Reversal),
+            _ => Err(ParserError::UnparsableValue(v.to_string())),
+        }
+    }
+    fn kind_to_u16(&self, v: TxKind) -> u16 {
+        match v {
+            TxKind::Deposit => 0,
+            TxKind::Transfer => 1,
+            TxKind::Withdrawal => 2,
+            TxKind::Refund => 3,
+            TxKind::Fee => 4,
+            TxKind::Reversal => 5,
+        }
+    }
+    fn parse_status(&self, v: u16) -> Result<TxStatus, ParserError> {
+        match v {
+            0 => Ok(TxStatus::Success),
+            1 => Ok(TxStatus::Failure),
+            2 => Ok(TxStatus::Pending),
+            3 => Ok(TxStatus::Cancelled),
+            4 => Ok(TxStatus::Reversed),
+            5 => Ok(TxStatus::Expired),
+            _ => Err(ParserError::UnparsableValue(v.to_string())),
+        }
+    }
+    fn status_to_u16(&self, v: TxStatus) -> u16 {
+        match v {
+            TxStatus::Success => 0,
+            TxStatus::Failure => 1,
+            TxStatus::Pending => 2,
+            TxStatus::Cancelled => 3,
+            TxStatus::Reversed => 4,
+            TxStatus::Expired => 5,
+        }
+    }
+
+    // `pos` is the stream offset of the segment, used for error contexts
+    fn parse_message(&self, segment: &Segment, pos: usize) -> Result<Vec<TxRecord>, AppError> {
+        let at_word = |word: usize| pos + word * WORD_SIZE;
+        let batch = segment
+            .struct_at(0)
+            .add_parser_ctx(ParserContext::with_position(pos))?;
+        let Some(batch) = batch else {
+            return Ok(Vec::new());
+        };
+        if 0 == batch.pointers {
+            return Ok(Vec::new());
+        }
+        let records = segment
+            .struct_list(batch.start + batch.data_words)
+            .add_parser_ctx(ParserContext::with_position(at_word(batch.start)))?;
+
+        let mut result = Vec::with_capacity(records.len());
+        for record in records {
+            let ctx = |field_key| {
+                ParserContext::with_position_and_field_key(at_word(record.start), field_key)
+            };
+            let field = |index| {
+                record
+                    .u64_field(index)
+                    .add_parser_ctx(ParserContext::with_position(at_word(record.start)))
+            };
+            let kind = record
+                .u16_field(KIND_U16)
+                .and_then(|v| self.parse_kind(v))
+                .add_parser_ctx(ctx(TxFieldKey::TxKind))?;
+            let status = record
+                .u16_field(STATUS_U16)
+                .and_then(|v| self.parse_status(v))
+                .add_parser_ctx(ctx(TxFieldKey::Status))?;
+            let description = record
+                .text_field(DESCRIPTION_POINTER)
+                .add_parser_ctx(ctx(TxFieldKey::Description))?
+                .map(str::to_string);
+            let tenant = record
+                .text_field(TENANT_POINTER)
+                .add_parser_ctx(ctx(TxFieldKey::Tenant))?
+                .map(str::to_string);
+            let from_iban = record
+                .text_field(FROM_IBAN_POINTER)
+                .add_parser_ctx(ctx(TxFieldKey::FromUserId))?
+                .map(str::to_string);
+            let to_iban = record
+                .text_field(TO_IBAN_POINTER)
+                .add_parser_ctx(ctx(TxFieldKey::ToUserId))?
+                .map(str::to_string);
+            let reference = record
+                .text_field(REFERENCE_POINTER)
+                .add_parser_ctx(ctx(TxFieldKey::Reference))?
+                .map(str::to_string);
+            let uuid = record
+                .data_field(UUID_POINTER)
+                .and_then(|bytes| {
+                    bytes
+                        .map(|bytes| {
+                            <[u8; UUID_SIZE]>::try_from(bytes)
+                                .map(u128::from_be_bytes)
+                                .map_err(|_| {
+                                    ParserError::UnparsableValue("UUID must be 16 bytes".into())
+                                })
+                        })
+                        .transpose()
+                })
+                .add_parser_ctx(ctx(TxFieldKey::Id))?;
+            let fee = match record.bool_field(HAS_FEE_BIT) {
+                Ok(true) => Some(Money::new(
+                    field(FEE_WORD)? as i64,
+                    record
+                        .scale_field(FEE_SCALE_U8)
+                        .add_parser_ctx(ctx(TxFieldKey::Fee))?,
+                )),
+                Ok(false) => None,
+                Err(e) => return Err(e).add_parser_ctx(ctx(TxFieldKey::Fee)),
+            };
+            let original_amount = record
+                .scaled_field(ORIGINAL_AMOUNT_WORD, ORIGINAL_AMOUNT_SCALE_U8)
+                .add_parser_ctx(ctx(TxFieldKey::OriginalAmount))?
+                .map(|(units, scale)| Money::new(units, scale));
+            let exchange_rate = record
+                .scaled_field(EXCHANGE_RATE_WORD, EXCHANGE_RATE_SCALE_U8)
+                .add_parser_ctx(ctx(TxFieldKey::ExchangeRate))?
+                .map(|(mantissa, scale)| ExchangeRate::new(mantissa, scale));
+            let amount_scale = record
+                .scale_field(AMOUNT_SCALE_U8)
+                .add_parser_ctx(ctx(TxFieldKey::Amount))?;
+            result.push(TxRecord {
+                id: TxIdType::from_parts(field(ID_WORD)?, uuid),
+                kind,
+                from: AccountType::from_parts(field(FROM_WORD)?, from_iban),
+                to: AccountType::from_parts(field(TO_WORD)?, to_iban),
+                amount: Money::new(field(AMOUNT_WORD)? as i64, amount_scale),
+                fee,
+                original_amount,
+                exchange_rate,
+                ts: TxTimestamp::from_millis(field(TIMESTAMP_WORD)?),
+                status,
+                description,
+                tenant,
+                reference,
+                extras: Default::default(),
+            });
+        }
+        Ok(result)
+    }
+
+    // single segment with TxBatch root, records list and texts appended after the records
+    fn encode_message(&self, data: &[TxRecord]) -> Result<Vec<u64>, AppError> {
+        let records_start = 3;
+        let mut words = vec![0u64; records_start + data.len() * RECORD_WORDS];
+        words[0] = struct_pointer(0, 0, 1);
+        words[1] = list_pointer(0, ELEMENT_SIZE_COMPOSITE, data.len() * RECORD_WORDS);
+        words[2] = struct_pointer(data.len() as i64, RECORD_DATA_WORDS, RECORD_POINTERS);
+
+        for (i, tx) in data.iter().enumerate() {
+            let base = records_start + i * RECORD_WORDS;
+            words[base + ID_WORD] = tx.id.as_numeric().unwrap_or(0);
+            words[base + FROM_WORD] = tx.from.as_numeric().unwrap_or(0);
+            words[base + TO_WORD] = tx.to.as_numeric().unwrap_or(0);
+            words[base + AMOUNT_WORD] = tx.amount.minor_units as u64;
+            words[base + TIMESTAMP_WORD] = tx.ts.millis();
+            words[base + KIND_U16 / 4] = u64::from(self.kind_to_u16(tx.kind))
+                | (u64::from(self.status_to_u16(tx.status)) << 16);
+            words[base + AMOUNT_SCALE_U8 / 8] |=
+                u64::from(tx.amount.scale + 1) << ((AMOUNT_SCALE_U8 % 8) * 8);
+            if let Some(fee) = tx.fee {
+                words[base + HAS_FEE_BIT / 64] |= 1 << (HAS_FEE_BIT % 64);
+                words[base + FEE_WORD] = fee.minor_units as u64;
+                words[base + FEE_SCALE_U8 / 8] |=
+                    u64::from(fee.scale + 1) << ((FEE_SCALE_U8 % 8) * 8);
+            }
+            let scaled = [
+                (
+                    ORIGINAL_AMOUNT_WORD,
+                    ORIGINAL_AMOUNT_SCALE_U8,
+                    tx.original_amount
+                        .map(|amount| (amount.minor_units, amount.scale)),
+                ),
+                (
+                    EXCHANGE_RATE_WORD,
+                    EXCHANGE_RATE_SCALE_U8,
+                    tx.exchange_rate.map(|rate| (rate.mantissa, rate.scale)),
+                ),
+            ];
+            for (word, scale_index, value) in scaled {
+                let Some((units, scale)) = value else {
+                    continue;
+                };
+                words[base + word] = units as u64;
+                words[base + scale_index / 8] |= u64::from(scale + 1) << ((scale_index % 8) * 8);
+            }
+
+            let texts = [
+                (DESCRIPTION_POINTER, tx.description.as_deref()),
+                (TENANT_POINTER, tx.tenant.as_deref()),
+                (FROM_IBAN_POINTER, tx.from.as_iban()),
+                (TO_IBAN_POINTER, tx.to.as_iban()),
+                (REFERENCE_POINTER, tx.reference.as_deref()),
+            ];
+            let texts = texts.into_iter().map(|(index, text)| {
+                let bytes = text.map(|text| {
+                    let mut bytes = text.as_bytes().to_vec();
+                    bytes.push(0);
+                    bytes
+                });
+                (index, bytes)
+            });
+            let uuid = tx.id.as_uuid().map(|uuid| uuid.to_be_bytes().to_vec());
+            for (index, bytes) in texts.chain([(UUID_POINTER, uuid)]) {
+                let Some(bytes) = bytes else { continue };
+                let at = base + RECORD_DATA_WORDS + index;
+                let offset = (words.len() - at - 1) as i64;
+                words[at] = list_pointer(offset, ELEMENT_SIZE_BYTE, bytes.len());
+                words.extend(bytes.chunks(WORD_SIZE).map(|chunk| {
+                    let mut word = [0u8; WORD_SIZE];
+                    word[..chunk.len()].copy_from_slice(chunk);
+                    u64::from_le_bytes(word)
+                }));
+            }
+            if words.len() > MAX_SEGMENT_WORDS {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "records are too large for capnp message",
+                ))
+                .add_write_ctx();
+            }
+        }
+        Ok(words)
+    }
+}
+
+fn struct_pointer(offset: i64, data_words: usize, pointers: usize) -> u64 {
+    POINTER_STRUCT
+        | u64::from((offset as u32) << 2)
+        | ((data_words as u64) << 32)
+        | ((pointers as u64) << 48)
+}
+
+fn list_pointer(offset: i64, element_size: u8, count: usize) -> u64 {
+    POINTER_LIST
+        | u64::from((offset as u32) << 2)
+        | (u64::from(element_size) << 32)
+        | ((count as u64) << 35)
+}
+
+fn read_u32_le<R: Read>(r: &mut R, pos: usize) -> Result<u32, AppError> {
+    let mut bytes = [0u8; 4];
+    r.read_exact(&mut bytes).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => AppError::ParsingError {
+            context: ParserContext::with_position(pos),
+            source: ParserError::IncompleteRecord,
+        },
+        _ => AppError::ReadError(e),
+    })?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+impl DataParser for CapnpCodec {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        collect_recovering(
+            CapnpRecordReader::new(self, r),
+            self.limits,
+            &mut |rejected| Err(rejected.into_error()),
+        )
+    }
+}
+
+impl StreamingParser for CapnpCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, r: R) -> RecordStream<'a> {
+        RecordStream::new(CapnpRecordReader::new(self, r))
+    }
+}
+
+// messages follow each other till the end of the stream, records are decoded message by message
+struct CapnpRecordReader<R> {
+    codec: CapnpCodec,
+    r: BufReader<R>,
+    pos: usize,
+    message: std::vec::IntoIter<TxRecord>,
+}
+
+impl<R: Read> CapnpRecordReader<R> {
+    fn new(codec: &CapnpCodec, r: R) -> Self {
+        Self {
+            codec: codec.clone(),
+            r: BufReader::new(r),
+            pos: 0,
+            message: Vec::new().into_iter(),
+        }
+    }
+
+    fn read_message(&mut self) -> Result<Option<Vec<TxRecord>>, AppError> {
+        let (r, mut pos, max_segment) = (&mut self.r, self.pos, self.codec.limits.max_block_size);
+        if r.fill_buf().add_read_ctx()?.is_empty() {
+            return Ok(None);
+        }
+        let segments = read_u32_le(r, pos)? as usize + 1;
+        if segments > MAX_SEGMENTS {
+            return Err(ParserError::InvalidFileHeader)
+                .add_parser_ctx(ParserContext::with_position(pos));
+        }
+        let mut sizes = Vec::with_capacity(segments);
+        for i in 0..segments {
+            let size_pos = pos + 4 + i * 4;
+            let size = read_u32_le(r, size_pos)? as usize * WORD_SIZE;
+            ParseLimits::check_size("segment", size as u64, max_segment)
+                .add_parser_ctx(ParserContext::with_position(size_pos))?;
+            sizes.push(size);
+        }
+        // segment table is padded to the word boundary
+        let mut table_len = 4 * (1 + segments);
+        if !table_len.is_multiple_of(WORD_SIZE) {
+            read_u32_le(r, pos + table_len)?;
+            table_len += 4;
+        }
+        pos += table_len;
+
+        let segment_pos = pos;
+        let mut first = Vec::new();
+        for (i, size) in sizes.into_iter().enumerate() {
+            let mut limited = (&mut *r).take(size as u64);
+            let read = if 0 == i {
+                limited.read_to_end(&mut first).add_read_ctx()?
+            } else {
+                std::io::copy(&mut limited, &mut std::io::sink()).add_read_ctx()? as usize
+            };
+            if read < size {
+                return Err(ParserError::IncompleteRecord)
+                    .add_parser_ctx(ParserContext::with_position(pos + read));
+            }
+            pos += size;
+        }
+        self.pos = pos;
+        let segment = Segment { bytes: &first };
+        self.codec.parse_message(&segment, segment_pos).map(Some)
+    }
+}
+
+impl<R: Read> RecordReader for CapnpRecordReader<R> {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        loop {
+            if let Some(tx) = self.message.next() {
+                return Some(Ok(Ok(tx)));
+            }
+            match self.read_message().transpose()? {
+                Ok(records) => self.message = records.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl DataWriter for CapnpCodec {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        for chunk in data.chunks(RECORDS_PER_MESSAGE) {
+            let words = self.encode_message(chunk)?;
+            // segment table: segments count minus one, then size of the only segment
+            w.write_all(&0u32.to_le_bytes()).add_write_ctx()?;
+            w.write_all(&(words.len() as u32).to_le_bytes())
+                .add_write_ctx()?;
+            for word in words {
+                w.write_all(&word.to_le_bytes()).add_write_ctx()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StreamingWriter for CapnpCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        Ok(RecordSink::new(Blocks::new(
+            self.clone(),
+            w,
+            RECORDS_PER_MESSAGE,
+        )))
+    }
+}
@@ -0,0 +1,612 @@
+use std::io::{BufReader, Read, Write};
+
+use super::errors::IoCtxBehavior;
+use super::errors::{ParserContext, ParserCtxBehavior, ParserError};
+use super::options::ParseLimits;
+use super::quarantine::RejectedInput;
+use super::traits::*;
+use crate::codecs::base::TxFieldKey;
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+// Stream is a sequence of blocks: magic, records count (u32), body size (u32), body.
+// Body stores fields column by column, all values of a column are adjacent:
+// ids, kinds, from, to, amounts, timestamps, statuses (fixed size, big-endian as YPBN),
+// then description lengths (u32) followed by description bytes, then tenant lengths
+// (u32, NO_TENANT for absent tenant) followed by tenant bytes.
+// Blocks with IBAN accounts end with IBANs of from and then to accounts stored as tenants,
+// from/to of IBAN account is 0.
+// Blocks with UUID ids end with UUID presence column (u8) followed by 16 big-endian bytes of
+// present UUIDs, IBAN columns precede it even without IBAN accounts, id of UUID record is 0.
+// Blocks with fees end with fee scale column (u8, 0 for absent fee or scale + 1) followed by
+// minor units (i64) of present fees, IBAN and UUID columns precede it even without IBAN
+// accounts and UUID ids.
+// Blocks with references end with references stored as tenants, all the optional columns
+// above precede them.
+// Blocks with original amounts or exchange rates end with a scale column (u8, 0 for absent
+// value or scale + 1) followed by minor units (i64) of present original amounts, then the same
+// columns of exchange rates; all the optional columns above precede them.
+// Blocks with amounts of other scale than Money::DEFAULT_SCALE end with amount scale column
+// (u8), all the optional columns above precede it.
+const BLOCK_MAGIC: [u8; 4] = *b"YPBC";
+const BLOCK_HEADER_SIZE: usize = 4 + 4 + 4;
+const BLOCK_RECORDS: usize = 8192;
+const FIXED_COLUMNS_SIZE: usize = 8 + 1 + 8 + 8 + 8 + 8 + 1;
+const NO_TENANT: u32 = u32::MAX;
+const UUID_SIZE: usize = 16;
+const SCALED_SIZE: usize = 8;
+
+/// Codec for column-wise binary layout (YPBN-C), records are grouped in blocks.
+///
+/// Similar values are stored next to each other, so the files compress better than record-wise
+/// YPBN ones and fixed-size columns are decoded without per-record framing.
+#[derive(Clone, Default)]
+pub(crate) struct ColumnarBinaryCodec {
+    limits: ParseLimits,
+}
+
+// sequential reader over block body, `pos` is stream offset of the body for error contexts
+struct ColumnReader<'a> {
+    body: &'a [u8],
+    offset: usize,
+    pos: usize,
+}
+
+impl<'a> ColumnReader<'a> {
+    fn position(&self) -> usize {
+        self.pos + self.offset
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], AppError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.body.len());
+        let Some(end) = end else {
+            return Err(ParserError::IncompleteRecord)
+                .add_parser_ctx(ParserContext::with_position(self.position()));
+        };
+        let bytes = &self.body[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u64_column(&mut self, count: usize) -> Result<Vec<u64>, AppError> {
+        let bytes = self.take(count * 8)?;
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|b| u64::from_be_bytes(b.try_into().expect("8 bytes chunk")))
+            .collect())
+    }
+
+    fn u32_column(&mut self, count: usize) -> Result<Vec<u32>, AppError> {
+        let bytes = self.take(count * 4)?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|b| u32::from_be_bytes(b.try_into().expect("4 bytes chunk")))
+            .collect())
+    }
+
+    fn codes_column<T>(
+        &mut self,
+        count: usize,
+        field_key: TxFieldKey,
+        decode: impl Fn(u8) -> Result<T, ParserError>,
+    ) -> Result<Vec<T>, AppError> {
+        let start = self.position();
+        let bytes = self.take(count)?;
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(i, &code)| {
+                decode(code).add_parser_ctx(ParserContext::with_position_and_field_key(
+                    start + i,
+                    field_key,
+                ))
+            })
+            .collect()
+    }
+
+    // lengths column followed by bytes of present strings
+    fn optional_strings(
+        &mut self,
+        count: usize,
+        field_key: TxFieldKey,
+    ) -> Result<Vec<Option<String>>, AppError> {
+        let lens = self.u32_column(count)?;
+        let mut strings = Vec::with_capacity(count);
+        for len in lens {
+            strings.push(match len {
+                NO_TENANT => None,
+                len => Some(self.string(len as usize, field_key)?),
+            });
+        }
+        Ok(strings)
+    }
+
+    fn presence_column(
+        &mut self,
+        count: usize,
+        field_key: TxFieldKey,
+    ) -> Result<Vec<bool>, AppError> {
+        self.codes_column(count, field_key, |v| match v {
+            0 | 1 => Ok(1 == v),
+            _ => Err(ParserError::UnparsableValue(v.to_string())),
+        })
+    }
+
+    // presence column followed by bytes of present UUIDs
+    fn optional_uuids(&mut self, count: usize) -> Result<Vec<Option<u128>>, AppError> {
+        let present = self.presence_column(count, TxFieldKey::Id)?;
+        let mut uuids = Vec::with_capacity(count);
+        for present in present {
+            uuids.push(match present {
+                false => None,
+                true => {
+                    let bytes = self.take(UUID_SIZE)?;
+                    Some(u128::from_be_bytes(bytes.try_into().expect("16 bytes")))
+                }
+            });
+        }
+        Ok(uuids)
+    }
+
+    // scale column (scale + 1, 0 for absent value) followed by present minor units
+    fn optional_scaled(
+        &mut self,
+        count: usize,
+        field_key: TxFieldKey,
+    ) -> Result<Vec<Option<(i64, u8)>>, AppError> {
+        let scales = self.codes_column(count, field_key, |v| match v.checked_sub(1) {
+            None => Ok(None),
+            Some(scale) if scale <= Money::MAX_SCALE => Ok(Some(scale)),
+            Some(scale) => Err(ParserError::UnparsableValue(format!("scale {}", scale))),
+        })?;
+        let mut values = Vec::with_capacity(count);
+        for scale in scales {
+            values.push(match scale {
+                None => None,
+                Some(scale) => {
+                    let bytes = self.take(SCALED_SIZE)?;
+                    Some((
+                        i64::from_be_bytes(bytes.try_into().expect("8 bytes")),
+                        scale,
+                    ))
+                }
+            });
+        }
+        Ok(values)
+    }
+
+    fn scale_column(&mut self, count: usize) -> Result<Vec<u8>, AppError> {
+        self.codes_column(count, TxFieldKey::Amount, |v| match v {
+            scale if scale <= Money::MAX_SCALE => Ok(scale),
+            scale => Err(ParserError::UnparsableValue(format!("scale {}", scale))),
+        })
+    }
+
+    fn string(&mut self, len: usize, field_key: TxFieldKey) -> Result<String, AppError> {
+        let start = self.position();
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| ParserError::UnparsableValue("non utf-8 string".into()))
+            .add_parser_ctx(ParserContext::with_position_and_field_key(start, field_key))
+    }
+}
+
+impl ColumnarBinaryCodec {
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn parse_kind_from_u8(&self, v: u8) -> Result<TxKind, ParserError> {
+        match v {
+            0 => Ok(TxKind::Deposit),
+            1 => Ok(TxKind::Transfer),
+            2 => Ok(TxKind::Withdrawal),
+            3 => Ok(TxKind::Refund),
+            4 => Ok(TxKind::Fee),
+            5 => Ok(TxKind::Reversal),
+            _ => Err(ParserError::UnparsableValue(v.to_string())),
+        }
+    }
+    fn kind_to_u8(&self, v: TxKind) -> u8 {
+        match v {
+            TxKind::Deposit => 0,
+            TxKind::Transfer => 1,
+            TxKind::Withdrawal => 2,
+            TxKind::Refund => 3,
+            TxKind::Fee => 4,
+            TxKind::Reversal => 5,
+        }
+    }
+    fn parse_status_from_u8(&self, v: u8) -> Result<TxStatus, ParserError> {
+        match v {
+            0 => Ok(TxStatus::Success),
+            1 => Ok(TxStatus::Failure),
+            2 => Ok(TxStatus::Pending),
+            3 => Ok(TxStatus::Cancelled),
+            4 => Ok(TxStatus::Reversed),
+            5 => Ok(TxStatus::Expired),
+            _ => Err(ParserError::UnparsableValue(v.to_string())),
+        }
+    }
+    fn status_to_u8(&self, v: TxStatus) -> u8 {
+        match v {
+            TxStatus::Success => 0,
+            TxStatus::Failure => 1,
+            TxStatus::Pending => 2,
+            TxStatus::Cancelled => 3,
+            TxStatus::Reversed => 4,
+            TxStatus::Expired => 5,
+        }
+    }
+
+    fn parse_block_body(
+        &self,
+        body: &[u8],
+        count: usize,
+        pos: usize,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        let mut columns = ColumnReader {
+            body,
+            offset: 0,
+            pos,
+        };
+        // fixed columns must fit before anything is allocated for them
+        if count.saturating_mul(FIXED_COLUMNS_SIZE) > body.len() {
+            return Err(ParserError::IncompleteRecord)
+                .add_parser_ctx(ParserContext::with_position(pos));
+        }
+        let ids = columns.u64_column(count)?;
+        let kinds =
+            columns.codes_column(count, TxFieldKey::TxKind, |v| self.parse_kind_from_u8(v))?;
+        let from = columns.u64_column(count)?;
+        let to = columns.u64_column(count)?;
+        let amounts = columns.u64_column(count)?;
+        let timestamps = columns.u64_column(count)?;
+        let statuses =
+            columns.codes_column(count, TxFieldKey::Status, |v| self.parse_status_from_u8(v))?;
+
+        let description_lens = columns.u32_column(count)?;
+        let mut descriptions = Vec::with_capacity(count);
+        for len in description_lens {
+            descriptions.push(columns.string(len as usize, TxFieldKey::Description)?);
+        }
+        let tenants = columns.optional_strings(count, TxFieldKey::Tenant)?;
+        let (from_ibans, to_ibans) = if columns.offset != body.len() {
+            (
+                columns.optional_strings(count, TxFieldKey::FromUserId)?,
+                columns.optional_strings(count, TxFieldKey::ToUserId)?,
+            )
+        } else {
+            (vec![None; count], vec![None; count])
+        };
+        let uuids = if columns.offset != body.len() {
+            columns.optional_uuids(count)?
+        } else {
+            vec![None; count]
+        };
+        let fees = if columns.offset != body.len() {
+            columns
+                .optional_scaled(count, TxFieldKey::Fee)?
+                .into_iter()
+                .map(|fee| fee.map(|(units, scale)| Money::new(units, scale)))
+                .collect()
+        } else {
+            vec![None; count]
+        };
+        let references = if columns.offset != body.len() {
+            columns.optional_strings(count, TxFieldKey::Reference)?
+        } else {
+            vec![None; count]
+        };
+        let (original_amounts, exchange_rates) = if columns.offset != body.len() {
+            (
+                columns.optional_scaled(count, TxFieldKey::OriginalAmount)?,
+                columns.optional_scaled(count, TxFieldKey::ExchangeRate)?,
+            )
+        } else {
+            (vec![None; count], vec![None; count])
+        };
+        let amount_scales = if columns.offset != body.len() {
+            columns.scale_column(count)?
+        } else {
+            vec![Money::DEFAULT_SCALE; count]
+        };
+        if columns.offset != body.len() {
+            return Err(ParserError::UnparsableValue(
+                "unexpected bytes after last column".into(),
+            ))
+            .add_parser_ctx(ParserContext::with_position(columns.position()));
+        }
+
+        let mut result = Vec::with_capacity(count);
+        let optionals = tenants
+            .into_iter()
+            .zip(from_ibans)
+            .zip(to_ibans)
+            .zip(references);
+        for (i, (description, (((tenant, from_iban), to_iban), reference))) in
+            descriptions.into_iter().zip(optionals).enumerate()
+        {
+            result.push(TxRecord {
+                id: TxIdType::from_parts(ids[i], uuids[i]),
+                kind: kinds[i],
+                from: AccountType::from_parts(from[i], from_iban),
+                to: AccountType::from_parts(to[i], to_iban),
+                amount: Money::new(amounts[i] as i64, amount_scales[i]),
+                fee: fees[i],
+                original_amount: original_amounts[i].map(|(units, scale)| Money::new(units, scale)),
+                exchange_rate: exchange_rates[i]
+                    .map(|(mantissa, scale)| ExchangeRate::new(mantissa, scale)),
+                ts: TxTimestamp::from_millis(timestamps[i]),
+                status: statuses[i],
+                description: Some(description).filter(|description| !description.is_empty()),
+                tenant,
+                reference,
+                extras: Default::default(),
+            });
+        }
+        Ok(result)
+    }
+
+    fn encode_block_body(&self, block: &[TxRecord]) -> Result<Vec<u8>, AppError> {
+        let mut body = Vec::with_capacity(block.len() * (FIXED_COLUMNS_SIZE + 8));
+        body.extend(
+            block
+                .iter()
+                .flat_map(|tx| tx.id.as_numeric().unwrap_or(0).to_be_bytes()),
+        );
+        body.extend(block.iter().map(|tx| self.kind_to_u8(tx.kind)));
+        body.extend(
+            block
+                .iter()
+                .flat_map(|tx| tx.from.as_numeric().unwrap_or(0).to_be_bytes()),
+        );
+        body.extend(
+            block
+                .iter()
+                .flat_map(|tx| tx.to.as_numeric().unwrap_or(0).to_be_bytes()),
+        );
+        body.extend(
+            block
+                .iter()
+                .flat_map(|tx| tx.amount.minor_units.to_be_bytes()),
+        );
+        body.extend(block.iter().flat_map(|tx| tx.ts.millis().to_be_bytes()));
+        body.extend(block.iter().map(|tx| self.status_to_u8(tx.status)));
+
+        body.extend(
+            block.iter().flat_map(|tx| {
+                (tx.description.as_deref().map_or(0, str::len) as u32).to_be_bytes()
+            }),
+        );
+        for tx in block {
+            body.extend_from_slice(tx.description.as_deref().unwrap_or_default().as_bytes());
+        }
+        push_optional_strings(&mut body, block.iter().map(|tx| tx.tenant.as_deref()));
+        let amount_scales = block
+            .iter()
+            .any(|tx| Money::DEFAULT_SCALE != tx.amount.scale);
+        let exchange = amount_scales
+            || block
+                .iter()
+                .any(|tx| tx.original_amount.is_some() || tx.exchange_rate.is_some());
+        let references = exchange || block.iter().any(|tx| tx.reference.is_some());
+        let fees = references || block.iter().any(|tx| tx.fee.is_some());
+        let uuids = fees || block.iter().any(|tx| tx.id.as_uuid().is_some());
+        if uuids
+            || block
+                .iter()
+                .any(|tx| tx.from.as_iban().is_some() || tx.to.as_iban().is_some())
+        {
+            push_optional_strings(&mut body, block.iter().map(|tx| tx.from.as_iban()));
+            push_optional_strings(&mut body, block.iter().map(|tx| tx.to.as_iban()));
+        }
+        if uuids {
+            body.extend(block.iter().map(|tx| u8::from(tx.id.as_uuid().is_some())));
+            for uuid in block.iter().filter_map(|tx| tx.id.as_uuid()) {
+                body.extend_from_slice(&uuid.to_be_bytes());
+            }
+        }
+        if fees {
+            push_optional_scaled(
+                &mut body,
+                block
+                    .iter()
+                    .map(|tx| tx.fee.map(|fee| (fee.minor_units, fee.scale))),
+            );
+        }
+        if references {
+            push_optional_strings(&mut body, block.iter().map(|tx| tx.reference.as_deref()));
+        }
+        if exchange {
+            push_optional_scaled(
+                &mut body,
+                block.iter().map(|tx| {
+                    tx.original_amount
+                        .map(|amount| (amount.minor_units, amount.scale))
+                }),
+            );
+            push_optional_scaled(
+                &mut body,
+                block
+                    .iter()
+                    .map(|tx| tx.exchange_rate.map(|rate| (rate.mantissa, rate.scale))),
+            );
+        }
+        if amount_scales {
+            body.extend(block.iter().map(|tx| tx.amount.scale));
+        }
+
+        if u32::try_from(body.len()).is_err() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "block is too large for columnar format",
+            ))
+            .add_write_ctx();
+        }
+        Ok(body)
+    }
+}
+
+// lengths column (NO_TENANT for absent string) followed by bytes of present strings
+fn push_optional_strings<'a>(
+    body: &mut Vec<u8>,
+    values: impl Iterator<Item = Option<&'a str>> + Clone,
+) {
+    body.extend(
+        values
+            .clone()
+            .flat_map(|value| value.map_or(NO_TENANT, |v| v.len() as u32).to_be_bytes()),
+    );
+    for value in values.flatten() {
+        body.extend_from_slice(value.as_bytes());
+    }
+}
+
+// scale column (scale + 1, 0 for absent value) followed by minor units of present values
+fn push_optional_scaled(
+    body: &mut Vec<u8>,
+    values: impl Iterator<Item = Option<(i64, u8)>> + Clone,
+) {
+    body.extend(
+        values
+            .clone()
+            .map(|value| value.map_or(0, |(_, scale)| scale + 1)),
+    );
+    for (units, _) in values.flatten() {
+        body.extend_from_slice(&units.to_be_bytes());
+    }
+}
+
+impl DataParser for ColumnarBinaryCodec {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_recovering(r, &mut |rejected| Err(rejected.into_error()))
+    }
+}
+
+impl RecoveringParser for ColumnarBinaryCodec {
+    fn parse_recovering<R: Read>(
+        &self,
+        r: R,
+        on_reject: &mut dyn FnMut(RejectedInput) -> Result<(), AppError>,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        collect_recovering(ColumnarRecordReader::new(self, r), self.limits, on_reject)
+    }
+}
+
+impl StreamingParser for ColumnarBinaryCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, r: R) -> RecordStream<'a> {
+        RecordStream::new(ColumnarRecordReader::new(self, r))
+    }
+}
+
+// block is the smallest unit which can be stepped over, malformed block is rejected at once
+struct ColumnarRecordReader<R> {
+    codec: ColumnarBinaryCodec,
+    r: BufReader<R>,
+    pos: usize,
+    block: std::vec::IntoIter<TxRecord>,
+}
+
+impl<R: Read> ColumnarRecordReader<R> {
+    fn new(codec: &ColumnarBinaryCodec, r: R) -> Self {
+        Self {
+            codec: codec.clone(),
+            r: BufReader::new(r),
+            pos: 0,
+            block: Vec::new().into_iter(),
+        }
+    }
+
+    fn read_block(&mut self) -> Result<Option<Result<Vec<TxRecord>, RejectedInput>>, AppError> {
+        let mut header = [0u8; BLOCK_HEADER_SIZE];
+        match self.r.read_exact(&mut header[..1]) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(AppError::ReadError(e)),
+        }
+        self.r
+            .read_exact(&mut header[1..])
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::UnexpectedEof => AppError::ParsingError {
+                    context: ParserContext::with_position(self.pos),
+                    source: ParserError::IncompleteRecord,
+                },
+                _ => AppError::ReadError(e),
+            })?;
+        if BLOCK_MAGIC != header[..4] {
+            let hex: String = header[..4].iter().map(|b| format!("{:02X}", b)).collect();
+            return Err(ParserError::InvalidRecordHeader(hex))
+                .add_parser_ctx(ParserContext::with_position(self.pos));
+        }
+        let count = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let body_size = u32::from_be_bytes([header[8], header[9], header[10], header[11]]) as usize;
+        ParseLimits::check_size("block", body_size as u64, self.codec.limits.max_block_size)
+            .add_parser_ctx(ParserContext::with_position(self.pos))?;
+        self.pos += BLOCK_HEADER_SIZE;
+
+        // body is read through `take` so a bogus size under the limit fails on EOF instead of
+        // allocating
+        let mut body = Vec::new();
+        let read = (&mut self.r)
+            .take(body_size as u64)
+            .read_to_end(&mut body)
+            .add_read_ctx()?;
+        if read < body_size {
+            return Err(ParserError::IncompleteRecord)
+                .add_parser_ctx(ParserContext::with_position(self.pos + read));
+        }
+        let body_pos = self.pos;
+        self.pos += body_size;
+        match self.codec.parse_block_body(&body, count, body_pos) {
+            Ok(records) => Ok(Some(Ok(records))),
+            // framing is intact, malformed block can be stepped over
+            Err(AppError::ParsingError { context, source }) => {
+                let mut raw = header.to_vec();
+                raw.extend_from_slice(&body);
+                Ok(Some(Err(RejectedInput::new(context, source, raw))))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<R: Read> RecordReader for ColumnarRecordReader<R> {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        loop {
+            if let Some(tx) = self.block.next() {
+                return Some(Ok(Ok(tx)));
+            }
+            match self.read_block().transpose()? {
+                Ok(Ok(records)) => self.block = records.into_iter(),
+                Ok(Err(rejected)) => return Some(Ok(Err(rejected))),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl DataWriter for ColumnarBinaryCodec {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        for block in data.chunks(BLOCK_RECORDS) {
+            let body = self.encode_block_body(block)?;
+            w.write_all(&BLOCK_MAGIC).add_write_ctx()?;
+            w.write_all(&(block.len() as u32).to_be_bytes())
+                .add_write_ctx()?;
+            w.write_all(&(body.len() as u32).to_be_bytes())
+                .add_write_ctx()?;
+            w.write_all(&body).add_write_ctx()?;
+        }
+        Ok(())
+    }
+}
+
+impl StreamingWriter for ColumnarBinaryCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        Ok(RecordSink::new(Blocks::new(self.clone(), w, BLOCK_RECORDS)))
+    }
+}
@@ -0,0 +1,231 @@
+use std::io::{Chain, Cursor, Read, Write};
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+use super::errors::IoCtxBehavior;
+use super::options::ParseLimits;
+use super::utils::SizeLimited;
+use crate::errors::AppError;
+
+// signature with deflate method, the only one defined for gzip
+const GZIP_MAGIC: [u8; 3] = [0x1F, 0x8B, 0x08];
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+// longest signature read to detect compression
+const MAX_MAGIC_SIZE: usize = 4;
+
+/// Compression applied on top of any codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip (RFC 1952), concatenated members are read as one stream.
+    Gzip,
+    /// Zstandard (RFC 8878), concatenated frames are read as one stream.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+/// Tuning of compressed output and dictionary shared by writer and reader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionOptions {
+    /// Zstandard level from 1 (fastest) to 19 (smallest output), gzip has a single level.
+    pub level: i32,
+    /// Zstandard dictionary, raw content or trained by `zstd --train`.
+    ///
+    /// Output written with dictionary can only be read with the same one.
+    pub dictionary: Option<Vec<u8>>,
+}
+
+impl CompressionOptions {
+    /// Level used unless configured, matches the one of `zstd` command line tool.
+    pub const DEFAULT_LEVEL: i32 = 3;
+}
+
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        Self {
+            level: Self::DEFAULT_LEVEL,
+            dictionary: None,
+        }
+    }
+}
+
+impl Compression {
+    /// Detects compression by leading bytes of input, `None` stands for uncompressed input.
+    pub fn detect(prefix: &[u8]) -> Option<Compression> {
+        if prefix.starts_with(&GZIP_MAGIC) {
+            return Some(Compression::Gzip);
+        }
+        #[cfg(feature = "zstd")]
+        if prefix.starts_with(&ZSTD_MAGIC) {
+            return Some(Compression::Zstd);
+        }
+        None
+    }
+}
+
+// malformed dictionary is a configuration issue, not the one of the compressed stream
+#[cfg(feature = "zstd")]
+fn invalid_dictionary(e: std::io::Error) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("invalid zstd dictionary, {}", e),
+    )
+}
+
+type Prefixed<R> = Chain<Cursor<Vec<u8>>, R>;
+
+enum Inner<R> {
+    Plain(Prefixed<R>),
+    Gzip(SizeLimited<MultiGzDecoder<Prefixed<R>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(SizeLimited<zstd::Decoder<'static, std::io::BufReader<Prefixed<R>>>>),
+}
+
+/// Reader detecting compression of input by its leading bytes.
+///
+/// Uncompressed input is passed through as is, compressed one is decompressed as it is read.
+/// Decompressed input over [`ParseLimits::max_block_size`] fails reading with
+/// [`LimitExceeded`] error once codecs turn it into parser error, so a small compressed input
+/// can't expand into unbounded output.
+///
+/// [`LimitExceeded`]: super::errors::ParserError::LimitExceeded
+pub struct CompressedReader<R> {
+    inner: Inner<R>,
+    compression: Option<Compression>,
+}
+
+impl<R: Read> CompressedReader<R> {
+    /// Detects compression of `r`, decompressed input is limited by default limits.
+    pub fn new(r: R) -> Result<Self, AppError> {
+        Self::with_options(r, &CompressionOptions::default())
+    }
+
+    /// Detects compression of `r` and decompresses it with dictionary of `options`.
+    pub fn with_options(r: R, options: &CompressionOptions) -> Result<Self, AppError> {
+        Self::with_limits(r, options, ParseLimits::default())
+    }
+
+    /// Detects compression of `r`, decompressed input over `limits.max_block_size` is rejected.
+    #[cfg_attr(not(feature = "zstd"), allow(unused_variables))]
+    pub fn with_limits(
+        mut r: R,
+        options: &CompressionOptions,
+        limits: ParseLimits,
+    ) -> Result<Self, AppError> {
+        let mut prefix = Vec::with_capacity(MAX_MAGIC_SIZE);
+        (&mut r)
+            .take(MAX_MAGIC_SIZE as u64)
+            .read_to_end(&mut prefix)
+            .add_read_ctx()?;
+        let compression = Compression::detect(&prefix);
+        let r = Cursor::new(prefix).chain(r);
+        let max_size = limits.max_block_size;
+        let inner = match compression {
+            None => Inner::Plain(r),
+            Some(Compression::Gzip) => {
+                Inner::Gzip(SizeLimited::new(MultiGzDecoder::new(r), max_size))
+            }
+            #[cfg(feature = "zstd")]
+            Some(Compression::Zstd) => {
+                let dictionary = options.dictionary.as_deref().unwrap_or_default();
+                let decoder =
+                    zstd::Decoder::with_dictionary(std::io::BufReader::new(r), dictionary)
+                        .map_err(invalid_dictionary)
+                        .add_read_ctx()?;
+                Inner::Zstd(SizeLimited::new(decoder, max_size))
+            }
+        };
+        Ok(Self { inner, compression })
+    }
+
+    /// Compression detected in input.
+    pub fn compression(&self) -> Option<Compression> {
+        self.compression
+    }
+}
+
+impl<R: Read> Read for CompressedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut self.inner {
+            Inner::Plain(r) => r.read(buf),
+            Inner::Gzip(r) => r.read(buf),
+            #[cfg(feature = "zstd")]
+            Inner::Zstd(r) => r.read(buf),
+        }
+    }
+}
+
+enum Encoder<W: Write> {
+    Gzip(GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+/// Writer compressing everything written to it.
+///
+/// Output is compressed as it is written, [`CompressedWriter::finish`] writes the trailer of
+/// the stream, dropping the writer without finishing it leaves the output truncated.
+pub struct CompressedWriter<W: Write> {
+    encoder: Encoder<W>,
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Creates writer compressing output into `w`.
+    pub fn new(w: W, compression: Compression) -> Result<Self, AppError> {
+        Self::with_options(w, compression, CompressionOptions::default())
+    }
+
+    /// Creates writer compressing output into `w` with level and dictionary of `options`.
+    #[cfg_attr(not(feature = "zstd"), allow(unused_variables))]
+    pub fn with_options(
+        w: W,
+        compression: Compression,
+        options: CompressionOptions,
+    ) -> Result<Self, AppError> {
+        let encoder = match compression {
+            Compression::Gzip => Encoder::Gzip(GzEncoder::new(w, flate2::Compression::default())),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                let dictionary = options.dictionary.as_deref().unwrap_or_default();
+                let mut encoder = zstd::Encoder::with_dictionary(w, options.level, dictionary)
+                    .map_err(invalid_dictionary)
+                    .add_write_ctx()?;
+                encoder.include_checksum(true).add_write_ctx()?;
+                Encoder::Zstd(encoder)
+            }
+        };
+        Ok(Self { encoder })
+    }
+
+    /// Writes the end of compressed output and returns underlying writer.
+    pub fn finish(self) -> Result<W, AppError> {
+        let mut w = match self.encoder {
+            Encoder::Gzip(e) => e.finish(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(e) => e.finish(),
+        }
+        .add_write_ctx()?;
+        w.flush().add_write_ctx()?;
+        Ok(w)
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.encoder {
+            Encoder::Gzip(e) => e.write(buf),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(e) => e.write(buf),
+        }
+    }
+
+    // compressed data of everything written so far reaches underlying writer
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.encoder {
+            Encoder::Gzip(e) => e.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(e) => e.flush(),
+        }
+    }
+}
@@ -1,106 +1,819 @@
-use std::io::{BufRead, BufReader, Read, Write};
+use std::collections::BTreeSet;
+use std::io::{BufRead, BufReader, Lines, Read, Write};
+use std::iter::{Enumerate, Peekable};
 
-use super::traits::{DataParser, DataWriter};
-use super::utils::unquote;
+use super::base::TxFieldKey;
+use super::options::{AmountFormat, ParseLimits, TimestampFormat};
+use super::quarantine::RejectedInput;
+use super::schema::{RecordFields, TxSchema, ValueFormats};
+use super::traits::*;
 
 use crate::codecs::errors::{IoCtxBehavior, ParserContext, ParserError};
+use crate::domain::batch::{BatchHeader, TxBatch};
 use crate::domain::tx::*;
 use crate::errors::AppError;
 
-const HEADER_SIGNATURE: &str =
-    "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION";
-const CSV_DELIMITER: char = ',';
-
 const FIELDS_COUNT: usize = 8;
+const FIELDS_COUNT_WITH_TENANT: usize = 9;
+const FIELDS_COUNT_WITH_FEE: usize = 10;
+const FIELDS_COUNT_WITH_REFERENCE: usize = 11;
+const FIELDS_COUNT_WITH_EXCHANGE: usize = 13;
 
-const TX_ID: usize = 0;
-const TX_TYPE: usize = 1;
-const FROM_USER_ID: usize = 2;
-const TO_USER_ID: usize = 3;
-const AMOUNT: usize = 4;
-const TIMESTAMP: usize = 5;
-const STATUS: usize = 6;
+// order of written columns and of headerless input
+const STANDARD_COLUMNS: [TxFieldKey; FIELDS_COUNT_WITH_EXCHANGE] = [
+    TxFieldKey::Id,
+    TxFieldKey::TxKind,
+    TxFieldKey::FromUserId,
+    TxFieldKey::ToUserId,
+    TxFieldKey::Amount,
+    TxFieldKey::Timestamp,
+    TxFieldKey::Status,
+    TxFieldKey::Description,
+    TxFieldKey::Tenant,
+    TxFieldKey::Fee,
+    TxFieldKey::Reference,
+    TxFieldKey::OriginalAmount,
+    TxFieldKey::ExchangeRate,
+];
 const DESCRIPTION: usize = 7;
 
-#[derive(Default)]
-pub(crate) struct CsvCodec;
+// Batch header is kept in `# KEY: value` lines preceding the header or the first record,
+// other lines starting with `#` there are comments.
+const METADATA_PREFIX: char = '#';
+const METADATA_SOURCE: &str = "SOURCE";
+const METADATA_CREATED: &str = "CREATED";
+const METADATA_RECORD_COUNT: &str = "RECORD_COUNT";
+
+/// Layout of delimiter-separated files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvDialect {
+    /// Fields delimiter.
+    pub delimiter: char,
+    /// Quote character of fields (RFC 4180 style), `None` for raw fields.
+    pub quote: Option<char>,
+    /// First non-blank line is the header.
+    pub has_header: bool,
+    /// Unquoted DESCRIPTION is rejected, other fields may be quoted or not.
+    pub require_quoted_description: bool,
+    /// Values of columns missing in header, e.g. `(TxFieldKey::Status, "PENDING")`.
+    ///
+    /// Defaults are parsed as column values of every record and are ignored in strict mode.
+    pub defaults: Vec<(TxFieldKey, String)>,
+    /// Form of TIMESTAMP values.
+    pub timestamps: TimestampFormat,
+    /// Form of AMOUNT values.
+    pub amounts: AmountFormat,
+    /// Header names of columns, e.g. `(TxFieldKey::Id, "transaction_id")`.
+    ///
+    /// Mapped names are matched first, standard ones are accepted as well.
+    pub column_names: Vec<(TxFieldKey, String)>,
+    /// Unknown columns are kept in [`TxRecord::extras`] instead of rejecting the header.
+    ///
+    /// Empty values of extra columns are not kept, headerless input has no extras.
+    pub keep_extras: bool,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: Some('"'),
+            has_header: true,
+            require_quoted_description: true,
+            defaults: Vec::new(),
+            timestamps: TimestampFormat::EpochMillis,
+            amounts: AmountFormat::MinorUnits,
+            column_names: Vec::new(),
+            keep_extras: false,
+        }
+    }
+}
+
+impl CsvDialect {
+    /// Tab-separated values with header and unquoted descriptions.
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: '\t',
+            quote: None,
+            has_header: true,
+            require_quoted_description: false,
+            defaults: Vec::new(),
+            timestamps: TimestampFormat::EpochMillis,
+            amounts: AmountFormat::MinorUnits,
+            column_names: Vec::new(),
+
+            keep_extras: false,
+        }
+    }
+
+    fn formats(&self) -> ValueFormats {
+        ValueFormats {
+            amounts: self.amounts,
+            timestamps: self.timestamps,
+        }
+    }
+
+    fn default_value(&self, key: TxFieldKey) -> Option<&str> {
+        self.defaults
+            .iter()
+            .find(|(default_key, _)| key == *default_key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn column(&self, name: &str) -> Result<Column, ParserError> {
+        if let Some(&(key, _)) = self.column_names.iter().find(|(_, column)| name == column) {
+            return Ok(Column::Field(key));
+        }
+        match name.parse() {
+            Ok(key) => Ok(Column::Field(key)),
+            Err(_) if self.keep_extras => Ok(Column::Extra(name.to_string())),
+            Err(_) => Err(ParserError::InvalidFileHeader),
+        }
+    }
+}
+
+/// Which fields of written CSV are wrapped in quote character.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CsvQuoting {
+    /// Every field is quoted.
+    Always,
+    /// Only fields containing delimiter, quote character, line breaks or surrounding spaces.
+    #[default]
+    Minimal,
+    /// No field is quoted, writing a field which needs quoting is an error.
+    Never,
+}
+
+/// Quoting and header of written CSV, dialects without quote character never quote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvWriteOptions {
+    /// Quoting policy of all fields.
+    pub quoting: CsvQuoting,
+    /// DESCRIPTION is quoted regardless of the policy.
+    ///
+    /// Files written without it are read with [`CsvDialect::require_quoted_description`] off.
+    pub quote_description: bool,
+    /// Header line is written, output without it is read with [`CsvDialect::has_header`] off.
+    pub header: bool,
+    /// Form of TIMESTAMP values.
+    pub timestamps: TimestampFormat,
+    /// Form of AMOUNT values.
+    pub amounts: AmountFormat,
+    /// Header names of columns written instead of standard ones, see [`CsvDialect::column_names`].
+    pub column_names: Vec<(TxFieldKey, String)>,
+}
+
+impl Default for CsvWriteOptions {
+    fn default() -> Self {
+        Self {
+            quoting: CsvQuoting::Minimal,
+            quote_description: true,
+            header: true,
+            timestamps: TimestampFormat::EpochMillis,
+            amounts: AmountFormat::MinorUnits,
+            column_names: Vec::new(),
+        }
+    }
+}
+
+// column of CSV header
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Column {
+    Field(TxFieldKey),
+    Extra(String),
+}
+
+// field value with quotes and escapes removed
+struct CsvField {
+    value: String,
+    quoted: bool,
+}
+
+impl CsvField {
+    fn unquoted(value: &str) -> Self {
+        Self {
+            value: value.to_string(),
+            quoted: false,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct CsvCodec {
+    dialect: CsvDialect,
+    write_options: CsvWriteOptions,
+    limits: ParseLimits,
+}
 impl CsvCodec {
-    fn parse_csv_line(&self, line: &str) -> Result<TxRecord, ParserError> {
-        let values: Vec<&str> = line.split(CSV_DELIMITER).map(str::trim).collect();
-        if values.len() != FIELDS_COUNT {
-            return Err(ParserError::IncompleteRecord);
-        }
-
-        Ok(TxRecord {
-            id: values[TX_ID].parse()?,
-            kind: values[TX_TYPE].parse()?,
-            from: values[FROM_USER_ID].parse()?,
-            to: values[TO_USER_ID].parse()?,
-            amount: values[AMOUNT].parse()?,
-            ts: values[TIMESTAMP].parse()?,
-            status: values[STATUS].parse()?,
-            description: unquote(values[DESCRIPTION])?.to_string(),
+    pub(crate) fn new(dialect: CsvDialect) -> Self {
+        Self {
+            dialect,
+            write_options: CsvWriteOptions::default(),
+            limits: ParseLimits::default(),
+        }
+    }
+
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub(crate) fn with_write_options(mut self, write_options: CsvWriteOptions) -> Self {
+        self.write_options = write_options;
+        self
+    }
+
+    fn writes_header(&self) -> bool {
+        self.dialect.has_header && self.write_options.header
+    }
+
+    // renamed and extra columns may need quoting, standard names never do
+    fn header(&self, fields_count: usize, extras: &[&str]) -> Result<String, AppError> {
+        let mut names = STANDARD_COLUMNS[..fields_count]
+            .iter()
+            .map(|&key| {
+                match self
+                    .write_options
+                    .column_names
+                    .iter()
+                    .find(|(renamed, _)| key == *renamed)
+                {
+                    Some((_, name)) => self.quote_header_name(name.clone()),
+                    None => Ok(key.to_string()),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for &name in extras {
+            let renamed = self
+                .write_options
+                .column_names
+                .iter()
+                .any(|(_, n)| name == n);
+            if renamed || name.parse::<TxFieldKey>().is_ok() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("extra column `{}` clashes with record field", name),
+                ))
+                .add_write_ctx();
+            }
+            names.push(self.quote_header_name(name.to_string())?);
+        }
+        Ok(names.join(&self.dialect.delimiter.to_string()))
+    }
+
+    fn quote_header_name(&self, name: String) -> Result<String, AppError> {
+        match self.dialect.quote {
+            Some(quote) if self.needs_quotes(&name, quote) => self.quote_field(name, false),
+            _ => Ok(name),
+        }
+    }
+
+    // RFC 4180 fields: quoted ones may hold delimiters, line breaks and doubled quotes,
+    // `None` if quoted field runs past the end of record
+    fn split_record(&self, record: &str) -> Result<Option<Vec<CsvField>>, ParserError> {
+        let delimiter = self.dialect.delimiter;
+        let Some(quote) = self.dialect.quote else {
+            return Ok(Some(
+                record
+                    .split(delimiter)
+                    .map(|value| CsvField::unquoted(value.trim()))
+                    .collect(),
+            ));
+        };
+        let mut fields = Vec::new();
+        let mut chars = record.chars().peekable();
+        loop {
+            while chars
+                .next_if(|&c| c != delimiter && c.is_whitespace())
+                .is_some()
+            {}
+            let mut value = String::new();
+            if chars.next_if_eq(&quote).is_some() {
+                loop {
+                    match chars.next() {
+                        None => return Ok(None),
+                        Some(c) if c == quote => match chars.next_if_eq(&quote) {
+                            Some(_) => value.push(quote),
+                            None => break,
+                        },
+                        Some(c) => value.push(c),
+                    }
+                }
+                // only spaces may follow the closing quote
+                let mut trailing = String::new();
+                while let Some(c) = chars.next_if(|&c| c != delimiter) {
+                    trailing.push(c);
+                }
+                if !trailing.trim().is_empty() {
+                    return Err(ParserError::UnparsableValue(format!(
+                        "{}{}{}{}",
+                        quote, value, quote, trailing
+                    )));
+                }
+                fields.push(CsvField {
+                    value,
+                    quoted: true,
+                });
+            } else {
+                while let Some(c) = chars.next_if(|&c| c != delimiter) {
+                    value.push(c);
+                }
+                fields.push(CsvField::unquoted(value.trim()));
+            }
+            if chars.next().is_none() {
+                return Ok(Some(fields));
+            }
+        }
+    }
+
+    // empty unquoted field is missing description, `""` is empty one
+    fn description<'a>(&self, field: &'a CsvField) -> Result<Option<&'a str>, ParserError> {
+        if field.value.is_empty() && !field.quoted {
+            return Ok(None);
+        }
+        let must_be_quoted =
+            self.dialect.quote.is_some() && self.dialect.require_quoted_description;
+        if must_be_quoted && !field.quoted {
+            return Err(ParserError::ShellBeQuoted(field.value.clone()));
+        }
+        Ok(Some(&field.value))
+    }
+
+    fn needs_quotes(&self, value: &str, quote: char) -> bool {
+        value.contains([self.dialect.delimiter, quote, '\n', '\r']) || value.trim() != value
+    }
+
+    fn quote_field(&self, value: String, is_description: bool) -> Result<String, AppError> {
+        let Some(quote) = self.dialect.quote else {
+            return Ok(value);
+        };
+        let quoted = match self.write_options.quoting {
+            _ if is_description && self.write_options.quote_description => true,
+            CsvQuoting::Always => true,
+            CsvQuoting::Minimal => self.needs_quotes(&value, quote),
+            CsvQuoting::Never if self.needs_quotes(&value, quote) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("field `{}` can't be written without quotes", value),
+                ))
+                .add_write_ctx();
+            }
+            CsvQuoting::Never => false,
+        };
+        Ok(if quoted {
+            let escaped = value.replace(quote, &format!("{}{}", quote, quote));
+            format!("{}{}{}", quote, escaped, quote)
+        } else {
+            value
         })
     }
 
-    fn write_single_record(&self, w: &mut dyn Write, tx: &TxRecord) -> Result<(), AppError> {
-        let mut values = Vec::with_capacity(FIELDS_COUNT);
+    // without header all standard layouts are accepted, record by record
+    fn parse_record(
+        &self,
+        values: &[CsvField],
+        columns: Option<&[Column]>,
+    ) -> Result<TxRecord, ParserError> {
+        match columns {
+            Some(columns) if columns.len() == values.len() => {}
+            None if (FIELDS_COUNT..=FIELDS_COUNT_WITH_EXCHANGE).contains(&values.len()) => {}
+            _ => return Err(ParserError::IncompleteRecord),
+        };
+        // header check guarantees every required column is present or has default
+        let value = |key: TxFieldKey| {
+            match columns {
+                Some(columns) => columns
+                    .iter()
+                    .position(|column| Column::Field(key) == *column),
+                None => STANDARD_COLUMNS[..values.len()]
+                    .iter()
+                    .position(|&column| key == column),
+            }
+            .map(|i| &values[i])
+        };
+        let extras = columns
+            .unwrap_or_default()
+            .iter()
+            .zip(values)
+            .filter_map(|(column, v)| match column {
+                Column::Extra(name) if !v.value.is_empty() => Some((name.clone(), v.value.clone())),
+                _ => None,
+            })
+            .collect();
+        let formats = self.dialect.formats();
+        let mut fields = RecordFields::default();
+        for spec in TxSchema::STANDARD.fields() {
+            let key = spec.key;
+            let text = match value(key) {
+                Some(v) if TxFieldKey::Description == key => self.description(v)?,
+                Some(v) => Some(v.value.as_str()),
+                None => self.dialect.default_value(key),
+            };
+            match text {
+                // empty optional values are absent ones, `""` description is an empty one
+                Some("") if !spec.required && TxFieldKey::Description != key => {}
+                Some(text) => fields.parse(key, text, &formats)?,
+                None => {}
+            }
+        }
+        fields.build(extras)
+    }
+
+    // columns may come in any order, optional ones and columns with defaults may be left out
+    fn parse_header(&self, header: &str) -> Result<Vec<Column>, ParserError> {
+        let names = self
+            .split_record(header)?
+            .ok_or(ParserError::InvalidFileHeader)?;
+        let mut columns = Vec::new();
+        for name in names {
+            let column = self.dialect.column(&name.value)?;
+            if columns.contains(&column) {
+                return Err(match column {
+                    Column::Field(key) => ParserError::Duplicate(key),
+                    Column::Extra(name) => ParserError::DuplicateExtra(name),
+                });
+            }
+            columns.push(column);
+        }
+        match TxSchema::STANDARD.required().find(|&key| {
+            !columns.contains(&Column::Field(key)) && self.dialect.default_value(key).is_none()
+        }) {
+            Some(missing) => Err(ParserError::MissingField(missing)),
+            None => Ok(columns),
+        }
+    }
+
+    fn parse_metadata(&self, header: &mut BatchHeader, line: &str) -> Result<(), ParserError> {
+        let Some((key, value)) = line
+            .trim_start()
+            .trim_start_matches(METADATA_PREFIX)
+            .split_once(':')
+        else {
+            return Ok(());
+        };
+        let value = value.trim();
+        match key.trim() {
+            METADATA_SOURCE => header.source = Some(value.to_string()),
+            METADATA_CREATED => header.created = Some(TxTimestamp::parse_rfc3339(value)?),
+            METADATA_RECORD_COUNT => header.record_count = Some(value.parse()?),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn write_metadata(&self, w: &mut dyn Write, header: &BatchHeader) -> Result<(), AppError> {
+        if let Some(source) = &header.source {
+            if source.contains(['\n', '\r']) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "batch source can't span several lines",
+                ))
+                .add_write_ctx();
+            }
+            writeln!(w, "{} {}: {}", METADATA_PREFIX, METADATA_SOURCE, source).add_write_ctx()?;
+        }
+        if let Some(created) = header.created {
+            writeln!(
+                w,
+                "{} {}: {}",
+                METADATA_PREFIX,
+                METADATA_CREATED,
+                created.to_rfc3339()
+            )
+            .add_write_ctx()?;
+        }
+        if let Some(count) = header.record_count {
+            writeln!(
+                w,
+                "{} {}: {}",
+                METADATA_PREFIX, METADATA_RECORD_COUNT, count
+            )
+            .add_write_ctx()?;
+        }
+        Ok(())
+    }
+
+    // batch header is read from metadata lines and its record count is verified
+    pub(crate) fn parse_batch<R: Read>(&self, r: R) -> Result<TxBatch, AppError> {
+        let mut reader = CsvRecordReader::new(self.clone(), r);
+        let records = collect_recovering(&mut reader, self.limits, &mut |rejected| {
+            Err(rejected.into_error())
+        })?;
+        Ok(TxBatch {
+            header: reader.batch_header,
+            records,
+        })
+    }
+
+    pub(crate) fn write_batch(
+        &self,
+        w: &mut dyn Write,
+        header: &BatchHeader,
+        data: &[TxRecord],
+    ) -> Result<(), AppError> {
+        self.write_metadata(w, header)?;
+        self.write(w, data)
+    }
+
+    fn write_single_record(
+        &self,
+        w: &mut dyn Write,
+        tx: &TxRecord,
+        fields_count: usize,
+        extras: &[&str],
+    ) -> Result<(), AppError> {
+        let mut values = Vec::with_capacity(fields_count + extras.len());
         values.push(tx.id.to_string());
         values.push(tx.kind.to_string());
         values.push(tx.from.to_string());
         values.push(tx.to.to_string());
-        values.push(tx.amount.to_string());
-        values.push(tx.ts.to_string());
+        values.push(
+            self.write_options
+                .amounts
+                .format(tx.amount)
+                .add_write_ctx()?,
+        );
+        values.push(self.write_options.timestamps.format(tx.ts));
         values.push(tx.status.to_string());
-        values.push(format!("\"{}\"", tx.description));
+        values.push(tx.description.clone().unwrap_or_default());
+        if FIELDS_COUNT_WITH_TENANT <= fields_count {
+            values.push(tx.tenant.clone().unwrap_or_default());
+        }
+        if FIELDS_COUNT_WITH_FEE <= fields_count {
+            values.push(match tx.fee {
+                Some(fee) => self.write_options.amounts.format(fee).add_write_ctx()?,
+                None => String::new(),
+            });
+        }
+        if FIELDS_COUNT_WITH_REFERENCE <= fields_count {
+            values.push(tx.reference.clone().unwrap_or_default());
+        }
+        // original amount is in other currency, it keeps its own scale
+        if FIELDS_COUNT_WITH_EXCHANGE == fields_count {
+            values.push(match tx.original_amount {
+                Some(amount) => AmountFormat::Exact.format(amount).add_write_ctx()?,
+                None => String::new(),
+            });
+            values.push(
+                tx.exchange_rate
+                    .map(|rate| rate.to_string())
+                    .unwrap_or_default(),
+            );
+        }
+        for &name in extras {
+            values.push(tx.extras.get(name).cloned().unwrap_or_default());
+        }
+        let values = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| match tx.description {
+                None if DESCRIPTION == i => Ok(value),
+                _ => self.quote_field(value, DESCRIPTION == i),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         // self-check
-        assert!(values.len() == FIELDS_COUNT);
+        assert!(values.len() == fields_count + extras.len());
 
-        writeln!(w, "{}", values.join(&CSV_DELIMITER.to_string())).add_write_ctx()
+        writeln!(w, "{}", values.join(&self.dialect.delimiter.to_string())).add_write_ctx()
     }
 }
 impl DataParser for CsvCodec {
-    fn parse<R: Read>(&self, r: R) -> Result<Vec<TxRecord>, AppError> {
-        let mut result = Vec::new();
-
-        let mut lines = BufReader::new(r).lines().enumerate();
-        // check header
-        if let Some((line_num, header_res)) = lines.next() {
-            let header = header_res.map_err(|e| AppError::ReadError(e))?;
-            if HEADER_SIGNATURE != header {
-                return Err(AppError::ParsingError {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_recovering(r, &mut |rejected| Err(rejected.into_error()))
+    }
+}
+
+impl RecoveringParser for CsvCodec {
+    fn parse_recovering<R: Read>(
+        &self,
+        r: R,
+        on_reject: &mut dyn FnMut(RejectedInput) -> Result<(), AppError>,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        collect_recovering(
+            CsvRecordReader::new(self.clone(), r),
+            self.limits,
+            on_reject,
+        )
+    }
+}
+
+impl StreamingParser for CsvCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, r: R) -> RecordStream<'a> {
+        RecordStream::new(CsvRecordReader::new(self.clone(), r))
+    }
+}
+
+// reads records line by line, header is the first non-blank line after metadata ones
+struct CsvRecordReader<R: Read> {
+    codec: CsvCodec,
+    lines: Peekable<Enumerate<Lines<BufReader<R>>>>,
+    metadata_pending: bool,
+    header_pending: bool,
+    columns: Option<Vec<Column>>,
+    batch_header: BatchHeader,
+    // RECORD_COUNT line, the count is verified at the end of input
+    count_line: Option<(usize, String)>,
+    records: u64,
+}
+
+impl<R: Read> CsvRecordReader<R> {
+    fn new(codec: CsvCodec, r: R) -> Self {
+        Self {
+            header_pending: codec.dialect.has_header,
+            codec,
+            lines: BufReader::new(r).lines().enumerate().peekable(),
+            metadata_pending: true,
+            columns: None,
+            batch_header: BatchHeader::default(),
+            count_line: None,
+            records: 0,
+        }
+    }
+
+    fn read_metadata(&mut self) -> Result<(), AppError> {
+        while let Some((line_num, line)) = self.lines.next_if(|(_, line_res)| {
+            line_res
+                .as_ref()
+                .is_ok_and(|l| l.trim().is_empty() || l.trim_start().starts_with(METADATA_PREFIX))
+        }) {
+            let line = line.map_err(AppError::ReadError)?;
+            let before = self.batch_header.record_count;
+            self.codec
+                .parse_metadata(&mut self.batch_header, &line)
+                .map_err(|source| AppError::ParsingError {
+                    context: ParserContext::with_line_number_and_line(line_num, line.clone()),
+                    source,
+                })?;
+            if before != self.batch_header.record_count {
+                self.count_line = Some((line_num, line));
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_count(&mut self) -> Result<(), AppError> {
+        match (self.batch_header.record_count, self.count_line.take()) {
+            (Some(declared), Some((line_num, line))) if declared != self.records => {
+                Err(AppError::ParsingError {
+                    context: ParserContext::with_line_number_and_line(line_num, line),
+                    source: ParserError::RecordCountMismatch {
+                        declared,
+                        actual: self.records,
+                    },
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // blank lines carry no records
+    fn next_line(&mut self) -> Option<(usize, Result<String, AppError>)> {
+        self.lines
+            .find(|(_, line_res)| line_res.as_ref().map_or(true, |l| !l.trim().is_empty()))
+            .map(|(line_num, line_res)| (line_num, line_res.map_err(AppError::ReadError)))
+    }
+
+    fn read_header(&mut self) -> Option<Result<(), AppError>> {
+        let (line_num, header_res) = self.next_line()?;
+        let header = match header_res {
+            Ok(header) => header,
+            Err(e) => return Some(Err(e)),
+        };
+        match self.codec.parse_header(&header) {
+            Ok(columns) => self.columns = Some(columns),
+            Err(source) => {
+                return Some(Err(AppError::ParsingError {
                     context: ParserContext::with_line_number_and_line(line_num, header),
-                    source: ParserError::InvalidFileHeader,
-                });
+                    source,
+                }));
             }
         }
+        Some(Ok(()))
+    }
+}
 
-        // read/parse records line by line
-        for (line_num, line_res) in lines {
-            let input_line = line_res.map_err(|e| AppError::ReadError(e))?;
-            let line = &input_line.trim();
-            result.push(
-                self.parse_csv_line(line)
-                    .map_err(|e| AppError::ParsingError {
-                        context: ParserContext::with_line_number_and_line(
-                            line_num,
-                            input_line.clone(),
-                        ),
-                        source: e,
-                    })?,
-            );
+impl<R: Read> RecordReader for CsvRecordReader<R> {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        if self.metadata_pending {
+            self.metadata_pending = false;
+            if let Err(e) = self.read_metadata() {
+                return Some(Err(e));
+            }
         }
-        Ok(result)
+        if self.header_pending {
+            self.header_pending = false;
+            match self.read_header() {
+                None => return self.verify_count().err().map(Err),
+                Some(Err(e)) => return Some(Err(e)),
+                Some(Ok(())) => {}
+            }
+        }
+
+        let Some((line_num, line_res)) = self.next_line() else {
+            return self.verify_count().err().map(Err);
+        };
+        self.records += 1;
+        let mut record = match line_res {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+        // quoted field may continue on the next lines, blank ones included
+        let parsed = loop {
+            match self.codec.split_record(&record) {
+                Ok(Some(values)) => {
+                    break self.codec.parse_record(&values, self.columns.as_deref());
+                }
+                Ok(None) => match self.lines.next() {
+                    Some((_, Ok(line))) => {
+                        record.push('\n');
+                        record.push_str(&line);
+                    }
+                    Some((_, Err(e))) => return Some(Err(AppError::ReadError(e))),
+                    None => break Err(ParserError::IncompleteRecord),
+                },
+                Err(e) => break Err(e),
+            }
+        };
+        let parsed = parsed.map_err(|e| {
+            RejectedInput::new(
+                ParserContext::with_line_number_and_line(line_num, record.clone()),
+                e,
+                record.into_bytes(),
+            )
+        });
+        Some(Ok(parsed))
     }
 }
 
 impl DataWriter for CsvCodec {
-    fn write<W: Write>(&self, w: &mut W, data: &[TxRecord]) -> Result<(), AppError> {
-        writeln!(w, "{}", HEADER_SIGNATURE).add_write_ctx()?;
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        // optional columns are emitted only when there are records with them, along with
+        // the optional columns preceding them
+        let fields_count = if data
+            .iter()
+            .any(|tx| tx.original_amount.is_some() || tx.exchange_rate.is_some())
+        {
+            FIELDS_COUNT_WITH_EXCHANGE
+        } else if data.iter().any(|tx| tx.reference.is_some()) {
+            FIELDS_COUNT_WITH_REFERENCE
+        } else if data.iter().any(|tx| tx.fee.is_some()) {
+            FIELDS_COUNT_WITH_FEE
+        } else if data.iter().any(|tx| tx.tenant.is_some()) {
+            FIELDS_COUNT_WITH_TENANT
+        } else {
+            FIELDS_COUNT
+        };
+        // extra columns need names, so headerless output has none
+        let extras: Vec<&str> = if self.writes_header() {
+            let names: BTreeSet<&str> = data
+                .iter()
+                .flat_map(|tx| tx.extras.keys().map(String::as_str))
+                .collect();
+            names.into_iter().collect()
+        } else {
+            Vec::new()
+        };
+        if self.writes_header() {
+            writeln!(w, "{}", self.header(fields_count, &extras)?).add_write_ctx()?;
+        }
         for tx in data {
-            self.write_single_record(w, tx)?;
+            self.write_single_record(w, tx, fields_count, &extras)?;
         }
         Ok(())
     }
 }
+
+impl StreamingWriter for CsvCodec {
+    // records to come are unknown, so optional columns are always emitted and extra ones
+    // never are
+    fn open_sink<'a, W: Write + 'a>(&self, mut w: W) -> Result<RecordSink<'a>, AppError> {
+        if self.writes_header() {
+            writeln!(w, "{}", self.header(FIELDS_COUNT_WITH_EXCHANGE, &[])?).add_write_ctx()?;
+        }
+        Ok(RecordSink::new(CsvRecordWriter {
+            codec: self.clone(),
+            w,
+        }))
+    }
+}
+
+struct CsvRecordWriter<W> {
+    codec: CsvCodec,
+    w: W,
+}
+
+impl<W: Write> RecordEncoder for CsvRecordWriter<W> {
+    fn push(&mut self, tx: &TxRecord) -> Result<(), AppError> {
+        self.codec
+            .write_single_record(&mut self.w, tx, FIELDS_COUNT_WITH_EXCHANGE, &[])
+    }
+
+    fn flush(&mut self) -> Result<(), AppError> {
+        self.w.flush().add_write_ctx()
+    }
+
+    fn finish(&mut self) -> Result<(), AppError> {
+        self.w.flush().add_write_ctx()
+    }
+}
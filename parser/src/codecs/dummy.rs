@@ -1,18 +1,31 @@
 use std::io::{Read, Write};
 
-use super::traits::{DataParser, DataWriter};
+use super::traits::{
+    DataParser, DataWriter, EachRecord, RecordSink, RecordStream, StreamingParser, StreamingWriter,
+};
 use crate::domain::tx::*;
 use crate::errors::AppError;
 
 #[derive(Default)]
 pub(crate) struct DummyCodec {}
 impl DataParser for DummyCodec {
-    fn parse<R: Read>(&self, _: R) -> Result<Vec<TxRecord>, AppError> {
+    fn parse(&self, _: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
         Ok(vec![])
     }
 }
+impl StreamingParser for DummyCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, mut r: R) -> RecordStream<'a> {
+        RecordStream::from_parsed(self.parse(&mut r))
+    }
+}
 impl DataWriter for DummyCodec {
-    fn write<W: Write>(&self, _: &mut W, _: &[TxRecord]) -> Result<(), AppError> {
+    fn write(&self, _: &mut dyn Write, _: &[TxRecord]) -> Result<(), AppError> {
         Ok(())
     }
 }
+
+impl StreamingWriter for DummyCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        Ok(RecordSink::new(EachRecord::new(DummyCodec::default(), w)))
+    }
+}
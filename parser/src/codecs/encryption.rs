@@ -0,0 +1,228 @@
+use std::collections::hash_map::RandomState;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Cursor, Read, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes_gcm::Aes256Gcm;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+
+use super::errors::{IoCtxBehavior, ParserContext, ParserCtxBehavior, ParserError};
+use crate::digest::Sha256;
+use crate::errors::AppError;
+
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const ENVELOPE_MAGIC: [u8; 4] = *b"YPEN";
+const ENVELOPE_VERSION: u8 = 1;
+const SALT_SIZE: usize = 16;
+// magic, version, key derivation iterations, salt and nonce
+const ENVELOPE_HEADER_SIZE: usize = 4 + 1 + 4 + SALT_SIZE + NONCE_SIZE;
+const VERSION_POSITION: usize = 4;
+const ITERATIONS_POSITION: usize = 5;
+// bounds work a crafted header can demand before its tag is checked
+const MAX_ITERATIONS: u32 = 10_000_000;
+
+/// Secret encrypted containers are sealed with, a passphrase or raw key bytes.
+///
+/// AES-256-GCM key of every container is derived from the secret and random salt of the
+/// container with PBKDF2-HMAC-SHA256, so the same secret never reuses a key and nonce pair.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey {
+    secret: Vec<u8>,
+    iterations: u32,
+}
+
+impl EncryptionKey {
+    /// Key derivation iterations of written containers unless configured.
+    pub const DEFAULT_ITERATIONS: u32 = 600_000;
+
+    /// Creates key from passphrase or raw key bytes.
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+            iterations: Self::DEFAULT_ITERATIONS,
+        }
+    }
+
+    /// Sets key derivation iterations of written containers, read ones store their own.
+    pub fn with_iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations.clamp(1, MAX_ITERATIONS);
+        self
+    }
+
+    fn cipher(&self, salt: &[u8], iterations: u32) -> Aes256Gcm {
+        let mut key = [0u8; KEY_SIZE];
+        pbkdf2::pbkdf2_hmac::<Sha256>(&self.secret, salt, iterations, &mut key);
+        Aes256Gcm::new(&key.into())
+    }
+}
+
+// secret is never printed
+impl Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("iterations", &self.iterations)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns true if input starts with encrypted container signature.
+pub fn is_encrypted(prefix: &[u8]) -> bool {
+    prefix.starts_with(&ENVELOPE_MAGIC)
+}
+
+// salt and nonce have to be unique rather than secret, hashers seeded by the operating system
+// stand in where its random device is not available
+fn random_bytes(out: &mut [u8]) {
+    if std::fs::File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(out))
+        .is_ok()
+    {
+        return;
+    }
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    for (i, chunk) in out.chunks_mut(8).enumerate() {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_usize(i);
+        hasher.write_u128(nanos);
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes()[..chunk.len()]);
+    }
+}
+
+// header is authenticated along with the content
+fn seal(key: &EncryptionKey, content: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(ENVELOPE_HEADER_SIZE + content.len() + TAG_SIZE);
+    header.extend_from_slice(&ENVELOPE_MAGIC);
+    header.push(ENVELOPE_VERSION);
+    header.extend_from_slice(&key.iterations.to_be_bytes());
+    let mut salt = [0u8; SALT_SIZE];
+    random_bytes(&mut salt);
+    header.extend_from_slice(&salt);
+    let mut nonce = [0u8; NONCE_SIZE];
+    random_bytes(&mut nonce);
+    header.extend_from_slice(&nonce);
+
+    let payload = Payload {
+        msg: content,
+        aad: &header,
+    };
+    // fails only for content over the limit of GCM, 64 GiB
+    let sealed = key
+        .cipher(&salt, key.iterations)
+        .encrypt(&nonce.into(), payload)
+        .map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "content too large for AES-256-GCM",
+            )
+        })?;
+    header.extend(sealed);
+    Ok(header)
+}
+
+fn open(key: &EncryptionKey, input: &[u8]) -> Result<Vec<u8>, AppError> {
+    if input.len() < ENVELOPE_HEADER_SIZE || !is_encrypted(input) {
+        return Err(ParserError::InvalidFileHeader).add_parser_ctx(ParserContext::with_position(0));
+    }
+    let (header, sealed) = input.split_at(ENVELOPE_HEADER_SIZE);
+    let version = header[VERSION_POSITION];
+    if ENVELOPE_VERSION != version {
+        return Err(ParserError::UnparsableValue(format!(
+            "encrypted container version {}",
+            version
+        )))
+        .add_parser_ctx(ParserContext::with_position(VERSION_POSITION));
+    }
+    let mut iterations = [0u8; 4];
+    iterations.copy_from_slice(&header[ITERATIONS_POSITION..ITERATIONS_POSITION + 4]);
+    let iterations = u32::from_be_bytes(iterations);
+    if !(1..=MAX_ITERATIONS).contains(&iterations) {
+        return Err(ParserError::UnparsableValue(format!(
+            "key derivation iterations {}",
+            iterations
+        )))
+        .add_parser_ctx(ParserContext::with_position(ITERATIONS_POSITION));
+    }
+    let salt = &header[ITERATIONS_POSITION + 4..ITERATIONS_POSITION + 4 + SALT_SIZE];
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce.copy_from_slice(&header[ENVELOPE_HEADER_SIZE - NONCE_SIZE..]);
+    let payload = Payload {
+        msg: sealed,
+        aad: header,
+    };
+    // wrong key and altered input are not told apart
+    key.cipher(salt, iterations)
+        .decrypt(&nonce.into(), payload)
+        .map_err(|_| ParserError::DecryptionFailed)
+        .add_parser_ctx(ParserContext::with_position(ENVELOPE_HEADER_SIZE))
+}
+
+/// Reader decrypting input sealed in encrypted container.
+///
+/// Input is read, authenticated and decrypted at once on construction, so nothing of altered
+/// input is ever returned; decrypted content is held in memory.
+pub struct EncryptedReader {
+    inner: Cursor<Vec<u8>>,
+}
+
+impl EncryptedReader {
+    /// Decrypts `r` with `key`, input which is not an encrypted container is rejected.
+    pub fn new<R: Read>(mut r: R, key: &EncryptionKey) -> Result<Self, AppError> {
+        let mut input = Vec::new();
+        r.read_to_end(&mut input).add_read_ctx()?;
+        Ok(Self {
+            inner: Cursor::new(open(key, &input)?),
+        })
+    }
+}
+
+impl Read for EncryptedReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+/// Writer sealing everything written to it in encrypted container.
+///
+/// Output is buffered in memory and written encrypted by [`EncryptedWriter::finish`],
+/// dropping the writer without finishing it loses the output.
+pub struct EncryptedWriter<W: Write> {
+    w: W,
+    key: EncryptionKey,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptedWriter<W> {
+    /// Creates writer encrypting output into `w` with `key`.
+    pub fn new(w: W, key: &EncryptionKey) -> Self {
+        Self {
+            w,
+            key: key.clone(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Writes encrypted container and returns underlying writer.
+    pub fn finish(mut self) -> Result<W, AppError> {
+        let sealed = seal(&self.key, &self.buffer).add_write_ctx()?;
+        self.w.write_all(&sealed).add_write_ctx()?;
+        self.w.flush().add_write_ctx()?;
+        Ok(self.w)
+    }
+}
+
+impl<W: Write> Write for EncryptedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    // nothing reaches underlying writer until the output is complete
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -13,6 +13,10 @@ pub enum ParserError {
     UnparsableValue(String),
     /// Same field was provided more than one time.
     Duplicate(TxFieldKey),
+    /// Same extra field, see [`TxRecord::extras`], was provided more than one time.
+    ///
+    /// [`TxRecord::extras`]: crate::domain::tx::TxRecord::extras
+    DuplicateExtra(String),
     /// Key-value delimiter is absent in text line.
     NoFieldDelimiter,
     /// String value expected to be wrapped in double quotes.
@@ -23,12 +27,56 @@ pub enum ParserError {
     InvalidRecordHeader(String),
     /// Record does not have all required fields.
     IncompleteRecord,
+    /// Input is empty, whitespace-only or has header only (strict mode).
+    EmptyInput,
+    /// Stored checksum does not match record content.
+    ChecksumMismatch,
+    /// Encrypted input cannot be decrypted with given key or was altered.
+    DecryptionFailed,
+    /// Footer index of binary stream is missing or malformed.
+    InvalidIndex,
+    /// Input exceeds one of [`ParseLimits`](super::options::ParseLimits).
+    LimitExceeded(String),
+    /// Number of records differs from the count declared in batch header.
+    RecordCountMismatch {
+        /// Count declared in header.
+        declared: u64,
+        /// Count of records in input.
+        actual: u64,
+    },
+    /// Records violate a validation rule of error severity (strict mode).
+    RuleViolation {
+        /// Name of the rule.
+        rule: String,
+        /// What is wrong with the record.
+        message: String,
+    },
 }
 
 impl std::error::Error for ParserError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        None
+    }
+}
+
+/// Signed input failed verification of its HMAC-SHA256 trailer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// Input does not end with signature trailer.
+    MissingTrailer,
+    /// Signature does not match content, wrong key or altered input.
+    SignatureMismatch,
+}
+
+impl std::error::Error for IntegrityError {}
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            _ => None,
+            IntegrityError::MissingTrailer => write!(f, "signature trailer is missing"),
+            IntegrityError::SignatureMismatch => {
+                write!(f, "signature mismatch, wrong key or altered input")
+            }
         }
     }
 }
@@ -61,20 +109,38 @@ pub enum ParserContext {
         /// Field key being parsed.
         field_key: TxFieldKey,
     },
+    /// Bytes skipped over while looking for the next record.
+    ByteRange {
+        /// Position of the first skipped byte.
+        start: usize,
+        /// Position right after the last skipped byte.
+        end: usize,
+    },
+    /// Number of the record among records read, starting from 1.
+    Record {
+        /// Record number.
+        number: usize,
+    },
 }
 impl ParserContext {
-    pub(super) fn with_line_number_and_line(line_num: usize, line: String) -> Self {
+    pub(crate) fn with_line_number_and_line(line_num: usize, line: String) -> Self {
         Self::LineNumAndLine { line_num, line }
     }
-    pub(super) fn with_position(position: usize) -> Self {
+    pub(crate) fn with_position(position: usize) -> Self {
         Self::Position { position }
     }
-    pub(super) fn with_position_and_field_key(position: usize, field_key: TxFieldKey) -> Self {
+    pub(crate) fn with_position_and_field_key(position: usize, field_key: TxFieldKey) -> Self {
         Self::PositionAndField {
             position,
             field_key,
         }
     }
+    pub(crate) fn with_byte_range(start: usize, end: usize) -> Self {
+        Self::ByteRange { start, end }
+    }
+    pub(crate) fn with_record(number: usize) -> Self {
+        Self::Record { number }
+    }
 }
 
 impl Display for ParserContext {
@@ -96,6 +162,12 @@ impl Display for ParserContext {
                     position, field_key
                 )
             }
+            ParserContext::ByteRange { start, end } => {
+                writeln!(f, "bytes #{}..#{}", start, end)
+            }
+            ParserContext::Record { number } => {
+                writeln!(f, "record #{}", number)
+            }
         }
     }
 }
@@ -118,6 +190,9 @@ impl Display for ParserError {
             ParserError::Duplicate(field_key) => {
                 write!(f, "field {} has duplicate", field_key)
             }
+            ParserError::DuplicateExtra(name) => {
+                write!(f, "extra field {} has duplicate", name)
+            }
             ParserError::NoFieldDelimiter => {
                 write!(f, "key-value delimiter is expected")
             }
@@ -130,25 +205,46 @@ impl Display for ParserError {
             ParserError::InvalidRecordHeader(instead) => {
                 write!(f, "invalid record header {:?}", instead)
             }
+            ParserError::EmptyInput => {
+                write!(f, "input has no records")
+            }
+            ParserError::ChecksumMismatch => {
+                write!(f, "checksum mismatch")
+            }
+            ParserError::DecryptionFailed => {
+                write!(f, "decryption failed, wrong key or altered input")
+            }
+            ParserError::InvalidIndex => {
+                write!(f, "footer index is missing or invalid")
+            }
+            ParserError::LimitExceeded(what) => {
+                write!(f, "parse limit exceeded, {}", what)
+            }
+            ParserError::RecordCountMismatch { declared, actual } => {
+                write!(f, "batch declares {} records, has {}", declared, actual)
+            }
+            ParserError::RuleViolation { rule, message } => {
+                write!(f, "rule {} is violated, {}", rule, message)
+            }
         }
     }
 }
 
-pub(super) trait IoCtxBehavior<T> {
+pub(crate) trait IoCtxBehavior<T> {
     fn add_read_ctx(self) -> Result<T, AppError>;
     fn add_write_ctx(self) -> Result<T, AppError>;
 }
 
 impl<T> IoCtxBehavior<T> for Result<T, std::io::Error> {
     fn add_read_ctx(self) -> Result<T, AppError> {
-        self.map_err(|e| AppError::ReadError(e))
+        self.map_err(AppError::ReadError)
     }
     fn add_write_ctx(self) -> Result<T, AppError> {
-        self.map_err(|e| AppError::WriteError(e))
+        self.map_err(AppError::WriteError)
     }
 }
 
-pub(super) trait ParserCtxBehavior<T> {
+pub(crate) trait ParserCtxBehavior<T> {
     fn add_parser_ctx(self, ctx: ParserContext) -> Result<T, AppError>;
 }
 
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Lines, Read, Write};
+use std::iter::Enumerate;
+
+use super::errors::{IoCtxBehavior, ParserContext, ParserError};
+use super::options::ParseLimits;
+use super::quarantine::RejectedInput;
+use super::traits::*;
+use super::utils::{parse_decimal_minor_units, parse_iso8601};
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+const MESSAGE_START: &str = "8=FIX";
+const SOH: char = '\u{1}';
+const LOG_DELIMITER: char = '|';
+const MSG_TYPE_TAG: u32 = 35;
+
+/// Mapping of FIX tags to transaction record fields.
+#[derive(Debug, Clone)]
+pub struct FixTagMapping {
+    /// Message types to convert, other messages (heartbeats, orders) are skipped.
+    pub msg_types: Vec<String>,
+    /// Numeric tag used as transaction id.
+    pub id: u32,
+    /// Own account, source for buys and destination for sells.
+    pub account: u32,
+    /// Counterparty account, optional in message.
+    pub counterparty: u32,
+    /// Side tag: `1` (buy) debits account, `2` (sell) credits it.
+    pub side: u32,
+    /// Decimal amount tag.
+    pub amount: u32,
+    /// Number of decimal places amount is scaled by into minor units.
+    pub amount_scale: u32,
+    /// UTC timestamp tag (`YYYYMMDD-HH:MM:SS[.sss]`).
+    pub timestamp: u32,
+    /// Order status tag.
+    pub status: u32,
+    /// Free text tag used as description, optional in message.
+    pub description: u32,
+    /// Counterparty reference tag, optional in message.
+    pub reference: u32,
+}
+
+impl Default for FixTagMapping {
+    fn default() -> Self {
+        Self {
+            msg_types: vec!["8".into()],
+            id: 37,
+            account: 1,
+            counterparty: 448,
+            side: 54,
+            amount: 381,
+            amount_scale: 2,
+            timestamp: 60,
+            status: 39,
+            description: 58,
+            reference: 11,
+        }
+    }
+}
+
+/// Reader of FIX tag=value execution reports, one message per line.
+///
+/// Both SOH and `|` delimited logs are accepted, text before `8=FIX` (log prefixes) is ignored.
+#[derive(Clone, Default)]
+pub(crate) struct FixCodec {
+    mapping: FixTagMapping,
+    limits: ParseLimits,
+}
+impl FixCodec {
+    pub(crate) fn new(mapping: FixTagMapping) -> Self {
+        Self {
+            mapping,
+            limits: ParseLimits::default(),
+        }
+    }
+
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn parse_tags<'a>(&self, message: &'a str) -> Result<HashMap<u32, &'a str>, ParserError> {
+        let delimiter = if message.contains(SOH) {
+            SOH
+        } else {
+            LOG_DELIMITER
+        };
+        message
+            .split(delimiter)
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(|field| {
+                let (tag, value) = field.split_once('=').ok_or(ParserError::NoFieldDelimiter)?;
+                let tag = tag
+                    .parse::<u32>()
+                    .map_err(|_| ParserError::UnparsableKey(tag.into()))?;
+                Ok((tag, value))
+            })
+            .collect()
+    }
+
+    fn parse_message(&self, tags: &HashMap<u32, &str>) -> Result<TxRecord, ParserError> {
+        let m = &self.mapping;
+        let required = |tag: u32| {
+            tags.get(&tag)
+                .copied()
+                .ok_or_else(|| ParserError::UnparsableValue(format!("tag {} is missing", tag)))
+        };
+        let optional_account = |tag: u32| -> Result<AccountType, ParserError> {
+            tags.get(&tag)
+                .map(|v| v.parse())
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+
+        let account: AccountType = required(m.account)?.parse()?;
+        let counterparty = optional_account(m.counterparty)?;
+        let (kind, from, to) = match (required(m.side)?, AccountType::default() != counterparty) {
+            ("1", true) => (TxKind::Transfer, account, counterparty),
+            ("2", true) => (TxKind::Transfer, counterparty, account),
+            ("1", false) => (TxKind::Withdrawal, account, counterparty),
+            ("2", false) => (TxKind::Deposit, counterparty, account),
+            (side, _) => return Err(ParserError::UnparsableValue(side.into())),
+        };
+
+        let status = match required(m.status)? {
+            "2" => TxStatus::Success,
+            "0" | "1" | "6" | "A" | "E" => TxStatus::Pending,
+            "4" => TxStatus::Cancelled,
+            "C" => TxStatus::Expired,
+            "8" => TxStatus::Failure,
+            other => return Err(ParserError::UnparsableValue(other.into())),
+        };
+
+        Ok(TxRecord {
+            id: required(m.id)?.parse()?,
+            kind,
+            from,
+            to,
+            amount: Money::new(
+                parse_decimal_minor_units(required(m.amount)?, m.amount_scale)?,
+                m.amount_scale as u8,
+            ),
+            fee: None,
+            original_amount: None,
+            exchange_rate: None,
+            ts: TxTimestamp::from_millis(parse_fix_timestamp(required(m.timestamp)?)?),
+            status,
+            description: tags
+                .get(&m.description)
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string()),
+            tenant: None,
+            reference: tags
+                .get(&m.reference)
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string()),
+            extras: Default::default(),
+        })
+    }
+}
+
+// FIX UTCTimestamp `YYYYMMDD-HH:MM:SS[.sss]`
+fn parse_fix_timestamp(value: &str) -> Result<u64, ParserError> {
+    let (date, time) = value
+        .split_once('-')
+        .filter(|(date, _)| 8 == date.len() && date.is_ascii())
+        .ok_or_else(|| ParserError::UnparsableValue(value.into()))?;
+    parse_iso8601(&format!(
+        "{}-{}-{}T{}Z",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        time
+    ))
+    .map_err(|_| ParserError::UnparsableValue(value.into()))
+}
+
+impl DataParser for FixCodec {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_recovering(r, &mut |rejected| Err(rejected.into_error()))
+    }
+}
+
+impl RecoveringParser for FixCodec {
+    fn parse_recovering<R: Read>(
+        &self,
+        r: R,
+        on_reject: &mut dyn FnMut(RejectedInput) -> Result<(), AppError>,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        collect_recovering(
+            FixRecordReader::new(self.clone(), r),
+            self.limits,
+            on_reject,
+        )
+    }
+}
+
+impl StreamingParser for FixCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, r: R) -> RecordStream<'a> {
+        RecordStream::new(FixRecordReader::new(self.clone(), r))
+    }
+}
+
+struct FixRecordReader<R> {
+    codec: FixCodec,
+    lines: Enumerate<Lines<BufReader<R>>>,
+}
+
+impl<R: Read> FixRecordReader<R> {
+    fn new(codec: FixCodec, r: R) -> Self {
+        Self {
+            codec,
+            lines: BufReader::new(r).lines().enumerate(),
+        }
+    }
+}
+
+impl<R: Read> RecordReader for FixRecordReader<R> {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        for (line_num, line_res) in self.lines.by_ref() {
+            let input_line = match line_res {
+                Ok(line) => line,
+                Err(e) => return Some(Err(AppError::ReadError(e))),
+            };
+            let Some(start) = input_line.find(MESSAGE_START) else {
+                continue;
+            };
+            let parsed = self
+                .codec
+                .parse_tags(&input_line[start..])
+                .and_then(|tags| {
+                    let msg_type = tags.get(&MSG_TYPE_TAG).copied().unwrap_or_default();
+                    if !self.codec.mapping.msg_types.iter().any(|t| t == msg_type) {
+                        return Ok(None);
+                    }
+                    self.codec.parse_message(&tags).map(Some)
+                });
+            match parsed {
+                Ok(None) => continue,
+                Ok(Some(tx)) => return Some(Ok(Ok(tx))),
+                Err(e) => {
+                    return Some(Ok(Err(RejectedInput::new(
+                        ParserContext::with_line_number_and_line(line_num, input_line.clone()),
+                        e,
+                        input_line.into_bytes(),
+                    ))));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl DataWriter for FixCodec {
+    fn write(&self, _: &mut dyn Write, _: &[TxRecord]) -> Result<(), AppError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "FIX format is read only",
+        ))
+        .add_write_ctx()
+    }
+}
+
+impl StreamingWriter for FixCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, _: W) -> Result<RecordSink<'a>, AppError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "FIX format is read only",
+        ))
+        .add_write_ctx()
+    }
+}
@@ -0,0 +1,127 @@
+use std::io::{Read, Write};
+
+use super::errors::IoCtxBehavior;
+use super::traits::{
+    DataParser, DataWriter, EachRecord, RecordSink, RecordStream, StreamingParser, StreamingWriter,
+};
+use super::utils::format_iso8601_date;
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+/// Ledger account of the user accounts, followed by `:<account id>`.
+pub const USER_ACCOUNT_PREFIX: &str = "Assets:Accounts";
+/// Ledger account standing for the `0` account of deposits and withdrawals.
+pub const EXTERNAL_ACCOUNT: &str = "Equity:External";
+
+/// Writer of ledger-cli journals, one entry per record.
+///
+/// Amounts are written in minor units without commodity. Failed, cancelled, reversed and expired
+/// transactions moved no funds, they are kept as commented out entries.
+#[derive(Default)]
+pub(crate) struct LedgerCodec;
+impl LedgerCodec {
+    fn entry_lines(&self, tx: &TxRecord) -> Vec<String> {
+        let mark = match tx.status {
+            TxStatus::Success => "* ",
+            TxStatus::Pending => "! ",
+            TxStatus::Failure | TxStatus::Cancelled | TxStatus::Reversed | TxStatus::Expired => "",
+        };
+        let mut lines = vec![format!(
+            "{} {}({}) {}",
+            format_iso8601_date(tx.ts.millis()),
+            mark,
+            tx.id,
+            payee(tx)
+        )];
+        if let Some(tenant) = &tx.tenant {
+            lines.push(format!("    ; tenant: {}", tenant));
+        }
+        if let Some(fee) = tx.fee {
+            lines.push(format!("    ; fee: {}", fee.minor_units));
+        }
+        if let Some(original_amount) = tx.original_amount {
+            lines.push(format!("    ; original amount: {}", original_amount));
+        }
+        if let Some(exchange_rate) = tx.exchange_rate {
+            lines.push(format!("    ; exchange rate: {}", exchange_rate));
+        }
+        if let Some(reference) = &tx.reference {
+            lines.push(format!("    ; reference: {}", reference));
+        }
+        lines.push(format!(
+            "    {}  {}",
+            account_name(tx.destination()),
+            tx.amount.minor_units
+        ));
+        lines.push(format!(
+            "    {}  {}",
+            account_name(tx.source()),
+            -i128::from(tx.amount.minor_units)
+        ));
+        lines
+    }
+}
+
+impl DataParser for LedgerCodec {
+    fn parse(&self, _: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "ledger format is write only",
+        ))
+        .add_read_ctx()
+    }
+}
+
+impl StreamingParser for LedgerCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, mut r: R) -> RecordStream<'a> {
+        RecordStream::from_parsed(self.parse(&mut r))
+    }
+}
+
+impl DataWriter for LedgerCodec {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        for (i, tx) in data.iter().enumerate() {
+            if i > 0 {
+                writeln!(w).add_write_ctx()?;
+            }
+            let prefix = match tx.status {
+                TxStatus::Success | TxStatus::Pending => "",
+                _ => "; ",
+            };
+            for line in self.entry_lines(tx) {
+                writeln!(w, "{}{}", prefix, line).add_write_ctx()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StreamingWriter for LedgerCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        Ok(RecordSink::new(
+            EachRecord::new(LedgerCodec, w).separated_by("\n"),
+        ))
+    }
+}
+
+// outside side of deposits and withdrawals is external whatever account it has
+fn account_name(account: Option<&AccountType>) -> String {
+    match account {
+        None | Some(AccountType::Numeric(0)) => EXTERNAL_ACCOUNT.to_string(),
+        Some(account) => format!("{}:{}", USER_ACCOUNT_PREFIX, account),
+    }
+}
+
+// payee runs till the end of line, missing or empty one falls back to transaction type
+fn payee(tx: &TxRecord) -> String {
+    let payee = tx
+        .description
+        .as_deref()
+        .unwrap_or_default()
+        .replace("\r\n", " ")
+        .replace(['\n', '\r'], " ");
+    match payee.trim() {
+        "" => tx.kind.to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
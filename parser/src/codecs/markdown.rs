@@ -0,0 +1,177 @@
+use std::io::{Read, Write};
+
+use super::base::TxFieldKey;
+use super::errors::IoCtxBehavior;
+use super::traits::{
+    DataParser, DataWriter, RecordEncoder, RecordSink, RecordStream, StreamingParser,
+    StreamingWriter,
+};
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+const COLUMNS: [(TxFieldKey, Alignment); 8] = [
+    (TxFieldKey::Id, Alignment::Right),
+    (TxFieldKey::TxKind, Alignment::Left),
+    (TxFieldKey::FromUserId, Alignment::Right),
+    (TxFieldKey::ToUserId, Alignment::Right),
+    (TxFieldKey::Amount, Alignment::Right),
+    (TxFieldKey::Timestamp, Alignment::Right),
+    (TxFieldKey::Status, Alignment::Left),
+    (TxFieldKey::Description, Alignment::Left),
+];
+const OPTIONAL_COLUMNS: [(TxFieldKey, Alignment); 5] = [
+    (TxFieldKey::Tenant, Alignment::Left),
+    (TxFieldKey::Fee, Alignment::Right),
+    (TxFieldKey::Reference, Alignment::Left),
+    (TxFieldKey::OriginalAmount, Alignment::Right),
+    (TxFieldKey::ExchangeRate, Alignment::Right),
+];
+
+#[derive(Clone, Copy)]
+enum Alignment {
+    Left,
+    Right,
+}
+
+/// Writer of GitHub-flavored markdown tables, one row per record.
+#[derive(Default)]
+pub(crate) struct MarkdownCodec;
+impl MarkdownCodec {
+    fn write_row(&self, w: &mut dyn Write, cells: &[String]) -> Result<(), AppError> {
+        writeln!(w, "| {} |", cells.join(" | ")).add_write_ctx()
+    }
+
+    fn columns(&self, present: impl Fn(TxFieldKey) -> bool) -> Vec<(TxFieldKey, Alignment)> {
+        let mut columns = COLUMNS.to_vec();
+        columns.extend(OPTIONAL_COLUMNS.iter().filter(|(key, _)| present(*key)));
+        columns
+    }
+
+    fn write_header(
+        &self,
+        w: &mut dyn Write,
+        columns: &[(TxFieldKey, Alignment)],
+    ) -> Result<(), AppError> {
+        let header: Vec<String> = columns.iter().map(|(key, _)| key.to_string()).collect();
+        self.write_row(w, &header)?;
+        let separator: Vec<String> = columns
+            .iter()
+            .map(|(_, alignment)| match alignment {
+                Alignment::Left => "---".to_string(),
+                Alignment::Right => "---:".to_string(),
+            })
+            .collect();
+        self.write_row(w, &separator)
+    }
+
+    fn write_record(
+        &self,
+        w: &mut dyn Write,
+        columns: &[(TxFieldKey, Alignment)],
+        tx: &TxRecord,
+    ) -> Result<(), AppError> {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|(key, _)| match key {
+                TxFieldKey::Id => tx.id.to_string(),
+                TxFieldKey::TxKind => tx.kind.to_string(),
+                TxFieldKey::FromUserId => tx.from.to_string(),
+                TxFieldKey::ToUserId => tx.to.to_string(),
+                TxFieldKey::Amount => tx.amount.minor_units.to_string(),
+                TxFieldKey::Timestamp => tx.ts.to_string(),
+                TxFieldKey::Status => tx.status.to_string(),
+                TxFieldKey::Description => {
+                    escape_cell(tx.description.as_deref().unwrap_or_default())
+                }
+                TxFieldKey::Tenant => escape_cell(tx.tenant.as_deref().unwrap_or_default()),
+                TxFieldKey::Fee => tx
+                    .fee
+                    .map(|fee| fee.minor_units.to_string())
+                    .unwrap_or_default(),
+                TxFieldKey::Reference => escape_cell(tx.reference.as_deref().unwrap_or_default()),
+                // original amount is in other currency, so it is a decimal of its own scale
+                TxFieldKey::OriginalAmount => tx
+                    .original_amount
+                    .map(|amount| amount.to_string())
+                    .unwrap_or_default(),
+                TxFieldKey::ExchangeRate => tx
+                    .exchange_rate
+                    .map(|rate| rate.to_string())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        self.write_row(w, &cells)
+    }
+}
+
+impl DataParser for MarkdownCodec {
+    fn parse(&self, _: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "markdown format is write only",
+        ))
+        .add_read_ctx()
+    }
+}
+
+impl StreamingParser for MarkdownCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, mut r: R) -> RecordStream<'a> {
+        RecordStream::from_parsed(self.parse(&mut r))
+    }
+}
+
+impl DataWriter for MarkdownCodec {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        // optional columns are emitted only when there are records with them
+        let columns = self.columns(|key| {
+            data.iter().any(|tx| match key {
+                TxFieldKey::Tenant => tx.tenant.is_some(),
+                TxFieldKey::Fee => tx.fee.is_some(),
+                TxFieldKey::Reference => tx.reference.is_some(),
+                TxFieldKey::OriginalAmount => tx.original_amount.is_some(),
+                _ => tx.exchange_rate.is_some(),
+            })
+        });
+        self.write_header(w, &columns)?;
+        for tx in data {
+            self.write_record(w, &columns, tx)?;
+        }
+        Ok(())
+    }
+}
+
+impl StreamingWriter for MarkdownCodec {
+    // records to come are unknown, so optional columns are always emitted
+    fn open_sink<'a, W: Write + 'a>(&self, mut w: W) -> Result<RecordSink<'a>, AppError> {
+        let columns = self.columns(|_| true);
+        self.write_header(&mut w, &columns)?;
+        Ok(RecordSink::new(MarkdownRowWriter { columns, w }))
+    }
+}
+
+struct MarkdownRowWriter<W> {
+    columns: Vec<(TxFieldKey, Alignment)>,
+    w: W,
+}
+
+impl<W: Write> RecordEncoder for MarkdownRowWriter<W> {
+    fn push(&mut self, tx: &TxRecord) -> Result<(), AppError> {
+        MarkdownCodec.write_record(&mut self.w, &self.columns, tx)
+    }
+
+    fn flush(&mut self) -> Result<(), AppError> {
+        self.w.flush().add_write_ctx()
+    }
+
+    fn finish(&mut self) -> Result<(), AppError> {
+        self.w.flush().add_write_ctx()
+    }
+}
+
+// pipes would split the cell and line breaks the row
+fn escape_cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
@@ -1,16 +1,59 @@
+/// BAI2 cash management file reader.
+pub mod bai2;
 /// Shared format enums and field mapping utilities.
 pub mod base;
 /// Binary format codec implementation.
 pub mod binary;
+/// Delta-encoded binary format (YPB2) codec implementation.
+pub mod binary_v2;
+/// Compact dumps in bincode layout.
+#[cfg(feature = "bincode")]
+pub mod bincode;
+/// ISO 20022 camt.053 statement reader.
+pub mod camt;
+/// Cap'n Proto codec with bundled schema.
+#[cfg(feature = "capnp")]
+pub mod capnp;
+/// Column-wise binary format (YPBN-C) codec implementation.
+pub mod columnar;
+/// Transparent compression of codec input and output.
+pub mod compression;
 /// CSV format codec implementation.
 pub mod csv;
 /// Stub codec used for testing and wiring.
 pub mod dummy;
+/// Encrypted container (AES-256-GCM) around codec output.
+#[cfg(feature = "encryption")]
+pub mod encryption;
 /// Parsing and IO helper error types.
 pub mod errors;
+/// FIX protocol execution report reader.
+pub mod fix;
+/// ledger-cli journal writer.
+pub mod ledger;
+/// Markdown table writer.
+pub mod markdown;
+/// Parsing and writing options shared by codecs.
+pub mod options;
+/// Quarantine of inputs rejected by parsers.
+pub mod quarantine;
+/// Human-readable report writer.
+pub mod report;
+/// Record schema driving generic field parsing.
+pub mod schema;
+/// HMAC-SHA256 signed output with verification on parse.
+pub mod signing;
 /// Text format codec implementation.
 pub mod text;
 /// Generic parse/write traits for codecs.
 pub mod traits;
 /// Internal helper functions used by codecs.
-mod utils;
+pub(crate) mod utils;
+/// Excel workbook codec.
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+/// Minimal XML reader used by XML-based codecs.
+mod xml;
+/// Zip container used by Office Open XML codecs.
+#[cfg(feature = "xlsx")]
+mod zip;
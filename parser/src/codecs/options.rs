@@ -0,0 +1,572 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, Read};
+use std::num::NonZeroUsize;
+
+use super::binary::{BinaryParseOptions, BinaryWriteOptions};
+use super::binary_v2::BinaryV2Options;
+use super::compression::CompressionOptions;
+use super::csv::{CsvDialect, CsvWriteOptions};
+use super::errors::{ParserContext, ParserError};
+use super::fix::FixTagMapping;
+use super::report::ReportOptions;
+use super::text::{TextDialect, TextWriteOptions};
+use super::utils::{
+    Decoded, LineLimited, format_iso8601, parse_decimal_minor_units, parse_iso8601,
+};
+use crate::domain::tx::{Money, TxRecord, TxTimestamp, UtcOffset, ZonedTimestamp};
+use crate::errors::AppError;
+use crate::validation::Validator;
+
+/// How strictly input streams are validated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Tolerate cosmetic issues; empty, whitespace-only and header-only inputs give no records.
+    #[default]
+    Lenient,
+    /// Reject anything unexpected; input without records is an [`EmptyInput`] error.
+    ///
+    /// Non-whitespace bytes after the last record are an error, binary input included,
+    /// where partial record signature is an [`IncompleteRecord`] error. Records violating
+    /// [`ParseOptions::validation`] rules of error severity are a [`RuleViolation`] error.
+    ///
+    /// [`RuleViolation`]: super::errors::ParserError::RuleViolation
+    /// [`EmptyInput`]: super::errors::ParserError::EmptyInput
+    /// [`IncompleteRecord`]: super::errors::ParserError::IncompleteRecord
+    Strict,
+}
+
+/// Character encoding of text and CSV input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// UTF-8, leading byte order mark is skipped.
+    #[default]
+    Utf8,
+    /// UTF-8 or UTF-16 of either byte order as its byte order mark says, UTF-8 without one.
+    Detect,
+    /// UTF-16 little endian, transcoded to UTF-8; leading byte order mark is skipped.
+    Utf16Le,
+    /// UTF-16 big endian, transcoded to UTF-8; leading byte order mark is skipped.
+    Utf16Be,
+}
+
+/// Size of buffer put between codec and underlying stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BufferSize {
+    /// Size suitable for streams of unknown kind, see [`BufferSize::adaptive_for`] for files.
+    #[default]
+    Adaptive,
+    /// Exact size in bytes.
+    Fixed(usize),
+}
+
+impl BufferSize {
+    /// Buffer size for regular files, sequential reads benefit from large chunks.
+    pub const FILE_BUFFER_SIZE: usize = 256 * 1024;
+    /// Buffer size for pipes, sockets and other streams, matches default pipe capacity.
+    pub const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+    /// Picks buffer size by kind of opened file: regular files get larger buffers than pipes.
+    pub fn adaptive_for(file: &File) -> Self {
+        match file.metadata() {
+            Ok(metadata) if metadata.is_file() => BufferSize::Fixed(Self::FILE_BUFFER_SIZE),
+            _ => BufferSize::Fixed(Self::STREAM_BUFFER_SIZE),
+        }
+    }
+
+    /// Buffer size in bytes, never zero.
+    pub fn bytes(&self) -> usize {
+        match self {
+            BufferSize::Adaptive => Self::STREAM_BUFFER_SIZE,
+            BufferSize::Fixed(size) => (*size).max(1),
+        }
+    }
+}
+
+/// Order records are written in, sorting is stable so records of equal keys keep input order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecordOrder {
+    /// Order of the input.
+    #[default]
+    Input,
+    /// Ascending ids.
+    ById,
+    /// Ascending timestamps, records of the same timestamp by ascending ids.
+    ByTimestamp,
+}
+
+impl RecordOrder {
+    pub(crate) fn sorted<'a>(&self, data: &'a [TxRecord]) -> Cow<'a, [TxRecord]> {
+        if RecordOrder::Input == *self {
+            return Cow::Borrowed(data);
+        }
+        let mut sorted = data.to_vec();
+        match self {
+            RecordOrder::Input => {}
+            RecordOrder::ById => sorted.sort_by_key(|tx| tx.id),
+            RecordOrder::ByTimestamp => sorted.sort_by_key(|tx| (tx.ts.0, tx.id)),
+        }
+        Cow::Owned(sorted)
+    }
+}
+
+/// Line break at the end of textual output, binary outputs are never changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailingNewline {
+    /// Output ends the way codec writes it, with a line break after the last record.
+    #[default]
+    Keep,
+    /// Line breaks at the end of output are dropped.
+    Trim,
+}
+
+/// When sinks flush output, e.g. for readers tailing it over a pipe.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SinkFlush {
+    /// Output is flushed when write buffer fills up and by [`RecordSink::finish`].
+    ///
+    /// [`RecordSink::finish`]: super::traits::RecordSink::finish
+    #[default]
+    OnFinish,
+    /// Output is flushed after every pushed record.
+    EachRecord,
+    /// Output is flushed after every n pushed records.
+    Every(NonZeroUsize),
+}
+
+/// Form of TIMESTAMP values in text and CSV records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// Milliseconds since Unix epoch, e.g. `1700000000000`.
+    #[default]
+    EpochMillis,
+    /// RFC 3339 date-time, written in UTC as `2023-11-14T22:13:20.000Z`.
+    ///
+    /// Input may carry any UTC offset; epoch milliseconds are still accepted.
+    Rfc3339,
+    /// RFC 3339 date-time in local time at the offset, written as `2023-11-14T23:13:20.000+01:00`.
+    ///
+    /// Input without offset is read as local time at it, other offsets and epoch
+    /// milliseconds are still accepted.
+    Local(UtcOffset),
+}
+
+impl TimestampFormat {
+    pub(crate) fn format(self, ts: TxTimestamp) -> String {
+        match self {
+            TimestampFormat::EpochMillis => ts.to_string(),
+            TimestampFormat::Rfc3339 => format_iso8601(ts.millis()),
+            TimestampFormat::Local(offset) => ts.at_offset(offset).to_rfc3339(),
+        }
+    }
+
+    pub(crate) fn parse(self, value: &str) -> Result<TxTimestamp, ParserError> {
+        match self {
+            TimestampFormat::Rfc3339 if !value.bytes().all(|b| b.is_ascii_digit()) => {
+                parse_iso8601(value).map(TxTimestamp::from_millis)
+            }
+            TimestampFormat::Local(offset) if !value.bytes().all(|b| b.is_ascii_digit()) => {
+                ZonedTimestamp::parse_rfc3339(value, offset).map(|zoned| zoned.ts)
+            }
+            _ => value.parse(),
+        }
+    }
+}
+
+/// Form of AMOUNT values in text and CSV records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmountFormat {
+    /// Integer minor units, e.g. `1234`; read amounts have [`Money::DEFAULT_SCALE`].
+    ///
+    /// Written amounts are rescaled to it, writing fails if digits would be lost.
+    #[default]
+    MinorUnits,
+    /// Decimal with `scale` fraction digits, e.g. `12.34` for scale 2.
+    ///
+    /// Input may omit fraction or have fewer digits; more digits than `scale` is an error,
+    /// so values are always read back exactly. Written amounts are rescaled, writing fails
+    /// if digits would be lost.
+    Decimal {
+        /// Number of fraction digits, 2 for cents.
+        scale: u8,
+    },
+    /// Decimal with every amount's own scale, e.g. `500` for JPY and `1.234` for BHD;
+    /// scale of read amounts is their number of fraction digits.
+    Exact,
+}
+
+impl AmountFormat {
+    pub(crate) fn format(self, amount: Money) -> io::Result<String> {
+        match self {
+            AmountFormat::MinorUnits => amount
+                .rescale(Money::DEFAULT_SCALE)
+                .map(|amount| amount.minor_units.to_string())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("amount {} can't be written in minor units", amount),
+                    )
+                }),
+            AmountFormat::Decimal { scale } => amount
+                .rescale(scale)
+                .map(|amount| amount.to_string())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "amount {} can't be written with {} fraction digits",
+                            amount, scale
+                        ),
+                    )
+                }),
+            AmountFormat::Exact => Ok(amount.to_string()),
+        }
+    }
+
+    pub(crate) fn parse(self, value: &str) -> Result<Money, ParserError> {
+        match self {
+            AmountFormat::MinorUnits => Ok(Money::from_minor_units(value.parse()?)),
+            AmountFormat::Decimal { scale } => Ok(Money::new(
+                parse_decimal_minor_units(value, u32::from(scale))?,
+                scale,
+            )),
+            AmountFormat::Exact => value.parse(),
+        }
+    }
+}
+
+/// Handling of descriptions over [`DescriptionLimit::max_chars`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverlongDescription {
+    /// Cut description to the limit, its end replaced with [`DescriptionLimit::ELLIPSIS`].
+    #[default]
+    Truncate,
+    /// Leave the record out of output.
+    Skip,
+    /// Fail writing, nothing is written by batch writes.
+    Fail,
+}
+
+/// Length limit of written descriptions, counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DescriptionLimit {
+    /// Longest description written.
+    pub max_chars: usize,
+    /// What happens to longer descriptions.
+    pub overlong: OverlongDescription,
+}
+
+impl DescriptionLimit {
+    /// Ends truncated descriptions, counted in the limit.
+    pub const ELLIPSIS: &str = "...";
+
+    /// Creates limit truncating longer descriptions.
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            max_chars,
+            overlong: OverlongDescription::default(),
+        }
+    }
+
+    /// True if description of `tx` is over the limit.
+    pub fn exceeds(&self, tx: &TxRecord) -> bool {
+        Self::chars(tx) > self.max_chars
+    }
+
+    fn chars(tx: &TxRecord) -> usize {
+        tx.description
+            .as_deref()
+            .map_or(0, |description| description.chars().count())
+    }
+
+    // record as it is written, `None` if it is left out; `number` counts records from 1
+    pub(crate) fn apply<'a>(
+        &self,
+        tx: &'a TxRecord,
+        number: usize,
+    ) -> Result<Option<Cow<'a, TxRecord>>, AppError> {
+        if !self.exceeds(tx) {
+            return Ok(Some(Cow::Borrowed(tx)));
+        }
+        match self.overlong {
+            OverlongDescription::Truncate => {
+                // ellipsis is dropped when it does not fit itself
+                let ellipsis = Some(Self::ELLIPSIS).filter(|e| e.len() <= self.max_chars);
+                let kept = self.max_chars - ellipsis.map_or(0, str::len);
+                let mut truncated = tx.clone();
+                let description = tx.description.as_deref().unwrap_or_default();
+                truncated.description = Some(
+                    description
+                        .chars()
+                        .take(kept)
+                        .chain(ellipsis.unwrap_or_default().chars())
+                        .collect(),
+                );
+                Ok(Some(Cow::Owned(truncated)))
+            }
+            OverlongDescription::Skip => Ok(None),
+            OverlongDescription::Fail => Err(AppError::WriteError(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "description of record #{} (id {}) is {} characters long, at most {} allowed",
+                    number,
+                    tx.id,
+                    Self::chars(tx),
+                    self.max_chars
+                ),
+            ))),
+        }
+    }
+}
+
+/// Bounds of input accepted by parsers, guarding memory against garbage and hostile streams.
+///
+/// Exceeding a limit is a [`LimitExceeded`] error which aborts lenient and quarantined parsing
+/// as well; binary input with resynchronization steps over records of garbage size instead.
+/// Sizes are checked while reading, records count and descriptions on every record as soon as
+/// the codec has decoded it.
+///
+/// [`LimitExceeded`]: super::errors::ParserError::LimitExceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Largest record in bytes: body of binary record, string of binary v2 and bincode record,
+    /// line of CSV, TSV, text, FIX and BAI2 input, BAI2 record joined with its continuations.
+    ///
+    /// Checked before anything is allocated for the record.
+    pub max_record_size: usize,
+    /// Largest part of input decoded at once: columnar block, Cap'n Proto segment, whole
+    /// camt.053 document, BAI2 file and xlsx workbook along with every inflated entry of it,
+    /// whole decompressed input.
+    ///
+    /// Checked before anything is allocated for the block where its size is declared.
+    pub max_block_size: usize,
+    /// Longest description in bytes.
+    pub max_description_len: usize,
+    /// Most records read from input.
+    pub max_records: usize,
+}
+
+impl ParseLimits {
+    /// Default record size limit, far above any real record.
+    pub const DEFAULT_MAX_RECORD_SIZE: usize = 16 * 1024 * 1024;
+    /// Default block size limit, far above blocks and documents written by this crate.
+    pub const DEFAULT_MAX_BLOCK_SIZE: usize = 1024 * 1024 * 1024;
+    /// Default description length limit.
+    pub const DEFAULT_MAX_DESCRIPTION_LEN: usize = 1024 * 1024;
+
+    /// No limits at all, for trusted input only.
+    pub fn unlimited() -> Self {
+        Self {
+            max_record_size: usize::MAX,
+            max_block_size: usize::MAX,
+            max_description_len: usize::MAX,
+            max_records: usize::MAX,
+        }
+    }
+
+    // `index` is position of `tx` among records read
+    pub(crate) fn check_record(&self, tx: &TxRecord, index: usize) -> Result<(), AppError> {
+        let error = if index >= self.max_records {
+            format!("more than {} records", self.max_records)
+        } else if tx.description.as_deref().map_or(0, str::len) > self.max_description_len {
+            format!(
+                "description of {} bytes, at most {} allowed",
+                tx.description.as_deref().map_or(0, str::len),
+                self.max_description_len
+            )
+        } else {
+            return Ok(());
+        };
+        Err(AppError::ParsingError {
+            context: ParserContext::with_record(index + 1),
+            source: ParserError::LimitExceeded(error),
+        })
+    }
+
+    // size field of `what` read from input is rejected before anything is allocated for it
+    pub(crate) fn check_size(what: &str, size: u64, max: usize) -> Result<(), ParserError> {
+        if size > max as u64 {
+            return Err(ParserError::LimitExceeded(format!(
+                "{} of {} bytes, at most {} allowed",
+                what, size, max
+            )));
+        }
+        Ok(())
+    }
+}
+
+// records count is not limited by default, records are streamed through or held anyway
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_record_size: Self::DEFAULT_MAX_RECORD_SIZE,
+            max_block_size: Self::DEFAULT_MAX_BLOCK_SIZE,
+            max_description_len: Self::DEFAULT_MAX_DESCRIPTION_LEN,
+            max_records: usize::MAX,
+        }
+    }
+}
+
+/// Options controlling how input streams are parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Validation strictness.
+    pub strictness: Strictness,
+    /// Delimiter, quoting and header layout of CSV input.
+    pub csv: CsvDialect,
+    /// Key names accepted in text input.
+    pub text: TextDialect,
+    /// Character encoding of text and CSV input.
+    pub encoding: TextEncoding,
+    /// FIX tags to record fields mapping.
+    pub fix: FixTagMapping,
+    /// Recovery of binary input.
+    pub binary: BinaryParseOptions,
+    /// Read buffer size.
+    pub buffer_size: BufferSize,
+    /// Dictionary of compressed input.
+    pub compression: CompressionOptions,
+    /// Bounds of accepted input.
+    pub limits: ParseLimits,
+    /// Rules records are validated against in strict mode, none by default.
+    ///
+    /// Enforced once whole input is parsed. Strict record streams enforce record rules on every
+    /// record as it is parsed, dataset rules need the whole input and are not enforced there.
+    pub validation: Validator,
+}
+
+impl ParseOptions {
+    /// Returns true for [`Strictness::Strict`].
+    pub fn is_strict(&self) -> bool {
+        Strictness::Strict == self.strictness
+    }
+
+    // missing columns are never defaulted in strict mode
+    pub(crate) fn csv_dialect(&self) -> CsvDialect {
+        let mut dialect = self.csv.clone();
+        if self.is_strict() {
+            dialect.defaults.clear();
+        }
+        dialect
+    }
+
+    // text and CSV input decoded to UTF-8, lines bounded by record size limit
+    pub(crate) fn text_input<R: Read>(&self, r: R) -> LineLimited<Decoded<R>> {
+        LineLimited::new(Decoded::new(r, self.encoding), self.limits.max_record_size)
+    }
+
+    // TSV layout is fixed, timestamps, amounts and column names follow CSV ones
+    pub(crate) fn tsv_dialect(&self) -> CsvDialect {
+        CsvDialect {
+            timestamps: self.csv.timestamps,
+            amounts: self.csv.amounts,
+            column_names: self.csv.column_names.clone(),
+            ..CsvDialect::tsv()
+        }
+    }
+}
+
+/// Options controlling how records are written.
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Write buffer size, output is flushed once all records are written.
+    pub buffer_size: BufferSize,
+    /// Layout version of binary output.
+    pub binary: BinaryWriteOptions,
+    /// Layout of binary v2 output.
+    pub binary_v2: BinaryV2Options,
+    /// Quoting and header of CSV and TSV output.
+    pub csv: CsvWriteOptions,
+    /// Form of text output values.
+    pub text: TextWriteOptions,
+    /// Account names of report output.
+    pub report: ReportOptions,
+    /// Level and dictionary of compressed output.
+    pub compression: CompressionOptions,
+    /// Order of written records, sinks write records as they come.
+    pub order: RecordOrder,
+    /// Line break at the end of textual output.
+    pub trailing_newline: TrailingNewline,
+    /// Normalize records before writing, see [`WriteOptions::canonical`]; `order` is ignored.
+    pub canonical: bool,
+    /// Length limit of descriptions, applied after normalization.
+    pub description_limit: Option<DescriptionLimit>,
+    /// Flushing of sink output, batch writing flushes once all records are written.
+    pub flush: SinkFlush,
+}
+
+impl WriteOptions {
+    /// Options of canonical output: records sorted by id, descriptions, tenants and references
+    /// trimmed.
+    ///
+    /// Output of the same records is byte-identical however they were ordered or padded,
+    /// so regenerated files can be compared with plain `diff`. Sinks do not normalize.
+    pub fn canonical() -> Self {
+        Self {
+            canonical: true,
+            ..Default::default()
+        }
+    }
+
+    // records in the form they are written
+    pub(crate) fn records<'a>(
+        &self,
+        data: &'a [TxRecord],
+    ) -> Result<Cow<'a, [TxRecord]>, AppError> {
+        let records = self.normalized(data);
+        let Some(limit) = self.description_limit else {
+            return Ok(records);
+        };
+        if !records.iter().any(|tx| limit.exceeds(tx)) {
+            return Ok(records);
+        }
+        let mut limited = Vec::with_capacity(records.len());
+        for (i, tx) in records.iter().enumerate() {
+            if let Some(tx) = limit.apply(tx, i + 1)? {
+                limited.push(tx.into_owned());
+            }
+        }
+        Ok(Cow::Owned(limited))
+    }
+
+    fn normalized<'a>(&self, data: &'a [TxRecord]) -> Cow<'a, [TxRecord]> {
+        if !self.canonical {
+            return self.order.sorted(data);
+        }
+        let mut records = data.to_vec();
+        for tx in &mut records {
+            tx.description = tx
+                .description
+                .as_ref()
+                .map(|description| description.trim().to_string());
+            tx.tenant = tx.tenant.as_ref().map(|tenant| tenant.trim().to_string());
+            tx.reference = tx
+                .reference
+                .as_ref()
+                .map(|reference| reference.trim().to_string());
+        }
+        // records of the same id are ordered by the rest of their fields
+        records.sort_by(|a, b| {
+            let key = |tx: &TxRecord| {
+                (
+                    tx.id,
+                    tx.ts.0,
+                    tx.from.clone(),
+                    tx.to.clone(),
+                    tx.amount,
+                    tx.kind.to_string(),
+                    tx.status.to_string(),
+                )
+            };
+            key(a)
+                .cmp(&key(b))
+                .then_with(|| a.description.cmp(&b.description))
+                .then_with(|| a.tenant.cmp(&b.tenant))
+                .then_with(|| a.fee.cmp(&b.fee))
+                .then_with(|| a.reference.cmp(&b.reference))
+                .then_with(|| a.original_amount.cmp(&b.original_amount))
+                .then_with(|| a.exchange_rate.cmp(&b.exchange_rate))
+        });
+        Cow::Owned(records)
+    }
+}
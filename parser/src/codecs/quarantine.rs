@@ -0,0 +1,188 @@
+use std::io::{BufRead, BufReader, Read, Write};
+
+use super::errors::{IoCtxBehavior, ParserContext, ParserCtxBehavior, ParserError};
+use crate::domain::tx::TxRecord;
+use crate::errors::AppError;
+
+// Quarantine file is a sequence of entries separated by blank lines:
+//
+//   # REJECTED
+//   ERROR: value X can't be parsed
+//   CONTEXT: line #3, content: `...`
+//   > original input line
+//   > another original input line
+//
+// Raw inputs that are not printable text (binary records) are stored as a single `HEX:` line.
+const ENTRY_MARKER: &str = "# REJECTED";
+const ERROR_KEY: &str = "ERROR: ";
+const CONTEXT_KEY: &str = "CONTEXT: ";
+const RAW_TEXT_PREFIX: &str = ">";
+const RAW_HEX_KEY: &str = "HEX: ";
+
+/// Input rejected by parser together with the reason.
+#[derive(Debug)]
+pub struct RejectedInput {
+    /// Where the input was found.
+    pub context: ParserContext,
+    /// Why the input was rejected.
+    pub error: ParserError,
+    /// Original bytes of the rejected line, record block or binary record.
+    pub raw: Vec<u8>,
+}
+
+impl RejectedInput {
+    pub(crate) fn new(context: ParserContext, error: ParserError, raw: Vec<u8>) -> Self {
+        Self {
+            context,
+            error,
+            raw,
+        }
+    }
+
+    /// Converts rejection into the error regular parsing would fail with.
+    pub fn into_error(self) -> AppError {
+        AppError::ParsingError {
+            context: self.context,
+            source: self.error,
+        }
+    }
+}
+
+/// Records parsed in lenient mode, see [`Codec::parse_lenient`].
+///
+/// [`Codec::parse_lenient`]: super::base::Codec::parse_lenient
+#[derive(Debug, Default)]
+pub struct LenientParse {
+    /// Successfully parsed records, in input order.
+    pub records: Vec<TxRecord>,
+    /// Where and why every skipped record was rejected, in input order.
+    pub errors: Vec<(ParserContext, ParserError)>,
+}
+
+/// Writes rejected inputs to quarantine stream so they can be repaired and re-submitted.
+pub struct QuarantineWriter<W: Write> {
+    w: W,
+    count: usize,
+}
+
+impl<W: Write> QuarantineWriter<W> {
+    /// Creates writer over quarantine stream.
+    pub fn new(w: W) -> Self {
+        Self { w, count: 0 }
+    }
+
+    /// Number of entries written so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Appends rejected input as quarantine entry.
+    pub fn write(&mut self, rejected: &RejectedInput) -> Result<(), AppError> {
+        let context = rejected.context.to_string();
+        writeln!(self.w, "{}", ENTRY_MARKER).add_write_ctx()?;
+        writeln!(self.w, "{}{}", ERROR_KEY, rejected.error).add_write_ctx()?;
+        writeln!(self.w, "{}{}", CONTEXT_KEY, single_line(context.trim_end())).add_write_ctx()?;
+        match printable_text(&rejected.raw) {
+            Some(text) => {
+                for line in text.split('\n') {
+                    writeln!(self.w, "{} {}", RAW_TEXT_PREFIX, line).add_write_ctx()?;
+                }
+            }
+            None => {
+                let hex: String = rejected.raw.iter().map(|b| format!("{:02X}", b)).collect();
+                writeln!(self.w, "{}{}", RAW_HEX_KEY, hex).add_write_ctx()?;
+            }
+        }
+        writeln!(self.w).add_write_ctx()?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Flushes and returns underlying stream.
+    pub fn into_inner(mut self) -> Result<W, AppError> {
+        self.w.flush().add_write_ctx()?;
+        Ok(self.w)
+    }
+}
+
+/// Entry read back from quarantine stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantineEntry {
+    /// Rejection reason as written.
+    pub error: String,
+    /// Rejection context as written.
+    pub context: String,
+    /// Original (possibly repaired) input bytes.
+    pub raw: Vec<u8>,
+}
+
+/// Reads quarantine stream written by [`QuarantineWriter`].
+pub fn read_quarantine<R: Read>(r: R) -> Result<Vec<QuarantineEntry>, AppError> {
+    let mut result = Vec::new();
+    let mut entry: Option<(QuarantineEntry, Vec<String>)> = None;
+    for (line_num, line_res) in BufReader::new(r).lines().enumerate() {
+        let line = line_res.map_err(AppError::ReadError)?;
+        let ctx = || ParserContext::with_line_number_and_line(line_num, line.clone());
+        if line == ENTRY_MARKER {
+            result.extend(entry.take().map(finish_entry));
+            entry = Some((
+                QuarantineEntry {
+                    error: String::new(),
+                    context: String::new(),
+                    raw: Vec::new(),
+                },
+                Vec::new(),
+            ));
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((current, raw_lines)) = entry.as_mut() else {
+            return Err(ParserError::InvalidRecordHeader(line.clone())).add_parser_ctx(ctx());
+        };
+        if let Some(error) = line.strip_prefix(ERROR_KEY) {
+            current.error = error.to_string();
+        } else if let Some(context) = line.strip_prefix(CONTEXT_KEY) {
+            current.context = context.to_string();
+        } else if let Some(raw) = line.strip_prefix(RAW_TEXT_PREFIX) {
+            raw_lines.push(raw.strip_prefix(' ').unwrap_or(raw).to_string());
+        } else if let Some(hex) = line.strip_prefix(RAW_HEX_KEY) {
+            current.raw = decode_hex(hex.trim()).add_parser_ctx(ctx())?;
+        } else {
+            return Err(ParserError::UnparsableValue(line.clone())).add_parser_ctx(ctx());
+        }
+    }
+    result.extend(entry.map(finish_entry));
+    Ok(result)
+}
+
+fn finish_entry((mut entry, raw_lines): (QuarantineEntry, Vec<String>)) -> QuarantineEntry {
+    if !raw_lines.is_empty() {
+        entry.raw = raw_lines.join("\n").into_bytes();
+    }
+    entry
+}
+
+// raw input is kept as text when it is valid UTF-8 without control characters except newlines and tabs
+fn printable_text(raw: &[u8]) -> Option<&str> {
+    std::str::from_utf8(raw).ok().filter(|text| {
+        !text
+            .chars()
+            .any(|c| c.is_control() && c != '\n' && c != '\t')
+    })
+}
+
+fn single_line(text: &str) -> String {
+    text.replace('\n', " ")
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, ParserError> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err(ParserError::UnparsableValue(hex.into()));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(ParserError::from))
+        .collect()
+}
@@ -0,0 +1,160 @@
+use std::io::{Read, Write};
+
+use super::errors::IoCtxBehavior;
+use super::traits::{
+    DataParser, DataWriter, EachRecord, RecordSink, RecordStream, StreamingParser, StreamingWriter,
+};
+use super::utils::format_iso8601;
+use crate::domain::directory::AccountDirectory;
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+const LABELS: [&str; 12] = [
+    "Type",
+    "From",
+    "To",
+    "Amount",
+    "Date",
+    "Status",
+    "Description",
+    "Tenant",
+    "Fee",
+    "Reference",
+    "Original",
+    "FX rate",
+];
+
+/// Form of report output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportOptions {
+    /// Names shown along with named accounts, e.g. `payroll (4021)`.
+    pub accounts: Option<AccountDirectory>,
+}
+
+/// Writer of operator-friendly reports, one labeled block per record.
+#[derive(Clone, Default)]
+pub(crate) struct ReportCodec {
+    options: ReportOptions,
+}
+impl ReportCodec {
+    pub(crate) fn new(options: ReportOptions) -> Self {
+        Self { options }
+    }
+
+    fn account_value(&self, account: &AccountType) -> String {
+        match self
+            .options
+            .accounts
+            .as_ref()
+            .and_then(|accounts| accounts.name_of(account))
+        {
+            Some(name) => format!("{} ({})", name, account),
+            None => account.to_string(),
+        }
+    }
+
+    fn write_block(&self, w: &mut dyn Write, tx: &TxRecord) -> Result<(), AppError> {
+        // extra fields, e.g. joined account data, are labeled by their names
+        let width = LABELS
+            .iter()
+            .copied()
+            .chain(tx.extras.keys().map(String::as_str))
+            .map(str::len)
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let mut fields = vec![
+            (LABELS[0], tx.kind.to_string()),
+            (LABELS[1], self.account_value(&tx.from)),
+            (LABELS[2], self.account_value(&tx.to)),
+            (LABELS[3], group_thousands(tx.amount.minor_units)),
+        ];
+        if let Some(fee) = tx.fee {
+            fields.push((LABELS[8], group_thousands(fee.minor_units)));
+        }
+        if let Some(original_amount) = tx.original_amount {
+            fields.push((LABELS[10], original_amount.to_string()));
+        }
+        if let Some(exchange_rate) = tx.exchange_rate {
+            fields.push((LABELS[11], exchange_rate.to_string()));
+        }
+        fields.push((LABELS[4], format_iso8601(tx.ts.millis())));
+        fields.push((LABELS[5], tx.status.to_string()));
+        if let Some(description) = &tx.description {
+            fields.push((LABELS[6], description.clone()));
+        }
+        if let Some(tenant) = &tx.tenant {
+            fields.push((LABELS[7], tenant.clone()));
+        }
+        if let Some(reference) = &tx.reference {
+            fields.push((LABELS[9], reference.clone()));
+        }
+        for (name, value) in &tx.extras {
+            fields.push((name, value.clone()));
+        }
+
+        writeln!(w, "Transaction {}", tx.id).add_write_ctx()?;
+        for (label, value) in fields {
+            // continuation lines of multi-line values stay in the value column
+            let mut lines = value.lines();
+            let first = lines.next().unwrap_or_default();
+            writeln!(w, "  {:<width$} {}", format!("{}:", label), first).add_write_ctx()?;
+            for line in lines {
+                writeln!(w, "  {:<width$} {}", "", line).add_write_ctx()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DataParser for ReportCodec {
+    fn parse(&self, _: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "report format is write only",
+        ))
+        .add_read_ctx()
+    }
+}
+
+impl StreamingParser for ReportCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, mut r: R) -> RecordStream<'a> {
+        RecordStream::from_parsed(self.parse(&mut r))
+    }
+}
+
+impl DataWriter for ReportCodec {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        for (i, tx) in data.iter().enumerate() {
+            if i > 0 {
+                writeln!(w).add_write_ctx()?;
+            }
+            self.write_block(w, tx)?;
+        }
+        Ok(())
+    }
+}
+
+impl StreamingWriter for ReportCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        Ok(RecordSink::new(
+            EachRecord::new(self.clone(), w).separated_by("\n"),
+        ))
+    }
+}
+
+// `-1234567` becomes `-1,234,567`
+fn group_thousands(amount: i64) -> String {
+    let digits = amount.unsigned_abs().to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+    if amount < 0 {
+        grouped.push('-');
+    }
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
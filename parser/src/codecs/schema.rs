@@ -0,0 +1,384 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::base::TxFieldKey;
+use super::errors::ParserError;
+use super::options::{AmountFormat, TimestampFormat};
+use crate::domain::tx::*;
+
+/// Type of values of a record field, tells how field text is parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    /// Numeric id or UUID, see [`TxIdType`].
+    Id,
+    /// Transaction kind name, e.g. `TRANSFER`.
+    Kind,
+    /// Numeric account id or IBAN, see [`AccountType`].
+    Account,
+    /// Amount in the form of the codec, see [`AmountFormat`].
+    Amount,
+    /// Decimal amount with its own scale, e.g. amount in other currency.
+    ExactAmount,
+    /// Timestamp in the form of the codec, see [`TimestampFormat`].
+    Timestamp,
+    /// Transaction status name, e.g. `SUCCESS`.
+    Status,
+    /// Free text.
+    Text,
+    /// Decimal exchange rate, e.g. `1.0825`.
+    Rate,
+}
+
+/// Description of a record field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    /// Key the field is read and written under.
+    pub key: TxFieldKey,
+    /// Type of field values.
+    pub field_type: FieldType,
+    /// Records can't be built without the field.
+    pub required: bool,
+}
+
+impl FieldSpec {
+    const fn new(key: TxFieldKey, field_type: FieldType, required: bool) -> Self {
+        Self {
+            key,
+            field_type,
+            required,
+        }
+    }
+}
+
+/// Fields of [`TxRecord`] with their types and requiredness.
+///
+/// Codecs reading fields by their keys (CSV, text, xlsx) parse values and check required
+/// fields through the schema, so a new field is described once here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxSchema {
+    fields: &'static [FieldSpec],
+}
+
+impl TxSchema {
+    /// Schema of all record fields, in the order of text and CSV output.
+    pub const STANDARD: TxSchema = TxSchema {
+        fields: &[
+            FieldSpec::new(TxFieldKey::Id, FieldType::Id, true),
+            FieldSpec::new(TxFieldKey::TxKind, FieldType::Kind, true),
+            FieldSpec::new(TxFieldKey::FromUserId, FieldType::Account, true),
+            FieldSpec::new(TxFieldKey::ToUserId, FieldType::Account, true),
+            FieldSpec::new(TxFieldKey::Amount, FieldType::Amount, true),
+            FieldSpec::new(TxFieldKey::Timestamp, FieldType::Timestamp, true),
+            FieldSpec::new(TxFieldKey::Status, FieldType::Status, true),
+            FieldSpec::new(TxFieldKey::Description, FieldType::Text, false),
+            FieldSpec::new(TxFieldKey::Tenant, FieldType::Text, false),
+            FieldSpec::new(TxFieldKey::Fee, FieldType::Amount, false),
+            FieldSpec::new(TxFieldKey::Reference, FieldType::Text, false),
+            FieldSpec::new(TxFieldKey::OriginalAmount, FieldType::ExactAmount, false),
+            FieldSpec::new(TxFieldKey::ExchangeRate, FieldType::Rate, false),
+        ],
+    };
+
+    /// Fields of the schema.
+    pub fn fields(&self) -> &[FieldSpec] {
+        self.fields
+    }
+
+    /// Field of `key`, `None` if the schema has no such field.
+    pub fn field(&self, key: TxFieldKey) -> Option<&FieldSpec> {
+        self.fields.iter().find(|spec| spec.key == key)
+    }
+
+    /// Keys of required fields, in schema order.
+    pub fn required(&self) -> impl Iterator<Item = TxFieldKey> + '_ {
+        self.fields
+            .iter()
+            .filter(|spec| spec.required)
+            .map(|spec| spec.key)
+    }
+}
+
+/// Forms of amounts and timestamps of a codec, other types have a single form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValueFormats {
+    /// Form of [`FieldType::Amount`] values.
+    pub amounts: AmountFormat,
+    /// Form of [`FieldType::Timestamp`] values.
+    pub timestamps: TimestampFormat,
+}
+
+/// Parsed value of a record field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldValue {
+    /// Value of [`FieldType::Id`] field.
+    Id(TxIdType),
+    /// Value of [`FieldType::Kind`] field.
+    Kind(TxKind),
+    /// Value of [`FieldType::Account`] field.
+    Account(AccountType),
+    /// Value of [`FieldType::Amount`] and [`FieldType::ExactAmount`] fields.
+    Amount(Money),
+    /// Value of [`FieldType::Timestamp`] field.
+    Timestamp(TxTimestamp),
+    /// Value of [`FieldType::Status`] field.
+    Status(TxStatus),
+    /// Value of [`FieldType::Text`] field.
+    Text(String),
+    /// Value of [`FieldType::Rate`] field.
+    Rate(ExchangeRate),
+}
+
+impl FieldType {
+    /// Parses field text, amounts and timestamps are read in `formats`.
+    pub fn parse(self, value: &str, formats: &ValueFormats) -> Result<FieldValue, ParserError> {
+        Ok(match self {
+            FieldType::Id => FieldValue::Id(value.parse()?),
+            FieldType::Kind => FieldValue::Kind(value.parse()?),
+            FieldType::Account => FieldValue::Account(value.parse()?),
+            FieldType::Amount => FieldValue::Amount(formats.amounts.parse(value)?),
+            FieldType::ExactAmount => FieldValue::Amount(AmountFormat::Exact.parse(value)?),
+            FieldType::Timestamp => FieldValue::Timestamp(formats.timestamps.parse(value)?),
+            FieldType::Status => FieldValue::Status(value.parse()?),
+            FieldType::Text => FieldValue::Text(value.to_string()),
+            FieldType::Rate => FieldValue::Rate(value.parse()?),
+        })
+    }
+
+    pub(crate) fn accepts(self, value: &FieldValue) -> bool {
+        matches!(
+            (self, value),
+            (FieldType::Id, FieldValue::Id(_))
+                | (FieldType::Kind, FieldValue::Kind(_))
+                | (FieldType::Account, FieldValue::Account(_))
+                | (
+                    FieldType::Amount | FieldType::ExactAmount,
+                    FieldValue::Amount(_)
+                )
+                | (FieldType::Timestamp, FieldValue::Timestamp(_))
+                | (FieldType::Status, FieldValue::Status(_))
+                | (FieldType::Text, FieldValue::Text(_))
+                | (FieldType::Rate, FieldValue::Rate(_))
+        )
+    }
+}
+
+/// Field values of a record being read, checked against [`TxSchema::STANDARD`].
+#[derive(Debug, Clone, Default)]
+pub struct RecordFields {
+    values: HashMap<TxFieldKey, FieldValue>,
+}
+
+impl RecordFields {
+    /// Whether value of `key` is already set.
+    pub fn is_set(&self, key: TxFieldKey) -> bool {
+        self.values.contains_key(&key)
+    }
+
+    /// Whether no value is set.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Sets value of `key`, fails if it is set already or has other type than the field.
+    pub fn set(&mut self, key: TxFieldKey, value: FieldValue) -> Result<(), ParserError> {
+        if self.is_set(key) {
+            return Err(ParserError::Duplicate(key));
+        }
+        match TxSchema::STANDARD.field(key) {
+            Some(spec) if spec.field_type.accepts(&value) => {
+                self.values.insert(key, value);
+                Ok(())
+            }
+            _ => Err(ParserError::UnparsableValue(format!(
+                "{:?} is not a {} value",
+                value, key
+            ))),
+        }
+    }
+
+    /// Parses `value` by type of `key` field and sets it.
+    pub fn parse(
+        &mut self,
+        key: TxFieldKey,
+        value: &str,
+        formats: &ValueFormats,
+    ) -> Result<(), ParserError> {
+        if self.is_set(key) {
+            return Err(ParserError::Duplicate(key));
+        }
+        let spec = TxSchema::STANDARD
+            .field(key)
+            .ok_or_else(|| ParserError::UnparsableKey(key.to_string()))?;
+        let value = spec.field_type.parse(value, formats)?;
+        self.set(key, value)
+    }
+
+    /// Record of set values with `extras`, fails on the first missing required field.
+    pub fn build(mut self, extras: BTreeMap<String, String>) -> Result<TxRecord, ParserError> {
+        if let Some(missing) = TxSchema::STANDARD.required().find(|&key| !self.is_set(key)) {
+            return Err(ParserError::MissingField(missing));
+        }
+        let mut take = |key| self.values.remove(&key);
+        fn required<T>(key: TxFieldKey, value: Option<T>) -> Result<T, ParserError> {
+            value.ok_or(ParserError::MissingField(key))
+        }
+        Ok(TxRecord {
+            id: required(
+                TxFieldKey::Id,
+                take(TxFieldKey::Id).and_then(FieldValue::into_id),
+            )?,
+            kind: required(
+                TxFieldKey::TxKind,
+                take(TxFieldKey::TxKind).and_then(FieldValue::into_kind),
+            )?,
+            from: required(
+                TxFieldKey::FromUserId,
+                take(TxFieldKey::FromUserId).and_then(FieldValue::into_account),
+            )?,
+            to: required(
+                TxFieldKey::ToUserId,
+                take(TxFieldKey::ToUserId).and_then(FieldValue::into_account),
+            )?,
+            amount: required(
+                TxFieldKey::Amount,
+                take(TxFieldKey::Amount).and_then(FieldValue::into_amount),
+            )?,
+            fee: take(TxFieldKey::Fee).and_then(FieldValue::into_amount),
+            original_amount: take(TxFieldKey::OriginalAmount).and_then(FieldValue::into_amount),
+            exchange_rate: take(TxFieldKey::ExchangeRate).and_then(FieldValue::into_rate),
+            ts: required(
+                TxFieldKey::Timestamp,
+                take(TxFieldKey::Timestamp).and_then(FieldValue::into_timestamp),
+            )?,
+            status: required(
+                TxFieldKey::Status,
+                take(TxFieldKey::Status).and_then(FieldValue::into_status),
+            )?,
+            description: take(TxFieldKey::Description).and_then(FieldValue::into_text),
+            tenant: take(TxFieldKey::Tenant).and_then(FieldValue::into_text),
+            reference: take(TxFieldKey::Reference).and_then(FieldValue::into_text),
+            extras,
+        })
+    }
+}
+
+// `RecordFields::set` guarantees values match types of their fields
+impl FieldValue {
+    fn into_id(self) -> Option<TxIdType> {
+        match self {
+            FieldValue::Id(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    fn into_kind(self) -> Option<TxKind> {
+        match self {
+            FieldValue::Kind(kind) => Some(kind),
+            _ => None,
+        }
+    }
+
+    fn into_account(self) -> Option<AccountType> {
+        match self {
+            FieldValue::Account(account) => Some(account),
+            _ => None,
+        }
+    }
+
+    fn into_amount(self) -> Option<Money> {
+        match self {
+            FieldValue::Amount(amount) => Some(amount),
+            _ => None,
+        }
+    }
+
+    fn into_timestamp(self) -> Option<TxTimestamp> {
+        match self {
+            FieldValue::Timestamp(ts) => Some(ts),
+            _ => None,
+        }
+    }
+
+    fn into_status(self) -> Option<TxStatus> {
+        match self {
+            FieldValue::Status(status) => Some(status),
+            _ => None,
+        }
+    }
+
+    fn into_text(self) -> Option<String> {
+        match self {
+            FieldValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn into_rate(self) -> Option<ExchangeRate> {
+        match self {
+            FieldValue::Rate(rate) => Some(rate),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_describes_every_field_key() {
+        let keys: Vec<TxFieldKey> = TxSchema::STANDARD
+            .fields()
+            .iter()
+            .map(|spec| spec.key)
+            .collect();
+        assert_eq!(keys, TxFieldKey::ALL);
+        assert_eq!(TxSchema::STANDARD.required().count(), 7);
+        assert_eq!(
+            TxSchema::STANDARD
+                .field(TxFieldKey::Fee)
+                .map(|spec| spec.field_type),
+            Some(FieldType::Amount)
+        );
+    }
+
+    #[test]
+    fn record_is_built_from_parsed_fields() {
+        let formats = ValueFormats::default();
+        let mut fields = RecordFields::default();
+        for (key, value) in [
+            (TxFieldKey::Id, "7"),
+            (TxFieldKey::TxKind, "TRANSFER"),
+            (TxFieldKey::FromUserId, "1"),
+            (TxFieldKey::ToUserId, "2"),
+            (TxFieldKey::Amount, "500"),
+            (TxFieldKey::Timestamp, "1700"),
+        ] {
+            fields.parse(key, value, &formats).unwrap();
+        }
+        assert!(matches!(
+            fields.clone().build(BTreeMap::new()),
+            Err(ParserError::MissingField(TxFieldKey::Status))
+        ));
+        fields
+            .parse(TxFieldKey::Status, "SUCCESS", &formats)
+            .unwrap();
+        fields
+            .parse(TxFieldKey::ExchangeRate, "1.0825", &formats)
+            .unwrap();
+        assert!(matches!(
+            fields.parse(TxFieldKey::Id, "8", &formats),
+            Err(ParserError::Duplicate(TxFieldKey::Id))
+        ));
+        assert!(
+            fields
+                .set(TxFieldKey::Tenant, FieldValue::Amount(Money::default()))
+                .is_err()
+        );
+
+        let tx = fields.build(BTreeMap::new()).unwrap();
+        assert_eq!(tx.id, TxIdType::Numeric(7));
+        assert_eq!(tx.amount, Money::from_minor_units(500));
+        assert_eq!(tx.exchange_rate, Some(ExchangeRate::new(10_825, 4)));
+        assert_eq!(tx.description, None);
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt::Debug;
+use std::io::{Cursor, Read, Write};
+
+use hmac::Mac;
+
+use super::errors::{IntegrityError, IoCtxBehavior};
+use crate::digest::{HmacSha256, hmac_sha256};
+use crate::errors::AppError;
+
+const TRAILER_MAGIC: [u8; 4] = *b"YPSG";
+const SIGNATURE_SIZE: usize = 32;
+// magic followed by HMAC-SHA256 of everything before the trailer
+const TRAILER_SIZE: usize = TRAILER_MAGIC.len() + SIGNATURE_SIZE;
+
+/// Secret shared by writer and verifier of signed output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SigningKey {
+    secret: Vec<u8>,
+}
+
+impl SigningKey {
+    /// Creates key from passphrase or raw key bytes.
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+        }
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        hmac_sha256(&self.secret)
+    }
+}
+
+// secret is never printed
+impl Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey").finish_non_exhaustive()
+    }
+}
+
+/// Reader verifying HMAC-SHA256 trailer of signed input.
+///
+/// Input is read and verified at once on construction, so nothing of altered input is ever
+/// returned; content without the trailer is held in memory.
+pub struct SignedReader {
+    inner: Cursor<Vec<u8>>,
+}
+
+impl SignedReader {
+    /// Verifies `r` with `key`, failures are reported as [`AppError::IntegrityError`].
+    pub fn new<R: Read>(mut r: R, key: &SigningKey) -> Result<Self, AppError> {
+        let mut input = Vec::new();
+        r.read_to_end(&mut input).add_read_ctx()?;
+        let content_len = input
+            .len()
+            .checked_sub(TRAILER_SIZE)
+            .filter(|&len| input[len..].starts_with(&TRAILER_MAGIC))
+            .ok_or(AppError::IntegrityError(IntegrityError::MissingTrailer))?;
+
+        let mut mac = key.mac();
+        mac.update(&input[..content_len]);
+        // compared in time independent of the signature
+        if mac
+            .verify_slice(&input[content_len + TRAILER_MAGIC.len()..])
+            .is_err()
+        {
+            return Err(AppError::IntegrityError(IntegrityError::SignatureMismatch));
+        }
+        input.truncate(content_len);
+        Ok(Self {
+            inner: Cursor::new(input),
+        })
+    }
+}
+
+impl Read for SignedReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+/// Writer appending HMAC-SHA256 trailer to everything written to it.
+///
+/// Output passes through as it is written, [`SignedWriter::finish`] appends the trailer;
+/// dropping the writer without finishing it leaves output unsigned.
+pub struct SignedWriter<W: Write> {
+    w: W,
+    mac: HmacSha256,
+}
+
+impl<W: Write> SignedWriter<W> {
+    /// Creates writer signing output written into `w` with `key`.
+    pub fn new(w: W, key: &SigningKey) -> Self {
+        Self { w, mac: key.mac() }
+    }
+
+    /// Writes signature trailer and returns underlying writer.
+    pub fn finish(mut self) -> Result<W, AppError> {
+        self.w.write_all(&TRAILER_MAGIC).add_write_ctx()?;
+        let signature = self.mac.finalize().into_bytes();
+        self.w.write_all(&signature).add_write_ctx()?;
+        self.w.flush().add_write_ctx()?;
+        Ok(self.w)
+    }
+}
+
+impl<W: Write> Write for SignedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.w.write(buf)?;
+        self.mac.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.w.flush()
+    }
+}
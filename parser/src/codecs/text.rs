@@ -1,127 +1,210 @@
 use super::base::TxFieldKey;
-use super::errors::{ParserContext, ParserCtxBehavior, ParserError};
-use super::traits::{DataParser, DataWriter};
+use super::errors::{ParserContext, ParserError};
+use super::options::{AmountFormat, ParseLimits, TimestampFormat};
+use super::quarantine::RejectedInput;
+use super::schema::{FieldValue, RecordFields, ValueFormats};
+use super::traits::*;
 use super::utils::unquote;
 use crate::codecs::errors::IoCtxBehavior;
+use crate::domain::directory::AccountDirectory;
 use crate::domain::tx::*;
 use crate::errors::AppError;
-use std::io::{BufRead, BufReader, Read, Write};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Lines, Read, Write};
 
 const FIELD_KV_DELIMITER: char = ':';
 const COMMENT_SYMBOL_1LINE: char = '#';
 
+#[derive(Default)]
 struct RecordBuilder {
-    is_dirty: bool,
-    id: Option<TxIdType>,
-    kind: Option<TxKind>,
-    from: Option<AccountType>,
-    to: Option<AccountType>,
-    amount: Option<i64>,
-    ts: Option<TxTimestamp>,
-    status: Option<TxStatus>,
-    description: Option<String>,
+    fields: RecordFields,
+    extras: BTreeMap<String, String>,
 }
 impl RecordBuilder {
-    fn new() -> Self {
-        Self {
-            is_dirty: false,
-            id: None,
-            kind: None,
-            from: None,
-            to: None,
-            amount: None,
-            ts: None,
-            status: None,
-            description: None,
-        }
-    }
-
-    fn is_key_already_present(&self, field_key: &TxFieldKey) -> bool {
-        match field_key {
-            TxFieldKey::Id => self.id.is_some(),
-            TxFieldKey::TxKind => self.kind.is_some(),
-            TxFieldKey::FromUserId => self.from.is_some(),
-            TxFieldKey::ToUserId => self.to.is_some(),
-            TxFieldKey::Amount => self.amount.is_some(),
-            TxFieldKey::Timestamp => self.ts.is_some(),
-            TxFieldKey::Status => self.status.is_some(),
-            TxFieldKey::Description => self.description.is_some(),
-        }
+    fn is_dirty(&self) -> bool {
+        !self.fields.is_empty() || !self.extras.is_empty()
     }
 
-    fn set_field_value(&mut self, field_key: TxFieldKey, value: &str) -> Result<(), ParserError> {
-        // println!("parse from line [{:?}]-->{}<--", field_key, value);
-        if self.is_key_already_present(&field_key) {
+    fn set_field_value(
+        &mut self,
+        field_key: TxFieldKey,
+        value: &str,
+        dialect: &TextDialect,
+    ) -> Result<(), ParserError> {
+        if self.fields.is_set(field_key) {
             return Err(ParserError::Duplicate(field_key));
         }
-        self.is_dirty = true;
+        // description is always quoted, tenant and reference are written quoted, account may be named
         match field_key {
-            TxFieldKey::Id => self.id = Some(value.parse()?),
-            TxFieldKey::TxKind => self.kind = Some(value.parse()?),
-            TxFieldKey::FromUserId => self.from = Some(value.parse()?),
-            TxFieldKey::ToUserId => self.to = Some(value.parse()?),
-            TxFieldKey::Amount => self.amount = Some(value.parse()?),
-            TxFieldKey::Timestamp => self.ts = Some(value.parse()?),
-            TxFieldKey::Status => self.status = Some(value.parse()?),
-            TxFieldKey::Description => self.description = Some(unquote(value)?.to_string()),
-        };
-        Ok(())
+            TxFieldKey::Description => self
+                .fields
+                .set(field_key, FieldValue::Text(unescape(unquote(value)?)?)),
+            TxFieldKey::Tenant | TxFieldKey::Reference => self
+                .fields
+                .set(field_key, FieldValue::Text(unquote_lenient(value)?)),
+            TxFieldKey::FromUserId | TxFieldKey::ToUserId => match &dialect.accounts {
+                Some(accounts) => self.fields.set(
+                    field_key,
+                    FieldValue::Account(accounts.parse_account(value)?),
+                ),
+                None => self.fields.parse(field_key, value, &dialect.formats()),
+            },
+            _ => self.fields.parse(field_key, value, &dialect.formats()),
+        }
     }
-    fn parse_field_from_line(&mut self, line: &str) -> Result<(), ParserError> {
+    fn parse_field_from_line(
+        &mut self,
+        line: &str,
+        dialect: &TextDialect,
+    ) -> Result<(), ParserError> {
         // split string to key=value pair and save to buffer
         let (key, value) = line
             .split_once(FIELD_KV_DELIMITER)
             .ok_or(ParserError::NoFieldDelimiter)?;
-        let field_key = key.trim().parse::<TxFieldKey>()?;
-        self.set_field_value(field_key, value.trim())?;
+        let (key, value) = (key.trim(), value.trim());
+        match dialect.field_key(key) {
+            Ok(field_key) => self.set_field_value(field_key, value, dialect),
+            Err(ParserError::UnparsableKey(_)) if dialect.keep_extras => self.set_extra(key, value),
+            Err(e) => Err(e),
+        }
+    }
+    fn set_extra(&mut self, key: &str, value: &str) -> Result<(), ParserError> {
+        if self.extras.contains_key(key) {
+            return Err(ParserError::DuplicateExtra(key.to_string()));
+        }
+        self.extras.insert(key.to_string(), unquote_lenient(value)?);
         Ok(())
     }
     fn finalize(&mut self) -> Result<TxRecord, ParserError> {
-        let tx = TxRecord {
-            id: self
-                .id
-                .take()
-                .ok_or(ParserError::MissingField(TxFieldKey::Id))?,
-            kind: self
-                .kind
-                .take()
-                .ok_or(ParserError::MissingField(TxFieldKey::TxKind))?,
-            from: self
-                .from
-                .take()
-                .ok_or(ParserError::MissingField(TxFieldKey::FromUserId))?,
-            to: self
-                .to
-                .take()
-                .ok_or(ParserError::MissingField(TxFieldKey::ToUserId))?,
-            amount: self
-                .amount
-                .take()
-                .ok_or(ParserError::MissingField(TxFieldKey::Amount))?,
-            ts: self
-                .ts
-                .take()
-                .ok_or(ParserError::MissingField(TxFieldKey::Timestamp))?,
-            status: self
-                .status
-                .take()
-                .ok_or(ParserError::MissingField(TxFieldKey::Status))?,
-            description: self
-                .description
-                .take()
-                .ok_or(ParserError::MissingField(TxFieldKey::Description))?,
+        std::mem::take(&mut self.fields).build(std::mem::take(&mut self.extras))
+    }
+}
+
+/// Key names accepted in text input, output always has the standard ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextDialect {
+    /// Match keys ignoring case, underscores, dashes and spaces, see [`TxFieldKey::from_str_lenient`].
+    pub lenient_keys: bool,
+    /// Other names of keys, e.g. `("SENDER", TxFieldKey::FromUserId)`; matched before standard ones.
+    ///
+    /// Aliases are matched the way keys are, exactly or leniently.
+    pub aliases: Vec<(String, TxFieldKey)>,
+    /// Form of TIMESTAMP values.
+    pub timestamps: TimestampFormat,
+    /// Form of AMOUNT values.
+    pub amounts: AmountFormat,
+    /// Unknown keys are kept in [`TxRecord::extras`] instead of rejecting the record.
+    pub keep_extras: bool,
+    /// Line ending records besides blank lines, consecutive ones give no empty records.
+    pub separator: RecordSeparator,
+    /// Names of FROM_USER_ID and TO_USER_ID accounts, ids and IBANs are read as well.
+    pub accounts: Option<AccountDirectory>,
+}
+
+/// Line following every text record, e.g. `---` of frontmatter-style dumps.
+///
+/// Line separator is compared with trimmed line content, comments stripped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RecordSeparator {
+    /// Empty line.
+    #[default]
+    BlankLine,
+    /// Line of this text.
+    Line(String),
+}
+
+impl RecordSeparator {
+    fn line(&self) -> &str {
+        match self {
+            RecordSeparator::BlankLine => "",
+            RecordSeparator::Line(line) => line,
+        }
+    }
+
+    fn matches(&self, content: &str) -> bool {
+        match self {
+            RecordSeparator::BlankLine => false,
+            RecordSeparator::Line(line) => line.trim() == content,
+        }
+    }
+}
+
+impl TextDialect {
+    fn formats(&self) -> ValueFormats {
+        ValueFormats {
+            amounts: self.amounts,
+            timestamps: self.timestamps,
+        }
+    }
+
+    fn field_key(&self, key: &str) -> Result<TxFieldKey, ParserError> {
+        let alias = if self.lenient_keys {
+            let folded = TxFieldKey::folded(key);
+            self.aliases
+                .iter()
+                .find(|(alias, _)| TxFieldKey::folded(alias) == folded)
+        } else {
+            self.aliases.iter().find(|(alias, _)| key == alias)
         };
-        Ok(tx)
+        match alias {
+            Some(&(_, field_key)) => Ok(field_key),
+            None if self.lenient_keys => TxFieldKey::from_str_lenient(key),
+            None => key.parse(),
+        }
     }
 }
 
-#[derive(Default)]
-pub(crate) struct TextCodec;
+/// Form of written text values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextWriteOptions {
+    /// Form of TIMESTAMP values, input written with RFC 3339 ones is read with the same
+    /// [`TextDialect::timestamps`].
+    pub timestamps: TimestampFormat,
+    /// Form of AMOUNT values, read back with the same [`TextDialect::amounts`].
+    pub amounts: AmountFormat,
+    /// Line written after every record.
+    pub separator: RecordSeparator,
+    /// Names written instead of named FROM_USER_ID and TO_USER_ID accounts, read back with
+    /// the same [`TextDialect::accounts`].
+    pub accounts: Option<AccountDirectory>,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct TextCodec {
+    dialect: TextDialect,
+    write_options: TextWriteOptions,
+    limits: ParseLimits,
+}
 impl TextCodec {
+    pub(crate) fn new(dialect: TextDialect) -> Self {
+        Self {
+            dialect,
+            write_options: TextWriteOptions::default(),
+            limits: ParseLimits::default(),
+        }
+    }
+
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub(crate) fn with_write_options(mut self, write_options: TextWriteOptions) -> Self {
+        self.write_options = write_options;
+        self
+    }
+
+    fn account_value(&self, account: &AccountType) -> String {
+        match &self.write_options.accounts {
+            Some(accounts) => accounts.display_name(account),
+            None => account.to_string(),
+        }
+    }
     fn write_kv_pair(
         &self,
         w: &mut dyn Write,
-        field_key: TxFieldKey,
+        field_key: impl Display,
         field_value: &str,
     ) -> Result<(), AppError> {
         writeln!(w, "{}{} {}", field_key, FIELD_KV_DELIMITER, field_value).add_write_ctx()
@@ -129,69 +212,328 @@ impl TextCodec {
     fn write_single_record(&self, w: &mut dyn Write, tx: &TxRecord) -> Result<(), AppError> {
         self.write_kv_pair(w, TxFieldKey::Id, &tx.id.to_string())?;
         self.write_kv_pair(w, TxFieldKey::TxKind, &tx.kind.to_string())?;
-        self.write_kv_pair(w, TxFieldKey::FromUserId, &tx.from.to_string())?;
-        self.write_kv_pair(w, TxFieldKey::ToUserId, &tx.to.to_string())?;
-        self.write_kv_pair(w, TxFieldKey::Amount, &tx.amount.to_string())?;
-        self.write_kv_pair(w, TxFieldKey::Timestamp, &tx.ts.to_string())?;
-        self.write_kv_pair(w, TxFieldKey::Status, &tx.status.to_string())?;
+        self.write_kv_pair(w, TxFieldKey::FromUserId, &self.account_value(&tx.from))?;
+        self.write_kv_pair(w, TxFieldKey::ToUserId, &self.account_value(&tx.to))?;
+        self.write_kv_pair(
+            w,
+            TxFieldKey::Amount,
+            &self
+                .write_options
+                .amounts
+                .format(tx.amount)
+                .add_write_ctx()?,
+        )?;
         self.write_kv_pair(
             w,
-            TxFieldKey::Description,
-            &format!("\"{}\"", &tx.description),
+            TxFieldKey::Timestamp,
+            &self.write_options.timestamps.format(tx.ts),
         )?;
+        self.write_kv_pair(w, TxFieldKey::Status, &tx.status.to_string())?;
+        if let Some(description) = &tx.description {
+            self.write_kv_pair(
+                w,
+                TxFieldKey::Description,
+                &format!("\"{}\"", escape(description)),
+            )?;
+        }
+        if let Some(tenant) = &tx.tenant {
+            self.write_kv_pair(w, TxFieldKey::Tenant, &format!("\"{}\"", escape(tenant)))?;
+        }
+        if let Some(fee) = tx.fee {
+            self.write_kv_pair(
+                w,
+                TxFieldKey::Fee,
+                &self.write_options.amounts.format(fee).add_write_ctx()?,
+            )?;
+        }
+        if let Some(reference) = &tx.reference {
+            self.write_kv_pair(
+                w,
+                TxFieldKey::Reference,
+                &format!("\"{}\"", escape(reference)),
+            )?;
+        }
+        // original amount is in other currency, it keeps its own scale
+        if let Some(original_amount) = tx.original_amount {
+            self.write_kv_pair(
+                w,
+                TxFieldKey::OriginalAmount,
+                &AmountFormat::Exact
+                    .format(original_amount)
+                    .add_write_ctx()?,
+            )?;
+        }
+        if let Some(exchange_rate) = tx.exchange_rate {
+            self.write_kv_pair(w, TxFieldKey::ExchangeRate, &exchange_rate.to_string())?;
+        }
+        for (name, value) in &tx.extras {
+            if !is_extra_key(name) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("extra field `{}` can't be written as text key", name),
+                ))
+                .add_write_ctx();
+            }
+            self.write_kv_pair(w, name, &format!("\"{}\"", escape(value)))?;
+        }
         Ok(())
     }
 }
 impl DataParser for TextCodec {
-    fn parse<R: Read>(&self, r: R) -> Result<Vec<TxRecord>, AppError> {
-        let mut result = Vec::new();
-        let mut record_builder = RecordBuilder::new();
-        let mut line_num: usize = 0;
-        let mut input_line: String = "".to_string();
-        for line_res in BufReader::new(r).lines() {
-            line_num += 1;
-            input_line = line_res.map_err(|e| AppError::ReadError(e))?;
-            // println!("{}: {}", line_num, input_line);
-            let line = &input_line.trim();
-
-            // skip comments
-            if let Some(first_char) = line.chars().nth(0) {
-                if COMMENT_SYMBOL_1LINE == first_char {
-                    continue;
-                }
-            }
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_recovering(r, &mut |rejected| Err(rejected.into_error()))
+    }
+}
+
+impl RecoveringParser for TextCodec {
+    fn parse_recovering<R: Read>(
+        &self,
+        r: R,
+        on_reject: &mut dyn FnMut(RejectedInput) -> Result<(), AppError>,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        collect_recovering(
+            TextRecordReader::new(self.dialect.clone(), r),
+            self.limits,
+            on_reject,
+        )
+    }
+}
 
-            // if line is empty - assemble the record
-            if line.is_empty() {
-                if record_builder.is_dirty {
-                    result.push(record_builder.finalize().add_parser_ctx(
-                        ParserContext::with_line_number_and_line(line_num, input_line.clone()),
-                    )?);
+impl StreamingParser for TextCodec {
+    fn parse_stream<'a, R: Read + 'a>(&self, r: R) -> RecordStream<'a> {
+        RecordStream::new(TextRecordReader::new(self.dialect.clone(), r))
+    }
+}
+
+// collects lines into record blocks, blocks are separated by empty lines
+struct TextRecordReader<R> {
+    dialect: TextDialect,
+    lines: Lines<BufReader<R>>,
+    line_num: usize,
+    input_line: String,
+    block: RecordBlock,
+    in_block_comment: bool,
+    at_eof: bool,
+}
+
+impl<R: Read> TextRecordReader<R> {
+    fn new(dialect: TextDialect, r: R) -> Self {
+        Self {
+            dialect,
+            lines: BufReader::new(r).lines(),
+            line_num: 0,
+            input_line: "".to_string(),
+            block: RecordBlock::new(),
+            in_block_comment: false,
+            at_eof: false,
+        }
+    }
+}
+
+impl<R: Read> RecordReader for TextRecordReader<R> {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        while !self.at_eof {
+            let Some(line_res) = self.lines.next() else {
+                // still some fields in the builder? -> assemble the record
+                self.at_eof = true;
+                return self.block.finish(self.line_num, &self.input_line).map(Ok);
+            };
+            self.line_num += 1;
+            self.input_line = match line_res {
+                Ok(line) => line,
+                Err(e) => return Some(Err(AppError::ReadError(e))),
+            };
+            let is_blank = !self.in_block_comment && self.input_line.trim().is_empty();
+            let content = strip_comments(&self.input_line, &mut self.in_block_comment);
+            let line = content.trim();
+
+            // blank or separator line - assemble the record, comment-only lines are skipped
+            if line.is_empty() && !is_blank {
+                continue;
+            }
+            if line.is_empty() || self.dialect.separator.matches(line) {
+                if let Some(outcome) = self.block.finish(self.line_num, &self.input_line) {
+                    return Some(Ok(outcome));
                 }
-                record_builder = RecordBuilder::new();
                 continue;
             }
-            record_builder.parse_field_from_line(line).add_parser_ctx(
-                ParserContext::with_line_number_and_line(line_num, input_line.clone()),
-            )?
+            self.block
+                .push_line(self.line_num, &self.input_line, line, &self.dialect);
         }
+        None
+    }
+}
 
-        // still some fields in the builder? -> assemble the record
-        if record_builder.is_dirty {
-            result.push(record_builder.finalize().add_parser_ctx(
-                ParserContext::with_line_number_and_line(line_num, input_line.clone()),
-            )?);
+// drops `# ...` comments (line start or after whitespace) and `/* ... */` comments
+// which may span lines, quoted values are kept intact
+fn strip_comments(line: &str, in_block_comment: &mut bool) -> String {
+    let mut content = String::with_capacity(line.len());
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if *in_block_comment {
+            if '*' == c && chars.next_if_eq(&'/').is_some() {
+                *in_block_comment = false;
+            }
+            continue;
+        }
+        if in_quotes {
+            content.push(c);
+            match c {
+                '\\' => content.extend(chars.next()),
+                '"' => in_quotes = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_quotes = true;
+                content.push(c);
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => *in_block_comment = true,
+            COMMENT_SYMBOL_1LINE
+                if content.is_empty() || content.ends_with(char::is_whitespace) =>
+            {
+                break;
+            }
+            c => content.push(c),
         }
-        Ok(result)
     }
+    content
+}
+
+// lines of a single record, first failure is kept and the rest of the block is only collected
+struct RecordBlock {
+    builder: RecordBuilder,
+    raw_lines: Vec<String>,
+    failure: Option<(ParserContext, ParserError)>,
+}
+impl RecordBlock {
+    fn new() -> Self {
+        Self {
+            builder: RecordBuilder::default(),
+            raw_lines: Vec::new(),
+            failure: None,
+        }
+    }
+
+    // `content` is the line without comments
+    fn push_line(
+        &mut self,
+        line_num: usize,
+        input_line: &str,
+        content: &str,
+        dialect: &TextDialect,
+    ) {
+        self.raw_lines.push(input_line.to_string());
+        if self.failure.is_some() {
+            return;
+        }
+        if let Err(e) = self.builder.parse_field_from_line(content, dialect) {
+            self.failure = Some((
+                ParserContext::with_line_number_and_line(line_num, input_line.to_string()),
+                e,
+            ));
+        }
+    }
+
+    // assembled record or rejected block, `None` for block without fields
+    fn finish(
+        &mut self,
+        line_num: usize,
+        input_line: &str,
+    ) -> Option<Result<TxRecord, RejectedInput>> {
+        let mut block = std::mem::replace(self, RecordBlock::new());
+        let (context, error) = match block.failure.take() {
+            Some(failure) => failure,
+            None if block.builder.is_dirty() => match block.builder.finalize() {
+                Ok(tx) => return Some(Ok(tx)),
+                Err(e) => (
+                    ParserContext::with_line_number_and_line(line_num, input_line.to_string()),
+                    e,
+                ),
+            },
+            None => return None,
+        };
+        Some(Err(RejectedInput::new(
+            context,
+            error,
+            block.raw_lines.join("\n").into_bytes(),
+        )))
+    }
+}
+
+// key which reads back as the same extra: no standard key, delimiter or comment in it
+fn is_extra_key(name: &str) -> bool {
+    !name.is_empty()
+        && name.trim() == name
+        && name.parse::<TxFieldKey>().is_err()
+        && !name.contains([FIELD_KV_DELIMITER, COMMENT_SYMBOL_1LINE, '"', '\n', '\r'])
+        && !name.contains("/*")
+}
+
+// quotes, backslashes and line breaks of description, tenant, reference and extras are backslash-escaped
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// tenant, reference and extra values are written quoted, hand-written ones may be not
+pub(crate) fn unquote_lenient(value: &str) -> Result<String, ParserError> {
+    if value.starts_with('"') {
+        unescape(unquote(value)?)
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+// unknown escapes and unescaped quotes are kept as is, so older files still parse
+fn unescape(value: &str) -> Result<String, ParserError> {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if '\\' != c {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('"') => unescaped.push('"'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            // closing quote is escaped
+            None => return Err(ParserError::ShellBeQuoted(value.into())),
+        }
+    }
+    Ok(unescaped)
 }
 
 impl DataWriter for TextCodec {
-    fn write<W: Write>(&self, w: &mut W, data: &[TxRecord]) -> Result<(), AppError> {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
         for tx in data {
             self.write_single_record(w, tx)?;
-            writeln!(w).add_write_ctx()?;
+            writeln!(w, "{}", self.write_options.separator.line()).add_write_ctx()?;
         }
         Ok(())
     }
 }
+
+impl StreamingWriter for TextCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        Ok(RecordSink::new(EachRecord::new(self.clone(), w)))
+    }
+}
@@ -1,14 +1,364 @@
+use super::errors::{IoCtxBehavior, ParserContext, ParserError};
+use super::options::{DescriptionLimit, ParseLimits, SinkFlush};
+use super::quarantine::RejectedInput;
+use super::utils::limit_error;
 use crate::domain::tx::*;
 use crate::errors::AppError;
+use crate::validation::Validator;
+use std::borrow::Cow;
 use std::io::{Read, Write};
 
 /// Parses transaction records from any input implementing [`Read`].
+///
+/// The trait is object safe, parsers chosen at runtime can be held as `Box<dyn DataParser>`.
 pub trait DataParser {
     /// Reads all records from stream and returns parsed domain objects.
-    fn parse<R: Read>(&self, r: R) -> Result<Vec<TxRecord>, AppError>;
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError>;
 }
 /// Writes transaction records to any output implementing [`Write`].
+///
+/// The trait is object safe, writers chosen at runtime can be held as `Box<dyn DataWriter>`.
 pub trait DataWriter {
     /// Serializes all provided records into writer in codec-specific format.
-    fn write<W: Write>(&self, w: &mut W, data: &[TxRecord]) -> Result<(), AppError>;
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError>;
+}
+
+/// Parses transaction records lazily, one at a time, without holding all of them in memory.
+pub trait StreamingParser {
+    /// Returns stream of records read from `r` on demand.
+    fn parse_stream<'a, R: Read + 'a>(&self, r: R) -> RecordStream<'a>;
+}
+
+/// Writes transaction records one at a time, without holding all of them in memory.
+pub trait StreamingWriter {
+    /// Opens sink writing records to `w`, file header or CSV header is written at once.
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError>;
+}
+
+/// Parser able to step over malformed records instead of failing on the first one.
+///
+/// Every rejected input is handed over to `on_reject`; returning an error from it aborts parsing.
+pub(crate) trait RecoveringParser {
+    fn parse_recovering<R: Read>(
+        &self,
+        r: R,
+        on_reject: &mut dyn FnMut(RejectedInput) -> Result<(), AppError>,
+    ) -> Result<Vec<TxRecord>, AppError>;
+}
+
+/// Next record read from input: fatal error or either parsed record or rejected input.
+pub(crate) type RecordOutcome = Result<Result<TxRecord, RejectedInput>, AppError>;
+
+/// Incremental source of records behind batch, recovering and streaming parsing.
+pub(crate) trait RecordReader {
+    /// Reads next record, `None` at the end of input.
+    fn next_record(&mut self) -> Option<RecordOutcome>;
+}
+
+// readers keeping state past the end of input, e.g. batch header, are drained by reference
+impl<T: RecordReader + ?Sized> RecordReader for &mut T {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        (**self).next_record()
+    }
+}
+
+// drains reader handing rejected inputs over to `on_reject`, the first record exceeding
+// `limits` stops it
+pub(crate) fn collect_recovering(
+    mut reader: impl RecordReader,
+    limits: ParseLimits,
+    on_reject: &mut dyn FnMut(RejectedInput) -> Result<(), AppError>,
+) -> Result<Vec<TxRecord>, AppError> {
+    let mut result = Vec::new();
+    while let Some(outcome) = reader.next_record() {
+        match outcome? {
+            Ok(tx) => {
+                limits.check_record(&tx, result.len())?;
+                result.push(tx);
+            }
+            Err(rejected) => on_reject(rejected)?,
+        }
+    }
+    Ok(result)
+}
+
+// records of a codec which decodes its input as a whole
+struct ParsedRecords {
+    records: std::vec::IntoIter<TxRecord>,
+    error: Option<AppError>,
+}
+
+impl RecordReader for ParsedRecords {
+    fn next_record(&mut self) -> Option<RecordOutcome> {
+        match self.error.take() {
+            Some(e) => Some(Err(e)),
+            None => self.records.next().map(|tx| Ok(Ok(tx))),
+        }
+    }
+}
+
+/// Records parsed on demand, see [`StreamingParser`].
+///
+/// Malformed record yields its error and parsing continues with the next one;
+/// after IO or framing errors the stream ends.
+pub struct RecordStream<'a> {
+    reader: Option<Box<dyn RecordReader + 'a>>,
+    require_records: bool,
+    yielded: bool,
+    limits: ParseLimits,
+    validation: Validator,
+    // records parsed so far
+    records: usize,
+}
+
+impl<'a> RecordStream<'a> {
+    pub(crate) fn new(reader: impl RecordReader + 'a) -> Self {
+        Self {
+            reader: Some(Box::new(reader)),
+            require_records: false,
+            yielded: false,
+            limits: ParseLimits::unlimited(),
+            validation: Validator::new(),
+            records: 0,
+        }
+    }
+
+    // stream over result of a codec without incremental parsing
+    pub(crate) fn from_parsed(parsed: Result<Vec<TxRecord>, AppError>) -> Self {
+        let (records, error) = match parsed {
+            Ok(records) => (records, None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+        Self::new(ParsedRecords {
+            records: records.into_iter(),
+            error,
+        })
+    }
+
+    // stream ends with error on the first record exceeding `limits`
+    pub(crate) fn limited(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // stream ends with error on the first record violating record rules of `validation`
+    // (strict mode)
+    pub(crate) fn validated(mut self, validation: Validator) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    // stream without any items ends with `EmptyInput` error (strict mode)
+    pub(crate) fn require_records(mut self) -> Self {
+        self.require_records = true;
+        self
+    }
+}
+
+impl Iterator for RecordStream<'_> {
+    type Item = Result<TxRecord, AppError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = match self.reader.as_mut()?.next_record() {
+            None => {
+                self.reader = None;
+                if self.require_records && !self.yielded {
+                    Err(AppError::ParsingError {
+                        context: ParserContext::with_position(0),
+                        source: ParserError::EmptyInput,
+                    })
+                } else {
+                    return None;
+                }
+            }
+            Some(Ok(Ok(tx))) => {
+                self.records += 1;
+                let index = self.records - 1;
+                match self
+                    .limits
+                    .check_record(&tx, index)
+                    .and_then(|()| self.validation.enforce_record(&tx, index))
+                {
+                    Ok(()) => Ok(tx),
+                    Err(e) => {
+                        self.reader = None;
+                        Err(e)
+                    }
+                }
+            }
+            Some(Ok(Err(rejected))) => Err(rejected.into_error()),
+            Some(Err(e)) => {
+                self.reader = None;
+                Err(limit_error(e))
+            }
+        };
+        self.yielded = true;
+        Some(item)
+    }
+}
+
+/// Incremental destination of records behind [`RecordSink`].
+pub(crate) trait RecordEncoder {
+    /// Encodes next record, codec may keep it buffered till the end of its block.
+    fn push(&mut self, tx: &TxRecord) -> Result<(), AppError>;
+    /// Flushes output of encoded records, records kept for the current block stay buffered.
+    fn flush(&mut self) -> Result<(), AppError>;
+    /// Writes everything still buffered and flushes output.
+    fn finish(&mut self) -> Result<(), AppError>;
+}
+
+// sink of a codec writing every record independently of others
+pub(crate) struct EachRecord<C, W> {
+    codec: C,
+    w: W,
+    separator: Option<&'static str>,
+    written: bool,
+}
+
+impl<C: DataWriter, W: Write> EachRecord<C, W> {
+    pub(crate) fn new(codec: C, w: W) -> Self {
+        Self {
+            codec,
+            w,
+            separator: None,
+            written: false,
+        }
+    }
+
+    // records are separated with `separator`, nothing follows the last one
+    pub(crate) fn separated_by(mut self, separator: &'static str) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+}
+
+impl<C: DataWriter, W: Write> RecordEncoder for EachRecord<C, W> {
+    fn push(&mut self, tx: &TxRecord) -> Result<(), AppError> {
+        if let (Some(separator), true) = (self.separator, self.written) {
+            self.w.write_all(separator.as_bytes()).add_write_ctx()?;
+        }
+        self.written = true;
+        self.codec.write(&mut self.w, std::slice::from_ref(tx))
+    }
+
+    fn flush(&mut self) -> Result<(), AppError> {
+        self.w.flush().add_write_ctx()
+    }
+
+    fn finish(&mut self) -> Result<(), AppError> {
+        self.w.flush().add_write_ctx()
+    }
+}
+
+// sink of a codec writing records by blocks of `block_records`
+pub(crate) struct Blocks<C, W> {
+    codec: C,
+    w: W,
+    block_records: usize,
+    records: Vec<TxRecord>,
+}
+
+impl<C: DataWriter, W: Write> Blocks<C, W> {
+    pub(crate) fn new(codec: C, w: W, block_records: usize) -> Self {
+        Self {
+            codec,
+            w,
+            block_records,
+            records: Vec::new(),
+        }
+    }
+
+    // codec which needs all records up front, they are written by `finish`
+    pub(crate) fn whole(codec: C, w: W) -> Self {
+        Self::new(codec, w, usize::MAX)
+    }
+
+    fn write_block(&mut self) -> Result<(), AppError> {
+        self.codec.write(&mut self.w, &self.records)?;
+        self.records.clear();
+        Ok(())
+    }
+}
+
+impl<C: DataWriter, W: Write> RecordEncoder for Blocks<C, W> {
+    fn push(&mut self, tx: &TxRecord) -> Result<(), AppError> {
+        self.records.push(tx.clone());
+        if self.records.len() == self.block_records {
+            self.write_block()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), AppError> {
+        self.w.flush().add_write_ctx()
+    }
+
+    fn finish(&mut self) -> Result<(), AppError> {
+        if !self.records.is_empty() || usize::MAX == self.block_records {
+            self.write_block()?;
+        }
+        self.w.flush().add_write_ctx()
+    }
+}
+
+/// Records written on demand, see [`StreamingWriter`].
+///
+/// [`RecordSink::finish`] has to be called once all records are pushed: formats written
+/// by blocks keep the last one in memory and some need all records before writing anything.
+pub struct RecordSink<'a> {
+    encoder: Box<dyn RecordEncoder + 'a>,
+    description_limit: Option<DescriptionLimit>,
+    flush: SinkFlush,
+    // records pushed so far
+    pushed: usize,
+}
+
+impl<'a> RecordSink<'a> {
+    pub(crate) fn new(encoder: impl RecordEncoder + 'a) -> Self {
+        Self {
+            encoder: Box::new(encoder),
+            description_limit: None,
+            flush: SinkFlush::OnFinish,
+            pushed: 0,
+        }
+    }
+
+    pub(crate) fn limited(mut self, description_limit: Option<DescriptionLimit>) -> Self {
+        self.description_limit = description_limit;
+        self
+    }
+
+    pub(crate) fn flushing(mut self, flush: SinkFlush) -> Self {
+        self.flush = flush;
+        self
+    }
+
+    /// Writes next record, unless its description is over the limit of records to skip.
+    ///
+    /// Output is flushed afterwards when [`SinkFlush`] policy says so.
+    pub fn push(&mut self, tx: &TxRecord) -> Result<(), AppError> {
+        self.pushed += 1;
+        let tx = match self.description_limit {
+            Some(limit) => limit.apply(tx, self.pushed)?,
+            None => Some(Cow::Borrowed(tx)),
+        };
+        if let Some(tx) = tx {
+            self.encoder.push(&tx)?;
+        }
+        match self.flush {
+            SinkFlush::EachRecord => self.flush(),
+            SinkFlush::Every(n) if self.pushed.is_multiple_of(n.get()) => self.flush(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Flushes output of records written so far, records of unfinished block are not written.
+    pub fn flush(&mut self) -> Result<(), AppError> {
+        self.encoder.flush()
+    }
+
+    /// Writes all pushed records still buffered and flushes output.
+    pub fn finish(mut self) -> Result<(), AppError> {
+        self.encoder.finish()
+    }
 }
@@ -1,9 +1,767 @@
-use super::errors::ParserError;
+use std::fmt::Display;
+use std::io::{Read, Write};
+
+use super::errors::{IoCtxBehavior, ParserContext, ParserError};
+use super::options::TextEncoding;
+use crate::errors::AppError;
+
+const MAX_VARINT_BYTES: usize = 10;
 
 // unquote description
-pub(super) fn unquote<'a>(value: &'a str) -> Result<&'a str, ParserError> {
+pub(crate) fn unquote(value: &str) -> Result<&str, ParserError> {
     value
         .strip_prefix('"')
         .and_then(|s| s.strip_suffix('"'))
         .ok_or_else(|| ParserError::ShellBeQuoted(value.into()))
 }
+
+// days since Unix epoch for proleptic Gregorian date
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// proleptic Gregorian date for days since Unix epoch, inverse of `days_from_civil`
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// formats milliseconds since Unix epoch as `YYYY-MM-DD` (UTC)
+pub(crate) fn format_iso8601_date(millis: u64) -> String {
+    let days = i64::try_from(millis / 86_400_000).unwrap_or(i64::MAX / 2);
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// formats milliseconds since Unix epoch as `YYYY-MM-DDTHH:MM:SS.fffZ`
+pub(crate) fn format_iso8601(millis: u64) -> String {
+    let ms_of_day = millis % 86_400_000;
+    format!(
+        "{}T{:02}:{:02}:{:02}.{:03}Z",
+        format_iso8601_date(millis),
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000
+    )
+}
+
+// formats milliseconds since Unix epoch as local time `offset` minutes ahead of UTC,
+// e.g. `2023-11-14T23:13:20.000+01:00`; UTC one ends with `Z`
+pub(crate) fn format_iso8601_zoned(millis: u64, offset: i64) -> String {
+    if 0 == offset {
+        return format_iso8601(millis);
+    }
+    let mut formatted = format_iso8601(millis.saturating_add_signed(offset * 60_000));
+    formatted.pop();
+    formatted + &format_utc_offset(offset)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn parse_fixed_digits(value: &str, range: std::ops::Range<usize>) -> Option<i64> {
+    let digits = value.get(range)?;
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+// parses `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS[.fff][Z|+HH:MM|-HH:MM]` into milliseconds since Unix epoch
+pub(crate) fn parse_iso8601(value: &str) -> Result<u64, ParserError> {
+    parse_iso8601_zoned(value, 0).map(|(millis, _)| millis)
+}
+
+// parses like `parse_iso8601`, values without offset are local time `default_offset` minutes
+// ahead of UTC; returns milliseconds since Unix epoch and offset of the value in minutes
+pub(crate) fn parse_iso8601_zoned(
+    value: &str,
+    default_offset: i64,
+) -> Result<(u64, i64), ParserError> {
+    let invalid = || ParserError::UnparsableValue(value.into());
+    let bytes = value.as_bytes();
+    if bytes.len() < 10 || b'-' != bytes[4] || b'-' != bytes[7] {
+        return Err(invalid());
+    }
+    let year = parse_fixed_digits(value, 0..4).ok_or_else(invalid)?;
+    let month = parse_fixed_digits(value, 5..7).ok_or_else(invalid)?;
+    let day = parse_fixed_digits(value, 8..10).ok_or_else(invalid)?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return Err(invalid());
+    }
+    let mut millis = days_from_civil(year, month, day) * 86_400_000;
+    let mut offset = default_offset;
+
+    let time = &value[10..];
+    if !time.is_empty() {
+        let time = time
+            .strip_prefix('T')
+            .or_else(|| time.strip_prefix(' '))
+            .ok_or_else(invalid)?;
+        let tb = time.as_bytes();
+        if tb.len() < 8 || b':' != tb[2] || b':' != tb[5] {
+            return Err(invalid());
+        }
+        let hour = parse_fixed_digits(time, 0..2).ok_or_else(invalid)?;
+        let minute = parse_fixed_digits(time, 3..5).ok_or_else(invalid)?;
+        let second = parse_fixed_digits(time, 6..8).ok_or_else(invalid)?;
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(invalid());
+        }
+        millis += ((hour * 60 + minute) * 60 + second) * 1000;
+
+        let mut rest = &time[8..];
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+            if 0 == len {
+                return Err(invalid());
+            }
+            let ms_digits = format!("{:0<3}", &fraction[..len.min(3)]);
+            millis += ms_digits.parse::<i64>().map_err(|_| invalid())?;
+            rest = &fraction[len..];
+        }
+        if !rest.is_empty() {
+            offset = parse_utc_offset(rest).ok_or_else(invalid)?;
+        }
+    }
+    millis -= offset * 60_000;
+    u64::try_from(millis)
+        .map(|millis| (millis, offset))
+        .map_err(|_| invalid())
+}
+
+// parses `Z`, `+HH:MM` or `-HH:MM` into minutes
+pub(crate) fn parse_utc_offset(value: &str) -> Option<i64> {
+    if "Z" == value || "z" == value {
+        return Some(0);
+    }
+    let sign = match value.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    if 6 != value.len() || b':' != value.as_bytes()[3] {
+        return None;
+    }
+    let hours = parse_fixed_digits(value, 1..3)?;
+    let minutes = parse_fixed_digits(value, 4..6)?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes))
+}
+
+// formats offset in minutes as `+HH:MM` or `-HH:MM`
+pub(crate) fn format_utc_offset(minutes: i64) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    let minutes = minutes.unsigned_abs();
+    format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+// parses decimal like `-12.3` into minor units with provided scale (`-1230` for scale 2)
+pub(crate) fn parse_decimal_minor_units(value: &str, scale: u32) -> Result<i64, ParserError> {
+    let invalid = || ParserError::UnparsableValue(value.into());
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !all_digits(whole) || !all_digits(fraction) {
+        return Err(invalid());
+    }
+    if fraction.len() > scale as usize {
+        return Err(invalid());
+    }
+    let units = format!("{}{:0<width$}", whole, fraction, width = scale as usize)
+        .parse::<i128>()
+        .map_err(|_| invalid())?;
+    i64::try_from(if negative { -units } else { units }).map_err(|_| invalid())
+}
+
+// formats minor units as decimal with provided scale (`-1230` is `-12.30` for scale 2)
+pub(crate) fn format_decimal_minor_units(units: i64, scale: u32) -> String {
+    let digits = format!(
+        "{:0>width$}",
+        units.unsigned_abs(),
+        width = scale as usize + 1
+    );
+    let (whole, fraction) = digits.split_at(digits.len() - scale as usize);
+    let sign = if units < 0 { "-" } else { "" };
+    if fraction.is_empty() {
+        format!("{}{}", sign, whole)
+    } else {
+        format!("{}{}.{}", sign, whole, fraction)
+    }
+}
+
+// zigzag maps signed values to unsigned ones so small magnitudes of both signs stay short
+pub(crate) fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+pub(crate) fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+// appends LEB128 encoding of value, 7 bits per byte with continuation bit
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+// reads LEB128 value, returns it with number of bytes consumed
+pub(crate) fn read_varint<R: Read>(r: &mut R) -> std::io::Result<(u64, usize)> {
+    let mut value = 0u64;
+    for i in 0..MAX_VARINT_BYTES {
+        let mut b = [0u8; 1];
+        r.read_exact(&mut b)?;
+        let bits = u64::from(b[0] & 0x7F);
+        if MAX_VARINT_BYTES - 1 == i && bits > 1 {
+            break;
+        }
+        value |= bits << (7 * i);
+        if 0 == b[0] & 0x80 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "varint overflows 64 bits",
+    ))
+}
+
+// holds line breaks back until something else is written, so output never ends with them
+pub(crate) struct TrailingNewlineTrimmer<W: Write> {
+    w: W,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> TrailingNewlineTrimmer<W> {
+    pub(crate) fn new(w: W) -> Self {
+        Self {
+            w,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for TrailingNewlineTrimmer<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match buf.iter().rposition(|&b| b != b'\n' && b != b'\r') {
+            Some(last) => {
+                self.w.write_all(&self.pending)?;
+                self.w.write_all(&buf[..=last])?;
+                self.pending.clear();
+                self.pending.extend_from_slice(&buf[last + 1..]);
+            }
+            None => self.pending.extend_from_slice(buf),
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.w.flush()
+    }
+}
+
+// reader failing once a line gets longer than `max_line` bytes, so line-based codecs never
+// buffer it; bytes preceding the long line are passed on first
+pub(crate) struct LineLimited<R> {
+    r: R,
+    max_line: usize,
+    line_len: usize,
+    pos: usize,
+    exceeded: Option<LineTooLong>,
+}
+
+impl<R: Read> LineLimited<R> {
+    pub(crate) fn new(r: R, max_line: usize) -> Self {
+        Self {
+            r,
+            max_line,
+            line_len: 0,
+            pos: 0,
+            exceeded: None,
+        }
+    }
+}
+
+impl<R: Read> Read for LineLimited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(exceeded) = self.exceeded {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                exceeded,
+            ));
+        }
+        let n = self.r.read(buf)?;
+        for (i, &b) in buf[..n].iter().enumerate() {
+            if b'\n' == b {
+                self.line_len = 0;
+                continue;
+            }
+            self.line_len += 1;
+            if self.line_len > self.max_line {
+                self.exceeded = Some(LineTooLong {
+                    position: self.pos + i + 1 - self.line_len,
+                    max_line: self.max_line,
+                });
+                if 0 == i {
+                    return self.read(buf);
+                }
+                self.pos += i;
+                return Ok(i);
+            }
+        }
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+// read error of `LineLimited`, turned into parser error by `limit_error`
+#[derive(Debug, Clone, Copy)]
+struct LineTooLong {
+    position: usize,
+    max_line: usize,
+}
+
+impl std::error::Error for LineTooLong {}
+
+impl Display for LineTooLong {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line longer than {} bytes", self.max_line)
+    }
+}
+
+// reader failing once more than `max` bytes are read, bytes up to the limit are passed on
+pub(crate) struct SizeLimited<R> {
+    r: R,
+    max: usize,
+    pos: usize,
+    exceeded: bool,
+}
+
+impl<R: Read> SizeLimited<R> {
+    pub(crate) fn new(r: R, max: usize) -> Self {
+        Self {
+            r,
+            max,
+            pos: 0,
+            exceeded: false,
+        }
+    }
+}
+
+impl<R: Read> Read for SizeLimited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.exceeded {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                InputTooLarge { max: self.max },
+            ));
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        // one byte over the limit tells it is exceeded
+        let allowed = (self.max - self.pos).saturating_add(1).min(buf.len());
+        let n = self.r.read(&mut buf[..allowed])?;
+        if self.pos + n > self.max {
+            self.exceeded = true;
+            let within = self.max - self.pos;
+            self.pos = self.max;
+            return if 0 == within {
+                self.read(buf)
+            } else {
+                Ok(within)
+            };
+        }
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+// read error of `SizeLimited`, turned into parser error by `limit_error`
+#[derive(Debug, Clone, Copy)]
+struct InputTooLarge {
+    max: usize,
+}
+
+impl std::error::Error for InputTooLarge {}
+
+impl Display for InputTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "input over {} bytes", self.max)
+    }
+}
+
+// whole input of codecs decoding it at once, rejected as soon as it grows over `max` bytes
+pub(crate) fn read_limited(r: &mut dyn Read, max: usize) -> Result<Vec<u8>, AppError> {
+    let mut input = Vec::new();
+    r.take((max as u64).saturating_add(1))
+        .read_to_end(&mut input)
+        .add_read_ctx()?;
+    if input.len() > max {
+        return Err(AppError::ParsingError {
+            context: ParserContext::with_position(max),
+            source: ParserError::LimitExceeded(format!("input over {} bytes", max)),
+        });
+    }
+    Ok(input)
+}
+
+// read error of too long line becomes parser error at the line start, the one of too large
+// input at the limit
+pub(crate) fn limit_error(e: AppError) -> AppError {
+    let AppError::ReadError(io) = &e else {
+        return e;
+    };
+    let Some(inner) = io.get_ref() else {
+        return e;
+    };
+    let (position, what) = if let Some(too_long) = inner.downcast_ref::<LineTooLong>() {
+        (too_long.position, too_long.to_string())
+    } else if let Some(too_large) = inner.downcast_ref::<InputTooLarge>() {
+        (too_large.max, too_large.to_string())
+    } else {
+        return e;
+    };
+    AppError::ParsingError {
+        context: ParserContext::with_position(position),
+        source: ParserError::LimitExceeded(what),
+    }
+}
+
+// reader of text input in `encoding`, passing it on as UTF-8 without byte order mark
+pub(crate) struct Decoded<R> {
+    r: R,
+    encoding: TextEncoding,
+    started: bool,
+    // undecoded input, the first bytes following byte order mark included
+    input: Vec<u8>,
+    // decoded bytes not read yet
+    output: Vec<u8>,
+    output_pos: usize,
+}
+
+impl<R: Read> Decoded<R> {
+    pub(crate) fn new(r: R, encoding: TextEncoding) -> Self {
+        Self {
+            r,
+            encoding,
+            started: false,
+            input: Vec::new(),
+            output: Vec::new(),
+            output_pos: 0,
+        }
+    }
+
+    // byte order mark resolves detected encoding and is skipped
+    fn start(&mut self) -> std::io::Result<()> {
+        self.started = true;
+        (&mut self.r).take(3).read_to_end(&mut self.input)?;
+        let (encoding, bom_len) = match (self.encoding, self.input.as_slice()) {
+            (TextEncoding::Utf8 | TextEncoding::Detect, [0xEF, 0xBB, 0xBF, ..]) => {
+                (TextEncoding::Utf8, 3)
+            }
+            (TextEncoding::Detect | TextEncoding::Utf16Le, [0xFF, 0xFE, ..]) => {
+                (TextEncoding::Utf16Le, 2)
+            }
+            (TextEncoding::Detect | TextEncoding::Utf16Be, [0xFE, 0xFF, ..]) => {
+                (TextEncoding::Utf16Be, 2)
+            }
+            (TextEncoding::Detect, _) => (TextEncoding::Utf8, 0),
+            (encoding, _) => (encoding, 0),
+        };
+        self.encoding = encoding;
+        self.input.drain(..bom_len);
+        if TextEncoding::Utf8 == encoding {
+            self.output = std::mem::take(&mut self.input);
+        }
+        Ok(())
+    }
+
+    // decodes next chunk of UTF-16 input, nothing is decoded at the end of input
+    fn decode_utf16(&mut self) -> std::io::Result<()> {
+        let invalid = |message: &str| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+        };
+        let mut chunk = [0u8; 4096];
+        self.output.clear();
+        self.output_pos = 0;
+        while self.output.is_empty() {
+            let n = self.r.read(&mut chunk)?;
+            self.input.extend_from_slice(&chunk[..n]);
+            let mut units: Vec<u16> = self
+                .input
+                .chunks_exact(2)
+                .map(|pair| match self.encoding {
+                    TextEncoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+                    _ => u16::from_le_bytes([pair[0], pair[1]]),
+                })
+                .collect();
+            // high surrogate waits for its pair from the next chunk
+            let split_pair = 0 != n && units.last().is_some_and(|u| (0xD800..0xDC00).contains(u));
+            if split_pair {
+                units.pop();
+            }
+            self.input.drain(..units.len() * 2);
+            for c in char::decode_utf16(units) {
+                let c = c.map_err(|_| invalid("unpaired surrogate in UTF-16 input"))?;
+                let mut utf8 = [0u8; 4];
+                self.output
+                    .extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+            }
+            if 0 == n {
+                if !self.input.is_empty() && self.output.is_empty() {
+                    return Err(invalid("UTF-16 input ends with odd byte"));
+                }
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Decoded<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if !self.started {
+            self.start()?;
+        }
+        if self.output_pos == self.output.len() {
+            if TextEncoding::Utf8 == self.encoding {
+                return self.r.read(buf);
+            }
+            self.decode_utf16()?;
+        }
+        let pending = &self.output[self.output_pos..];
+        let n = pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&pending[..n]);
+        self.output_pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests_utils {
+    use super::*;
+
+    #[test]
+    fn iso8601_dates_and_times() {
+        assert_eq!(parse_iso8601("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_iso8601("2024-01-03").unwrap(), 1_704_240_000_000);
+        assert_eq!(
+            parse_iso8601("2024-01-03T10:15:30Z").unwrap(),
+            1_704_276_930_000
+        );
+        assert_eq!(
+            parse_iso8601("2024-01-03T10:15:30.5").unwrap(),
+            1_704_276_930_500
+        );
+        assert_eq!(
+            parse_iso8601("2024-01-03T12:15:30+02:00").unwrap(),
+            1_704_276_930_000
+        );
+        assert_eq!(
+            parse_iso8601("2024-02-29T00:00:00Z").unwrap(),
+            1_709_164_800_000
+        );
+    }
+
+    #[test]
+    fn iso8601_date_formatting() {
+        assert_eq!(format_iso8601_date(0), "1970-01-01");
+        assert_eq!(format_iso8601_date(1_704_276_930_000), "2024-01-03");
+        assert_eq!(format_iso8601_date(1_709_164_800_000), "2024-02-29");
+        assert_eq!(format_iso8601_date(1_709_164_799_999), "2024-02-28");
+        assert_eq!(
+            format_iso8601(1_704_276_930_500),
+            "2024-01-03T10:15:30.500Z"
+        );
+        let millis = parse_iso8601(&format_iso8601(1_709_164_799_999)).unwrap();
+        assert_eq!(millis, 1_709_164_799_999);
+    }
+
+    #[test]
+    fn iso8601_rejects_invalid_values() {
+        for value in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-1-01",
+            "2024-01-03T25:00:00",
+            "2024-01-03T10:00",
+            "2024-01-03T10:00:00+0200",
+            "1969-12-31",
+            "yesterday",
+        ] {
+            assert!(parse_iso8601(value).is_err(), "{} should fail", value);
+        }
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut out = Vec::new();
+            write_varint(&mut out, value);
+            assert_eq!(
+                read_varint(&mut out.as_slice()).unwrap(),
+                (value, out.len())
+            );
+        }
+        for value in [0, -1, 1, i64::MIN, i64::MAX] {
+            assert_eq!(zigzag_decode(zigzag_encode(value)), value);
+        }
+        assert_eq!(zigzag_encode(-1), 1);
+        assert!(read_varint(&mut [0xFFu8; 11].as_slice()).is_err());
+        assert!(read_varint(&mut [0x80u8].as_slice()).is_err());
+    }
+
+    #[test]
+    fn decimal_minor_units() {
+        assert_eq!(parse_decimal_minor_units("12.34", 2).unwrap(), 1234);
+        assert_eq!(parse_decimal_minor_units("-12.3", 2).unwrap(), -1230);
+        assert_eq!(parse_decimal_minor_units("100", 0).unwrap(), 100);
+        assert_eq!(parse_decimal_minor_units("1.005", 3).unwrap(), 1005);
+        assert!(parse_decimal_minor_units("1.005", 2).is_err());
+        assert!(parse_decimal_minor_units(".5", 2).is_err());
+        assert!(parse_decimal_minor_units("1,5", 2).is_err());
+
+        for (units, scale, formatted) in [
+            (1234, 2, "12.34"),
+            (-5, 2, "-0.05"),
+            (100, 0, "100"),
+            (0, 3, "0.000"),
+            (i64::MIN, 2, "-92233720368547758.08"),
+        ] {
+            assert_eq!(format_decimal_minor_units(units, scale), formatted);
+            assert_eq!(parse_decimal_minor_units(formatted, scale).unwrap(), units);
+        }
+    }
+
+    #[test]
+    fn trailing_newlines_are_trimmed() {
+        let mut out = Vec::new();
+        let mut w = TrailingNewlineTrimmer::new(&mut out);
+        for chunk in ["a\n", "\r\n", "b\n\n", "\n"] {
+            w.write_all(chunk.as_bytes()).unwrap();
+        }
+        assert_eq!(out, b"a\n\r\nb");
+    }
+
+    #[test]
+    fn lines_over_limit_fail_after_preceding_bytes() {
+        let mut r = LineLimited::new(&b"abc\nabcdef\n"[..], 4);
+        let mut buf = [0u8; 16];
+        assert_eq!(r.read(&mut buf).unwrap(), 8);
+        assert_eq!(&buf[..8], b"abc\nabcd");
+        let e = limit_error(AppError::ReadError(r.read(&mut buf).unwrap_err()));
+        assert!(matches!(
+            e,
+            AppError::ParsingError {
+                context: ParserContext::Position { position: 4 },
+                source: ParserError::LimitExceeded(_),
+            }
+        ));
+
+        let mut out = Vec::new();
+        LineLimited::new(&b"abcd\nabcd"[..], 4)
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"abcd\nabcd");
+    }
+
+    #[test]
+    fn input_over_limit_fails_at_the_limit() {
+        let mut out = Vec::new();
+        let e = SizeLimited::new(&b"abcdef"[..], 4)
+            .read_to_end(&mut out)
+            .unwrap_err();
+        assert_eq!(out, b"abcd");
+        assert!(matches!(
+            limit_error(AppError::ReadError(e)),
+            AppError::ParsingError {
+                context: ParserContext::Position { position: 4 },
+                source: ParserError::LimitExceeded(_),
+            }
+        ));
+
+        let mut out = Vec::new();
+        SizeLimited::new(&b"abcd"[..], 4)
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, b"abcd");
+    }
+
+    // reader returning a byte at a time
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    fn decoded(input: &[u8], encoding: TextEncoding) -> std::io::Result<String> {
+        let mut out = String::new();
+        Decoded::new(Trickle(input), encoding).read_to_string(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn text_input_is_decoded_to_utf8() {
+        let text = "TX_ID: 1 \u{1F4B6}å";
+        let utf16 = |be: bool| -> Vec<u8> {
+            text.encode_utf16()
+                .flat_map(|u| if be { u.to_be_bytes() } else { u.to_le_bytes() })
+                .collect()
+        };
+        let with_bom = |bom: &[u8], body: &[u8]| [bom, body].concat();
+
+        for encoding in [TextEncoding::Utf8, TextEncoding::Detect] {
+            let input = with_bom(&[0xEF, 0xBB, 0xBF], text.as_bytes());
+            assert_eq!(decoded(&input, encoding).unwrap(), text);
+            assert_eq!(decoded(text.as_bytes(), encoding).unwrap(), text);
+        }
+        let le = with_bom(&[0xFF, 0xFE], &utf16(false));
+        let be = with_bom(&[0xFE, 0xFF], &utf16(true));
+        assert_eq!(decoded(&le, TextEncoding::Detect).unwrap(), text);
+        assert_eq!(decoded(&be, TextEncoding::Detect).unwrap(), text);
+        assert_eq!(decoded(&le, TextEncoding::Utf16Le).unwrap(), text);
+        assert_eq!(decoded(&utf16(true), TextEncoding::Utf16Be).unwrap(), text);
+        assert_eq!(decoded(b"", TextEncoding::Detect).unwrap(), "");
+
+        // UTF-16 is not detected without byte order mark
+        assert_eq!(decoded(b"T\0X\0", TextEncoding::Detect).unwrap(), "T\0X\0");
+        let mut odd = utf16(false);
+        odd.pop();
+        assert!(decoded(&odd, TextEncoding::Utf16Le).is_err());
+        assert!(decoded(&[0x00, 0xDC], TextEncoding::Utf16Le).is_err());
+    }
+}
@@ -0,0 +1,548 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use super::base::TxFieldKey;
+use super::errors::{IoCtxBehavior, ParserContext, ParserCtxBehavior, ParserError};
+use super::options::{AmountFormat, ParseLimits};
+use super::quarantine::RejectedInput;
+use super::schema::{RecordFields, TxSchema, ValueFormats};
+use super::traits::{
+    Blocks, DataParser, DataWriter, RecordSink, RecordStream, RecoveringParser, StreamingParser,
+    StreamingWriter,
+};
+use super::utils::read_limited;
+use super::xml::XmlElement;
+use super::zip::{ZipArchive, ZipBuilder};
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+const WORKBOOK_PATH: &str = "xl/workbook.xml";
+const WORKBOOK_RELS_PATH: &str = "xl/_rels/workbook.xml.rels";
+const SHARED_STRINGS_PATH: &str = "xl/sharedStrings.xml";
+const DEFAULT_SHEET_PATH: &str = "xl/worksheets/sheet1.xml";
+
+const CONTENT_TYPES_XML: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+    r#"<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+    r#"<Default Extension="xml" ContentType="application/xml"/>"#,
+    r#"<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>"#,
+    r#"<Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+    r#"</Types>"#
+);
+const ROOT_RELS_XML: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>"#,
+    r#"</Relationships>"#
+);
+const WORKBOOK_XML: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" "#,
+    r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">"#,
+    r#"<sheets><sheet name="Transactions" sheetId="1" r:id="rId1"/></sheets>"#,
+    r#"</workbook>"#
+);
+const WORKBOOK_RELS_XML: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+    r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/>"#,
+    r#"</Relationships>"#
+);
+
+// worksheet row number and its non-empty cells by column index
+type SheetRow = (usize, BTreeMap<usize, String>);
+
+/// Reader and writer of Excel workbooks.
+///
+/// Records are rows of the first worksheet, its first row names `TxFieldKey` of every column.
+#[derive(Clone, Default)]
+pub(crate) struct XlsxCodec {
+    limits: ParseLimits,
+}
+impl XlsxCodec {
+    pub(crate) fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn read_xml(&self, archive: &ZipArchive, path: &str) -> Result<Option<XmlElement>, AppError> {
+        let Some(bytes) = archive
+            .read(path, self.limits.max_block_size)
+            .add_parser_ctx(ParserContext::with_position(0))?
+        else {
+            return Ok(None);
+        };
+        let text = String::from_utf8(bytes)
+            .map_err(|_| ParserError::UnparsableValue(format!("non utf-8 {}", path)))
+            .add_parser_ctx(ParserContext::with_position(0))?;
+        XmlElement::parse_document(&text)
+            .map(Some)
+            .map_err(|(position, source)| AppError::ParsingError {
+                context: ParserContext::with_position(position),
+                source,
+            })
+    }
+
+    // first sheet of workbook resolved through workbook relationships
+    fn first_sheet_path(&self, archive: &ZipArchive) -> Result<String, AppError> {
+        let workbook = self.read_xml(archive, WORKBOOK_PATH)?;
+        let rels = self.read_xml(archive, WORKBOOK_RELS_PATH)?;
+        let (Some(workbook), Some(rels)) = (workbook, rels) else {
+            return Ok(DEFAULT_SHEET_PATH.into());
+        };
+        let target = workbook
+            .path(&["sheets", "sheet"])
+            .and_then(|sheet| sheet.attribute("id"))
+            .and_then(|id| {
+                rels.children("Relationship")
+                    .find(|rel| rel.attribute("Id") == Some(id))
+            })
+            .and_then(|rel| rel.attribute("Target"));
+        Ok(match target {
+            Some(absolute) if absolute.starts_with('/') => absolute[1..].to_string(),
+            Some(relative) => format!("xl/{}", relative),
+            None => DEFAULT_SHEET_PATH.into(),
+        })
+    }
+
+    fn shared_strings(&self, archive: &ZipArchive) -> Result<Vec<String>, AppError> {
+        Ok(self
+            .read_xml(archive, SHARED_STRINGS_PATH)?
+            .map(|sst| sst.children("si").map(rich_text).collect())
+            .unwrap_or_default())
+    }
+
+    // empty rows are dropped
+    fn read_rows(&self, sheet: &XmlElement, shared: &[String]) -> Result<Vec<SheetRow>, AppError> {
+        let mut rows = Vec::new();
+        let Some(data) = sheet.child("sheetData") else {
+            return Ok(rows);
+        };
+        for (i, row) in data.children("row").enumerate() {
+            let row_num = row
+                .attribute("r")
+                .and_then(|r| r.parse().ok())
+                .unwrap_or(i + 1);
+            let mut cells = BTreeMap::new();
+            for (j, cell) in row.children("c").enumerate() {
+                let column = cell.attribute("r").map_or(Some(j), column_index);
+                let column = column
+                    .ok_or_else(|| {
+                        ParserError::UnparsableValue(cell.attribute("r").unwrap_or_default().into())
+                    })
+                    .add_parser_ctx(ParserContext::with_position(row_num))?;
+                let text = cell_text(cell, shared)
+                    .add_parser_ctx(ParserContext::with_position(row_num))?;
+                if !text.is_empty() {
+                    cells.insert(column, text);
+                }
+            }
+            if !cells.is_empty() {
+                rows.push((row_num, cells));
+            }
+        }
+        Ok(rows)
+    }
+
+    fn parse_header(
+        &self,
+        cells: &BTreeMap<usize, String>,
+    ) -> Result<BTreeMap<usize, TxFieldKey>, ParserError> {
+        let mut columns = BTreeMap::new();
+        for (&column, name) in cells {
+            let field_key: TxFieldKey = name.trim().parse()?;
+            if columns.values().any(|&k| k == field_key) {
+                return Err(ParserError::Duplicate(field_key));
+            }
+            columns.insert(column, field_key);
+        }
+        for field_key in TxSchema::STANDARD.required() {
+            if !columns.values().any(|&k| k == field_key) {
+                return Err(ParserError::MissingField(field_key));
+            }
+        }
+        Ok(columns)
+    }
+
+    fn parse_row(
+        &self,
+        columns: &BTreeMap<usize, TxFieldKey>,
+        cells: &BTreeMap<usize, String>,
+    ) -> Result<TxRecord, ParserError> {
+        // empty cells are not stored by Excel, optional fields of empty ones are absent
+        let mut fields = RecordFields::default();
+        for (column, &field_key) in columns {
+            let required = TxSchema::STANDARD
+                .field(field_key)
+                .is_some_and(|spec| spec.required);
+            match cells.get(column).map(|text| text.trim()) {
+                Some("") if !required => {}
+                Some(text) => fields.parse(field_key, text, &ValueFormats::default())?,
+                None => {}
+            }
+        }
+        fields.build(Default::default())
+    }
+
+    fn sheet_xml(&self, data: &[TxRecord]) -> Result<String, AppError> {
+        let mut header = vec![
+            TxFieldKey::Id,
+            TxFieldKey::TxKind,
+            TxFieldKey::FromUserId,
+            TxFieldKey::ToUserId,
+            TxFieldKey::Amount,
+            TxFieldKey::Timestamp,
+            TxFieldKey::Status,
+            TxFieldKey::Description,
+        ];
+        if data.iter().any(|tx| tx.tenant.is_some()) {
+            header.push(TxFieldKey::Tenant);
+        }
+        if data.iter().any(|tx| tx.fee.is_some()) {
+            header.push(TxFieldKey::Fee);
+        }
+        if data.iter().any(|tx| tx.reference.is_some()) {
+            header.push(TxFieldKey::Reference);
+        }
+        if data.iter().any(|tx| tx.original_amount.is_some()) {
+            header.push(TxFieldKey::OriginalAmount);
+        }
+        if data.iter().any(|tx| tx.exchange_rate.is_some()) {
+            header.push(TxFieldKey::ExchangeRate);
+        }
+
+        let mut xml = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+            r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#
+        ));
+        let header_cells: Vec<Cell> = header.iter().map(|k| Cell::Text(k.to_string())).collect();
+        push_row(&mut xml, 1, &header_cells);
+        for (i, tx) in data.iter().enumerate() {
+            // ids and accounts are text cells, Excel numbers lose precision above 2^53
+            let cells = header
+                .iter()
+                .map(|field_key| {
+                    Ok(match field_key {
+                        TxFieldKey::Id => Cell::Text(tx.id.to_string()),
+                        TxFieldKey::TxKind => Cell::Text(tx.kind.to_string()),
+                        TxFieldKey::FromUserId => Cell::Text(tx.from.to_string()),
+                        TxFieldKey::ToUserId => Cell::Text(tx.to.to_string()),
+                        TxFieldKey::Amount => Cell::Number(
+                            AmountFormat::MinorUnits.format(tx.amount).add_write_ctx()?,
+                        ),
+                        TxFieldKey::Timestamp => Cell::Number(tx.ts.to_string()),
+                        TxFieldKey::Status => Cell::Text(tx.status.to_string()),
+                        TxFieldKey::Description => {
+                            Cell::Text(tx.description.clone().unwrap_or_default())
+                        }
+                        TxFieldKey::Tenant => Cell::Text(tx.tenant.clone().unwrap_or_default()),
+                        TxFieldKey::Fee => match tx.fee {
+                            Some(fee) => {
+                                Cell::Number(AmountFormat::MinorUnits.format(fee).add_write_ctx()?)
+                            }
+                            None => Cell::Text(String::new()),
+                        },
+                        TxFieldKey::Reference => {
+                            Cell::Text(tx.reference.clone().unwrap_or_default())
+                        }
+                        // decimals are text cells, so their scale is kept
+                        TxFieldKey::OriginalAmount => Cell::Text(
+                            tx.original_amount
+                                .map(|amount| amount.to_string())
+                                .unwrap_or_default(),
+                        ),
+                        TxFieldKey::ExchangeRate => Cell::Text(
+                            tx.exchange_rate
+                                .map(|rate| rate.to_string())
+                                .unwrap_or_default(),
+                        ),
+                    })
+                })
+                .collect::<Result<Vec<Cell>, AppError>>()?;
+            push_row(&mut xml, i + 2, &cells);
+        }
+        xml.push_str("</sheetData></worksheet>");
+        Ok(xml)
+    }
+}
+
+impl DataParser for XlsxCodec {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        self.parse_recovering(r, &mut |rejected| Err(rejected.into_error()))
+    }
+}
+
+impl RecoveringParser for XlsxCodec {
+    fn parse_recovering<R: Read>(
+        &self,
+        mut r: R,
+        on_reject: &mut dyn FnMut(RejectedInput) -> Result<(), AppError>,
+    ) -> Result<Vec<TxRecord>, AppError> {
+        let input = read_limited(&mut r, self.limits.max_block_size)?;
+        if input.iter().all(u8::is_ascii_whitespace) {
+            return Ok(vec![]);
+        }
+        let archive = ZipArchive::new(&input).add_parser_ctx(ParserContext::with_position(0))?;
+        let sheet_path = self.first_sheet_path(&archive)?;
+        let sheet = self
+            .read_xml(&archive, &sheet_path)?
+            .ok_or_else(|| ParserError::UnparsableValue(format!("{} is missing", sheet_path)))
+            .add_parser_ctx(ParserContext::with_position(0))?;
+        let shared = self.shared_strings(&archive)?;
+
+        let mut rows = self.read_rows(&sheet, &shared)?.into_iter();
+        let mut result = Vec::new();
+        let Some((header_row, header_cells)) = rows.next() else {
+            return Ok(result);
+        };
+        let columns = self.parse_header(&header_cells).add_parser_ctx(
+            ParserContext::with_line_number_and_line(header_row, row_line(&header_cells)),
+        )?;
+
+        for (row_num, cells) in rows {
+            match self.parse_row(&columns, &cells) {
+                Ok(tx) => {
+                    self.limits.check_record(&tx, result.len())?;
+                    result.push(tx);
+                }
+                Err(e) => {
+                    let line = row_line(&cells);
+                    on_reject(RejectedInput::new(
+                        ParserContext::with_line_number_and_line(row_num, line.clone()),
+                        e,
+                        line.into_bytes(),
+                    ))?
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl StreamingParser for XlsxCodec {
+    // workbook is a zip archive, it is decoded as a whole
+    fn parse_stream<'a, R: Read + 'a>(&self, mut r: R) -> RecordStream<'a> {
+        RecordStream::from_parsed(self.parse(&mut r))
+    }
+}
+
+impl DataWriter for XlsxCodec {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        let mut archive = ZipBuilder::default();
+        archive.add("[Content_Types].xml", CONTENT_TYPES_XML.as_bytes());
+        archive.add("_rels/.rels", ROOT_RELS_XML.as_bytes());
+        archive.add(WORKBOOK_PATH, WORKBOOK_XML.as_bytes());
+        archive.add(WORKBOOK_RELS_PATH, WORKBOOK_RELS_XML.as_bytes());
+        archive.add(DEFAULT_SHEET_PATH, self.sheet_xml(data)?.as_bytes());
+        w.write_all(&archive.finish()).add_write_ctx()
+    }
+}
+
+impl StreamingWriter for XlsxCodec {
+    fn open_sink<'a, W: Write + 'a>(&self, w: W) -> Result<RecordSink<'a>, AppError> {
+        // archive is built in memory as a whole
+        Ok(RecordSink::new(Blocks::whole(self.clone(), w)))
+    }
+}
+
+enum Cell {
+    Text(String),
+    Number(String),
+}
+
+fn push_row(xml: &mut String, row_num: usize, cells: &[Cell]) {
+    xml.push_str(&format!(r#"<row r="{}">"#, row_num));
+    for (column, cell) in cells.iter().enumerate() {
+        let reference = format!("{}{}", column_name(column), row_num);
+        match cell {
+            Cell::Text(text) => xml.push_str(&format!(
+                r#"<c r="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                reference,
+                escape_xml(text)
+            )),
+            Cell::Number(number) => {
+                xml.push_str(&format!(r#"<c r="{}"><v>{}</v></c>"#, reference, number))
+            }
+        }
+    }
+    xml.push_str("</row>");
+}
+
+// text of shared or inline string, rich text runs are concatenated
+fn rich_text(si: &XmlElement) -> String {
+    match si.child("t") {
+        Some(t) => t.text.clone(),
+        None => si
+            .children("r")
+            .filter_map(|run| run.child("t"))
+            .map(|t| t.text.as_str())
+            .collect(),
+    }
+}
+
+fn cell_text(cell: &XmlElement, shared: &[String]) -> Result<String, ParserError> {
+    let value = cell.path_text(&["v"]).unwrap_or_default();
+    match cell.attribute("t").unwrap_or("n") {
+        "s" => value
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| shared.get(i))
+            .cloned()
+            .ok_or_else(|| ParserError::UnparsableValue(format!("shared string {}", value))),
+        "inlineStr" => Ok(cell.child("is").map(rich_text).unwrap_or_default()),
+        "n" => Ok(normalize_number(value)),
+        _ => Ok(value.to_string()),
+    }
+}
+
+// Excel keeps whole numbers as floats in some writers, e.g. `1.7E+12` or `100.0`
+fn normalize_number(value: &str) -> String {
+    if !value.contains(['.', 'e', 'E']) {
+        return value.to_string();
+    }
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() && 0.0 == number.fract() && number.abs() < 9.0e15 => {
+            format!("{}", number as i64)
+        }
+        _ => value.to_string(),
+    }
+}
+
+// `B12` -> 1
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: Vec<u8> = reference
+        .bytes()
+        .take_while(u8::is_ascii_alphabetic)
+        .collect();
+    if letters.is_empty() {
+        return None;
+    }
+    letters
+        .iter()
+        .try_fold(0usize, |acc, &c| {
+            acc.checked_mul(26)?
+                .checked_add((c.to_ascii_uppercase() - b'A') as usize + 1)
+        })
+        .map(|n| n - 1)
+}
+
+// 27 -> `AB`
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+fn row_line(cells: &BTreeMap<usize, String>) -> String {
+    cells
+        .iter()
+        .map(|(column, text)| format!("{}={}", column_name(*column), text))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests_xlsx {
+    use super::*;
+
+    const SHEET: &str = r#"<?xml version="1.0"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+  <sheetData>
+    <row r="1">
+      <c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c><c r="C1" t="s"><v>2</v></c>
+      <c r="D1" t="s"><v>3</v></c><c r="E1" t="s"><v>4</v></c><c r="F1" t="s"><v>5</v></c>
+      <c r="H1" t="s"><v>6</v></c><c r="I1" t="s"><v>7</v></c>
+    </row>
+    <row r="3">
+      <c r="A3"><v>7</v></c><c r="B3" t="s"><v>8</v></c><c r="C3"><v>0</v></c>
+      <c r="D3" t="inlineStr"><is><t>0042</t></is></c><c r="E3"><v>150.0</v></c>
+      <c r="F3"><v>1.7E+12</v></c><c r="H3" t="str"><v>SUCCESS</v></c>
+      <c r="I3" t="inlineStr"><is><r><t>bonus </t></r><r><t>&amp; more</t></r></is></c>
+    </row>
+  </sheetData>
+</worksheet>"#;
+
+    const SHARED: &str = r#"<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<si><t>TX_ID</t></si><si><t>TX_TYPE</t></si><si><t>FROM_USER_ID</t></si><si><t>TO_USER_ID</t></si>
+<si><t>AMOUNT</t></si><si><t>TIMESTAMP</t></si><si><t>STATUS</t></si><si><t>DESCRIPTION</t></si>
+<si><t>DEPOSIT</t></si></sst>"#;
+
+    #[test]
+    fn xlsx_reads_shared_strings_and_sparse_cells() {
+        let mut archive = ZipBuilder::default();
+        archive.add(DEFAULT_SHEET_PATH, SHEET.as_bytes());
+        archive.add(SHARED_STRINGS_PATH, SHARED.as_bytes());
+        let bytes = archive.finish();
+
+        let records = XlsxCodec::default()
+            .parse(&mut bytes.as_slice())
+            .expect("sheet should parse");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, TxIdType::Numeric(7));
+        assert_eq!(records[0].kind, TxKind::Deposit);
+        assert_eq!(records[0].to, AccountType::Numeric(42));
+        assert_eq!(records[0].amount, Money::from_minor_units(150));
+        assert_eq!(records[0].ts.millis(), 1_700_000_000_000);
+        assert_eq!(records[0].description.as_deref(), Some("bonus & more"));
+    }
+
+    #[test]
+    fn xlsx_header_requires_known_and_mandatory_columns() {
+        let header = |names: &[&str]| -> BTreeMap<usize, String> {
+            names.iter().map(|n| n.to_string()).enumerate().collect()
+        };
+        let all = [
+            "TX_ID",
+            "TX_TYPE",
+            "FROM_USER_ID",
+            "TO_USER_ID",
+            "AMOUNT",
+            "TIMESTAMP",
+            "STATUS",
+        ];
+        assert!(XlsxCodec::default().parse_header(&header(&all)).is_ok());
+        assert!(matches!(
+            XlsxCodec::default().parse_header(&header(&all[..6])),
+            Err(ParserError::MissingField(TxFieldKey::Status))
+        ));
+        assert!(matches!(
+            XlsxCodec::default().parse_header(&header(&["TX_ID", "NOTES"])),
+            Err(ParserError::UnparsableKey(_))
+        ));
+        assert!(matches!(
+            XlsxCodec::default().parse_header(&header(&["TX_ID", "TX_ID"])),
+            Err(ParserError::Duplicate(TxFieldKey::Id))
+        ));
+    }
+
+    #[test]
+    fn xlsx_column_references() {
+        for (name, index) in [("A", 0), ("Z", 25), ("AA", 26), ("AB", 27), ("ZZ", 701)] {
+            assert_eq!(column_index(&format!("{}12", name)), Some(index));
+            assert_eq!(column_name(index), name);
+        }
+        assert_eq!(column_index("12"), None);
+    }
+}
@@ -0,0 +1,238 @@
+use super::errors::ParserError;
+
+/// Minimal XML element tree, enough for statement-like documents.
+///
+/// Namespace prefixes are dropped from element and attribute names.
+#[derive(Debug, Default)]
+pub(crate) struct XmlElement {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) children: Vec<XmlElement>,
+    pub(crate) text: String,
+}
+
+impl XmlElement {
+    /// Parses document and returns its root element.
+    pub(crate) fn parse_document(input: &str) -> Result<Self, (usize, ParserError)> {
+        let mut reader = XmlReader { input, pos: 0 };
+        reader.skip_misc()?;
+        let root = reader.parse_element()?;
+        reader.skip_misc()?;
+        if reader.pos < input.len() {
+            return Err(reader.error("content after root element"));
+        }
+        Ok(root)
+    }
+
+    pub(crate) fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub(crate) fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlElement> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Follows chain of child names, e.g. `["BookgDt", "DtTm"]`.
+    pub(crate) fn path(&self, names: &[&str]) -> Option<&XmlElement> {
+        names
+            .iter()
+            .try_fold(self, |element, name| element.child(name))
+    }
+
+    /// Trimmed text of the element found by path.
+    pub(crate) fn path_text(&self, names: &[&str]) -> Option<&str> {
+        self.path(names).map(|e| e.text.trim())
+    }
+
+    pub(crate) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Depth-first search of all descendants with provided name.
+    pub(crate) fn descendants<'a>(&'a self, name: &str, found: &mut Vec<&'a XmlElement>) {
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            } else {
+                child.descendants(name, found);
+            }
+        }
+    }
+}
+
+struct XmlReader<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlReader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error(&self, message: &str) -> (usize, ParserError) {
+        (
+            self.pos,
+            ParserError::UnparsableValue(format!("malformed xml, {}", message)),
+        )
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+    }
+
+    fn skip_past(&mut self, terminator: &str) -> Result<(), (usize, ParserError)> {
+        match self.rest().find(terminator) {
+            Some(i) => {
+                self.pos += i + terminator.len();
+                Ok(())
+            }
+            None => Err(self.error(&format!("`{}` expected", terminator))),
+        }
+    }
+
+    // prolog, processing instructions, comments and doctype between elements
+    fn skip_misc(&mut self) -> Result<(), (usize, ParserError)> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!") && !self.rest().starts_with("<![CDATA[") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<String, (usize, ParserError)> {
+        let len = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '='))
+            .unwrap_or(self.rest().len());
+        if 0 == len {
+            return Err(self.error("name expected"));
+        }
+        let qualified = &self.rest()[..len];
+        self.pos += len;
+        let local = qualified.rsplit(':').next().unwrap_or(qualified);
+        Ok(local.to_string())
+    }
+
+    fn parse_element(&mut self) -> Result<XmlElement, (usize, ParserError)> {
+        if !self.rest().starts_with('<') {
+            return Err(self.error("`<` expected"));
+        }
+        self.pos += 1;
+        let mut element = XmlElement {
+            name: self.parse_name()?,
+            ..Default::default()
+        };
+
+        // attributes
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let name = self.parse_name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error("`=` expected"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(self.error("quoted attribute value expected")),
+            };
+            self.pos += 1;
+            let len = self
+                .rest()
+                .find(quote)
+                .ok_or_else(|| self.error("unterminated attribute value"))?;
+            let value = decode_entities(&self.rest()[..len]).map_err(|m| self.error(&m))?;
+            self.pos += len + 1;
+            element.attributes.push((name, value));
+        }
+
+        // content
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(self.error(&format!("`</{}>` expected", element.name)));
+            }
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let name = self.parse_name()?;
+                if name != element.name {
+                    return Err(self.error(&format!("`</{}>` expected", element.name)));
+                }
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return Err(self.error("`>` expected"));
+                }
+                self.pos += 1;
+                return Ok(element);
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                let len = self
+                    .rest()
+                    .find("]]>")
+                    .ok_or_else(|| self.error("unterminated CDATA"))?;
+                element.text.push_str(&self.rest()[..len]);
+                self.pos += len + 3;
+            } else if rest.starts_with("<!--") || rest.starts_with("<?") {
+                self.skip_misc()?;
+            } else if rest.starts_with('<') {
+                element.children.push(self.parse_element()?);
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                let text = decode_entities(&rest[..len]).map_err(|m| self.error(&m))?;
+                element.text.push_str(&text);
+                self.pos += len;
+            }
+        }
+    }
+}
+
+fn decode_entities(raw: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| "unterminated entity".to_string())?;
+        let entity = &rest[start + 1..start + end];
+        let decoded = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("unknown entity &{};", entity))?,
+        };
+        out.push(decoded);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
@@ -0,0 +1,240 @@
+use super::errors::ParserError;
+use super::options::ParseLimits;
+use crate::deflate::inflate;
+use crate::digest::Crc32;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+const LOCAL_HEADER_SIZE: usize = 30;
+const CENTRAL_HEADER_SIZE: usize = 46;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+// end of central directory record may be followed by comment of up to 64K
+const MAX_COMMENT_SIZE: usize = 0xFFFF;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+const VERSION_NEEDED: u16 = 20;
+// 1980-01-01 00:00 in MS-DOS format, entries carry no meaningful modification time
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+fn malformed(message: &str) -> ParserError {
+    ParserError::UnparsableValue(format!("malformed zip archive, {}", message))
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Result<u16, ParserError> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| malformed("unexpected end of archive"))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, ParserError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| malformed("unexpected end of archive"))
+}
+
+struct ZipEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: usize,
+    size: usize,
+    local_header_offset: usize,
+}
+
+/// Read-only view of in-memory zip archive.
+pub(crate) struct ZipArchive<'a> {
+    bytes: &'a [u8],
+    entries: Vec<ZipEntry>,
+}
+
+impl<'a> ZipArchive<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Result<Self, ParserError> {
+        let search_start = bytes
+            .len()
+            .saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE + MAX_COMMENT_SIZE);
+        let eocd = (search_start..=bytes.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE))
+            .rev()
+            .find(|&i| u32_at(bytes, i).ok() == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+            .ok_or_else(|| malformed("end of central directory not found"))?;
+
+        let count = u16_at(bytes, eocd + 10)? as usize;
+        let mut offset = u32_at(bytes, eocd + 16)? as usize;
+        if 0xFFFF == count || 0xFFFF_FFFF == offset {
+            return Err(malformed("zip64 archives are not supported"));
+        }
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if CENTRAL_HEADER_SIGNATURE != u32_at(bytes, offset)? {
+                return Err(malformed("invalid central directory header"));
+            }
+            let name_len = u16_at(bytes, offset + 28)? as usize;
+            let extra_len = u16_at(bytes, offset + 30)? as usize;
+            let comment_len = u16_at(bytes, offset + 32)? as usize;
+            let name_start = offset + CENTRAL_HEADER_SIZE;
+            let name = bytes
+                .get(name_start..name_start + name_len)
+                .ok_or_else(|| malformed("unexpected end of archive"))?;
+            entries.push(ZipEntry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method: u16_at(bytes, offset + 10)?,
+                crc: u32_at(bytes, offset + 16)?,
+                compressed_size: u32_at(bytes, offset + 20)? as usize,
+                size: u32_at(bytes, offset + 24)? as usize,
+                local_header_offset: u32_at(bytes, offset + 42)? as usize,
+            });
+            offset = name_start + name_len + extra_len + comment_len;
+        }
+        Ok(Self { bytes, entries })
+    }
+
+    /// Decompressed content of the entry, `None` if archive has no such entry.
+    ///
+    /// Entries declaring more than `max_size` bytes are rejected before they are inflated.
+    pub(crate) fn read(&self, name: &str, max_size: usize) -> Result<Option<Vec<u8>>, ParserError> {
+        let Some(entry) = self.entries.iter().find(|e| e.name == name) else {
+            return Ok(None);
+        };
+        ParseLimits::check_size(name, entry.size as u64, max_size)?;
+        let offset = entry.local_header_offset;
+        if LOCAL_HEADER_SIGNATURE != u32_at(self.bytes, offset)? {
+            return Err(malformed("invalid local file header"));
+        }
+        let data_start = offset
+            + LOCAL_HEADER_SIZE
+            + u16_at(self.bytes, offset + 26)? as usize
+            + u16_at(self.bytes, offset + 28)? as usize;
+        let data = self
+            .bytes
+            .get(data_start..data_start + entry.compressed_size)
+            .ok_or_else(|| malformed("unexpected end of archive"))?;
+
+        let content = match entry.method {
+            METHOD_STORED => data.to_vec(),
+            // declared size bounds the output, the entry is not inflated any further
+            METHOD_DEFLATED => inflate(data, entry.size)?,
+            method => return Err(malformed(&format!("compression method {}", method))),
+        };
+        if content.len() != entry.size || Crc32::checksum(&content) != entry.crc {
+            return Err(malformed(&format!("checksum mismatch in {}", name)));
+        }
+        Ok(Some(content))
+    }
+}
+
+/// Builds zip archive with uncompressed entries.
+#[derive(Default)]
+pub(crate) struct ZipBuilder {
+    bytes: Vec<u8>,
+    central_directory: Vec<u8>,
+    count: u16,
+}
+
+impl ZipBuilder {
+    pub(crate) fn add(&mut self, name: &str, content: &[u8]) {
+        let crc = Crc32::checksum(content);
+        let offset = self.bytes.len() as u32;
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // flags
+        common.extend_from_slice(&METHOD_STORED.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // time
+        common.extend_from_slice(&DOS_DATE.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // extra length
+
+        self.bytes
+            .extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        self.bytes.extend_from_slice(&common);
+        self.bytes.extend_from_slice(name.as_bytes());
+        self.bytes.extend_from_slice(content);
+
+        let cd = &mut self.central_directory;
+        cd.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+        cd.extend_from_slice(&VERSION_NEEDED.to_le_bytes()); // version made by
+        cd.extend_from_slice(&common);
+        cd.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        cd.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        cd.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        cd.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        cd.extend_from_slice(&offset.to_le_bytes());
+        cd.extend_from_slice(name.as_bytes());
+        self.count += 1;
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        let cd_offset = self.bytes.len() as u32;
+        let cd_size = self.central_directory.len() as u32;
+        self.bytes.append(&mut self.central_directory);
+        self.bytes
+            .extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // disk with central directory
+        self.bytes.extend_from_slice(&self.count.to_le_bytes());
+        self.bytes.extend_from_slice(&self.count.to_le_bytes());
+        self.bytes.extend_from_slice(&cd_size.to_le_bytes());
+        self.bytes.extend_from_slice(&cd_offset.to_le_bytes());
+        self.bytes.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests_zip {
+    use super::*;
+
+    #[test]
+    fn zip_reads_deflated_entry() {
+        let hex = concat!(
+            "504b03041400000008009d8c4e5de3513d8d0a0000001700000005000000612e747874cb48cdc9c957",
+            "c8402701504b010214031400000008009d8c4e5de3513d8d0a00000017000000050000000000000000",
+            "000000800100000000612e747874504b05060000000001000100330000002d0000000000"
+        );
+        let bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        let archive = ZipArchive::new(&bytes).expect("valid archive");
+        assert_eq!(
+            archive.read("a.txt", usize::MAX).unwrap().as_deref(),
+            Some(&b"hello hello hello hello"[..])
+        );
+        assert!(archive.read("b.txt", usize::MAX).unwrap().is_none());
+        assert!(matches!(
+            archive.read("a.txt", 22),
+            Err(ParserError::LimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn zip_builder_round_trip() {
+        let mut builder = ZipBuilder::default();
+        builder.add("one.xml", b"<a/>");
+        builder.add("dir/two.xml", b"");
+        let bytes = builder.finish();
+
+        let archive = ZipArchive::new(&bytes).expect("valid archive");
+        assert_eq!(
+            archive.read("one.xml", usize::MAX).unwrap(),
+            Some(b"<a/>".to_vec())
+        );
+        assert_eq!(
+            archive.read("dir/two.xml", usize::MAX).unwrap(),
+            Some(Vec::new())
+        );
+
+        let mut corrupted = bytes.clone();
+        corrupted[LOCAL_HEADER_SIZE + "one.xml".len()] = b'X';
+        let archive = ZipArchive::new(&corrupted).expect("directory is intact");
+        assert!(archive.read("one.xml", usize::MAX).is_err());
+        assert!(ZipArchive::new(b"not a zip").is_err());
+    }
+}
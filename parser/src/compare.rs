@@ -0,0 +1,484 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+use std::fs::File;
+use std::path::Path;
+
+use crate::codecs::base::{Codec, TxFieldKey};
+use crate::codecs::options::ParseOptions;
+use crate::codecs::traits::RecordStream;
+use crate::domain::key::{ALL_FIELDS, Fingerprint, RecordIdentity, RecordKey, field_value};
+use crate::domain::tx::{Money, TxIdType, TxRecord};
+use crate::errors::AppError;
+
+const ABSENT: &str = "-";
+
+/// Options for [`assert_equivalent`].
+#[derive(Debug, Clone)]
+pub struct EquivalenceOptions {
+    /// Compare as sets: a record repeated in one file matches a single occurence in another.
+    pub ignore_multiplicity: bool,
+    /// Maximum number of unmatched records kept per side in the report.
+    pub max_examples: usize,
+    /// Part of record that identifies it, whole record by default.
+    pub key: RecordKey,
+}
+
+impl Default for EquivalenceOptions {
+    fn default() -> Self {
+        Self {
+            ignore_multiplicity: false,
+            max_examples: 10,
+            key: RecordKey::FullRecord,
+        }
+    }
+}
+
+/// Outcome of cross-format equivalence check.
+#[derive(Debug, Default)]
+pub struct EquivalenceReport {
+    /// Number of records read from the first file.
+    pub records_a: usize,
+    /// Number of records read from the second file.
+    pub records_b: usize,
+    /// Total number of unmatched records on both sides.
+    pub mismatches: usize,
+    /// Sample of records present only in the first file.
+    pub only_in_a: Vec<TxRecord>,
+    /// Sample of records present only in the second file.
+    pub only_in_b: Vec<TxRecord>,
+}
+
+impl EquivalenceReport {
+    /// True if both files encode the same transaction set.
+    pub fn is_equivalent(&self) -> bool {
+        0 == self.mismatches
+    }
+}
+
+/// Options for [`compare_with`].
+#[derive(Debug, Clone, Default)]
+pub struct CompareOptions {
+    /// Compare as sets: a record repeated in one set matches a single occurence in another.
+    pub ignore_multiplicity: bool,
+    /// Part of record that identifies it, whole record by default.
+    pub key: RecordKey,
+}
+
+/// Record without equivalent on the other side of comparison.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unmatched {
+    /// First record of the identity on its side.
+    pub record: TxRecord,
+    /// Number of its occurences the other side lacks.
+    pub count: usize,
+}
+
+/// Outcome of comparing two record sets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompareReport {
+    /// Number of records of the first set.
+    pub records_a: usize,
+    /// Number of records of the second set.
+    pub records_b: usize,
+    /// Records of the first set without equivalent in the second one, ordered by id.
+    pub only_in_a: Vec<Unmatched>,
+    /// Records of the second set without equivalent in the first one, ordered by id.
+    pub only_in_b: Vec<Unmatched>,
+}
+
+impl CompareReport {
+    /// True if both sets hold the same records.
+    pub fn is_identical(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty()
+    }
+
+    /// Total number of unmatched records on both sides.
+    pub fn mismatches(&self) -> usize {
+        self.only_in_a
+            .iter()
+            .chain(&self.only_in_b)
+            .map(|unmatched| unmatched.count)
+            .sum()
+    }
+}
+
+/// Compares two record sets as multisets of whole records, order of records does not matter.
+pub fn compare(a: &[TxRecord], b: &[TxRecord]) -> CompareReport {
+    compare_with(a, b, &CompareOptions::default())
+}
+
+/// Compares two record sets as multisets of record identities under `opts.key`.
+pub fn compare_with(a: &[TxRecord], b: &[TxRecord], opts: &CompareOptions) -> CompareReport {
+    // count is number_of_occurences_in_a - number_of_occurences_in_b for each unique identity,
+    // first record met with the identity on each side is kept as example
+    let mut record_count: HashMap<RecordIdentity, Occurences> = HashMap::new();
+    for tx in a {
+        let occurences = record_count.entry(opts.key.identity(tx)).or_default();
+        occurences.in_a.get_or_insert(tx);
+        if !opts.ignore_multiplicity || 0 == occurences.count {
+            occurences.count += 1;
+        }
+    }
+    let mut seen_in_b: HashSet<RecordIdentity> = HashSet::new();
+    for tx in b {
+        let identity = opts.key.identity(tx);
+        if opts.ignore_multiplicity && !seen_in_b.insert(identity.clone()) {
+            continue;
+        }
+        let occurences = record_count.entry(identity).or_default();
+        occurences.in_b.get_or_insert(tx);
+        occurences.count -= 1;
+    }
+
+    let mut unmatched: Vec<(RecordIdentity, Occurences)> = record_count
+        .into_iter()
+        .filter(|(_, occurences)| 0 != occurences.count)
+        .collect();
+    unmatched.sort_by(|(x, a), (y, b)| a.id().cmp(&b.id()).then(x.cmp(y)));
+    let mut report = CompareReport {
+        records_a: a.len(),
+        records_b: b.len(),
+        ..Default::default()
+    };
+    for (_, occurences) in unmatched {
+        let (side, record) = match (occurences.count > 0, occurences.in_a, occurences.in_b) {
+            (true, Some(record), _) => (&mut report.only_in_a, record),
+            (false, _, Some(record)) => (&mut report.only_in_b, record),
+            _ => continue,
+        };
+        side.push(Unmatched {
+            record: record.clone(),
+            count: occurences.count.unsigned_abs() as usize,
+        });
+    }
+    report
+}
+
+#[derive(Default)]
+struct Occurences<'a> {
+    in_a: Option<&'a TxRecord>,
+    in_b: Option<&'a TxRecord>,
+    count: i64,
+}
+
+impl Occurences<'_> {
+    fn id(&self) -> Option<TxIdType> {
+        self.in_a.or(self.in_b).map(|tx| tx.id)
+    }
+}
+
+/// Field of a record differing between compared sets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// Differing field.
+    pub field: TxFieldKey,
+    /// Value in the first set, `None` when absent.
+    pub old: Option<String>,
+    /// Value in the second set, `None` when absent.
+    pub new: Option<String>,
+}
+
+impl Display for FieldDiff {
+    /// Formats difference as `AMOUNT: 5 -> 5.01`, absent values are `-`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = |value: &Option<String>| value.clone().unwrap_or_else(|| ABSENT.into());
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.field,
+            value(&self.old),
+            value(&self.new)
+        )
+    }
+}
+
+/// Records of the same id differing in some fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordDiff {
+    /// Id of the records.
+    pub id: TxIdType,
+    /// Differing fields in [`TxFieldKey`] order.
+    pub fields: Vec<FieldDiff>,
+}
+
+impl Display for RecordDiff {
+    /// Formats difference as `#42 AMOUNT: 5 -> 5.01, STATUS: PENDING -> SUCCESS`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.id)?;
+        for (i, field) in self.fields.iter().enumerate() {
+            write!(f, "{}{}", if 0 == i { " " } else { ", " }, field)?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of matching two record sets by id, see [`diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffReport {
+    /// Number of matched records with all fields equal.
+    pub unchanged: usize,
+    /// Matched records with differing fields, ordered by id.
+    pub changed: Vec<RecordDiff>,
+    /// Records of the first set without record of the same id in the second one.
+    pub only_in_a: Vec<TxRecord>,
+    /// Records of the second set without record of the same id in the first one.
+    pub only_in_b: Vec<TxRecord>,
+}
+
+impl DiffReport {
+    /// True if every record has its equal in the other set.
+    pub fn is_identical(&self) -> bool {
+        self.changed.is_empty() && self.only_in_a.is_empty() && self.only_in_b.is_empty()
+    }
+}
+
+/// Matches records of two sets by `TX_ID` and reports fields differing between them.
+///
+/// Records sharing an id are paired in the order they come. Amounts are compared by value,
+/// extras are not compared.
+pub fn diff(a: &[TxRecord], b: &[TxRecord]) -> DiffReport {
+    let mut by_id: BTreeMap<TxIdType, (Vec<&TxRecord>, Vec<&TxRecord>)> = BTreeMap::new();
+    for tx in a {
+        by_id.entry(tx.id).or_default().0.push(tx);
+    }
+    for tx in b {
+        by_id.entry(tx.id).or_default().1.push(tx);
+    }
+    let mut report = DiffReport::default();
+    for (id, (in_a, in_b)) in by_id {
+        for (tx_a, tx_b) in in_a.iter().zip(&in_b) {
+            let fields = field_diffs(tx_a, tx_b);
+            if fields.is_empty() {
+                report.unchanged += 1;
+            } else {
+                report.changed.push(RecordDiff { id, fields });
+            }
+        }
+        let paired = in_a.len().min(in_b.len());
+        report
+            .only_in_a
+            .extend(in_a[paired..].iter().map(|&tx| tx.clone()));
+        report
+            .only_in_b
+            .extend(in_b[paired..].iter().map(|&tx| tx.clone()));
+    }
+    report
+}
+
+/// Tolerances of [`fuzzy_match`], default ones match identical records only.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tolerances {
+    /// Largest difference of timestamps in milliseconds.
+    pub timestamp_drift_ms: u64,
+    /// Largest difference of amounts.
+    pub amount: Money,
+    /// Fields not compared at all, e.g. `DESCRIPTION` rewritten by the bank.
+    pub ignored: Vec<TxFieldKey>,
+}
+
+impl Tolerances {
+    // timestamp and amount are compared with tolerance, ignored fields are not compared
+    fn strict_key(&self) -> RecordKey {
+        let mut excluded = vec![TxFieldKey::Timestamp, TxFieldKey::Amount];
+        excluded.extend_from_slice(&self.ignored);
+        RecordKey::excluding(&excluded)
+    }
+
+    // `None` for records out of tolerance, distance of timestamps and amounts otherwise
+    fn distance(&self, a: &TxRecord, b: &TxRecord) -> Option<(u64, Money)> {
+        let drift = a.ts.millis().abs_diff(b.ts.millis());
+        let amount = a.amount.checked_sub(b.amount)?;
+        let amount = if amount < Money::default() {
+            amount.checked_neg()?
+        } else {
+            amount
+        };
+        let within = (self.ignored.contains(&TxFieldKey::Timestamp)
+            || drift <= self.timestamp_drift_ms)
+            && (self.ignored.contains(&TxFieldKey::Amount) || amount <= self.amount);
+        within.then_some((drift, amount))
+    }
+}
+
+/// Records matched within tolerances but not identical.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyPair {
+    /// Record of the first set.
+    pub a: TxRecord,
+    /// Record of the second set.
+    pub b: TxRecord,
+    /// Fields differing between them, ignored ones included.
+    pub fields: Vec<FieldDiff>,
+}
+
+/// Outcome of [`fuzzy_match`], every record is either matched exactly, matched fuzzily or
+/// left unmatched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FuzzyReport {
+    /// Number of record pairs matched exactly.
+    pub exact: usize,
+    /// Record pairs matched within tolerances, ordered by record of the first set.
+    pub fuzzy: Vec<FuzzyPair>,
+    /// Records of the first set without match, in input order.
+    pub only_in_a: Vec<TxRecord>,
+    /// Records of the second set without match, in input order.
+    pub only_in_b: Vec<TxRecord>,
+}
+
+/// Matches records of two sets allowing `tolerances`.
+///
+/// Identical records are matched first. Every remaining record of the first set is then
+/// matched with the closest one of the second set, by timestamp and then by amount,
+/// that equals it in all fields but timestamp, amount and ignored ones. Ids take part in
+/// matching unless `TX_ID` is ignored.
+pub fn fuzzy_match(a: &[TxRecord], b: &[TxRecord], tolerances: &Tolerances) -> FuzzyReport {
+    let mut report = FuzzyReport::default();
+    let mut matched_b = vec![false; b.len()];
+
+    // identical records, paired in the order they come
+    let mut identical: HashMap<RecordIdentity, Vec<usize>> = HashMap::new();
+    for (i, tx) in b.iter().enumerate().rev() {
+        identical
+            .entry(RecordKey::FullRecord.identity(tx))
+            .or_default()
+            .push(i);
+    }
+    let mut rest_of_a = Vec::new();
+    for tx in a {
+        match identical
+            .get_mut(&RecordKey::FullRecord.identity(tx))
+            .and_then(Vec::pop)
+        {
+            Some(i) => {
+                matched_b[i] = true;
+                report.exact += 1;
+            }
+            None => rest_of_a.push(tx),
+        }
+    }
+
+    let strict_key = tolerances.strict_key();
+    let mut candidates: HashMap<RecordIdentity, Vec<usize>> = HashMap::new();
+    for (i, tx) in b.iter().enumerate().filter(|(i, _)| !matched_b[*i]) {
+        candidates
+            .entry(strict_key.identity(tx))
+            .or_default()
+            .push(i);
+    }
+    for tx in rest_of_a {
+        let closest = candidates
+            .get(&strict_key.identity(tx))
+            .into_iter()
+            .flatten()
+            .filter(|&&i| !matched_b[i])
+            .filter_map(|&i| tolerances.distance(tx, &b[i]).map(|distance| (distance, i)))
+            .min();
+        match closest {
+            Some((_, i)) => {
+                matched_b[i] = true;
+                report.fuzzy.push(FuzzyPair {
+                    a: tx.clone(),
+                    b: b[i].clone(),
+                    fields: field_diffs(tx, &b[i]),
+                });
+            }
+            None => report.only_in_a.push(tx.clone()),
+        }
+    }
+    report.only_in_b = b
+        .iter()
+        .zip(matched_b)
+        .filter(|(_, matched)| !matched)
+        .map(|(tx, _)| tx.clone())
+        .collect();
+    report
+}
+
+pub(crate) fn field_diffs(a: &TxRecord, b: &TxRecord) -> Vec<FieldDiff> {
+    ALL_FIELDS
+        .into_iter()
+        .map(|field| FieldDiff {
+            field,
+            old: field_value(a, field),
+            new: field_value(b, field),
+        })
+        .filter(|field| field.old != field.new)
+        .collect()
+}
+
+/// Reads two files (possibly in different formats) and checks they encode the same transactions.
+///
+/// Both files are streamed, only fingerprints of record identities with their counts are held
+/// in memory, so the first file is read again to pick its examples of unmatched records.
+/// Examples are kept in order of their files, one per identity.
+pub fn assert_equivalent<A: AsRef<Path>, B: AsRef<Path>>(
+    path_a: A,
+    format_a: &Codec,
+    path_b: B,
+    format_b: &Codec,
+    opts: &EquivalenceOptions,
+) -> Result<EquivalenceReport, AppError> {
+    let mut report = EquivalenceReport::default();
+    // count is number_of_occurences_in_a - number_of_occurences_in_b for each unique identity
+    let mut record_count: HashMap<Fingerprint, i64> = HashMap::new();
+    for tx in open_records(path_a.as_ref(), format_a)? {
+        let count = record_count
+            .entry(opts.key.identity(&tx?).fingerprint())
+            .or_default();
+        if !opts.ignore_multiplicity || 0 == *count {
+            *count += 1;
+        }
+        report.records_a += 1;
+    }
+    let mut seen_in_b: HashSet<Fingerprint> = HashSet::new();
+    for tx in open_records(path_b.as_ref(), format_b)? {
+        let tx = tx?;
+        report.records_b += 1;
+        let fingerprint = opts.key.identity(&tx).fingerprint();
+        if opts.ignore_multiplicity && !seen_in_b.insert(fingerprint) {
+            continue;
+        }
+        let count = record_count.entry(fingerprint).or_default();
+        *count -= 1;
+        // counts only decrease from now on, so the record is known to be unmatched
+        if -1 == *count && report.only_in_b.len() < opts.max_examples {
+            report.only_in_b.push(tx);
+        }
+    }
+    record_count.retain(|_, count| 0 != *count);
+    report.mismatches = record_count
+        .values()
+        .map(|count| count.unsigned_abs() as usize)
+        .sum();
+
+    // identities left in excess in the first file are picked from it again
+    let mut left_in_a = record_count.values().filter(|count| 0 < **count).count();
+    if 0 < left_in_a && 0 < opts.max_examples {
+        for tx in open_records(path_a.as_ref(), format_a)? {
+            let tx = tx?;
+            let fingerprint = opts.key.identity(&tx).fingerprint();
+            if record_count
+                .get(&fingerprint)
+                .is_some_and(|count| 0 < *count)
+            {
+                record_count.remove(&fingerprint);
+                report.only_in_a.push(tx);
+                left_in_a -= 1;
+                if 0 == left_in_a || report.only_in_a.len() == opts.max_examples {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn open_records(path: &Path, format: &Codec) -> Result<RecordStream<'static>, AppError> {
+    let f = File::open(path).map_err(|e| {
+        AppError::ReadError(std::io::Error::new(
+            e.kind(),
+            format!("Error opening a file {} {}", path.display(), e),
+        ))
+    })?;
+    Ok(format.parse_stream(f, &ParseOptions::default()))
+}
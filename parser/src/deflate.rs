@@ -0,0 +1,347 @@
+//! Dependency-free DEFLATE (RFC 1951) decoder used by compressed containers.
+
+use crate::codecs::errors::ParserError;
+
+const MAX_BITS: usize = 15;
+const LITERAL_CODES: usize = 288;
+const DISTANCE_CODES: usize = 30;
+const END_OF_BLOCK: u16 = 256;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// order code length code lengths are stored in dynamic block header
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn corrupted(message: &str) -> ParserError {
+    ParserError::UnparsableValue(format!("corrupted deflate stream, {}", message))
+}
+
+/// Decompresses raw DEFLATE stream (no zlib/gzip wrapper) of at most `max_len` bytes.
+pub(crate) fn inflate(input: &[u8], max_len: usize) -> Result<Vec<u8>, ParserError> {
+    let mut bits = BitReader::new(input);
+    let mut out = Vec::with_capacity(input.len().saturating_mul(4).min(max_len));
+    loop {
+        let is_final = 1 == bits.read(1)?;
+        match bits.read(2)? {
+            0 => inflate_stored(&mut bits, &mut out, max_len)?,
+            1 => {
+                let (literals, distances) = fixed_codes();
+                inflate_block(&mut bits, &mut out, max_len, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut bits)?;
+                inflate_block(&mut bits, &mut out, max_len, &literals, &distances)?;
+            }
+            _ => return Err(corrupted("invalid block type")),
+        }
+        if is_final {
+            return Ok(out);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            pos: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    // bits are packed starting from the least significant one
+    fn read(&mut self, n: u32) -> Result<u32, ParserError> {
+        while self.count < n {
+            let byte = *self
+                .input
+                .get(self.pos)
+                .ok_or_else(|| corrupted("unexpected end of stream"))?;
+            self.pos += 1;
+            self.buffer |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1u64 << n) - 1) as u32;
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], ParserError> {
+        let bytes = self
+            .input
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| corrupted("unexpected end of stream"))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+}
+
+// canonical Huffman code: number of codes per length and symbols ordered by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, ParserError> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // over-subscribed code can't be decoded, incomplete ones are allowed by the spec
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(corrupted("over-subscribed huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if 0 != len {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Result<u16, ParserError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= bits.read(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupted("invalid huffman code"))
+    }
+}
+
+fn inflate_stored(
+    bits: &mut BitReader,
+    out: &mut Vec<u8>,
+    max_len: usize,
+) -> Result<(), ParserError> {
+    bits.align_to_byte();
+    let header = bits.read_bytes(4)?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err(corrupted("stored block length mismatch"));
+    }
+    check_len(out.len() + len as usize, max_len)?;
+    out.extend_from_slice(bits.read_bytes(len as usize)?);
+    Ok(())
+}
+
+fn check_len(len: usize, max_len: usize) -> Result<(), ParserError> {
+    if len > max_len {
+        return Err(ParserError::LimitExceeded(format!(
+            "inflated data over {} bytes",
+            max_len
+        )));
+    }
+    Ok(())
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; LITERAL_CODES];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    // fixed tables are complete by construction
+    let literals = Huffman::new(&lengths).expect("fixed literal code is valid");
+    let distances = Huffman::new(&[5u8; DISTANCE_CODES]).expect("fixed distance code is valid");
+    (literals, distances)
+}
+
+fn dynamic_codes(bits: &mut BitReader) -> Result<(Huffman, Huffman), ParserError> {
+    let literal_count = bits.read(5)? as usize + 257;
+    let distance_count = bits.read(5)? as usize + 1;
+    let code_length_count = bits.read(4)? as usize + 4;
+    if literal_count > LITERAL_CODES - 2 || distance_count > DISTANCE_CODES {
+        return Err(corrupted("too many codes"));
+    }
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[index] = bits.read(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code_length_code.decode(bits)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *i
+                    .checked_sub(1)
+                    .and_then(|p| lengths.get(p))
+                    .ok_or_else(|| corrupted("repeat without previous length"))?;
+                (previous, 3 + bits.read(2)? as usize)
+            }
+            17 => (0, 3 + bits.read(3)? as usize),
+            _ => (0, 11 + bits.read(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err(corrupted("too many code lengths"));
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+    if 0 == lengths[END_OF_BLOCK as usize] {
+        return Err(corrupted("missing end of block code"));
+    }
+
+    let literals = Huffman::new(&lengths[..literal_count])?;
+    let distances = Huffman::new(&lengths[literal_count..])?;
+    Ok((literals, distances))
+}
+
+fn inflate_block(
+    bits: &mut BitReader,
+    out: &mut Vec<u8>,
+    max_len: usize,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), ParserError> {
+    loop {
+        let symbol = literals.decode(bits)?;
+        if symbol < END_OF_BLOCK {
+            check_len(out.len() + 1, max_len)?;
+            out.push(symbol as u8);
+            continue;
+        }
+        if END_OF_BLOCK == symbol {
+            return Ok(());
+        }
+
+        let index = (symbol - END_OF_BLOCK - 1) as usize;
+        if index >= LENGTH_BASE.len() {
+            return Err(corrupted("invalid length code"));
+        }
+        let len = LENGTH_BASE[index] as usize + bits.read(LENGTH_EXTRA[index] as u32)? as usize;
+
+        let index = distances.decode(bits)? as usize;
+        if index >= DISTANCE_BASE.len() {
+            return Err(corrupted("invalid distance code"));
+        }
+        let distance =
+            DISTANCE_BASE[index] as usize + bits.read(DISTANCE_EXTRA[index] as u32)? as usize;
+        if distance > out.len() {
+            return Err(corrupted("distance too far back"));
+        }
+
+        check_len(out.len() + len, max_len)?;
+        // copied ranges may overlap with the bytes being written
+        let start = out.len() - distance;
+        for k in 0..len {
+            out.push(out[start + k]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_deflate {
+    use super::*;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn inflate_stored_and_fixed_blocks() {
+        assert_eq!(inflate(&from_hex("010300fcff616263"), 3).unwrap(), b"abc");
+        assert_eq!(
+            inflate(&from_hex("cb48cdc9c957c8409000"), 17).unwrap(),
+            b"hello hello hello"
+        );
+    }
+
+    #[test]
+    fn inflate_dynamic_block() {
+        let compressed = from_hex(concat!(
+            "7dd4a90ec240144051cf5790ea8a79db2cbe882a48a8c011041282c0f0f72424a3865c7dd5c95bb6cb",
+            "755de6342f87d3f1bc6ef3f4ba7d1ef7e77b9fa6ddf66b3236e94dc7a6bdd9d8ac371f9bf716638bde",
+            "f2d8726f050c150c0d0c920021020a51608881439c861120914c9442944a9406144d4051018a2a2d96",
+            "01451d281a40d14c9442944a9406144b7424021453a09801c51c281640b14c9442944a9406144f4071",
+            "018a2b50dc80e20e140ffa5d9928852895280d28918012029450a084d11f76a044002532510a512a51",
+            "da5fca17"
+        ));
+        let expected: String = (0..60)
+            .map(|i| format!("TX_ID,{},DEPOSIT,\"payment {}\"\n", i, i % 7))
+            .collect();
+        assert_eq!(
+            inflate(&compressed, usize::MAX).unwrap(),
+            expected.as_bytes()
+        );
+    }
+
+    #[test]
+    fn inflate_rejects_corrupted_streams() {
+        // truncated, reserved block type, stored length mismatch
+        for hex in ["cb48cdc9", "07", "010300fcfe616263"] {
+            assert!(
+                inflate(&from_hex(hex), usize::MAX).is_err(),
+                "{} should fail",
+                hex
+            );
+        }
+    }
+
+    #[test]
+    fn inflate_stops_at_max_len() {
+        for hex in ["010300fcff616263", "cb48cdc9c957c8409000"] {
+            assert!(matches!(
+                inflate(&from_hex(hex), 2),
+                Err(ParserError::LimitExceeded(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Digest primitives used for hashing record content and checksums.
+
+use hmac::{Hmac, KeyInit};
+pub(crate) use sha2::Sha256;
+
+/// Incremental HMAC-SHA256 (RFC 2104) authenticator.
+pub(crate) type HmacSha256 = Hmac<Sha256>;
+
+/// Creates HMAC-SHA256 authenticator keyed with `key`.
+pub(crate) fn hmac_sha256(key: &[u8]) -> HmacSha256 {
+    // keys longer than a block are hashed first, so any length is accepted
+    HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+const CRC32_POLYNOMIAL: u32 = 0xEDB8_8320;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if 0 != crc & 1 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Incremental CRC-32 (IEEE 802.3) checksum as used by zip and binary records.
+#[derive(Clone)]
+pub(crate) struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Self { crc: !0 }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.crc = CRC32_TABLE[((self.crc ^ b as u32) & 0xFF) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    pub(crate) fn finalize(self) -> u32 {
+        !self.crc
+    }
+
+    pub(crate) fn checksum(data: &[u8]) -> u32 {
+        let mut crc = Self::new();
+        crc.update(data);
+        crc.finalize()
+    }
+}
+
+/// Lowercase hex representation of bytes.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests_digest {
+    use super::*;
+    use hmac::Mac;
+
+    fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut hmac = hmac_sha256(key);
+        hmac.update(data);
+        hmac.finalize().into_bytes().to_vec()
+    }
+
+    #[test]
+    fn hmac_sha256_known_vectors() {
+        // RFC 4231 test cases 1, 2 and 6
+        assert_eq!(
+            to_hex(&hmac(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            to_hex(&hmac(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            to_hex(&hmac(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn crc32_known_vectors() {
+        assert_eq!(Crc32::checksum(b""), 0);
+        assert_eq!(Crc32::checksum(b"123456789"), 0xCBF4_3926);
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finalize(), 0xCBF4_3926);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::codecs::errors::ParserError;
+use crate::domain::tx::{TxRecord, TxTimestamp};
+
+/// File-level metadata of a batch of records.
+///
+/// All fields are optional, formats without file header read the default one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchHeader {
+    /// System the records come from.
+    pub source: Option<String>,
+    /// Time the batch was created.
+    pub created: Option<TxTimestamp>,
+    /// Number of records the batch declares, verified against records read.
+    pub record_count: Option<u64>,
+}
+
+/// Records along with file-level metadata of their batch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TxBatch {
+    /// Metadata of the batch.
+    pub header: BatchHeader,
+    /// Records of the batch.
+    pub records: Vec<TxRecord>,
+}
+
+impl TxBatch {
+    /// Batch of `records` from `source` created now, declaring their count.
+    pub fn new(source: Option<String>, records: Vec<TxRecord>) -> Self {
+        Self {
+            header: BatchHeader {
+                source,
+                created: Some(TxTimestamp::default()),
+                record_count: Some(records.len() as u64),
+            },
+            records,
+        }
+    }
+
+    /// Checks that declared record count, if any, matches the number of records.
+    pub fn verify(&self) -> Result<(), ParserError> {
+        match self.header.record_count {
+            Some(declared) if declared != self.records.len() as u64 => {
+                Err(ParserError::RecordCountMismatch {
+                    declared,
+                    actual: self.records.len() as u64,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
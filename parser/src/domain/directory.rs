@@ -0,0 +1,150 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read};
+
+use crate::codecs::errors::{ParserContext, ParserCtxBehavior, ParserError};
+use crate::domain::tx::AccountType;
+use crate::errors::AppError;
+use crate::json::JsonValue;
+
+const DIRECTORY_DELIMITER: char = ',';
+const DIRECTORY_COMMENT_SYMBOL: char = '#';
+const DIRECTORY_HEADER: &str = "NAME,ACCOUNT";
+
+/// Friendly names of accounts, e.g. `payroll` for account `4021`.
+///
+/// Names and accounts are unique. A name never reads as an account itself, so a value
+/// naming an account is resolved unambiguously, and is kept free of quotes, comments and
+/// line breaks so it is written to text output as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountDirectory {
+    names: BTreeMap<String, AccountType>,
+    accounts: HashMap<AccountType, String>,
+}
+
+impl AccountDirectory {
+    /// Adds `name` of `account`, both must not be in the directory yet.
+    pub fn insert(&mut self, name: &str, account: AccountType) -> Result<(), ParserError> {
+        let valid = !name.is_empty()
+            && name.trim() == name
+            && !name.contains(['"', DIRECTORY_COMMENT_SYMBOL, '\n', '\r'])
+            && !name.contains("/*")
+            && name.parse::<AccountType>().is_err();
+        if !valid {
+            return Err(ParserError::UnparsableValue(format!(
+                "invalid account name `{}`",
+                name
+            )));
+        }
+        if self.names.contains_key(name) {
+            return Err(ParserError::UnparsableValue(format!(
+                "duplicate account name `{}`",
+                name
+            )));
+        }
+        if self.accounts.contains_key(&account) {
+            return Err(ParserError::UnparsableValue(format!(
+                "duplicate account {}",
+                account
+            )));
+        }
+        self.names.insert(name.to_string(), account.clone());
+        self.accounts.insert(account, name.to_string());
+        Ok(())
+    }
+
+    /// Name of `account`, `None` for unnamed one.
+    pub fn name_of(&self, account: &AccountType) -> Option<&str> {
+        self.accounts.get(account).map(String::as_str)
+    }
+
+    /// Account named `name`, names are matched exactly.
+    pub fn resolve(&self, name: &str) -> Option<&AccountType> {
+        self.names.get(name)
+    }
+
+    /// Name of `account` if it has one, the account itself otherwise.
+    pub fn display_name(&self, account: &AccountType) -> String {
+        self.name_of(account)
+            .map_or_else(|| account.to_string(), str::to_string)
+    }
+
+    /// Parses account id or IBAN, or resolves account name.
+    pub fn parse_account(&self, value: &str) -> Result<AccountType, ParserError> {
+        match self.resolve(value) {
+            Some(account) => Ok(account.clone()),
+            None => value.parse(),
+        }
+    }
+
+    /// Named accounts ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AccountType)> {
+        self.names
+            .iter()
+            .map(|(name, account)| (name.as_str(), account))
+    }
+
+    /// Number of named accounts.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if no account is named.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Loads directory from CSV with `name,account` lines, e.g. `payroll,4021`.
+    ///
+    /// Empty lines, `#` comments and leading `NAME,ACCOUNT` header are skipped. Account is
+    /// the value after the last comma, so names may have commas.
+    pub fn read_csv<R: Read>(r: R) -> Result<Self, AppError> {
+        let mut directory = Self::default();
+        for (line_num, line_res) in BufReader::new(r).lines().enumerate() {
+            let input_line = line_res.map_err(AppError::ReadError)?;
+            let line = input_line.trim();
+            if line.is_empty()
+                || line.starts_with(DIRECTORY_COMMENT_SYMBOL)
+                || (directory.is_empty() && line.eq_ignore_ascii_case(DIRECTORY_HEADER))
+            {
+                continue;
+            }
+            directory
+                .parse_line(line)
+                .add_parser_ctx(ParserContext::with_line_number_and_line(
+                    line_num + 1,
+                    input_line.clone(),
+                ))?;
+        }
+        Ok(directory)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), ParserError> {
+        let (name, account) = line
+            .rsplit_once(DIRECTORY_DELIMITER)
+            .ok_or(ParserError::NoFieldDelimiter)?;
+        self.insert(name.trim(), account.trim().parse()?)
+    }
+
+    /// Loads directory from JSON object of names, e.g. `{"payroll": 4021, "rent": "DE89..."}`.
+    pub fn read_json<R: Read>(mut r: R) -> Result<Self, AppError> {
+        let mut input = String::new();
+        r.read_to_string(&mut input).map_err(AppError::ReadError)?;
+        Self::parse_json(&input).add_parser_ctx(ParserContext::with_position(0))
+    }
+
+    fn parse_json(input: &str) -> Result<Self, ParserError> {
+        let JsonValue::Object(fields) = JsonValue::parse(input)? else {
+            return Err(ParserError::UnparsableValue(
+                "account directory is not json object".into(),
+            ));
+        };
+        let mut directory = Self::default();
+        for (name, value) in &fields {
+            let account = value
+                .as_text()
+                .ok_or_else(|| ParserError::UnparsableValue(format!("account of `{}`", name)))?;
+            directory.insert(name, account.parse()?)?;
+        }
+        Ok(directory)
+    }
+}
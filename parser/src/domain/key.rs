@@ -0,0 +1,195 @@
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use sha2::Digest;
+
+use crate::codecs::base::TxFieldKey;
+use crate::codecs::errors::ParserError;
+use crate::digest::{Sha256, to_hex};
+use crate::domain::tx::TxRecord;
+
+const KEY_FULL_RECORD: &str = "FULL";
+const KEY_ID_ONLY: &str = "ID";
+const KEY_FIELDS_DELIMITER: char = ',';
+// bumped whenever field serialization changes, so fingerprints of different layouts never match
+const FINGERPRINT_VERSION: u8 = 1;
+
+pub(crate) const ALL_FIELDS: [TxFieldKey; 13] = [
+    TxFieldKey::Id,
+    TxFieldKey::TxKind,
+    TxFieldKey::FromUserId,
+    TxFieldKey::ToUserId,
+    TxFieldKey::Amount,
+    TxFieldKey::Timestamp,
+    TxFieldKey::Status,
+    TxFieldKey::Description,
+    TxFieldKey::Tenant,
+    TxFieldKey::Fee,
+    TxFieldKey::Reference,
+    TxFieldKey::OriginalAmount,
+    TxFieldKey::ExchangeRate,
+];
+
+/// Identity of a record under some [`RecordKey`], records with equal identities are the same.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RecordIdentity(Vec<u8>);
+
+impl RecordIdentity {
+    /// SHA-256 fingerprint of the identity.
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut hasher = Sha256::new();
+        hasher.update([FINGERPRINT_VERSION]);
+        hasher.update(&self.0);
+        Fingerprint(hasher.finalize().into())
+    }
+}
+
+/// Stable SHA-256 digest of record fields, see [`TxRecord::fingerprint`].
+///
+/// Unlike [`std::hash::Hash`] output it is the same across builds and platforms, so it can
+/// be stored and compared later. Displayed and parsed as 64 lowercase hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// Digest bytes.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", to_hex(&self.0))
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut digest = [0u8; 32];
+        if s.len() != 2 * digest.len() || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParserError::UnparsableValue(s.into()));
+        }
+        for (i, byte) in digest.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16)
+                .map_err(|_| ParserError::UnparsableValue(s.into()))?;
+        }
+        Ok(Fingerprint(digest))
+    }
+}
+
+/// Defines which part of a record identifies it for comparison, deduplication and merging.
+#[derive(Clone, Default)]
+pub enum RecordKey {
+    /// All fields take part in identity.
+    #[default]
+    FullRecord,
+    /// Transaction id only.
+    IdOnly,
+    /// Listed fields only, in the listed order.
+    Fields(Vec<TxFieldKey>),
+    /// Identity computed by user function.
+    Custom(Arc<dyn Fn(&TxRecord) -> u64 + Send + Sync>),
+}
+
+impl Debug for RecordKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordKey::FullRecord => write!(f, "FullRecord"),
+            RecordKey::IdOnly => write!(f, "IdOnly"),
+            RecordKey::Fields(fields) => f.debug_tuple("Fields").field(fields).finish(),
+            RecordKey::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+impl RecordKey {
+    /// Key computed by provided hasher.
+    pub fn custom<F: Fn(&TxRecord) -> u64 + Send + Sync + 'static>(hasher: F) -> Self {
+        RecordKey::Custom(Arc::new(hasher))
+    }
+
+    /// All fields but `excluded` ones.
+    pub fn excluding(excluded: &[TxFieldKey]) -> Self {
+        RecordKey::Fields(
+            ALL_FIELDS
+                .into_iter()
+                .filter(|field_key| !excluded.contains(field_key))
+                .collect(),
+        )
+    }
+
+    /// Identity of the record under this key.
+    pub fn identity(&self, tx: &TxRecord) -> RecordIdentity {
+        let mut bytes = Vec::new();
+        match self {
+            RecordKey::FullRecord => ALL_FIELDS
+                .iter()
+                .for_each(|&field_key| push_field(&mut bytes, tx, field_key)),
+            RecordKey::IdOnly => push_field(&mut bytes, tx, TxFieldKey::Id),
+            RecordKey::Fields(fields) => fields
+                .iter()
+                .for_each(|&field_key| push_field(&mut bytes, tx, field_key)),
+            RecordKey::Custom(hasher) => bytes.extend_from_slice(&hasher(tx).to_be_bytes()),
+        }
+        RecordIdentity(bytes)
+    }
+}
+
+impl FromStr for RecordKey {
+    type Err = ParserError;
+
+    /// Parses `FULL`, `ID` or comma separated field names, e.g. `TX_ID,AMOUNT`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            KEY_FULL_RECORD => Ok(RecordKey::FullRecord),
+            KEY_ID_ONLY => Ok(RecordKey::IdOnly),
+            fields => fields
+                .split(KEY_FIELDS_DELIMITER)
+                .map(|name| name.trim().parse())
+                .collect::<Result<Vec<_>, _>>()
+                .map(RecordKey::Fields),
+        }
+    }
+}
+
+// fields are tagged and length-prefixed so different field sets never produce equal identities
+fn push_field(bytes: &mut Vec<u8>, tx: &TxRecord, field_key: TxFieldKey) {
+    let value = field_value(tx, field_key);
+    let tag = ALL_FIELDS
+        .iter()
+        .position(|&k| k == field_key)
+        .unwrap_or_default() as u8;
+    bytes.push(tag);
+    match value {
+        Some(value) => {
+            bytes.push(1);
+            bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(value.as_bytes());
+        }
+        None => bytes.push(0),
+    }
+}
+
+// value of the field as compared, amounts without trailing zero fraction digits
+pub(crate) fn field_value(tx: &TxRecord, field_key: TxFieldKey) -> Option<String> {
+    match field_key {
+        TxFieldKey::Id => Some(tx.id.to_string()),
+        TxFieldKey::TxKind => Some(tx.kind.to_string()),
+        TxFieldKey::FromUserId => Some(tx.from.to_string()),
+        TxFieldKey::ToUserId => Some(tx.to.to_string()),
+        TxFieldKey::Amount => Some(tx.amount.normalized().to_string()),
+        TxFieldKey::Timestamp => Some(tx.ts.to_string()),
+        TxFieldKey::Status => Some(tx.status.to_string()),
+        TxFieldKey::Description => tx.description.clone(),
+        TxFieldKey::Tenant => tx.tenant.clone(),
+        TxFieldKey::Fee => tx.fee.map(|fee| fee.normalized().to_string()),
+        TxFieldKey::Reference => tx.reference.clone(),
+        TxFieldKey::OriginalAmount => tx
+            .original_amount
+            .map(|amount| amount.normalized().to_string()),
+        TxFieldKey::ExchangeRate => tx.exchange_rate.map(|rate| rate.to_string()),
+    }
+}
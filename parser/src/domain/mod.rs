@@ -1,2 +1,10 @@
+/// Batches of records with file-level metadata.
+pub mod batch;
+/// Friendly names of accounts.
+pub mod directory;
+/// Record identity used for comparison, deduplication and merging.
+pub mod key;
+/// Checked arithmetic over record amounts.
+pub mod money;
 /// Transaction domain entities.
 pub mod tx;
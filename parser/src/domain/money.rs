@@ -0,0 +1,77 @@
+use std::fmt::Display;
+
+use crate::domain::tx::{AccountType, Money, TxRecord};
+
+/// Amount arithmetic does not fit 64-bit minor units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountOverflow;
+
+impl std::error::Error for AmountOverflow {}
+
+impl Display for AmountOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "amount overflows 64-bit minor units")
+    }
+}
+
+/// Sum of amounts at the larger of their scales.
+pub fn add(a: Money, b: Money) -> Result<Money, AmountOverflow> {
+    a.checked_add(b).ok_or(AmountOverflow)
+}
+
+/// Difference of amounts at the larger of their scales.
+pub fn sub(a: Money, b: Money) -> Result<Money, AmountOverflow> {
+    a.checked_sub(b).ok_or(AmountOverflow)
+}
+
+/// Negated amount, fails for the smallest one.
+pub fn neg(amount: Money) -> Result<Money, AmountOverflow> {
+    amount.checked_neg().ok_or(AmountOverflow)
+}
+
+/// Sum of amounts at the largest of their scales, zero of scale 0 for no amounts.
+///
+/// Intermediate sums may not overflow either, so the order of amounts matters near the
+/// bounds.
+pub fn sum<I: IntoIterator<Item = Money>>(amounts: I) -> Result<Money, AmountOverflow> {
+    amounts.into_iter().try_fold(Money::new(0, 0), add)
+}
+
+/// Sum of record amounts, status and fees are not taken into account.
+pub fn sum_amounts<'a, I: IntoIterator<Item = &'a TxRecord>>(
+    records: I,
+) -> Result<Money, AmountOverflow> {
+    sum(records.into_iter().map(|tx| tx.amount))
+}
+
+/// Amount with sign of its kind, see [`TxKind::flow_sign`](crate::domain::tx::TxKind::flow_sign):
+/// positive for deposits, negative for withdrawals and fees, zero for moves between accounts.
+pub fn signed_amount(tx: &TxRecord) -> Result<Money, AmountOverflow> {
+    match tx.kind.flow_sign() {
+        1 => Ok(tx.amount),
+        -1 => neg(tx.amount),
+        _ => Ok(Money::new(0, tx.amount.scale)),
+    }
+}
+
+/// Net funds records bring into the books, sum of their signed amounts.
+pub fn net_flow<'a, I: IntoIterator<Item = &'a TxRecord>>(
+    records: I,
+) -> Result<Money, AmountOverflow> {
+    records
+        .into_iter()
+        .try_fold(Money::new(0, 0), |total, tx| add(total, signed_amount(tx)?))
+}
+
+/// Amount the record moves into `account`, see [`TxRecord::effect_on`]; fails instead of
+/// saturating.
+pub fn effect_on(tx: &TxRecord, account: &AccountType) -> Result<Money, AmountOverflow> {
+    match (
+        tx.destination() == Some(account),
+        tx.source() == Some(account),
+    ) {
+        (true, false) => Ok(tx.amount),
+        (false, true) => neg(tx.amount),
+        _ => Ok(Money::new(0, tx.amount.scale)),
+    }
+}
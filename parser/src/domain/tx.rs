@@ -1,29 +1,218 @@
+use crate::codecs::base::TxFieldKey;
 use crate::codecs::errors::ParserError;
+use crate::codecs::utils::{
+    format_decimal_minor_units, format_iso8601, format_iso8601_date, format_iso8601_zoned,
+    format_utc_offset, parse_decimal_minor_units, parse_iso8601, parse_iso8601_zoned,
+    parse_utc_offset,
+};
+use crate::domain::key::{Fingerprint, RecordKey};
+use crate::transform::normalize::Normalizer;
 use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
     fmt::Display,
+    hash::{Hash, Hasher},
+    str::FromStr,
     time::{SystemTime, UNIX_EPOCH},
 };
 
-/// Type wrapper for transaction Id field.
-#[derive(Debug, Default, Eq, PartialEq, Hash, Clone, Copy)]
-pub struct TxIdType(pub u64);
+/// Transaction identifier field: numeric id or UUID.
+///
+/// Numeric ids are ordered before UUIDs.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum TxIdType {
+    /// Numeric transaction id.
+    Numeric(u64),
+    /// UUID as big-endian 128-bit integer, displayed in canonical hyphenated form.
+    #[cfg_attr(feature = "serde", serde(with = "uuid_string"))]
+    Uuid(u128),
+}
+
+impl TxIdType {
+    /// Length of UUID in canonical form.
+    pub const UUID_LEN: usize = 36;
+
+    /// Numeric id, `None` for UUID.
+    pub fn as_numeric(&self) -> Option<u64> {
+        match self {
+            TxIdType::Numeric(id) => Some(*id),
+            TxIdType::Uuid(_) => None,
+        }
+    }
+    /// UUID of the transaction, `None` for numeric id.
+    pub fn as_uuid(&self) -> Option<u128> {
+        match self {
+            TxIdType::Numeric(_) => None,
+            TxIdType::Uuid(uuid) => Some(*uuid),
+        }
+    }
+    /// Parses UUID in canonical `8-4-4-4-12` hex digits form, in either case.
+    pub fn parse_uuid(value: &str) -> Result<Self, ParserError> {
+        let bytes = value.as_bytes();
+        let valid = Self::UUID_LEN == bytes.len()
+            && bytes.iter().enumerate().all(|(i, b)| match i {
+                8 | 13 | 18 | 23 => b'-' == *b,
+                _ => b.is_ascii_hexdigit(),
+            });
+        if !valid {
+            return Err(ParserError::UnparsableValue(value.into()));
+        }
+        let digits: String = value.chars().filter(|c| '-' != *c).collect();
+        u128::from_str_radix(&digits, 16)
+            .map(TxIdType::Uuid)
+            .map_err(|_| ParserError::UnparsableValue(value.into()))
+    }
+
+    // numeric slot and optional UUID of binary layouts, numeric slot of UUID id is 0
+    pub(crate) fn from_parts(numeric: u64, uuid: Option<u128>) -> Self {
+        uuid.map_or(TxIdType::Numeric(numeric), TxIdType::Uuid)
+    }
+}
+
+impl Default for TxIdType {
+    fn default() -> Self {
+        TxIdType::Numeric(0)
+    }
+}
+
+impl From<u64> for TxIdType {
+    fn from(id: u64) -> Self {
+        TxIdType::Numeric(id)
+    }
+}
+
 impl Display for TxIdType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
+        match self {
+            TxIdType::Numeric(id) => id.fmt(f),
+            TxIdType::Uuid(uuid) => write!(
+                f,
+                "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+                uuid >> 96,
+                (uuid >> 80) & 0xffff,
+                (uuid >> 64) & 0xffff,
+                (uuid >> 48) & 0xffff,
+                uuid & 0xffff_ffff_ffff
+            ),
+        }
+    }
+}
+
+// UUIDs are exchanged as canonical strings, JSON numbers can't hold 128 bits
+#[cfg(feature = "serde")]
+mod uuid_string {
+    use super::TxIdType;
+
+    pub(super) fn serialize<S: serde::Serializer>(uuid: &u128, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(&TxIdType::Uuid(*uuid))
+    }
+
+    pub(super) fn deserialize<'de, D: serde::Deserializer<'de>>(d: D) -> Result<u128, D::Error> {
+        let value = <std::borrow::Cow<str> as serde::Deserialize>::deserialize(d)?;
+        match TxIdType::parse_uuid(&value) {
+            Ok(TxIdType::Uuid(uuid)) => Ok(uuid),
+            _ => Err(serde::de::Error::custom(format!("invalid UUID {}", value))),
+        }
+    }
+}
+
+/// Account identifier field: numeric account id or IBAN.
+///
+/// Numeric accounts are ordered before IBAN ones.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum AccountType {
+    /// Numeric account id, `0` stands for no account in deposits and withdrawals.
+    Numeric(u64),
+    /// International bank account number in electronic format: uppercase, without spaces.
+    Iban(String),
+}
+
+impl AccountType {
+    /// Longest IBAN allowed by ISO 13616.
+    pub const MAX_IBAN_LEN: usize = 34;
+
+    /// Numeric account id, `None` for IBAN.
+    pub fn as_numeric(&self) -> Option<u64> {
+        match self {
+            AccountType::Numeric(id) => Some(*id),
+            AccountType::Iban(_) => None,
+        }
+    }
+    /// IBAN of the account, `None` for numeric account.
+    pub fn as_iban(&self) -> Option<&str> {
+        match self {
+            AccountType::Numeric(_) => None,
+            AccountType::Iban(iban) => Some(iban),
+        }
+    }
+    /// Parses IBAN in electronic or print format (`DE89 3704 ...`), its check digits are verified.
+    pub fn parse_iban(value: &str) -> Result<Self, ParserError> {
+        let iban: String = value
+            .chars()
+            .filter(|c| ' ' != *c)
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        let bytes = iban.as_bytes();
+        let valid = (15..=Self::MAX_IBAN_LEN).contains(&bytes.len())
+            && bytes[..2].iter().all(u8::is_ascii_uppercase)
+            && bytes[2..4].iter().all(u8::is_ascii_digit)
+            && bytes.iter().all(u8::is_ascii_alphanumeric)
+            && 1 == iban_mod97(&iban);
+        if !valid {
+            return Err(ParserError::UnparsableValue(value.into()));
+        }
+        Ok(AccountType::Iban(iban))
+    }
+
+    // numeric slot and optional IBAN of binary layouts, numeric slot of IBAN account is 0
+    pub(crate) fn from_parts(numeric: u64, iban: Option<String>) -> Self {
+        iban.map_or(AccountType::Numeric(numeric), AccountType::Iban)
+    }
+}
+
+// ISO 7064 MOD 97-10 remainder of IBAN with its first four characters moved to the end
+pub(crate) fn iban_mod97(iban: &str) -> u32 {
+    iban.bytes()
+        .cycle()
+        .skip(4)
+        .take(iban.len())
+        .fold(0, |rest, b| match b {
+            b'0'..=b'9' => (rest * 10 + u32::from(b - b'0')) % 97,
+            _ => (rest * 100 + u32::from(b.to_ascii_uppercase() - b'A') + 10) % 97,
+        })
+}
+
+impl Default for AccountType {
+    fn default() -> Self {
+        AccountType::Numeric(0)
+    }
+}
+
+impl From<u64> for AccountType {
+    fn from(id: u64) -> Self {
+        AccountType::Numeric(id)
     }
 }
 
-/// Type wrapper for account Id field.
-#[derive(Debug, Default, Eq, PartialEq, Hash, Clone, Copy)]
-pub struct AccountType(pub u64);
 impl Display for AccountType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
+        match self {
+            AccountType::Numeric(id) => id.fmt(f),
+            AccountType::Iban(iban) => iban.fmt(f),
+        }
     }
 }
 
 /// Type wrapper for transaction operation type/kind field.
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+///
+/// Kinds are ordered as declared.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING_SNAKE_CASE"))]
 pub enum TxKind {
     /// Incoming funds to destination account : 0->to.
     Deposit,
@@ -31,6 +220,12 @@ pub enum TxKind {
     Transfer,
     /// Outgoing funds from source account : from->0 .
     Withdrawal,
+    /// Funds returned for an earlier payment : from->to.
+    Refund,
+    /// Charge collected from source account : from->0 .
+    Fee,
+    /// Cancellation of an earlier transaction moving its funds back : from->to.
+    Reversal,
 }
 impl Display for TxKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -38,16 +233,36 @@ impl Display for TxKind {
             TxKind::Deposit => write!(f, "DEPOSIT"),
             TxKind::Transfer => write!(f, "TRANSFER"),
             TxKind::Withdrawal => write!(f, "WITHDRAWAL"),
+            TxKind::Refund => write!(f, "REFUND"),
+            TxKind::Fee => write!(f, "FEE"),
+            TxKind::Reversal => write!(f, "REVERSAL"),
         }
     }
 }
 
+impl TxKind {
+    /// Whether records of the kind take funds from their `from` account, all but deposits do.
+    pub fn debits_source(self) -> bool {
+        !matches!(self, TxKind::Deposit)
+    }
+    /// Whether records of the kind bring funds to their `to` account, withdrawals and fees don't.
+    pub fn credits_destination(self) -> bool {
+        !matches!(self, TxKind::Withdrawal | TxKind::Fee)
+    }
+    /// Direction of funds across the books: `1` for deposits bringing them in, `-1` for
+    /// withdrawals and fees taking them out, `0` for kinds moving them between accounts.
+    pub fn flow_sign(self) -> i8 {
+        i8::from(!self.debits_source()) - i8::from(!self.credits_destination())
+    }
+}
+
 /// Type wrapper for transaction timestamp field.
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TxTimestamp(pub u64);
-impl TxTimestamp {
+impl Default for TxTimestamp {
     /// Returns milliseconds for now or 0 if now before Unix epoc
-    pub fn default() -> Self {
+    fn default() -> Self {
         let now_or_zero = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .ok()
@@ -56,6 +271,8 @@ impl TxTimestamp {
             .unwrap_or(0);
         Self(now_or_zero)
     }
+}
+impl TxTimestamp {
     /// Returns timestamp as milliseconds since Unix epoc
     pub fn millis(&self) -> u64 {
         self.0
@@ -69,6 +286,130 @@ impl TxTimestamp {
         let milliseconds: u64 = value.parse()?;
         Ok(TxTimestamp::from_millis(milliseconds))
     }
+    /// Parses RFC 3339 date-time with any UTC offset, e.g. `2023-11-14T23:13:20+01:00`.
+    ///
+    /// Digits past milliseconds are dropped, date-times before Unix epoch are rejected.
+    pub fn parse_rfc3339(value: &str) -> Result<Self, ParserError> {
+        parse_iso8601(value).map(TxTimestamp::from_millis)
+    }
+    /// Formats timestamp as RFC 3339 date-time in UTC, e.g. `2023-11-14T22:13:20.000Z`.
+    pub fn to_rfc3339(&self) -> String {
+        format_iso8601(self.millis())
+    }
+    /// Same moment in local time `offset` ahead of UTC.
+    pub fn at_offset(self, offset: UtcOffset) -> ZonedTimestamp {
+        ZonedTimestamp { ts: self, offset }
+    }
+}
+
+/// Offset of local time from UTC, e.g. `+02:00` for CEST.
+///
+/// Offsets are ordered from west to east.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, Default)]
+pub struct UtcOffset(i16);
+
+impl UtcOffset {
+    /// Offset of UTC itself.
+    pub const UTC: UtcOffset = UtcOffset(0);
+
+    /// Offset of `minutes` ahead of UTC, negative ones are behind it; `None` beyond 23:59.
+    pub fn from_minutes(minutes: i32) -> Option<Self> {
+        i16::try_from(minutes)
+            .ok()
+            .filter(|minutes| minutes.unsigned_abs() < 24 * 60)
+            .map(UtcOffset)
+    }
+    /// Minutes ahead of UTC.
+    pub fn minutes(&self) -> i32 {
+        i32::from(self.0)
+    }
+}
+
+impl FromStr for UtcOffset {
+    type Err = ParserError;
+    /// Parses `+HH:MM`, `-HH:MM` or `Z`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_utc_offset(s)
+            .and_then(|minutes| Self::from_minutes(minutes as i32))
+            .ok_or_else(|| ParserError::UnparsableValue(s.into()))
+    }
+}
+
+impl Display for UtcOffset {
+    /// Formats offset as `+HH:MM` or `-HH:MM`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format_utc_offset(i64::from(self.0)))
+    }
+}
+
+/// Timestamp along with UTC offset of the local time it was recorded in.
+///
+/// Partner files deliver local times, the offset keeps their local date while records
+/// are compared by [`TxTimestamp`] moment.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default)]
+pub struct ZonedTimestamp {
+    /// Moment in time.
+    pub ts: TxTimestamp,
+    /// Offset of local time.
+    pub offset: UtcOffset,
+}
+
+impl ZonedTimestamp {
+    /// Parses RFC 3339 date-time keeping its offset, e.g. `2023-11-14T23:13:20+01:00`.
+    ///
+    /// Date-time without offset is local time at `default_offset`.
+    pub fn parse_rfc3339(value: &str, default_offset: UtcOffset) -> Result<Self, ParserError> {
+        let (millis, offset) = parse_iso8601_zoned(value, i64::from(default_offset.minutes()))?;
+        Ok(ZonedTimestamp {
+            ts: TxTimestamp::from_millis(millis),
+            offset: UtcOffset::from_minutes(offset as i32)
+                .ok_or_else(|| ParserError::UnparsableValue(value.into()))?,
+        })
+    }
+    /// Local date, e.g. `2023-11-15` for `2023-11-14T23:30:00Z` at `+01:00`.
+    pub fn local_date(&self) -> String {
+        format_iso8601_date(self.local_millis())
+    }
+    /// Formats local date-time with offset, e.g. `2023-11-14T23:13:20.000+01:00`.
+    pub fn to_rfc3339(&self) -> String {
+        format_iso8601_zoned(self.ts.millis(), i64::from(self.offset.minutes()))
+    }
+
+    // wall clock milliseconds, clamped to Unix epoch
+    fn local_millis(&self) -> u64 {
+        self.ts
+            .millis()
+            .saturating_add_signed(i64::from(self.offset.minutes()) * 60_000)
+    }
+}
+
+impl Display for ZonedTimestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_rfc3339())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<TxTimestamp> for chrono::DateTime<chrono::Utc> {
+    type Error = ParserError;
+    /// Fails on timestamps past the last date-time chrono represents.
+    fn try_from(ts: TxTimestamp) -> Result<Self, Self::Error> {
+        i64::try_from(ts.millis())
+            .ok()
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .ok_or_else(|| ParserError::UnparsableValue(ts.to_string()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::DateTime<chrono::Utc>> for TxTimestamp {
+    type Error = ParserError;
+    /// Fails on date-times before Unix epoch, sub-millisecond part is dropped.
+    fn try_from(dt: chrono::DateTime<chrono::Utc>) -> Result<Self, Self::Error> {
+        u64::try_from(dt.timestamp_millis())
+            .map(TxTimestamp::from_millis)
+            .map_err(|_| ParserError::UnparsableValue(dt.to_rfc3339()))
+    }
 }
 
 impl Display for TxTimestamp {
@@ -77,8 +418,240 @@ impl Display for TxTimestamp {
     }
 }
 
+/// Money amount in minor currency units with number of fraction digits.
+///
+/// `scale` tells what a minor unit is: 2 for cents, 0 for JPY, 3 for BHD, so `1234` is
+/// `12.34`, `1234` or `1.234`. Amounts are compared and hashed by value, `5.0` equals `5.00`.
+/// Formats storing bare minor units (CSV, text and XLSX by default) carry no scale, amounts
+/// are written rescaled to [`Money::DEFAULT_SCALE`] and read back with it.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Money {
+    /// Amount in minor currency units.
+    pub minor_units: i64,
+    /// Number of fraction digits of a currency unit.
+    pub scale: u8,
+}
+
+impl Money {
+    /// Scale of amounts read without one, minor units are cents.
+    pub const DEFAULT_SCALE: u8 = 2;
+    /// Largest scale, one currency unit still fits minor units.
+    pub const MAX_SCALE: u8 = 18;
+
+    /// Creates amount of `minor_units` with `scale` fraction digits.
+    pub fn new(minor_units: i64, scale: u8) -> Self {
+        Self { minor_units, scale }
+    }
+    /// Creates amount of `minor_units` with [`Money::DEFAULT_SCALE`].
+    pub fn from_minor_units(minor_units: i64) -> Self {
+        Self::new(minor_units, Self::DEFAULT_SCALE)
+    }
+    /// Same amount with `scale` fraction digits, `None` if it overflows or loses digits.
+    pub fn rescale(self, scale: u8) -> Option<Self> {
+        let minor_units = if scale >= self.scale {
+            self.minor_units
+                .checked_mul(10i64.checked_pow(u32::from(scale - self.scale))?)?
+        } else {
+            let divisor = 10i64.checked_pow(u32::from(self.scale - scale))?;
+            if self.minor_units % divisor != 0 {
+                return None;
+            }
+            self.minor_units / divisor
+        };
+        Some(Self::new(minor_units, scale))
+    }
+    /// Sum of amounts at the larger of their scales, `None` on overflow.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (a, b) = self.aligned(other)?;
+        Some(Self::new(
+            a.minor_units.checked_add(b.minor_units)?,
+            a.scale,
+        ))
+    }
+    /// Difference of amounts at the larger of their scales, `None` on overflow.
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (a, b) = self.aligned(other)?;
+        Some(Self::new(
+            a.minor_units.checked_sub(b.minor_units)?,
+            a.scale,
+        ))
+    }
+    /// Negated amount, `None` on overflow.
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self::new(self.minor_units.checked_neg()?, self.scale))
+    }
+
+    /// Same amount with trailing zero fraction digits dropped, `5.00` is `5`.
+    pub fn normalized(self) -> Self {
+        let (mut minor_units, mut scale) = (self.minor_units, self.scale);
+        while scale > 0 && minor_units % 10 == 0 {
+            minor_units /= 10;
+            scale -= 1;
+        }
+        Self::new(minor_units, scale)
+    }
+
+    fn aligned(self, other: Self) -> Option<(Self, Self)> {
+        let scale = self.scale.max(other.scale);
+        Some((self.rescale(scale)?, other.rescale(scale)?))
+    }
+
+    // exact value in units of a finer scale, `None` when it does not fit i128
+    fn value_at(self, scale: u8) -> Option<i128> {
+        i128::from(self.minor_units)
+            .checked_mul(10i128.checked_pow(u32::from(scale.saturating_sub(self.scale)))?)
+    }
+}
+
+impl Default for Money {
+    fn default() -> Self {
+        Self::from_minor_units(0)
+    }
+}
+
+impl PartialEq for Money {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Money {}
+
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Money {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        // only the coarser amount is scaled up, overflowing it outweighs the other one
+        match (self.value_at(scale), other.value_at(scale)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (None, _) => self.minor_units.cmp(&0),
+            (_, None) => 0.cmp(&other.minor_units),
+        }
+    }
+}
+
+impl Hash for Money {
+    // equal amounts of different scales hash alike once normalized
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Money { minor_units, scale } = self.normalized();
+        minor_units.hash(state);
+        scale.hash(state);
+    }
+}
+
+impl Display for Money {
+    /// Formats amount as decimal with `scale` fraction digits, e.g. `-12.30`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            format_decimal_minor_units(self.minor_units, u32::from(self.scale))
+        )
+    }
+}
+
+impl FromStr for Money {
+    type Err = ParserError;
+    /// Parses decimal, its scale is the number of fraction digits: `5` is 0, `5.00` is 2.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let scale = s.split_once('.').map_or(0, |(_, fraction)| fraction.len());
+        if scale > usize::from(Self::MAX_SCALE) {
+            return Err(ParserError::UnparsableValue(s.into()));
+        }
+        let scale = scale as u8;
+        Ok(Self::new(
+            parse_decimal_minor_units(s, u32::from(scale))?,
+            scale,
+        ))
+    }
+}
+
+/// Exchange rate of original currency into the currency of amount, decimal like `1.0825`.
+///
+/// Rates are compared and hashed by value, `1.10` equals `1.1`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExchangeRate {
+    /// Rate without decimal point, `10825` for `1.0825`.
+    pub mantissa: i64,
+    /// Number of fraction digits of the rate.
+    pub scale: u8,
+}
+
+impl ExchangeRate {
+    /// Creates rate of `mantissa` with `scale` fraction digits.
+    pub fn new(mantissa: i64, scale: u8) -> Self {
+        Self { mantissa, scale }
+    }
+
+    /// Exact amount of original currency `amount` in the currency of rate, `None` on overflow.
+    ///
+    /// Scale of the result is the sum of scales, `100.00 * 1.0825` is `108.250000`.
+    pub fn convert(self, amount: Money) -> Option<Money> {
+        let scale = amount.scale.checked_add(self.scale)?;
+        if scale > Money::MAX_SCALE {
+            return None;
+        }
+        Some(Money::new(
+            amount.minor_units.checked_mul(self.mantissa)?,
+            scale,
+        ))
+    }
+
+    fn as_money(self) -> Money {
+        Money::new(self.mantissa, self.scale)
+    }
+}
+
+impl PartialEq for ExchangeRate {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_money() == other.as_money()
+    }
+}
+impl Eq for ExchangeRate {}
+
+impl PartialOrd for ExchangeRate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for ExchangeRate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_money().cmp(&other.as_money())
+    }
+}
+
+impl Hash for ExchangeRate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_money().hash(state);
+    }
+}
+
+impl Display for ExchangeRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_money())
+    }
+}
+
+impl FromStr for ExchangeRate {
+    type Err = ParserError;
+    /// Parses decimal, its scale is the number of fraction digits.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Money { minor_units, scale } = s.parse()?;
+        Ok(Self::new(minor_units, scale))
+    }
+}
+
 /// Transaction processing status enum.
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+///
+/// Statuses are ordered as declared.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING_SNAKE_CASE"))]
 pub enum TxStatus {
     /// Transaction was processed successfully.
     Success,
@@ -86,6 +659,12 @@ pub enum TxStatus {
     Failure,
     /// Transaction processing is in progress.
     Pending,
+    /// Transaction was cancelled before settlement.
+    Cancelled,
+    /// Settled transaction was reversed afterwards.
+    Reversed,
+    /// Transaction was not completed in time.
+    Expired,
 }
 
 impl Display for TxStatus {
@@ -94,12 +673,16 @@ impl Display for TxStatus {
             TxStatus::Success => write!(f, "SUCCESS"),
             TxStatus::Failure => write!(f, "FAILURE"),
             TxStatus::Pending => write!(f, "PENDING"),
+            TxStatus::Cancelled => write!(f, "CANCELLED"),
+            TxStatus::Reversed => write!(f, "REVERSED"),
+            TxStatus::Expired => write!(f, "EXPIRED"),
         }
     }
 }
 
 /// Transaction record domain model.
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TxRecord {
     /// Unique transaction identifier.
     pub id: TxIdType,
@@ -109,14 +692,146 @@ pub struct TxRecord {
     pub from: AccountType,
     /// Destination account id.
     pub to: AccountType,
-    /// Amount in minimal currency units.
-    pub amount: i64,
+    /// Transferred amount, gross of fee.
+    pub amount: Money,
+    /// Processing fee charged on the amount, if reported.
+    pub fee: Option<Money>,
+    /// Amount in original currency of cross-currency transfer, if converted.
+    pub original_amount: Option<Money>,
+    /// Rate the original amount was converted into the amount with, if converted.
+    pub exchange_rate: Option<ExchangeRate>,
     /// Transaction processing timestamp.
     pub ts: TxTimestamp,
     /// Processing status.
     pub status: TxStatus,
-    /// Transaction description/ operation purpose.
-    pub description: String,
+    /// Transaction description/ operation purpose, if any.
+    ///
+    /// Formats without missing values (binary ones, markdown, xlsx) store missing description
+    /// as empty one and read empty one as missing.
+    pub description: Option<String>,
+    /// Tenant/source system the record belongs to, if known.
+    pub tenant: Option<String>,
+    /// Reference of the transaction in external (counterparty) system, if any.
+    pub reference: Option<String>,
+    /// Unrecognized CSV columns and text keys kept for round-tripping, by their names.
+    ///
+    /// Filled only when the dialect keeps extras. CSV and text codecs write them back,
+    /// except for CSV sinks and headerless CSV; other codecs drop them.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extras: BTreeMap<String, String>,
+}
+
+impl PartialOrd for TxRecord {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for TxRecord {
+    /// Records are ordered by timestamp, then by id.
+    ///
+    /// Records of the same timestamp and id are ordered by the rest of their fields in
+    /// declaration order, so only equal records compare as equal.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.ts, self.id)
+            .cmp(&(other.ts, other.id))
+            .then_with(|| self.kind.cmp(&other.kind))
+            .then_with(|| self.from.cmp(&other.from))
+            .then_with(|| self.to.cmp(&other.to))
+            .then_with(|| self.amount.cmp(&other.amount))
+            .then_with(|| self.fee.cmp(&other.fee))
+            .then_with(|| self.original_amount.cmp(&other.original_amount))
+            .then_with(|| self.exchange_rate.cmp(&other.exchange_rate))
+            .then_with(|| self.status.cmp(&other.status))
+            .then_with(|| self.description.cmp(&other.description))
+            .then_with(|| self.tenant.cmp(&other.tenant))
+            .then_with(|| self.reference.cmp(&other.reference))
+            .then_with(|| self.extras.cmp(&other.extras))
+    }
+}
+
+impl TxRecord {
+    /// Amount net of fee, the amount itself without fee, `None` on overflow.
+    pub fn net_amount(&self) -> Option<Money> {
+        match self.fee {
+            Some(fee) => self.amount.checked_sub(fee),
+            None => Some(self.amount),
+        }
+    }
+
+    /// Account the funds are taken from, `None` for deposits coming from outside.
+    pub fn source(&self) -> Option<&AccountType> {
+        Some(&self.from).filter(|_| self.kind.debits_source())
+    }
+
+    /// Account the funds go to, `None` for withdrawals and fees leaving to outside.
+    pub fn destination(&self) -> Option<&AccountType> {
+        Some(&self.to).filter(|_| self.kind.credits_destination())
+    }
+
+    /// Minor units the record moves into `account`: amount for destination, negated amount
+    /// for source, 0 for other accounts and transfers to the same account.
+    ///
+    /// Status and fee are not taken into account, debit of `i64::MIN` saturates.
+    pub fn effect_on(&self, account: &AccountType) -> i64 {
+        let units = self.amount.minor_units;
+        match (
+            self.destination() == Some(account),
+            self.source() == Some(account),
+        ) {
+            (true, false) => units,
+            (false, true) => units.saturating_neg(),
+            _ => 0,
+        }
+    }
+
+    /// Other side of the record for `account` on one of its sides, `None` when the other side
+    /// is outside (deposits and withdrawals) or `account` is not involved.
+    pub fn counterparty(&self, account: &AccountType) -> Option<&AccountType> {
+        if self.source() == Some(account) {
+            self.destination()
+        } else if self.destination() == Some(account) {
+            self.source()
+        } else {
+            None
+        }
+    }
+
+    /// Copy of the record with cosmetic differences between sources normalized by
+    /// default [`Normalizer`].
+    pub fn normalize(&self) -> TxRecord {
+        Normalizer::default().normalize(self)
+    }
+
+    /// Stable SHA-256 fingerprint of all record fields but extras, equal for equal records
+    /// read from any format.
+    ///
+    /// Amounts are compared by value, so `1.50` and `1.5` give the same fingerprint.
+    pub fn fingerprint(&self) -> Fingerprint {
+        RecordKey::FullRecord.identity(self).fingerprint()
+    }
+
+    /// Fingerprint of the record without `excluded` fields, e.g. without `STATUS` to match
+    /// records of a transaction before and after its settlement.
+    pub fn fingerprint_excluding(&self, excluded: &[TxFieldKey]) -> Fingerprint {
+        RecordKey::excluding(excluded).identity(self).fingerprint()
+    }
+}
+
+impl Display for TxRecord {
+    /// One-line summary of the record, e.g. `#42 TRANSFER 11→22 -5.00 PENDING @2024-01-03`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{} {} {}→{} {} {} @{}",
+            self.id,
+            self.kind,
+            self.from,
+            self.to,
+            self.amount,
+            self.status,
+            format_iso8601_date(self.ts.millis())
+        )
+    }
 }
 
 impl Default for TxRecord {
@@ -127,9 +842,15 @@ impl Default for TxRecord {
             from: Default::default(),
             to: Default::default(),
             amount: Default::default(),
+            fee: Default::default(),
+            original_amount: Default::default(),
+            exchange_rate: Default::default(),
             ts: TxTimestamp::default(),
             status: TxStatus::Failure,
             description: Default::default(),
+            tenant: Default::default(),
+            reference: Default::default(),
+            extras: Default::default(),
         }
     }
 }
@@ -144,12 +865,184 @@ mod tests_tx {
         assert_eq!(tx, tx.clone());
     }
 
+    #[test]
+    fn records_are_ordered_by_timestamp_then_id() {
+        let tx = |id: u64, ts: u64| TxRecord {
+            id: TxIdType::Numeric(id),
+            ts: TxTimestamp(ts),
+            ..Default::default()
+        };
+        let mut data = vec![tx(1, 300), tx(3, 100), tx(2, 100)];
+        data.sort();
+        assert_eq!(data, vec![tx(2, 100), tx(3, 100), tx(1, 300)]);
+        assert_eq!(data.binary_search(&tx(3, 100)), Ok(1));
+
+        let described = TxRecord {
+            description: Some("a".to_string()),
+            ..tx(2, 100)
+        };
+        assert!(tx(2, 100) < described);
+        assert!(TxIdType::Numeric(1) < TxIdType::Numeric(2) && TxTimestamp(1) < TxTimestamp(2));
+    }
+
+    #[test]
+    fn effect_follows_kind_sign_rules() {
+        let (alice, bob) = (AccountType::Numeric(1), AccountType::Numeric(2));
+        let tx = |kind: TxKind, from: &AccountType, to: &AccountType| TxRecord {
+            kind,
+            from: from.clone(),
+            to: to.clone(),
+            amount: Money::from_minor_units(500),
+            ..Default::default()
+        };
+
+        let transfer = tx(TxKind::Transfer, &alice, &bob);
+        assert_eq!(transfer.effect_on(&alice), -500);
+        assert_eq!(transfer.effect_on(&bob), 500);
+        assert_eq!(transfer.effect_on(&AccountType::Numeric(3)), 0);
+        assert_eq!(transfer.counterparty(&alice), Some(&bob));
+        assert_eq!(transfer.counterparty(&bob), Some(&alice));
+        assert_eq!(transfer.counterparty(&AccountType::Numeric(3)), None);
+
+        // deposits and withdrawals ignore the outside side even if it is filled in
+        let deposit = tx(TxKind::Deposit, &alice, &bob);
+        assert_eq!(
+            (deposit.effect_on(&alice), deposit.effect_on(&bob)),
+            (0, 500)
+        );
+        assert_eq!(deposit.counterparty(&bob), None);
+        assert_eq!(deposit.source(), None);
+        for kind in [TxKind::Withdrawal, TxKind::Fee] {
+            let withdrawal = tx(kind, &alice, &bob);
+            assert_eq!(withdrawal.effect_on(&alice), -500);
+            assert_eq!(withdrawal.effect_on(&bob), 0);
+            assert_eq!(withdrawal.counterparty(&alice), None);
+        }
+        for kind in [TxKind::Refund, TxKind::Reversal] {
+            assert_eq!(tx(kind, &alice, &bob).effect_on(&bob), 500);
+        }
+
+        assert_eq!(tx(TxKind::Transfer, &alice, &alice).effect_on(&alice), 0);
+        let overflowing = TxRecord {
+            amount: Money::from_minor_units(i64::MIN),
+            ..transfer
+        };
+        assert_eq!(overflowing.effect_on(&alice), i64::MAX);
+    }
+
+    #[test]
+    fn tx_is_displayed_as_summary() {
+        let tx = TxRecord {
+            id: TxIdType::Numeric(42),
+            kind: TxKind::Transfer,
+            from: AccountType::Numeric(11),
+            to: AccountType::Numeric(22),
+            amount: Money::from_minor_units(-500),
+            ts: TxTimestamp::from_millis(1_704_276_930_500),
+            status: TxStatus::Pending,
+            description: Some("rent".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            tx.to_string(),
+            "#42 TRANSFER 11→22 -5.00 PENDING @2024-01-03"
+        );
+    }
+
+    #[test]
+    fn iban_is_validated_and_normalized() {
+        let iban = AccountType::parse_iban("de89 3704 0044 0532 0130 00").unwrap();
+        assert_eq!(iban.as_iban(), Some("DE89370400440532013000"));
+        assert_eq!(iban.as_numeric(), None);
+        assert_eq!(iban.to_string(), "DE89370400440532013000");
+        // wrong check digits, too short, bad characters
+        for invalid in [
+            "DE88370400440532013000",
+            "DE89",
+            "DE89-3704-0044-0532-0130-00",
+        ] {
+            assert!(AccountType::parse_iban(invalid).is_err());
+        }
+        assert_eq!(AccountType::from(42).as_numeric(), Some(42));
+    }
+
+    #[test]
+    fn uuid_is_parsed_and_displayed_canonically() {
+        let id = TxIdType::parse_uuid("123E4567-e89b-12d3-A456-426614174000").unwrap();
+        assert_eq!(id, TxIdType::Uuid(0x123e4567_e89b_12d3_a456_426614174000));
+        assert_eq!(id.to_string(), "123e4567-e89b-12d3-a456-426614174000");
+        assert_eq!(
+            TxIdType::Uuid(1).to_string(),
+            "00000000-0000-0000-0000-000000000001"
+        );
+        assert_eq!(id.as_numeric(), None);
+        for invalid in [
+            "123e4567e89b12d3a456426614174000",
+            "123e4567-e89b-12d3-a456-42661417400",
+            "{23e4567-e89b-12d3-a456-426614174000",
+            "+23e4567-e89b-12d3-a456-426614174000",
+        ] {
+            assert!(TxIdType::parse_uuid(invalid).is_err());
+        }
+        assert!(TxIdType::Numeric(u64::MAX) < TxIdType::Uuid(0));
+    }
+
     #[test]
     fn ts_is_equal() {
         let ts = TxTimestamp(42424242);
         assert_eq!(ts, ts.clone());
     }
 
+    #[test]
+    fn money_is_compared_by_value() {
+        let cents = Money::new(500, 2);
+        assert_eq!(cents, Money::new(5, 0));
+        assert_eq!(cents, "5.000".parse().unwrap());
+        assert_ne!(cents, Money::new(500, 0));
+        assert!(Money::new(1, 3) < Money::new(1, 2));
+
+        let hash = |money: Money| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            money.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(cents), hash(Money::new(5, 0)));
+    }
+
+    #[test]
+    fn money_arithmetic_is_checked() {
+        let yen = Money::new(500, 0);
+        let dinar = Money::new(1_234, 3);
+        assert_eq!(yen.checked_add(dinar), Some(Money::new(501_234, 3)));
+        assert_eq!(yen.checked_sub(dinar).unwrap().to_string(), "498.766");
+        assert_eq!(Money::new(i64::MAX, 0).checked_add(Money::new(1, 0)), None);
+        assert_eq!(Money::new(i64::MIN, 0).checked_neg(), None);
+        assert_eq!(yen.rescale(Money::MAX_SCALE), None);
+        assert_eq!(dinar.rescale(2), None);
+        assert_eq!(Money::new(1_230, 3).rescale(2), Some(Money::new(123, 2)));
+    }
+
+    #[test]
+    fn money_is_formatted_and_parsed_with_its_scale() {
+        assert_eq!(Money::new(-1_230, 2).to_string(), "-12.30");
+        assert_eq!(Money::new(500, 0).to_string(), "500");
+        assert_eq!(Money::new(5, 3).to_string(), "0.005");
+        let parsed: Money = "-0.005".parse().unwrap();
+        assert_eq!((parsed.minor_units, parsed.scale), (-5, 3));
+        assert_eq!("42".parse::<Money>().unwrap().scale, 0);
+        assert!("1.0000000000000000000".parse::<Money>().is_err());
+        assert!("12,3".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn ts_rfc3339_round_trip() {
+        let ts = TxTimestamp::parse_rfc3339("2023-11-14T23:13:20.5+01:00").unwrap();
+        assert_eq!(ts.millis(), 1_700_000_000_500);
+        assert_eq!(ts.to_rfc3339(), "2023-11-14T22:13:20.500Z");
+        assert_eq!(TxTimestamp::parse_rfc3339(&ts.to_rfc3339()).unwrap(), ts);
+        assert!(TxTimestamp::parse_rfc3339("1969-12-31T23:59:59Z").is_err());
+    }
+
     #[test]
     fn ts_parse() {
         let err = TxTimestamp::parse_timestamp("asdf").expect_err("unparseable uint");
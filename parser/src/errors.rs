@@ -1,5 +1,6 @@
 use std::{fmt::Display, io};
 
+use crate::codecs::errors::IntegrityError;
 use crate::codecs::errors::ParserContext;
 use crate::codecs::errors::ParserError;
 
@@ -17,6 +18,8 @@ pub enum AppError {
         /// Concrete parsing error cause.
         source: ParserError,
     },
+    /// Signed input failed verification.
+    IntegrityError(IntegrityError),
 }
 
 impl std::error::Error for AppError {
@@ -25,6 +28,7 @@ impl std::error::Error for AppError {
             AppError::ReadError(e) => Some(e),
             AppError::WriteError(e) => Some(e),
             AppError::ParsingError { context: _, source } => Some(source),
+            AppError::IntegrityError(e) => Some(e),
         }
     }
 }
@@ -35,8 +39,9 @@ impl Display for AppError {
             AppError::ReadError(e) => write!(f, "read error, {}", e),
             AppError::WriteError(e) => write!(f, "write error, {}", e),
             AppError::ParsingError { context, source } => {
-                writeln!(f, "{}:\n{}", source.to_string(), context.to_string(),)
+                writeln!(f, "{}:\n{}", source, context)
             }
+            AppError::IntegrityError(e) => write!(f, "integrity error, {}", e),
         }
     }
 }
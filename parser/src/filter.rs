@@ -0,0 +1,492 @@
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::codecs::base::TxFieldKey;
+use crate::codecs::errors::{ParserContext, ParserError};
+use crate::codecs::utils::parse_iso8601;
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+/// Predicate over records compiled from a filter expression.
+///
+/// Expression is a combination of comparisons `FIELD OP VALUE` with `&&`, `||`, `!` and
+/// parentheses, `&&` binding tighter than `||`, e.g.
+/// ```text
+/// amount > 1000 && kind == TRANSFER && ts >= 2024-01-01
+/// (from == 42 || to == 42) && !(description ~ "refund")
+/// ```
+/// Fields are named as in text format (`TX_ID`, `TX_TYPE`, ...) or by their short names:
+///
+/// | Field | Short names | Operators | Value |
+/// |-------|-------------|-----------|-------|
+/// | `TX_ID` | `id` | `== != < <= > >=` | id |
+/// | `TX_TYPE` | `kind`, `type` | `== !=` | e.g. `TRANSFER` |
+/// | `FROM_USER_ID` | `from` | `== != < <= > >=` | account |
+/// | `TO_USER_ID` | `to` | `== != < <= > >=` | account |
+/// | either side | `account` | `== != < <= > >=` | account |
+/// | `AMOUNT` | `amount` | `== != < <= > >=` | decimal, compared by value |
+/// | `FEE` | `fee` | `== != < <= > >=` | decimal, compared by value |
+/// | `TIMESTAMP` | `ts`, `timestamp` | `== != < <= > >=` | millis or ISO 8601 UTC date/time |
+/// | `STATUS` | `status` | `== !=` | e.g. `SUCCESS` |
+/// | `DESCRIPTION` | `description` | `== != ~` | word or quoted string |
+/// | `TENANT` | `tenant` | `== != ~` | word or quoted string |
+/// | `REFERENCE` | `reference` | `== != ~` | word or quoted string |
+///
+/// `~` tests that the field contains the value ignoring case. `a != b` is `!(a == b)`, so it
+/// holds for records without the field, other comparisons never hold for them. `account` holds
+/// if comparison holds for either side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    source: String,
+    expr: Expr,
+}
+
+impl Filter {
+    /// Compiles expression, error context holds byte position of the offending token.
+    pub fn parse(s: &str) -> Result<Self, AppError> {
+        let tokens = tokenize(s)?;
+        let mut parser = ExprParser {
+            tokens,
+            pos: 0,
+            end: s.len(),
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(filter_error(
+                token.at,
+                ParserError::UnparsableValue(token.kind.to_string()),
+            ));
+        }
+        Ok(Self {
+            source: s.trim().to_string(),
+            expr,
+        })
+    }
+
+    /// True if the record satisfies the expression.
+    pub fn matches(&self, tx: &TxRecord) -> bool {
+        self.expr.eval(tx)
+    }
+
+    /// Returns records satisfying the expression, in input order.
+    pub fn apply(&self, data: &[TxRecord]) -> Vec<TxRecord> {
+        data.iter().filter(|tx| self.matches(tx)).cloned().collect()
+    }
+}
+
+impl FromStr for Filter {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Filter::parse(s)
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn filter_error(position: usize, source: ParserError) -> AppError {
+    AppError::ParsingError {
+        context: ParserContext::with_position(position),
+        source,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Condition(Condition),
+}
+
+impl Expr {
+    fn eval(&self, tx: &TxRecord) -> bool {
+        match self {
+            Expr::Not(expr) => !expr.eval(tx),
+            Expr::And(a, b) => a.eval(tx) && b.eval(tx),
+            Expr::Or(a, b) => a.eval(tx) || b.eval(tx),
+            Expr::Condition(condition) => condition.eval(tx),
+        }
+    }
+}
+
+// comparison of ordered values, `!=` is compiled into negated `==`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering.is_eq(),
+            Comparison::Lt => ordering.is_lt(),
+            Comparison::Le => ordering.is_le(),
+            Comparison::Gt => ordering.is_gt(),
+            Comparison::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextField {
+    Description,
+    Tenant,
+    Reference,
+}
+
+impl TextField {
+    fn value<'a>(&self, tx: &'a TxRecord) -> Option<&'a str> {
+        match self {
+            TextField::Description => tx.description.as_deref(),
+            TextField::Tenant => tx.tenant.as_deref(),
+            TextField::Reference => tx.reference.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    Id(Comparison, TxIdType),
+    Kind(TxKind),
+    From(Comparison, AccountType),
+    To(Comparison, AccountType),
+    Account(Comparison, AccountType),
+    Amount(Comparison, Money),
+    Fee(Comparison, Money),
+    Timestamp(Comparison, TxTimestamp),
+    Status(TxStatus),
+    TextEquals(TextField, String),
+    // value is lowercase
+    TextContains(TextField, String),
+}
+
+impl Condition {
+    fn eval(&self, tx: &TxRecord) -> bool {
+        match self {
+            Condition::Id(cmp, id) => cmp.holds(tx.id.cmp(id)),
+            Condition::Kind(kind) => tx.kind == *kind,
+            Condition::From(cmp, account) => cmp.holds(tx.from.cmp(account)),
+            Condition::To(cmp, account) => cmp.holds(tx.to.cmp(account)),
+            Condition::Account(cmp, account) => {
+                cmp.holds(tx.from.cmp(account)) || cmp.holds(tx.to.cmp(account))
+            }
+            Condition::Amount(cmp, amount) => cmp.holds(tx.amount.cmp(amount)),
+            Condition::Fee(cmp, amount) => tx.fee.is_some_and(|fee| cmp.holds(fee.cmp(amount))),
+            Condition::Timestamp(cmp, ts) => cmp.holds(tx.ts.cmp(ts)),
+            Condition::Status(status) => tx.status == *status,
+            Condition::TextEquals(field, text) => field.value(tx) == Some(text.as_str()),
+            Condition::TextContains(field, text) => field
+                .value(tx)
+                .is_some_and(|value| value.to_lowercase().contains(text)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    Kind,
+    From,
+    To,
+    Account,
+    Amount,
+    Fee,
+    Timestamp,
+    Status,
+    Text(TextField),
+}
+
+impl FromStr for Field {
+    type Err = ParserError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let field = match s {
+            "id" => Field::Id,
+            "kind" | "type" => Field::Kind,
+            "from" => Field::From,
+            "to" => Field::To,
+            "account" => Field::Account,
+            "amount" => Field::Amount,
+            "fee" => Field::Fee,
+            "ts" | "timestamp" => Field::Timestamp,
+            "status" => Field::Status,
+            "description" => Field::Text(TextField::Description),
+            "tenant" => Field::Text(TextField::Tenant),
+            "reference" => Field::Text(TextField::Reference),
+            _ => match s.parse::<TxFieldKey>()? {
+                TxFieldKey::Id => Field::Id,
+                TxFieldKey::TxKind => Field::Kind,
+                TxFieldKey::FromUserId => Field::From,
+                TxFieldKey::ToUserId => Field::To,
+                TxFieldKey::Amount => Field::Amount,
+                TxFieldKey::Fee => Field::Fee,
+                TxFieldKey::Timestamp => Field::Timestamp,
+                TxFieldKey::Status => Field::Status,
+                TxFieldKey::Description => Field::Text(TextField::Description),
+                TxFieldKey::Tenant => Field::Text(TextField::Tenant),
+                TxFieldKey::Reference => Field::Text(TextField::Reference),
+                other => return Err(ParserError::UnparsableKey(other.to_string())),
+            },
+        };
+        Ok(field)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+impl Field {
+    // `None` for operators not applicable to the field
+    fn condition(&self, op: Operator, value: &str) -> Option<Result<Expr, ParserError>> {
+        let cmp = match op {
+            Operator::Eq | Operator::Ne | Operator::Contains => Comparison::Eq,
+            Operator::Lt => Comparison::Lt,
+            Operator::Le => Comparison::Le,
+            Operator::Gt => Comparison::Gt,
+            Operator::Ge => Comparison::Ge,
+        };
+        let contains = matches!(op, Operator::Contains);
+        let equality = matches!(op, Operator::Eq | Operator::Ne);
+        let condition = match self {
+            Field::Id if !contains => value.parse().map(|id| Condition::Id(cmp, id)),
+            Field::Kind if equality => value.to_ascii_uppercase().parse().map(Condition::Kind),
+            Field::From if !contains => value.parse().map(|a| Condition::From(cmp, a)),
+            Field::To if !contains => value.parse().map(|a| Condition::To(cmp, a)),
+            Field::Account if !contains => value.parse().map(|a| Condition::Account(cmp, a)),
+            Field::Amount if !contains => value.parse().map(|m| Condition::Amount(cmp, m)),
+            Field::Fee if !contains => value.parse().map(|m| Condition::Fee(cmp, m)),
+            Field::Timestamp if !contains => {
+                parse_timestamp(value).map(|ts| Condition::Timestamp(cmp, ts))
+            }
+            Field::Status if equality => value.to_ascii_uppercase().parse().map(Condition::Status),
+            Field::Text(field) if equality => Ok(Condition::TextEquals(*field, value.into())),
+            Field::Text(field) if contains => {
+                Ok(Condition::TextContains(*field, value.to_lowercase()))
+            }
+            _ => return None,
+        };
+        Some(condition.map(|condition| match op {
+            Operator::Ne => Expr::Not(Box::new(Expr::Condition(condition))),
+            _ => Expr::Condition(condition),
+        }))
+    }
+}
+
+// milliseconds since Unix epoch or ISO 8601 date/time, UTC unless it holds offset
+fn parse_timestamp(value: &str) -> Result<TxTimestamp, ParserError> {
+    if value.bytes().all(|b| b.is_ascii_digit()) {
+        return Ok(TxTimestamp::from_millis(value.parse()?));
+    }
+    parse_iso8601(value).map(TxTimestamp::from_millis)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Word(String),
+    Quoted(String),
+    Operator(Operator),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Word(word) => write!(f, "{}", word),
+            TokenKind::Quoted(text) => write!(f, "\"{}\"", text),
+            TokenKind::Operator(op) => write!(
+                f,
+                "{}",
+                match op {
+                    Operator::Eq => "==",
+                    Operator::Ne => "!=",
+                    Operator::Lt => "<",
+                    Operator::Le => "<=",
+                    Operator::Gt => ">",
+                    Operator::Ge => ">=",
+                    Operator::Contains => "~",
+                }
+            ),
+            TokenKind::And => write!(f, "&&"),
+            TokenKind::Or => write!(f, "||"),
+            TokenKind::Not => write!(f, "!"),
+            TokenKind::Open => write!(f, "("),
+            TokenKind::Close => write!(f, ")"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    kind: TokenKind,
+    // byte position in expression
+    at: usize,
+}
+
+const SPECIAL_CHARS: &str = "()!=<>&|~\"";
+
+fn tokenize(s: &str) -> Result<Vec<Token>, AppError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        let mut followed_by = |next: char| chars.next_if(|&(_, c)| c == next).is_some();
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '(' => TokenKind::Open,
+            ')' => TokenKind::Close,
+            '~' => TokenKind::Operator(Operator::Contains),
+            '=' if followed_by('=') => TokenKind::Operator(Operator::Eq),
+            '!' if followed_by('=') => TokenKind::Operator(Operator::Ne),
+            '!' => TokenKind::Not,
+            '<' if followed_by('=') => TokenKind::Operator(Operator::Le),
+            '<' => TokenKind::Operator(Operator::Lt),
+            '>' if followed_by('=') => TokenKind::Operator(Operator::Ge),
+            '>' => TokenKind::Operator(Operator::Gt),
+            '&' if followed_by('&') => TokenKind::And,
+            '|' if followed_by('|') => TokenKind::Or,
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => text.push(escaped),
+                            None => {
+                                return Err(filter_error(at, ParserError::ShellBeQuoted(text)));
+                            }
+                        },
+                        Some((_, c)) => text.push(c),
+                        None => return Err(filter_error(at, ParserError::ShellBeQuoted(text))),
+                    }
+                }
+                TokenKind::Quoted(text)
+            }
+            c if !SPECIAL_CHARS.contains(c) => {
+                let mut word = c.to_string();
+                while let Some((_, c)) =
+                    chars.next_if(|&(_, c)| !c.is_whitespace() && !SPECIAL_CHARS.contains(c))
+                {
+                    word.push(c);
+                }
+                TokenKind::Word(word)
+            }
+            _ => return Err(filter_error(at, ParserError::UnparsableValue(c.into()))),
+        };
+        tokens.push(Token { kind, at });
+    }
+    Ok(tokens)
+}
+
+struct ExprParser {
+    tokens: Vec<Token>,
+    pos: usize,
+    // position reported for unexpected end of expression
+    end: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.pos).map(|token| &token.kind)
+    }
+
+    fn next(&mut self) -> Result<Token, AppError> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| {
+            filter_error(
+                self.end,
+                ParserError::UnparsableValue("end of filter".into()),
+            )
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn or(&mut self) -> Result<Expr, AppError> {
+        let mut expr = self.and()?;
+        while Some(&TokenKind::Or) == self.peek() {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, AppError> {
+        let mut expr = self.unary()?;
+        while Some(&TokenKind::And) == self.peek() {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, AppError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Not => Ok(Expr::Not(Box::new(self.unary()?))),
+            TokenKind::Open => {
+                let expr = self.or()?;
+                let close = self.next()?;
+                if TokenKind::Close != close.kind {
+                    return Err(filter_error(
+                        close.at,
+                        ParserError::UnparsableValue(close.kind.to_string()),
+                    ));
+                }
+                Ok(expr)
+            }
+            TokenKind::Word(name) => {
+                let field: Field = name.parse().map_err(|e| filter_error(token.at, e))?;
+                let op = self.next()?;
+                let TokenKind::Operator(operator) = op.kind else {
+                    return Err(filter_error(
+                        op.at,
+                        ParserError::UnparsableValue(op.kind.to_string()),
+                    ));
+                };
+                let value = self.next()?;
+                let text = match value.kind {
+                    TokenKind::Word(text) | TokenKind::Quoted(text) => text,
+                    other => {
+                        return Err(filter_error(
+                            value.at,
+                            ParserError::UnparsableValue(other.to_string()),
+                        ));
+                    }
+                };
+                field
+                    .condition(operator, &text)
+                    .ok_or_else(|| {
+                        filter_error(
+                            op.at,
+                            ParserError::UnparsableValue(format!("{} {}", name, op.kind)),
+                        )
+                    })?
+                    .map_err(|e| filter_error(value.at, e))
+            }
+            other => Err(filter_error(
+                token.at,
+                ParserError::UnparsableValue(other.to_string()),
+            )),
+        }
+    }
+}
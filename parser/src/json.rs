@@ -0,0 +1,293 @@
+//! Minimal JSON reader used by JSON-based inputs and writer of JSON outputs.
+
+use std::fmt::Display;
+
+use crate::codecs::errors::ParserError;
+
+/// Parsed JSON value, object keys keep document order.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    // numbers are kept as written to avoid precision loss on 64-bit integers
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub(crate) fn parse(input: &str) -> Result<Self, ParserError> {
+        let mut reader = JsonReader {
+            bytes: input.as_bytes(),
+            input,
+            pos: 0,
+        };
+        let value = reader.parse_value()?;
+        reader.skip_whitespace();
+        if reader.pos < input.len() {
+            return Err(reader.error("content after value"));
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// String or number value as text.
+    pub(crate) fn as_text(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) | JsonValue::Number(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+// compact JSON, numbers are written as kept
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonValue::Null => write!(f, "null"),
+            JsonValue::Bool(b) => write!(f, "{}", b),
+            JsonValue::Number(n) => write!(f, "{}", n),
+            JsonValue::String(s) => write_json_string(f, s),
+            JsonValue::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            JsonValue::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_json_string(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct JsonReader<'a> {
+    bytes: &'a [u8],
+    input: &'a str,
+    pos: usize,
+}
+
+impl JsonReader<'_> {
+    fn error(&self, message: &str) -> ParserError {
+        ParserError::UnparsableValue(format!("malformed json at #{}, {}", self.pos, message))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), ParserError> {
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) != Some(&b) {
+            return Err(self.error(&format!("`{}` expected", b as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, ParserError> {
+        if self.input[self.pos..].starts_with(literal) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("unknown literal"))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, ParserError> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some(b't') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some(b'n') => self.parse_literal("null", JsonValue::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while self.pos < self.bytes.len()
+                    && matches!(
+                        self.bytes[self.pos],
+                        b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'
+                    )
+                {
+                    self.pos += 1;
+                }
+                Ok(JsonValue::Number(self.input[start..self.pos].to_string()))
+            }
+            _ => Err(self.error("value expected")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, ParserError> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(b':')?;
+            fields.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(fields));
+                }
+                _ => return Err(self.error("`,` or `}` expected")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, ParserError> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(self.error("`,` or `]` expected")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, ParserError> {
+        if self.bytes.get(self.pos) != Some(&b'"') {
+            return Err(self.error("string expected"));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let rest = &self.input[self.pos..];
+            let Some(i) = rest.find(['"', '\\']) else {
+                return Err(self.error("unterminated string"));
+            };
+            out.push_str(&rest[..i]);
+            self.pos += i;
+            if b'"' == self.bytes[self.pos] {
+                self.pos += 1;
+                return Ok(out);
+            }
+            let escaped = self.bytes.get(self.pos + 1).copied();
+            self.pos += 2;
+            match escaped {
+                Some(b'"') => out.push('"'),
+                Some(b'\\') => out.push('\\'),
+                Some(b'/') => out.push('/'),
+                Some(b'n') => out.push('\n'),
+                Some(b'r') => out.push('\r'),
+                Some(b't') => out.push('\t'),
+                Some(b'b') => out.push('\u{8}'),
+                Some(b'f') => out.push('\u{c}'),
+                Some(b'u') => {
+                    let code = self
+                        .input
+                        .get(self.pos..self.pos + 4)
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .ok_or_else(|| self.error("invalid unicode escape"))?;
+                    self.pos += 4;
+                    out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                _ => return Err(self.error("invalid escape")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_json {
+    use super::*;
+
+    #[test]
+    fn json_parse_object() {
+        let value =
+            JsonValue::parse(r#" {"id": 18446744073709551615, "s": "a\"bA", "x": [true, null]} "#)
+                .expect("valid json");
+        assert_eq!(
+            value.get("id").and_then(JsonValue::as_text),
+            Some("18446744073709551615")
+        );
+        assert_eq!(value.get("s").and_then(JsonValue::as_text), Some("a\"bA"));
+        assert_eq!(
+            value.get("x"),
+            Some(&JsonValue::Array(vec![
+                JsonValue::Bool(true),
+                JsonValue::Null
+            ]))
+        );
+    }
+
+    #[test]
+    fn json_written_compact_and_parsed_back() {
+        let value = JsonValue::Object(vec![
+            ("n".into(), JsonValue::Number("-1.50".into())),
+            ("s".into(), JsonValue::String("a\"b\\\n\u{1}".into())),
+            (
+                "x".into(),
+                JsonValue::Array(vec![JsonValue::Bool(false), JsonValue::Null]),
+            ),
+        ]);
+        let json = value.to_string();
+        assert_eq!(json, r#"{"n":-1.50,"s":"a\"b\\\n\u0001","x":[false,null]}"#);
+        assert_eq!(JsonValue::parse(&json).ok(), Some(value));
+    }
+
+    #[test]
+    fn json_rejects_malformed() {
+        for input in [r#"{"a": }"#, r#"{"a": 1"#, r#""abc"#, "[1,]", "{} x"] {
+            assert!(JsonValue::parse(input).is_err(), "{} should fail", input);
+        }
+    }
+}
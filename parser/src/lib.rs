@@ -2,9 +2,30 @@
 //! Supports conversion and comparison through shared domain types.
 #![warn(missing_docs)]
 
+/// Analytics over record sets.
+pub mod analytics;
 /// Codecs for reading and writing supported file formats.
 pub mod codecs;
+/// Comparison of transaction sets.
+pub mod compare;
 /// Domain model for transaction records.
 pub mod domain;
 /// Common application-level errors.
 pub mod errors;
+/// Filter expressions over records.
+pub mod filter;
+/// Reconciliation of parsed batches against control totals.
+pub mod reconcile;
+/// Reconstruction of batch state from status events.
+pub mod replay;
+/// Free-text search over record descriptions.
+pub mod search;
+/// Record transformations applied between parsing and writing.
+pub mod transform;
+/// Rule-based validation of record sets.
+pub mod validation;
+
+#[cfg(feature = "xlsx")]
+mod deflate;
+mod digest;
+mod json;
@@ -0,0 +1,128 @@
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Read};
+
+use crate::codecs::errors::{ParserContext, ParserCtxBehavior, ParserError};
+use crate::domain::tx::TxRecord;
+use crate::errors::AppError;
+
+const CONTROL_KV_DELIMITER: char = ':';
+const CONTROL_COMMENT_SYMBOL: char = '#';
+const RECORD_COUNT_KEY: &str = "RECORD_COUNT";
+const TOTAL_AMOUNT_KEY: &str = "TOTAL_AMOUNT";
+
+/// Expected (or actual) totals of a batch, usually provided by partner in a control file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ControlTotals {
+    /// Number of records.
+    pub record_count: Option<usize>,
+    /// Sum of signed record amounts in minor units.
+    pub total_amount: Option<i128>,
+}
+
+/// Difference between expected and actual totals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlMismatch {
+    /// Record count differs.
+    RecordCount {
+        /// Count from control totals.
+        expected: usize,
+        /// Count of parsed records.
+        actual: usize,
+    },
+    /// Total amount differs.
+    TotalAmount {
+        /// Amount from control totals.
+        expected: i128,
+        /// Sum of parsed amounts.
+        actual: i128,
+    },
+}
+
+impl Display for ControlMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControlMismatch::RecordCount { expected, actual } => {
+                write!(f, "expected {} records, got {}", expected, actual)
+            }
+            ControlMismatch::TotalAmount { expected, actual } => {
+                write!(f, "expected total amount {}, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl ControlTotals {
+    /// Actual totals of records.
+    pub fn from_records(data: &[TxRecord]) -> Self {
+        Self {
+            record_count: Some(data.len()),
+            total_amount: Some(
+                data.iter()
+                    .map(|tx| i128::from(tx.amount.minor_units))
+                    .sum(),
+            ),
+        }
+    }
+
+    /// Loads totals from control file with `RECORD_COUNT: N` and `TOTAL_AMOUNT: N` lines.
+    ///
+    /// Empty lines and `#` comments are skipped, both keys are optional.
+    pub fn from_reader<R: Read>(r: R) -> Result<Self, AppError> {
+        let mut totals = Self::default();
+        for (line_num, line_res) in BufReader::new(r).lines().enumerate() {
+            let input_line = line_res.map_err(AppError::ReadError)?;
+            let line = input_line.trim();
+            if line.is_empty() || line.starts_with(CONTROL_COMMENT_SYMBOL) {
+                continue;
+            }
+            totals
+                .parse_line(line)
+                .add_parser_ctx(ParserContext::with_line_number_and_line(
+                    line_num + 1,
+                    input_line.clone(),
+                ))?;
+        }
+        Ok(totals)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), ParserError> {
+        let (key, value) = line
+            .split_once(CONTROL_KV_DELIMITER)
+            .ok_or(ParserError::NoFieldDelimiter)?;
+        let (key, value) = (key.trim(), value.trim());
+        let duplicate = match key {
+            RECORD_COUNT_KEY => self.record_count.replace(value.parse()?).is_some(),
+            TOTAL_AMOUNT_KEY => self.total_amount.replace(value.parse()?).is_some(),
+            _ => return Err(ParserError::UnparsableKey(key.into())),
+        };
+        if duplicate {
+            return Err(ParserError::UnparsableKey(format!("duplicate {}", key)));
+        }
+        Ok(())
+    }
+
+    /// Totals where values set in `overrides` replace own ones.
+    pub fn overridden_by(self, overrides: ControlTotals) -> Self {
+        Self {
+            record_count: overrides.record_count.or(self.record_count),
+            total_amount: overrides.total_amount.or(self.total_amount),
+        }
+    }
+
+    /// Compares expected totals with actual totals of records, unset totals are not checked.
+    pub fn check(&self, data: &[TxRecord]) -> Vec<ControlMismatch> {
+        let actual = Self::from_records(data);
+        let mut mismatches = Vec::new();
+        if let (Some(expected), Some(actual)) = (self.record_count, actual.record_count)
+            && expected != actual
+        {
+            mismatches.push(ControlMismatch::RecordCount { expected, actual });
+        }
+        if let (Some(expected), Some(actual)) = (self.total_amount, actual.total_amount)
+            && expected != actual
+        {
+            mismatches.push(ControlMismatch::TotalAmount { expected, actual });
+        }
+        mismatches
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read};
+
+use crate::codecs::errors::{ParserContext, ParserCtxBehavior, ParserError};
+use crate::domain::tx::*;
+use crate::errors::AppError;
+use crate::json::JsonValue;
+
+const EVENT_ID_KEY: &str = "tx_id";
+const EVENT_TIMESTAMP_KEY: &str = "timestamp";
+const EVENT_STATUS_KEY: &str = "status";
+
+/// Status change of a transaction at a point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEvent {
+    /// Transaction the event applies to.
+    pub id: TxIdType,
+    /// Moment the status changed.
+    pub ts: TxTimestamp,
+    /// New status.
+    pub status: TxStatus,
+}
+
+impl StatusEvent {
+    /// Reads events from JSONL stream, one `{"tx_id": 1, "timestamp": 1700, "status": "SUCCESS"}` per line.
+    pub fn read_jsonl<R: Read>(r: R) -> Result<Vec<StatusEvent>, AppError> {
+        let mut result = Vec::new();
+        for (line_num, line_res) in BufReader::new(r).lines().enumerate() {
+            let input_line = line_res.map_err(AppError::ReadError)?;
+            if input_line.trim().is_empty() {
+                continue;
+            }
+            result.push(Self::parse_json_line(&input_line).add_parser_ctx(
+                ParserContext::with_line_number_and_line(line_num, input_line.clone()),
+            )?);
+        }
+        Ok(result)
+    }
+
+    fn parse_json_line(line: &str) -> Result<StatusEvent, ParserError> {
+        let event = JsonValue::parse(line)?;
+        let field = |key: &str| {
+            event
+                .get(key)
+                .and_then(JsonValue::as_text)
+                .ok_or_else(|| ParserError::UnparsableKey(key.into()))
+        };
+        Ok(StatusEvent {
+            id: field(EVENT_ID_KEY)?.parse()?,
+            ts: field(EVENT_TIMESTAMP_KEY)?.parse()?,
+            status: field(EVENT_STATUS_KEY)?.parse()?,
+        })
+    }
+}
+
+/// Replays status events over a base batch to reconstruct its state at any moment.
+#[derive(Debug, Default)]
+pub struct Replay {
+    base: Vec<TxRecord>,
+    // sorted by timestamp, events with the same timestamp keep arrival order
+    events: Vec<StatusEvent>,
+}
+
+impl Replay {
+    /// Creates engine over base batch.
+    pub fn new(base: Vec<TxRecord>) -> Self {
+        Self {
+            base,
+            events: Vec::new(),
+        }
+    }
+
+    /// Adds status events, may be called several times with events in any order.
+    pub fn add_events<I: IntoIterator<Item = StatusEvent>>(&mut self, events: I) {
+        self.events.extend(events);
+        self.events.sort_by_key(|e| e.ts.millis());
+    }
+
+    /// Batch as it was at `at`: records created up to that moment with all status changes applied.
+    pub fn state_at(&self, at: TxTimestamp) -> Vec<TxRecord> {
+        let mut latest: HashMap<TxIdType, TxStatus> = HashMap::new();
+        for event in self
+            .events
+            .iter()
+            .take_while(|e| e.ts.millis() <= at.millis())
+        {
+            latest.insert(event.id, event.status);
+        }
+        self.base
+            .iter()
+            .filter(|tx| tx.ts.millis() <= at.millis())
+            .map(|tx| {
+                let mut tx = tx.clone();
+                if let Some(status) = latest.get(&tx.id) {
+                    tx.status = *status;
+                }
+                tx
+            })
+            .collect()
+    }
+
+    /// Events referring to transactions absent in the base batch.
+    pub fn unknown_events(&self) -> Vec<&StatusEvent> {
+        let known: HashSet<TxIdType> = self.base.iter().map(|tx| tx.id).collect();
+        self.events
+            .iter()
+            .filter(|e| !known.contains(&e.id))
+            .collect()
+    }
+}
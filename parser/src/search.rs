@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use crate::domain::tx::TxRecord;
+
+/// Splits text into lowercase alphanumeric tokens.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// In-memory inverted index over record descriptions.
+#[derive(Debug, Default)]
+pub struct Index {
+    // token -> ascending positions of records containing it
+    postings: HashMap<String, Vec<usize>>,
+    records: usize,
+}
+
+impl Index {
+    /// Builds index over descriptions of provided records.
+    pub fn build(data: &[TxRecord]) -> Self {
+        let mut index = Self {
+            postings: HashMap::new(),
+            records: data.len(),
+        };
+        for (position, tx) in data.iter().enumerate() {
+            for token in tokenize(tx.description.as_deref().unwrap_or_default()) {
+                let positions = index.postings.entry(token).or_default();
+                if positions.last() != Some(&position) {
+                    positions.push(position);
+                }
+            }
+        }
+        index
+    }
+
+    /// Number of indexed records.
+    pub fn len(&self) -> usize {
+        self.records
+    }
+
+    /// True if no records were indexed.
+    pub fn is_empty(&self) -> bool {
+        0 == self.records
+    }
+
+    /// Returns ascending positions of records whose description contains all query tokens.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let mut result: Option<Vec<usize>> = None;
+        for token in tokenize(query) {
+            let Some(positions) = self.postings.get(&token) else {
+                return vec![];
+            };
+            result = Some(match result {
+                None => positions.clone(),
+                Some(found) => intersect(&found, positions),
+            });
+        }
+        result.unwrap_or_default()
+    }
+}
+
+fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let (mut i, mut j) = (0, 0);
+    let mut result = Vec::new();
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                result.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,35 @@
+use std::collections::HashSet;
+
+use crate::codecs::base::TxFieldKey;
+use crate::domain::key::RecordKey;
+use crate::domain::tx::TxRecord;
+
+/// Records left after deduplication.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deduplicated {
+    /// First occurence of every record, in input order.
+    pub records: Vec<TxRecord>,
+    /// Number of removed duplicates.
+    pub removed: usize,
+}
+
+/// Key of records re-sent with a new timestamp, all fields but `TIMESTAMP`.
+pub fn resent_key() -> RecordKey {
+    RecordKey::excluding(&[TxFieldKey::Timestamp])
+}
+
+/// Removes records equal to an earlier one under `key`, e.g. [`RecordKey::FullRecord`] for
+/// exact duplicates, [`RecordKey::IdOnly`] for repeated ids or [`resent_key`].
+pub fn dedup(data: &[TxRecord], key: &RecordKey) -> Deduplicated {
+    let mut records = data.to_vec();
+    let removed = dedup_in_place(&mut records, key);
+    Deduplicated { records, removed }
+}
+
+/// Removes duplicates in place keeping first occurences, returns number of removed ones.
+pub fn dedup_in_place(data: &mut Vec<TxRecord>, key: &RecordKey) -> usize {
+    let mut seen = HashSet::new();
+    let before = data.len();
+    data.retain(|tx| seen.insert(key.identity(tx)));
+    before - data.len()
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Read};
+
+use crate::codecs::errors::{ParserContext, ParserCtxBehavior, ParserError};
+use crate::domain::tx::*;
+use crate::errors::AppError;
+use crate::json::JsonValue;
+
+/// Name of the extra field holding owner of the source account, see
+/// [`EnrichedRecord::into_record`].
+pub const FROM_OWNER_KEY: &str = "FROM_OWNER";
+/// Name of the extra field holding country of the source account.
+pub const FROM_COUNTRY_KEY: &str = "FROM_COUNTRY";
+/// Name of the extra field holding owner of the destination account.
+pub const TO_OWNER_KEY: &str = "TO_OWNER";
+/// Name of the extra field holding country of the destination account.
+pub const TO_COUNTRY_KEY: &str = "TO_COUNTRY";
+
+const METADATA_DELIMITER: char = ',';
+const METADATA_COMMENT_SYMBOL: char = '#';
+const METADATA_HEADER: &str = "ACCOUNT,OWNER,COUNTRY";
+
+/// Data of an account kept outside of records.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccountInfo {
+    /// Name of the account owner.
+    pub owner: String,
+    /// ISO 3166-1 alpha-2 code of the account country, e.g. `DE`.
+    pub country: String,
+}
+
+impl AccountInfo {
+    /// Checks owner is a non-empty single line and country is two uppercase letters.
+    pub fn new(owner: &str, country: &str) -> Result<Self, ParserError> {
+        if owner.is_empty() || owner.trim() != owner || owner.contains(['\n', '\r']) {
+            return Err(ParserError::UnparsableValue(format!(
+                "invalid account owner `{}`",
+                owner
+            )));
+        }
+        if country.len() != 2 || !country.bytes().all(|b| b.is_ascii_uppercase()) {
+            return Err(ParserError::UnparsableValue(format!(
+                "invalid country `{}`",
+                country
+            )));
+        }
+        Ok(Self {
+            owner: owner.to_string(),
+            country: country.to_string(),
+        })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            ("owner".into(), JsonValue::String(self.owner.clone())),
+            ("country".into(), JsonValue::String(self.country.clone())),
+        ])
+    }
+}
+
+/// Owners and countries of accounts, loaded from a secondary file to enrich records with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountMetadata {
+    accounts: HashMap<AccountType, AccountInfo>,
+}
+
+impl AccountMetadata {
+    /// Adds data of `account`, which must not be in the metadata yet.
+    pub fn insert(&mut self, account: AccountType, info: AccountInfo) -> Result<(), ParserError> {
+        if self.accounts.contains_key(&account) {
+            return Err(ParserError::UnparsableValue(format!(
+                "duplicate account {}",
+                account
+            )));
+        }
+        self.accounts.insert(account, info);
+        Ok(())
+    }
+
+    /// Data of `account`, `None` for unknown one.
+    pub fn get(&self, account: &AccountType) -> Option<&AccountInfo> {
+        self.accounts.get(account)
+    }
+
+    /// Number of accounts.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Returns true if no account is known.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Loads metadata from CSV with `account,owner,country` lines, e.g. `4021,Acme GmbH,DE`.
+    ///
+    /// Empty lines, `#` comments and leading `ACCOUNT,OWNER,COUNTRY` header are skipped.
+    /// Account is the value before the first comma and country the one after the last, so
+    /// owners may have commas.
+    pub fn read_csv<R: Read>(r: R) -> Result<Self, AppError> {
+        let mut metadata = Self::default();
+        for (line_num, line_res) in BufReader::new(r).lines().enumerate() {
+            let input_line = line_res.map_err(AppError::ReadError)?;
+            let line = input_line.trim();
+            if line.is_empty()
+                || line.starts_with(METADATA_COMMENT_SYMBOL)
+                || (metadata.is_empty() && line.eq_ignore_ascii_case(METADATA_HEADER))
+            {
+                continue;
+            }
+            metadata
+                .parse_line(line)
+                .add_parser_ctx(ParserContext::with_line_number_and_line(
+                    line_num + 1,
+                    input_line.clone(),
+                ))?;
+        }
+        Ok(metadata)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), ParserError> {
+        let (account, rest) = line
+            .split_once(METADATA_DELIMITER)
+            .ok_or(ParserError::NoFieldDelimiter)?;
+        let (owner, country) = rest
+            .rsplit_once(METADATA_DELIMITER)
+            .ok_or(ParserError::NoFieldDelimiter)?;
+        self.insert(
+            account.trim().parse()?,
+            AccountInfo::new(owner.trim(), country.trim())?,
+        )
+    }
+
+    /// Loads metadata from JSON object of accounts, e.g.
+    /// `{"4021": {"owner": "Acme GmbH", "country": "DE"}}`.
+    pub fn read_json<R: Read>(mut r: R) -> Result<Self, AppError> {
+        let mut input = String::new();
+        r.read_to_string(&mut input).map_err(AppError::ReadError)?;
+        Self::parse_json(&input).add_parser_ctx(ParserContext::with_position(0))
+    }
+
+    fn parse_json(input: &str) -> Result<Self, ParserError> {
+        let JsonValue::Object(fields) = JsonValue::parse(input)? else {
+            return Err(ParserError::UnparsableValue(
+                "account metadata is not json object".into(),
+            ));
+        };
+        let mut metadata = Self::default();
+        for (account, value) in &fields {
+            let field = |key: &str| {
+                value.get(key).and_then(JsonValue::as_text).ok_or_else(|| {
+                    ParserError::UnparsableValue(format!("{} of account {}", key, account))
+                })
+            };
+            let info = AccountInfo::new(field("owner")?, field("country")?)?;
+            metadata.insert(account.parse()?, info)?;
+        }
+        Ok(metadata)
+    }
+}
+
+/// Record along with data of its accounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnrichedRecord {
+    /// The record.
+    pub tx: TxRecord,
+    /// Data of the source account, `None` if unknown or funds come from outside.
+    pub from: Option<AccountInfo>,
+    /// Data of the destination account, `None` if unknown or funds go outside.
+    pub to: Option<AccountInfo>,
+}
+
+impl EnrichedRecord {
+    /// The record with account data in extra fields [`FROM_OWNER_KEY`],
+    /// [`FROM_COUNTRY_KEY`], [`TO_OWNER_KEY`] and [`TO_COUNTRY_KEY`], written as columns or
+    /// keys by CSV and text codecs and as labeled lines by report one; fields of unknown
+    /// accounts are left out.
+    pub fn into_record(self) -> TxRecord {
+        let mut tx = self.tx;
+        for (info, owner_key, country_key) in [
+            (self.from, FROM_OWNER_KEY, FROM_COUNTRY_KEY),
+            (self.to, TO_OWNER_KEY, TO_COUNTRY_KEY),
+        ] {
+            if let Some(info) = info {
+                tx.extras.insert(owner_key.to_string(), info.owner);
+                tx.extras.insert(country_key.to_string(), info.country);
+            }
+        }
+        tx
+    }
+
+    /// Record as compact JSON object, amounts and rates are decimal numbers, timestamp is
+    /// RFC 3339 string and absent fields are `null`; account data is in `from_account` and
+    /// `to_account` objects.
+    pub fn to_json(&self) -> String {
+        let tx = &self.tx;
+        let text = |value: &Option<String>| {
+            value
+                .as_ref()
+                .map_or(JsonValue::Null, |value| JsonValue::String(value.clone()))
+        };
+        let info = |info: &Option<AccountInfo>| {
+            info.as_ref().map_or(JsonValue::Null, AccountInfo::to_json)
+        };
+        JsonValue::Object(vec![
+            ("tx_id".into(), id_json(&tx.id)),
+            ("kind".into(), JsonValue::String(tx.kind.to_string())),
+            ("from".into(), account_json(&tx.from)),
+            ("to".into(), account_json(&tx.to)),
+            ("amount".into(), number(tx.amount)),
+            ("fee".into(), tx.fee.map_or(JsonValue::Null, number)),
+            (
+                "original_amount".into(),
+                tx.original_amount.map_or(JsonValue::Null, number),
+            ),
+            (
+                "exchange_rate".into(),
+                tx.exchange_rate.map_or(JsonValue::Null, number),
+            ),
+            ("timestamp".into(), JsonValue::String(tx.ts.to_rfc3339())),
+            ("status".into(), JsonValue::String(tx.status.to_string())),
+            ("description".into(), text(&tx.description)),
+            ("tenant".into(), text(&tx.tenant)),
+            ("reference".into(), text(&tx.reference)),
+            (
+                "extras".into(),
+                JsonValue::Object(
+                    tx.extras
+                        .iter()
+                        .map(|(key, value)| (key.clone(), JsonValue::String(value.clone())))
+                        .collect(),
+                ),
+            ),
+            ("from_account".into(), info(&self.from)),
+            ("to_account".into(), info(&self.to)),
+        ])
+        .to_string()
+    }
+}
+
+fn number<T: Display>(value: T) -> JsonValue {
+    JsonValue::Number(value.to_string())
+}
+
+// UUIDs are not numbers
+fn id_json(id: &TxIdType) -> JsonValue {
+    match id {
+        TxIdType::Numeric(id) => number(id),
+        TxIdType::Uuid(_) => JsonValue::String(id.to_string()),
+    }
+}
+
+fn account_json(account: &AccountType) -> JsonValue {
+    match account {
+        AccountType::Numeric(id) => number(id),
+        AccountType::Iban(iban) => JsonValue::String(iban.clone()),
+    }
+}
+
+/// Enriches records with data of their accounts, records keep input order.
+///
+/// Only accounts funds actually move from and to are looked up, see [`TxRecord::source`]
+/// and [`TxRecord::destination`], so placeholder account `0` of deposits and withdrawals is
+/// never joined.
+pub fn join(data: &[TxRecord], metadata: &AccountMetadata) -> Vec<EnrichedRecord> {
+    data.iter()
+        .map(|tx| EnrichedRecord {
+            tx: tx.clone(),
+            from: tx
+                .source()
+                .and_then(|account| metadata.get(account))
+                .cloned(),
+            to: tx
+                .destination()
+                .and_then(|account| metadata.get(account))
+                .cloned(),
+        })
+        .collect()
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::domain::key::RecordKey;
+use crate::domain::tx::{TxIdType, TxRecord};
+
+/// Resolution of records sharing an id but differing in other fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep the record met first, sources are read in order.
+    #[default]
+    KeepFirst,
+    /// Keep the record of the latest timestamp, the first of them on ties.
+    KeepLatest,
+    /// Fail on the first conflict.
+    Error,
+}
+
+/// Place of a record: index of its source and position in it, both starting from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Origin {
+    /// Index of the source.
+    pub source: usize,
+    /// Position in the source.
+    pub position: usize,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "source #{} record #{}", self.source, self.position)
+    }
+}
+
+/// Record left out in favor of a differing one of the same id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The shared id.
+    pub id: TxIdType,
+    /// Record kept in the result, as it is at the end of the merge.
+    pub kept: Origin,
+    /// Record left out.
+    pub dropped: Origin,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TX_ID {}: {} kept, {} dropped",
+            self.id, self.kept, self.dropped
+        )
+    }
+}
+
+/// Two records share an id under [`ConflictPolicy::Error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeError {
+    /// The shared id.
+    pub id: TxIdType,
+    /// Record met first.
+    pub first: Origin,
+    /// Record conflicting with it.
+    pub second: Origin,
+}
+
+impl std::error::Error for MergeError {}
+
+impl Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TX_ID {} of {} conflicts with {}",
+            self.id, self.second, self.first
+        )
+    }
+}
+
+/// Result of [`merge`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Merged {
+    /// One record of every id, ordered by the first occurence of their ids.
+    pub records: Vec<TxRecord>,
+    /// Origin of every record of `records`.
+    pub origins: Vec<Origin>,
+    /// Number of records read from every source.
+    pub read: Vec<usize>,
+    /// Number of records equal in all fields to the record kept for their id.
+    pub duplicates: usize,
+    /// Records left out for differing ones of the same id, ordered as they were met.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merged {
+    /// Number of records kept from every source.
+    pub fn kept(&self) -> Vec<usize> {
+        let mut kept = vec![0; self.read.len()];
+        for origin in &self.origins {
+            kept[origin.source] += 1;
+        }
+        kept
+    }
+}
+
+/// Combines record sets into one holding a single record of every id.
+///
+/// Sources are read in order. Records equal in all fields to the record kept for their id are
+/// dropped as duplicates, records of the same id with other fields are resolved by `policy`.
+pub fn merge(sources: &[&[TxRecord]], policy: ConflictPolicy) -> Result<Merged, MergeError> {
+    let mut merged = Merged {
+        read: sources.iter().map(|source| source.len()).collect(),
+        ..Default::default()
+    };
+    // slot of every id in the result
+    let mut slots: HashMap<TxIdType, usize> = HashMap::new();
+    let mut origins: Vec<Origin> = Vec::new();
+    let mut conflicts: Vec<(usize, Origin)> = Vec::new();
+    for (source, records) in sources.iter().enumerate() {
+        for (position, tx) in records.iter().enumerate() {
+            let origin = Origin { source, position };
+            let Some(&slot) = slots.get(&tx.id) else {
+                slots.insert(tx.id, merged.records.len());
+                merged.records.push(tx.clone());
+                origins.push(origin);
+                continue;
+            };
+            let kept = &mut merged.records[slot];
+            if RecordKey::FullRecord.identity(kept) == RecordKey::FullRecord.identity(tx) {
+                merged.duplicates += 1;
+                continue;
+            }
+            match policy {
+                ConflictPolicy::Error => {
+                    return Err(MergeError {
+                        id: tx.id,
+                        first: origins[slot],
+                        second: origin,
+                    });
+                }
+                ConflictPolicy::KeepLatest if tx.ts > kept.ts => {
+                    conflicts.push((slot, origins[slot]));
+                    *kept = tx.clone();
+                    origins[slot] = origin;
+                }
+                _ => conflicts.push((slot, origin)),
+            }
+        }
+    }
+    merged.conflicts = conflicts
+        .into_iter()
+        .map(|(slot, dropped)| Conflict {
+            id: merged.records[slot].id,
+            kept: origins[slot],
+            dropped,
+        })
+        .collect();
+    merged.origins = origins;
+    Ok(merged)
+}
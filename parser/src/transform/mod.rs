@@ -0,0 +1,24 @@
+/// Removal of duplicate records.
+pub mod dedup;
+/// Enrichment of records with external account metadata.
+pub mod join;
+/// Combination of record sets with resolution of conflicting ids.
+pub mod merge;
+/// Normalization of cosmetic differences between sources.
+pub mod normalize;
+/// Correction of records by amendments with audit trail of changes.
+pub mod patch;
+/// Single-pass chains of filters, maps, deduplication and sorting over record streams.
+pub mod pipeline;
+/// Field-level redaction of records driven by declarative policy.
+pub mod redact;
+/// Sampling of record streams: first, every n-th or random records.
+pub mod sample;
+/// Multi-key stable sorting, in memory or by external merge.
+pub mod sort;
+/// Partitioning of records by a dimension.
+pub mod split;
+/// Running balances of account statements.
+pub mod statement;
+/// Multi-tenant filtering and grouping.
+pub mod tenant;
@@ -0,0 +1,122 @@
+use crate::domain::tx::*;
+
+/// Handling of whitespace in descriptions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WhitespaceHandling {
+    /// Description is kept as is.
+    Keep,
+    /// Leading and trailing whitespace is removed.
+    Trim,
+    /// Whitespace is trimmed and inner runs of it, line breaks included, become single space.
+    #[default]
+    Collapse,
+}
+
+impl WhitespaceHandling {
+    /// Applies handling to `value`.
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            WhitespaceHandling::Keep => value.to_string(),
+            WhitespaceHandling::Trim => value.trim().to_string(),
+            WhitespaceHandling::Collapse => value.split_whitespace().collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+/// Precision timestamps are truncated to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampPrecision {
+    /// Milliseconds, precision timestamps are held with.
+    #[default]
+    Millis,
+    /// Whole seconds, for sources without sub-second part.
+    Seconds,
+    /// Whole minutes.
+    Minutes,
+    /// Whole UTC days.
+    Days,
+}
+
+impl TimestampPrecision {
+    fn millis(&self) -> u64 {
+        match self {
+            TimestampPrecision::Millis => 1,
+            TimestampPrecision::Seconds => 1000,
+            TimestampPrecision::Minutes => 60 * 1000,
+            TimestampPrecision::Days => 24 * 60 * 60 * 1000,
+        }
+    }
+
+    /// Timestamp truncated to the precision.
+    pub fn truncate(&self, ts: TxTimestamp) -> TxTimestamp {
+        let step = self.millis();
+        TxTimestamp::from_millis(ts.millis() / step * step)
+    }
+}
+
+/// Normalization of cosmetic differences between sources, so records of the same
+/// transaction compare equal whichever source they are read from.
+///
+/// Default normalizer collapses description whitespace, keeps millisecond timestamps and
+/// zeroes counterparties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Normalizer {
+    /// Whitespace of descriptions.
+    pub description: WhitespaceHandling,
+    /// Precision of timestamps.
+    pub timestamps: TimestampPrecision,
+    /// Outside side of deposits, withdrawals and fees, see [`TxRecord::source`] and
+    /// [`TxRecord::destination`], becomes account `0`.
+    ///
+    /// Sources fill it differently: with `0`, with the account itself or with a clearing one.
+    pub zero_counterparties: bool,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self {
+            description: WhitespaceHandling::default(),
+            timestamps: TimestampPrecision::default(),
+            zero_counterparties: true,
+        }
+    }
+}
+
+impl Normalizer {
+    /// Normalizer changing nothing, options are set on top of it.
+    pub fn identity() -> Self {
+        Self {
+            description: WhitespaceHandling::Keep,
+            timestamps: TimestampPrecision::Millis,
+            zero_counterparties: false,
+        }
+    }
+
+    /// Returns normalized copy of the record.
+    pub fn normalize(&self, tx: &TxRecord) -> TxRecord {
+        let mut tx = tx.clone();
+        self.normalize_in_place(&mut tx);
+        tx
+    }
+
+    /// Returns normalized copies of all records.
+    pub fn normalize_all(&self, data: &[TxRecord]) -> Vec<TxRecord> {
+        data.iter().map(|tx| self.normalize(tx)).collect()
+    }
+
+    /// Normalizes the record in place.
+    pub fn normalize_in_place(&self, tx: &mut TxRecord) {
+        if let Some(description) = &tx.description {
+            tx.description = Some(self.description.apply(description));
+        }
+        tx.ts = self.timestamps.truncate(tx.ts);
+        if self.zero_counterparties {
+            if tx.source().is_none() {
+                tx.from = AccountType::Numeric(0);
+            }
+            if tx.destination().is_none() {
+                tx.to = AccountType::Numeric(0);
+            }
+        }
+    }
+}
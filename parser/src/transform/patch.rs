@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+
+use crate::codecs::base::TxFieldKey;
+use crate::codecs::errors::{ParserContext, ParserCtxBehavior, ParserError};
+use crate::codecs::options::{AmountFormat, TimestampFormat};
+use crate::codecs::schema::{FieldType, FieldValue, TxSchema, ValueFormats};
+use crate::codecs::text::unquote_lenient;
+use crate::compare::{RecordDiff, field_diffs};
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+const AMENDMENT_KV_DELIMITER: char = ':';
+const AMENDMENT_COMMENT_SYMBOL: char = '#';
+
+/// Correction of the records of one id: new values of some of their fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Amendment {
+    /// Id of the records corrected.
+    pub id: TxIdType,
+    changes: Vec<(TxFieldKey, Option<FieldValue>)>,
+}
+
+impl Amendment {
+    /// Amendment of records of `id` changing nothing yet.
+    pub fn new(id: TxIdType) -> Self {
+        Self {
+            id,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Adds new value of the field, `None` clears an optional one.
+    ///
+    /// Fails for `TX_ID`, which amendments are keyed on, for required fields being cleared,
+    /// values of other field types and fields already changed.
+    pub fn with_change(
+        mut self,
+        field_key: TxFieldKey,
+        value: Option<FieldValue>,
+    ) -> Result<Self, ParserError> {
+        let spec = TxSchema::STANDARD
+            .field(field_key)
+            .filter(|spec| TxFieldKey::Id != spec.key)
+            .ok_or_else(|| {
+                ParserError::UnparsableValue(format!("{} can't be amended", field_key))
+            })?;
+        match &value {
+            None if spec.required => return Err(ParserError::MissingField(field_key)),
+            Some(value) if !spec.field_type.accepts(value) => {
+                return Err(ParserError::UnparsableValue(format!(
+                    "{:?} of {}",
+                    value, field_key
+                )));
+            }
+            _ => {}
+        }
+        if self.changes.iter().any(|(key, _)| *key == field_key) {
+            return Err(ParserError::Duplicate(field_key));
+        }
+        self.changes.push((field_key, value));
+        Ok(self)
+    }
+
+    /// Changed fields with their new values, in order they were added.
+    pub fn changes(&self) -> &[(TxFieldKey, Option<FieldValue>)] {
+        &self.changes
+    }
+
+    /// Reads amendments from blocks of `KEY: VALUE` lines separated by empty lines, e.g.
+    /// ```text
+    /// # corrections of 2024-03-05
+    /// TX_ID: 1001
+    /// AMOUNT: 150.00
+    /// STATUS: SUCCESS
+    ///
+    /// TX_ID: 1002
+    /// DESCRIPTION: "refund of #1001"
+    /// FEE:
+    /// ```
+    /// Every block has `TX_ID` and fields to change; empty value clears an optional field.
+    /// Amounts are decimals, timestamps epoch millis or RFC 3339, text may be quoted as in
+    /// text format.
+    pub fn read<R: Read>(r: R) -> Result<Vec<Amendment>, AppError> {
+        let mut result = Vec::new();
+        let mut block: Vec<(usize, String)> = Vec::new();
+        for (line_num, line_res) in BufReader::new(r).lines().enumerate() {
+            let input_line = line_res.map_err(AppError::ReadError)?;
+            let line = input_line.trim();
+            if line.starts_with(AMENDMENT_COMMENT_SYMBOL) {
+                continue;
+            }
+            if line.is_empty() {
+                if !block.is_empty() {
+                    result.push(Self::parse_block(&std::mem::take(&mut block))?);
+                }
+                continue;
+            }
+            block.push((line_num + 1, input_line));
+        }
+        if !block.is_empty() {
+            result.push(Self::parse_block(&block)?);
+        }
+        Ok(result)
+    }
+
+    // id may come on any line of the block, fields are checked once it is known
+    fn parse_block(block: &[(usize, String)]) -> Result<Amendment, AppError> {
+        let context = |(line_num, line): &(usize, String)| {
+            ParserContext::with_line_number_and_line(*line_num, line.clone())
+        };
+        let mut fields = Vec::with_capacity(block.len());
+        for entry in block {
+            fields.push(Self::parse_line(&entry.1).add_parser_ctx(context(entry))?);
+        }
+        let mut ids = fields
+            .iter()
+            .zip(block)
+            .filter(|((key, _), _)| TxFieldKey::Id == *key);
+        let Some(((_, id), entry)) = ids.next() else {
+            return Err(ParserError::MissingField(TxFieldKey::Id))
+                .add_parser_ctx(context(&block[0]));
+        };
+        let id = id
+            .as_deref()
+            .ok_or(ParserError::MissingField(TxFieldKey::Id))
+            .and_then(str::parse)
+            .add_parser_ctx(context(entry))?;
+        if let Some((_, entry)) = ids.next() {
+            return Err(ParserError::Duplicate(TxFieldKey::Id)).add_parser_ctx(context(entry));
+        }
+
+        let mut amendment = Amendment::new(id);
+        for ((key, value), entry) in fields.into_iter().zip(block) {
+            if TxFieldKey::Id == key {
+                continue;
+            }
+            amendment = Self::parse_change(amendment, key, value.as_deref())
+                .add_parser_ctx(context(entry))?;
+        }
+        Ok(amendment)
+    }
+
+    fn parse_line(line: &str) -> Result<(TxFieldKey, Option<String>), ParserError> {
+        let (key, value) = line
+            .split_once(AMENDMENT_KV_DELIMITER)
+            .ok_or(ParserError::NoFieldDelimiter)?;
+        let value = value.trim();
+        Ok((
+            key.trim().parse()?,
+            Some(value.to_string()).filter(|value| !value.is_empty()),
+        ))
+    }
+
+    fn parse_change(self, field_key: TxFieldKey, value: Option<&str>) -> Result<Self, ParserError> {
+        let formats = ValueFormats {
+            amounts: AmountFormat::Exact,
+            timestamps: TimestampFormat::Rfc3339,
+        };
+        let value = match (value, TxSchema::STANDARD.field(field_key)) {
+            (Some(value), Some(spec)) if FieldType::Text == spec.field_type => {
+                Some(FieldValue::Text(unquote_lenient(value)?))
+            }
+            (Some(value), Some(spec)) => Some(spec.field_type.parse(value, &formats)?),
+            _ => None,
+        };
+        self.with_change(field_key, value)
+    }
+
+    // amounts keep the scale of the record where digits allow, e.g. `150` stays in cents
+    fn apply(&self, tx: &mut TxRecord) {
+        let scale = tx.amount.scale;
+        let scaled = |amount: Money| amount.rescale(scale).unwrap_or(amount);
+        for (field_key, value) in &self.changes {
+            match (field_key, value.clone()) {
+                (TxFieldKey::TxKind, Some(FieldValue::Kind(kind))) => tx.kind = kind,
+                (TxFieldKey::FromUserId, Some(FieldValue::Account(account))) => tx.from = account,
+                (TxFieldKey::ToUserId, Some(FieldValue::Account(account))) => tx.to = account,
+                (TxFieldKey::Amount, Some(FieldValue::Amount(amount))) => {
+                    tx.amount = scaled(amount)
+                }
+                (TxFieldKey::Timestamp, Some(FieldValue::Timestamp(ts))) => tx.ts = ts,
+                (TxFieldKey::Status, Some(FieldValue::Status(status))) => tx.status = status,
+                (TxFieldKey::Description, value) => tx.description = text(value),
+                (TxFieldKey::Tenant, value) => tx.tenant = text(value),
+                (TxFieldKey::Reference, value) => tx.reference = text(value),
+                (TxFieldKey::Fee, value) => tx.fee = amount(value).map(scaled),
+                (TxFieldKey::OriginalAmount, value) => tx.original_amount = amount(value),
+                (TxFieldKey::ExchangeRate, Some(FieldValue::Rate(rate))) => {
+                    tx.exchange_rate = Some(rate)
+                }
+                (TxFieldKey::ExchangeRate, None) => tx.exchange_rate = None,
+                // other pairs are rejected by `with_change`
+                _ => {}
+            }
+        }
+    }
+}
+
+fn text(value: Option<FieldValue>) -> Option<String> {
+    match value {
+        Some(FieldValue::Text(text)) => Some(text),
+        _ => None,
+    }
+}
+
+fn amount(value: Option<FieldValue>) -> Option<Money> {
+    match value {
+        Some(FieldValue::Amount(amount)) => Some(amount),
+        _ => None,
+    }
+}
+
+/// Result of [`apply_patch`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patched {
+    /// Base records in their order, amended ones corrected.
+    pub records: Vec<TxRecord>,
+    /// Audit trail: fields changed in every amended record with old and new values, in
+    /// order amendments were applied.
+    pub applied: Vec<RecordDiff>,
+    /// Number of amended records whose fields already had the new values.
+    pub unchanged: usize,
+    /// Ids of amendments no base record has.
+    pub unmatched: Vec<TxIdType>,
+}
+
+/// Corrects base records with amendments applied in order, every amendment to all records of
+/// its id, so later amendments of an id override earlier ones.
+///
+/// Amended fields are compared as [`diff`](crate::compare::diff) does, amounts by value.
+pub fn apply_patch(base: &[TxRecord], amendments: &[Amendment]) -> Patched {
+    let mut positions: HashMap<TxIdType, Vec<usize>> = HashMap::new();
+    for (i, tx) in base.iter().enumerate() {
+        positions.entry(tx.id).or_default().push(i);
+    }
+    let mut patched = Patched {
+        records: base.to_vec(),
+        ..Default::default()
+    };
+    for amendment in amendments {
+        let Some(matched) = positions.get(&amendment.id) else {
+            patched.unmatched.push(amendment.id);
+            continue;
+        };
+        for &i in matched {
+            let before = patched.records[i].clone();
+            amendment.apply(&mut patched.records[i]);
+            let fields = field_diffs(&before, &patched.records[i]);
+            if fields.is_empty() {
+                patched.unchanged += 1;
+            } else {
+                patched.applied.push(RecordDiff {
+                    id: amendment.id,
+                    fields,
+                });
+            }
+        }
+    }
+    patched
+}
@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::codecs::traits::RecordSink;
+use crate::domain::key::RecordKey;
+use crate::domain::tx::TxRecord;
+use crate::errors::AppError;
+use crate::filter::Filter;
+use crate::transform::sample::{Sampling, sample};
+use crate::transform::sort::{ExternalSorter, SortOrder};
+
+/// Records flowing out of a [`Pipeline`] stage.
+pub type Records<'a> = Box<dyn Iterator<Item = Result<TxRecord, AppError>> + 'a>;
+
+type Predicate = Arc<dyn Fn(&TxRecord) -> bool + Send + Sync>;
+type Mapping = Arc<dyn Fn(TxRecord) -> TxRecord + Send + Sync>;
+
+#[derive(Clone)]
+enum Stage {
+    Filter(Predicate),
+    Map(Mapping),
+    Dedup(RecordKey),
+    Sort(ExternalSorter),
+    Sample(Sampling),
+}
+
+impl Debug for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::Filter(_) => write!(f, "Filter(..)"),
+            Stage::Map(_) => write!(f, "Map(..)"),
+            Stage::Dedup(key) => f.debug_tuple("Dedup").field(key).finish(),
+            Stage::Sort(sorter) => f.debug_tuple("Sort").field(sorter).finish(),
+            Stage::Sample(sampling) => f.debug_tuple("Sample").field(sampling).finish(),
+        }
+    }
+}
+
+impl Stage {
+    fn chain<'a>(&'a self, records: Records<'a>) -> Records<'a> {
+        match self {
+            Stage::Filter(predicate) => Box::new(records.filter(move |tx| match tx {
+                Ok(tx) => predicate(tx),
+                Err(_) => true,
+            })),
+            Stage::Map(mapping) => Box::new(records.map(move |tx| tx.map(|tx| mapping(tx)))),
+            Stage::Dedup(key) => {
+                let mut seen = HashSet::new();
+                Box::new(records.filter(move |tx| match tx {
+                    Ok(tx) => seen.insert(key.identity(tx)),
+                    Err(_) => true,
+                }))
+            }
+            // records are pulled in and sorted once the first sorted one is asked for
+            Stage::Sort(sorter) => {
+                Box::new(std::iter::once_with(move || sorter.sort(records)).flat_map(
+                    |sorted| -> Records<'a> {
+                        match sorted {
+                            Ok(sorted) => Box::new(sorted),
+                            Err(e) => Box::new(std::iter::once(Err(e))),
+                        }
+                    },
+                ))
+            }
+            // input is not read past the first `n` records
+            Stage::Sample(Sampling::First(n)) => {
+                let (n, mut records, mut taken) = (*n, records, 0);
+                Box::new(std::iter::from_fn(move || {
+                    if taken >= n {
+                        return None;
+                    }
+                    let tx = records.next()?;
+                    taken += usize::from(tx.is_ok());
+                    Some(tx)
+                }))
+            }
+            Stage::Sample(Sampling::EveryNth(n)) => {
+                let (n, mut seen) = (*n, 0usize);
+                Box::new(records.filter(move |tx| match tx {
+                    Ok(_) => {
+                        let position = seen;
+                        seen += 1;
+                        n > 0 && position.is_multiple_of(n)
+                    }
+                    Err(_) => true,
+                }))
+            }
+            // like sorting, the reservoir is known once all records came in
+            Stage::Sample(sampling) => Box::new(
+                std::iter::once_with(move || sample(records, *sampling)).flat_map(
+                    |sampled| -> Records<'a> {
+                        match sampled {
+                            Ok(sampled) => Box::new(sampled.into_iter().map(Ok)),
+                            Err(e) => Box::new(std::iter::once(Err(e))),
+                        }
+                    },
+                ),
+            ),
+        }
+    }
+}
+
+/// Chain of transformations applied to a record stream in one pass.
+///
+/// Stages run in order they were added. Filters, maps and deduplication handle one record at
+/// a time, deduplication keeps identities of records seen; sorting needs all records coming to
+/// it, held in memory by [`Pipeline::sort`] or spilled to disk by [`Pipeline::sort_external`].
+/// Errors of the input pass through all stages as they are, sort and reservoir sample stages
+/// end their output with the first one.
+/// ```no_run
+/// # use parser::codecs::base::Codec;
+/// # use parser::codecs::options::{ParseOptions, WriteOptions};
+/// # use parser::domain::key::RecordKey;
+/// # use parser::transform::pipeline::Pipeline;
+/// # fn main() -> Result<(), parser::errors::AppError> {
+/// let pipeline = Pipeline::new()
+///     .filter_expr("status == SUCCESS".parse()?)
+///     .dedup(RecordKey::IdOnly)
+///     .sort("TIMESTAMP".parse().unwrap());
+/// let records = Codec::CsvCodec.parse_stream(std::io::stdin(), &ParseOptions::default());
+/// let sink = Codec::TextCodec.open_sink(std::io::stdout(), &WriteOptions::default())?;
+/// pipeline.run_into(records, sink)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    /// Pipeline passing records through unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps records the predicate holds for.
+    pub fn filter<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&TxRecord) -> bool + Send + Sync + 'static,
+    {
+        self.stages.push(Stage::Filter(Arc::new(predicate)));
+        self
+    }
+
+    /// Keeps records satisfying the filter expression.
+    pub fn filter_expr(self, filter: Filter) -> Self {
+        self.filter(move |tx| filter.matches(tx))
+    }
+
+    /// Replaces every record with its mapping, e.g. redacted or normalized copy.
+    pub fn map<F>(mut self, mapping: F) -> Self
+    where
+        F: Fn(TxRecord) -> TxRecord + Send + Sync + 'static,
+    {
+        self.stages.push(Stage::Map(Arc::new(mapping)));
+        self
+    }
+
+    /// Drops records equal to an earlier one under `key`, see [`dedup`](super::dedup::dedup).
+    pub fn dedup(mut self, key: RecordKey) -> Self {
+        self.stages.push(Stage::Dedup(key));
+        self
+    }
+
+    /// Sorts records in memory.
+    pub fn sort(self, order: SortOrder) -> Self {
+        self.sort_external(ExternalSorter::new(order, usize::MAX))
+    }
+
+    /// Sorts records by external merge, holding at most a run of them in memory.
+    pub fn sort_external(mut self, sorter: ExternalSorter) -> Self {
+        self.stages.push(Stage::Sort(sorter));
+        self
+    }
+
+    /// Keeps a sample of records in input order, see [`Sampler`](super::sample::Sampler).
+    ///
+    /// First `n` and every `n`-th records are passed on one at a time, a reservoir is held
+    /// until all records came in.
+    pub fn sample(mut self, sampling: Sampling) -> Self {
+        self.stages.push(Stage::Sample(sampling));
+        self
+    }
+
+    /// Number of stages.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// True for pipeline without stages.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Returns lazy stream of transformed records, e.g. of [`Codec::parse_stream`].
+    ///
+    /// [`Codec::parse_stream`]: crate::codecs::base::Codec::parse_stream
+    pub fn run<'a, I>(&'a self, records: I) -> Records<'a>
+    where
+        I: IntoIterator<Item = Result<TxRecord, AppError>>,
+        I::IntoIter: 'a,
+    {
+        self.stages
+            .iter()
+            .fold(Box::new(records.into_iter()), |records, stage| {
+                stage.chain(records)
+            })
+    }
+
+    /// Pushes transformed records to the sink and finishes it, fails on the first error.
+    ///
+    /// Returns number of records pushed.
+    pub fn run_into<I>(&self, records: I, mut sink: RecordSink<'_>) -> Result<usize, AppError>
+    where
+        I: IntoIterator<Item = Result<TxRecord, AppError>>,
+    {
+        let mut pushed = 0;
+        for tx in self.run(records) {
+            sink.push(&tx?)?;
+            pushed += 1;
+        }
+        sink.finish()?;
+        Ok(pushed)
+    }
+}
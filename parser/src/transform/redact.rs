@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::str::FromStr;
+
+use hmac::Mac;
+
+use crate::codecs::base::TxFieldKey;
+use crate::codecs::errors::{ParserContext, ParserCtxBehavior, ParserError};
+use crate::codecs::utils::unquote;
+use crate::digest::{hmac_sha256, to_hex};
+use crate::domain::tx::*;
+use crate::errors::AppError;
+
+const POLICY_KV_DELIMITER: char = ':';
+const POLICY_COMMENT_SYMBOL: char = '#';
+const SALT_KEY: &str = "SALT";
+const PRESERVE_ACCOUNT_FORMAT_KEY: &str = "PRESERVE_ACCOUNT_FORMAT";
+const MASK_SYMBOL: char = '*';
+const MASK_KEEP_DIGITS_MODULO: u64 = 10_000;
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+const FEISTEL_ROUNDS: u32 = 8;
+
+/// Action applied to a single record field during redaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldAction {
+    /// Field is copied as is.
+    Keep,
+    /// Field is replaced with a deterministic hash keyed by the salt (pseudonym), so the same
+    /// value gets the same pseudonym in every record and records can still be joined on it.
+    Hash,
+    /// Field is partially hidden (last 4 digits kept, chars replaced by `*`, timestamps cut to day).
+    Mask,
+    /// Field is replaced with its empty/zero value.
+    Drop,
+}
+
+impl FromStr for FieldAction {
+    type Err = ParserError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "KEEP" => Ok(FieldAction::Keep),
+            "HASH" => Ok(FieldAction::Hash),
+            "MASK" => Ok(FieldAction::Mask),
+            "DROP" => Ok(FieldAction::Drop),
+            _ => Err(ParserError::UnparsableValue(s.into())),
+        }
+    }
+}
+
+/// Declarative redaction policy: which fields to hash, mask or drop.
+///
+/// Policy can be loaded from a `KEY: VALUE` config, e.g.
+/// ```text
+/// # policy for partner X
+/// SALT: "partner-x-2024"
+/// PRESERVE_ACCOUNT_FORMAT: true
+/// FROM_USER_ID: HASH
+/// TO_USER_ID: HASH
+/// DESCRIPTION: MASK
+/// ```
+/// Fields without a rule are kept as is.
+#[derive(Debug, Clone, Default)]
+pub struct RedactionPolicy {
+    /// Key of HMAC-SHA256 every pseudonym is derived with, shall be kept private to make
+    /// pseudonyms irreversible.
+    pub salt: String,
+    /// Hashed account ids keep the same number of decimal digits as originals; distinct
+    /// accounts still get distinct pseudonyms.
+    pub preserve_account_format: bool,
+    rules: HashMap<TxFieldKey, FieldAction>,
+}
+
+impl RedactionPolicy {
+    /// Creates empty (keep everything) policy with provided salt.
+    pub fn new(salt: &str) -> Self {
+        Self {
+            salt: salt.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds rule for the field, fails if action is not applicable to the field type or if it
+    /// is [`FieldAction::Hash`] and salt is empty.
+    pub fn with_rule(
+        mut self,
+        field_key: TxFieldKey,
+        action: FieldAction,
+    ) -> Result<Self, ParserError> {
+        self.set_rule(field_key, action)?;
+        self.check_salt(field_key, action)?;
+        Ok(self)
+    }
+
+    /// Returns action configured for the field.
+    pub fn action(&self, field_key: TxFieldKey) -> FieldAction {
+        self.rules
+            .get(&field_key)
+            .copied()
+            .unwrap_or(FieldAction::Keep)
+    }
+
+    /// Loads policy from `KEY: VALUE` config stream.
+    ///
+    /// Policy with `HASH` rules shall have non-empty `SALT`, pseudonyms keyed by an empty one
+    /// are easily reversed.
+    pub fn from_reader<R: Read>(r: R) -> Result<Self, AppError> {
+        let mut policy = Self::default();
+        // the first hashed field with its line, salt may follow it
+        let mut hashed = None;
+        for (line_num, line_res) in BufReader::new(r).lines().enumerate() {
+            let input_line = line_res.map_err(AppError::ReadError)?;
+            let line = input_line.trim();
+            if line.is_empty() || line.starts_with(POLICY_COMMENT_SYMBOL) {
+                continue;
+            }
+            let context =
+                || ParserContext::with_line_number_and_line(line_num + 1, input_line.clone());
+            if let Some(field_key) = policy.parse_line(line).add_parser_ctx(context())? {
+                hashed.get_or_insert_with(|| (field_key, context()));
+            }
+        }
+        if let Some((field_key, context)) = hashed {
+            policy
+                .check_salt(field_key, FieldAction::Hash)
+                .add_parser_ctx(context)?;
+        }
+        Ok(policy)
+    }
+
+    /// Returns redacted copy of the record, extra fields are dropped as nothing is known of them.
+    pub fn redact(&self, tx: &TxRecord) -> TxRecord {
+        TxRecord {
+            id: self.redact_id(tx.id),
+            kind: tx.kind,
+            from: self.redact_account(TxFieldKey::FromUserId, &tx.from),
+            to: self.redact_account(TxFieldKey::ToUserId, &tx.to),
+            amount: match self.action(TxFieldKey::Amount) {
+                FieldAction::Drop => Money::new(0, tx.amount.scale),
+                _ => tx.amount,
+            },
+            fee: match self.action(TxFieldKey::Fee) {
+                FieldAction::Drop => None,
+                _ => tx.fee,
+            },
+            original_amount: match self.action(TxFieldKey::OriginalAmount) {
+                FieldAction::Drop => None,
+                _ => tx.original_amount,
+            },
+            exchange_rate: match self.action(TxFieldKey::ExchangeRate) {
+                FieldAction::Drop => None,
+                _ => tx.exchange_rate,
+            },
+            ts: match self.action(TxFieldKey::Timestamp) {
+                FieldAction::Mask => {
+                    TxTimestamp::from_millis(tx.ts.millis() / MILLIS_PER_DAY * MILLIS_PER_DAY)
+                }
+                FieldAction::Drop => TxTimestamp::from_millis(0),
+                _ => tx.ts,
+            },
+            status: tx.status,
+            description: match self.action(TxFieldKey::Description) {
+                FieldAction::Drop => None,
+                _ => tx
+                    .description
+                    .as_ref()
+                    .map(|description| self.redact_text(TxFieldKey::Description, description)),
+            },
+            tenant: match self.action(TxFieldKey::Tenant) {
+                FieldAction::Drop => None,
+                _ => tx
+                    .tenant
+                    .as_ref()
+                    .map(|tenant| self.redact_text(TxFieldKey::Tenant, tenant)),
+            },
+            reference: match self.action(TxFieldKey::Reference) {
+                FieldAction::Drop => None,
+                _ => tx
+                    .reference
+                    .as_ref()
+                    .map(|reference| self.redact_text(TxFieldKey::Reference, reference)),
+            },
+            extras: Default::default(),
+        }
+    }
+
+    /// Returns redacted copies of all records.
+    pub fn redact_all(&self, data: &[TxRecord]) -> Vec<TxRecord> {
+        data.iter().map(|tx| self.redact(tx)).collect()
+    }
+
+    fn set_rule(&mut self, field_key: TxFieldKey, action: FieldAction) -> Result<(), ParserError> {
+        let applicable = match field_key {
+            TxFieldKey::Id
+            | TxFieldKey::FromUserId
+            | TxFieldKey::ToUserId
+            | TxFieldKey::Description
+            | TxFieldKey::Tenant
+            | TxFieldKey::Reference => true,
+            TxFieldKey::Amount
+            | TxFieldKey::Fee
+            | TxFieldKey::OriginalAmount
+            | TxFieldKey::ExchangeRate => {
+                matches!(action, FieldAction::Keep | FieldAction::Drop)
+            }
+            TxFieldKey::Timestamp => action != FieldAction::Hash,
+            TxFieldKey::TxKind | TxFieldKey::Status => action == FieldAction::Keep,
+        };
+        if !applicable {
+            return Err(ParserError::UnparsableValue(format!(
+                "{:?} is not applicable to {}",
+                action, field_key
+            )));
+        }
+        if self.rules.insert(field_key, action).is_some() {
+            return Err(ParserError::Duplicate(field_key));
+        }
+        Ok(())
+    }
+
+    // pseudonyms keyed by an empty salt are a plain hash anyone can recompute
+    fn check_salt(&self, field_key: TxFieldKey, action: FieldAction) -> Result<(), ParserError> {
+        if FieldAction::Hash == action && self.salt.is_empty() {
+            return Err(ParserError::UnparsableValue(format!(
+                "HASH of {} without SALT",
+                field_key
+            )));
+        }
+        Ok(())
+    }
+
+    // field of `HASH` rule is returned for the salt check once the whole policy is read
+    fn parse_line(&mut self, line: &str) -> Result<Option<TxFieldKey>, ParserError> {
+        let (key, value) = line
+            .split_once(POLICY_KV_DELIMITER)
+            .ok_or(ParserError::NoFieldDelimiter)?;
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            SALT_KEY => self.salt = unquote(value)?.to_string(),
+            PRESERVE_ACCOUNT_FORMAT_KEY => {
+                self.preserve_account_format = value
+                    .parse()
+                    .map_err(|_| ParserError::UnparsableValue(value.into()))?
+            }
+            _ => {
+                let (field_key, action) = (key.parse()?, value.parse()?);
+                self.set_rule(field_key, action)?;
+                return Ok((FieldAction::Hash == action).then_some(field_key));
+            }
+        }
+        Ok(None)
+    }
+
+    fn digest(&self, domain: &str, value: &str) -> [u8; 32] {
+        let mut hasher = hmac_sha256(self.salt.as_bytes());
+        hasher.update(domain.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(value.as_bytes());
+        hasher.finalize().into_bytes().into()
+    }
+
+    // zero is kept as is for all actions except drop: it marks "no counterparty" in deposits/withdrawals,
+    // so non-zero values never become zero, e.g. masked 10000 is 10000 not 0
+    fn redact_number(&self, field_key: TxFieldKey, domain: &str, value: u64) -> u64 {
+        match self.action(field_key) {
+            FieldAction::Keep => value,
+            FieldAction::Drop => 0,
+            _ if 0 == value => 0,
+            FieldAction::Mask => match value % MASK_KEEP_DIGITS_MODULO {
+                0 => MASK_KEEP_DIGITS_MODULO,
+                tail => tail,
+            },
+            FieldAction::Hash if self.preserve_account_format => {
+                self.same_digits_pseudonym(domain, value)
+            }
+            FieldAction::Hash => self.digest_head(domain, &value.to_string()),
+        }
+    }
+
+    fn digest_head(&self, domain: &str, value: &str) -> u64 {
+        let digest = self.digest(domain, value);
+        let mut head = [0u8; 8];
+        head.copy_from_slice(&digest[..8]);
+        u64::from_be_bytes(head)
+    }
+
+    // keyed permutation of numbers with as many decimal digits as the value, so pseudonyms of
+    // distinct values differ: Feistel network over the smallest even number of bits holding
+    // them, outputs out of range are walked through the network again until one fits
+    fn same_digits_pseudonym(&self, domain: &str, value: u64) -> u64 {
+        let digits = value.to_string().len() as u32;
+        let low: u128 = if 1 == digits {
+            1
+        } else {
+            10u128.pow(digits - 1)
+        };
+        let high: u128 = 10u128.pow(digits).min(u64::MAX as u128 + 1);
+        let size = high - low;
+        let bits = (u128::BITS - (size - 1).leading_zeros()).max(2);
+        let half = bits.div_ceil(2);
+        let mask = (1u128 << half) - 1;
+        let mut x = u128::from(value) - low;
+        loop {
+            let (mut left, mut right) = (x >> half, x & mask);
+            for round in 0..FEISTEL_ROUNDS {
+                let f = u128::from(self.digest_head(domain, &format!("{}/{}", round, right)));
+                (left, right) = (right, left ^ (f & mask));
+            }
+            x = (left << half) | right;
+            if x < size {
+                return (low + x) as u64;
+            }
+        }
+    }
+
+    // masked UUID keeps its last 4 hex digits, hashed one is taken from the digest
+    fn redact_id(&self, id: TxIdType) -> TxIdType {
+        let uuid = match id {
+            TxIdType::Numeric(id) => {
+                return TxIdType::Numeric(self.redact_number(TxFieldKey::Id, "id", id));
+            }
+            TxIdType::Uuid(uuid) => uuid,
+        };
+        match self.action(TxFieldKey::Id) {
+            FieldAction::Keep => id,
+            FieldAction::Drop => TxIdType::default(),
+            FieldAction::Mask => TxIdType::Uuid(uuid & 0xffff),
+            FieldAction::Hash => {
+                let digest = self.digest("uuid", &id.to_string());
+                let mut head = [0u8; 16];
+                head.copy_from_slice(&digest[..16]);
+                TxIdType::Uuid(u128::from_be_bytes(head))
+            }
+        }
+    }
+
+    // IBAN keeps its country code: masked one its last 4 characters too, hashed one becomes
+    // valid IBAN of the same length
+    fn redact_account(&self, field_key: TxFieldKey, account: &AccountType) -> AccountType {
+        let iban = match account {
+            AccountType::Numeric(id) => {
+                return AccountType::Numeric(self.redact_number(field_key, "account", *id));
+            }
+            AccountType::Iban(iban) => iban,
+        };
+        let country_len = iban.char_indices().nth(2).map_or(iban.len(), |(i, _)| i);
+        let (country, rest) = iban.split_at(country_len);
+        match self.action(field_key) {
+            FieldAction::Keep => account.clone(),
+            FieldAction::Drop => AccountType::default(),
+            FieldAction::Mask => {
+                let keep_from = rest.len().saturating_sub(4);
+                let masked: String = rest
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| if i < keep_from { MASK_SYMBOL } else { c })
+                    .collect();
+                AccountType::Iban(format!("{}{}", country, masked))
+            }
+            FieldAction::Hash => {
+                let digest = self.digest("iban", iban);
+                let bban: String = (0..rest.len().saturating_sub(2))
+                    .map(|i| char::from(b'0' + digest[i % digest.len()] % 10))
+                    .collect();
+                let check = 98 - iban_mod97(&format!("{}00{}", country, bban));
+                AccountType::Iban(format!("{}{:02}{}", country, check, bban))
+            }
+        }
+    }
+
+    fn redact_text(&self, field_key: TxFieldKey, value: &str) -> String {
+        match self.action(field_key) {
+            FieldAction::Keep => value.into(),
+            FieldAction::Drop => "".into(),
+            FieldAction::Mask => value
+                .chars()
+                .map(|c| if c.is_whitespace() { c } else { MASK_SYMBOL })
+                .collect(),
+            FieldAction::Hash => to_hex(&self.digest("text", value)[..8]),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::domain::tx::TxRecord;
+use crate::errors::AppError;
+
+/// Way records are picked into a sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sampling {
+    /// The first `n` records.
+    First(usize),
+    /// Every `n`-th record starting from the first one, `0` picks nothing.
+    EveryNth(usize),
+    /// `size` records picked uniformly at random, the same `seed` picks the same records of
+    /// the same input.
+    Reservoir {
+        /// Number of records picked.
+        size: usize,
+        /// Seed of the pseudo-random generator.
+        seed: u64,
+    },
+}
+
+// SplitMix64, small and fast, good enough to pick records; not for secrets
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // number in `0..bound`, bias is below 2^-64 * bound
+    fn below(&mut self, bound: u64) -> u64 {
+        ((u128::from(self.next()) * u128::from(bound)) >> 64) as u64
+    }
+}
+
+/// Sample of records pushed, e.g. read from a stream; at most the sample is held.
+#[derive(Debug, Clone)]
+pub struct Sampler {
+    sampling: Sampling,
+    seen: usize,
+    rng: SplitMix64,
+    // records picked with their positions
+    picked: Vec<(usize, TxRecord)>,
+}
+
+impl Sampler {
+    /// Picks records the way of `sampling`.
+    pub fn new(sampling: Sampling) -> Self {
+        let seed = match sampling {
+            Sampling::Reservoir { seed, .. } => seed,
+            _ => 0,
+        };
+        Self {
+            sampling,
+            seen: 0,
+            rng: SplitMix64(seed),
+            picked: Vec::new(),
+        }
+    }
+
+    /// Takes the record into account.
+    pub fn push(&mut self, tx: &TxRecord) {
+        let position = self.seen;
+        self.seen += 1;
+        match self.sampling {
+            Sampling::First(n) if position < n => self.picked.push((position, tx.clone())),
+            Sampling::EveryNth(n) if n > 0 && position.is_multiple_of(n) => {
+                self.picked.push((position, tx.clone()))
+            }
+            Sampling::Reservoir { size, .. } if position < size => {
+                self.picked.push((position, tx.clone()))
+            }
+            // Algorithm R: the record replaces a random one with probability size / seen
+            Sampling::Reservoir { size, .. } => {
+                let slot = self.rng.below(self.seen as u64) as usize;
+                if slot < size {
+                    self.picked[slot] = (position, tx.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// True once no later record can be picked, so reading may stop.
+    pub fn is_done(&self) -> bool {
+        match self.sampling {
+            Sampling::First(n) => self.seen >= n,
+            Sampling::EveryNth(n) => n == 0,
+            Sampling::Reservoir { size, .. } => size == 0,
+        }
+    }
+
+    /// Number of records pushed.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Records picked in order they were pushed in.
+    pub fn into_vec(mut self) -> Vec<TxRecord> {
+        self.picked.sort_by_key(|(position, _)| *position);
+        self.picked.into_iter().map(|(_, tx)| tx).collect()
+    }
+}
+
+/// Sample of records of a stream, e.g. of [`Codec::parse_stream`], in input order.
+///
+/// Only the sample is held in memory and reading stops as soon as no later record can be
+/// picked, e.g. after the first `n` ones. Fails on the first error of the stream.
+///
+/// [`Codec::parse_stream`]: crate::codecs::base::Codec::parse_stream
+pub fn sample<I>(records: I, sampling: Sampling) -> Result<Vec<TxRecord>, AppError>
+where
+    I: IntoIterator<Item = Result<TxRecord, AppError>>,
+{
+    let mut sampler = Sampler::new(sampling);
+    let mut records = records.into_iter();
+    while !sampler.is_done() {
+        let Some(tx) = records.next() else {
+            break;
+        };
+        sampler.push(&tx?);
+    }
+    Ok(sampler.into_vec())
+}
@@ -0,0 +1,349 @@
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+use crate::codecs::base::{Codec, TxFieldKey};
+use crate::codecs::errors::ParserError;
+use crate::codecs::options::{AmountFormat, ParseOptions, WriteOptions};
+use crate::codecs::traits::RecordStream;
+use crate::domain::tx::TxRecord;
+use crate::errors::AppError;
+
+const KEYS_DELIMITER: char = ',';
+const DIRECTION_DELIMITER: char = ':';
+const ASCENDING: &str = "ASC";
+const DESCENDING: &str = "DESC";
+
+// runs of all sorters of the process get distinct file names
+static RUN_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Field records are sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    /// `TIMESTAMP`.
+    Timestamp,
+    /// `TX_ID`.
+    Id,
+    /// `AMOUNT`, compared by value whatever the scale.
+    Amount,
+    /// `FROM_USER_ID`.
+    FromAccount,
+    /// `TO_USER_ID`.
+    ToAccount,
+}
+
+impl SortField {
+    fn compare(&self, a: &TxRecord, b: &TxRecord) -> Ordering {
+        match self {
+            SortField::Timestamp => a.ts.cmp(&b.ts),
+            SortField::Id => a.id.cmp(&b.id),
+            SortField::Amount => a.amount.cmp(&b.amount),
+            SortField::FromAccount => a.from.cmp(&b.from),
+            SortField::ToAccount => a.to.cmp(&b.to),
+        }
+    }
+}
+
+impl TryFrom<TxFieldKey> for SortField {
+    type Error = ParserError;
+
+    fn try_from(field_key: TxFieldKey) -> Result<Self, Self::Error> {
+        match field_key {
+            TxFieldKey::Timestamp => Ok(SortField::Timestamp),
+            TxFieldKey::Id => Ok(SortField::Id),
+            TxFieldKey::Amount => Ok(SortField::Amount),
+            TxFieldKey::FromUserId => Ok(SortField::FromAccount),
+            TxFieldKey::ToUserId => Ok(SortField::ToAccount),
+            other => Err(ParserError::UnparsableKey(other.to_string())),
+        }
+    }
+}
+
+/// Direction of a sort key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortDirection {
+    /// Smallest values first.
+    #[default]
+    Ascending,
+    /// Largest values first.
+    Descending,
+}
+
+/// Field and direction of one sort key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    /// Compared field.
+    pub field: SortField,
+    /// Direction of the field.
+    pub direction: SortDirection,
+}
+
+impl SortKey {
+    /// Ascending key of the field.
+    pub fn asc(field: SortField) -> Self {
+        Self {
+            field,
+            direction: SortDirection::Ascending,
+        }
+    }
+
+    /// Descending key of the field.
+    pub fn desc(field: SortField) -> Self {
+        Self {
+            field,
+            direction: SortDirection::Descending,
+        }
+    }
+
+    fn compare(&self, a: &TxRecord, b: &TxRecord) -> Ordering {
+        match self.direction {
+            SortDirection::Ascending => self.field.compare(a, b),
+            SortDirection::Descending => self.field.compare(b, a),
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = ParserError;
+
+    /// Parses field name optionally followed by `:ASC` or `:DESC`, e.g. `AMOUNT:DESC`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, direction) = match s.trim().split_once(DIRECTION_DELIMITER) {
+            Some((name, direction)) => (name, direction.trim()),
+            None => (s.trim(), ASCENDING),
+        };
+        let field = SortField::try_from(name.trim().parse::<TxFieldKey>()?)?;
+        match direction {
+            ASCENDING => Ok(SortKey::asc(field)),
+            DESCENDING => Ok(SortKey::desc(field)),
+            _ => Err(ParserError::UnparsableValue(direction.into())),
+        }
+    }
+}
+
+/// Order of records by keys in priority order.
+///
+/// Sorting is stable: records equal in all keys, e.g. under no keys at all, keep input order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortOrder {
+    /// Keys, later ones break ties of earlier ones.
+    pub keys: Vec<SortKey>,
+}
+
+impl SortOrder {
+    /// Order by provided keys.
+    pub fn new(keys: Vec<SortKey>) -> Self {
+        Self { keys }
+    }
+
+    /// Compares records by all keys.
+    pub fn compare(&self, a: &TxRecord, b: &TxRecord) -> Ordering {
+        self.keys
+            .iter()
+            .map(|key| key.compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// Sorts records in place.
+    pub fn sort(&self, data: &mut [TxRecord]) {
+        data.sort_by(|a, b| self.compare(a, b));
+    }
+
+    /// Returns sorted copy of records.
+    pub fn sorted(&self, data: &[TxRecord]) -> Vec<TxRecord> {
+        let mut sorted = data.to_vec();
+        self.sort(&mut sorted);
+        sorted
+    }
+}
+
+impl FromStr for SortOrder {
+    type Err = ParserError;
+
+    /// Parses comma separated keys, e.g. `TIMESTAMP:DESC,TX_ID`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(KEYS_DELIMITER)
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map(SortOrder::new)
+    }
+}
+
+/// Sorts datasets larger than memory by external merge.
+///
+/// Input is cut into runs of at most `run_len` records, every run but the last is sorted and
+/// spilled to a temporary file, then runs are merged while the sorted records are read.
+/// Runs are stored in text format, extras of records need names valid as text keys.
+#[derive(Debug, Clone)]
+pub struct ExternalSorter {
+    order: SortOrder,
+    run_len: usize,
+    dir: PathBuf,
+}
+
+impl ExternalSorter {
+    /// Sorter holding at most `run_len` records in memory, runs are spilled to the system
+    /// temporary directory.
+    pub fn new(order: SortOrder, run_len: usize) -> Self {
+        Self {
+            order,
+            run_len: run_len.max(1),
+            dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Spills runs to `dir` instead.
+    pub fn in_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Sorts records of the stream, e.g. [`Codec::parse_stream`], failing on its first error.
+    pub fn sort<I>(&self, records: I) -> Result<SortedRecords, AppError>
+    where
+        I: IntoIterator<Item = Result<TxRecord, AppError>>,
+    {
+        let mut sorted = SortedRecords {
+            order: self.order.clone(),
+            runs: Vec::new(),
+            heads: Vec::new(),
+            files: Vec::new(),
+            error: None,
+        };
+        let mut run = Vec::with_capacity(self.run_len.min(64 * 1024));
+        for tx in records {
+            run.push(tx?);
+            if run.len() == self.run_len {
+                self.order.sort(&mut run);
+                let path = self.spill(&run)?;
+                sorted.files.push(path.clone());
+                let f = File::open(&path).map_err(AppError::ReadError)?;
+                sorted
+                    .runs
+                    .push(Run::File(Codec::TextCodec.parse_stream(f, &run_options())));
+                run.clear();
+            }
+        }
+        self.order.sort(&mut run);
+        sorted.runs.push(Run::Memory(run.into_iter()));
+        for run in sorted.runs.iter_mut() {
+            let head = run.next().transpose()?;
+            sorted.heads.push(head);
+        }
+        Ok(sorted)
+    }
+
+    fn spill(&self, run: &[TxRecord]) -> Result<PathBuf, AppError> {
+        let path = self.dir.join(format!(
+            "rustyapa_sort_{}_{}.txt",
+            std::process::id(),
+            RUN_FILES.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        let write = || -> Result<(), AppError> {
+            let mut w = BufWriter::new(File::create(&path).map_err(AppError::WriteError)?);
+            Codec::TextCodec.write_with(&mut w, run, &run_write_options())?;
+            w.flush().map_err(AppError::WriteError)?;
+            Ok(())
+        };
+        write().inspect_err(|_| {
+            let _ = std::fs::remove_file(&path);
+        })?;
+        Ok(path)
+    }
+}
+
+// runs keep amounts of any scale and extras as they are
+fn run_options() -> ParseOptions {
+    let mut options = ParseOptions::default();
+    options.text.amounts = AmountFormat::Exact;
+    options.text.keep_extras = true;
+    options
+}
+
+fn run_write_options() -> WriteOptions {
+    let mut options = WriteOptions::default();
+    options.text.amounts = AmountFormat::Exact;
+    options
+}
+
+enum Run {
+    Memory(std::vec::IntoIter<TxRecord>),
+    File(RecordStream<'static>),
+}
+
+impl Iterator for Run {
+    type Item = Result<TxRecord, AppError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Run::Memory(records) => records.next().map(Ok),
+            Run::File(stream) => stream.next(),
+        }
+    }
+}
+
+/// Records merged from sorted runs, see [`ExternalSorter::sort`].
+///
+/// Stream ends after the first error reading runs back. Temporary files are removed when
+/// it is dropped.
+pub struct SortedRecords {
+    order: SortOrder,
+    runs: Vec<Run>,
+    // next record of every run
+    heads: Vec<Option<TxRecord>>,
+    files: Vec<PathBuf>,
+    error: Option<AppError>,
+}
+
+impl SortedRecords {
+    /// Number of runs spilled to temporary files.
+    pub fn spilled_runs(&self) -> usize {
+        self.files.len()
+    }
+}
+
+impl Iterator for SortedRecords {
+    type Item = Result<TxRecord, AppError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            self.heads.clear();
+            return Some(Err(e));
+        }
+        // runs hold records in input order, so earlier run wins a tie
+        let mut best: Option<usize> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            let Some(tx) = head else {
+                continue;
+            };
+            if best
+                .and_then(|b| self.heads[b].as_ref())
+                .is_none_or(|best| self.order.compare(tx, best).is_lt())
+            {
+                best = Some(i);
+            }
+        }
+        let best = best?;
+        let tx = self.heads[best].take();
+        match self.runs[best].next() {
+            Some(Ok(next)) => self.heads[best] = Some(next),
+            Some(Err(e)) => self.error = Some(e),
+            None => {}
+        }
+        tx.map(Ok)
+    }
+}
+
+impl Drop for SortedRecords {
+    fn drop(&mut self) {
+        // files are closed before removal
+        self.runs.clear();
+        for path in &self.files {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::analytics::aggregate::{GroupBy, GroupValue};
+use crate::codecs::traits::RecordSink;
+use crate::domain::tx::TxRecord;
+use crate::errors::AppError;
+
+/// Partitions records by values of the dimension, records keep input order in every partition.
+pub fn split(data: &[TxRecord], by: GroupBy) -> HashMap<GroupValue, Vec<TxRecord>> {
+    let mut partitions: HashMap<GroupValue, Vec<TxRecord>> = HashMap::new();
+    for tx in data {
+        partitions.entry(by.value(tx)).or_default().push(tx.clone());
+    }
+    partitions
+}
+
+/// Writes every partition of records to a sink of its own.
+///
+/// Sink of a partition is opened by `open` when the first record of the partition is pushed,
+/// e.g. with [`Codec::open_sink`] over a file named after the partition.
+///
+/// [`Codec::open_sink`]: crate::codecs::base::Codec::open_sink
+pub struct PartitionedSink<'a, F> {
+    by: GroupBy,
+    open: F,
+    // sinks with number of records pushed to them
+    sinks: HashMap<GroupValue, (RecordSink<'a>, usize)>,
+}
+
+impl<'a, F> PartitionedSink<'a, F>
+where
+    F: FnMut(&GroupValue) -> Result<RecordSink<'a>, AppError>,
+{
+    /// Sink partitioning records by the dimension.
+    pub fn new(by: GroupBy, open: F) -> Self {
+        Self {
+            by,
+            open,
+            sinks: HashMap::new(),
+        }
+    }
+
+    /// Writes record to the sink of its partition.
+    pub fn push(&mut self, tx: &TxRecord) -> Result<(), AppError> {
+        let value = self.by.value(tx);
+        let (sink, pushed) = match self.sinks.get_mut(&value) {
+            Some(sink) => sink,
+            None => {
+                let sink = (self.open)(&value)?;
+                self.sinks.entry(value).or_insert((sink, 0))
+            }
+        };
+        sink.push(tx)?;
+        *pushed += 1;
+        Ok(())
+    }
+
+    /// Finishes all sinks, returns number of records pushed to every partition.
+    pub fn finish(self) -> Result<BTreeMap<GroupValue, usize>, AppError> {
+        let mut counts = BTreeMap::new();
+        for (value, (sink, pushed)) in self.sinks {
+            sink.finish()?;
+            counts.insert(value, pushed);
+        }
+        Ok(counts)
+    }
+}
@@ -0,0 +1,71 @@
+use crate::domain::money::{self, AmountOverflow};
+use crate::domain::tx::*;
+
+/// Name of the extra field holding running balance, see [`StatementLine::into_record`].
+pub const BALANCE_KEY: &str = "BALANCE";
+
+/// Record of an account statement with its effect on the account and balance after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementLine {
+    /// The record.
+    pub tx: TxRecord,
+    /// Amount the record moves into the account, negative for debits.
+    pub effect: Money,
+    /// Balance of the account after the record.
+    pub balance: Money,
+}
+
+impl StatementLine {
+    /// The record with running balance in extra field [`BALANCE_KEY`], written as a column
+    /// or key by CSV and text codecs.
+    pub fn into_record(self) -> TxRecord {
+        let mut tx = self.tx;
+        tx.extras
+            .insert(BALANCE_KEY.to_string(), self.balance.to_string());
+        tx
+    }
+}
+
+/// Statement of `account` starting from `opening` balance, records ordered by timestamp.
+///
+/// Only successful records of the account are listed, as [`Ledger`] applies them; sorting is
+/// stable, so records of the same timestamp keep input order. Fails if a balance overflows.
+///
+/// [`Ledger`]: crate::analytics::balance::Ledger
+pub fn statement(
+    data: &[TxRecord],
+    account: &AccountType,
+    opening: Money,
+) -> Result<Vec<StatementLine>, AmountOverflow> {
+    let mut records: Vec<&TxRecord> = data
+        .iter()
+        .filter(|tx| TxStatus::Success == tx.status)
+        .filter(|tx| tx.source() == Some(account) || tx.destination() == Some(account))
+        .collect();
+    records.sort_by_key(|tx| tx.ts);
+    let mut balance = opening;
+    records
+        .into_iter()
+        .map(|tx| {
+            let effect = money::effect_on(tx, account)?;
+            balance = money::add(balance, effect)?;
+            Ok(StatementLine {
+                tx: tx.clone(),
+                effect,
+                balance,
+            })
+        })
+        .collect()
+}
+
+/// Records of `account` statement with running balances, see [`statement`].
+pub fn with_running_balance(
+    data: &[TxRecord],
+    account: &AccountType,
+    opening: Money,
+) -> Result<Vec<TxRecord>, AmountOverflow> {
+    Ok(statement(data, account, opening)?
+        .into_iter()
+        .map(StatementLine::into_record)
+        .collect())
+}
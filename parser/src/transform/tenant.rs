@@ -0,0 +1,23 @@
+use std::collections::BTreeMap;
+
+use crate::domain::tx::TxRecord;
+
+/// Returns records belonging to the tenant, `None` selects records without tenant.
+pub fn filter_by_tenant(data: &[TxRecord], tenant: Option<&str>) -> Vec<TxRecord> {
+    data.iter()
+        .filter(|tx| tx.tenant.as_deref() == tenant)
+        .cloned()
+        .collect()
+}
+
+/// Groups records by tenant, records without tenant are grouped under `None`.
+pub fn group_by_tenant(data: &[TxRecord]) -> BTreeMap<Option<String>, Vec<TxRecord>> {
+    let mut groups: BTreeMap<Option<String>, Vec<TxRecord>> = BTreeMap::new();
+    for tx in data {
+        groups
+            .entry(tx.tenant.clone())
+            .or_default()
+            .push(tx.clone());
+    }
+    groups
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::io::{BufRead, BufReader, Read};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::analytics::anomalies::{self, AnomalyOptions};
+use crate::codecs::errors::{ParserContext, ParserError};
+use crate::domain::tx::*;
+use crate::errors::AppError;
+use crate::filter::Filter;
+
+const RULES_COMMENT_SYMBOL: char = '#';
+const RULE_DELIMITER: char = ':';
+
+/// How serious a violation of a rule is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Worth a note, record is fine.
+    Info,
+    /// Suspicious record, input is still accepted.
+    Warning,
+    /// Input shall be rejected.
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "INFO"),
+            Severity::Warning => write!(f, "WARNING"),
+            Severity::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = ParserError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "INFO" => Ok(Severity::Info),
+            "WARNING" => Ok(Severity::Warning),
+            "ERROR" => Ok(Severity::Error),
+            _ => Err(ParserError::UnparsableValue(s.into())),
+        }
+    }
+}
+
+type RecordCheck = Arc<dyn Fn(&TxRecord) -> Option<String> + Send + Sync>;
+type DatasetCheck = Arc<dyn Fn(&[TxRecord]) -> Vec<(usize, String)> + Send + Sync>;
+
+#[derive(Clone)]
+enum Check {
+    Record(RecordCheck),
+    Dataset(DatasetCheck),
+}
+
+/// Named check of records and severity of its violations.
+#[derive(Clone)]
+pub struct Rule {
+    name: String,
+    severity: Severity,
+    check: Check,
+}
+
+impl Debug for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rule")
+            .field("name", &self.name)
+            .field("severity", &self.severity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Rule {
+    /// Rule checking every record on its own, `check` returns message of the violation.
+    pub fn record<F>(name: &str, severity: Severity, check: F) -> Self
+    where
+        F: Fn(&TxRecord) -> Option<String> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            severity,
+            check: Check::Record(Arc::new(check)),
+        }
+    }
+
+    /// Rule checking records together, `check` returns positions of violating records with
+    /// messages.
+    pub fn dataset<F>(name: &str, severity: Severity, check: F) -> Self
+    where
+        F: Fn(&[TxRecord]) -> Vec<(usize, String)> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            severity,
+            check: Check::Dataset(Arc::new(check)),
+        }
+    }
+
+    /// Rule every record shall satisfy the filter expression of.
+    pub fn expression(name: &str, severity: Severity, filter: Filter) -> Self {
+        Self::record(name, severity, move |tx| {
+            (!filter.matches(tx)).then(|| format!("does not satisfy `{}`", filter))
+        })
+    }
+
+    /// Built-in `unique_ids`: no two records share an id, exact duplicates included.
+    pub fn unique_ids(severity: Severity) -> Self {
+        Self::dataset("unique_ids", severity, |data| {
+            let mut first = HashMap::new();
+            data.iter()
+                .enumerate()
+                .filter_map(|(position, tx)| {
+                    let first = *first.entry(tx.id).or_insert(position);
+                    (first != position)
+                        .then(|| (position, format!("id is already used by record #{}", first)))
+                })
+                .collect()
+        })
+    }
+
+    /// Built-in `positive_amounts`: amounts are above zero.
+    pub fn positive_amounts(severity: Severity) -> Self {
+        Self::record("positive_amounts", severity, |tx| {
+            (tx.amount <= Money::default()).then(|| format!("amount {} is not positive", tx.amount))
+        })
+    }
+
+    /// Built-in `anomalies`: every finding of [`anomalies::detect`] is a violation.
+    pub fn anomalies(severity: Severity, options: AnomalyOptions) -> Self {
+        Self::dataset("anomalies", severity, move |data| {
+            anomalies::detect(data, &options)
+                .into_iter()
+                .map(|finding| (finding.position, finding.anomaly.to_string()))
+                .collect()
+        })
+    }
+
+    /// Name the rule is reported by.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Severity of violations.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn run(&self, data: &[TxRecord]) -> Vec<(usize, String)> {
+        match &self.check {
+            Check::Record(check) => data
+                .iter()
+                .enumerate()
+                .filter_map(|(position, tx)| check(tx).map(|message| (position, message)))
+                .collect(),
+            Check::Dataset(check) => check(data),
+        }
+    }
+}
+
+/// Record failing a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Position of the record, starting from 0.
+    pub position: usize,
+    /// Id of the record.
+    pub id: TxIdType,
+    /// Name of the rule.
+    pub rule: String,
+    /// Severity of the rule.
+    pub severity: Severity,
+    /// What is wrong with the record.
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: record #{} (TX_ID {}): {}",
+            self.severity, self.rule, self.position, self.id, self.message
+        )
+    }
+}
+
+/// Violations of all rules, ordered by position, then by rule.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// The violations.
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// True if no rule of [`Severity::Error`] is violated.
+    pub fn is_valid(&self) -> bool {
+        self.violations
+            .iter()
+            .all(|violation| violation.severity < Severity::Error)
+    }
+
+    /// Number of violations of the severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.violations
+            .iter()
+            .filter(|violation| violation.severity == severity)
+            .count()
+    }
+
+    /// Severity of the worst violation, `None` for no violations.
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.violations
+            .iter()
+            .map(|violation| violation.severity)
+            .max()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for violation in &self.violations {
+            writeln!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Set of rules records are validated against.
+///
+/// Strict parsing enforces rules of [`ParseOptions::validation`], input violating a rule of
+/// [`Severity::Error`] is rejected. Strict record streams enforce record rules only.
+///
+/// [`ParseOptions::validation`]: crate::codecs::options::ParseOptions::validation
+#[derive(Debug, Clone, Default)]
+pub struct Validator {
+    rules: Vec<Rule>,
+}
+
+impl Validator {
+    /// Validator without rules, every input is valid.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Built-in rules: [`Rule::unique_ids`] and [`Rule::positive_amounts`] as errors.
+    pub fn builtin() -> Self {
+        Self::new()
+            .with_rule(Rule::unique_ids(Severity::Error))
+            .with_rule(Rule::positive_amounts(Severity::Error))
+    }
+
+    /// Adds the rule.
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Rules in order they were added.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Loads expression rules, one `SEVERITY NAME: EXPRESSION` per line, e.g.
+    /// ```text
+    /// # comment
+    /// ERROR positive_amount: amount > 0
+    /// WARNING described: description != ""
+    /// ```
+    /// Expressions are [`Filter`] ones, records shall satisfy them.
+    pub fn from_reader<R: Read>(r: R) -> Result<Self, AppError> {
+        let mut validator = Self::default();
+        for (line_num, line_res) in BufReader::new(r).lines().enumerate() {
+            let input_line = line_res.map_err(AppError::ReadError)?;
+            let line = input_line.trim();
+            if line.is_empty() || line.starts_with(RULES_COMMENT_SYMBOL) {
+                continue;
+            }
+            let context =
+                || ParserContext::with_line_number_and_line(line_num + 1, input_line.clone());
+            let rule = parse_rule(line).map_err(|e| match e {
+                AppError::ParsingError { source, .. } => AppError::ParsingError {
+                    context: context(),
+                    source,
+                },
+                other => other,
+            })?;
+            validator.rules.push(rule);
+        }
+        Ok(validator)
+    }
+
+    /// Runs all rules over records.
+    pub fn validate(&self, data: &[TxRecord]) -> ValidationReport {
+        let mut violations = Vec::new();
+        for rule in &self.rules {
+            for (position, message) in rule.run(data) {
+                violations.push(Violation {
+                    position,
+                    id: data[position].id,
+                    rule: rule.name.clone(),
+                    severity: rule.severity,
+                    message,
+                });
+            }
+        }
+        // stable, rules keep their order at every position
+        violations.sort_by_key(|violation| violation.position);
+        ValidationReport { violations }
+    }
+
+    /// Fails on the first record violating a rule of [`Severity::Error`] with
+    /// [`ParserError::RuleViolation`] error.
+    pub fn enforce(&self, data: &[TxRecord]) -> Result<(), AppError> {
+        match self
+            .validate(data)
+            .violations
+            .into_iter()
+            .find(|violation| Severity::Error == violation.severity)
+        {
+            Some(violation) => Err(AppError::ParsingError {
+                context: ParserContext::with_record(violation.position + 1),
+                source: ParserError::RuleViolation {
+                    rule: violation.rule,
+                    message: violation.message,
+                },
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // record rules of error severity on `tx`, `index`-th record of a stream; dataset rules
+    // need all records and are left out
+    pub(crate) fn enforce_record(&self, tx: &TxRecord, index: usize) -> Result<(), AppError> {
+        for rule in &self.rules {
+            if let (Severity::Error, Check::Record(check)) = (rule.severity, &rule.check)
+                && let Some(message) = check(tx)
+            {
+                return Err(AppError::ParsingError {
+                    context: ParserContext::with_record(index + 1),
+                    source: ParserError::RuleViolation {
+                        rule: rule.name.clone(),
+                        message,
+                    },
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_rule(line: &str) -> Result<Rule, AppError> {
+    let unparsable = |value: &str| AppError::ParsingError {
+        context: ParserContext::with_position(0),
+        source: ParserError::UnparsableValue(value.into()),
+    };
+    let (head, expression) = line
+        .split_once(RULE_DELIMITER)
+        .ok_or(AppError::ParsingError {
+            context: ParserContext::with_position(0),
+            source: ParserError::NoFieldDelimiter,
+        })?;
+    let (severity, name) = head
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| unparsable(head))?;
+    let severity = severity.parse().map_err(|_| unparsable(severity))?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(unparsable(name));
+    }
+    Ok(Rule::expression(name, severity, Filter::parse(expression)?))
+}
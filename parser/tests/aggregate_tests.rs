@@ -0,0 +1,115 @@
+use parser::analytics::aggregate::{GroupBy, GroupValue, aggregate};
+use parser::domain::money::AmountOverflow;
+use parser::domain::tx::{
+    AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp, UtcOffset,
+};
+
+fn sample_tx(id: u64, kind: TxKind, amount: Money, ts: &str) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount,
+        ts: TxTimestamp::parse_rfc3339(ts).expect("timestamp should parse"),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn dataset() -> Vec<TxRecord> {
+    vec![
+        sample_tx(
+            1,
+            TxKind::Deposit,
+            Money::new(500, 2),
+            "2024-01-01T10:00:00Z",
+        ),
+        sample_tx(
+            2,
+            TxKind::Transfer,
+            Money::new(12, 0),
+            "2024-01-01T23:30:00Z",
+        ),
+        sample_tx(
+            3,
+            TxKind::Deposit,
+            Money::new(1_250, 3),
+            "2024-01-02T08:00:00Z",
+        ),
+        sample_tx(4, TxKind::Deposit, Money::new(2, 0), "2024-01-01T12:00:00Z"),
+    ]
+}
+
+#[test]
+fn amounts_are_aggregated_by_group() {
+    let result = aggregate(&dataset(), &[GroupBy::Kind]).expect("sums should fit");
+    assert_eq!(result.groups.len(), 2);
+    let deposits = result
+        .get(&[GroupValue::Kind(TxKind::Deposit)])
+        .expect("deposits should be grouped");
+    assert_eq!(deposits.count, 3);
+    assert_eq!(deposits.sum, Money::new(8_250, 3));
+    assert_eq!(deposits.sum.scale, 3);
+    assert_eq!(deposits.min, Money::new(125, 2));
+    assert_eq!(deposits.max, Money::new(5, 0));
+    assert_eq!(
+        result.groups[1].key,
+        vec![GroupValue::Kind(TxKind::Transfer)]
+    );
+
+    let total = aggregate(&dataset(), &[]).unwrap();
+    assert_eq!(total.groups.len(), 1);
+    assert_eq!(total.groups[0].count, 4);
+    assert!(aggregate(&[], &[GroupBy::Kind]).unwrap().groups.is_empty());
+}
+
+#[test]
+fn groups_by_day_follow_offset_and_are_written_as_csv() {
+    let cest: UtcOffset = "+02:00".parse().unwrap();
+    let dimensions: Vec<GroupBy> = ["TX_TYPE", "DAY"]
+        .into_iter()
+        .map(|name| name.parse().expect("dimension should parse"))
+        .collect();
+    assert_eq!(
+        dimensions,
+        vec![GroupBy::Kind, GroupBy::Day(UtcOffset::UTC)]
+    );
+    assert!("AMOUNT".parse::<GroupBy>().is_err());
+
+    let mut out = Vec::new();
+    aggregate(&dataset(), &dimensions)
+        .unwrap()
+        .write_csv(&mut out)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "TX_TYPE,DAY,COUNT,SUM,MIN,MAX\n\
+         DEPOSIT,2024-01-01,2,7.00,2,5.00\n\
+         DEPOSIT,2024-01-02,1,1.250,1.250,1.250\n\
+         TRANSFER,2024-01-01,1,12,12,12\n"
+    );
+
+    // late evening transfer is on the next day in Berlin
+    let local = aggregate(&dataset(), &[GroupBy::Day(cest)]).unwrap();
+    let days: Vec<(String, usize)> = local
+        .groups
+        .iter()
+        .map(|group| (group.key[0].to_string(), group.count))
+        .collect();
+    assert_eq!(
+        days,
+        vec![("2024-01-01".to_string(), 2), ("2024-01-02".to_string(), 2)]
+    );
+}
+
+#[test]
+fn overflowing_sum_is_an_error() {
+    let max = Money::from_minor_units(i64::MAX);
+    let data = vec![
+        sample_tx(1, TxKind::Deposit, max, "2024-01-01T10:00:00Z"),
+        sample_tx(2, TxKind::Deposit, max, "2024-01-01T10:00:00Z"),
+    ];
+    assert_eq!(aggregate(&data, &[GroupBy::Kind]), Err(AmountOverflow));
+}
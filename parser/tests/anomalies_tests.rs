@@ -0,0 +1,100 @@
+use parser::analytics::anomalies::{Anomaly, AnomalyOptions, Finding, detect};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+
+const NOW: u64 = 1_700_000_000_000;
+
+fn sample_tx(id: u64, kind: TxKind, from: u64, to: u64, amount: i64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(to),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(NOW - 1_000),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn options() -> AnomalyOptions {
+    AnomalyOptions {
+        now: TxTimestamp::from_millis(NOW),
+        ..Default::default()
+    }
+}
+
+fn anomalies(findings: &[Finding]) -> Vec<(usize, Anomaly)> {
+    findings
+        .iter()
+        .map(|finding| (finding.position, finding.anomaly.clone()))
+        .collect()
+}
+
+#[test]
+fn suspicious_records_are_flagged() {
+    let mut future = sample_tx(4, TxKind::Transfer, 1, 2, 100);
+    future.ts = TxTimestamp::from_millis(NOW + 60_000);
+    let mut changed = sample_tx(1, TxKind::Deposit, 0, 1, 100);
+    changed.description = Some("edited".to_string());
+    let data = vec![
+        sample_tx(1, TxKind::Deposit, 0, 1, 100),
+        sample_tx(2, TxKind::Deposit, 0, 1, 0),
+        sample_tx(3, TxKind::Transfer, 5, 5, 100),
+        future,
+        // exact duplicate is not an anomaly
+        sample_tx(1, TxKind::Deposit, 0, 1, 100),
+        changed.clone(),
+        // the same conflicting payload again
+        changed,
+        sample_tx(6, TxKind::Deposit, 0, 1, -5),
+        // deposit from the account itself moves nothing out of it
+        sample_tx(7, TxKind::Deposit, 1, 1, 100),
+    ];
+    let findings = detect(&data, &options());
+    assert_eq!(
+        anomalies(&findings),
+        vec![
+            (1, Anomaly::NonPositiveDeposit),
+            (2, Anomaly::SelfTransfer),
+            (3, Anomaly::FutureTimestamp),
+            (5, Anomaly::ConflictingId { first: 0 }),
+            (7, Anomaly::NonPositiveDeposit),
+        ]
+    );
+    assert_eq!(findings[3].id, TxIdType::Numeric(1));
+    assert_eq!(
+        findings[3].to_string(),
+        "record #5 (TX_ID 1): id is used by record #0 with other fields"
+    );
+
+    // clock skew is tolerated
+    let tolerant = AnomalyOptions {
+        future_tolerance_ms: 60_000,
+        ..options()
+    };
+    assert!(!anomalies(&detect(&data, &tolerant)).contains(&(3, Anomaly::FutureTimestamp)));
+}
+
+#[test]
+fn amounts_beyond_thresholds_are_flagged() {
+    let data = vec![
+        sample_tx(1, TxKind::Transfer, 1, 2, 99),
+        sample_tx(2, TxKind::Transfer, 1, 2, 100),
+        sample_tx(3, TxKind::Transfer, 1, 2, 1_000_000),
+        sample_tx(4, TxKind::Transfer, 1, 2, 1_000_001),
+    ];
+    let thresholds = AnomalyOptions {
+        min_amount: Some(Money::new(1, 0)),
+        max_amount: Some(Money::new(10_000, 0)),
+        ..options()
+    };
+    assert_eq!(
+        anomalies(&detect(&data, &thresholds)),
+        vec![
+            (0, Anomaly::AmountBelowMinimum),
+            (3, Anomaly::AmountAboveMaximum),
+        ]
+    );
+    assert!(detect(&data, &options()).is_empty());
+}
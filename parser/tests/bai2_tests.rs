@@ -0,0 +1,128 @@
+use parser::codecs::base::Codec;
+use parser::codecs::errors::ParserError;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxStatus};
+use parser::errors::AppError;
+
+const FILE: &str = "\
+01,BANK,CUSTOMER,240102,2300,1,80,10,2/
+02,CUSTOMER,BANK,1,240103,1015,USD,2/
+03,4242,USD,010,500000,,/
+16,165,150050,Z,9001,,Lockbox deposit
+16,475,2500,S,100,200,300,,77,Check paid, no. 1234
+88,continued text
+16,195,100,V,240103,1200,,,wire
+49,500000,4/
+98,500000,1,6/
+99,500000,1,8/
+";
+
+#[test]
+fn parse_transaction_details() {
+    let records = Codec::Bai2Codec
+        .parse(FILE.as_bytes())
+        .expect("BAI2 file should parse");
+    assert_eq!(records.len(), 3);
+
+    let deposit = &records[0];
+    assert_eq!(deposit.id, TxIdType::Numeric(9001));
+    assert_eq!(deposit.kind, TxKind::Deposit);
+    assert_eq!(deposit.to, AccountType::Numeric(4242));
+    assert_eq!(deposit.amount, Money::from_minor_units(150_050));
+    assert_eq!(deposit.status, TxStatus::Success);
+    assert_eq!(deposit.ts.millis(), 1_704_276_900_000);
+    assert_eq!(deposit.description.as_deref(), Some("Lockbox deposit"));
+    assert_eq!(deposit.reference.as_deref(), Some("9001"));
+
+    let check = &records[1];
+    assert_eq!(check.id, TxIdType::Numeric(77));
+    assert_eq!(check.kind, TxKind::Withdrawal);
+    assert_eq!(check.from, AccountType::Numeric(4242));
+    assert_eq!(check.amount, Money::from_minor_units(2500));
+    assert_eq!(
+        check.description.as_deref(),
+        Some("Check paid, no. 1234,continued text")
+    );
+    // customer reference stands in for missing bank one
+    assert_eq!(check.reference.as_deref(), Some("77"));
+
+    assert_eq!(records[2].id, TxIdType::Numeric(3));
+    assert_eq!(records[2].description.as_deref(), Some("wire"));
+    assert_eq!(records[2].reference, None);
+}
+
+#[test]
+fn parse_rejects_unknown_type_code_and_orphan_continuation() {
+    let input = FILE.replace("16,165,", "16,999,");
+    let err = Codec::Bai2Codec
+        .parse(input.as_bytes())
+        .expect_err("type code out of ranges should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableValue(_),
+            ..
+        }
+    ));
+
+    let err = Codec::Bai2Codec
+        .parse("88,text/\n".as_bytes())
+        .expect_err("continuation without record should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::IncompleteRecord,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn text_keeps_its_slashes_and_empty_text_is_terminated() {
+    let input = FILE
+        .replace("Lockbox deposit", "invoice 12/2023/")
+        .replace(",,,wire", ",,,/");
+    let records = Codec::Bai2Codec
+        .parse(input.as_bytes())
+        .expect("BAI2 file should parse");
+    assert_eq!(records[0].description.as_deref(), Some("invoice 12/2023/"));
+    assert_eq!(records[2].description, None);
+}
+
+#[test]
+fn end_of_day_is_midnight_of_the_next_day() {
+    let as_of = |time: &str| {
+        let input = FILE.replace("240103,1015,USD", &format!("240103,{},USD", time));
+        Codec::Bai2Codec
+            .parse(input.as_bytes())
+            .expect("BAI2 file should parse")[0]
+            .ts
+            .millis()
+    };
+    // 2024-01-04T00:00:00Z
+    assert_eq!(as_of("2400"), 1_704_326_400_000);
+    assert_eq!(as_of("9999"), 1_704_326_400_000);
+    assert_eq!(as_of(""), 1_704_326_400_000 - 24 * 60 * 60 * 1000);
+}
+
+#[test]
+fn parse_rejects_other_currencies() {
+    for input in [
+        FILE.replace("1015,USD", "1015,EUR"),
+        FILE.replace("03,4242,USD", "03,4242,EUR"),
+    ] {
+        let err = Codec::Bai2Codec
+            .parse(input.as_bytes())
+            .expect_err("only USD amounts are read");
+        assert!(matches!(
+            err,
+            AppError::ParsingError {
+                source: ParserError::UnparsableValue(_),
+                ..
+            }
+        ));
+    }
+    let input = FILE
+        .replace("1015,USD", "1015,")
+        .replace("03,4242,USD", "03,4242,");
+    assert_eq!(Codec::Bai2Codec.parse(input.as_bytes()).unwrap().len(), 3);
+}
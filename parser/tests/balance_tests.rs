@@ -0,0 +1,99 @@
+use parser::analytics::balance::{Ledger, LedgerError};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+
+fn sample_tx(id: u64, kind: TxKind, from: u64, to: u64, amount: i64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(to),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(1_700 + id),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn account(id: u64) -> AccountType {
+    AccountType::Numeric(id)
+}
+
+#[test]
+fn balances_follow_kinds() {
+    let mut failed = sample_tx(5, TxKind::Deposit, 0, 1, 9_999);
+    failed.status = TxStatus::Failure;
+    let data = vec![
+        sample_tx(1, TxKind::Deposit, 0, 1, 1_000),
+        sample_tx(2, TxKind::Transfer, 1, 2, 300),
+        sample_tx(3, TxKind::Withdrawal, 2, 99, 100),
+        sample_tx(4, TxKind::Fee, 1, 0, 50),
+        failed,
+    ];
+    let mut ledger = Ledger::new();
+    assert!(ledger.apply_all(&data).is_empty());
+    assert_eq!(ledger.balance(&account(1)), Money::from_minor_units(650));
+    assert_eq!(ledger.balance(&account(2)), Money::from_minor_units(200));
+    // outside sides of deposits, withdrawals and fees are not accounts of the books
+    let accounts: Vec<&AccountType> = ledger.balances().map(|(account, _)| account).collect();
+    assert_eq!(accounts, vec![&account(1), &account(2)]);
+    assert_eq!(ledger.applied(), 4);
+    assert_eq!(ledger.skipped(), 1);
+    assert_eq!(ledger.balance(&account(3)), Money::default());
+}
+
+#[test]
+fn overdrafts_are_rejected_unless_allowed() {
+    let withdrawal = sample_tx(2, TxKind::Withdrawal, 1, 0, 600);
+    let mut ledger = Ledger::new();
+    ledger.open(account(1), Money::from_minor_units(500));
+    assert_eq!(
+        ledger.apply(&withdrawal),
+        Err(LedgerError::Overdraft {
+            id: TxIdType::Numeric(2),
+            account: account(1),
+            balance: Money::from_minor_units(500),
+            amount: Money::from_minor_units(600),
+        })
+    );
+    assert_eq!(ledger.balance(&account(1)), Money::from_minor_units(500));
+    assert_eq!(ledger.applied(), 0);
+
+    ledger.allow_overdraft = true;
+    assert_eq!(ledger.apply(&withdrawal), Ok(()));
+    assert_eq!(ledger.balance(&account(1)), Money::from_minor_units(-100));
+}
+
+#[test]
+fn inconsistent_records_are_rejected() {
+    let mut ledger = Ledger::new();
+    ledger.allow_overdraft = true;
+    let errors = ledger.apply_all(&[
+        sample_tx(1, TxKind::Transfer, 1, 1, 100),
+        sample_tx(2, TxKind::Deposit, 0, 1, -100),
+        // deposit from the account itself moves nothing out of it
+        sample_tx(3, TxKind::Deposit, 1, 1, 1),
+        sample_tx(4, TxKind::Deposit, 0, 1, i64::MAX - 1),
+        sample_tx(5, TxKind::Deposit, 0, 1, 1),
+    ]);
+    assert_eq!(
+        errors,
+        vec![
+            LedgerError::SameAccount {
+                id: TxIdType::Numeric(1),
+                kind: TxKind::Transfer
+            },
+            LedgerError::NegativeAmount {
+                id: TxIdType::Numeric(2)
+            },
+            LedgerError::Overflow {
+                id: TxIdType::Numeric(5)
+            },
+        ]
+    );
+    assert_eq!(errors[0].to_string(), "#1 is TRANSFER to the same account");
+    assert_eq!(
+        ledger.balance(&account(1)),
+        Money::from_minor_units(i64::MAX)
+    );
+}
@@ -0,0 +1,143 @@
+use parser::codecs::base::Codec;
+use parser::codecs::errors::ParserError;
+use parser::codecs::options::{ParseOptions, WriteOptions};
+use parser::domain::batch::{BatchHeader, TxBatch};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Deposit,
+        from: AccountType::Numeric(0),
+        to: AccountType::Numeric(7),
+        amount: Money::from_minor_units(10),
+        ts: TxTimestamp::from_millis(1700),
+        status: TxStatus::Success,
+        description: Some("x".into()),
+        ..Default::default()
+    }
+}
+
+fn sample_batch() -> TxBatch {
+    TxBatch {
+        header: BatchHeader {
+            source: Some("core banking".into()),
+            created: Some(TxTimestamp::from_millis(1_704_276_930_000)),
+            record_count: Some(2),
+        },
+        records: vec![sample_tx(1), sample_tx(2)],
+    }
+}
+
+fn write_batch(codec: &Codec, batch: &TxBatch) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    codec
+        .write_batch(&mut bytes, batch, &WriteOptions::default())
+        .expect("batch write should succeed");
+    bytes
+}
+
+fn is_count_mismatch(err: &AppError, declared: u64, read: u64) -> bool {
+    matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::RecordCountMismatch { declared: d, actual: a },
+            ..
+        } if *d == declared && *a == read
+    )
+}
+
+#[test]
+fn batch_header_round_trips() {
+    let batch = sample_batch();
+    for codec in [Codec::CsvCodec, Codec::TsvCodec, Codec::BinaryV2Codec] {
+        let bytes = write_batch(&codec, &batch);
+        let parsed = codec
+            .parse_batch(bytes.as_slice(), &ParseOptions::default())
+            .expect("batch parse should succeed");
+        assert_eq!(parsed, batch, "{:?} should keep batch header", codec);
+        // plain parsing steps over the header
+        assert_eq!(codec.parse(bytes.as_slice()).unwrap(), batch.records);
+    }
+}
+
+#[test]
+fn csv_header_is_kept_in_metadata_lines() {
+    let bytes = write_batch(&Codec::CsvCodec, &sample_batch());
+    let text = String::from_utf8(bytes).unwrap();
+    assert!(text.starts_with(
+        "# SOURCE: core banking\n# CREATED: 2024-01-03T10:15:30.000Z\n# RECORD_COUNT: 2\nTX_ID,"
+    ));
+
+    // other lines starting with `#` are comments, absent values stay absent
+    let input = "# exported by hand\n# RECORD_COUNT: 1\n\nTX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1,DEPOSIT,0,7,10,1700,SUCCESS,\"x\"\n";
+    let parsed = Codec::CsvCodec
+        .parse_batch(input.as_bytes(), &ParseOptions::default())
+        .expect("batch parse should succeed");
+    assert_eq!(
+        parsed.header,
+        BatchHeader {
+            record_count: Some(1),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn declared_count_is_verified() {
+    let mut batch = sample_batch();
+    batch.records.pop();
+    let err = Codec::CsvCodec
+        .write_batch(&mut Vec::new(), &batch, &WriteOptions::default())
+        .expect_err("mismatching count can't be written");
+    assert!(matches!(err, AppError::WriteError(_)));
+
+    let csv = "# RECORD_COUNT: 3\nTX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n1,DEPOSIT,0,7,10,1700,SUCCESS,\"x\"\n2,DEPOSIT,0,7,10,1700,SUCCESS,\"x\"\n";
+    let mut v2 = write_batch(
+        &Codec::BinaryV2Codec,
+        &TxBatch {
+            header: BatchHeader {
+                record_count: Some(2),
+                ..Default::default()
+            },
+            records: vec![sample_tx(1), sample_tx(2)],
+        },
+    );
+    // magic, flags, absent source and creation time, then count + 1
+    assert_eq!(v2[7], 3);
+    v2[7] = 4;
+    for (codec, bytes) in [
+        (Codec::CsvCodec, csv.as_bytes()),
+        (Codec::BinaryV2Codec, v2.as_slice()),
+    ] {
+        let errors = [
+            codec.parse(bytes).unwrap_err(),
+            codec
+                .parse_batch(bytes, &ParseOptions::default())
+                .unwrap_err(),
+        ];
+        for err in errors {
+            assert!(is_count_mismatch(&err, 3, 2), "{:?}", err);
+        }
+    }
+}
+
+#[test]
+fn other_formats_have_default_header() {
+    let batch = sample_batch();
+    let bytes = write_batch(&Codec::TextCodec, &batch);
+    let parsed = Codec::TextCodec
+        .parse_batch(bytes.as_slice(), &ParseOptions::default())
+        .expect("batch parse should succeed");
+    assert_eq!(parsed.header, BatchHeader::default());
+    assert_eq!(parsed.records, batch.records);
+}
+
+#[test]
+fn new_batch_declares_its_records() {
+    let batch = TxBatch::new(Some("ledger".into()), vec![sample_tx(1)]);
+    assert_eq!(batch.header.record_count, Some(1));
+    assert!(batch.header.created.is_some());
+    assert!(batch.verify().is_ok());
+}
@@ -1,18 +1,23 @@
 use parser::codecs::base::Codec;
-use parser::codecs::errors::ParserError;
-use parser::domain::tx::{AccountType, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::codecs::binary;
+use parser::codecs::binary::{BinaryVersion, Endianness, IndexedReader};
+use parser::codecs::errors::{ParserContext, ParserError};
+use parser::codecs::options::{ParseOptions, Strictness, WriteOptions};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
 use parser::errors::AppError;
+use std::io::Cursor;
 
 fn sample_tx() -> TxRecord {
     TxRecord {
-        id: TxIdType(1),
+        id: TxIdType::Numeric(1),
         kind: TxKind::Transfer,
-        from: AccountType(11),
-        to: AccountType(22),
-        amount: -500,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(-500),
         ts: TxTimestamp::from_millis(1_700_000),
         status: TxStatus::Pending,
-        description: "payment".to_string(),
+        description: Some("payment".to_string()),
+        ..Default::default()
     }
 }
 
@@ -65,9 +70,9 @@ fn parse_empty_binary_is_ok_and_returns_no_records() {
 fn binary_round_trip_multiple_records() {
     let tx1 = sample_tx();
     let mut tx2 = sample_tx();
-    tx2.id = TxIdType(2);
+    tx2.id = TxIdType::Numeric(2);
     tx2.status = TxStatus::Success;
-    tx2.description = "refund".to_string();
+    tx2.description = Some("refund".to_string());
 
     let mut bytes = Vec::new();
     Codec::BinaryCodec
@@ -80,6 +85,23 @@ fn binary_round_trip_multiple_records() {
     assert_eq!(parsed, vec![tx1, tx2]);
 }
 
+#[test]
+fn binary_round_trip_iban_accounts() {
+    let tx = TxRecord {
+        from: AccountType::Iban("GB82WEST12345698765432".into()),
+        to: AccountType::Iban("DE89370400440532013000".into()),
+        ..sample_tx()
+    };
+    let mut bytes = Vec::new();
+    Codec::BinaryCodec
+        .write(&mut bytes, std::slice::from_ref(&tx))
+        .expect("binary write should succeed");
+    let parsed = Codec::BinaryCodec
+        .parse(bytes.as_slice())
+        .expect("binary parse should succeed");
+    assert_eq!(parsed, vec![tx]);
+}
+
 #[test]
 fn parse_rejects_invalid_magic_header() {
     let input = encode_record(0, 0, b"ok", None, *b"NOPE");
@@ -164,3 +186,804 @@ fn parse_returns_read_error_for_truncated_body() {
         .expect_err("truncated body should fail");
     assert!(matches!(err, AppError::ReadError(_)));
 }
+
+#[test]
+fn scan_reports_healthy_stream() {
+    let mut bytes = Vec::new();
+    Codec::BinaryCodec
+        .write(&mut bytes, &[sample_tx(), sample_tx()])
+        .expect("binary write should succeed");
+
+    let report =
+        binary::scan(bytes.as_slice(), &Default::default(), 10).expect("scan should succeed");
+    assert!(report.is_healthy());
+    assert_eq!(report.records, 2);
+    assert_eq!(report.bytes, bytes.len() as u64);
+}
+
+#[test]
+fn scan_collects_field_issues_and_continues() {
+    let mut input = encode_record(9, 0, b"ok", None, *b"YPBN");
+    input.extend(encode_record(0, 9, b"ok", None, *b"YPBN"));
+    input.extend(encode_record(0, 0, b"ok", None, *b"YPBN"));
+
+    let report =
+        binary::scan(input.as_slice(), &Default::default(), 10).expect("scan should succeed");
+    assert_eq!(report.records, 3);
+    assert_eq!(report.issues.len(), 2);
+    assert!(!report.aborted);
+    assert_eq!(report.issues[1].offset, (input.len() / 3) as u64);
+}
+
+#[test]
+fn scan_stops_when_framing_is_lost() {
+    let mut input = encode_record(0, 0, b"ok", None, *b"YPBN");
+    input.extend(encode_record(0, 0, b"ok", None, *b"NOPE"));
+    input.extend(encode_record(0, 0, b"ok", None, *b"YPBN"));
+
+    let report =
+        binary::scan(input.as_slice(), &Default::default(), 10).expect("scan should succeed");
+    assert_eq!(report.records, 1);
+    assert!(report.aborted);
+    assert!(matches!(
+        report.issues[0].error,
+        ParserError::InvalidRecordHeader(_)
+    ));
+}
+
+#[test]
+fn scan_reports_truncated_tail_and_issue_limit() {
+    let mut input = encode_record(0, 0, b"ok", None, *b"YPBN");
+    input.truncate(input.len() - 2);
+    let report =
+        binary::scan(input.as_slice(), &Default::default(), 10).expect("scan should succeed");
+    assert!(report.aborted);
+    assert_eq!(report.records, 0);
+
+    let mut input = Vec::new();
+    for _ in 0..5 {
+        input.extend(encode_record(9, 0, b"ok", None, *b"YPBN"));
+    }
+    let report =
+        binary::scan(input.as_slice(), &Default::default(), 2).expect("scan should succeed");
+    assert_eq!(report.issues.len(), 2);
+    assert_eq!(report.issues_found, 5);
+    assert_eq!(report.records, 5);
+
+    // issues are counted even when none is kept
+    let report =
+        binary::scan(input.as_slice(), &Default::default(), 0).expect("scan should succeed");
+    assert!(report.issues.is_empty());
+    assert_eq!(report.records, 5);
+    assert!(!report.is_healthy());
+}
+
+fn resync_options() -> ParseOptions {
+    let mut options = ParseOptions::default();
+    options.binary.resync = true;
+    options
+}
+
+fn tx_with_id(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        ..sample_tx()
+    }
+}
+
+fn encode_all(data: &[TxRecord]) -> Vec<u8> {
+    let mut buff = Vec::new();
+    Codec::BinaryCodec.write(&mut buff, data).unwrap();
+    buff
+}
+
+#[test]
+fn resync_skips_garbage_between_records() {
+    let mut input = encode_all(&[tx_with_id(1)]);
+    let garbage_start = input.len();
+    input.extend_from_slice(b"garbage\x00\xFF");
+    let garbage_end = input.len();
+    input.extend(encode_all(&[tx_with_id(2), tx_with_id(3)]));
+
+    let parsed = Codec::BinaryCodec
+        .parse_lenient(input.as_slice(), &resync_options())
+        .expect("garbage should be skipped");
+    assert_eq!(
+        parsed.records,
+        vec![tx_with_id(1), tx_with_id(2), tx_with_id(3)]
+    );
+    assert_eq!(parsed.errors.len(), 1);
+    match &parsed.errors[0] {
+        (ParserContext::ByteRange { start, end }, ParserError::InvalidRecordHeader(_)) => {
+            assert_eq!((*start, *end), (garbage_start, garbage_end));
+        }
+        other => panic!("unexpected error {:?}", other),
+    }
+
+    // without resync framing is lost for good
+    assert!(
+        Codec::BinaryCodec
+            .parse_lenient(input.as_slice(), &ParseOptions::default())
+            .is_err()
+    );
+}
+
+#[test]
+fn resync_rescans_body_of_record_with_corrupt_size() {
+    let first = encode_all(&[tx_with_id(1)]);
+    let mut input = first.clone();
+    // size covers the next record and more than record size limit allows
+    input[4..8].copy_from_slice(&u32::MAX.to_be_bytes()[..]);
+    input.extend(encode_all(&[tx_with_id(2)]));
+
+    let parsed = Codec::BinaryCodec
+        .parse_lenient(input.as_slice(), &resync_options())
+        .expect("corrupt record should be skipped");
+    assert_eq!(parsed.records, vec![tx_with_id(2)]);
+    assert!(matches!(
+        parsed.errors.as_slice(),
+        [(ParserContext::ByteRange { start: 0, end }, ParserError::LimitExceeded(_))]
+            if *end == first.len()
+    ));
+}
+
+#[test]
+fn resync_rejects_truncated_tail() {
+    let mut input = encode_all(&[tx_with_id(1), tx_with_id(2)]);
+    let tail_start = encode_all(&[tx_with_id(1)]).len();
+    input.truncate(input.len() - 3);
+
+    let items: Vec<_> = Codec::BinaryCodec
+        .parse_stream(input.as_slice(), &resync_options())
+        .collect();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_ref().unwrap(), &tx_with_id(1));
+    match &items[1] {
+        Err(AppError::ParsingError {
+            context: ParserContext::ByteRange { start, end },
+            source: ParserError::IncompleteRecord,
+        }) => assert_eq!((*start, *end), (tail_start, input.len())),
+        other => panic!("unexpected item {:?}", other),
+    }
+}
+
+fn headered_options() -> WriteOptions {
+    let mut options = WriteOptions::default();
+    options.binary.version = BinaryVersion::V2;
+    options
+}
+
+fn encode_headered(data: &[TxRecord]) -> Vec<u8> {
+    let mut buff = Vec::new();
+    Codec::BinaryCodec
+        .write_with(&mut buff, data, &headered_options())
+        .unwrap();
+    buff
+}
+
+#[test]
+fn file_header_precedes_records_in_v2() {
+    let data = vec![tx_with_id(1), tx_with_id(2)];
+    let headered = encode_headered(&data);
+    assert_eq!(&headered[..4], b"YPBF");
+    assert_eq!(&headered[4..6], &2u16.to_be_bytes());
+    assert_eq!(&headered[6..14], &2u64.to_be_bytes());
+    assert_eq!(headered[14..], encode_all(&data));
+
+    let mut streamed = Vec::new();
+    let mut sink = Codec::BinaryCodec
+        .open_sink(&mut streamed, &headered_options())
+        .unwrap();
+    for tx in &data {
+        sink.push(tx).unwrap();
+    }
+    sink.finish().unwrap();
+    assert_eq!(streamed, headered);
+}
+
+#[test]
+fn parse_accepts_headerless_and_headered_streams() {
+    let data = vec![tx_with_id(1), tx_with_id(2)];
+    for input in [encode_all(&data), encode_headered(&data)] {
+        let parsed = Codec::BinaryCodec.parse(input.as_slice()).unwrap();
+        assert_eq!(parsed, data);
+    }
+    let parsed = Codec::BinaryCodec
+        .parse(encode_headered(&[]).as_slice())
+        .unwrap();
+    assert!(parsed.is_empty());
+
+    let report = binary::scan(encode_headered(&data).as_slice(), &Default::default(), 10).unwrap();
+    assert!(report.is_healthy());
+    assert_eq!(report.records, 2);
+}
+
+#[test]
+fn parse_rejects_unsupported_version() {
+    let mut input = encode_headered(&[tx_with_id(1)]);
+    input[4..6].copy_from_slice(&5u16.to_be_bytes());
+    let err = Codec::BinaryCodec
+        .parse(input.as_slice())
+        .expect_err("future versions should be rejected");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableValue(_),
+            ..
+        }
+    ));
+
+    let report = binary::scan(input.as_slice(), &Default::default(), 10).unwrap();
+    assert!(report.aborted);
+}
+
+#[test]
+fn parse_checks_records_count_of_file_header() {
+    let data = vec![tx_with_id(1), tx_with_id(2)];
+    let mut input = encode_headered(&data);
+    input.truncate(14 + encode_all(&data[..1]).len());
+    let err = Codec::BinaryCodec
+        .parse(input.as_slice())
+        .expect_err("missing records should be detected");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::InvalidFileHeader,
+            ..
+        }
+    ));
+}
+
+fn checksum_options() -> WriteOptions {
+    let mut options = WriteOptions::default();
+    options.binary.checksum = true;
+    options
+}
+
+fn encode_with_checksum(data: &[TxRecord]) -> Vec<u8> {
+    let mut buff = Vec::new();
+    Codec::BinaryCodec
+        .write_with(&mut buff, data, &checksum_options())
+        .unwrap();
+    buff
+}
+
+#[test]
+fn checksum_round_trips_and_is_optional() {
+    let data = vec![
+        tx_with_id(1),
+        TxRecord {
+            tenant: Some("acme".into()),
+            ..tx_with_id(2)
+        },
+    ];
+    let input = encode_with_checksum(&data);
+    assert_eq!(input.len(), encode_all(&data).len() + 2 * 9);
+    assert_eq!(Codec::BinaryCodec.parse(input.as_slice()).unwrap(), data);
+    assert!(
+        binary::scan(input.as_slice(), &Default::default(), 10)
+            .unwrap()
+            .is_healthy()
+    );
+}
+
+#[test]
+fn checksum_mismatch_reports_record_position() {
+    let data = vec![tx_with_id(1), tx_with_id(2)];
+    let mut input = encode_with_checksum(&data);
+    let second = input.len() / 2;
+    // flip a bit of the second record amount
+    input[second + 8 + 8 + 1 + 8 + 8 + 7] ^= 0x01;
+
+    let err = Codec::BinaryCodec
+        .parse(input.as_slice())
+        .expect_err("corruption should be detected");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            context: ParserContext::Position { position },
+            source: ParserError::ChecksumMismatch,
+        } if position == second + 8
+    ));
+
+    let report = binary::scan(input.as_slice(), &Default::default(), 10).unwrap();
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].offset, second as u64);
+    assert!(matches!(
+        report.issues[0].error,
+        ParserError::ChecksumMismatch
+    ));
+}
+
+#[test]
+fn little_endian_record_of_partner_tool_is_read() {
+    // record as laid out by a C struct dump on x86
+    let desc = b"partner";
+    let mut body = Vec::new();
+    body.extend_from_slice(&1u64.to_le_bytes());
+    body.push(1);
+    body.extend_from_slice(&11u64.to_le_bytes());
+    body.extend_from_slice(&22u64.to_le_bytes());
+    body.extend_from_slice(&(-500i64).to_le_bytes());
+    body.extend_from_slice(&1_700_000u64.to_le_bytes());
+    body.push(2);
+    body.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+    body.extend_from_slice(desc);
+    let mut bytes = b"YPBN".to_vec();
+    bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&body);
+
+    let mut options = ParseOptions::default();
+    options.binary.endianness = Endianness::Little;
+    let parsed = Codec::BinaryCodec
+        .parse_with(bytes.as_slice(), &options)
+        .expect("little-endian record should parse");
+    let expected = TxRecord {
+        description: Some("partner".to_string()),
+        ..sample_tx()
+    };
+    assert_eq!(parsed, vec![expected]);
+
+    // big-endian reader sees oversized record
+    assert!(Codec::BinaryCodec.parse(bytes.as_slice()).is_err());
+}
+
+#[test]
+fn little_endian_round_trip_with_header_and_checksum() {
+    let mut tx2 = sample_tx();
+    tx2.id = TxIdType::Numeric(2);
+    tx2.tenant = Some("acme".to_string());
+    let data = vec![sample_tx(), tx2];
+
+    let mut write_options = WriteOptions::default();
+    write_options.binary.version = BinaryVersion::V2;
+    write_options.binary.checksum = true;
+    write_options.binary.endianness = Endianness::Little;
+    let mut bytes = Vec::new();
+    Codec::BinaryCodec
+        .write_with(&mut bytes, &data, &write_options)
+        .expect("little-endian write should succeed");
+    // version and records count of file header
+    assert_eq!(&bytes[4..6], &2u16.to_le_bytes());
+    assert_eq!(&bytes[6..14], &2u64.to_le_bytes());
+
+    let mut options = ParseOptions::default();
+    options.binary.endianness = Endianness::Little;
+    let parsed = Codec::BinaryCodec
+        .parse_with(bytes.as_slice(), &options)
+        .expect("little-endian stream should parse");
+    assert_eq!(parsed, data);
+    let report = binary::scan(bytes.as_slice(), &options.binary, 10).unwrap();
+    assert!(report.is_healthy());
+    assert_eq!(report.records, 2);
+    assert!(
+        !binary::scan(bytes.as_slice(), &Default::default(), 10)
+            .unwrap()
+            .is_healthy()
+    );
+
+    write_options.binary.endianness = Endianness::Big;
+    let mut big = Vec::new();
+    Codec::BinaryCodec
+        .write_with(&mut big, &data, &write_options)
+        .unwrap();
+    assert_ne!(big, bytes);
+    assert_eq!(Codec::BinaryCodec.parse(big.as_slice()).unwrap(), data);
+}
+
+fn varint_options() -> WriteOptions {
+    let mut options = WriteOptions::default();
+    options.binary.version = BinaryVersion::V3;
+    options
+}
+
+#[test]
+fn varint_records_round_trip_and_shrink_output() {
+    let mut data: Vec<TxRecord> = (1..50).map(tx_with_id).collect();
+    data[0].amount = Money::from_minor_units(i64::MIN);
+    data[1].id = TxIdType::Numeric(u64::MAX);
+    data[2].tenant = Some("acme".to_string());
+    let mut varint = Vec::new();
+    Codec::BinaryCodec
+        .write_with(&mut varint, &data, &varint_options())
+        .expect("varint write should succeed");
+    assert_eq!(&varint[..4], b"YPBF");
+    assert_eq!(&varint[4..6], &3u16.to_be_bytes());
+    assert!(varint.len() * 2 < encode_all(&data).len());
+
+    // encoding is picked from file header, no parse options needed
+    let parsed = Codec::BinaryCodec.parse(varint.as_slice()).unwrap();
+    assert_eq!(parsed, data);
+
+    let report = binary::scan(varint.as_slice(), &Default::default(), 10).unwrap();
+    assert!(report.is_healthy());
+    assert_eq!(report.records, data.len());
+}
+
+#[test]
+fn extended_kinds_and_statuses_need_version_4() {
+    let kinds = [TxKind::Refund, TxKind::Fee, TxKind::Reversal];
+    let statuses = [TxStatus::Cancelled, TxStatus::Reversed, TxStatus::Expired];
+    let data: Vec<TxRecord> = kinds
+        .into_iter()
+        .zip(statuses)
+        .zip(1..)
+        .flat_map(|((kind, status), id)| {
+            [
+                TxRecord {
+                    kind,
+                    ..tx_with_id(id)
+                },
+                TxRecord {
+                    status,
+                    ..tx_with_id(id)
+                },
+            ]
+        })
+        .collect();
+    for version in [BinaryVersion::V1, BinaryVersion::V2, BinaryVersion::V3] {
+        let mut options = WriteOptions::default();
+        options.binary.version = version;
+        for tx in &data {
+            let err = Codec::BinaryCodec
+                .write_with(&mut Vec::new(), std::slice::from_ref(tx), &options)
+                .expect_err("older versions have no codes for new kinds and statuses");
+            assert!(matches!(err, AppError::WriteError(_)));
+        }
+    }
+
+    let mut options = WriteOptions::default();
+    options.binary.version = BinaryVersion::LATEST;
+    let mut bytes = Vec::new();
+    Codec::BinaryCodec
+        .write_with(&mut bytes, &data, &options)
+        .expect("version 4 write should succeed");
+    assert_eq!(&bytes[4..6], &4u16.to_be_bytes());
+    assert_eq!(Codec::BinaryCodec.parse(bytes.as_slice()).unwrap(), data);
+}
+
+#[test]
+fn varint_records_keep_checksums_and_recovery() {
+    let data = vec![tx_with_id(1), tx_with_id(2), tx_with_id(3)];
+    let mut options = varint_options();
+    options.binary.checksum = true;
+    let mut bytes = Vec::new();
+    Codec::BinaryCodec
+        .write_with(&mut bytes, &data, &options)
+        .unwrap();
+    assert_eq!(Codec::BinaryCodec.parse(bytes.as_slice()).unwrap(), data);
+
+    // altered description of the second record
+    let second = bytes
+        .windows(b"payment".len())
+        .enumerate()
+        .filter(|(_, w)| w == b"payment")
+        .nth(1)
+        .unwrap()
+        .0;
+    bytes[second] = b'P';
+    let err = Codec::BinaryCodec.parse(bytes.as_slice()).unwrap_err();
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::ChecksumMismatch,
+            ..
+        }
+    ));
+    let lenient = Codec::BinaryCodec
+        .parse_lenient(bytes.as_slice(), &ParseOptions::default())
+        .unwrap();
+    assert_eq!(lenient.records, vec![tx_with_id(1), tx_with_id(3)]);
+}
+
+#[test]
+fn truncated_varint_record_is_rejected() {
+    let mut bytes = Vec::new();
+    Codec::BinaryCodec
+        .write_with(&mut bytes, &[tx_with_id(1)], &varint_options())
+        .unwrap();
+    // record ends right after status, before description length
+    let record_start = 14;
+    bytes.truncate(record_start + 8 + 10);
+    bytes[record_start + 4..record_start + 8].copy_from_slice(&10u32.to_be_bytes());
+    let err = Codec::BinaryCodec.parse(bytes.as_slice()).unwrap_err();
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::IncompleteRecord,
+            ..
+        }
+    ));
+}
+
+fn append_to(existing: Vec<u8>, data: &[TxRecord], options: &WriteOptions) -> (Vec<u8>, u64) {
+    let mut file = Cursor::new(existing);
+    let records = binary::append(&mut file, data, &options.binary).expect("append should succeed");
+    (file.into_inner(), records)
+}
+
+#[test]
+fn append_extends_stream_in_its_own_layout() {
+    let first = vec![tx_with_id(1), tx_with_id(2)];
+    let second = vec![tx_with_id(3)];
+    let all: Vec<TxRecord> = first.iter().chain(&second).cloned().collect();
+    for options in [
+        WriteOptions::default(),
+        headered_options(),
+        varint_options(),
+    ] {
+        let mut existing = Vec::new();
+        Codec::BinaryCodec
+            .write_with(&mut existing, &first, &options)
+            .unwrap();
+        // layout of existing stream wins over requested one
+        let (appended, records) = append_to(existing, &second, &WriteOptions::default());
+        assert_eq!(records, 3);
+
+        let mut expected = Vec::new();
+        Codec::BinaryCodec
+            .write_with(&mut expected, &all, &options)
+            .unwrap();
+        assert_eq!(appended, expected);
+        assert_eq!(Codec::BinaryCodec.parse(appended.as_slice()).unwrap(), all);
+    }
+}
+
+#[test]
+fn append_to_empty_stream_writes_requested_layout() {
+    let data = vec![tx_with_id(1)];
+    let (appended, records) = append_to(Vec::new(), &data, &headered_options());
+    assert_eq!(records, 1);
+    assert_eq!(appended, encode_headered(&data));
+}
+
+#[test]
+fn append_rejects_corrupt_tail_and_leaves_stream_untouched() {
+    let mut truncated = encode_headered(&[tx_with_id(1), tx_with_id(2)]);
+    truncated.truncate(truncated.len() - 3);
+    let mut garbage = encode_all(&[tx_with_id(1)]);
+    garbage.extend_from_slice(b"YPXX\0\0\0\0");
+    let mut miscounted = encode_headered(&[tx_with_id(1)]);
+    miscounted[13] = 5;
+
+    for existing in [truncated, garbage, miscounted] {
+        let mut file = Cursor::new(existing.clone());
+        let err = binary::append(&mut file, &[tx_with_id(3)], &Default::default())
+            .expect_err("corrupt stream should not be appended to");
+        assert!(matches!(err, AppError::ParsingError { .. }));
+        assert_eq!(file.into_inner(), existing);
+    }
+}
+
+#[test]
+fn failed_append_leaves_stream_untouched() {
+    let existing = encode_with(&[tx_with_id(1)], &indexed(headered_options()));
+    // refund needs version 4, it fails after the deposit is encoded
+    let refund = TxRecord {
+        kind: TxKind::Refund,
+        ..tx_with_id(3)
+    };
+    let mut file = Cursor::new(existing.clone());
+    let err = binary::append(&mut file, &[tx_with_id(2), refund], &Default::default())
+        .expect_err("refund can't be appended to version 2 stream");
+    assert!(matches!(err, AppError::WriteError(_)));
+    assert_eq!(file.into_inner(), existing);
+}
+
+fn indexed(mut options: WriteOptions) -> WriteOptions {
+    options.binary.index = true;
+    options
+}
+
+fn encode_with(data: &[TxRecord], options: &WriteOptions) -> Vec<u8> {
+    let mut out = Vec::new();
+    Codec::BinaryCodec
+        .write_with(&mut out, data, options)
+        .expect("write should succeed");
+    out
+}
+
+#[test]
+fn indexed_stream_gives_random_access_to_records() {
+    let data: Vec<TxRecord> = [5, 3, 9, 3].into_iter().map(tx_with_id).collect();
+    for options in [
+        WriteOptions::default(),
+        headered_options(),
+        varint_options(),
+    ] {
+        let plain = encode_with(&data, &options);
+        let bytes = encode_with(&data, &indexed(options));
+        assert!(bytes.starts_with(&plain));
+        assert_eq!(bytes.len(), plain.len() + 24 + 16 * data.len());
+        // footer is stepped over by sequential reading
+        assert_eq!(Codec::BinaryCodec.parse(bytes.as_slice()).unwrap(), data);
+
+        let mut reader = IndexedReader::open(Cursor::new(bytes), &Default::default()).unwrap();
+        assert_eq!(reader.len(), 4);
+        assert_eq!(reader.get(2).unwrap(), Some(data[2].clone()));
+        assert_eq!(reader.get(0).unwrap(), Some(data[0].clone()));
+        assert_eq!(reader.get(4).unwrap(), None);
+        assert_eq!(
+            reader.find(TxIdType::Numeric(9)).unwrap(),
+            Some(data[2].clone())
+        );
+        assert_eq!(reader.find(TxIdType::Numeric(7)).unwrap(), None);
+    }
+
+    let mut reader = IndexedReader::open(
+        Cursor::new(encode_with(&[], &indexed(headered_options()))),
+        &Default::default(),
+    )
+    .unwrap();
+    assert!(reader.is_empty());
+    assert_eq!(reader.get(0).unwrap(), None);
+}
+
+#[test]
+fn uuid_ids_round_trip_and_are_found_by_index() {
+    let uuid = TxIdType::Uuid(0x123e4567_e89b_12d3_a456_426614174000);
+    let data = vec![
+        TxRecord {
+            id: TxIdType::Uuid(1),
+            ..tx_with_id(0)
+        },
+        tx_with_id(0),
+        TxRecord {
+            id: uuid,
+            ..tx_with_id(0)
+        },
+    ];
+    for options in [WriteOptions::default(), varint_options()] {
+        let bytes = encode_with(&data, &indexed(options));
+        assert_eq!(Codec::BinaryCodec.parse(bytes.as_slice()).unwrap(), data);
+
+        // uuids share index entries with numeric id 0
+        let mut reader = IndexedReader::open(Cursor::new(bytes), &Default::default()).unwrap();
+        assert_eq!(reader.find(uuid).unwrap(), Some(data[2].clone()));
+        assert_eq!(
+            reader.find(TxIdType::Numeric(0)).unwrap(),
+            Some(data[1].clone())
+        );
+        assert_eq!(reader.find(TxIdType::Uuid(2)).unwrap(), None);
+    }
+}
+
+#[test]
+fn index_is_checked_and_required_for_random_access() {
+    let data = vec![tx_with_id(1), tx_with_id(2)];
+    let invalid_index = |err: AppError| {
+        matches!(
+            err,
+            AppError::ParsingError {
+                source: ParserError::InvalidIndex,
+                ..
+            }
+        )
+    };
+    let unindexed = encode_headered(&data);
+    let err = IndexedReader::open(Cursor::new(unindexed), &Default::default())
+        .err()
+        .expect("stream without index should be rejected");
+    assert!(invalid_index(err));
+
+    let bytes = encode_with(&data, &indexed(headered_options()));
+    let mut truncated = bytes.clone();
+    truncated.remove(bytes.len() - 13);
+    let err = Codec::BinaryCodec.parse(truncated.as_slice()).unwrap_err();
+    assert!(invalid_index(err));
+    let err = IndexedReader::open(Cursor::new(truncated), &Default::default())
+        .err()
+        .expect("truncated index should be rejected");
+    assert!(invalid_index(err));
+
+    let mut trailing = bytes;
+    trailing.push(0);
+    assert!(invalid_index(
+        Codec::BinaryCodec.parse(trailing.as_slice()).unwrap_err()
+    ));
+}
+
+#[test]
+fn append_extends_footer_index() {
+    let first = vec![tx_with_id(1), tx_with_id(2)];
+    let second = vec![tx_with_id(3), tx_with_id(4)];
+    let all: Vec<TxRecord> = first.iter().chain(&second).cloned().collect();
+    for options in [WriteOptions::default(), varint_options()] {
+        let options = indexed(options);
+        let existing = encode_with(&first, &options);
+        let (appended, records) = append_to(existing, &second, &WriteOptions::default());
+        assert_eq!(records, 4);
+        assert_eq!(appended, encode_with(&all, &options));
+
+        let mut reader = IndexedReader::open(Cursor::new(appended), &Default::default()).unwrap();
+        assert_eq!(
+            reader.find(TxIdType::Numeric(4)).unwrap(),
+            Some(all[3].clone())
+        );
+    }
+}
+
+#[test]
+fn scan_steps_over_footer_index() {
+    let bytes = encode_with(
+        &[tx_with_id(1), tx_with_id(2)],
+        &indexed(headered_options()),
+    );
+    let report = binary::scan(bytes.as_slice(), &Default::default(), 10).unwrap();
+    assert!(report.is_healthy());
+    assert_eq!(report.records, 2);
+    assert_eq!(report.bytes, bytes.len() as u64);
+}
+
+#[test]
+fn scan_checks_footer_index_offsets() {
+    let bytes = encode_with(
+        &[tx_with_id(1), tx_with_id(2)],
+        &indexed(headered_options()),
+    );
+    // offsets of the two entries preceding the trailer
+    let second = bytes.len() - 12 - 8;
+    let first = second - 16;
+    let index_issue = |bytes: &[u8]| {
+        let report = binary::scan(bytes, &Default::default(), 10).unwrap();
+        assert_eq!(report.records, 2);
+        matches!(
+            report.issues[..],
+            [binary::ScanIssue {
+                error: ParserError::InvalidIndex,
+                ..
+            }]
+        )
+    };
+
+    let mut not_increasing = bytes.clone();
+    let first_offset = not_increasing[first..first + 8].to_vec();
+    not_increasing[second..second + 8].copy_from_slice(&first_offset);
+    assert!(index_issue(&not_increasing));
+
+    let mut shifted = bytes.clone();
+    shifted[second + 7] += 1;
+    assert!(index_issue(&shifted));
+
+    let mut truncated = bytes.clone();
+    truncated.truncate(bytes.len() - 1);
+    assert!(index_issue(&truncated));
+}
+
+#[test]
+fn strict_parser_rejects_partial_signature_after_last_record() {
+    let strict = ParseOptions {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    };
+    let data = vec![tx_with_id(1), tx_with_id(2)];
+    for tail in [&b"YPB"[..], b"Y", b"\x00\x01"] {
+        let mut input = encode_all(&data);
+        input.extend_from_slice(tail);
+        // lenient parser drops bytes too few to hold a signature
+        assert_eq!(Codec::BinaryCodec.parse(input.as_slice()).unwrap(), data);
+
+        let err = Codec::BinaryCodec
+            .parse_with(input.as_slice(), &strict)
+            .unwrap_err();
+        match err {
+            AppError::ParsingError {
+                source: ParserError::IncompleteRecord,
+                context: ParserContext::Position { position },
+            } => assert_eq!(position, input.len()),
+            other => panic!("unexpected error {}", other),
+        }
+    }
+
+    for tail in [&b"\n\n"[..], b"\n\n\n\n", b" \r\n\t\n\n  \n\n"] {
+        let mut input = encode_all(&data);
+        input.extend_from_slice(tail);
+        assert_eq!(Codec::BinaryCodec.parse(input.as_slice()).unwrap(), data);
+        assert_eq!(
+            Codec::BinaryCodec
+                .parse_with(input.as_slice(), &strict)
+                .unwrap(),
+            data
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use parser::codecs::base::Codec;
+use parser::codecs::binary_v2::BinaryV2Options;
+use parser::codecs::options::WriteOptions;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64, ts: u64, description: &str) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(-500),
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Pending,
+        description: Some(description.to_string()),
+        ..Default::default()
+    }
+}
+
+fn daily_records() -> Vec<TxRecord> {
+    (0..1000)
+        .map(|i| {
+            let description = if i % 2 == 0 { "salary" } else { "rent" };
+            sample_tx(1_000_000_000 + i, 1_704_276_930_000 + i * 250, description)
+        })
+        .collect()
+}
+
+fn write_v2(data: &[TxRecord], dedup_descriptions: bool) -> Vec<u8> {
+    let options = WriteOptions {
+        binary_v2: BinaryV2Options {
+            dedup_descriptions,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut buff = Vec::new();
+    Codec::BinaryV2Codec
+        .write_with(&mut buff, data, &options)
+        .expect("binary v2 write should succeed");
+    buff
+}
+
+#[test]
+fn binary_v2_round_trip_with_and_without_dedup() {
+    let mut data = daily_records();
+    // non-monotonic values and optional fields
+    data.push(sample_tx(5, 0, "ünïcode"));
+    data.push(TxRecord {
+        id: TxIdType::Numeric(u64::MAX),
+        amount: Money::from_minor_units(i64::MIN),
+        description: None,
+        tenant: Some("acme".to_string()),
+        ..sample_tx(0, u64::MAX, "")
+    });
+    for dedup in [false, true] {
+        let parsed = Codec::BinaryV2Codec
+            .parse(write_v2(&data, dedup).as_slice())
+            .expect("binary v2 parse should succeed");
+        assert_eq!(parsed, data);
+    }
+}
+
+#[test]
+fn binary_v2_iban_accounts() {
+    let data = vec![
+        sample_tx(1, 1, "numeric"),
+        TxRecord {
+            to: AccountType::Iban("DE89370400440532013000".into()),
+            ..sample_tx(2, 2, "iban")
+        },
+    ];
+    // batch writing turns the flag on by itself
+    let buff = write_v2(&data, false);
+    assert_eq!(buff[4] & 2, 2);
+    let parsed = Codec::BinaryV2Codec
+        .parse(buff.as_slice())
+        .expect("binary v2 parse should succeed");
+    assert_eq!(parsed, data);
+
+    // streaming needs it upfront
+    let mut sink = Codec::BinaryV2Codec
+        .open_sink(Vec::new(), &WriteOptions::default())
+        .expect("sink should open");
+    sink.push(&data[0])
+        .expect("numeric accounts are always allowed");
+    let err = sink.push(&data[1]).expect_err("iban needs the flag");
+    assert!(matches!(err, AppError::WriteError(_)));
+}
+
+#[test]
+fn binary_v2_uuid_ids() {
+    let data = vec![
+        sample_tx(10, 1, "numeric"),
+        TxRecord {
+            id: TxIdType::Uuid(0x123e4567_e89b_12d3_a456_426614174000),
+            ..sample_tx(0, 2, "uuid")
+        },
+        sample_tx(11, 3, "numeric"),
+    ];
+    let buff = write_v2(&data, false);
+    assert_eq!(buff[4] & 4, 4);
+    let parsed = Codec::BinaryV2Codec
+        .parse(buff.as_slice())
+        .expect("binary v2 parse should succeed");
+    assert_eq!(parsed, data);
+
+    let mut sink = Codec::BinaryV2Codec
+        .open_sink(Vec::new(), &WriteOptions::default())
+        .expect("sink should open");
+    let err = sink.push(&data[1]).expect_err("uuid needs the flag");
+    assert!(matches!(err, AppError::WriteError(_)));
+}
+
+#[test]
+fn binary_v2_is_smaller_than_v1() {
+    let data = daily_records();
+    let mut v1 = Vec::new();
+    Codec::BinaryCodec
+        .write(&mut v1, &data)
+        .expect("binary write should succeed");
+    let plain = write_v2(&data, false);
+    let dedup = write_v2(&data, true);
+    assert!(plain.len() * 3 < v1.len());
+    assert!(dedup.len() < plain.len());
+    assert_eq!(&dedup[..5], b"YPB2\x01");
+}
+
+#[test]
+fn binary_v2_rejects_broken_input() {
+    let buff = write_v2(&daily_records()[..2], true);
+    let err = Codec::BinaryV2Codec
+        .parse(&buff[..buff.len() - 1])
+        .expect_err("truncated record must fail");
+    assert!(matches!(err, AppError::ParsingError { .. }));
+
+    // unknown flags
+    let mut broken = buff.clone();
+    broken[4] = 0x80;
+    assert!(Codec::BinaryV2Codec.parse(broken.as_slice()).is_err());
+
+    // reference to description not met yet
+    let mut broken = write_v2(&[sample_tx(1, 1, "")], true);
+    let reference = broken.len() - 3;
+    broken[reference] = 2;
+    assert!(Codec::BinaryV2Codec.parse(broken.as_slice()).is_err());
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "bincode")]
+
+use parser::codecs::base::Codec;
+use parser::domain::tx::{
+    AccountType, ExchangeRate, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp,
+};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64, description: &str, tenant: Option<&str>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(i64::MIN),
+        ts: TxTimestamp::from_millis(u64::MAX),
+        status: TxStatus::Failure,
+        description: Some(description.to_string()),
+        tenant: tenant.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn write_bincode(data: &[TxRecord]) -> Vec<u8> {
+    let mut buff = Vec::new();
+    Codec::BincodeCodec
+        .write(&mut buff, data)
+        .expect("bincode write should succeed");
+    buff
+}
+
+#[test]
+fn bincode_round_trip() {
+    let data = vec![
+        TxRecord {
+            kind: TxKind::Reversal,
+            status: TxStatus::Expired,
+            ..sample_tx(1, "with \"quotes\"\nand newline", None)
+        },
+        TxRecord {
+            description: None,
+            ..sample_tx(u64::MAX, "", Some(""))
+        },
+        TxRecord {
+            id: TxIdType::Uuid(0x123e4567_e89b_12d3_a456_426614174000),
+            from: AccountType::Iban("DE89370400440532013000".into()),
+            ..sample_tx(3, "ünïcode", Some("acme"))
+        },
+        TxRecord {
+            fee: Some(Money::from_minor_units(i64::MAX)),
+            reference: Some("E2E-1".into()),
+            original_amount: Some(Money::new(15_000, 0)),
+            exchange_rate: Some(ExchangeRate::new(7_217, 4)),
+            ..sample_tx(4, "fee", None)
+        },
+    ];
+    let parsed = Codec::BincodeCodec
+        .parse(write_bincode(&data).as_slice())
+        .expect("bincode parse should succeed");
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn bincode_layout() {
+    let buff = write_bincode(&[sample_tx(7, "ab", None)]);
+    // count, id with variant, kind, from and to with variants, amount with scale, fee,
+    // original amount and exchange rate tags, ts, status, description, tenant and reference tags
+    assert_eq!(
+        buff.len(),
+        8 + (4 + 8) + 4 + (4 + 8) * 2 + (8 + 1) + 1 + 1 + 1 + 8 + 4 + 8 + 2 + 1 + 1
+    );
+    assert_eq!(&buff[..8], &1u64.to_le_bytes());
+    assert_eq!(&buff[8..12], &0u32.to_le_bytes());
+    assert_eq!(&buff[12..20], &7u64.to_le_bytes());
+    assert_eq!(&buff[20..24], &1u32.to_le_bytes());
+    assert_eq!(buff.last(), Some(&0));
+}
+
+#[test]
+fn bincode_rejects_truncated_and_trailing_input() {
+    let buff = write_bincode(&[sample_tx(1, "description", None)]);
+    for broken in [&buff[..buff.len() - 1], &buff[..4]] {
+        let err = Codec::BincodeCodec
+            .parse(broken)
+            .expect_err("truncated dump must fail");
+        assert!(matches!(
+            err,
+            AppError::ParsingError {
+                source: parser::codecs::errors::ParserError::IncompleteRecord,
+                ..
+            }
+        ));
+    }
+
+    let mut trailing = buff.clone();
+    trailing.push(0);
+    assert!(Codec::BincodeCodec.parse(trailing.as_slice()).is_err());
+}
+
+#[test]
+fn bincode_rejects_unknown_variant() {
+    let mut buff = write_bincode(&[sample_tx(1, "d", None)]);
+    buff[20] = 6;
+    let err = Codec::BincodeCodec
+        .parse(buff.as_slice())
+        .expect_err("unknown kind must fail");
+    assert!(matches!(err, AppError::ParsingError { .. }));
+}
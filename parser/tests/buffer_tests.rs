@@ -0,0 +1,86 @@
+use std::io::{self, Read, Write};
+
+use parser::codecs::base::Codec;
+use parser::codecs::options::{BufferSize, ParseOptions, WriteOptions};
+use parser::domain::tx::{TxIdType, TxRecord};
+
+// counts calls to underlying stream
+struct CountingStream {
+    data: io::Cursor<Vec<u8>>,
+    calls: usize,
+}
+
+impl Read for CountingStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.calls += 1;
+        self.data.read(buf)
+    }
+}
+
+impl Write for CountingStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.calls += 1;
+        self.data.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn records(count: u64) -> Vec<TxRecord> {
+    (0..count)
+        .map(|id| TxRecord {
+            id: TxIdType::Numeric(id),
+            description: Some("payment".to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[test]
+fn write_with_buffer_size_batches_writes() {
+    let data = records(200);
+    for codec in [Codec::TextCodec, Codec::CsvCodec, Codec::BinaryCodec] {
+        let mut stream = CountingStream {
+            data: io::Cursor::new(Vec::new()),
+            calls: 0,
+        };
+        let options = WriteOptions {
+            buffer_size: BufferSize::Fixed(1 << 20),
+            ..Default::default()
+        };
+        codec
+            .write_with(&mut stream, &data, &options)
+            .expect("write should succeed");
+        assert_eq!(stream.calls, 1, "{:?} shall write once", codec);
+
+        let bytes = stream.data.into_inner();
+        let mut stream = CountingStream {
+            data: io::Cursor::new(bytes),
+            calls: 0,
+        };
+        let options = ParseOptions {
+            buffer_size: BufferSize::Fixed(1 << 20),
+            ..Default::default()
+        };
+        let parsed = codec
+            .parse_with(&mut stream, &options)
+            .expect("parse should succeed");
+        assert_eq!(parsed, data);
+        // whole input and EOF
+        assert_eq!(stream.calls, 2, "{:?} shall read in one chunk", codec);
+    }
+}
+
+#[test]
+fn buffer_size_resolution() {
+    assert_eq!(BufferSize::Adaptive.bytes(), BufferSize::STREAM_BUFFER_SIZE);
+    assert_eq!(BufferSize::Fixed(0).bytes(), 1);
+
+    let path = std::env::temp_dir().join(format!("rustyapa_{}_buffer", std::process::id()));
+    let f = std::fs::File::create(&path).expect("temp file should be created");
+    assert_eq!(
+        BufferSize::adaptive_for(&f),
+        BufferSize::Fixed(BufferSize::FILE_BUFFER_SIZE)
+    );
+}
@@ -0,0 +1,111 @@
+use parser::codecs::base::Codec;
+use parser::codecs::errors::ParserError;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxStatus};
+use parser::errors::AppError;
+
+const STATEMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <BkToCstmrStmt>
+    <GrpHdr><MsgId>MSG-1</MsgId></GrpHdr>
+    <Stmt>
+      <Id>STMT-1</Id>
+      <Acct><Id><Othr><Id>4242</Id></Othr></Id></Acct>
+      <!-- incoming salary -->
+      <Ntry>
+        <NtryRef>101</NtryRef>
+        <Amt Ccy="EUR">1500.50</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><DtTm>2024-01-03T10:15:30+01:00</DtTm></BookgDt>
+        <AddtlNtryInf>Salary &amp; bonus</AddtlNtryInf>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="JPY">700</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts><Cd>PDNG</Cd></Sts>
+        <BookgDt><Dt>2024-01-04</Dt></BookgDt>
+        <NtryDtls><TxDtls><Refs><EndToEndId>E2E-7</EndToEndId></Refs><RmtInf><Ustrd>card</Ustrd><Ustrd>payment</Ustrd></RmtInf></TxDtls></NtryDtls>
+      </Ntry>
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#;
+
+#[test]
+fn parse_statement_entries() {
+    let records = Codec::CamtCodec
+        .parse(STATEMENT.as_bytes())
+        .expect("statement should parse");
+    assert_eq!(records.len(), 2);
+
+    let salary = &records[0];
+    assert_eq!(salary.id, TxIdType::Numeric(101));
+    assert_eq!(salary.kind, TxKind::Deposit);
+    assert_eq!(salary.from, AccountType::Numeric(0));
+    assert_eq!(salary.to, AccountType::Numeric(4242));
+    assert_eq!(salary.amount, Money::new(150_050, 2));
+    assert_eq!(salary.status, TxStatus::Success);
+    assert_eq!(salary.ts.millis(), 1_704_273_330_000);
+    assert_eq!(salary.description.as_deref(), Some("Salary & bonus"));
+    assert_eq!(salary.reference, None);
+
+    let card = &records[1];
+    assert_eq!(card.id, TxIdType::Numeric(2));
+    assert_eq!(card.kind, TxKind::Withdrawal);
+    assert_eq!(card.from, AccountType::Numeric(4242));
+    // yen have no minor units
+    assert_eq!(card.amount, Money::new(700, 0));
+    assert_eq!(card.amount.to_string(), "700");
+    assert_eq!(card.status, TxStatus::Pending);
+    assert_eq!(card.ts.millis(), 1_704_326_400_000);
+    assert_eq!(card.description.as_deref(), Some("card payment"));
+    assert_eq!(card.reference.as_deref(), Some("E2E-7"));
+}
+
+#[test]
+fn parse_prefers_iban_of_statement_account() {
+    let input = STATEMENT.replace(
+        "<Id><Othr><Id>4242</Id></Othr></Id>",
+        "<Id><IBAN>DE89 3704 0044 0532 0130 00</IBAN></Id>",
+    );
+    let records = Codec::CamtCodec
+        .parse(input.as_bytes())
+        .expect("statement should parse");
+    let iban = AccountType::Iban("DE89370400440532013000".into());
+    assert_eq!(records[0].to, iban);
+    assert_eq!(records[1].from, iban);
+}
+
+#[test]
+fn parse_rejects_malformed_xml_and_entries() {
+    let err = Codec::CamtCodec
+        .parse("<Document><Stmt></Document>".as_bytes())
+        .expect_err("unbalanced tags should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableValue(_),
+            ..
+        }
+    ));
+
+    let input = STATEMENT.replace("<CdtDbtInd>CRDT</CdtDbtInd>", "<CdtDbtInd>XXX</CdtDbtInd>");
+    let err = Codec::CamtCodec
+        .parse(input.as_bytes())
+        .expect_err("unknown credit/debit indicator should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableValue(_),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn camt_is_read_only() {
+    let err = Codec::CamtCodec
+        .write(&mut Vec::new(), &[])
+        .expect_err("camt writer is not supported");
+    assert!(matches!(err, AppError::WriteError(_)));
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "capnp")]
+
+use parser::codecs::base::Codec;
+use parser::codecs::capnp::SCHEMA;
+use parser::domain::tx::{
+    AccountType, ExchangeRate, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp,
+};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64, description: &str, tenant: Option<&str>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Withdrawal,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(0),
+        amount: Money::from_minor_units(-500),
+        ts: TxTimestamp::from_millis(1_704_276_930_000),
+        status: TxStatus::Pending,
+        description: Some(description.to_string()),
+        tenant: tenant.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn write_capnp(data: &[TxRecord]) -> Vec<u8> {
+    let mut buff = Vec::new();
+    Codec::CapnpCodec
+        .write(&mut buff, data)
+        .expect("capnp write should succeed");
+    buff
+}
+
+#[test]
+fn capnp_round_trip() {
+    let data = vec![
+        sample_tx(1, "seven b", None),
+        sample_tx(2, "", Some("acme")),
+        sample_tx(3, "ünïcode description", None),
+        TxRecord {
+            from: AccountType::Iban("GB82WEST12345698765432".into()),
+            to: AccountType::Iban("DE89370400440532013000".into()),
+            ..sample_tx(4, "iban", None)
+        },
+        TxRecord {
+            id: TxIdType::Uuid(0x123e4567_e89b_12d3_a456_426614174000),
+            ..sample_tx(5, "uuid", None)
+        },
+        TxRecord {
+            fee: Some(Money::from_minor_units(-25)),
+            reference: Some("E2E-1".into()),
+            original_amount: Some(Money::new(15_000, 0)),
+            exchange_rate: Some(ExchangeRate::new(7_217, 4)),
+            ..sample_tx(6, "fee", None)
+        },
+    ];
+    let buff = write_capnp(&data);
+    assert_eq!(0, buff.len() % 8);
+    let parsed = Codec::CapnpCodec
+        .parse(buff.as_slice())
+        .expect("capnp parse should succeed");
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn capnp_message_layout() {
+    let buff = write_capnp(&[sample_tx(7, "", None)]);
+    // segment table, root pointer, list pointer, tag, 16 words of record, empty text
+    assert_eq!(&buff[..8], &[0, 0, 0, 0, 20, 0, 0, 0]);
+    assert_eq!(buff.len(), 8 + 20 * 8);
+    // record id is the first data word of the first element
+    assert_eq!(&buff[8 + 3 * 8..8 + 4 * 8], &7u64.to_le_bytes());
+    assert!(SCHEMA.contains("struct TxRecord"));
+}
+
+#[test]
+fn capnp_many_messages() {
+    let data: Vec<TxRecord> = (0..70_000).map(|id| sample_tx(id, "d", None)).collect();
+    let parsed = Codec::CapnpCodec
+        .parse(write_capnp(&data).as_slice())
+        .expect("capnp parse should succeed");
+    assert_eq!(parsed.len(), data.len());
+    assert_eq!(parsed.last(), data.last());
+}
+
+#[test]
+fn capnp_rejects_truncated_and_invalid_input() {
+    let buff = write_capnp(&[sample_tx(1, "description", None)]);
+    let err = Codec::CapnpCodec
+        .parse(&buff[..buff.len() - 8])
+        .expect_err("truncated segment must fail");
+    assert!(matches!(err, AppError::ParsingError { .. }));
+
+    // kind enum value out of range
+    let mut broken = buff.clone();
+    broken[8 + 8 * 8] = 9;
+    let err = Codec::CapnpCodec
+        .parse(broken.as_slice())
+        .expect_err("unknown kind must fail");
+    assert!(matches!(err, AppError::ParsingError { .. }));
+}
+
+#[test]
+fn capnp_empty_input() {
+    assert!(write_capnp(&[]).is_empty());
+    let parsed = Codec::CapnpCodec
+        .parse(&b""[..])
+        .expect("empty stream has no messages");
+    assert!(parsed.is_empty());
+}
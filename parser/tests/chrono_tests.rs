@@ -0,0 +1,20 @@
+#![cfg(feature = "chrono")]
+
+use chrono::{DateTime, TimeZone, Utc};
+use parser::domain::tx::TxTimestamp;
+
+#[test]
+fn timestamp_converts_to_and_from_chrono() {
+    let dt = Utc.with_ymd_and_hms(2023, 11, 14, 22, 13, 20).unwrap();
+    let ts = TxTimestamp::try_from(dt).expect("date-time after epoch should convert");
+    assert_eq!(ts.millis(), 1_700_000_000_000);
+    assert_eq!(DateTime::<Utc>::try_from(ts).unwrap(), dt);
+    assert_eq!(TxTimestamp::parse_rfc3339(&dt.to_rfc3339()).unwrap(), ts);
+}
+
+#[test]
+fn out_of_range_conversions_fail() {
+    let before_epoch = Utc.with_ymd_and_hms(1969, 12, 31, 23, 59, 59).unwrap();
+    assert!(TxTimestamp::try_from(before_epoch).is_err());
+    assert!(DateTime::<Utc>::try_from(TxTimestamp::from_millis(u64::MAX)).is_err());
+}
@@ -0,0 +1,125 @@
+use parser::codecs::base::Codec;
+use parser::codecs::options::ParseOptions;
+use parser::codecs::quarantine::QuarantineWriter;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64, description: &str, tenant: Option<&str>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Deposit,
+        from: AccountType::Numeric(0),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(-500),
+        ts: TxTimestamp::from_millis(1_700_000 + id),
+        status: TxStatus::Success,
+        description: Some(description.to_string()),
+        tenant: tenant.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn write_columnar(data: &[TxRecord]) -> Vec<u8> {
+    let mut buff = Vec::new();
+    Codec::ColumnarBinaryCodec
+        .write(&mut buff, data)
+        .expect("columnar write should succeed");
+    buff
+}
+
+#[test]
+fn columnar_round_trip_across_blocks() {
+    let data: Vec<TxRecord> = (0..10_000)
+        .map(|id| match id % 3 {
+            0 => TxRecord {
+                description: None,
+                ..sample_tx(id, "", None)
+            },
+            1 => sample_tx(id, "ünïcode", Some("acme")),
+            _ => sample_tx(id, "payment", Some("")),
+        })
+        .collect();
+    let buff = write_columnar(&data);
+    assert_eq!(&buff[..4], b"YPBC");
+    let parsed = Codec::ColumnarBinaryCodec
+        .parse(buff.as_slice())
+        .expect("columnar parse should succeed");
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn columnar_round_trip_with_iban_accounts() {
+    let data = vec![
+        sample_tx(1, "numeric", None),
+        TxRecord {
+            from: AccountType::Iban("GB82WEST12345698765432".into()),
+            ..sample_tx(2, "iban", None)
+        },
+    ];
+    let buff = write_columnar(&data);
+    // iban columns follow only in blocks which have ibans
+    let numeric = [sample_tx(1, "numeric", None), sample_tx(2, "iban", None)];
+    assert!(buff.len() > write_columnar(&numeric).len());
+    let parsed = Codec::ColumnarBinaryCodec
+        .parse(buff.as_slice())
+        .expect("columnar parse should succeed");
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn columnar_round_trip_with_uuid_ids() {
+    let data = vec![
+        TxRecord {
+            id: TxIdType::Uuid(0x123e4567_e89b_12d3_a456_426614174000),
+            ..sample_tx(0, "uuid", None)
+        },
+        sample_tx(2, "numeric", None),
+    ];
+    let parsed = Codec::ColumnarBinaryCodec
+        .parse(write_columnar(&data).as_slice())
+        .expect("columnar parse should succeed");
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn columnar_block_layout() {
+    let buff = write_columnar(&[sample_tx(1, "a", None), sample_tx(2, "bc", None)]);
+    // header, fixed columns, description lengths and bytes, tenant lengths
+    assert_eq!(buff.len(), 12 + 2 * 42 + 2 * 4 + 3 + 2 * 4);
+    assert_eq!(&buff[4..8], &2u32.to_be_bytes());
+    // ids column goes first
+    assert_eq!(&buff[12..20], &1u64.to_be_bytes());
+    assert_eq!(&buff[20..28], &2u64.to_be_bytes());
+}
+
+#[test]
+fn columnar_rejects_bad_magic_and_truncated_block() {
+    let buff = write_columnar(&[sample_tx(1, "description", None)]);
+    let err = Codec::ColumnarBinaryCodec
+        .parse(&buff[..buff.len() - 1])
+        .expect_err("truncated block must fail");
+    assert!(matches!(err, AppError::ParsingError { .. }));
+
+    let mut broken = buff.clone();
+    broken[0] = b'X';
+    let err = Codec::ColumnarBinaryCodec
+        .parse(broken.as_slice())
+        .expect_err("bad magic must fail");
+    assert!(matches!(err, AppError::ParsingError { .. }));
+}
+
+#[test]
+fn columnar_quarantines_malformed_block() {
+    let mut bad = write_columnar(&[sample_tx(1, "bad", None)]);
+    // kind code of the only record
+    bad[12 + 8] = 9;
+    let mut input = bad;
+    input.extend(write_columnar(&[sample_tx(2, "good", None)]));
+
+    let mut quarantine = QuarantineWriter::new(Vec::new());
+    let records = Codec::ColumnarBinaryCodec
+        .parse_quarantined(input.as_slice(), &ParseOptions::default(), &mut quarantine)
+        .expect("malformed block should be quarantined");
+    assert_eq!(records, vec![sample_tx(2, "good", None)]);
+    assert_eq!(quarantine.count(), 1);
+}
@@ -0,0 +1,322 @@
+use std::path::PathBuf;
+
+use parser::codecs::base::Codec;
+use parser::codecs::base::TxFieldKey;
+use parser::compare::{
+    CompareOptions, EquivalenceOptions, FieldDiff, RecordDiff, Tolerances, Unmatched,
+    assert_equivalent, compare, compare_with, diff, fuzzy_match,
+};
+use parser::domain::key::RecordKey;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(100 * id as i64),
+        ts: TxTimestamp::from_millis(1_700_000 + id),
+        status: TxStatus::Success,
+        description: Some(format!("payment {}", id)),
+        ..Default::default()
+    }
+}
+
+fn write_fixture(name: &str, codec: &Codec, data: &[TxRecord]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rustyapa_{}_{}", std::process::id(), name));
+    let mut f = std::fs::File::create(&path).expect("fixture file should be created");
+    codec
+        .write(&mut f, data)
+        .expect("fixture should be written");
+    path
+}
+
+#[test]
+fn same_records_in_different_formats_are_equivalent() {
+    let data = vec![sample_tx(1), sample_tx(2), sample_tx(3)];
+    let csv = write_fixture("eq_a.csv", &Codec::CsvCodec, &data);
+    let mut reversed = data.clone();
+    reversed.reverse();
+    let bin = write_fixture("eq_b.bin", &Codec::BinaryCodec, &reversed);
+
+    let report = assert_equivalent(
+        &csv,
+        &Codec::CsvCodec,
+        &bin,
+        &Codec::BinaryCodec,
+        &EquivalenceOptions::default(),
+    )
+    .expect("both files should be read");
+    assert!(report.is_equivalent());
+    assert_eq!(report.records_a, 3);
+    assert_eq!(report.records_b, 3);
+}
+
+#[test]
+fn lost_and_extra_records_are_reported() {
+    let csv = write_fixture(
+        "lost_a.csv",
+        &Codec::CsvCodec,
+        &[sample_tx(1), sample_tx(2), sample_tx(2)],
+    );
+    let txt = write_fixture(
+        "lost_b.txt",
+        &Codec::TextCodec,
+        &[sample_tx(2), sample_tx(3)],
+    );
+
+    let report = assert_equivalent(
+        &csv,
+        &Codec::CsvCodec,
+        &txt,
+        &Codec::TextCodec,
+        &EquivalenceOptions::default(),
+    )
+    .expect("both files should be read");
+    assert!(!report.is_equivalent());
+    assert_eq!(report.mismatches, 3);
+    assert_eq!(report.only_in_a, vec![sample_tx(1), sample_tx(2)]);
+    assert_eq!(report.only_in_b, vec![sample_tx(3)]);
+
+    let opts = EquivalenceOptions {
+        ignore_multiplicity: true,
+        max_examples: 0,
+        ..Default::default()
+    };
+    let report = assert_equivalent(&csv, &Codec::CsvCodec, &txt, &Codec::TextCodec, &opts)
+        .expect("both files should be read");
+    assert_eq!(report.mismatches, 2);
+    assert!(report.only_in_a.is_empty());
+}
+
+#[test]
+fn examples_are_capped_and_kept_in_file_order() {
+    let csv = write_fixture(
+        "cap_a.csv",
+        &Codec::CsvCodec,
+        &[sample_tx(5), sample_tx(1), sample_tx(4), sample_tx(2)],
+    );
+    let bin = write_fixture(
+        "cap_b.bin",
+        &Codec::BinaryCodec,
+        &[sample_tx(1), sample_tx(9), sample_tx(8), sample_tx(8)],
+    );
+    let opts = EquivalenceOptions {
+        max_examples: 2,
+        ..Default::default()
+    };
+    let report = assert_equivalent(&csv, &Codec::CsvCodec, &bin, &Codec::BinaryCodec, &opts)
+        .expect("both files should be read");
+    assert_eq!((report.records_a, report.records_b), (4, 4));
+    assert_eq!(report.mismatches, 6);
+    assert_eq!(report.only_in_a, vec![sample_tx(5), sample_tx(4)]);
+    assert_eq!(report.only_in_b, vec![sample_tx(9), sample_tx(8)]);
+}
+
+#[test]
+fn records_are_matched_by_configured_key() {
+    let mut updated = sample_tx(2);
+    updated.status = TxStatus::Failure;
+    let csv = write_fixture("key_a.csv", &Codec::CsvCodec, &[sample_tx(1), sample_tx(2)]);
+    let bin = write_fixture("key_b.bin", &Codec::BinaryCodec, &[sample_tx(1), updated]);
+
+    let compare = |key: RecordKey| {
+        let opts = EquivalenceOptions {
+            key,
+            ..Default::default()
+        };
+        assert_equivalent(&csv, &Codec::CsvCodec, &bin, &Codec::BinaryCodec, &opts)
+            .expect("both files should be read")
+    };
+    assert_eq!(compare(RecordKey::FullRecord).mismatches, 2);
+    assert!(compare(RecordKey::IdOnly).is_equivalent());
+    assert!(compare("TX_ID,AMOUNT".parse().unwrap()).is_equivalent());
+    assert!(!compare("TX_ID,STATUS".parse().unwrap()).is_equivalent());
+    assert!(compare(RecordKey::custom(|tx| tx.amount.minor_units as u64)).is_equivalent());
+}
+
+#[test]
+fn missing_file_is_read_error() {
+    let err = assert_equivalent(
+        "/nonexistent/a.csv",
+        &Codec::CsvCodec,
+        "/nonexistent/b.csv",
+        &Codec::CsvCodec,
+        &EquivalenceOptions::default(),
+    )
+    .expect_err("missing file should fail");
+    assert!(matches!(err, AppError::ReadError(_)));
+}
+
+#[test]
+fn record_sets_are_compared_in_memory() {
+    let a = vec![sample_tx(3), sample_tx(1), sample_tx(2), sample_tx(2)];
+    let b = vec![sample_tx(2), sample_tx(4), sample_tx(3)];
+    let report = compare(&a, &b);
+    assert!(!report.is_identical());
+    assert_eq!((report.records_a, report.records_b), (4, 3));
+    assert_eq!(
+        report.only_in_a,
+        vec![
+            Unmatched {
+                record: sample_tx(1),
+                count: 1
+            },
+            Unmatched {
+                record: sample_tx(2),
+                count: 1
+            },
+        ]
+    );
+    assert_eq!(
+        report.only_in_b,
+        vec![Unmatched {
+            record: sample_tx(4),
+            count: 1
+        }]
+    );
+    assert_eq!(report.mismatches(), 3);
+
+    let mut shuffled = a.clone();
+    shuffled.reverse();
+    assert!(compare(&a, &shuffled).is_identical());
+}
+
+#[test]
+fn unmatched_records_come_from_their_own_side() {
+    let mut updated = sample_tx(2);
+    updated.status = TxStatus::Failure;
+    let opts = CompareOptions {
+        key: RecordKey::IdOnly,
+        ..Default::default()
+    };
+    // the second set has one more record of id 2, its own record is reported
+    let report = compare_with(&[sample_tx(2)], &[updated.clone(), updated.clone()], &opts);
+    assert_eq!(
+        report.only_in_b,
+        vec![Unmatched {
+            record: updated,
+            count: 1
+        }]
+    );
+
+    let opts = CompareOptions {
+        ignore_multiplicity: true,
+        ..opts
+    };
+    assert!(compare_with(&[sample_tx(2)], &[sample_tx(2), sample_tx(2)], &opts).is_identical());
+}
+
+#[test]
+fn records_of_same_id_are_diffed_by_field() {
+    let mut updated = sample_tx(2);
+    updated.amount = Money::from_minor_units(201);
+    updated.status = TxStatus::Pending;
+    updated.reference = Some("E2E-2".into());
+    let rescaled = TxRecord {
+        amount: Money::new(1, 0),
+        ..sample_tx(1)
+    };
+    let report = diff(
+        &[sample_tx(1), sample_tx(2), sample_tx(3)],
+        &[updated, rescaled, sample_tx(4)],
+    );
+    assert!(!report.is_identical());
+    // amounts are compared by value
+    assert_eq!(report.unchanged, 1);
+    assert_eq!(
+        report.changed,
+        vec![RecordDiff {
+            id: TxIdType::Numeric(2),
+            fields: vec![
+                FieldDiff {
+                    field: TxFieldKey::Amount,
+                    old: Some("2".into()),
+                    new: Some("2.01".into()),
+                },
+                FieldDiff {
+                    field: TxFieldKey::Status,
+                    old: Some("SUCCESS".into()),
+                    new: Some("PENDING".into()),
+                },
+                FieldDiff {
+                    field: TxFieldKey::Reference,
+                    old: None,
+                    new: Some("E2E-2".into()),
+                },
+            ],
+        }]
+    );
+    assert_eq!(
+        report.changed[0].to_string(),
+        "#2 AMOUNT: 2 -> 2.01, STATUS: SUCCESS -> PENDING, REFERENCE: - -> E2E-2"
+    );
+    assert_eq!(report.only_in_a, vec![sample_tx(3)]);
+    assert_eq!(report.only_in_b, vec![sample_tx(4)]);
+}
+
+#[test]
+fn repeated_ids_are_paired_in_order() {
+    let mut second = sample_tx(1);
+    second.description = Some("retry".into());
+    let report = diff(&[sample_tx(1), second.clone()], &[sample_tx(1)]);
+    assert_eq!(report.unchanged, 1);
+    assert!(report.changed.is_empty());
+    assert_eq!(report.only_in_a, vec![second]);
+    assert!(diff(&[sample_tx(1)], &[sample_tx(1)]).is_identical());
+}
+
+fn bank_tolerances() -> Tolerances {
+    Tolerances {
+        timestamp_drift_ms: 1_000,
+        amount: Money::from_minor_units(1),
+        ignored: vec![TxFieldKey::Description],
+    }
+}
+
+#[test]
+fn records_are_matched_within_tolerances() {
+    let ours: Vec<TxRecord> = (1..=4).map(sample_tx).collect();
+    let mut bank = ours.clone();
+    bank[1].ts = TxTimestamp::from_millis(bank[1].ts.millis() + 250);
+    bank[1].description = Some("PAYMENT 2 REF 0002".to_string());
+    bank[2].ts = TxTimestamp::from_millis(bank[2].ts.millis() + 5_000);
+    bank[3].amount = Money::from_minor_units(401);
+
+    let report = fuzzy_match(&ours, &bank, &bank_tolerances());
+    assert_eq!(report.exact, 1);
+    assert_eq!(report.fuzzy.len(), 2);
+    assert_eq!(report.fuzzy[0].a, ours[1]);
+    assert_eq!(report.fuzzy[0].b, bank[1]);
+    let fields: Vec<TxFieldKey> = report.fuzzy[0].fields.iter().map(|f| f.field).collect();
+    assert_eq!(fields, vec![TxFieldKey::Timestamp, TxFieldKey::Description]);
+    assert_eq!(report.fuzzy[1].fields[0].to_string(), "AMOUNT: 4 -> 4.01");
+    assert_eq!(report.only_in_a, vec![ours[2].clone()]);
+    assert_eq!(report.only_in_b, vec![bank[2].clone()]);
+
+    // without tolerances only identical records match
+    let strict = fuzzy_match(&ours, &bank, &Tolerances::default());
+    assert_eq!(strict.exact, 1);
+    assert!(strict.fuzzy.is_empty());
+    assert_eq!(strict.only_in_a.len(), 3);
+}
+
+#[test]
+fn closest_record_is_matched_fuzzily() {
+    let ours = vec![sample_tx(1)];
+    let mut late = sample_tx(1);
+    late.ts = TxTimestamp::from_millis(late.ts.millis() + 800);
+    let mut early = sample_tx(1);
+    early.ts = TxTimestamp::from_millis(early.ts.millis() - 100);
+    let mut other_account = sample_tx(1);
+    other_account.to = AccountType::Numeric(33);
+    let bank = vec![other_account.clone(), late.clone(), early.clone()];
+
+    let report = fuzzy_match(&ours, &bank, &bank_tolerances());
+    assert_eq!(report.exact, 0);
+    assert_eq!(report.fuzzy.len(), 1);
+    assert_eq!(report.fuzzy[0].b, early);
+    assert_eq!(report.only_in_b, vec![other_account, late]);
+}
@@ -0,0 +1,116 @@
+use parser::codecs::base::Codec;
+use parser::codecs::compression::{CompressedReader, CompressedWriter, Compression};
+use parser::codecs::options::{ParseOptions, WriteOptions};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use std::io::{Read, Write};
+
+fn sample(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Deposit,
+        from: AccountType::Numeric(0),
+        to: AccountType::Numeric(id % 5),
+        amount: Money::from_minor_units(100 * id as i64),
+        ts: TxTimestamp::from_millis(1_700_000 + id),
+        status: TxStatus::Success,
+        description: Some(format!("payment {}", id % 3)),
+        ..Default::default()
+    }
+}
+
+fn compress(codec: &Codec, data: &[TxRecord]) -> Vec<u8> {
+    let mut out = Vec::new();
+    codec
+        .write_compressed(&mut out, data, &WriteOptions::default(), Compression::Gzip)
+        .expect("compressed write should succeed");
+    out
+}
+
+#[test]
+fn compressed_round_trip_for_every_codec() {
+    let data: Vec<TxRecord> = (1..50).map(sample).collect();
+    for codec in [Codec::CsvCodec, Codec::TextCodec, Codec::BinaryCodec] {
+        let compressed = compress(&codec, &data);
+        assert_eq!(Compression::detect(&compressed), Some(Compression::Gzip));
+        let parsed = codec
+            .parse_compressed(compressed.as_slice(), &ParseOptions::default())
+            .expect("compressed input should parse");
+        assert_eq!(parsed, data);
+    }
+}
+
+#[test]
+fn compressed_output_is_smaller() {
+    let data: Vec<TxRecord> = (1..500).map(sample).collect();
+    let mut plain = Vec::new();
+    Codec::CsvCodec.write(&mut plain, &data).unwrap();
+    assert!(compress(&Codec::CsvCodec, &data).len() * 3 < plain.len());
+}
+
+#[test]
+fn uncompressed_input_is_passed_through() {
+    let mut plain = Vec::new();
+    Codec::CsvCodec.write(&mut plain, &[sample(1)]).unwrap();
+    let parsed = Codec::CsvCodec
+        .parse_compressed(plain.as_slice(), &ParseOptions::default())
+        .unwrap();
+    assert_eq!(parsed, vec![sample(1)]);
+
+    let mut reader = CompressedReader::new(&b"ab"[..]).unwrap();
+    assert_eq!(reader.compression(), None);
+    let mut read = String::new();
+    reader.read_to_string(&mut read).unwrap();
+    assert_eq!(read, "ab");
+}
+
+#[test]
+fn concatenated_members_are_read_as_one_stream() {
+    let mut compressed = Vec::new();
+    for part in [&b"hello "[..], b"world"] {
+        let mut w = CompressedWriter::new(Vec::new(), Compression::Gzip).unwrap();
+        w.write_all(part).unwrap();
+        compressed.extend(w.finish().unwrap());
+    }
+    let mut read = String::new();
+    CompressedReader::new(compressed.as_slice())
+        .unwrap()
+        .read_to_string(&mut read)
+        .unwrap();
+    assert_eq!(read, "hello world");
+}
+
+#[test]
+fn corrupted_member_is_rejected() {
+    let mut compressed = compress(&Codec::CsvCodec, &[sample(1)]);
+    let last = compressed.len() - 5;
+    compressed[last] ^= 0xFF;
+    let mut reader = CompressedReader::new(compressed.as_slice()).unwrap();
+    let err = reader
+        .read_to_end(&mut Vec::new())
+        .expect_err("checksum should not match");
+    assert!(err.to_string().contains("checksum"), "{err}");
+    assert!(
+        Codec::CsvCodec
+            .parse_compressed(compressed.as_slice(), &ParseOptions::default())
+            .is_err()
+    );
+
+    let truncated = &compress(&Codec::CsvCodec, &[sample(1)])[..12];
+    let mut reader = CompressedReader::new(truncated).unwrap();
+    assert!(reader.read_to_end(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn flush_writes_compressed_data_written_so_far() {
+    let mut out = Vec::new();
+    let mut w = CompressedWriter::new(&mut out, Compression::Gzip).unwrap();
+    w.write_all(b"hello").unwrap();
+    w.flush().unwrap();
+    drop(w);
+    let mut read = Vec::new();
+    // flushed output is readable up to the missing trailer
+    let _ = CompressedReader::new(out.as_slice())
+        .unwrap()
+        .read_to_end(&mut read);
+    assert_eq!(read, b"hello");
+}
@@ -1,6 +1,10 @@
-use parser::codecs::base::Codec;
+use parser::codecs::base::{Codec, TxFieldKey};
+use parser::codecs::csv::{CsvDialect, CsvQuoting, CsvWriteOptions};
 use parser::codecs::errors::{ParserContext, ParserError};
-use parser::domain::tx::TxRecord;
+use parser::codecs::options::{
+    AmountFormat, ParseOptions, Strictness, TextEncoding, TimestampFormat, WriteOptions,
+};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxRecord, TxStatus};
 use parser::errors::AppError;
 
 const CSV_HEADER: &str =
@@ -24,8 +28,40 @@ fn parse_csv_accepts_whitespace_around_fields() {
         .parse(input.as_bytes())
         .expect("csv with spaces should parse");
     assert_eq!(parsed.len(), 1);
-    assert_eq!(parsed[0].id.0, 7);
-    assert_eq!(parsed[0].description, "bonus");
+    assert_eq!(parsed[0].id.as_numeric(), Some(7));
+    assert_eq!(parsed[0].description.as_deref(), Some("bonus"));
+}
+
+#[test]
+fn uuid_ids_round_trip() {
+    let input = format!(
+        "{}{}",
+        CSV_HEADER, "123E4567-E89B-12D3-A456-426614174000,DEPOSIT,0,3,99,1700,SUCCESS,\"x\"\n"
+    );
+    let parsed = Codec::CsvCodec
+        .parse(input.as_bytes())
+        .expect("csv with uuid id should parse");
+    assert_eq!(
+        parsed[0].id,
+        TxIdType::Uuid(0x123e4567_e89b_12d3_a456_426614174000)
+    );
+
+    let mut bytes = Vec::new();
+    Codec::CsvCodec
+        .write(&mut bytes, &parsed)
+        .expect("csv write should succeed");
+    let written = String::from_utf8(bytes).unwrap();
+    assert!(written.contains("\n123e4567-e89b-12d3-a456-426614174000,"));
+    let reparsed = Codec::CsvCodec
+        .parse(written.as_bytes())
+        .expect("written uuid should parse");
+    assert_eq!(reparsed, parsed);
+
+    let input = format!(
+        "{}{}",
+        CSV_HEADER, "123e4567-e89b-12d3-a456-42661417400g,DEPOSIT,0,3,99,1700,SUCCESS,\"x\"\n"
+    );
+    assert!(Codec::CsvCodec.parse(input.as_bytes()).is_err());
 }
 
 #[test]
@@ -129,7 +165,7 @@ fn csv_format_round_trip_single_record() {
     let mut bytes = Vec::new();
 
     Codec::CsvCodec
-        .write(&mut bytes, &[tx.clone()])
+        .write(&mut bytes, std::slice::from_ref(&tx))
         .expect("csv write should succeed");
 
     let parsed = Codec::CsvCodec
@@ -138,3 +174,674 @@ fn csv_format_round_trip_single_record() {
 
     assert_eq!(parsed, vec![tx]);
 }
+
+#[test]
+fn parse_semicolon_separated_csv() {
+    let input = "TX_ID;TX_TYPE;FROM_USER_ID;TO_USER_ID;AMOUNT;TIMESTAMP;STATUS;DESCRIPTION\n\
+                 7;DEPOSIT;0;3;99;1700;SUCCESS;'bonus, paid'\n";
+    let options = ParseOptions {
+        csv: CsvDialect {
+            delimiter: ';',
+            quote: Some('\''),
+            has_header: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let parsed = Codec::CsvCodec
+        .parse_with(input.as_bytes(), &options)
+        .expect("semicolon csv should parse");
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[0].description.as_deref(), Some("bonus, paid"));
+
+    // default dialect rejects it at the header check
+    let err = Codec::CsvCodec
+        .parse(input.as_bytes())
+        .expect_err("comma dialect should reject semicolon header");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::InvalidFileHeader,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn parse_headerless_csv() {
+    let input = "1,DEPOSIT,0,3,10,1700,SUCCESS,\"a\"\n2,DEPOSIT,0,3,20,1700,SUCCESS,\"b\",acme\n";
+    let options = ParseOptions {
+        csv: CsvDialect {
+            has_header: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let parsed = Codec::CsvCodec
+        .parse_with(input.as_bytes(), &options)
+        .expect("headerless csv should parse");
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[1].tenant.as_deref(), Some("acme"));
+}
+
+#[test]
+fn tsv_round_trip() {
+    let tx = TxRecord {
+        description: Some("coffee, \"large\"".to_string()),
+        ..Default::default()
+    };
+    let mut buff = Vec::new();
+    Codec::TsvCodec
+        .write(&mut buff, std::slice::from_ref(&tx))
+        .expect("tsv write should succeed");
+    let text = String::from_utf8(buff.clone()).unwrap();
+    assert!(text.starts_with("TX_ID\tTX_TYPE\t"));
+    assert!(text.contains("\tcoffee, \"large\"\n"));
+
+    let parsed = Codec::TsvCodec
+        .parse(buff.as_slice())
+        .expect("tsv read should succeed");
+    assert_eq!(parsed, vec![tx]);
+}
+
+fn write_quoted(data: &[TxRecord], quoting: CsvQuoting, quote_description: bool) -> String {
+    let options = WriteOptions {
+        csv: CsvWriteOptions {
+            quoting,
+            quote_description,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut buff = Vec::new();
+    Codec::CsvCodec
+        .write_with(&mut buff, data, &options)
+        .expect("csv write should succeed");
+    String::from_utf8(buff).unwrap()
+}
+
+#[test]
+fn always_quoting_quotes_every_field() {
+    let tx = TxRecord {
+        description: Some("coffee".to_string()),
+        ..Default::default()
+    };
+    let text = write_quoted(std::slice::from_ref(&tx), CsvQuoting::Always, false);
+    let row = text.lines().nth(1).unwrap();
+    assert!(
+        row.split(',')
+            .all(|v| v.starts_with('"') && v.ends_with('"'))
+    );
+    assert_eq!(Codec::CsvCodec.parse(text.as_bytes()).unwrap(), vec![tx]);
+}
+
+#[test]
+fn minimal_quoting_leaves_plain_descriptions_unquoted() {
+    let data = vec![
+        TxRecord {
+            description: Some("coffee".to_string()),
+            ..Default::default()
+        },
+        TxRecord {
+            description: Some(" padded".to_string()),
+            ..Default::default()
+        },
+    ];
+    let text = write_quoted(&data, CsvQuoting::Minimal, false);
+    assert!(text.contains(",coffee\n"));
+    assert!(text.contains(",\" padded\"\n"));
+
+    // default dialect insists on quoted descriptions
+    assert!(Codec::CsvCodec.parse(text.as_bytes()).is_err());
+    let options = ParseOptions {
+        csv: CsvDialect {
+            require_quoted_description: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let parsed = Codec::CsvCodec
+        .parse_with(text.as_bytes(), &options)
+        .expect("lenient dialect should accept unquoted descriptions");
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn never_quoting_fails_on_fields_needing_quotes() {
+    let tx = TxRecord {
+        description: Some("lunch, taxi".to_string()),
+        ..Default::default()
+    };
+    let options = WriteOptions {
+        csv: CsvWriteOptions {
+            quoting: CsvQuoting::Never,
+            quote_description: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let err = Codec::CsvCodec
+        .write_with(&mut Vec::new(), std::slice::from_ref(&tx), &options)
+        .expect_err("delimiter can't be written unquoted");
+    assert!(matches!(err, AppError::WriteError(_)));
+
+    let text = write_quoted(&[TxRecord::default()], CsvQuoting::Never, false);
+    assert!(!text.contains('"'));
+}
+
+#[test]
+fn default_quoting_keeps_quoted_descriptions() {
+    let tx = TxRecord {
+        description: Some("coffee".to_string()),
+        ..Default::default()
+    };
+    let text = write_quoted(std::slice::from_ref(&tx), CsvQuoting::Minimal, true);
+    let row = text.lines().nth(1).unwrap();
+    assert!(row.starts_with("0,"));
+    assert!(row.ends_with(",\"coffee\""));
+
+    let mut buff = Vec::new();
+    Codec::CsvCodec
+        .write(&mut buff, std::slice::from_ref(&tx))
+        .unwrap();
+    assert_eq!(String::from_utf8(buff).unwrap(), text);
+}
+
+#[test]
+fn parse_columns_in_any_order() {
+    let input = "STATUS,TENANT,DESCRIPTION,AMOUNT,TX_ID,TIMESTAMP,TO_USER_ID,FROM_USER_ID,TX_TYPE\n\
+                 SUCCESS,acme,\"coffee\",250,7,1700,3,0,DEPOSIT\n";
+    let parsed = Codec::CsvCodec
+        .parse(input.as_bytes())
+        .expect("reordered columns should parse");
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[0].id.as_numeric(), Some(7));
+    assert_eq!(parsed[0].amount, Money::from_minor_units(250));
+    assert_eq!(parsed[0].to, AccountType::Numeric(3));
+    assert_eq!(parsed[0].description.as_deref(), Some("coffee"));
+    assert_eq!(parsed[0].tenant.as_deref(), Some("acme"));
+}
+
+#[test]
+fn parse_rejects_bad_header_columns() {
+    let cases = [
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,DESCRIPTION\n",
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,TX_ID\n",
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,MEMO\n",
+    ];
+    let errors: Vec<ParserError> = cases
+        .iter()
+        .map(|input| match Codec::CsvCodec.parse(input.as_bytes()) {
+            Err(AppError::ParsingError { source, .. }) => source,
+            other => panic!("unexpected result {:?}", other),
+        })
+        .collect();
+    assert!(matches!(
+        errors.as_slice(),
+        [
+            ParserError::MissingField(TxFieldKey::Status),
+            ParserError::Duplicate(TxFieldKey::Id),
+            ParserError::InvalidFileHeader,
+        ]
+    ));
+}
+
+fn options_with_defaults(strictness: Strictness) -> ParseOptions {
+    ParseOptions {
+        strictness,
+        csv: CsvDialect {
+            defaults: vec![
+                (TxFieldKey::Status, "PENDING".to_string()),
+                (TxFieldKey::Description, "n/a".to_string()),
+            ],
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn missing_columns_take_defaults() {
+    let input = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP\n\
+                 1,DEPOSIT,0,3,10,1700\n";
+    let parsed = Codec::CsvCodec
+        .parse_with(
+            input.as_bytes(),
+            &options_with_defaults(Strictness::Lenient),
+        )
+        .expect("missing columns should be defaulted");
+    assert_eq!(parsed[0].status, TxStatus::Pending);
+    assert_eq!(parsed[0].description.as_deref(), Some("n/a"));
+
+    // present column wins over default
+    let input = format!(
+        "{}{}",
+        CSV_HEADER, "1,DEPOSIT,0,3,10,1700,SUCCESS,\"coffee\"\n"
+    );
+    let parsed = Codec::CsvCodec
+        .parse_with(
+            input.as_bytes(),
+            &options_with_defaults(Strictness::Lenient),
+        )
+        .unwrap();
+    assert_eq!(parsed[0].status, TxStatus::Success);
+    assert_eq!(parsed[0].description.as_deref(), Some("coffee"));
+}
+
+#[test]
+fn strict_mode_ignores_defaults() {
+    let input = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,DESCRIPTION\n\
+                 1,DEPOSIT,0,3,10,1700,\"a\"\n";
+    let err = Codec::CsvCodec
+        .parse_with(input.as_bytes(), &options_with_defaults(Strictness::Strict))
+        .expect_err("strict mode requires all columns");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::MissingField(TxFieldKey::Status),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn missing_and_empty_descriptions_round_trip() {
+    let input = format!(
+        "{}{}{}",
+        CSV_HEADER, "1,DEPOSIT,0,3,10,1700,SUCCESS,\n", "2,DEPOSIT,0,3,10,1700,SUCCESS,\"\"\n"
+    );
+    let parsed = Codec::CsvCodec
+        .parse(input.as_bytes())
+        .expect("empty descriptions should parse");
+    assert_eq!(parsed[0].description, None);
+    assert_eq!(parsed[1].description.as_deref(), Some(""));
+
+    let mut buff = Vec::new();
+    Codec::CsvCodec
+        .write(&mut buff, &parsed)
+        .expect("csv write should succeed");
+    assert_eq!(String::from_utf8(buff).unwrap(), input);
+
+    // description column may be left out altogether
+    let input = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS\n\
+                 1,DEPOSIT,0,3,10,1700,SUCCESS\n";
+    let parsed = Codec::CsvCodec
+        .parse(input.as_bytes())
+        .expect("csv without description column should parse");
+    assert_eq!(parsed[0].description, None);
+}
+
+#[test]
+fn parse_rfc4180_quoted_fields() {
+    let input = format!(
+        "{}{}{}",
+        CSV_HEADER,
+        "1,DEPOSIT,0,3,10,1700,SUCCESS,\"lunch, taxi\"\n",
+        "2,DEPOSIT,0,3,10,1700,SUCCESS,\"say \"\"hi\"\"\nand\n\nbye\"\n"
+    );
+    let parsed = Codec::CsvCodec
+        .parse(input.as_bytes())
+        .expect("quoted fields should parse");
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].description.as_deref(), Some("lunch, taxi"));
+    assert_eq!(
+        parsed[1].description.as_deref(),
+        Some("say \"hi\"\nand\n\nbye")
+    );
+}
+
+#[test]
+fn csv_round_trip_of_special_characters() {
+    let data = vec![
+        TxRecord {
+            description: Some("lunch, taxi".to_string()),
+            tenant: Some("acme, inc".to_string()),
+            ..Default::default()
+        },
+        TxRecord {
+            description: Some("\"quoted\"\nmulti-line".to_string()),
+            ..Default::default()
+        },
+    ];
+    for quoting in [CsvQuoting::Always, CsvQuoting::Minimal] {
+        let text = write_quoted(&data, quoting, true);
+        assert_eq!(Codec::CsvCodec.parse(text.as_bytes()).unwrap(), data);
+    }
+    let text = write_quoted(&data, CsvQuoting::Minimal, true);
+    assert!(text.contains(",\"\"\"quoted\"\"\nmulti-line\""));
+}
+
+#[test]
+fn unterminated_quote_rejects_rest_of_input() {
+    let input = format!(
+        "{}{}{}",
+        CSV_HEADER,
+        "1,DEPOSIT,0,3,10,1700,SUCCESS,\"ok\"\n",
+        "2,DEPOSIT,0,3,10,1700,SUCCESS,\"open\n3,DEPOSIT,0,3,10,1700,SUCCESS,x\n"
+    );
+    let parsed = Codec::CsvCodec
+        .parse_lenient(input.as_bytes(), &ParseOptions::default())
+        .unwrap();
+    assert_eq!(parsed.records.len(), 1);
+    assert!(matches!(
+        parsed.errors.as_slice(),
+        [(
+            ParserContext::LineNumAndLine { line_num: 2, .. },
+            ParserError::IncompleteRecord
+        )]
+    ));
+
+    let input = format!("{}{}", CSV_HEADER, "1,DEPOSIT,0,3,10,1700,SUCCESS,\"a\"b\n");
+    assert!(matches!(
+        Codec::CsvCodec.parse(input.as_bytes()),
+        Err(AppError::ParsingError {
+            source: ParserError::UnparsableValue(_),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn trailing_half_record_and_garbage_are_rejected() {
+    let strict = ParseOptions {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    };
+    let records = format!("{}1,DEPOSIT,0,1,100,1000,SUCCESS,\"ok\"\n", CSV_HEADER);
+    for tail in [
+        "2,DEPOSIT,0,1,10",
+        "2,DEPOSIT,0,1,100,1000,SUCCESS,\"cut",
+        "\0\0\0",
+    ] {
+        let input = format!("{}{}", records, tail);
+        for options in [ParseOptions::default(), strict.clone()] {
+            let err = Codec::CsvCodec
+                .parse_with(input.as_bytes(), &options)
+                .expect_err("trailing garbage should be rejected");
+            assert!(matches!(
+                err,
+                AppError::ParsingError {
+                    source: ParserError::IncompleteRecord,
+                    ..
+                }
+            ));
+        }
+    }
+    // trailing whitespace is no garbage
+    let input = format!("{}\n \t\n", records);
+    assert_eq!(
+        Codec::CsvCodec
+            .parse_with(input.as_bytes(), &strict)
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn rfc3339_timestamps_round_trip_csv_and_tsv() {
+    let input = format!(
+        "{}1,DEPOSIT,0,1,100,1700000000000,SUCCESS,\"ok\"\n",
+        CSV_HEADER
+    );
+    let records = Codec::CsvCodec.parse(input.as_bytes()).unwrap();
+    let write_options = WriteOptions {
+        csv: CsvWriteOptions {
+            timestamps: TimestampFormat::Rfc3339,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let parse_options = ParseOptions {
+        csv: CsvDialect {
+            timestamps: TimestampFormat::Rfc3339,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    for codec in [Codec::CsvCodec, Codec::TsvCodec] {
+        let mut out = Vec::new();
+        codec
+            .write_with(&mut out, &records, &write_options)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("2023-11-14T22:13:20.000Z"), "{}", out);
+        assert_eq!(
+            codec.parse_with(out.as_bytes(), &parse_options).unwrap(),
+            records
+        );
+        // epoch milliseconds are expected by default
+        assert!(codec.parse(out.as_bytes()).is_err());
+    }
+}
+
+#[test]
+fn decimal_amounts_round_trip_csv() {
+    let input = format!(
+        "{}1,DEPOSIT,0,1,1234,1000,SUCCESS,\"ok\"\n2,WITHDRAWAL,1,0,-7,1000,SUCCESS,\"ok\"\n",
+        CSV_HEADER
+    );
+    let records = Codec::CsvCodec.parse(input.as_bytes()).unwrap();
+    let amounts = AmountFormat::Decimal { scale: 3 };
+    let write_options = WriteOptions {
+        csv: CsvWriteOptions {
+            amounts,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    Codec::CsvCodec
+        .write_with(&mut out, &records, &write_options)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    // minor units are cents, rescaled to three fraction digits
+    assert!(
+        out.contains(",12.340,") && out.contains(",-0.070,"),
+        "{}",
+        out
+    );
+
+    let parse_options = ParseOptions {
+        csv: CsvDialect {
+            amounts,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(
+        Codec::CsvCodec
+            .parse_with(out.as_bytes(), &parse_options)
+            .unwrap(),
+        records
+    );
+
+    // cents can't be written with one fraction digit
+    let write_options = WriteOptions {
+        csv: CsvWriteOptions {
+            amounts: AmountFormat::Decimal { scale: 1 },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let err = Codec::CsvCodec
+        .write_with(&mut Vec::new(), &records, &write_options)
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("amount 12.34 can't be written with 1 fraction digits"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn exact_amounts_keep_their_scale() {
+    let input = format!(
+        "{}1,DEPOSIT,0,1,500,1000,SUCCESS,\"yen\"\n2,DEPOSIT,0,1,1.234,1000,SUCCESS,\"dinar\"\n",
+        CSV_HEADER
+    );
+    let options = ParseOptions {
+        csv: CsvDialect {
+            amounts: AmountFormat::Exact,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let records = Codec::CsvCodec
+        .parse_with(input.as_bytes(), &options)
+        .unwrap();
+    assert_eq!(
+        (records[0].amount.minor_units, records[0].amount.scale),
+        (500, 0)
+    );
+    assert_eq!(
+        (records[1].amount.minor_units, records[1].amount.scale),
+        (1234, 3)
+    );
+
+    let write_options = WriteOptions {
+        csv: CsvWriteOptions {
+            amounts: AmountFormat::Exact,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    Codec::CsvCodec
+        .write_with(&mut out, &records, &write_options)
+        .unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), input);
+}
+
+#[test]
+fn renamed_columns_are_written_and_read() {
+    let input = format!("{}1,DEPOSIT,0,1,100,1000,SUCCESS,\"ok\"\n", CSV_HEADER);
+    let records = Codec::CsvCodec.parse(input.as_bytes()).unwrap();
+    let column_names = vec![
+        (TxFieldKey::Id, "transaction_id".to_string()),
+        (TxFieldKey::Description, "memo, free text".to_string()),
+    ];
+    let write_options = WriteOptions {
+        csv: CsvWriteOptions {
+            column_names: column_names.clone(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    Codec::CsvCodec
+        .write_with(&mut out, &records, &write_options)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with(
+        "transaction_id,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,\"memo, free text\"\n"
+    ));
+
+    let parse_options = ParseOptions {
+        csv: CsvDialect {
+            column_names,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(
+        Codec::CsvCodec
+            .parse_with(out.as_bytes(), &parse_options)
+            .unwrap(),
+        records
+    );
+    // standard names are still accepted
+    assert_eq!(
+        Codec::CsvCodec
+            .parse_with(input.as_bytes(), &parse_options)
+            .unwrap(),
+        records
+    );
+    assert!(matches!(
+        Codec::CsvCodec.parse(out.as_bytes()),
+        Err(AppError::ParsingError {
+            source: ParserError::InvalidFileHeader,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn byte_order_marks_are_skipped_and_utf16_transcoded() {
+    let input = format!("{}1,DEPOSIT,0,1,100,1000,SUCCESS,\"café\"\n", CSV_HEADER);
+    let expected = Codec::CsvCodec.parse(input.as_bytes()).unwrap();
+
+    let with_bom = [&[0xEF, 0xBB, 0xBF][..], input.as_bytes()].concat();
+    assert_eq!(
+        Codec::CsvCodec.parse(with_bom.as_slice()).unwrap(),
+        expected
+    );
+
+    let utf16: Vec<u8> = [0xFEFF]
+        .into_iter()
+        .chain(input.encode_utf16())
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    let detect = ParseOptions {
+        encoding: TextEncoding::Detect,
+        ..Default::default()
+    };
+    assert_eq!(
+        Codec::CsvCodec
+            .parse_with(utf16.as_slice(), &detect)
+            .unwrap(),
+        expected
+    );
+    assert!(Codec::CsvCodec.parse(utf16.as_slice()).is_err());
+}
+
+#[test]
+fn extra_columns_are_kept_and_written_back() {
+    let input = "TX_ID,vendor_ref,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,note\n\
+                 1,A-17,DEPOSIT,0,1,100,1000,SUCCESS,\"ok\",\"a, b\"\n\
+                 2,,DEPOSIT,0,1,100,1000,SUCCESS,\"ok\",\n";
+    assert!(matches!(
+        Codec::CsvCodec.parse(input.as_bytes()),
+        Err(AppError::ParsingError {
+            source: ParserError::InvalidFileHeader,
+            ..
+        })
+    ));
+    let options = ParseOptions {
+        csv: CsvDialect {
+            keep_extras: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let records = Codec::CsvCodec
+        .parse_with(input.as_bytes(), &options)
+        .unwrap();
+    assert_eq!(records[0].extras["vendor_ref"], "A-17");
+    assert_eq!(records[0].extras["note"], "a, b");
+    // empty values are not kept
+    assert!(records[1].extras.is_empty());
+
+    let mut out = Vec::new();
+    Codec::CsvCodec.write(&mut out, &records).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with(
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,note,vendor_ref\n"
+    ));
+    assert_eq!(
+        Codec::CsvCodec
+            .parse_with(out.as_bytes(), &options)
+            .unwrap(),
+        records
+    );
+
+    let dup = "TX_ID,x,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,x\n";
+    assert!(matches!(
+        Codec::CsvCodec.parse_with(dup.as_bytes(), &options),
+        Err(AppError::ParsingError {
+            source: ParserError::DuplicateExtra(_),
+            ..
+        })
+    ));
+}
@@ -0,0 +1,67 @@
+use parser::domain::key::RecordKey;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::transform::dedup::{self, Deduplicated};
+
+fn sample_tx(id: u64, ts: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Deposit,
+        from: AccountType::Numeric(0),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(100),
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Success,
+        description: Some("salary".to_string()),
+        ..Default::default()
+    }
+}
+
+fn batch() -> Vec<TxRecord> {
+    let mut changed = sample_tx(2, 2_000);
+    changed.amount = Money::from_minor_units(200);
+    vec![
+        sample_tx(1, 1_000),
+        sample_tx(2, 2_000),
+        // exact duplicate
+        sample_tx(1, 1_000),
+        // re-sent later
+        sample_tx(2, 9_000),
+        // same id, other amount
+        changed,
+    ]
+}
+
+#[test]
+fn exact_duplicates_are_removed() {
+    let data = batch();
+    let result = dedup::dedup(&data, &RecordKey::FullRecord);
+    assert_eq!(result.removed, 1);
+    assert_eq!(
+        result.records,
+        vec![
+            data[0].clone(),
+            data[1].clone(),
+            data[3].clone(),
+            data[4].clone()
+        ]
+    );
+    assert_eq!(
+        dedup::dedup(&[], &RecordKey::FullRecord),
+        Deduplicated::default()
+    );
+}
+
+#[test]
+fn duplicates_are_removed_by_key() {
+    let data = batch();
+    let resent = dedup::dedup(&data, &dedup::resent_key());
+    assert_eq!(resent.removed, 2);
+    assert_eq!(
+        resent.records,
+        vec![data[0].clone(), data[1].clone(), data[4].clone()]
+    );
+
+    let mut by_id = data.clone();
+    assert_eq!(dedup::dedup_in_place(&mut by_id, &RecordKey::IdOnly), 3);
+    assert_eq!(by_id, vec![data[0].clone(), data[1].clone()]);
+}
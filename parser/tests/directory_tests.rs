@@ -0,0 +1,115 @@
+use parser::codecs::base::Codec;
+use parser::codecs::options::{ParseOptions, WriteOptions};
+use parser::codecs::report::ReportOptions;
+use parser::codecs::text::{TextDialect, TextWriteOptions};
+use parser::domain::directory::AccountDirectory;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(from: u64, to: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(1),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(to),
+        amount: Money::from_minor_units(500),
+        ts: TxTimestamp::from_millis(1700),
+        status: TxStatus::Success,
+        description: Some("salary".into()),
+        ..Default::default()
+    }
+}
+
+fn sample_directory() -> AccountDirectory {
+    AccountDirectory::read_csv(
+        "NAME,ACCOUNT\n# treasury accounts\npayroll,4021\n\nrent, deposit,DE89 3704 0044 0532 0130 00\n"
+            .as_bytes(),
+    )
+    .expect("directory should load")
+}
+
+#[test]
+fn directory_loads_from_csv_and_json() {
+    let directory = sample_directory();
+    assert_eq!(directory.len(), 2);
+    assert_eq!(
+        directory.resolve("payroll"),
+        Some(&AccountType::Numeric(4021))
+    );
+    assert_eq!(
+        directory.name_of(&AccountType::Iban("DE89370400440532013000".into())),
+        Some("rent, deposit")
+    );
+    assert_eq!(directory.name_of(&AccountType::Numeric(1)), None);
+
+    let json = r#"{"payroll": 4021, "rent, deposit": "DE89370400440532013000"}"#;
+    assert_eq!(
+        AccountDirectory::read_json(json.as_bytes()).expect("directory should load"),
+        directory
+    );
+}
+
+#[test]
+fn directory_rejects_ambiguous_names() {
+    for csv in [
+        "payroll,4021\npayroll,4022\n",
+        "payroll,4021\nsalaries,4021\n",
+        "42,4021\n",
+        "pay\"roll,4021\n",
+        "payroll\n",
+    ] {
+        let err = AccountDirectory::read_csv(csv.as_bytes()).expect_err("csv should be rejected");
+        assert!(matches!(err, AppError::ParsingError { .. }), "{}", csv);
+    }
+    assert!(AccountDirectory::read_json("[1]".as_bytes()).is_err());
+    assert!(AccountDirectory::read_json(r#"{"payroll": null}"#.as_bytes()).is_err());
+}
+
+#[test]
+fn text_names_round_trip_with_directory() {
+    let data = vec![sample_tx(4021, 7)];
+    let write_options = WriteOptions {
+        text: TextWriteOptions {
+            accounts: Some(sample_directory()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut text = Vec::new();
+    Codec::TextCodec
+        .write_with(&mut text, &data, &write_options)
+        .expect("text write");
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("FROM_USER_ID: payroll\nTO_USER_ID: 7\n"));
+
+    // names are not accounts without directory
+    assert!(Codec::TextCodec.parse(text.as_bytes()).is_err());
+    let parse_options = ParseOptions {
+        text: TextDialect {
+            accounts: Some(sample_directory()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let parsed = Codec::TextCodec
+        .parse_with(text.as_bytes(), &parse_options)
+        .expect("names should resolve");
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn report_shows_names_along_with_accounts() {
+    let options = WriteOptions {
+        report: ReportOptions {
+            accounts: Some(sample_directory()),
+        },
+        ..Default::default()
+    };
+    let mut report = Vec::new();
+    Codec::ReportCodec
+        .write_with(&mut report, &[sample_tx(4021, 7)], &options)
+        .expect("report write");
+    let report = String::from_utf8(report).unwrap();
+    assert!(report.contains("From:        payroll (4021)\n"));
+    assert!(report.contains("To:          7\n"));
+}
@@ -0,0 +1,65 @@
+use parser::codecs::base::Codec;
+use parser::codecs::traits::{DataParser, DataWriter};
+use parser::domain::tx::{TxIdType, TxRecord};
+use parser::errors::AppError;
+use std::io::{Read, Write};
+
+fn records() -> Vec<TxRecord> {
+    (1..=3)
+        .map(|id| TxRecord {
+            id: TxIdType::Numeric(id),
+            description: Some(format!("payment {}", id)),
+            ..Default::default()
+        })
+        .collect()
+}
+
+// downstream codec writing one id per line
+struct IdLines;
+
+impl DataParser for IdLines {
+    fn parse(&self, r: &mut dyn Read) -> Result<Vec<TxRecord>, AppError> {
+        let mut input = String::new();
+        r.read_to_string(&mut input).map_err(AppError::ReadError)?;
+        Ok(input
+            .lines()
+            .map(|line| TxRecord {
+                id: TxIdType::Numeric(line.parse().unwrap()),
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+impl DataWriter for IdLines {
+    fn write(&self, w: &mut dyn Write, data: &[TxRecord]) -> Result<(), AppError> {
+        for tx in data {
+            writeln!(w, "{}", tx.id).map_err(AppError::WriteError)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn codecs_selected_at_runtime_are_held_as_trait_objects() {
+    let data = records();
+    let codecs: Vec<(Box<dyn DataWriter>, Box<dyn DataParser>)> = vec![
+        (Box::new(Codec::CsvCodec), Box::new(Codec::CsvCodec)),
+        (Box::new(Codec::TextCodec), Box::new(Codec::TextCodec)),
+        (Box::new(Codec::BinaryCodec), Box::new(Codec::BinaryCodec)),
+        (Box::new(IdLines), Box::new(IdLines)),
+    ];
+    for (writer, parser) in &codecs {
+        let mut out = Vec::new();
+        writer.write(&mut out, &data).unwrap();
+        let parsed = parser.parse(&mut out.as_slice()).unwrap();
+        let ids: Vec<u64> = parsed.iter().filter_map(|tx| tx.id.as_numeric()).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+    let mut out = Vec::new();
+    DataWriter::write(&Codec::CsvCodec, &mut out, &data).unwrap();
+    assert_eq!(
+        DataParser::parse(&Codec::CsvCodec, &mut out.as_slice()).unwrap(),
+        data
+    );
+}
@@ -0,0 +1,80 @@
+use parser::codecs::base::Codec;
+use parser::codecs::errors::ParserError;
+use parser::codecs::options::{ParseOptions, Strictness};
+use parser::errors::AppError;
+
+const CSV_HEADER: &str =
+    "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n";
+
+const ALL_CODECS: [Codec; 3] = [Codec::BinaryCodec, Codec::TextCodec, Codec::CsvCodec];
+
+fn strict() -> ParseOptions {
+    ParseOptions {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn lenient_empty_and_whitespace_inputs_give_no_records() {
+    for codec in ALL_CODECS {
+        for input in ["", " \n\t\r\n  \n"] {
+            let parsed = codec
+                .parse(input.as_bytes())
+                .unwrap_or_else(|e| panic!("{:?} should accept {:?}: {}", codec, input, e));
+            assert!(parsed.is_empty());
+        }
+    }
+}
+
+#[test]
+fn strict_empty_and_whitespace_inputs_are_rejected() {
+    for codec in ALL_CODECS {
+        for input in ["", " \n\t\r\n  \n"] {
+            let err = codec
+                .parse_with(input.as_bytes(), &strict())
+                .expect_err("strict mode should reject input without records");
+            assert!(matches!(
+                err,
+                AppError::ParsingError {
+                    source: ParserError::EmptyInput,
+                    ..
+                }
+            ));
+        }
+    }
+}
+
+#[test]
+fn csv_header_only_and_blank_lines() {
+    let input = format!("\n{}\n  \n", CSV_HEADER);
+    let parsed = Codec::CsvCodec
+        .parse(input.as_bytes())
+        .expect("header with blank lines should parse");
+    assert!(parsed.is_empty());
+
+    let err = Codec::CsvCodec
+        .parse_with(CSV_HEADER.as_bytes(), &strict())
+        .expect_err("strict mode should reject header-only csv");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::EmptyInput,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn binary_leading_whitespace_before_data_is_rejected() {
+    let err = Codec::BinaryCodec
+        .parse(b"  YPBN".as_slice())
+        .expect_err("binary data can't start with whitespace");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::InvalidRecordHeader(_),
+            ..
+        }
+    ));
+}
@@ -0,0 +1,140 @@
+#![cfg(feature = "encryption")]
+
+use parser::codecs::base::Codec;
+use parser::codecs::encryption::{EncryptedReader, EncryptedWriter, EncryptionKey, is_encrypted};
+use parser::codecs::errors::{ParserContext, ParserError};
+use parser::codecs::options::{ParseOptions, WriteOptions};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use std::io::{Read, Write};
+
+// "sealed at rest\n" with key of "auditor passphrase", 1000 iterations, fixed salt and nonce
+const KNOWN_CONTAINER: [u8; 68] = [
+    0x59, 0x50, 0x45, 0x4E, 0x01, 0x00, 0x00, 0x03, 0xE8, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+    0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A,
+    0x6B, 0x6C, 0x6D, 0x6E, 0x6F, 0xD8, 0x98, 0xAA, 0x4D, 0x83, 0x19, 0x90, 0xCF, 0x8A, 0x42, 0x8B,
+    0x32, 0xF7, 0x5A, 0x59, 0x60, 0x89, 0xCA, 0xDA, 0xCF, 0xA6, 0x81, 0xD5, 0x3A, 0xCE, 0x7E, 0x3A,
+    0x0F, 0xAF, 0xBB, 0x70,
+];
+
+fn sample(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Withdrawal,
+        from: AccountType::Numeric(id % 5 + 1),
+        to: AccountType::Numeric(0),
+        amount: Money::from_minor_units(100 * id as i64),
+        ts: TxTimestamp::from_millis(1_700_000 + id),
+        status: TxStatus::Success,
+        description: Some(format!("card holder {}", id)),
+        ..Default::default()
+    }
+}
+
+// few iterations keep tests fast, they are stored in the container
+fn key(secret: &str) -> EncryptionKey {
+    EncryptionKey::new(secret).with_iterations(1000)
+}
+
+fn encrypt(data: &[u8], key: &EncryptionKey) -> Vec<u8> {
+    let mut w = EncryptedWriter::new(Vec::new(), key);
+    w.write_all(data).unwrap();
+    w.finish().unwrap()
+}
+
+fn decrypt(data: &[u8], key: &EncryptionKey) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::new();
+    EncryptedReader::new(data, key)?
+        .read_to_end(&mut out)
+        .unwrap();
+    Ok(out)
+}
+
+fn failed_at(err: AppError) -> (ParserError, usize) {
+    match err {
+        AppError::ParsingError {
+            source,
+            context: ParserContext::Position { position },
+        } => (source, position),
+        other => panic!("unexpected error {}", other),
+    }
+}
+
+#[test]
+fn encrypted_round_trip_for_every_codec() {
+    let data: Vec<TxRecord> = (1..20).map(sample).collect();
+    let key = key("s3cret");
+    for codec in [Codec::CsvCodec, Codec::TextCodec, Codec::BinaryCodec] {
+        let mut encrypted = Vec::new();
+        codec
+            .write_encrypted(&mut encrypted, &data, &WriteOptions::default(), &key)
+            .expect("encrypted write should succeed");
+        assert!(is_encrypted(&encrypted));
+        assert!(
+            !encrypted
+                .windows(b"card holder".len())
+                .any(|w| w == b"card holder")
+        );
+        let parsed = codec
+            .parse_encrypted(encrypted.as_slice(), &ParseOptions::default(), &key)
+            .expect("encrypted input should parse");
+        assert_eq!(parsed, data);
+    }
+}
+
+#[test]
+fn encrypted_container_of_known_layout_is_read() {
+    let content = decrypt(&KNOWN_CONTAINER, &EncryptionKey::new("auditor passphrase")).unwrap();
+    assert_eq!(content, b"sealed at rest\n");
+}
+
+#[test]
+fn every_container_gets_fresh_salt_and_nonce() {
+    let key = key("s3cret");
+    let first = encrypt(b"same content", &key);
+    let second = encrypt(b"same content", &key);
+    assert_ne!(first, second);
+    assert_eq!(
+        decrypt(&first, &key).unwrap(),
+        decrypt(&second, &key).unwrap()
+    );
+}
+
+#[test]
+fn wrong_key_and_altered_input_are_rejected() {
+    let encrypted = encrypt(b"payroll", &key("s3cret"));
+    let (err, position) = failed_at(decrypt(&encrypted, &key("guess")).unwrap_err());
+    assert!(matches!(err, ParserError::DecryptionFailed));
+    assert_eq!(position, 37);
+
+    // salt, ciphertext and tag are all authenticated
+    for altered in [10, 40, encrypted.len() - 1] {
+        let mut tampered = encrypted.clone();
+        tampered[altered] ^= 0x01;
+        let (err, _) = failed_at(decrypt(&tampered, &key("s3cret")).unwrap_err());
+        assert!(matches!(err, ParserError::DecryptionFailed));
+    }
+}
+
+#[test]
+fn malformed_containers_are_rejected() {
+    let (err, position) = failed_at(decrypt(b"TX_ID,TX_TYPE", &key("s3cret")).unwrap_err());
+    assert!(matches!(err, ParserError::InvalidFileHeader));
+    assert_eq!(position, 0);
+
+    let mut future = KNOWN_CONTAINER;
+    future[4] = 2;
+    let (err, position) = failed_at(decrypt(&future, &key("s3cret")).unwrap_err());
+    assert!(matches!(err, ParserError::UnparsableValue(_)));
+    assert_eq!(position, 4);
+
+    let (err, _) = failed_at(decrypt(&KNOWN_CONTAINER[..40], &key("s3cret")).unwrap_err());
+    assert!(matches!(err, ParserError::DecryptionFailed));
+}
+
+#[test]
+fn key_debug_output_hides_secret() {
+    let printed = format!("{:?}", EncryptionKey::new("hunter2"));
+    assert!(!printed.contains("hunter2"));
+    assert!(printed.contains("600000"));
+}
@@ -0,0 +1,171 @@
+use parser::codecs::base::Codec;
+use parser::codecs::binary_v2::BinaryV2Options;
+use parser::codecs::csv::CsvDialect;
+use parser::codecs::options::{ParseOptions, WriteOptions};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64, fee: Option<i64>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Deposit,
+        from: AccountType::Numeric(0),
+        to: AccountType::Numeric(7),
+        amount: Money::from_minor_units(1_000),
+        fee: fee.map(Money::from_minor_units),
+        ts: TxTimestamp::from_millis(1700),
+        status: TxStatus::Success,
+        description: Some("card".into()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn fee_round_trips_in_all_codecs() {
+    let data = vec![
+        sample_tx(1, Some(25)),
+        sample_tx(2, None),
+        sample_tx(3, Some(i64::MIN)),
+    ];
+    for codec in [
+        Codec::BinaryCodec,
+        Codec::BinaryV2Codec,
+        Codec::ColumnarBinaryCodec,
+        Codec::TextCodec,
+        Codec::CsvCodec,
+        Codec::TsvCodec,
+    ] {
+        let mut bytes = Vec::new();
+        codec
+            .write(&mut bytes, &data)
+            .expect("write should succeed");
+        let parsed = codec.parse(bytes.as_slice()).expect("parse should succeed");
+        assert_eq!(parsed, data, "{:?} should keep fee", codec);
+    }
+}
+
+#[test]
+fn fee_scales_round_trip_in_binary_codecs() {
+    let data = vec![
+        TxRecord {
+            fee: Some(Money::new(1_234, 3)),
+            ..sample_tx(1, None)
+        },
+        TxRecord {
+            fee: Some(Money::new(5, 0)),
+            ..sample_tx(2, None)
+        },
+    ];
+    for codec in [
+        Codec::BinaryCodec,
+        Codec::BinaryV2Codec,
+        Codec::ColumnarBinaryCodec,
+        #[cfg(feature = "bincode")]
+        Codec::BincodeCodec,
+        #[cfg(feature = "capnp")]
+        Codec::CapnpCodec,
+    ] {
+        let mut bytes = Vec::new();
+        codec
+            .write(&mut bytes, &data)
+            .expect("write should succeed");
+        let parsed = codec.parse(bytes.as_slice()).expect("parse should succeed");
+        assert_eq!(parsed, data, "{:?}", codec);
+        let scales: Vec<_> = parsed
+            .iter()
+            .map(|tx| tx.fee.map(|fee| fee.scale))
+            .collect();
+        assert_eq!(
+            scales,
+            [Some(3), Some(0)],
+            "{:?} should keep fee scale",
+            codec
+        );
+    }
+}
+
+#[test]
+fn records_without_fee_keep_original_layout() {
+    let data = vec![sample_tx(1, None)];
+
+    let mut csv = Vec::new();
+    Codec::CsvCodec.write(&mut csv, &data).expect("csv write");
+    assert!(String::from_utf8(csv).unwrap().starts_with(
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n"
+    ));
+
+    let mut text = Vec::new();
+    Codec::TextCodec
+        .write(&mut text, &data)
+        .expect("text write");
+    assert!(!String::from_utf8(text).unwrap().contains("FEE"));
+}
+
+#[test]
+fn csv_fee_column_comes_with_tenant_one() {
+    let mut csv = Vec::new();
+    Codec::CsvCodec
+        .write(&mut csv, &[sample_tx(1, Some(25))])
+        .expect("csv write");
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.starts_with(
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,TENANT,FEE\n"
+    ));
+    assert!(csv.ends_with(",\"card\",,25\n"));
+
+    // headerless input may have fee column as well, empty fee is absent one
+    let input = "1,DEPOSIT,0,7,1000,1700,SUCCESS,\"card\",,25\n2,DEPOSIT,0,7,1000,1700,SUCCESS,\"card\",,\n";
+    let options = ParseOptions {
+        csv: CsvDialect {
+            has_header: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let parsed = Codec::CsvCodec
+        .parse_with(input.as_bytes(), &options)
+        .expect("headerless csv should parse");
+    assert_eq!(parsed, vec![sample_tx(1, Some(25)), sample_tx(2, None)]);
+}
+
+#[test]
+fn binary_v2_stream_needs_fees_option() {
+    let data = vec![sample_tx(1, Some(25))];
+    let err = Codec::BinaryV2Codec
+        .open_sink(Vec::new(), &WriteOptions::default())
+        .and_then(|mut sink| sink.push(&data[0]))
+        .expect_err("fee needs the option");
+    assert!(matches!(err, AppError::WriteError(_)));
+
+    let options = WriteOptions {
+        binary_v2: BinaryV2Options {
+            fees: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bytes = Vec::new();
+    let mut sink = Codec::BinaryV2Codec
+        .open_sink(&mut bytes, &options)
+        .expect("sink should open");
+    sink.push(&data[0]).expect("push should succeed");
+    sink.finish().expect("finish should succeed");
+    assert_eq!(Codec::BinaryV2Codec.parse(bytes.as_slice()).unwrap(), data);
+}
+
+#[test]
+fn net_amount_is_amount_without_fee() {
+    assert_eq!(
+        sample_tx(1, Some(25)).net_amount(),
+        Some(Money::from_minor_units(975))
+    );
+    assert_eq!(
+        sample_tx(1, None).net_amount(),
+        Some(Money::from_minor_units(1_000))
+    );
+    let overflowing = TxRecord {
+        amount: Money::from_minor_units(i64::MIN),
+        ..sample_tx(1, Some(1))
+    };
+    assert_eq!(overflowing.net_amount(), None);
+}
@@ -0,0 +1,117 @@
+use parser::codecs::errors::{ParserContext, ParserError};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use parser::filter::Filter;
+
+fn sample_tx(id: u64, kind: TxKind, amount: i64, ts: &str) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(20 + id),
+        amount: Money::new(amount, 2),
+        ts: TxTimestamp::parse_rfc3339(ts).expect("timestamp should parse"),
+        status: TxStatus::Success,
+        description: Some(format!("Invoice {} paid", id)),
+        ..Default::default()
+    }
+}
+
+fn dataset() -> Vec<TxRecord> {
+    let mut data = vec![
+        sample_tx(1, TxKind::Transfer, 250_000, "2024-02-01T10:00:00Z"),
+        sample_tx(2, TxKind::Transfer, 50_000, "2024-03-01T10:00:00Z"),
+        sample_tx(3, TxKind::Deposit, 300_000, "2024-01-15T10:00:00Z"),
+        sample_tx(4, TxKind::Transfer, 100_000, "2023-12-31T23:59:59Z"),
+    ];
+    data[1].status = TxStatus::Failure;
+    data[2].fee = Some(Money::new(150, 2));
+    data[3].description = Some("Refund of \"order 7\"".to_string());
+    data
+}
+
+fn matching_ids(filter: &str) -> Vec<u64> {
+    let filter = Filter::parse(filter).expect("filter should parse");
+    filter
+        .apply(&dataset())
+        .iter()
+        .map(|tx| match tx.id {
+            TxIdType::Numeric(id) => id,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+#[test]
+fn comparisons_are_combined() {
+    assert_eq!(
+        matching_ids("amount > 1000 && kind == TRANSFER && ts >= 2024-01-01"),
+        vec![1]
+    );
+    assert_eq!(
+        matching_ids("amount >= 1000.00 || status == FAILURE"),
+        vec![1, 2, 3, 4]
+    );
+    // `&&` binds tighter than `||`
+    assert_eq!(
+        matching_ids("kind == DEPOSIT || kind == TRANSFER && amount < 600"),
+        vec![2, 3]
+    );
+    assert_eq!(
+        matching_ids("(kind == DEPOSIT || kind == TRANSFER) && amount < 600"),
+        vec![2]
+    );
+    assert_eq!(matching_ids("!(kind == transfer) && fee > 1"), vec![3]);
+    assert_eq!(matching_ids("TX_ID <= 2 && TO_USER_ID != 21"), vec![2]);
+    assert_eq!(matching_ids("account == 23"), vec![3]);
+    assert_eq!(matching_ids("ts < 1704067200000"), vec![4]);
+    assert_eq!(matching_ids("ts > 2024-02-01T11:00:00+02:00"), vec![1, 2]);
+}
+
+#[test]
+fn missing_fields_satisfy_inequality_only() {
+    assert_eq!(matching_ids("fee > 0"), vec![3]);
+    assert_eq!(matching_ids("fee != 1.5"), vec![1, 2, 4]);
+    assert_eq!(matching_ids("tenant != acme"), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn text_fields_are_matched_exactly_or_by_substring() {
+    assert_eq!(matching_ids("description ~ invoice"), vec![1, 2, 3]);
+    assert_eq!(matching_ids("description ~ \"ORDER 7\""), vec![4]);
+    assert_eq!(
+        matching_ids("description == \"Refund of \\\"order 7\\\"\""),
+        vec![4]
+    );
+    assert_eq!(matching_ids("description == refund"), Vec::<u64>::new());
+}
+
+#[test]
+fn invalid_filters_report_position() {
+    let position = |filter: &str| match Filter::parse(filter) {
+        Err(AppError::ParsingError {
+            context: ParserContext::Position { position },
+            ..
+        }) => position,
+        other => panic!("{}: {:?}", filter, other),
+    };
+    assert_eq!(position("amount > ten"), 9);
+    assert_eq!(position("amount > 10 &&"), 14);
+    assert_eq!(position("kind < TRANSFER"), 5);
+    assert_eq!(position("description > a"), 12);
+    assert_eq!(position("(amount > 1"), 11);
+    assert_eq!(position("amount > 1)"), 10);
+    assert_eq!(position("amount = 1"), 7);
+    assert_eq!(position("description ~ \"open"), 14);
+    assert!(matches!(
+        Filter::parse("exchange_rate > 1"),
+        Err(AppError::ParsingError {
+            source: ParserError::UnparsableKey(_),
+            ..
+        })
+    ));
+    assert_eq!(
+        "amount > 1".parse::<Filter>().unwrap().to_string(),
+        "amount > 1"
+    );
+}
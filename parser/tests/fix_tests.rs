@@ -0,0 +1,85 @@
+use parser::codecs::base::Codec;
+use parser::codecs::errors::ParserError;
+use parser::codecs::fix::FixTagMapping;
+use parser::codecs::options::ParseOptions;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxStatus};
+use parser::errors::AppError;
+
+const LOG: &str = "\
+20240103-10:00:00.000 : 8=FIX.4.4|9=60|35=0|49=BROKER|56=US|10=001|
+20240103-10:00:01.000 : 8=FIX.4.4|9=120|35=8|37=1001|11=ORD-1|1=42|54=1|381=1500.25|60=20240103-10:00:01.123|39=2|58=buy AAPL|10=123|
+8=FIX.4.4\u{1}35=8\u{1}37=1002\u{1}1=42\u{1}448=77\u{1}54=2\u{1}381=10\u{1}60=20240103-11:00:00\u{1}39=0\u{1}10=124\u{1}
+";
+
+#[test]
+fn parse_execution_reports_skipping_other_messages() {
+    let records = Codec::FixCodec
+        .parse(LOG.as_bytes())
+        .expect("FIX log should parse");
+    assert_eq!(records.len(), 2);
+
+    assert_eq!(records[0].id, TxIdType::Numeric(1001));
+    assert_eq!(records[0].kind, TxKind::Withdrawal);
+    assert_eq!(records[0].from, AccountType::Numeric(42));
+    assert_eq!(records[0].amount, Money::from_minor_units(150_025));
+    assert_eq!(records[0].ts.millis(), 1_704_276_001_123);
+    assert_eq!(records[0].status, TxStatus::Success);
+    assert_eq!(records[0].description.as_deref(), Some("buy AAPL"));
+    assert_eq!(records[0].reference.as_deref(), Some("ORD-1"));
+
+    assert_eq!(records[1].kind, TxKind::Transfer);
+    assert_eq!(records[1].from, AccountType::Numeric(77));
+    assert_eq!(records[1].to, AccountType::Numeric(42));
+    assert_eq!(records[1].amount, Money::from_minor_units(1000));
+    assert_eq!(records[1].status, TxStatus::Pending);
+    assert_eq!(records[1].description, None);
+}
+
+#[test]
+fn parse_with_custom_tag_mapping() {
+    let input = "8=FIX.4.2|35=8|17=5|1=42|54=2|6=3.5|52=20240103-10:00:00|39=4|10=1|\n";
+    let options = ParseOptions {
+        fix: FixTagMapping {
+            id: 17,
+            amount: 6,
+            amount_scale: 1,
+            timestamp: 52,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let records = Codec::FixCodec
+        .parse_with(input.as_bytes(), &options)
+        .expect("remapped message should parse");
+    assert_eq!(records[0].id, TxIdType::Numeric(5));
+    assert_eq!(records[0].kind, TxKind::Deposit);
+    assert_eq!(records[0].amount, Money::new(35, 1));
+    assert_eq!(records[0].status, TxStatus::Cancelled);
+}
+
+#[test]
+fn parse_rejects_missing_and_invalid_tags() {
+    let input = "8=FIX.4.4|35=8|37=1|1=42|54=1|60=20240103-10:00:00|39=2|\n";
+    let err = Codec::FixCodec
+        .parse(input.as_bytes())
+        .expect_err("missing amount should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableValue(_),
+            ..
+        }
+    ));
+
+    let input = "8=FIX.4.4|35=8|X=1|\n";
+    let err = Codec::FixCodec
+        .parse(input.as_bytes())
+        .expect_err("non numeric tag should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableKey(_),
+            ..
+        }
+    ));
+}
@@ -0,0 +1,125 @@
+use parser::codecs::base::Codec;
+use parser::codecs::binary_v2::BinaryV2Options;
+use parser::codecs::options::WriteOptions;
+use parser::domain::tx::{
+    AccountType, ExchangeRate, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp,
+};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64, original_amount: Option<Money>, rate: Option<ExchangeRate>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(3),
+        to: AccountType::Numeric(7),
+        amount: Money::from_minor_units(10_825),
+        original_amount,
+        exchange_rate: rate,
+        ts: TxTimestamp::from_millis(1700),
+        status: TxStatus::Success,
+        description: Some("invoice".into()),
+        ..Default::default()
+    }
+}
+
+fn converted_tx(id: u64) -> TxRecord {
+    // 15000 JPY converted at 0.7217
+    sample_tx(
+        id,
+        Some(Money::new(15_000, 0)),
+        Some(ExchangeRate::new(7_217, 4)),
+    )
+}
+
+#[test]
+fn fx_fields_round_trip_in_all_codecs() {
+    let data = vec![
+        converted_tx(1),
+        sample_tx(2, None, None),
+        sample_tx(3, Some(Money::new(-1, 18)), None),
+        sample_tx(4, None, Some(ExchangeRate::new(i64::MAX, 0))),
+    ];
+    for codec in [
+        Codec::BinaryCodec,
+        Codec::BinaryV2Codec,
+        Codec::ColumnarBinaryCodec,
+        Codec::TextCodec,
+        Codec::CsvCodec,
+        Codec::TsvCodec,
+    ] {
+        let mut bytes = Vec::new();
+        codec
+            .write(&mut bytes, &data)
+            .expect("write should succeed");
+        let parsed = codec.parse(bytes.as_slice()).expect("parse should succeed");
+        assert_eq!(parsed, data, "{:?} should keep fx fields", codec);
+        // values are equal by value, scales must survive as well
+        assert_eq!(parsed[0].original_amount.unwrap().scale, 0, "{:?}", codec);
+        assert_eq!(parsed[0].exchange_rate.unwrap().scale, 4, "{:?}", codec);
+    }
+}
+
+#[test]
+fn fx_fields_are_decimals_in_text_formats() {
+    let data = vec![converted_tx(1)];
+
+    let mut csv = Vec::new();
+    Codec::CsvCodec.write(&mut csv, &data).expect("csv write");
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.starts_with(
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,TENANT,FEE,REFERENCE,ORIGINAL_AMOUNT,EXCHANGE_RATE\n"
+    ));
+    assert!(csv.ends_with(",\"invoice\",,,,15000,0.7217\n"));
+
+    let mut text = Vec::new();
+    Codec::TextCodec
+        .write(&mut text, &data)
+        .expect("text write");
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("ORIGINAL_AMOUNT: 15000\n"));
+    assert!(text.contains("EXCHANGE_RATE: 0.7217\n"));
+}
+
+#[test]
+fn binary_v2_stream_needs_exchange_rates_option() {
+    let data = vec![converted_tx(1)];
+    let err = Codec::BinaryV2Codec
+        .open_sink(Vec::new(), &WriteOptions::default())
+        .and_then(|mut sink| sink.push(&data[0]))
+        .expect_err("fx fields need the option");
+    assert!(matches!(err, AppError::WriteError(_)));
+
+    let options = WriteOptions {
+        binary_v2: BinaryV2Options {
+            exchange_rates: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bytes = Vec::new();
+    let mut sink = Codec::BinaryV2Codec
+        .open_sink(&mut bytes, &options)
+        .expect("sink should open");
+    sink.push(&data[0]).expect("push should succeed");
+    sink.finish().expect("finish should succeed");
+    assert_eq!(Codec::BinaryV2Codec.parse(bytes.as_slice()).unwrap(), data);
+}
+
+#[test]
+fn exchange_rate_converts_exactly() {
+    let rate: ExchangeRate = "1.0825".parse().expect("rate should parse");
+    assert_eq!(rate, ExchangeRate::new(10_825, 4));
+    assert_eq!(rate.to_string(), "1.0825");
+    assert_eq!(
+        rate.convert(Money::from_minor_units(10_000)),
+        Some(Money::new(108_250_000, 6))
+    );
+    assert_eq!(rate.convert(Money::new(1, 15)), None);
+    assert_eq!(
+        ExchangeRate::new(2, 0).convert(Money::from_minor_units(i64::MAX)),
+        None
+    );
+    // rates are compared by value
+    assert_eq!(ExchangeRate::new(110, 2), ExchangeRate::new(11, 1));
+    assert!(ExchangeRate::new(9, 1) < ExchangeRate::new(100, 2));
+}
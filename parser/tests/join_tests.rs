@@ -0,0 +1,134 @@
+use parser::codecs::base::Codec;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use parser::transform::join::{
+    AccountInfo, AccountMetadata, FROM_COUNTRY_KEY, FROM_OWNER_KEY, TO_COUNTRY_KEY, TO_OWNER_KEY,
+    join,
+};
+
+fn sample_tx(id: u64, kind: TxKind, from: u64, to: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(to),
+        amount: Money::from_minor_units(1_050),
+        ts: TxTimestamp::from_millis(1_704_276_930_500),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn info(owner: &str, country: &str) -> AccountInfo {
+    AccountInfo::new(owner, country).expect("account info should be valid")
+}
+
+const METADATA_CSV: &str = "ACCOUNT,OWNER,COUNTRY\n\
+    # payroll accounts\n\
+    11, Acme, Inc., US\n\
+    \n\
+    DE89370400440532013000,Müller GmbH,DE\n";
+
+#[test]
+fn metadata_is_read_from_csv_and_json() {
+    let metadata = AccountMetadata::read_csv(METADATA_CSV.as_bytes()).expect("CSV should load");
+    assert_eq!(metadata.len(), 2);
+    assert_eq!(
+        metadata.get(&AccountType::Numeric(11)),
+        Some(&info("Acme, Inc.", "US"))
+    );
+    assert_eq!(
+        metadata.get(&AccountType::Iban("DE89370400440532013000".into())),
+        Some(&info("Müller GmbH", "DE"))
+    );
+    assert_eq!(metadata.get(&AccountType::Numeric(22)), None);
+
+    let json = r#"{"11": {"owner": "Acme, Inc.", "country": "US"},
+        "DE89370400440532013000": {"owner": "Müller GmbH", "country": "DE"}}"#;
+    assert_eq!(
+        AccountMetadata::read_json(json.as_bytes()).expect("JSON should load"),
+        metadata
+    );
+}
+
+#[test]
+fn invalid_metadata_is_rejected() {
+    for csv in [
+        "11,Acme\n",
+        "11,Acme,us\n",
+        "11,Acme,USA\n",
+        "11,,US\n",
+        "eleven,Acme,US\n",
+        "11,Acme,US\n11,Other,DE\n",
+    ] {
+        let e = AccountMetadata::read_csv(csv.as_bytes()).expect_err(csv);
+        assert!(matches!(e, AppError::ParsingError { .. }), "{}", csv);
+    }
+    for json in [
+        "[]",
+        r#"{"11": {"owner": "Acme"}}"#,
+        r#"{"11": {"owner": "Acme", "country": "XX1"}}"#,
+    ] {
+        assert!(
+            AccountMetadata::read_json(json.as_bytes()).is_err(),
+            "{}",
+            json
+        );
+    }
+}
+
+#[test]
+fn records_are_joined_on_accounts_funds_move_between() {
+    let metadata = AccountMetadata::read_csv(METADATA_CSV.as_bytes()).expect("CSV should load");
+    let data = [
+        sample_tx(1, TxKind::Transfer, 11, 22),
+        // account 0 of deposits and withdrawals is not a real one
+        sample_tx(2, TxKind::Deposit, 11, 11),
+        sample_tx(3, TxKind::Withdrawal, 22, 0),
+    ];
+    let joined = join(&data, &metadata);
+    assert_eq!(joined.len(), 3);
+    assert_eq!(joined[0].tx, data[0]);
+    assert_eq!(joined[0].from, Some(info("Acme, Inc.", "US")));
+    assert_eq!(joined[0].to, None);
+    assert_eq!(joined[1].from, None);
+    assert_eq!(joined[1].to, Some(info("Acme, Inc.", "US")));
+    assert_eq!((&joined[2].from, &joined[2].to), (&None, &None));
+
+    let tx = joined[0].clone().into_record();
+    assert_eq!(tx.extras[FROM_OWNER_KEY], "Acme, Inc.");
+    assert_eq!(tx.extras[FROM_COUNTRY_KEY], "US");
+    assert!(!tx.extras.contains_key(TO_OWNER_KEY));
+    assert!(!tx.extras.contains_key(TO_COUNTRY_KEY));
+    assert!(
+        join(&data, &AccountMetadata::default())
+            .iter()
+            .all(|enriched| enriched.from.is_none() && enriched.to.is_none())
+    );
+}
+
+#[test]
+fn enriched_records_are_written_as_json_and_report() {
+    let metadata = AccountMetadata::read_csv(METADATA_CSV.as_bytes()).expect("CSV should load");
+    let mut tx = sample_tx(1, TxKind::Transfer, 11, 22);
+    tx.description = Some("rent \"march\"".into());
+    let enriched = join(&[tx], &metadata).remove(0);
+    assert_eq!(
+        enriched.to_json(),
+        "{\"tx_id\":1,\"kind\":\"TRANSFER\",\"from\":11,\"to\":22,\"amount\":10.50,\
+         \"fee\":null,\"original_amount\":null,\"exchange_rate\":null,\
+         \"timestamp\":\"2024-01-03T10:15:30.500Z\",\"status\":\"SUCCESS\",\
+         \"description\":\"rent \\\"march\\\"\",\"tenant\":null,\"reference\":null,\"extras\":{},\
+         \"from_account\":{\"owner\":\"Acme, Inc.\",\"country\":\"US\"},\"to_account\":null}"
+    );
+
+    let mut buff = Vec::new();
+    Codec::ReportCodec
+        .write(&mut buff, &[enriched.into_record()])
+        .expect("report write should succeed");
+    let text = String::from_utf8(buff).expect("report is utf-8");
+    assert!(text.contains("  FROM_COUNTRY: US\n"), "{}", text);
+    assert!(text.contains("  FROM_OWNER:   Acme, Inc.\n"), "{}", text);
+    assert!(text.contains("  Type:         TRANSFER\n"), "{}", text);
+}
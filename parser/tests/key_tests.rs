@@ -0,0 +1,119 @@
+use parser::codecs::base::{Codec, TxFieldKey};
+use parser::codecs::errors::ParserError;
+use parser::domain::key::{Fingerprint, RecordKey};
+use parser::domain::tx::{Money, TxIdType, TxRecord, TxStatus, TxTimestamp};
+
+fn sample_tx(id: u64, description: &str, tenant: Option<&str>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        ts: TxTimestamp::from_millis(1_700_000),
+        description: Some(description.to_string()),
+        tenant: tenant.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn identity_depends_only_on_key_fields() {
+    let a = sample_tx(1, "rent", None);
+    let b = sample_tx(1, "rent, corrected", None);
+
+    assert_ne!(
+        RecordKey::FullRecord.identity(&a),
+        RecordKey::FullRecord.identity(&b)
+    );
+    assert_eq!(
+        RecordKey::IdOnly.identity(&a),
+        RecordKey::IdOnly.identity(&b)
+    );
+    let key = RecordKey::Fields(vec![TxFieldKey::Id, TxFieldKey::Timestamp]);
+    assert_eq!(key.identity(&a), key.identity(&b));
+}
+
+#[test]
+fn identity_is_unambiguous() {
+    // absent and empty tenant, shifted field boundaries
+    assert_ne!(
+        RecordKey::FullRecord.identity(&sample_tx(1, "", None)),
+        RecordKey::FullRecord.identity(&sample_tx(1, "", Some("")))
+    );
+    let key = RecordKey::Fields(vec![TxFieldKey::Description, TxFieldKey::Tenant]);
+    assert_ne!(
+        key.identity(&sample_tx(1, "ab", Some("c"))),
+        key.identity(&sample_tx(1, "a", Some("bc")))
+    );
+}
+
+#[test]
+fn record_key_from_str() {
+    assert!(matches!("FULL".parse(), Ok(RecordKey::FullRecord)));
+    assert!(matches!(" ID ".parse(), Ok(RecordKey::IdOnly)));
+    match "TX_ID, AMOUNT".parse::<RecordKey>() {
+        Ok(RecordKey::Fields(fields)) => {
+            assert_eq!(fields, vec![TxFieldKey::Id, TxFieldKey::Amount])
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(matches!(
+        "TX_ID,NOPE".parse::<RecordKey>(),
+        Err(ParserError::UnparsableKey(_))
+    ));
+}
+
+#[test]
+fn fingerprint_is_stable() {
+    let tx = sample_tx(1, "rent", Some("acme"));
+    // pinned, fingerprints are stored by pipelines and must not change between releases
+    assert_eq!(
+        tx.fingerprint().to_string(),
+        "86a6b609bdb2f52409c59a42ffe182ea893bb381a3feaa1f72f4be4340c840ff"
+    );
+    assert_eq!(
+        tx.fingerprint().to_string().parse::<Fingerprint>().ok(),
+        Some(tx.fingerprint())
+    );
+    assert!("abc".parse::<Fingerprint>().is_err());
+    assert!("zz".repeat(32).parse::<Fingerprint>().is_err());
+}
+
+#[test]
+fn fingerprint_is_equal_across_formats() {
+    let data = vec![TxRecord {
+        amount: Money::new(150, 2),
+        ..sample_tx(1, "rent", None)
+    }];
+    let mut csv = Vec::new();
+    Codec::CsvCodec.write(&mut csv, &data).expect("csv write");
+    let mut binary = Vec::new();
+    Codec::BinaryCodec
+        .write(&mut binary, &data)
+        .expect("binary write");
+    let from_csv = Codec::CsvCodec.parse(csv.as_slice()).unwrap();
+    let from_binary = Codec::BinaryCodec.parse(binary.as_slice()).unwrap();
+    assert_eq!(from_csv[0].fingerprint(), from_binary[0].fingerprint());
+
+    // amounts are compared by value
+    let rescaled = TxRecord {
+        amount: Money::new(15, 1),
+        ..data[0].clone()
+    };
+    assert_eq!(rescaled.fingerprint(), data[0].fingerprint());
+}
+
+#[test]
+fn fingerprint_skips_excluded_fields() {
+    let pending = TxRecord {
+        status: TxStatus::Pending,
+        ..sample_tx(1, "rent", None)
+    };
+    let settled = TxRecord {
+        status: TxStatus::Success,
+        ..pending.clone()
+    };
+    assert_ne!(pending.fingerprint(), settled.fingerprint());
+    assert_eq!(
+        pending.fingerprint_excluding(&[TxFieldKey::Status]),
+        settled.fingerprint_excluding(&[TxFieldKey::Status])
+    );
+    assert_eq!(pending.fingerprint_excluding(&[]), pending.fingerprint());
+}
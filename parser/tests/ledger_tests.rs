@@ -0,0 +1,67 @@
+use parser::codecs::base::Codec;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64, from: u64, to: u64, status: TxStatus, description: &str) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(to),
+        amount: Money::from_minor_units(500),
+        ts: TxTimestamp::from_millis(1_704_276_930_000),
+        status,
+        description: Some(description.to_string()),
+        ..Default::default()
+    }
+}
+
+fn write_ledger(data: &[TxRecord]) -> String {
+    let mut buff = Vec::new();
+    Codec::LedgerCodec
+        .write(&mut buff, data)
+        .expect("ledger write should succeed");
+    String::from_utf8(buff).expect("ledger is utf-8")
+}
+
+#[test]
+fn write_ledger_entries() {
+    let mut deposit = sample_tx(2, 0, 22, TxStatus::Pending, "");
+    deposit.kind = TxKind::Deposit;
+    deposit.tenant = Some("acme".to_string());
+    let text = write_ledger(&[
+        sample_tx(1, 11, 22, TxStatus::Success, "rent\nmarch"),
+        deposit,
+    ]);
+    assert_eq!(
+        text,
+        "2024-01-03 * (1) rent march\n\
+         \x20   Assets:Accounts:22  500\n\
+         \x20   Assets:Accounts:11  -500\n\
+         \n\
+         2024-01-03 ! (2) DEPOSIT\n\
+         \x20   ; tenant: acme\n\
+         \x20   Assets:Accounts:22  500\n\
+         \x20   Equity:External  -500\n"
+    );
+}
+
+#[test]
+fn write_ledger_comments_out_failed_transactions() {
+    let text = write_ledger(&[sample_tx(3, 11, 0, TxStatus::Failure, "atm")]);
+    assert!(text.lines().all(|line| line.starts_with("; ")));
+    assert!(text.starts_with("; 2024-01-03 (3) atm\n"));
+
+    for status in [TxStatus::Cancelled, TxStatus::Reversed, TxStatus::Expired] {
+        let text = write_ledger(&[sample_tx(3, 11, 0, status, "atm")]);
+        assert!(text.lines().all(|line| line.starts_with("; ")));
+    }
+}
+
+#[test]
+fn ledger_is_write_only() {
+    let err = Codec::LedgerCodec
+        .parse(&b"2024-01-03 * payee\n"[..])
+        .expect_err("ledger can't be parsed");
+    assert!(matches!(err, AppError::ReadError(e) if e.kind() == std::io::ErrorKind::Unsupported));
+}
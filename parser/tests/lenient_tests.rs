@@ -0,0 +1,99 @@
+use parser::codecs::base::Codec;
+use parser::codecs::errors::{ParserContext, ParserError};
+use parser::codecs::options::{ParseOptions, Strictness};
+use parser::domain::tx::{TxIdType, TxRecord};
+
+const CSV_INPUT: &str = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION
+1,DEPOSIT,0,10,100,1700,SUCCESS,\"ok\"
+2,DEPOSIT,0,10,abc,1700,SUCCESS,\"bad amount\"
+3,DEPOSIT,0,10,300,1700,SUCCESS,\"ok\"
+4,DEPOSIT,0,10
+";
+
+#[test]
+fn csv_errors_are_collected_with_their_lines() {
+    let parsed = Codec::CsvCodec
+        .parse_lenient(CSV_INPUT.as_bytes(), &ParseOptions::default())
+        .expect("malformed records should be skipped");
+    assert_eq!(
+        parsed
+            .records
+            .iter()
+            .filter_map(|tx| tx.id.as_numeric())
+            .collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+    assert_eq!(parsed.errors.len(), 2);
+    assert!(matches!(
+        &parsed.errors[0],
+        (
+            ParserContext::LineNumAndLine { line_num: 2, .. },
+            ParserError::UnparsableValue(_)
+        )
+    ));
+    assert!(matches!(
+        &parsed.errors[1],
+        (
+            ParserContext::LineNumAndLine { line_num: 4, .. },
+            ParserError::IncompleteRecord
+        )
+    ));
+}
+
+#[test]
+fn binary_corrupt_records_are_collected() {
+    let data: Vec<TxRecord> = (1..=3)
+        .map(|id| TxRecord {
+            id: TxIdType::Numeric(id),
+            ..Default::default()
+        })
+        .collect();
+    let mut buff = Vec::new();
+    Codec::BinaryCodec.write(&mut buff, &data).unwrap();
+    // kind byte of the first record follows magic, size and id
+    buff[16] = 0xFF;
+
+    let parsed = Codec::BinaryCodec
+        .parse_lenient(buff.as_slice(), &ParseOptions::default())
+        .expect("corrupt record should be skipped");
+    assert_eq!(parsed.records, data[1..]);
+    assert_eq!(parsed.errors.len(), 1);
+}
+
+#[test]
+fn valid_input_has_no_errors() {
+    let parsed = Codec::CsvCodec
+        .parse_lenient(
+            CSV_INPUT
+                .lines()
+                .take(2)
+                .collect::<Vec<_>>()
+                .join("\n")
+                .as_bytes(),
+            &ParseOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(parsed.records.len(), 1);
+    assert!(parsed.errors.is_empty());
+}
+
+#[test]
+fn strict_mode_rejects_input_without_valid_records() {
+    let input = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION
+4,DEPOSIT,0,10
+";
+    let options = ParseOptions {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    };
+    assert!(
+        Codec::CsvCodec
+            .parse_lenient(input.as_bytes(), &options)
+            .is_err()
+    );
+    let parsed = Codec::CsvCodec
+        .parse_lenient(input.as_bytes(), &ParseOptions::default())
+        .unwrap();
+    assert!(parsed.records.is_empty());
+    assert_eq!(parsed.errors.len(), 1);
+}
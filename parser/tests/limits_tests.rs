@@ -0,0 +1,301 @@
+use parser::codecs::base::Codec;
+use parser::codecs::binary;
+use parser::codecs::compression::Compression;
+use parser::codecs::errors::{ParserContext, ParserError};
+use parser::codecs::options::{ParseLimits, ParseOptions};
+use parser::codecs::quarantine::QuarantineWriter;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Deposit,
+        from: AccountType::Numeric(0),
+        to: AccountType::Numeric(7),
+        amount: Money::from_minor_units(100 * id as i64),
+        ts: TxTimestamp::from_millis(1_700_000 + id),
+        status: TxStatus::Success,
+        description: Some(format!("deposit {}", id)),
+        ..Default::default()
+    }
+}
+
+fn written(codec: &Codec, data: &[TxRecord]) -> Vec<u8> {
+    let mut out = Vec::new();
+    codec.write(&mut out, data).expect("write should succeed");
+    out
+}
+
+fn limited(limits: ParseLimits) -> ParseOptions {
+    ParseOptions {
+        limits,
+        ..Default::default()
+    }
+}
+
+fn exceeded(err: AppError) -> ParserContext {
+    match err {
+        AppError::ParsingError {
+            context,
+            source: ParserError::LimitExceeded(_),
+        } => context,
+        other => panic!("unexpected error {}", other),
+    }
+}
+
+#[test]
+fn garbage_record_size_is_rejected_before_allocation() {
+    let mut bytes = written(&Codec::BinaryCodec, &[sample(1)]);
+    bytes[4..8].copy_from_slice(&u32::MAX.to_be_bytes());
+    let err = Codec::BinaryCodec.parse(bytes.as_slice()).unwrap_err();
+    assert!(err.to_string().contains("record of 4294967295 bytes"));
+    exceeded(err);
+
+    let mut stream = Codec::BinaryCodec.parse_stream(bytes.as_slice(), &ParseOptions::default());
+    exceeded(stream.next().unwrap().unwrap_err());
+    assert!(stream.next().is_none());
+
+    let report = binary::scan(bytes.as_slice(), &Default::default(), 10).unwrap();
+    assert!(report.aborted);
+    assert!(matches!(
+        report.issues[0].error,
+        ParserError::LimitExceeded(_)
+    ));
+}
+
+#[test]
+fn records_over_limit_fail_every_codec() {
+    let data: Vec<TxRecord> = (1..=3).map(sample).collect();
+    let options = limited(ParseLimits {
+        max_records: 2,
+        ..Default::default()
+    });
+    for codec in [
+        Codec::CsvCodec,
+        Codec::TextCodec,
+        Codec::BinaryCodec,
+        Codec::BinaryV2Codec,
+        Codec::ColumnarBinaryCodec,
+    ] {
+        let input = written(&codec, &data);
+        let context = exceeded(codec.parse_with(input.as_slice(), &options).unwrap_err());
+        assert!(matches!(context, ParserContext::Record { number: 3 }));
+        exceeded(codec.parse_lenient(input.as_slice(), &options).unwrap_err());
+
+        let streamed: Vec<_> = codec.parse_stream(input.as_slice(), &options).collect();
+        assert_eq!(streamed.len(), 3, "{:?}", codec);
+        assert!(streamed[..2].iter().all(Result::is_ok));
+        let err = streamed.into_iter().last().unwrap().unwrap_err();
+        exceeded(err);
+
+        // records up to the limit are accepted
+        let two = written(&codec, &data[..2]);
+        assert_eq!(
+            codec.parse_with(two.as_slice(), &options).unwrap(),
+            data[..2]
+        );
+    }
+}
+
+#[test]
+fn long_descriptions_are_rejected() {
+    let mut tx = sample(1);
+    tx.description = Some("x".repeat(65));
+    let options = limited(ParseLimits {
+        max_description_len: 64,
+        ..Default::default()
+    });
+    for codec in [
+        Codec::CsvCodec,
+        Codec::BinaryCodec,
+        Codec::ColumnarBinaryCodec,
+    ] {
+        let input = written(&codec, std::slice::from_ref(&tx));
+        let err = codec.parse_with(input.as_slice(), &options).unwrap_err();
+        assert!(err.to_string().contains("description of 65 bytes"));
+        assert!(matches!(exceeded(err), ParserContext::Record { number: 1 }));
+        assert_eq!(
+            codec
+                .parse_with(input.as_slice(), &limited(ParseLimits::unlimited()))
+                .unwrap(),
+            vec![tx.clone()]
+        );
+    }
+}
+
+#[test]
+fn long_lines_of_text_formats_are_rejected() {
+    let data = vec![sample(1), sample(2)];
+    let input = written(&Codec::CsvCodec, &data);
+    let longest = input.split(|&b| b == b'\n').map(<[u8]>::len).max().unwrap();
+    let second_line = input.iter().position(|&b| b == b'\n').unwrap() + 1;
+    let options = |max_record_size| {
+        limited(ParseLimits {
+            max_record_size,
+            ..Default::default()
+        })
+    };
+
+    assert_eq!(
+        Codec::CsvCodec
+            .parse_with(input.as_slice(), &options(longest))
+            .unwrap(),
+        data
+    );
+    // header is the longest line
+    let err = Codec::CsvCodec
+        .parse_with(input.as_slice(), &options(longest - 1))
+        .unwrap_err();
+    assert!(err.to_string().contains("line longer than"));
+    assert!(matches!(
+        exceeded(err),
+        ParserContext::Position { position: 0 }
+    ));
+
+    let mut padded = input.clone();
+    padded.splice(second_line..second_line, b"1".repeat(longest));
+    let err = Codec::CsvCodec
+        .parse_quarantined(
+            padded.as_slice(),
+            &options(longest),
+            &mut QuarantineWriter::new(Vec::new()),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        exceeded(err),
+        ParserContext::Position { position } if position == second_line
+    ));
+}
+
+// length prefix of the first description, found by its text
+fn description_at(bytes: &[u8]) -> usize {
+    bytes
+        .windows(9)
+        .position(|w| w == b"deposit 1")
+        .expect("description is written")
+}
+
+fn assert_garbage_length_rejected(codec: Codec, bytes: &[u8], message: &str) {
+    let err = codec.parse(bytes).unwrap_err();
+    assert!(err.to_string().contains(message), "{:?}: {}", codec, err);
+    exceeded(err);
+    let mut stream = codec.parse_stream(bytes, &ParseOptions::default());
+    exceeded(stream.next().unwrap().unwrap_err());
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn garbage_string_length_of_binary_v2_is_rejected() {
+    let mut bytes = written(&Codec::BinaryV2Codec, &[sample(1)]);
+    let at = description_at(&bytes);
+    // varint of u32::MAX in place of one byte length
+    bytes.splice(at - 1..at, [0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+    assert_garbage_length_rejected(Codec::BinaryV2Codec, &bytes, "string of 4294967295 bytes");
+}
+
+#[test]
+fn garbage_block_size_of_columnar_is_rejected() {
+    let mut bytes = written(&Codec::ColumnarBinaryCodec, &[sample(1)]);
+    bytes[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert_garbage_length_rejected(
+        Codec::ColumnarBinaryCodec,
+        &bytes,
+        "block of 4294967295 bytes",
+    );
+
+    let block = written(&Codec::ColumnarBinaryCodec, &[sample(1)]);
+    let options = limited(ParseLimits {
+        max_block_size: block.len() - 13,
+        ..Default::default()
+    });
+    let err = Codec::ColumnarBinaryCodec
+        .parse_with(block.as_slice(), &options)
+        .unwrap_err();
+    assert!(matches!(
+        exceeded(err),
+        ParserContext::Position { position: 0 }
+    ));
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn garbage_string_length_of_bincode_is_rejected() {
+    let mut bytes = written(&Codec::BincodeCodec, &[sample(1)]);
+    let at = description_at(&bytes);
+    bytes[at - 8..at].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert_garbage_length_rejected(
+        Codec::BincodeCodec,
+        &bytes,
+        "string of 18446744073709551615 bytes",
+    );
+}
+
+#[cfg(feature = "capnp")]
+#[test]
+fn garbage_segment_size_of_capnp_is_rejected() {
+    let mut bytes = written(&Codec::CapnpCodec, &[sample(1)]);
+    bytes[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_garbage_length_rejected(Codec::CapnpCodec, &bytes, "segment of 34359738360 bytes");
+}
+
+#[test]
+fn inputs_decoded_as_a_whole_are_bounded_by_block_size() {
+    let options = limited(ParseLimits {
+        max_block_size: 64,
+        ..Default::default()
+    });
+    let camt = format!("<Document>{}</Document>", " ".repeat(64));
+    let bai2 = "01,SENDER,RECEIVER,230101,0000,1,,,2/\n".repeat(3);
+    for (codec, input) in [(Codec::CamtCodec, camt), (Codec::Bai2Codec, bai2)] {
+        let err = codec.parse_with(input.as_bytes(), &options).unwrap_err();
+        exceeded(err);
+        let mut stream = codec.parse_stream(input.as_bytes(), &options);
+        exceeded(stream.next().unwrap().unwrap_err());
+    }
+}
+
+#[test]
+fn decompressed_input_is_bounded_by_block_size() {
+    let data: Vec<TxRecord> = (1..200).map(sample).collect();
+    let options = limited(ParseLimits {
+        max_block_size: 1024,
+        ..Default::default()
+    });
+    for compression in [
+        Compression::Gzip,
+        #[cfg(feature = "zstd")]
+        Compression::Zstd,
+    ] {
+        for codec in [Codec::CsvCodec, Codec::BinaryCodec] {
+            let mut compressed = Vec::new();
+            codec
+                .write_compressed(&mut compressed, &data, &Default::default(), compression)
+                .unwrap();
+            let err = codec
+                .parse_compressed(compressed.as_slice(), &options)
+                .unwrap_err();
+            exceeded(err);
+            assert_eq!(
+                codec
+                    .parse_compressed(compressed.as_slice(), &Default::default())
+                    .unwrap(),
+                data
+            );
+        }
+    }
+}
+
+#[test]
+fn records_limit_stops_parsing_before_the_rest_is_read() {
+    let mut input = written(&Codec::CsvCodec, &[sample(1), sample(2), sample(3)]);
+    input.extend_from_slice(b"not a record\n");
+    let options = limited(ParseLimits {
+        max_records: 2,
+        ..Default::default()
+    });
+    let err = Codec::CsvCodec
+        .parse_with(input.as_slice(), &options)
+        .unwrap_err();
+    assert!(matches!(exceeded(err), ParserContext::Record { number: 3 }));
+}
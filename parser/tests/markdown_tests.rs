@@ -0,0 +1,56 @@
+use parser::codecs::base::Codec;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(description: &str, tenant: Option<&str>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(1),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(-500),
+        ts: TxTimestamp::from_millis(1_700_000),
+        status: TxStatus::Pending,
+        description: Some(description.to_string()),
+        tenant: tenant.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn write_markdown(data: &[TxRecord]) -> String {
+    let mut buff = Vec::new();
+    Codec::MarkdownCodec
+        .write(&mut buff, data)
+        .expect("markdown write should succeed");
+    String::from_utf8(buff).expect("markdown is utf-8")
+}
+
+#[test]
+fn write_markdown_table() {
+    let text = write_markdown(&[sample_tx("a | b\nc", None)]);
+    assert_eq!(
+        text,
+        "| TX_ID | TX_TYPE | FROM_USER_ID | TO_USER_ID | AMOUNT | TIMESTAMP | STATUS | DESCRIPTION |\n\
+         | ---: | --- | ---: | ---: | ---: | ---: | --- | --- |\n\
+         | 1 | TRANSFER | 11 | 22 | -500 | 1700000 | PENDING | a \\| b<br>c |\n"
+    );
+}
+
+#[test]
+fn write_markdown_tenant_column_and_empty_table() {
+    let text = write_markdown(&[sample_tx("x", Some("acme")), sample_tx("y", None)]);
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].ends_with("| DESCRIPTION | TENANT |"));
+    assert!(lines[2].ends_with("| x | acme |"));
+    assert!(lines[3].ends_with("| y |  |"));
+
+    assert_eq!(write_markdown(&[]).lines().count(), 2);
+}
+
+#[test]
+fn markdown_is_write_only() {
+    let err = Codec::MarkdownCodec
+        .parse(&b"| TX_ID |\n"[..])
+        .expect_err("markdown can't be parsed");
+    assert!(matches!(err, AppError::ReadError(e) if e.kind() == std::io::ErrorKind::Unsupported));
+}
@@ -0,0 +1,130 @@
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::transform::merge::{Conflict, ConflictPolicy, MergeError, Origin, merge};
+
+fn sample_tx(id: u64, amount: i64, ts: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn origin(source: usize, position: usize) -> Origin {
+    Origin { source, position }
+}
+
+// three regional exports: id 2 is sent twice as is, id 3 is amended later
+fn regions() -> [Vec<TxRecord>; 3] {
+    [
+        vec![sample_tx(1, 100, 10), sample_tx(2, 200, 20)],
+        vec![sample_tx(2, 200, 20), sample_tx(3, 300, 30)],
+        vec![sample_tx(3, 350, 40), sample_tx(4, 400, 50)],
+    ]
+}
+
+#[test]
+fn keep_first_prefers_earlier_sources() {
+    let regions = regions();
+    let sources: Vec<&[TxRecord]> = regions.iter().map(Vec::as_slice).collect();
+    let merged = merge(&sources, ConflictPolicy::KeepFirst).expect("merge should succeed");
+    assert_eq!(
+        merged.records,
+        vec![
+            sample_tx(1, 100, 10),
+            sample_tx(2, 200, 20),
+            sample_tx(3, 300, 30),
+            sample_tx(4, 400, 50),
+        ]
+    );
+    assert_eq!(merged.read, vec![2, 2, 2]);
+    assert_eq!(merged.kept(), vec![2, 1, 1]);
+    assert_eq!(merged.duplicates, 1);
+    assert_eq!(
+        merged.conflicts,
+        vec![Conflict {
+            id: TxIdType::Numeric(3),
+            kept: origin(1, 1),
+            dropped: origin(2, 0),
+        }]
+    );
+    assert_eq!(
+        merged.conflicts[0].to_string(),
+        "TX_ID 3: source #1 record #1 kept, source #2 record #0 dropped"
+    );
+}
+
+#[test]
+fn keep_latest_replaces_records_in_place() {
+    let regions = regions();
+    let sources: Vec<&[TxRecord]> = regions.iter().map(Vec::as_slice).collect();
+    let merged = merge(&sources, ConflictPolicy::KeepLatest).expect("merge should succeed");
+    assert_eq!(merged.records[2], sample_tx(3, 350, 40));
+    assert_eq!(
+        merged.origins,
+        vec![origin(0, 0), origin(0, 1), origin(2, 0), origin(2, 1)]
+    );
+    assert_eq!(merged.kept(), vec![2, 0, 2]);
+    assert_eq!(
+        merged.conflicts,
+        vec![Conflict {
+            id: TxIdType::Numeric(3),
+            kept: origin(2, 0),
+            dropped: origin(1, 1),
+        }]
+    );
+
+    // earlier record wins a tie
+    let first = [sample_tx(1, 100, 10)];
+    let second = [sample_tx(1, 999, 10)];
+    let merged =
+        merge(&[&first, &second], ConflictPolicy::KeepLatest).expect("merge should succeed");
+    assert_eq!(merged.records, first.to_vec());
+    assert_eq!(merged.conflicts[0].dropped, origin(1, 0));
+}
+
+#[test]
+fn error_policy_fails_on_conflicts_only() {
+    let regions = regions();
+    assert_eq!(
+        merge(&[&regions[0], &regions[1]], ConflictPolicy::Error).map(|merged| merged.duplicates),
+        Ok(1)
+    );
+    let sources: Vec<&[TxRecord]> = regions.iter().map(Vec::as_slice).collect();
+    let e = merge(&sources, ConflictPolicy::Error).expect_err("conflict should fail");
+    assert_eq!(
+        e,
+        MergeError {
+            id: TxIdType::Numeric(3),
+            first: origin(1, 1),
+            second: origin(2, 0),
+        }
+    );
+    assert_eq!(
+        e.to_string(),
+        "TX_ID 3 of source #2 record #0 conflicts with source #1 record #1"
+    );
+}
+
+#[test]
+fn conflicts_within_one_source_are_resolved_too() {
+    let source = [
+        sample_tx(1, 100, 30),
+        sample_tx(1, 200, 10),
+        sample_tx(1, 300, 20),
+    ];
+    let merged = merge(&[&source], ConflictPolicy::KeepLatest).expect("merge should succeed");
+    assert_eq!(merged.records, vec![sample_tx(1, 100, 30)]);
+    assert_eq!(merged.conflicts.len(), 2);
+    assert!(
+        merge(&[], ConflictPolicy::Error)
+            .expect("nothing to merge")
+            .records
+            .is_empty()
+    );
+}
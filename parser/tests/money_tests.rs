@@ -0,0 +1,162 @@
+use parser::codecs::base::Codec;
+use parser::domain::money::{self, AmountOverflow};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(kind: TxKind, amount: Money) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(1),
+        kind,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount,
+        ts: TxTimestamp::from_millis(1700),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn sums_keep_the_finest_scale() {
+    let total = money::sum([Money::new(5, 0), Money::new(125, 2), Money::new(1, 3)])
+        .expect("sum should fit");
+    assert_eq!(total, Money::new(6_251, 3));
+    assert_eq!(total.scale, 3);
+    assert_eq!(money::sum([]), Ok(Money::new(0, 0)));
+    assert_eq!(
+        money::sub(Money::new(1, 0), Money::new(25, 2)),
+        Ok(Money::new(75, 2))
+    );
+}
+
+#[test]
+fn overflow_is_an_error() {
+    let max = Money::from_minor_units(i64::MAX);
+    assert_eq!(
+        money::add(max, Money::from_minor_units(1)),
+        Err(AmountOverflow)
+    );
+    assert_eq!(money::sum([max, max]), Err(AmountOverflow));
+    assert_eq!(
+        money::neg(Money::from_minor_units(i64::MIN)),
+        Err(AmountOverflow)
+    );
+    // rescaling to the finer scale overflows as well
+    assert_eq!(money::add(max, Money::new(1, 3)), Err(AmountOverflow));
+    let records = [
+        sample_tx(TxKind::Deposit, max),
+        sample_tx(TxKind::Deposit, max),
+    ];
+    assert_eq!(money::sum_amounts(&records), Err(AmountOverflow));
+}
+
+#[test]
+fn signs_follow_kinds() {
+    assert_eq!(TxKind::Deposit.flow_sign(), 1);
+    assert_eq!(TxKind::Withdrawal.flow_sign(), -1);
+    assert_eq!(TxKind::Fee.flow_sign(), -1);
+    for kind in [TxKind::Transfer, TxKind::Refund, TxKind::Reversal] {
+        assert_eq!(kind.flow_sign(), 0, "{}", kind);
+    }
+
+    let amount = Money::from_minor_units(500);
+    let records = [
+        sample_tx(TxKind::Deposit, amount),
+        sample_tx(TxKind::Withdrawal, Money::from_minor_units(120)),
+        sample_tx(TxKind::Transfer, amount),
+        sample_tx(TxKind::Fee, Money::from_minor_units(30)),
+    ];
+    assert_eq!(
+        money::signed_amount(&records[1]),
+        Ok(Money::from_minor_units(-120))
+    );
+    assert_eq!(money::net_flow(&records), Ok(Money::from_minor_units(350)));
+    assert_eq!(
+        money::sum_amounts(&records),
+        Ok(Money::from_minor_units(1_150))
+    );
+    assert_eq!(
+        money::signed_amount(&sample_tx(TxKind::Fee, Money::from_minor_units(i64::MIN))),
+        Err(AmountOverflow)
+    );
+}
+
+#[test]
+fn effect_on_account_fails_instead_of_saturating() {
+    let tx = sample_tx(TxKind::Transfer, Money::from_minor_units(i64::MIN));
+    assert_eq!(
+        money::effect_on(&tx, &AccountType::Numeric(22)),
+        Ok(Money::from_minor_units(i64::MIN))
+    );
+    assert_eq!(
+        money::effect_on(&tx, &AccountType::Numeric(11)),
+        Err(AmountOverflow)
+    );
+    assert_eq!(
+        money::effect_on(&tx, &AccountType::Numeric(33)),
+        Ok(Money::default())
+    );
+}
+
+#[test]
+fn amount_scales_round_trip_in_all_codecs() {
+    let data = vec![
+        sample_tx(TxKind::Deposit, Money::new(500, 0)),
+        sample_tx(TxKind::Withdrawal, Money::new(1_234, 3)),
+        sample_tx(TxKind::Deposit, Money::from_minor_units(25)),
+    ];
+    for codec in [
+        Codec::BinaryCodec,
+        Codec::BinaryV2Codec,
+        Codec::ColumnarBinaryCodec,
+        #[cfg(feature = "bincode")]
+        Codec::BincodeCodec,
+        #[cfg(feature = "capnp")]
+        Codec::CapnpCodec,
+    ] {
+        let mut bytes = Vec::new();
+        codec
+            .write(&mut bytes, &data)
+            .expect("write should succeed");
+        let parsed = codec.parse(bytes.as_slice()).expect("parse should succeed");
+        assert_eq!(parsed, data, "{:?}", codec);
+        let scales: Vec<u8> = parsed.iter().map(|tx| tx.amount.scale).collect();
+        assert_eq!(scales, [0, 3, 2], "{:?} should keep scales", codec);
+    }
+}
+
+#[test]
+fn minor_units_formats_rescale_to_cents() {
+    // amounts in cents keep their value, scale of read amounts is the default one
+    let data = vec![
+        sample_tx(TxKind::Deposit, Money::new(500, 0)),
+        sample_tx(TxKind::Withdrawal, Money::new(1_230, 3)),
+    ];
+    let finer = vec![sample_tx(TxKind::Withdrawal, Money::new(1_234, 3))];
+    for codec in [
+        Codec::TextCodec,
+        Codec::CsvCodec,
+        Codec::TsvCodec,
+        #[cfg(feature = "xlsx")]
+        Codec::XlsxCodec,
+    ] {
+        let mut bytes = Vec::new();
+        codec
+            .write(&mut bytes, &data)
+            .expect("write should succeed");
+        let parsed = codec.parse(bytes.as_slice()).expect("parse should succeed");
+        assert_eq!(parsed, data, "{:?}", codec);
+        assert_eq!(parsed[0].amount.minor_units, 50_000, "{:?}", codec);
+        assert!(
+            parsed
+                .iter()
+                .all(|tx| Money::DEFAULT_SCALE == tx.amount.scale)
+        );
+
+        let err = codec
+            .write(&mut Vec::new(), &finer)
+            .expect_err("amount finer than cents can't be written");
+        assert!(matches!(err, AppError::WriteError(_)), "{:?}", codec);
+    }
+}
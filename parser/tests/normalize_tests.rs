@@ -0,0 +1,61 @@
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::transform::normalize::{Normalizer, TimestampPrecision, WhitespaceHandling};
+
+fn sample_tx(kind: TxKind, description: &str) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(1),
+        kind,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(500),
+        ts: TxTimestamp::from_millis(1_704_276_930_500),
+        status: TxStatus::Success,
+        description: Some(description.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn description_whitespace_is_normalized() {
+    let value = "  rent \n\t march ";
+    assert_eq!(WhitespaceHandling::Keep.apply(value), value);
+    assert_eq!(WhitespaceHandling::Trim.apply(value), "rent \n\t march");
+    assert_eq!(WhitespaceHandling::Collapse.apply(value), "rent march");
+    assert_eq!(WhitespaceHandling::Collapse.apply(" \n "), "");
+}
+
+#[test]
+fn timestamps_are_truncated() {
+    let ts = TxTimestamp::from_millis(1_704_276_930_500);
+    assert_eq!(TimestampPrecision::Millis.truncate(ts), ts);
+    assert_eq!(
+        TimestampPrecision::Seconds.truncate(ts),
+        TxTimestamp::from_millis(1_704_276_930_000)
+    );
+    assert_eq!(
+        TimestampPrecision::Days.truncate(ts),
+        TxTimestamp::from_millis(1_704_240_000_000)
+    );
+}
+
+#[test]
+fn outside_counterparties_are_zeroed() {
+    let normalizer = Normalizer::default();
+    let deposit = normalizer.normalize(&sample_tx(TxKind::Deposit, ""));
+    assert_eq!((deposit.from, deposit.to), (0.into(), 22.into()));
+    let fee = normalizer.normalize(&sample_tx(TxKind::Fee, ""));
+    assert_eq!((fee.from, fee.to), (11.into(), 0.into()));
+    let transfer = normalizer.normalize(&sample_tx(TxKind::Transfer, ""));
+    assert_eq!((transfer.from, transfer.to), (11.into(), 22.into()));
+}
+
+#[test]
+fn identity_normalizer_keeps_record() {
+    let tx = sample_tx(TxKind::Deposit, " a  b ");
+    assert_eq!(Normalizer::identity().normalize(&tx), tx);
+    assert_eq!(
+        tx.normalize().description.as_deref(),
+        Some("a b"),
+        "default normalizer collapses whitespace"
+    );
+}
@@ -0,0 +1,202 @@
+use parser::codecs::base::TxFieldKey;
+use parser::codecs::errors::{ParserContext, ParserError};
+use parser::codecs::schema::FieldValue;
+use parser::compare::{FieldDiff, RecordDiff};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use parser::transform::patch::{Amendment, apply_patch};
+
+fn sample_tx(id: u64, amount: i64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(1_700_000_000_000 + id),
+        status: TxStatus::Success,
+        description: Some(format!("record {}", id)),
+        ..Default::default()
+    }
+}
+
+fn field(field: TxFieldKey, old: Option<&str>, new: Option<&str>) -> FieldDiff {
+    FieldDiff {
+        field,
+        old: old.map(str::to_string),
+        new: new.map(str::to_string),
+    }
+}
+
+const AMENDMENTS: &str = r##"# corrections of 2024-03-05
+TX_ID: 2
+AMOUNT: 4.50
+STATUS: FAILURE
+
+DESCRIPTION: "refund of \"#1\""
+TX_ID: 3
+FEE: 0.1
+TIMESTAMP: 2023-11-14T22:13:20Z
+
+TX_ID: 9
+STATUS: PENDING
+"##;
+
+#[test]
+fn amendments_are_read_from_blocks() {
+    let amendments = Amendment::read(AMENDMENTS.as_bytes()).expect("amendments should load");
+    assert_eq!(amendments.len(), 3);
+    assert_eq!(amendments[0].id, TxIdType::Numeric(2));
+    assert_eq!(
+        amendments[0].changes(),
+        &[
+            (
+                TxFieldKey::Amount,
+                Some(FieldValue::Amount(Money::new(450, 2)))
+            ),
+            (
+                TxFieldKey::Status,
+                Some(FieldValue::Status(TxStatus::Failure))
+            ),
+        ]
+    );
+    // id may follow fields, text is unescaped
+    assert_eq!(amendments[1].id, TxIdType::Numeric(3));
+    assert_eq!(
+        amendments[1].changes()[0],
+        (
+            TxFieldKey::Description,
+            Some(FieldValue::Text("refund of \"#1\"".into()))
+        )
+    );
+    assert_eq!(
+        amendments[1].changes()[2],
+        (
+            TxFieldKey::Timestamp,
+            Some(FieldValue::Timestamp(TxTimestamp::from_millis(
+                1_700_000_000_000
+            )))
+        )
+    );
+    assert!(
+        Amendment::read("\n# nothing\n\n".as_bytes())
+            .expect("empty file should load")
+            .is_empty()
+    );
+}
+
+#[test]
+fn invalid_amendments_are_rejected_with_their_lines() {
+    let e = Amendment::read("TX_ID: 1\n\nSTATUS: SUCCESS\nAMOUNT: 1\n".as_bytes())
+        .expect_err("block without id should fail");
+    assert!(matches!(
+        e,
+        AppError::ParsingError {
+            context: ParserContext::LineNumAndLine { line_num: 3, .. },
+            source: ParserError::MissingField(TxFieldKey::Id),
+        }
+    ));
+
+    for (input, line) in [
+        ("TX_ID: 1\nSTATUS: DONE\n", 2),
+        ("TX_ID: 1\nAMOUNT:\n", 2),
+        ("TX_ID: 1\nFEE: 1\nFEE: 2\n", 3),
+        ("TX_ID: 1\nTX_ID: 2\n", 2),
+        ("TX_ID: 1\nCOLOR: red\n", 2),
+        ("TX_ID: 1\nSTATUS SUCCESS\n", 2),
+    ] {
+        let e = Amendment::read(input.as_bytes()).expect_err(input);
+        assert!(
+            matches!(
+                e,
+                AppError::ParsingError {
+                    context: ParserContext::LineNumAndLine { line_num, .. },
+                    ..
+                } if line_num == line
+            ),
+            "{}: {:?}",
+            input,
+            e
+        );
+    }
+
+    let amendment = Amendment::new(TxIdType::Numeric(1));
+    assert!(
+        amendment
+            .clone()
+            .with_change(TxFieldKey::Id, Some(FieldValue::Id(TxIdType::Numeric(2))))
+            .is_err()
+    );
+    assert!(
+        amendment
+            .with_change(
+                TxFieldKey::Amount,
+                Some(FieldValue::Status(TxStatus::Success))
+            )
+            .is_err()
+    );
+}
+
+#[test]
+fn patch_corrects_records_and_keeps_audit_trail() {
+    let base = vec![sample_tx(1, 100), sample_tx(2, 300), sample_tx(3, 500)];
+    let amendments = Amendment::read(AMENDMENTS.as_bytes()).expect("amendments should load");
+    let patched = apply_patch(&base, &amendments);
+
+    assert_eq!(patched.records.len(), 3);
+    assert_eq!(patched.records[0], base[0]);
+    // amounts keep the scale of the record
+    assert_eq!(patched.records[1].amount.minor_units, 450);
+    assert_eq!(patched.records[1].status, TxStatus::Failure);
+    assert_eq!(patched.records[2].fee, Some(Money::new(10, 2)));
+    assert_eq!(patched.records[2].ts.millis(), 1_700_000_000_000);
+    assert_eq!(patched.unmatched, vec![TxIdType::Numeric(9)]);
+    assert_eq!(patched.unchanged, 0);
+
+    assert_eq!(
+        patched.applied[0],
+        RecordDiff {
+            id: TxIdType::Numeric(2),
+            fields: vec![
+                field(TxFieldKey::Amount, Some("3"), Some("4.5")),
+                field(TxFieldKey::Status, Some("SUCCESS"), Some("FAILURE")),
+            ],
+        }
+    );
+    assert_eq!(
+        patched.applied[1].to_string(),
+        "#3 TIMESTAMP: 1700000000003 -> 1700000000000, DESCRIPTION: record 3 -> refund of \"#1\", \
+         FEE: - -> 0.1"
+    );
+}
+
+#[test]
+fn later_amendments_override_and_no_op_ones_are_counted() {
+    let mut base = vec![sample_tx(1, 100), sample_tx(1, 200)];
+    base[0].fee = Some(Money::from_minor_units(5));
+    let amendments = Amendment::read(
+        "TX_ID: 1\nSTATUS: PENDING\nFEE:\n\nTX_ID: 1\nSTATUS: FAILURE\n\nTX_ID: 1\nSTATUS: FAILURE\n"
+            .as_bytes(),
+    )
+    .expect("amendments should load");
+    let patched = apply_patch(&base, &amendments);
+
+    // every record of the id is amended
+    assert!(
+        patched
+            .records
+            .iter()
+            .all(|tx| TxStatus::Failure == tx.status && tx.fee.is_none())
+    );
+    assert_eq!(patched.applied.len(), 4);
+    assert_eq!(
+        patched.applied[0].fields,
+        vec![
+            field(TxFieldKey::Status, Some("SUCCESS"), Some("PENDING")),
+            field(TxFieldKey::Fee, Some("0.05"), None),
+        ]
+    );
+    assert_eq!(patched.unchanged, 2);
+    assert!(patched.unmatched.is_empty());
+    assert_eq!(apply_patch(&[], &amendments).unmatched.len(), 3);
+}
@@ -0,0 +1,148 @@
+use parser::codecs::base::Codec;
+use parser::codecs::errors::ParserError;
+use parser::codecs::options::{ParseOptions, WriteOptions};
+use parser::domain::key::RecordKey;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use parser::transform::pipeline::Pipeline;
+use parser::transform::sort::{ExternalSorter, SortField, SortKey, SortOrder};
+
+fn sample_tx(id: u64, amount: i64, ts: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn ids(records: &[TxRecord]) -> Vec<TxIdType> {
+    records.iter().map(|tx| tx.id).collect()
+}
+
+fn by_timestamp() -> SortOrder {
+    SortOrder::new(vec![SortKey::asc(SortField::Timestamp)])
+}
+
+#[test]
+fn stages_run_in_order() {
+    let data = vec![
+        sample_tx(1, 500, 30),
+        sample_tx(2, 50, 10),
+        sample_tx(1, 500, 20),
+        sample_tx(3, 700, 40),
+        sample_tx(4, 900, 0),
+    ];
+    let pipeline = Pipeline::new()
+        .filter_expr("amount >= 1".parse().expect("filter should compile"))
+        .dedup(RecordKey::IdOnly)
+        .map(|mut tx| {
+            tx.description = Some(format!("#{}", tx.id));
+            tx
+        })
+        .sort(by_timestamp());
+    assert_eq!(pipeline.len(), 4);
+    let records = pipeline
+        .run(data.into_iter().map(Ok))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("pipeline should run");
+    assert_eq!(
+        ids(&records),
+        vec![
+            TxIdType::Numeric(4),
+            TxIdType::Numeric(1),
+            TxIdType::Numeric(3)
+        ]
+    );
+    assert_eq!(records[1].ts, TxTimestamp::from_millis(30));
+    assert_eq!(records[1].description.as_deref(), Some("#1"));
+
+    // sorting first lets deduplication keep the earliest record of an id
+    let records = Pipeline::new()
+        .sort(by_timestamp())
+        .dedup(RecordKey::IdOnly)
+        .run([sample_tx(1, 500, 30), sample_tx(1, 500, 20)].map(Ok))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("pipeline should run");
+    assert_eq!(records, vec![sample_tx(1, 500, 20)]);
+}
+
+#[test]
+fn empty_pipeline_passes_records_through() {
+    let data = vec![sample_tx(2, 50, 10), sample_tx(1, 500, 30)];
+    let pipeline = Pipeline::new();
+    assert!(pipeline.is_empty());
+    let records = pipeline
+        .run(data.clone().into_iter().map(Ok))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("pipeline should run");
+    assert_eq!(records, data);
+}
+
+#[test]
+fn errors_pass_through_filters_and_end_sorting() {
+    let error = || {
+        Err(AppError::ParsingError {
+            context: parser::codecs::errors::ParserContext::Record { number: 2 },
+            source: ParserError::IncompleteRecord,
+        })
+    };
+    let input = || vec![Ok(sample_tx(1, 500, 30)), error(), Ok(sample_tx(2, 50, 10))];
+
+    let items: Vec<_> = Pipeline::new()
+        .filter(|tx| tx.amount > Money::from_minor_units(100))
+        .dedup(RecordKey::FullRecord)
+        .run(input())
+        .collect();
+    assert_eq!(items.len(), 2);
+    assert!(items[0].is_ok());
+    assert!(matches!(
+        items[1],
+        Err(AppError::ParsingError {
+            source: ParserError::IncompleteRecord,
+            ..
+        })
+    ));
+
+    let items: Vec<_> = Pipeline::new().sort(by_timestamp()).run(input()).collect();
+    assert_eq!(items.len(), 1);
+    assert!(items[0].is_err());
+}
+
+#[test]
+fn streams_are_converted_in_one_pass() {
+    let data: Vec<TxRecord> = (0..10)
+        .map(|i| sample_tx(i, 100 * (i as i64 + 1), 1_000 - i))
+        .collect();
+    let mut csv = Vec::new();
+    Codec::CsvCodec
+        .write(&mut csv, &data)
+        .expect("CSV should be written");
+
+    let dir = std::env::temp_dir();
+    let pipeline = Pipeline::new()
+        .filter_expr("amount > 2".parse().expect("filter should compile"))
+        .sort_external(ExternalSorter::new(by_timestamp(), 3).in_dir(&dir));
+    let mut out = Vec::new();
+    let sink = Codec::TextCodec
+        .open_sink(&mut out, &WriteOptions::default())
+        .expect("sink should open");
+    let pushed = pipeline
+        .run_into(
+            Codec::CsvCodec.parse_stream(&csv[..], &ParseOptions::default()),
+            sink,
+        )
+        .expect("conversion should succeed");
+    assert_eq!(pushed, 8);
+
+    let records = Codec::TextCodec
+        .parse(&out[..])
+        .expect("output should parse");
+    let expected: Vec<TxIdType> = (2..10).rev().map(TxIdType::Numeric).collect();
+    assert_eq!(ids(&records), expected);
+}
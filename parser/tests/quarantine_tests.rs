@@ -0,0 +1,109 @@
+use parser::codecs::base::Codec;
+use parser::codecs::options::ParseOptions;
+use parser::codecs::quarantine::{QuarantineWriter, read_quarantine};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+
+const CSV_INPUT: &str = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION
+1,DEPOSIT,0,10,100,1700,SUCCESS,\"ok\"
+2,DEPOSIT,0,10,abc,1700,SUCCESS,\"bad amount\"
+3,DEPOSIT,0,10,300,1700,SUCCESS,\"ok\"
+4,DEPOSIT,0,10
+";
+
+fn sample_tx(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(500),
+        ts: TxTimestamp::from_millis(1_700_000),
+        status: TxStatus::Success,
+        description: Some("payment".to_string()),
+        ..Default::default()
+    }
+}
+
+fn parse_quarantined(codec: Codec, input: &[u8]) -> (Vec<TxRecord>, Vec<u8>, usize) {
+    let mut quarantine = QuarantineWriter::new(Vec::new());
+    let records = codec
+        .parse_quarantined(input, &ParseOptions::default(), &mut quarantine)
+        .expect("malformed records should be quarantined");
+    let count = quarantine.count();
+    let bytes = quarantine.into_inner().expect("flush should succeed");
+    (records, bytes, count)
+}
+
+#[test]
+fn csv_rejected_lines_round_trip_through_quarantine() {
+    let (records, bytes, count) = parse_quarantined(Codec::CsvCodec, CSV_INPUT.as_bytes());
+    assert_eq!(
+        records
+            .iter()
+            .filter_map(|tx| tx.id.as_numeric())
+            .collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+    assert_eq!(count, 2);
+
+    let entries = read_quarantine(bytes.as_slice()).expect("quarantine should read back");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        entries[0].raw,
+        b"2,DEPOSIT,0,10,abc,1700,SUCCESS,\"bad amount\"".to_vec()
+    );
+    assert!(entries[0].context.starts_with("line #2"));
+    assert_eq!(entries[1].raw, b"4,DEPOSIT,0,10".to_vec());
+
+    // plain parse still fails on the first malformed line
+    assert!(Codec::CsvCodec.parse(CSV_INPUT.as_bytes()).is_err());
+}
+
+#[test]
+fn text_rejects_whole_record_block() {
+    let input = "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 5\nAMOUNT: 10\nTIMESTAMP: 1\nSTATUS: SUCCESS\nDESCRIPTION: \"a\"\n\n\
+                 TX_ID: 2\nTX_TYPE: GIFT\nAMOUNT: 10\n\n\
+                 TX_ID: 3\nTX_TYPE: DEPOSIT\n";
+    let (records, bytes, count) = parse_quarantined(Codec::TextCodec, input.as_bytes());
+    assert_eq!(records.len(), 1);
+    assert_eq!(count, 2);
+
+    let entries = read_quarantine(bytes.as_slice()).expect("quarantine should read back");
+    assert_eq!(
+        entries[0].raw,
+        b"TX_ID: 2\nTX_TYPE: GIFT\nAMOUNT: 10".to_vec()
+    );
+    assert_eq!(entries[1].raw, b"TX_ID: 3\nTX_TYPE: DEPOSIT".to_vec());
+}
+
+#[test]
+fn binary_rejected_record_is_kept_as_bytes() {
+    let mut bad = Vec::new();
+    Codec::BinaryCodec
+        .write(&mut bad, &[sample_tx(2)])
+        .expect("binary write should succeed");
+    // corrupt TX_TYPE of the record
+    bad[8 + 8] = 9;
+
+    let mut input = Vec::new();
+    Codec::BinaryCodec
+        .write(&mut input, &[sample_tx(1)])
+        .expect("binary write should succeed");
+    input.extend_from_slice(&bad);
+    Codec::BinaryCodec
+        .write(&mut input, &[sample_tx(3)])
+        .expect("binary write should succeed");
+
+    let (records, bytes, count) = parse_quarantined(Codec::BinaryCodec, &input);
+    assert_eq!(records, vec![sample_tx(1), sample_tx(3)]);
+    assert_eq!(count, 1);
+
+    let entries = read_quarantine(bytes.as_slice()).expect("quarantine should read back");
+    assert_eq!(entries[0].raw, bad);
+}
+
+#[test]
+fn read_quarantine_rejects_foreign_content() {
+    assert!(read_quarantine("TX_ID: 1\n".as_bytes()).is_err());
+    assert!(read_quarantine("# REJECTED\nHEX: 0G\n".as_bytes()).is_err());
+}
@@ -0,0 +1,82 @@
+use parser::codecs::errors::ParserError;
+use parser::domain::tx::{Money, TxIdType, TxRecord};
+use parser::errors::AppError;
+use parser::reconcile::{ControlMismatch, ControlTotals};
+
+fn records(amounts: &[i64]) -> Vec<TxRecord> {
+    amounts
+        .iter()
+        .enumerate()
+        .map(|(i, &amount)| TxRecord {
+            id: TxIdType::Numeric(i as u64),
+            amount: Money::from_minor_units(amount),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[test]
+fn check_reports_count_and_total_mismatches() {
+    let data = records(&[100, -30, i64::MAX, i64::MAX]);
+    let actual = ControlTotals::from_records(&data);
+    assert_eq!(actual.record_count, Some(4));
+    assert_eq!(actual.total_amount, Some(70 + 2 * i64::MAX as i128));
+    assert!(actual.check(&data).is_empty());
+
+    let expected = ControlTotals {
+        record_count: Some(5),
+        total_amount: Some(0),
+    };
+    assert_eq!(
+        expected.check(&data),
+        vec![
+            ControlMismatch::RecordCount {
+                expected: 5,
+                actual: 4
+            },
+            ControlMismatch::TotalAmount {
+                expected: 0,
+                actual: 70 + 2 * i64::MAX as i128
+            },
+        ]
+    );
+    assert!(ControlTotals::default().check(&data).is_empty());
+}
+
+#[test]
+fn control_file_and_overrides() {
+    let control = "# partner control file\nRECORD_COUNT: 2\n\nTOTAL_AMOUNT: -50\n";
+    let totals = ControlTotals::from_reader(control.as_bytes()).expect("control file should parse");
+    assert_eq!(totals.record_count, Some(2));
+    assert_eq!(totals.total_amount, Some(-50));
+    assert!(totals.check(&records(&[-100, 50])).is_empty());
+
+    let overridden = totals.overridden_by(ControlTotals {
+        record_count: Some(3),
+        total_amount: None,
+    });
+    assert_eq!(overridden.record_count, Some(3));
+    assert_eq!(overridden.total_amount, Some(-50));
+}
+
+#[test]
+fn control_file_rejects_unknown_keys_and_values() {
+    for (input, unknown_key) in [
+        ("RECORDS: 2\n", true),
+        ("RECORD_COUNT: 2\nRECORD_COUNT: 3\n", true),
+        ("RECORD_COUNT: two\n", false),
+    ] {
+        let err = ControlTotals::from_reader(input.as_bytes()).expect_err("should fail");
+        match err {
+            AppError::ParsingError {
+                source: ParserError::UnparsableKey(_),
+                ..
+            } => assert!(unknown_key),
+            AppError::ParsingError {
+                source: ParserError::UnparsableValue(_),
+                ..
+            } => assert!(!unknown_key),
+            _ => panic!("unexpected error {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use parser::codecs::base::TxFieldKey;
+use parser::codecs::errors::ParserError;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use parser::transform::redact::{FieldAction, RedactionPolicy};
+
+const POLICY: &str = r#"# partner X data sharing agreement
+SALT: "s3cret"
+PRESERVE_ACCOUNT_FORMAT: true
+FROM_USER_ID: HASH
+TO_USER_ID: HASH
+DESCRIPTION: MASK
+TIMESTAMP: MASK
+"#;
+
+fn sample_tx() -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(1),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(123456),
+        to: AccountType::Numeric(987),
+        amount: Money::from_minor_units(500),
+        ts: TxTimestamp::from_millis(1_700_000_123_456),
+        status: TxStatus::Success,
+        description: Some("rent May".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn policy_loads_from_config() {
+    let policy = RedactionPolicy::from_reader(POLICY.as_bytes()).expect("policy should load");
+    assert_eq!(policy.salt, "s3cret");
+    assert!(policy.preserve_account_format);
+    assert_eq!(policy.action(TxFieldKey::FromUserId), FieldAction::Hash);
+    assert_eq!(policy.action(TxFieldKey::Description), FieldAction::Mask);
+    assert_eq!(policy.action(TxFieldKey::Amount), FieldAction::Keep);
+}
+
+#[test]
+fn policy_applies_field_actions() {
+    let policy = RedactionPolicy::from_reader(POLICY.as_bytes()).expect("policy should load");
+    let tx = sample_tx();
+    let redacted = policy.redact(&tx);
+
+    assert_eq!(redacted.id, tx.id);
+    assert_eq!(redacted.amount, tx.amount);
+    assert_ne!(redacted.from, tx.from);
+    assert_eq!(redacted.from.to_string().len(), 6);
+    assert_eq!(redacted.to.to_string().len(), 3);
+    assert_eq!(redacted.description.as_deref(), Some("**** ***"));
+    assert_eq!(redacted.ts.millis() % (24 * 60 * 60 * 1000), 0);
+}
+
+#[test]
+fn hashing_is_deterministic_and_salted() {
+    let policy = RedactionPolicy::new("a")
+        .with_rule(TxFieldKey::FromUserId, FieldAction::Hash)
+        .and_then(|p| p.with_rule(TxFieldKey::ToUserId, FieldAction::Hash))
+        .expect("rules are applicable");
+    let mut tx = sample_tx();
+    tx.to = tx.from.clone();
+    let redacted = policy.redact(&tx);
+    // same account maps to the same pseudonym in both positions
+    assert_eq!(redacted.from, redacted.to);
+    assert_eq!(policy.redact(&tx), redacted);
+
+    let mut other = policy.clone();
+    other.salt = "b".into();
+    assert_ne!(other.redact(&tx).from, redacted.from);
+}
+
+#[test]
+fn same_digits_pseudonyms_of_distinct_accounts_differ() {
+    let mut policy = RedactionPolicy::new("a")
+        .with_rule(TxFieldKey::FromUserId, FieldAction::Hash)
+        .expect("rule is applicable");
+    policy.preserve_account_format = true;
+    let pseudonym = |account: u64| {
+        let tx = TxRecord {
+            from: AccountType::Numeric(account),
+            ..sample_tx()
+        };
+        policy
+            .redact(&tx)
+            .from
+            .as_numeric()
+            .expect("numeric account")
+    };
+
+    // every account of a digit count gets its own pseudonym of the same digit count
+    let mut one_digit: Vec<u64> = (1..10).map(pseudonym).collect();
+    one_digit.sort();
+    assert_eq!(one_digit, (1..10).collect::<Vec<u64>>());
+    let mut three_digits: Vec<u64> = (100..1_000).map(pseudonym).collect();
+    assert!(three_digits.iter().all(|id| (100..1_000).contains(id)));
+    three_digits.sort();
+    three_digits.dedup();
+    assert_eq!(three_digits.len(), 900);
+
+    for account in [10_000_000_000_000_000_000, u64::MAX] {
+        assert!(pseudonym(account) >= 10_000_000_000_000_000_000);
+    }
+}
+
+#[test]
+fn zero_accounts_stay_zero() {
+    let policy = RedactionPolicy::new("a")
+        .with_rule(TxFieldKey::FromUserId, FieldAction::Hash)
+        .expect("rule is applicable");
+    let tx = TxRecord {
+        kind: TxKind::Deposit,
+        from: AccountType::Numeric(0),
+        ..sample_tx()
+    };
+    assert_eq!(policy.redact(&tx).from, AccountType::Numeric(0));
+}
+
+#[test]
+fn masked_accounts_never_become_zero() {
+    let policy = RedactionPolicy::new("a")
+        .with_rule(TxFieldKey::FromUserId, FieldAction::Mask)
+        .and_then(|p| p.with_rule(TxFieldKey::ToUserId, FieldAction::Mask))
+        .expect("rules are applicable");
+    let tx = TxRecord {
+        from: AccountType::Numeric(10_000),
+        to: AccountType::Numeric(1_234_567),
+        ..sample_tx()
+    };
+    let redacted = policy.redact(&tx);
+    assert_eq!(redacted.from, AccountType::Numeric(10_000));
+    assert_eq!(redacted.to, AccountType::Numeric(4_567));
+}
+
+#[test]
+fn dropped_optional_fields_are_cleared() {
+    let policy = RedactionPolicy::new("a")
+        .with_rule(TxFieldKey::Tenant, FieldAction::Drop)
+        .and_then(|p| p.with_rule(TxFieldKey::Description, FieldAction::Drop))
+        .expect("rules are applicable");
+    let tx = TxRecord {
+        tenant: Some("acme".into()),
+        ..sample_tx()
+    };
+    let redacted = policy.redact(&tx);
+    assert_eq!(redacted.tenant, None);
+    assert_eq!(redacted.description, None);
+}
+
+#[test]
+fn iban_accounts_keep_their_shape() {
+    let policy = RedactionPolicy::new("a")
+        .with_rule(TxFieldKey::FromUserId, FieldAction::Mask)
+        .and_then(|p| p.with_rule(TxFieldKey::ToUserId, FieldAction::Hash))
+        .expect("rules are applicable");
+    let iban = AccountType::Iban("GB82WEST12345698765432".into());
+    let tx = TxRecord {
+        from: iban.clone(),
+        to: iban.clone(),
+        ..sample_tx()
+    };
+    let redacted = policy.redact(&tx);
+    assert_eq!(redacted.from.to_string(), "GB****************5432");
+
+    // pseudonym is a valid iban of the same country
+    let hashed = redacted.to.to_string();
+    assert_ne!(redacted.to, iban);
+    assert_eq!(&hashed[..2], "GB");
+    let reparsed = AccountType::parse_iban(&hashed).expect("pseudonym should be valid iban");
+    assert_eq!(reparsed, redacted.to);
+}
+
+#[test]
+fn policy_rejects_inapplicable_and_duplicate_rules() {
+    let err = RedactionPolicy::new("a")
+        .with_rule(TxFieldKey::Amount, FieldAction::Hash)
+        .expect_err("amount can't be hashed");
+    assert!(matches!(err, ParserError::UnparsableValue(_)));
+
+    let err = RedactionPolicy::from_reader("DESCRIPTION: MASK\nDESCRIPTION: DROP\n".as_bytes())
+        .expect_err("duplicate rule should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::Duplicate(TxFieldKey::Description),
+            ..
+        }
+    ));
+
+    let err = RedactionPolicy::from_reader("DESCRIPTION: SHRED\n".as_bytes())
+        .expect_err("unknown action should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableValue(_),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn hash_rules_require_salt() {
+    let err = RedactionPolicy::default()
+        .with_rule(TxFieldKey::FromUserId, FieldAction::Hash)
+        .expect_err("hashing without salt should fail");
+    assert!(matches!(err, ParserError::UnparsableValue(_)));
+    assert!(
+        RedactionPolicy::default()
+            .with_rule(TxFieldKey::Description, FieldAction::Mask)
+            .is_ok()
+    );
+
+    for config in [
+        "FROM_USER_ID: HASH\n",
+        "SALT: \"\"\nDESCRIPTION: MASK\nTO_USER_ID: HASH\n",
+    ] {
+        let err = RedactionPolicy::from_reader(config.as_bytes())
+            .expect_err("hashing without salt should fail");
+        assert!(
+            err.to_string().contains("without SALT"),
+            "{}: {}",
+            config,
+            err
+        );
+    }
+    // salt may follow the rules
+    let policy = RedactionPolicy::from_reader("TO_USER_ID: HASH\nSALT: \"s3cret\"\n".as_bytes())
+        .expect("policy should load");
+    assert_eq!(policy.action(TxFieldKey::ToUserId), FieldAction::Hash);
+}
@@ -0,0 +1,98 @@
+use parser::codecs::base::Codec;
+use parser::codecs::binary_v2::BinaryV2Options;
+use parser::codecs::options::WriteOptions;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64, reference: Option<&str>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(3),
+        to: AccountType::Numeric(7),
+        amount: Money::from_minor_units(1_000),
+        ts: TxTimestamp::from_millis(1700),
+        status: TxStatus::Success,
+        description: Some("invoice".into()),
+        reference: reference.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reference_round_trips_in_all_codecs() {
+    let data = vec![
+        sample_tx(1, Some("E2E-2024-0001")),
+        sample_tx(2, None),
+        sample_tx(3, Some("ünï \"quoted\", with comma")),
+    ];
+    for codec in [
+        Codec::BinaryCodec,
+        Codec::BinaryV2Codec,
+        Codec::ColumnarBinaryCodec,
+        Codec::TextCodec,
+        Codec::CsvCodec,
+        Codec::TsvCodec,
+    ] {
+        let mut bytes = Vec::new();
+        codec
+            .write(&mut bytes, &data)
+            .expect("write should succeed");
+        let parsed = codec.parse(bytes.as_slice()).expect("parse should succeed");
+        assert_eq!(parsed, data, "{:?} should keep reference", codec);
+    }
+}
+
+#[test]
+fn text_reference_is_quoted_and_may_be_not() {
+    let data = vec![sample_tx(1, Some("line\nbreak"))];
+    let mut text = Vec::new();
+    Codec::TextCodec
+        .write(&mut text, &data)
+        .expect("text write");
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("REFERENCE: \"line\\nbreak\"\n"));
+    assert_eq!(Codec::TextCodec.parse(text.as_bytes()).unwrap(), data);
+
+    let hand_written = text.replace("\"line\\nbreak\"", "E2E-1");
+    let parsed = Codec::TextCodec.parse(hand_written.as_bytes()).unwrap();
+    assert_eq!(parsed[0].reference.as_deref(), Some("E2E-1"));
+}
+
+#[test]
+fn csv_reference_column_comes_with_other_optional_ones() {
+    let mut csv = Vec::new();
+    Codec::CsvCodec
+        .write(&mut csv, &[sample_tx(1, Some("E2E-1"))])
+        .expect("csv write");
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.starts_with(
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,TENANT,FEE,REFERENCE\n"
+    ));
+    assert!(csv.ends_with(",\"invoice\",,,E2E-1\n"));
+}
+
+#[test]
+fn binary_v2_stream_needs_references_option() {
+    let data = vec![sample_tx(1, Some("E2E-1"))];
+    let err = Codec::BinaryV2Codec
+        .open_sink(Vec::new(), &WriteOptions::default())
+        .and_then(|mut sink| sink.push(&data[0]))
+        .expect_err("reference needs the option");
+    assert!(matches!(err, AppError::WriteError(_)));
+
+    let options = WriteOptions {
+        binary_v2: BinaryV2Options {
+            references: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bytes = Vec::new();
+    let mut sink = Codec::BinaryV2Codec
+        .open_sink(&mut bytes, &options)
+        .expect("sink should open");
+    sink.push(&data[0]).expect("push should succeed");
+    sink.finish().expect("finish should succeed");
+    assert_eq!(Codec::BinaryV2Codec.parse(bytes.as_slice()).unwrap(), data);
+}
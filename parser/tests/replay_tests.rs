@@ -0,0 +1,87 @@
+use parser::codecs::errors::ParserError;
+use parser::domain::tx::{TxIdType, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use parser::replay::{Replay, StatusEvent};
+
+const EVENTS: &str = r#"{"tx_id": 1, "timestamp": 1500, "status": "SUCCESS"}
+{"tx_id": 2, "timestamp": 1200, "status": "FAILURE"}
+
+{"tx_id": 9, "timestamp": 1300, "status": "SUCCESS"}
+{"tx_id": 2, "timestamp": 1600, "status": "SUCCESS"}
+"#;
+
+fn pending_tx(id: u64, ts: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Pending,
+        ..Default::default()
+    }
+}
+
+fn statuses(records: &[TxRecord]) -> Vec<(u64, TxStatus)> {
+    records
+        .iter()
+        .map(|tx| (tx.id.as_numeric().unwrap(), tx.status))
+        .collect()
+}
+
+#[test]
+fn state_at_applies_events_up_to_moment() {
+    let events = StatusEvent::read_jsonl(EVENTS.as_bytes()).expect("events should parse");
+    assert_eq!(events.len(), 4);
+
+    let mut replay = Replay::new(vec![
+        pending_tx(1, 1000),
+        pending_tx(2, 1100),
+        pending_tx(3, 1400),
+    ]);
+    replay.add_events(events);
+
+    assert_eq!(
+        statuses(&replay.state_at(TxTimestamp::from_millis(1050))),
+        vec![(1, TxStatus::Pending)]
+    );
+    assert_eq!(
+        statuses(&replay.state_at(TxTimestamp::from_millis(1450))),
+        vec![
+            (1, TxStatus::Pending),
+            (2, TxStatus::Failure),
+            (3, TxStatus::Pending)
+        ]
+    );
+    assert_eq!(
+        statuses(&replay.state_at(TxTimestamp::from_millis(2000))),
+        vec![
+            (1, TxStatus::Success),
+            (2, TxStatus::Success),
+            (3, TxStatus::Pending)
+        ]
+    );
+    assert_eq!(replay.unknown_events().len(), 1);
+    assert_eq!(replay.unknown_events()[0].id, TxIdType::Numeric(9));
+}
+
+#[test]
+fn read_jsonl_rejects_bad_events() {
+    let err = StatusEvent::read_jsonl(r#"{"tx_id": 1, "status": "SUCCESS"}"#.as_bytes())
+        .expect_err("missing timestamp should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableKey(_),
+            ..
+        }
+    ));
+
+    let err =
+        StatusEvent::read_jsonl(r#"{"tx_id": 1, "timestamp": 1, "status": "DONE"}"#.as_bytes())
+            .expect_err("unknown status should fail");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableValue(_),
+            ..
+        }
+    ));
+}
@@ -0,0 +1,65 @@
+use parser::codecs::base::Codec;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+
+fn sample_tx(amount: i64, description: &str, tenant: Option<&str>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(1),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(1_704_276_930_500),
+        status: TxStatus::Pending,
+        description: Some(description.to_string()),
+        tenant: tenant.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+fn write_report(data: &[TxRecord]) -> String {
+    let mut buff = Vec::new();
+    Codec::ReportCodec
+        .write(&mut buff, data)
+        .expect("report write should succeed");
+    String::from_utf8(buff).expect("report is utf-8")
+}
+
+#[test]
+fn write_report_block() {
+    let text = write_report(&[sample_tx(-1_234_567, "rent\nmarch", Some("acme"))]);
+    assert_eq!(
+        text,
+        "Transaction 1\n\
+         \x20 Type:        TRANSFER\n\
+         \x20 From:        11\n\
+         \x20 To:          22\n\
+         \x20 Amount:      -1,234,567\n\
+         \x20 Date:        2024-01-03T10:15:30.500Z\n\
+         \x20 Status:      PENDING\n\
+         \x20 Description: rent\n\
+         \x20              march\n\
+         \x20 Tenant:      acme\n"
+    );
+}
+
+#[test]
+fn write_report_amounts_and_separators() {
+    let text = write_report(&[sample_tx(999, "", None), sample_tx(1000, "", None)]);
+    assert!(text.contains("Amount:      999\n"));
+    assert!(text.contains("Amount:      1,000\n"));
+    assert!(text.contains("Description: \n"));
+    assert!(!text.contains("Tenant:"));
+    assert_eq!(text.matches("\n\nTransaction 1\n").count(), 1);
+
+    let text = write_report(&[sample_tx(i64::MIN, "", None)]);
+    assert!(text.contains("-9,223,372,036,854,775,808\n"));
+}
+
+#[test]
+fn report_is_write_only() {
+    let err = Codec::ReportCodec
+        .parse(&b"Transaction 1\n"[..])
+        .expect_err("report can't be parsed");
+    assert!(matches!(err, AppError::ReadError(e) if e.kind() == std::io::ErrorKind::Unsupported));
+}
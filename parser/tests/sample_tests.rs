@@ -0,0 +1,149 @@
+use std::cell::Cell;
+
+use parser::codecs::base::Codec;
+use parser::codecs::errors::{ParserContext, ParserError};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use parser::transform::pipeline::Pipeline;
+use parser::transform::sample::{Sampler, Sampling, sample};
+
+fn sample_tx(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(100),
+        ts: TxTimestamp::from_millis(1_700_000_000_000 + id),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn ids(records: &[TxRecord]) -> Vec<u64> {
+    records
+        .iter()
+        .map(|tx| tx.id.as_numeric().expect("numeric id"))
+        .collect()
+}
+
+fn stream(n: u64) -> impl Iterator<Item = Result<TxRecord, AppError>> {
+    (0..n).map(|id| Ok(sample_tx(id)))
+}
+
+fn sampled(n: u64, sampling: Sampling) -> Vec<u64> {
+    ids(&sample(stream(n), sampling).expect("stream should be sampled"))
+}
+
+#[test]
+fn first_and_every_nth_records_are_picked() {
+    assert_eq!(sampled(10, Sampling::First(3)), vec![0, 1, 2]);
+    assert_eq!(sampled(2, Sampling::First(3)), vec![0, 1]);
+    assert_eq!(sampled(10, Sampling::EveryNth(4)), vec![0, 4, 8]);
+    assert_eq!(sampled(3, Sampling::EveryNth(1)), vec![0, 1, 2]);
+    assert!(sampled(10, Sampling::EveryNth(0)).is_empty());
+    assert!(sampled(10, Sampling::First(0)).is_empty());
+}
+
+#[test]
+fn reading_stops_once_sample_is_complete() {
+    let read = Cell::new(0);
+    let records = stream(1_000).inspect(|_| read.set(read.get() + 1));
+    assert_eq!(
+        ids(&sample(records, Sampling::First(5)).expect("stream should be sampled")),
+        vec![0, 1, 2, 3, 4]
+    );
+    assert_eq!(read.get(), 5);
+
+    read.set(0);
+    let records = stream(1_000).inspect(|_| read.set(read.get() + 1));
+    let items: Vec<_> = Pipeline::new()
+        .sample(Sampling::First(5))
+        .run(records)
+        .collect();
+    assert_eq!(items.len(), 5);
+    assert_eq!(read.get(), 5);
+}
+
+#[test]
+fn reservoir_is_reproducible_with_seed() {
+    let reservoir = |seed| Sampling::Reservoir { size: 10, seed };
+    let picked = sampled(1_000, reservoir(42));
+    assert_eq!(picked.len(), 10);
+    assert!(picked.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(sampled(1_000, reservoir(42)), picked);
+    assert_ne!(sampled(1_000, reservoir(7)), picked);
+
+    // short streams are taken whole
+    assert_eq!(sampled(4, reservoir(42)), vec![0, 1, 2, 3]);
+    assert!(sampled(100, Sampling::Reservoir { size: 0, seed: 1 }).is_empty());
+}
+
+#[test]
+fn reservoir_picks_records_uniformly() {
+    // every record of 10 is picked into 5 of 2_000 samples about half of times
+    let mut picks = [0usize; 10];
+    for seed in 0..2_000 {
+        for id in sampled(10, Sampling::Reservoir { size: 5, seed }) {
+            picks[id as usize] += 1;
+        }
+    }
+    assert!(
+        picks.iter().all(|&n| (850..1_150).contains(&n)),
+        "{:?}",
+        picks
+    );
+}
+
+#[test]
+fn sampler_takes_records_pushed() {
+    let data: Vec<TxRecord> = (0..20).map(sample_tx).collect();
+    let mut csv = Vec::new();
+    Codec::CsvCodec
+        .write(&mut csv, &data)
+        .expect("CSV should be written");
+
+    let mut sampler = Sampler::new(Sampling::Reservoir { size: 3, seed: 42 });
+    for tx in Codec::CsvCodec.parse_stream(&csv[..], &Default::default()) {
+        sampler.push(&tx.expect("record should parse"));
+    }
+    assert_eq!(sampler.seen(), 20);
+    assert!(!sampler.is_done());
+    assert_eq!(
+        ids(&sampler.into_vec()),
+        sampled(20, Sampling::Reservoir { size: 3, seed: 42 })
+    );
+}
+
+#[test]
+fn errors_end_reservoir_and_pass_through_other_samples() {
+    let input = || {
+        vec![
+            Ok(sample_tx(1)),
+            Err(AppError::ParsingError {
+                context: ParserContext::Record { number: 2 },
+                source: ParserError::IncompleteRecord,
+            }),
+            Ok(sample_tx(2)),
+            Ok(sample_tx(3)),
+        ]
+    };
+    assert!(sample(input(), Sampling::First(1)).is_ok());
+    assert!(sample(input(), Sampling::First(2)).is_err());
+
+    let items: Vec<_> = Pipeline::new()
+        .sample(Sampling::EveryNth(2))
+        .run(input())
+        .collect();
+    assert_eq!(items.len(), 3);
+    assert!(items[1].is_err());
+    assert_eq!(items[2].as_ref().ok(), Some(&sample_tx(3)));
+
+    let items: Vec<_> = Pipeline::new()
+        .sample(Sampling::Reservoir { size: 2, seed: 0 })
+        .run(input())
+        .collect();
+    assert_eq!(items.len(), 1);
+    assert!(items[0].is_err());
+}
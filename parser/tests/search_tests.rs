@@ -0,0 +1,43 @@
+use parser::domain::tx::TxRecord;
+use parser::search::{Index, tokenize};
+
+fn records(descriptions: &[&str]) -> Vec<TxRecord> {
+    descriptions
+        .iter()
+        .map(|d| TxRecord {
+            description: Some(d.to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[test]
+fn tokenize_splits_and_lowercases() {
+    let tokens: Vec<String> = tokenize("Invoice #4711, ACME-corp").collect();
+    assert_eq!(tokens, vec!["invoice", "4711", "acme", "corp"]);
+}
+
+#[test]
+fn search_matches_all_query_tokens() {
+    let data = records(&[
+        "INVOICE 4711 acme",
+        "invoice 4712",
+        "refund for invoice 4711",
+        "salary",
+    ]);
+    let index = Index::build(&data);
+    assert_eq!(index.len(), 4);
+
+    assert_eq!(index.search("INVOICE 4711"), vec![0, 2]);
+    assert_eq!(index.search("invoice"), vec![0, 1, 2]);
+    assert_eq!(index.search("salary"), vec![3]);
+    assert!(index.search("invoice 9999").is_empty());
+    assert!(index.search("  ").is_empty());
+}
+
+#[test]
+fn repeated_tokens_are_indexed_once() {
+    let data = records(&["fee fee fee"]);
+    let index = Index::build(&data);
+    assert_eq!(index.search("fee fee"), vec![0]);
+}
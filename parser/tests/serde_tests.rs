@@ -0,0 +1,80 @@
+#![cfg(feature = "serde")]
+
+use parser::analytics::aggregate::{Group, GroupBy, aggregate};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::transform::join::{AccountInfo, EnrichedRecord};
+
+#[test]
+fn record_round_trips_through_json() {
+    let tx = TxRecord {
+        id: TxIdType::Numeric(7),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::new(-1_234, 3),
+        ts: TxTimestamp::from_millis(1_700_000_000_000),
+        status: TxStatus::Pending,
+        description: Some("coffee".to_string()),
+        extras: [("MEMO".to_string(), "x".to_string())].into(),
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&tx).expect("record should serialize");
+    assert_eq!(
+        json,
+        r#"{"id":7,"kind":"TRANSFER","from":11,"to":22,"amount":{"minor_units":-1234,"scale":3},"fee":null,"original_amount":null,"exchange_rate":null,"ts":1700000000000,"status":"PENDING","description":"coffee","tenant":null,"reference":null,"extras":{"MEMO":"x"}}"#
+    );
+    let parsed: TxRecord = serde_json::from_str(&json).expect("record should deserialize");
+    assert_eq!(parsed, tx);
+}
+
+#[test]
+fn uuid_ids_are_strings() {
+    let tx = TxRecord {
+        id: TxIdType::Uuid(0x123e4567_e89b_12d3_a456_426614174000),
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&tx).expect("record should serialize");
+    assert!(json.starts_with(r#"{"id":"123e4567-e89b-12d3-a456-426614174000","#));
+    let parsed: TxRecord = serde_json::from_str(&json).expect("record should deserialize");
+    assert_eq!(parsed, tx);
+    assert!(serde_json::from_str::<TxIdType>(r#""not-a-uuid""#).is_err());
+}
+
+#[test]
+fn optional_fields_may_be_omitted() {
+    let json = r#"{"id":1,"kind":"DEPOSIT","from":0,"to":3,"amount":{"minor_units":10,"scale":2},"ts":1700,"status":"SUCCESS"}"#;
+    let parsed: TxRecord = serde_json::from_str(json).expect("record should deserialize");
+    assert_eq!(parsed.description, None);
+    assert!(parsed.extras.is_empty());
+    assert!(serde_json::from_str::<TxKind>(r#""Deposit""#).is_err());
+}
+
+#[test]
+fn aggregation_groups_round_trip_through_json() {
+    let tx = TxRecord {
+        kind: TxKind::Deposit,
+        amount: Money::new(250, 2),
+        ..Default::default()
+    };
+    let result = aggregate(&[tx], &[GroupBy::Kind, GroupBy::FromAccount]).unwrap();
+    let json = serde_json::to_string(&result.groups[0]).expect("group should serialize");
+    assert_eq!(
+        json,
+        r#"{"key":[{"KIND":"DEPOSIT"},{"ACCOUNT":0}],"count":1,"sum":{"minor_units":250,"scale":2},"min":{"minor_units":250,"scale":2},"max":{"minor_units":250,"scale":2}}"#
+    );
+    let parsed: Group = serde_json::from_str(&json).expect("group should deserialize");
+    assert_eq!(parsed, result.groups[0]);
+}
+
+#[test]
+fn enriched_records_round_trip_through_json() {
+    let enriched = EnrichedRecord {
+        tx: TxRecord::default(),
+        from: Some(AccountInfo::new("Acme", "US").expect("account info should be valid")),
+        to: None,
+    };
+    let json = serde_json::to_string(&enriched).expect("record should serialize");
+    assert!(json.ends_with(r#""from":{"owner":"Acme","country":"US"},"to":null}"#));
+    let parsed: EnrichedRecord = serde_json::from_str(&json).expect("record should deserialize");
+    assert_eq!(parsed, enriched);
+}
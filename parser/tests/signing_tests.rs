@@ -0,0 +1,123 @@
+use parser::codecs::base::Codec;
+use parser::codecs::errors::IntegrityError;
+use parser::codecs::options::{ParseOptions, WriteOptions};
+use parser::codecs::signing::{SignedReader, SignedWriter, SigningKey};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use std::io::{Read, Write};
+
+// "TX_ID,TX_TYPE\n" signed with "auditor key"
+const KNOWN_SIGNED: [u8; 50] = [
+    0x54, 0x58, 0x5F, 0x49, 0x44, 0x2C, 0x54, 0x58, 0x5F, 0x54, 0x59, 0x50, 0x45, 0x0A, 0x59, 0x50,
+    0x53, 0x47, 0xC0, 0xF7, 0x32, 0x67, 0xA8, 0x2D, 0xA2, 0xB3, 0x80, 0x57, 0x79, 0xFA, 0x5A, 0x9C,
+    0x72, 0xE3, 0x02, 0xDE, 0x57, 0xE0, 0xF9, 0xDD, 0x10, 0x13, 0x5F, 0x3F, 0x44, 0xA1, 0xB9, 0x38,
+    0xDE, 0x41,
+];
+
+fn sample(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Deposit,
+        from: AccountType::Numeric(0),
+        to: AccountType::Numeric(id % 4 + 1),
+        amount: Money::from_minor_units(250 * id as i64),
+        ts: TxTimestamp::from_millis(1_700_000 + id),
+        status: TxStatus::Success,
+        description: Some(format!("audited {}", id)),
+        ..Default::default()
+    }
+}
+
+fn sign(data: &[u8], key: &SigningKey) -> Vec<u8> {
+    let mut w = SignedWriter::new(Vec::new(), key);
+    w.write_all(data).unwrap();
+    w.finish().unwrap()
+}
+
+fn verify(data: &[u8], key: &SigningKey) -> Result<Vec<u8>, AppError> {
+    let mut out = Vec::new();
+    SignedReader::new(data, key)?.read_to_end(&mut out).unwrap();
+    Ok(out)
+}
+
+fn integrity_error(result: Result<Vec<u8>, AppError>) -> IntegrityError {
+    match result {
+        Err(AppError::IntegrityError(e)) => e,
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn signed_round_trip_for_every_codec() {
+    let data: Vec<TxRecord> = (1..20).map(sample).collect();
+    let key = SigningKey::new("auditor key");
+    for codec in [Codec::CsvCodec, Codec::TextCodec, Codec::BinaryCodec] {
+        let mut plain = Vec::new();
+        codec.write(&mut plain, &data).unwrap();
+        let mut signed = Vec::new();
+        codec
+            .write_signed(&mut signed, &data, &WriteOptions::default(), &key)
+            .expect("signed write should succeed");
+        // content stays readable, only the trailer is appended
+        assert!(signed.starts_with(&plain));
+        assert_eq!(signed.len(), plain.len() + 36);
+        let parsed = codec
+            .parse_signed(signed.as_slice(), &ParseOptions::default(), &key)
+            .expect("signed input should parse");
+        assert_eq!(parsed, data);
+    }
+}
+
+#[test]
+fn signature_of_known_layout_is_verified() {
+    assert_eq!(
+        sign(b"TX_ID,TX_TYPE\n", &SigningKey::new("auditor key")),
+        KNOWN_SIGNED
+    );
+    let content = verify(&KNOWN_SIGNED, &SigningKey::new("auditor key")).unwrap();
+    assert_eq!(content, b"TX_ID,TX_TYPE\n");
+}
+
+#[test]
+fn wrong_key_and_altered_input_are_rejected() {
+    let signed = sign(b"TX_ID: 1\nAMOUNT: 100\n", &SigningKey::new("auditor key"));
+    assert_eq!(
+        integrity_error(verify(&signed, &SigningKey::new("guess"))),
+        IntegrityError::SignatureMismatch
+    );
+    for altered in [0, 15, signed.len() - 1] {
+        let mut tampered = signed.clone();
+        tampered[altered] ^= 0x01;
+        assert_eq!(
+            integrity_error(verify(&tampered, &SigningKey::new("auditor key"))),
+            IntegrityError::SignatureMismatch
+        );
+    }
+}
+
+#[test]
+fn unsigned_and_truncated_inputs_are_rejected() {
+    let key = SigningKey::new("auditor key");
+    assert_eq!(
+        integrity_error(verify(b"TX_ID,TX_TYPE\n", &key)),
+        IntegrityError::MissingTrailer
+    );
+    assert_eq!(
+        integrity_error(verify(&KNOWN_SIGNED[..KNOWN_SIGNED.len() - 1], &key)),
+        IntegrityError::MissingTrailer
+    );
+    assert_eq!(
+        integrity_error(verify(b"", &key)),
+        IntegrityError::MissingTrailer
+    );
+    let err = Codec::CsvCodec
+        .parse_signed(&b"TX_ID,TX_TYPE\n"[..], &ParseOptions::default(), &key)
+        .unwrap_err();
+    assert!(err.to_string().contains("signature trailer is missing"));
+}
+
+#[test]
+fn key_debug_output_hides_secret() {
+    let printed = format!("{:?}", SigningKey::new("hunter2"));
+    assert!(!printed.contains("hunter2"));
+}
@@ -0,0 +1,196 @@
+use parser::codecs::base::Codec;
+use parser::codecs::binary_v2::BinaryV2Options;
+use parser::codecs::options::{SinkFlush, WriteOptions};
+use parser::domain::tx::{TxIdType, TxRecord};
+use parser::errors::AppError;
+use std::io::Write;
+use std::num::NonZeroUsize;
+
+fn records(count: u64) -> Vec<TxRecord> {
+    (0..count)
+        .map(|id| TxRecord {
+            id: TxIdType::Numeric(id),
+            description: Some(format!("payment {}", id % 3)),
+            tenant: (id % 2 == 0).then(|| "acme".to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn write_with(codec: &Codec, data: &[TxRecord], options: &WriteOptions) -> Vec<u8> {
+    let mut buff = Vec::new();
+    codec
+        .write_with(&mut buff, data, options)
+        .expect("write should succeed");
+    buff
+}
+
+fn push_all(codec: &Codec, data: &[TxRecord], options: &WriteOptions) -> Vec<u8> {
+    let mut buff = Vec::new();
+    let mut sink = codec
+        .open_sink(&mut buff, options)
+        .expect("sink should open");
+    for tx in data {
+        sink.push(tx).expect("push should succeed");
+    }
+    sink.finish().expect("finish should succeed");
+    buff
+}
+
+#[test]
+fn sink_output_matches_batch_writing() {
+    // more records than fit into one columnar block
+    let data = records(10_000);
+    let codecs = [
+        Codec::BinaryCodec,
+        Codec::BinaryV2Codec,
+        Codec::ColumnarBinaryCodec,
+        Codec::TextCodec,
+        #[cfg(feature = "bincode")]
+        Codec::BincodeCodec,
+        #[cfg(feature = "capnp")]
+        Codec::CapnpCodec,
+        #[cfg(feature = "xlsx")]
+        Codec::XlsxCodec,
+        Codec::LedgerCodec,
+        Codec::ReportCodec,
+        Codec::DummyCodec,
+    ];
+    let options = WriteOptions::default();
+    for codec in codecs {
+        assert!(
+            write_with(&codec, &data, &options) == push_all(&codec, &data, &options),
+            "{:?}",
+            codec
+        );
+    }
+}
+
+#[test]
+fn binary_v2_sink_deduplicates_descriptions() {
+    let data = records(100);
+    let options = WriteOptions {
+        binary_v2: BinaryV2Options {
+            dedup_descriptions: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let buff = push_all(&Codec::BinaryV2Codec, &data, &options);
+    assert_eq!(buff, write_with(&Codec::BinaryV2Codec, &data, &options));
+    assert_eq!(Codec::BinaryV2Codec.parse(buff.as_slice()).unwrap(), data);
+}
+
+#[test]
+fn delimited_sinks_always_emit_optional_columns() {
+    let data = records(5);
+    for (codec, columns) in [
+        (
+            Codec::CsvCodec,
+            &b"TENANT,FEE,REFERENCE,ORIGINAL_AMOUNT,EXCHANGE_RATE"[..],
+        ),
+        (
+            Codec::TsvCodec,
+            &b"TENANT\tFEE\tREFERENCE\tORIGINAL_AMOUNT\tEXCHANGE_RATE"[..],
+        ),
+    ] {
+        let buff = push_all(&codec, &data, &WriteOptions::default());
+        let header = buff.split(|&b| b == b'\n').next().unwrap();
+        assert!(header.ends_with(columns), "{:?}", codec);
+        assert_eq!(codec.parse(buff.as_slice()).unwrap(), data, "{:?}", codec);
+    }
+
+    let untenanted: Vec<TxRecord> = data
+        .into_iter()
+        .map(|tx| TxRecord { tenant: None, ..tx })
+        .collect();
+    let buff = push_all(&Codec::CsvCodec, &untenanted, &WriteOptions::default());
+    assert_eq!(Codec::CsvCodec.parse(buff.as_slice()).unwrap(), untenanted);
+}
+
+#[test]
+fn header_is_written_on_open() {
+    let mut buff = Vec::new();
+    let sink = Codec::CsvCodec
+        .open_sink(&mut buff, &WriteOptions::default())
+        .unwrap();
+    sink.finish().unwrap();
+    assert!(String::from_utf8(buff).unwrap().starts_with("TX_ID,"));
+
+    let buff = push_all(&Codec::MarkdownCodec, &[], &WriteOptions::default());
+    let table = String::from_utf8(buff).unwrap();
+    assert_eq!(table.lines().count(), 2);
+    assert!(
+        table
+            .lines()
+            .next()
+            .unwrap()
+            .ends_with("| TENANT | FEE | REFERENCE | ORIGINAL_AMOUNT | EXCHANGE_RATE |")
+    );
+
+    assert!(push_all(&Codec::BinaryCodec, &[], &WriteOptions::default()).is_empty());
+}
+
+#[test]
+fn read_only_formats_cannot_open_sink() {
+    for codec in [Codec::CamtCodec, Codec::FixCodec, Codec::Bai2Codec] {
+        let res = codec.open_sink(Vec::new(), &WriteOptions::default());
+        assert!(matches!(res, Err(AppError::WriteError(_))), "{:?}", codec);
+    }
+}
+
+// output recording how much of it was there on every flush
+#[derive(Default)]
+struct FlushLog {
+    data: Vec<u8>,
+    flushed: Vec<usize>,
+}
+
+impl Write for FlushLog {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flushed.push(self.data.len());
+        Ok(())
+    }
+}
+
+fn flushed_lines(codec: &Codec, count: u64, flush: SinkFlush) -> Vec<usize> {
+    let options = WriteOptions {
+        flush,
+        ..Default::default()
+    };
+    let mut log = FlushLog::default();
+    let mut sink = codec.open_sink(&mut log, &options).unwrap();
+    for tx in records(count) {
+        sink.push(&tx).unwrap();
+    }
+    sink.finish().unwrap();
+    let text = String::from_utf8(log.data.clone()).unwrap();
+    log.flushed
+        .iter()
+        .map(|&len| text[..len].lines().count())
+        .collect()
+}
+
+#[test]
+fn sink_output_is_flushed_by_policy() {
+    assert_eq!(
+        flushed_lines(&Codec::CsvCodec, 3, SinkFlush::OnFinish),
+        vec![4]
+    );
+    assert_eq!(
+        flushed_lines(&Codec::CsvCodec, 3, SinkFlush::EachRecord),
+        vec![2, 3, 4, 4]
+    );
+    let every_two = SinkFlush::Every(NonZeroUsize::new(2).unwrap());
+    assert_eq!(flushed_lines(&Codec::CsvCodec, 5, every_two), vec![3, 5, 6]);
+    // text records are a line per field and blank separator line, the first one has tenant
+    assert_eq!(
+        flushed_lines(&Codec::TextCodec, 2, SinkFlush::EachRecord),
+        vec![10, 19, 19]
+    );
+}
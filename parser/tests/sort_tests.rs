@@ -0,0 +1,115 @@
+use parser::codecs::base::Codec;
+use parser::codecs::options::ParseOptions;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use parser::transform::sort::{ExternalSorter, SortField, SortKey, SortOrder};
+
+fn sample_tx(id: u64, ts: u64, amount: Money) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(10 + id % 3),
+        to: AccountType::Numeric(22),
+        amount,
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Success,
+        description: Some(format!("payment {}", id)),
+        ..Default::default()
+    }
+}
+
+fn ids(data: &[TxRecord]) -> Vec<u64> {
+    data.iter()
+        .map(|tx| match tx.id {
+            TxIdType::Numeric(id) => id,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn dataset(len: u64) -> Vec<TxRecord> {
+    // timestamps repeat, so ties are frequent
+    (1..=len)
+        .map(|id| sample_tx(id, 1_000 * ((id * 7) % 5), Money::new(id as i64 % 4, 0)))
+        .collect()
+}
+
+#[test]
+fn records_are_sorted_by_several_keys() {
+    let data = vec![
+        sample_tx(1, 2_000, Money::new(5, 0)),
+        sample_tx(2, 1_000, Money::new(500, 2)),
+        sample_tx(3, 2_000, Money::new(7, 0)),
+        sample_tx(4, 1_000, Money::new(1, 0)),
+    ];
+    let order: SortOrder = "TIMESTAMP:DESC, AMOUNT"
+        .parse()
+        .expect("order should parse");
+    assert_eq!(
+        order.keys,
+        vec![
+            SortKey::desc(SortField::Timestamp),
+            SortKey::asc(SortField::Amount)
+        ]
+    );
+    assert_eq!(ids(&order.sorted(&data)), vec![1, 3, 4, 2]);
+
+    // equal amounts of different scales keep input order
+    let order = SortOrder::new(vec![SortKey::desc(SortField::Amount)]);
+    assert_eq!(ids(&order.sorted(&data)), vec![3, 1, 2, 4]);
+    assert_eq!(ids(&SortOrder::default().sorted(&data)), vec![1, 2, 3, 4]);
+
+    for invalid in ["DESCRIPTION", "AMOUNT:DOWN", "", "TX_ID,"] {
+        assert!(invalid.parse::<SortOrder>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn external_sort_matches_in_memory_one() {
+    let data = dataset(103);
+    let order: SortOrder = "TIMESTAMP,FROM_USER_ID:DESC".parse().unwrap();
+    let expected = order.sorted(&data);
+    for run_len in [1, 10, 103, 1_000] {
+        let sorted = ExternalSorter::new(order.clone(), run_len)
+            .sort(data.iter().cloned().map(Ok))
+            .expect("records should be sorted");
+        assert_eq!(sorted.spilled_runs(), 103 / run_len, "{}", run_len);
+        let sorted: Vec<TxRecord> = sorted.collect::<Result<_, _>>().unwrap();
+        assert_eq!(sorted, expected, "{}", run_len);
+    }
+}
+
+#[test]
+fn external_sort_keeps_all_fields_and_cleans_up() {
+    let dir = std::env::temp_dir().join(format!("rustyapa_sort_tests_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut data = dataset(5);
+    data[0].amount = Money::new(1_250, 3);
+    data[1].fee = Some(Money::from_minor_units(3));
+    data[2].tenant = Some("acme".to_string());
+    data[3].reference = Some("INV \"7\"".to_string());
+    data[4]
+        .extras
+        .insert("BRANCH".to_string(), "north".to_string());
+    data[4].description = None;
+
+    let order = SortOrder::new(vec![SortKey::desc(SortField::Id)]);
+    let sorted = ExternalSorter::new(order.clone(), 2)
+        .in_dir(&dir)
+        .sort(data.iter().cloned().map(Ok))
+        .unwrap();
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+    let sorted: Vec<TxRecord> = sorted.collect::<Result<_, _>>().unwrap();
+    assert_eq!(sorted, order.sorted(&data));
+    assert_eq!(sorted[4].amount.scale, 3);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    std::fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn external_sort_fails_on_input_error() {
+    let input = "TX_ID: 1\nTX_TYPE: DEPOSIT\n\nTX_ID: x\n";
+    let stream = Codec::TextCodec.parse_stream(input.as_bytes(), &ParseOptions::default());
+    let result = ExternalSorter::new(SortOrder::default(), 10).sort(stream);
+    assert!(matches!(result, Err(AppError::ParsingError { .. })));
+}
@@ -0,0 +1,154 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use parser::analytics::aggregate::{GroupBy, GroupValue};
+use parser::codecs::base::Codec;
+use parser::codecs::options::WriteOptions;
+use parser::domain::tx::{
+    AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp, UtcOffset,
+};
+use parser::transform::split::{PartitionedSink, split};
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+fn sample_tx(id: u64, kind: TxKind, from: u64, ts: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(99),
+        amount: Money::from_minor_units(100),
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn ids(records: &[TxRecord]) -> Vec<TxIdType> {
+    records.iter().map(|tx| tx.id).collect()
+}
+
+fn sample_data() -> Vec<TxRecord> {
+    vec![
+        sample_tx(1, TxKind::Deposit, 11, 0),
+        sample_tx(2, TxKind::Transfer, 22, DAY_MS - 1),
+        sample_tx(3, TxKind::Deposit, 22, DAY_MS),
+        sample_tx(4, TxKind::Transfer, 11, DAY_MS + 1),
+        sample_tx(5, TxKind::Deposit, 11, 2 * DAY_MS),
+    ]
+}
+
+#[test]
+fn records_are_partitioned_keeping_input_order() {
+    let data = sample_data();
+    let by_kind = split(&data, GroupBy::Kind);
+    assert_eq!(by_kind.len(), 2);
+    assert_eq!(
+        ids(&by_kind[&GroupValue::Kind(TxKind::Deposit)]),
+        vec![
+            TxIdType::Numeric(1),
+            TxIdType::Numeric(3),
+            TxIdType::Numeric(5)
+        ]
+    );
+    assert_eq!(
+        ids(&by_kind[&GroupValue::Kind(TxKind::Transfer)]),
+        vec![TxIdType::Numeric(2), TxIdType::Numeric(4)]
+    );
+
+    let by_account = split(&data, GroupBy::FromAccount);
+    assert_eq!(
+        ids(&by_account[&GroupValue::Account(AccountType::Numeric(22))]),
+        vec![TxIdType::Numeric(2), TxIdType::Numeric(3)]
+    );
+    assert!(split(&[], GroupBy::Status).is_empty());
+}
+
+#[test]
+fn days_follow_the_offset() {
+    let data = sample_data();
+    let utc = split(&data, GroupBy::Day(UtcOffset::UTC));
+    let sizes: BTreeMap<String, usize> = utc
+        .iter()
+        .map(|(day, records)| (day.to_string(), records.len()))
+        .collect();
+    assert_eq!(
+        sizes,
+        BTreeMap::from([
+            ("1970-01-01".to_string(), 2),
+            ("1970-01-02".to_string(), 2),
+            ("1970-01-03".to_string(), 1),
+        ])
+    );
+
+    let east: UtcOffset = "+01:00".parse().expect("offset should parse");
+    let local = split(&data, GroupBy::Day(east));
+    assert_eq!(
+        ids(&local[&GroupValue::Day("1970-01-01".into())]),
+        vec![TxIdType::Numeric(1)]
+    );
+    assert_eq!(
+        ids(&local[&GroupValue::Day("1970-01-02".into())]),
+        vec![
+            TxIdType::Numeric(2),
+            TxIdType::Numeric(3),
+            TxIdType::Numeric(4)
+        ]
+    );
+}
+
+#[test]
+fn partitions_are_streamed_to_sinks_of_their_own() {
+    let outputs: Rc<RefCell<BTreeMap<String, Vec<u8>>>> = Default::default();
+    let opened = Rc::clone(&outputs);
+    let mut sink = PartitionedSink::new(GroupBy::FromAccount, move |value: &GroupValue| {
+        let outputs = Rc::clone(&opened);
+        let name = value.to_string();
+        outputs.borrow_mut().insert(name.clone(), Vec::new());
+        Codec::CsvCodec.open_sink(SharedBuffer { outputs, name }, &WriteOptions::default())
+    });
+    for tx in sample_data() {
+        sink.push(&tx).expect("record should be written");
+    }
+    let counts = sink.finish().expect("sinks should finish");
+    assert_eq!(
+        counts,
+        BTreeMap::from([
+            (GroupValue::Account(AccountType::Numeric(11)), 3),
+            (GroupValue::Account(AccountType::Numeric(22)), 2),
+        ])
+    );
+
+    let outputs = outputs.borrow();
+    assert_eq!(outputs.len(), 2);
+    let records = Codec::CsvCodec
+        .parse(&outputs["22"][..])
+        .expect("partition should parse");
+    assert_eq!(
+        ids(&records),
+        vec![TxIdType::Numeric(2), TxIdType::Numeric(3)]
+    );
+}
+
+// appends everything written to the named buffer
+struct SharedBuffer {
+    outputs: Rc<RefCell<BTreeMap<String, Vec<u8>>>>,
+    name: String,
+}
+
+impl std::io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.outputs
+            .borrow_mut()
+            .get_mut(&self.name)
+            .expect("buffer should be opened")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
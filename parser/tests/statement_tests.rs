@@ -0,0 +1,88 @@
+use parser::codecs::base::Codec;
+use parser::domain::money::AmountOverflow;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::transform::statement::{self, BALANCE_KEY};
+
+fn sample_tx(id: u64, kind: TxKind, from: u64, to: u64, amount: i64, ts: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(to),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn dataset() -> Vec<TxRecord> {
+    let mut failed = sample_tx(5, TxKind::Deposit, 0, 1, 700, 1_500);
+    failed.status = TxStatus::Failure;
+    vec![
+        sample_tx(1, TxKind::Transfer, 1, 2, 300, 3_000),
+        sample_tx(2, TxKind::Deposit, 0, 1, 1_000, 1_000),
+        sample_tx(3, TxKind::Deposit, 0, 2, 50, 2_000),
+        sample_tx(4, TxKind::Fee, 1, 0, 25, 3_000),
+        failed,
+    ]
+}
+
+#[test]
+fn statement_lists_records_of_account_by_timestamp() {
+    let account = AccountType::Numeric(1);
+    let lines =
+        statement::statement(&dataset(), &account, Money::new(1, 0)).expect("balances should fit");
+    let rows: Vec<(TxIdType, i64, i64)> = lines
+        .iter()
+        .map(|line| {
+            (
+                line.tx.id,
+                line.effect.minor_units,
+                line.balance.rescale(2).unwrap().minor_units,
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (TxIdType::Numeric(2), 1_000, 1_100),
+            // same timestamp keeps input order
+            (TxIdType::Numeric(1), -300, 800),
+            (TxIdType::Numeric(4), -25, 775),
+        ]
+    );
+
+    let other =
+        statement::statement(&dataset(), &AccountType::Numeric(2), Money::default()).unwrap();
+    assert_eq!(other.len(), 2);
+    assert_eq!(other[1].balance, Money::from_minor_units(350));
+}
+
+#[test]
+fn running_balance_is_written_as_extra_column() {
+    let records =
+        statement::with_running_balance(&dataset(), &AccountType::Numeric(1), Money::default())
+            .unwrap();
+    assert_eq!(
+        records[2].extras.get(BALANCE_KEY).map(String::as_str),
+        Some("6.75")
+    );
+
+    let mut out = Vec::new();
+    Codec::CsvCodec.write(&mut out, &records).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let mut lines = out.lines();
+    assert!(lines.next().unwrap().ends_with(",BALANCE"));
+    assert!(lines.next().unwrap().ends_with(",10.00"));
+}
+
+#[test]
+fn overflowing_balance_is_an_error() {
+    let data = vec![sample_tx(1, TxKind::Deposit, 0, 1, i64::MAX, 1_000)];
+    assert_eq!(
+        statement::statement(&data, &AccountType::Numeric(1), Money::from_minor_units(1)),
+        Err(AmountOverflow)
+    );
+}
@@ -0,0 +1,145 @@
+use std::cell::Cell;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+use parser::codecs::base::Codec;
+use parser::codecs::errors::ParserError;
+use parser::codecs::options::{ParseOptions, Strictness};
+use parser::domain::tx::{TxIdType, TxRecord};
+use parser::errors::AppError;
+
+// counts bytes handed out by underlying stream
+struct CountingReader {
+    data: io::Cursor<Vec<u8>>,
+    read: Rc<Cell<usize>>,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.data.read(buf)?;
+        self.read.set(self.read.get() + n);
+        Ok(n)
+    }
+}
+
+fn records(count: u64) -> Vec<TxRecord> {
+    (0..count)
+        .map(|id| TxRecord {
+            id: TxIdType::Numeric(id),
+            description: Some("payment".to_string()),
+            tenant: (id % 2 == 0).then(|| "acme".to_string()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn write(codec: &Codec, data: &[TxRecord]) -> Vec<u8> {
+    let mut buff = Vec::new();
+    codec.write(&mut buff, data).expect("write should succeed");
+    buff
+}
+
+#[test]
+fn streams_match_batch_parsing() {
+    let data = records(100);
+    let codecs = [
+        Codec::BinaryCodec,
+        Codec::BinaryV2Codec,
+        Codec::ColumnarBinaryCodec,
+        Codec::TextCodec,
+        Codec::CsvCodec,
+        #[cfg(feature = "bincode")]
+        Codec::BincodeCodec,
+        #[cfg(feature = "capnp")]
+        Codec::CapnpCodec,
+        #[cfg(feature = "xlsx")]
+        Codec::XlsxCodec,
+    ];
+    for codec in codecs {
+        let buff = write(&codec, &data);
+        let streamed: Vec<TxRecord> = codec
+            .parse_stream(buff.as_slice(), &ParseOptions::default())
+            .collect::<Result<_, _>>()
+            .unwrap_or_else(|e| panic!("{:?} stream should succeed: {}", codec, e));
+        assert_eq!(streamed, data, "{:?}", codec);
+    }
+}
+
+#[test]
+fn binary_stream_reads_input_on_demand() {
+    let buff = write(&Codec::BinaryCodec, &records(20_000));
+    let total = buff.len();
+    let read = Rc::new(Cell::new(0));
+    let reader = CountingReader {
+        data: io::Cursor::new(buff),
+        read: read.clone(),
+    };
+    let mut stream = Codec::BinaryCodec.parse_stream(reader, &ParseOptions::default());
+    let first = stream.next().expect("stream has records");
+    assert_eq!(
+        first.expect("first record is valid").id,
+        TxIdType::Numeric(0)
+    );
+    assert!(
+        read.get() < total / 2,
+        "{} of {} bytes read",
+        read.get(),
+        total
+    );
+    assert_eq!(stream.count(), 19_999);
+}
+
+#[test]
+fn stream_yields_errors_of_malformed_records_and_continues() {
+    let input = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION
+1,DEPOSIT,0,10,100,1700,SUCCESS,\"ok\"
+2,DEPOSIT,0,10,abc,1700,SUCCESS,\"bad amount\"
+3,DEPOSIT,0,10,300,1700,SUCCESS,\"ok\"
+";
+    let items: Vec<Result<TxRecord, AppError>> = Codec::CsvCodec
+        .parse_stream(input.as_bytes(), &ParseOptions::default())
+        .collect();
+    assert_eq!(items.len(), 3);
+    assert!(items[0].is_ok());
+    assert!(matches!(items[1], Err(AppError::ParsingError { .. })));
+    assert_eq!(
+        items[2].as_ref().map(|tx| tx.id).ok(),
+        Some(TxIdType::Numeric(3))
+    );
+}
+
+#[test]
+fn stream_ends_after_fatal_error() {
+    let mut buff = write(&Codec::BinaryCodec, &records(3));
+    buff.extend_from_slice(b"XXXX");
+    buff.extend(write(&Codec::BinaryCodec, &records(3)));
+    let items: Vec<Result<TxRecord, AppError>> = Codec::BinaryCodec
+        .parse_stream(buff.as_slice(), &ParseOptions::default())
+        .collect();
+    assert_eq!(items.len(), 4);
+    assert!(items[3].is_err());
+}
+
+#[test]
+fn strict_stream_without_records_is_rejected() {
+    let options = ParseOptions {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    };
+    let items: Vec<Result<TxRecord, AppError>> = Codec::BinaryCodec
+        .parse_stream(&b""[..], &options)
+        .collect();
+    assert!(matches!(
+        items.as_slice(),
+        [Err(AppError::ParsingError {
+            source: ParserError::EmptyInput,
+            ..
+        })]
+    ));
+    assert_eq!(
+        Codec::TextCodec
+            .parse_stream(&b""[..], &ParseOptions::default())
+            .count(),
+        0
+    );
+}
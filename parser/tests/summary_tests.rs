@@ -0,0 +1,125 @@
+use parser::analytics::{Summary, summarize};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+
+fn sample_tx(id: u64, kind: TxKind, from: u64, to: u64, amount: Money, ts: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(to),
+        amount,
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn dataset() -> Vec<TxRecord> {
+    let mut failed = sample_tx(
+        4,
+        TxKind::Transfer,
+        2,
+        3,
+        Money::new(1, 0),
+        1_704_067_200_000,
+    );
+    failed.status = TxStatus::Failure;
+    vec![
+        sample_tx(
+            1,
+            TxKind::Deposit,
+            0,
+            1,
+            Money::new(1_000, 2),
+            1_704_153_600_000,
+        ),
+        sample_tx(
+            2,
+            TxKind::Transfer,
+            1,
+            2,
+            Money::new(2_505, 3),
+            1_704_110_400_000,
+        ),
+        sample_tx(
+            3,
+            TxKind::Withdrawal,
+            2,
+            0,
+            Money::new(5, 0),
+            1_704_240_000_000,
+        ),
+        failed,
+    ]
+}
+
+#[test]
+fn records_are_summarized() {
+    let summary = summarize(&dataset());
+    assert_eq!(summary.records, 4);
+    assert_eq!(
+        summary.kinds.into_iter().collect::<Vec<_>>(),
+        vec![
+            (TxKind::Deposit, 1),
+            (TxKind::Transfer, 2),
+            (TxKind::Withdrawal, 1)
+        ]
+    );
+    assert_eq!(summary.statuses[&TxStatus::Failure], 1);
+    let amounts = summary.amounts.expect("amounts should be summarized");
+    assert_eq!(amounts.min, Money::new(1, 0));
+    assert_eq!(amounts.max, Money::new(10, 0));
+    assert_eq!(amounts.total, Some(Money::new(18_505, 3)));
+    // 4.62625 rounded to scale of total
+    assert_eq!(amounts.mean, Some(Money::new(4_626, 3)));
+    assert_eq!(
+        summary.first_timestamp,
+        Some(TxTimestamp::from_millis(1_704_067_200_000))
+    );
+    assert_eq!(
+        summary.last_timestamp,
+        Some(TxTimestamp::from_millis(1_704_240_000_000))
+    );
+    // outside side of deposit and withdrawal is not an account
+    assert_eq!(
+        (summary.accounts, summary.sources, summary.destinations),
+        (3, 2, 3)
+    );
+}
+
+#[test]
+fn summary_is_rendered_as_text_and_json() {
+    let summary = summarize(&dataset()[..2]);
+    assert_eq!(
+        summary.to_string(),
+        "records: 2\n\
+         kinds: DEPOSIT 1, TRANSFER 1\n\
+         statuses: SUCCESS 2\n\
+         amounts: min 2.505, max 10.00, mean 6.253, total 12.505\n\
+         timestamps: 2024-01-01T12:00:00.000Z .. 2024-01-02T00:00:00.000Z\n\
+         accounts: 2 (1 sources, 2 destinations)\n"
+    );
+    assert_eq!(
+        summary.to_json(),
+        r#"{"records":2,"kinds":{"DEPOSIT":1,"TRANSFER":1},"statuses":{"SUCCESS":2},"amounts":{"min":2.505,"max":10.00,"mean":6.253,"total":12.505},"first_timestamp":"2024-01-01T12:00:00.000Z","last_timestamp":"2024-01-02T00:00:00.000Z","accounts":2,"sources":1,"destinations":2}"#
+    );
+}
+
+#[test]
+fn empty_and_overflowing_datasets_are_summarized() {
+    assert_eq!(summarize(&[]), Summary::default());
+    assert_eq!(
+        summarize(&[]).to_json(),
+        r#"{"records":0,"kinds":{},"statuses":{},"amounts":null,"first_timestamp":null,"last_timestamp":null,"accounts":0,"sources":0,"destinations":0}"#
+    );
+
+    let max = Money::from_minor_units(i64::MAX);
+    let data = vec![
+        sample_tx(1, TxKind::Deposit, 0, 1, max, 1_000),
+        sample_tx(2, TxKind::Deposit, 0, 1, max, 2_000),
+    ];
+    let amounts = summarize(&data).amounts.unwrap();
+    assert_eq!((amounts.total, amounts.mean), (None, None));
+    assert!(summarize(&data).to_string().contains("mean -, total -"));
+}
@@ -0,0 +1,139 @@
+use parser::codecs::base::Codec;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::transform::tenant::{filter_by_tenant, group_by_tenant};
+
+fn sample_tx(id: u64, tenant: Option<&str>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Deposit,
+        from: AccountType::Numeric(0),
+        to: AccountType::Numeric(7),
+        amount: Money::from_minor_units(10),
+        ts: TxTimestamp::from_millis(1700),
+        status: TxStatus::Success,
+        description: Some("x".into()),
+        tenant: tenant.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn tenant_round_trips_in_all_codecs() {
+    let data = vec![sample_tx(1, Some("emea")), sample_tx(2, None)];
+    for codec in [Codec::BinaryCodec, Codec::TextCodec, Codec::CsvCodec] {
+        let mut bytes = Vec::new();
+        codec
+            .write(&mut bytes, &data)
+            .expect("write should succeed");
+        let parsed = codec.parse(bytes.as_slice()).expect("parse should succeed");
+        assert_eq!(parsed, data, "{:?} should keep tenant", codec);
+    }
+}
+
+#[test]
+fn tenant_with_quotes_and_line_breaks_round_trips_through_text() {
+    let data = vec![sample_tx(1, Some("multi\nline \"acme\" corp"))];
+    let mut csv = Vec::new();
+    Codec::CsvCodec.write(&mut csv, &data).expect("csv write");
+    let from_csv = Codec::CsvCodec.parse(csv.as_slice()).expect("csv parse");
+
+    let mut text = Vec::new();
+    Codec::TextCodec
+        .write(&mut text, &from_csv)
+        .expect("text write");
+    assert!(
+        String::from_utf8(text.clone())
+            .unwrap()
+            .contains("TENANT: \"multi\\nline \\\"acme\\\" corp\"\n")
+    );
+    let from_text = Codec::TextCodec.parse(text.as_slice()).expect("text parse");
+
+    let mut round_trip = Vec::new();
+    Codec::CsvCodec
+        .write(&mut round_trip, &from_text)
+        .expect("csv write");
+    assert_eq!(round_trip, csv);
+    assert_eq!(from_text, data);
+}
+
+#[test]
+fn quoted_tenant_is_unquoted_by_text_parser() {
+    let record = |tenant: &str| {
+        format!(
+            "TX_ID: 1\nTX_TYPE: DEPOSIT\nFROM_USER_ID: 0\nTO_USER_ID: 7\nAMOUNT: 10\n\
+             TIMESTAMP: 1700\nSTATUS: SUCCESS\nTENANT: {}\n",
+            tenant
+        )
+    };
+    let tenant = |input: String| {
+        Codec::TextCodec
+            .parse(input.as_bytes())
+            .expect("text parse")
+            .remove(0)
+            .tenant
+    };
+    assert_eq!(
+        tenant(record("\"acme corp\"")).as_deref(),
+        Some("acme corp")
+    );
+    assert_eq!(tenant(record("acme")).as_deref(), Some("acme"));
+    assert_eq!(tenant(record("\"\"")).as_deref(), Some(""));
+}
+
+#[test]
+fn records_without_tenant_keep_original_layout() {
+    let data = vec![sample_tx(1, None)];
+
+    let mut csv = Vec::new();
+    Codec::CsvCodec.write(&mut csv, &data).expect("csv write");
+    assert!(String::from_utf8(csv).unwrap().starts_with(
+        "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n"
+    ));
+
+    let mut text = Vec::new();
+    Codec::TextCodec
+        .write(&mut text, &data)
+        .expect("text write");
+    assert!(!String::from_utf8(text).unwrap().contains("TENANT"));
+
+    let mut binary = Vec::new();
+    Codec::BinaryCodec
+        .write(&mut binary, &data)
+        .expect("binary write");
+    assert_eq!(binary.len(), 8 + 46 + 1);
+}
+
+#[test]
+fn csv_with_tenant_column_parses() {
+    let input = "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION,TENANT\n\
+                 1,DEPOSIT,0,7,10,1700,SUCCESS,\"x\",apac\n\
+                 2,DEPOSIT,0,7,10,1700,SUCCESS,\"x\",\n";
+    let parsed = Codec::CsvCodec
+        .parse(input.as_bytes())
+        .expect("csv with tenant should parse");
+    assert_eq!(parsed[0].tenant.as_deref(), Some("apac"));
+    assert_eq!(parsed[1].tenant, None);
+}
+
+#[test]
+fn filter_and_group_by_tenant() {
+    let data = vec![
+        sample_tx(1, Some("emea")),
+        sample_tx(2, None),
+        sample_tx(3, Some("emea")),
+        sample_tx(4, Some("apac")),
+    ];
+    let emea = filter_by_tenant(&data, Some("emea"));
+    assert_eq!(
+        emea.iter()
+            .filter_map(|tx| tx.id.as_numeric())
+            .collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+    assert_eq!(filter_by_tenant(&data, None).len(), 1);
+
+    let groups = group_by_tenant(&data);
+    assert_eq!(groups.len(), 3);
+    assert_eq!(groups[&Some("emea".to_string())].len(), 2);
+    assert_eq!(groups[&None].len(), 1);
+}
@@ -1,6 +1,11 @@
 use parser::codecs::base::Codec;
 use parser::codecs::base::TxFieldKey;
 use parser::codecs::errors::ParserError;
+use parser::codecs::options::{
+    AmountFormat, ParseOptions, TextEncoding, TimestampFormat, WriteOptions,
+};
+use parser::codecs::text::{RecordSeparator, TextDialect, TextWriteOptions};
+use parser::domain::tx::{AccountType, Money, TxKind, TxStatus};
 use parser::errors::AppError;
 
 const RECORD_1: &str = r#"TX_ID: 1
@@ -40,8 +45,8 @@ DESCRIPTION: "Fee"
         .parse(input.as_bytes())
         .expect("text parse should succeed");
     assert_eq!(records.len(), 2);
-    assert_eq!(records[0].id.0, 1);
-    assert_eq!(records[1].id.0, 2);
+    assert_eq!(records[0].id.as_numeric(), Some(1));
+    assert_eq!(records[1].id.as_numeric(), Some(2));
 }
 
 #[test]
@@ -60,8 +65,8 @@ TX_ID: 7
         .parse(input.as_bytes())
         .expect("record with shuffled fields should parse");
     assert_eq!(records.len(), 1);
-    assert_eq!(records[0].id.0, 7);
-    assert_eq!(records[0].description, "Out of order");
+    assert_eq!(records[0].id.as_numeric(), Some(7));
+    assert_eq!(records[0].description.as_deref(), Some("Out of order"));
 }
 
 #[test]
@@ -97,22 +102,46 @@ FROM_USER_ID: 0
 TO_USER_ID: 100
 AMOUNT: 500
 TIMESTAMP: 1700
-STATUS: SUCCESS
+DESCRIPTION: "no status"
 
 "#;
 
     let err = Codec::TextCodec
         .parse(input.as_bytes())
-        .expect_err("missing description should fail");
+        .expect_err("missing status should fail");
     assert!(matches!(
         err,
         AppError::ParsingError {
             context: _,
-            source: ParserError::MissingField(TxFieldKey::Description),
+            source: ParserError::MissingField(TxFieldKey::Status),
         }
     ));
 }
 
+#[test]
+fn parse_accepts_missing_description() {
+    let input = r#"TX_ID: 1
+TX_TYPE: DEPOSIT
+FROM_USER_ID: 0
+TO_USER_ID: 100
+AMOUNT: 500
+TIMESTAMP: 1700
+STATUS: SUCCESS
+
+"#;
+
+    let records = Codec::TextCodec
+        .parse(input.as_bytes())
+        .expect("record without description should parse");
+    assert_eq!(records[0].description, None);
+
+    let mut buff = Vec::new();
+    Codec::TextCodec
+        .write(&mut buff, &records)
+        .expect("text write should succeed");
+    assert!(!String::from_utf8(buff).unwrap().contains("DESCRIPTION"));
+}
+
 #[test]
 fn parse_rejects_line_without_delimiter() {
     let input = "TX_ID 1\n";
@@ -174,3 +203,427 @@ fn text_write_then_parse_single_record() {
         .expect("written text should parse");
     assert_eq!(reparsed, records);
 }
+
+#[test]
+fn refund_fee_and_reversal_kinds_round_trip() {
+    for (token, kind) in [
+        ("REFUND", TxKind::Refund),
+        ("FEE", TxKind::Fee),
+        ("REVERSAL", TxKind::Reversal),
+    ] {
+        let input = RECORD_1.replace("DEPOSIT", token);
+        let records = Codec::TextCodec
+            .parse(input.as_bytes())
+            .expect("new kinds should parse");
+        assert_eq!(records[0].kind, kind);
+
+        let mut bytes = Vec::new();
+        Codec::TextCodec
+            .write(&mut bytes, &records)
+            .expect("text write should succeed");
+        assert!(String::from_utf8(bytes).unwrap().contains(token));
+    }
+}
+
+#[test]
+fn cancelled_reversed_and_expired_statuses_round_trip() {
+    for (token, status) in [
+        ("CANCELLED", TxStatus::Cancelled),
+        ("REVERSED", TxStatus::Reversed),
+        ("EXPIRED", TxStatus::Expired),
+    ] {
+        let input = RECORD_1.replace("SUCCESS", token);
+        let records = Codec::TextCodec
+            .parse(input.as_bytes())
+            .expect("new statuses should parse");
+        assert_eq!(records[0].status, status);
+
+        let mut bytes = Vec::new();
+        Codec::TextCodec
+            .write(&mut bytes, &records)
+            .expect("text write should succeed");
+        assert!(String::from_utf8(bytes).unwrap().contains(token));
+    }
+}
+
+#[test]
+fn iban_accounts_round_trip() {
+    let input = RECORD_1.replace("TO_USER_ID: 100", "TO_USER_ID: GB82 WEST 1234 5698 7654 32");
+    let records = Codec::TextCodec
+        .parse(input.as_bytes())
+        .expect("iban account should parse");
+    assert_eq!(
+        records[0].to,
+        AccountType::Iban("GB82WEST12345698765432".into())
+    );
+
+    let mut bytes = Vec::new();
+    Codec::TextCodec
+        .write(&mut bytes, &records)
+        .expect("text write should succeed");
+    let reparsed = Codec::TextCodec
+        .parse(bytes.as_slice())
+        .expect("written iban should parse");
+    assert_eq!(reparsed, records);
+
+    let input = RECORD_1.replace("TO_USER_ID: 100", "TO_USER_ID: GB00WEST12345698765432");
+    assert!(Codec::TextCodec.parse(input.as_bytes()).is_err());
+}
+
+#[test]
+fn descriptions_with_quotes_and_line_breaks_round_trip() {
+    let tx = parser::domain::tx::TxRecord {
+        description: Some("say \"hi\"\nC:\\temp\r\n".to_string()),
+        ..Default::default()
+    };
+    let mut buff = Vec::new();
+    Codec::TextCodec
+        .write(&mut buff, std::slice::from_ref(&tx))
+        .expect("text write should succeed");
+    let text = String::from_utf8(buff.clone()).unwrap();
+    assert!(text.contains(r#"DESCRIPTION: "say \"hi\"\nC:\\temp\r\n""#));
+
+    let parsed = Codec::TextCodec
+        .parse(buff.as_slice())
+        .expect("escaped description should parse");
+    assert_eq!(parsed, vec![tx]);
+}
+
+#[test]
+fn unknown_escapes_and_bare_quotes_are_kept() {
+    let input = RECORD_1.replace(r#""Salary""#, r#""pay "now" to C:\data""#);
+    let parsed = Codec::TextCodec.parse(input.as_bytes()).unwrap();
+    assert_eq!(
+        parsed[0].description.as_deref(),
+        Some(r#"pay "now" to C:\data"#)
+    );
+
+    let input = RECORD_1.replace(r#""Salary""#, r#""open\""#);
+    let err = Codec::TextCodec
+        .parse(input.as_bytes())
+        .expect_err("escaped closing quote leaves description open");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::ShellBeQuoted(_),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn parse_strips_inline_and_block_comments() {
+    let input = r#"/* fixture for
+   the salary case
+
+   spans several lines */
+TX_ID: 1 # first one
+TX_TYPE: DEPOSIT /* money in */
+FROM_USER_ID: 0
+TO_USER_ID: 100
+AMOUNT: 500    # minor units
+TIMESTAMP: 1700
+/* status is final */
+STATUS: SUCCESS
+DESCRIPTION: "Salary #3 /* not a comment */" # paid by "acme"
+TENANT: acme#1
+
+TX_ID: 2
+TX_TYPE: DEPOSIT
+FROM_USER_ID: 0
+TO_USER_ID: 100
+AMOUNT: 500
+TIMESTAMP: 1700
+STATUS: SUCCESS
+DESCRIPTION: "second"
+"#;
+    let parsed = Codec::TextCodec
+        .parse(input.as_bytes())
+        .expect("commented fixture should parse");
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].id.as_numeric(), Some(1));
+    assert_eq!(parsed[0].amount, Money::from_minor_units(500));
+    assert_eq!(
+        parsed[0].description.as_deref(),
+        Some("Salary #3 /* not a comment */")
+    );
+    assert_eq!(parsed[0].tenant.as_deref(), Some("acme#1"));
+    assert_eq!(parsed[1].description.as_deref(), Some("second"));
+}
+
+const HAND_EDITED: &str = r#"tx_id: 1
+TxType: DEPOSIT
+sender: 0
+To-User-Id: 100
+amount: 500
+Timestamp: 1700
+STATUS: SUCCESS
+Description: "Salary"
+"#;
+
+fn dialect(lenient_keys: bool) -> ParseOptions {
+    ParseOptions {
+        text: TextDialect {
+            lenient_keys,
+            aliases: vec![("SENDER".to_string(), TxFieldKey::FromUserId)],
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn lenient_keys_ignore_case_and_separators() {
+    for (key, expected) in [
+        ("tx_id", TxFieldKey::Id),
+        ("TxId", TxFieldKey::Id),
+        ("from user id", TxFieldKey::FromUserId),
+        ("TENANT", TxFieldKey::Tenant),
+    ] {
+        assert_eq!(TxFieldKey::from_str_lenient(key).unwrap(), expected);
+    }
+    assert!(matches!(
+        TxFieldKey::from_str_lenient("TXIDS"),
+        Err(ParserError::UnparsableKey(_))
+    ));
+    assert!("tx_id".parse::<TxFieldKey>().is_err());
+
+    let expected = Codec::TextCodec.parse(RECORD_1.as_bytes()).unwrap();
+    let parsed = Codec::TextCodec
+        .parse_with(HAND_EDITED.as_bytes(), &dialect(true))
+        .expect("hand edited keys should parse");
+    assert_eq!(parsed, expected);
+}
+
+#[test]
+fn aliases_are_matched_exactly_without_lenient_keys() {
+    let input = RECORD_1.replace("FROM_USER_ID", "SENDER");
+    let parsed = Codec::TextCodec
+        .parse_with(input.as_bytes(), &dialect(false))
+        .unwrap();
+    assert_eq!(parsed, Codec::TextCodec.parse(RECORD_1.as_bytes()).unwrap());
+
+    let err = Codec::TextCodec
+        .parse_with(HAND_EDITED.as_bytes(), &dialect(false))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableKey(_),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn rfc3339_timestamps_are_read_and_written() {
+    let options = ParseOptions {
+        text: TextDialect {
+            timestamps: TimestampFormat::Rfc3339,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let expected = Codec::TextCodec.parse(RECORD_1.as_bytes()).unwrap();
+    let millis = expected[0].ts.millis();
+    for ts in [
+        "1970-01-01T00:00:01.700Z".to_string(),
+        "1970-01-01T01:00:01.7+01:00".to_string(),
+        millis.to_string(),
+    ] {
+        let input = RECORD_1.replace(
+            &format!("TIMESTAMP: {}", millis),
+            &format!("TIMESTAMP: {}", ts),
+        );
+        let parsed = Codec::TextCodec
+            .parse_with(input.as_bytes(), &options)
+            .expect("RFC 3339 timestamp should parse");
+        assert_eq!(parsed, expected, "{}", ts);
+    }
+    let input = RECORD_1.replace(
+        &format!("TIMESTAMP: {}", millis),
+        "TIMESTAMP: 1970-13-01T00:00:00Z",
+    );
+    assert!(
+        Codec::TextCodec
+            .parse_with(input.as_bytes(), &options)
+            .is_err()
+    );
+
+    let write_options = WriteOptions {
+        text: TextWriteOptions {
+            timestamps: TimestampFormat::Rfc3339,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    Codec::TextCodec
+        .write_with(&mut out, &expected, &write_options)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("TIMESTAMP: 1970-01-01T00:00:01.700Z\n"));
+    assert_eq!(
+        Codec::TextCodec
+            .parse_with(out.as_bytes(), &options)
+            .unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn decimal_amounts_are_read_and_written_exactly() {
+    let cents = AmountFormat::Decimal { scale: 2 };
+    let options = ParseOptions {
+        text: TextDialect {
+            amounts: cents,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let expected = Codec::TextCodec.parse(RECORD_1.as_bytes()).unwrap();
+    for amount in ["5.00", "5", "5.0", "+5"] {
+        let input = RECORD_1.replace("AMOUNT: 500", &format!("AMOUNT: {}", amount));
+        let parsed = Codec::TextCodec
+            .parse_with(input.as_bytes(), &options)
+            .unwrap();
+        assert_eq!(parsed, expected, "{}", amount);
+    }
+    // fraction digits beyond scale would be lost
+    let input = RECORD_1.replace("AMOUNT: 500", "AMOUNT: 5.001");
+    assert!(
+        Codec::TextCodec
+            .parse_with(input.as_bytes(), &options)
+            .is_err()
+    );
+
+    let mut records = expected.clone();
+    records[0].amount = Money::from_minor_units(-1234);
+    let write_options = WriteOptions {
+        text: TextWriteOptions {
+            amounts: cents,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    Codec::TextCodec
+        .write_with(&mut out, &records, &write_options)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("AMOUNT: -12.34\n"));
+    assert_eq!(
+        Codec::TextCodec
+            .parse_with(out.as_bytes(), &options)
+            .unwrap(),
+        records
+    );
+}
+
+#[test]
+fn utf16_input_is_read_with_explicit_or_detected_encoding() {
+    let expected = Codec::TextCodec.parse(RECORD_1.as_bytes()).unwrap();
+    let utf16be: Vec<u8> = RECORD_1.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    let options = |encoding| ParseOptions {
+        encoding,
+        ..Default::default()
+    };
+    let parsed = Codec::TextCodec
+        .parse_with(utf16be.as_slice(), &options(TextEncoding::Utf16Be))
+        .unwrap();
+    assert_eq!(parsed, expected);
+
+    let with_bom = [&[0xFE, 0xFF][..], &utf16be].concat();
+    let mut stream =
+        Codec::TextCodec.parse_stream(with_bom.as_slice(), &options(TextEncoding::Detect));
+    assert_eq!(stream.next().unwrap().unwrap(), expected[0]);
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn unknown_keys_are_kept_as_extras() {
+    let input = RECORD_1.replace(
+        "AMOUNT: 500",
+        "AMOUNT: 500\nVENDOR_REF: A-17\nNOTE: \"two\\nlines\"",
+    );
+    assert!(Codec::TextCodec.parse(input.as_bytes()).is_err());
+    let options = ParseOptions {
+        text: TextDialect {
+            keep_extras: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let records = Codec::TextCodec
+        .parse_with(input.as_bytes(), &options)
+        .unwrap();
+    assert_eq!(records[0].extras["VENDOR_REF"], "A-17");
+    assert_eq!(records[0].extras["NOTE"], "two\nlines");
+
+    let mut out = Vec::new();
+    Codec::TextCodec.write(&mut out, &records).unwrap();
+    assert_eq!(
+        Codec::TextCodec
+            .parse_with(out.as_slice(), &options)
+            .unwrap(),
+        records
+    );
+
+    let mut unwritable = records[0].clone();
+    unwritable
+        .extras
+        .insert("TX_ID".to_string(), "2".to_string());
+    assert!(
+        Codec::TextCodec
+            .write(&mut Vec::new(), &[unwritable])
+            .is_err()
+    );
+}
+
+#[test]
+fn records_may_be_separated_by_custom_line_and_several_blank_lines() {
+    let second = RECORD_1.replace("TX_ID: 1", "TX_ID: 2");
+    let expected = Codec::TextCodec
+        .parse(format!("{}\n{}", RECORD_1, second).as_bytes())
+        .unwrap();
+
+    let spaced = format!("\n\n{}\n\n \n\n{}\n\n", RECORD_1, second);
+    assert_eq!(Codec::TextCodec.parse(spaced.as_bytes()).unwrap(), expected);
+
+    let separator = RecordSeparator::Line("---".to_string());
+    let options = ParseOptions {
+        text: TextDialect {
+            separator: separator.clone(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let frontmatter = format!("---\n{}---\n\n---\n{}---\n", RECORD_1, second);
+    assert_eq!(
+        Codec::TextCodec
+            .parse_with(frontmatter.as_bytes(), &options)
+            .unwrap(),
+        expected
+    );
+
+    let write_options = WriteOptions {
+        text: TextWriteOptions {
+            separator,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    Codec::TextCodec
+        .write_with(&mut out, &expected, &write_options)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.matches("\n---\n").count(), 2);
+    assert!(!out.contains("\n\n"));
+    assert_eq!(
+        Codec::TextCodec
+            .parse_with(out.as_bytes(), &options)
+            .unwrap(),
+        expected
+    );
+}
@@ -0,0 +1,105 @@
+use parser::codecs::base::Codec;
+use parser::codecs::csv::{CsvDialect, CsvWriteOptions};
+use parser::codecs::options::{ParseOptions, TimestampFormat, WriteOptions};
+use parser::codecs::text::{TextDialect, TextWriteOptions};
+use parser::domain::tx::{TxTimestamp, UtcOffset, ZonedTimestamp};
+
+const CSV_HEADER: &str =
+    "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n";
+
+fn cest() -> UtcOffset {
+    "+02:00".parse().expect("offset should parse")
+}
+
+#[test]
+fn utc_offset_is_parsed_and_displayed() {
+    assert_eq!(cest().minutes(), 120);
+    assert_eq!(cest().to_string(), "+02:00");
+    assert_eq!("Z".parse::<UtcOffset>().ok(), Some(UtcOffset::UTC));
+    assert_eq!("-05:30".parse::<UtcOffset>().unwrap().minutes(), -330);
+    for invalid in ["+2:00", "+24:00", "02:00", "+02:60", ""] {
+        assert!(invalid.parse::<UtcOffset>().is_err(), "{}", invalid);
+    }
+    assert_eq!(UtcOffset::from_minutes(24 * 60), None);
+}
+
+#[test]
+fn zoned_timestamp_keeps_local_date() {
+    // half past midnight in Berlin is still previous day in UTC
+    let zoned = ZonedTimestamp::parse_rfc3339("2024-01-03T00:30:00+02:00", UtcOffset::UTC)
+        .expect("timestamp should parse");
+    assert_eq!(zoned.ts.to_rfc3339(), "2024-01-02T22:30:00.000Z");
+    assert_eq!(zoned.offset, cest());
+    assert_eq!(zoned.local_date(), "2024-01-03");
+    assert_eq!(zoned.to_string(), "2024-01-03T00:30:00.000+02:00");
+
+    // values without offset are local time at the default one
+    let local = ZonedTimestamp::parse_rfc3339("2024-01-03T00:30:00", cest()).unwrap();
+    assert_eq!(local, zoned);
+    assert_eq!(
+        zoned.ts.at_offset(UtcOffset::UTC).local_date(),
+        "2024-01-02"
+    );
+}
+
+#[test]
+fn local_timestamps_round_trip_csv_and_text() {
+    let input = format!(
+        "{}1,DEPOSIT,0,1,100,2024-01-03T00:30:00,SUCCESS,\"ok\"\n2,DEPOSIT,0,1,100,2024-01-02T22:30:00Z,SUCCESS,\"ok\"\n",
+        CSV_HEADER
+    );
+    let format = TimestampFormat::Local(cest());
+    let parse_options = ParseOptions {
+        csv: CsvDialect {
+            timestamps: format,
+            ..Default::default()
+        },
+        text: TextDialect {
+            timestamps: format,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let records = Codec::CsvCodec
+        .parse_with(input.as_bytes(), &parse_options)
+        .expect("local times should parse");
+    let expected = TxTimestamp::parse_rfc3339("2024-01-02T22:30:00Z").unwrap();
+    assert!(records.iter().all(|tx| tx.ts == expected));
+
+    let write_options = WriteOptions {
+        csv: CsvWriteOptions {
+            timestamps: format,
+            ..Default::default()
+        },
+        text: TextWriteOptions {
+            timestamps: format,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    for codec in [Codec::CsvCodec, Codec::TextCodec] {
+        let mut out = Vec::new();
+        codec
+            .write_with(&mut out, &records, &write_options)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("2024-01-03T00:30:00.000+02:00"), "{}", out);
+        assert_eq!(
+            codec.parse_with(out.as_bytes(), &parse_options).unwrap(),
+            records
+        );
+        // offsets are honored by plain RFC 3339 format as well
+        let utc = ParseOptions {
+            csv: CsvDialect {
+                timestamps: TimestampFormat::Rfc3339,
+                ..Default::default()
+            },
+            text: TextDialect {
+                timestamps: TimestampFormat::Rfc3339,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(codec.parse_with(out.as_bytes(), &utc).unwrap(), records);
+    }
+}
@@ -0,0 +1,118 @@
+use parser::analytics::top::{
+    AccountVolume, AccountVolumes, Flow, LargestRecords, largest, top_accounts,
+};
+use parser::codecs::base::Codec;
+use parser::domain::money::AmountOverflow;
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+
+fn sample_tx(id: u64, kind: TxKind, from: u64, to: u64, amount: i64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(to),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(1_700_000_000_000 + id),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn ids(records: &[TxRecord]) -> Vec<u64> {
+    records
+        .iter()
+        .map(|tx| tx.id.as_numeric().expect("numeric id"))
+        .collect()
+}
+
+fn volume(account: u64, count: usize, volume: i64) -> AccountVolume {
+    AccountVolume {
+        account: AccountType::Numeric(account),
+        count,
+        volume: Money::from_minor_units(volume),
+    }
+}
+
+fn sample_data() -> Vec<TxRecord> {
+    vec![
+        sample_tx(1, TxKind::Transfer, 11, 22, 500),
+        sample_tx(2, TxKind::Transfer, 22, 33, 900),
+        sample_tx(3, TxKind::Deposit, 0, 11, 5_000),
+        sample_tx(4, TxKind::Withdrawal, 11, 0, 300),
+        sample_tx(5, TxKind::Transfer, 33, 11, 900),
+        sample_tx(6, TxKind::Transfer, 11, 33, 100),
+    ]
+}
+
+#[test]
+fn largest_records_keep_input_order_on_ties() {
+    let data = sample_data();
+    assert_eq!(ids(&largest(&data, 3)), vec![3, 2, 5]);
+    assert_eq!(ids(&largest(&data, 100)), vec![3, 2, 5, 1, 4, 6]);
+    assert!(largest(&data, 0).is_empty());
+    assert!(largest(&[], 3).is_empty());
+
+    // amounts of other scales are compared by value
+    let mut fine = sample_tx(7, TxKind::Transfer, 11, 22, 0);
+    fine.amount = Money::new(9_001, 3);
+    let mut data = data;
+    data.push(fine);
+    assert_eq!(ids(&largest(&data, 3)), vec![3, 7, 2]);
+}
+
+#[test]
+fn largest_records_of_a_stream_are_held_within_bound() {
+    let data = sample_data();
+    let mut csv = Vec::new();
+    Codec::CsvCodec
+        .write(&mut csv, &data)
+        .expect("CSV should be written");
+
+    let mut top = LargestRecords::new(2);
+    for tx in Codec::CsvCodec.parse_stream(&csv[..], &Default::default()) {
+        top.push(&tx.expect("record should parse"));
+    }
+    assert_eq!(ids(&top.into_vec()), vec![3, 2]);
+}
+
+#[test]
+fn accounts_are_ranked_by_volume_and_count() {
+    let data = sample_data();
+    assert_eq!(
+        top_accounts(&data, Flow::Outgoing, 2),
+        Ok(vec![volume(11, 3, 900), volume(22, 1, 900)])
+    );
+    assert_eq!(
+        top_accounts(&data, Flow::Incoming, 10),
+        Ok(vec![
+            volume(11, 2, 5_900),
+            volume(33, 2, 1_000),
+            volume(22, 1, 500)
+        ])
+    );
+
+    let mut volumes = AccountVolumes::new(Flow::Outgoing);
+    for tx in &data {
+        volumes.push(tx).expect("volume should fit");
+    }
+    assert_eq!(
+        volumes.top_by_count(2),
+        vec![volume(11, 3, 900), volume(22, 1, 900)]
+    );
+    assert_eq!(
+        volumes.get(&AccountType::Numeric(33)),
+        Some(volume(33, 1, 900))
+    );
+    // deposits have no source
+    assert_eq!(volumes.get(&AccountType::Numeric(0)), None);
+}
+
+#[test]
+fn volume_overflow_is_an_error() {
+    let data = [
+        sample_tx(1, TxKind::Transfer, 11, 22, i64::MAX),
+        sample_tx(2, TxKind::Transfer, 11, 22, 1),
+    ];
+    assert_eq!(top_accounts(&data, Flow::Outgoing, 1), Err(AmountOverflow));
+}
@@ -0,0 +1,277 @@
+use parser::analytics::anomalies::AnomalyOptions;
+use parser::codecs::base::Codec;
+use parser::codecs::errors::{ParserContext, ParserError};
+use parser::codecs::options::{ParseOptions, Strictness};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use parser::errors::AppError;
+use parser::filter::Filter;
+use parser::validation::{Rule, Severity, Validator};
+
+const CSV_HEADER: &str =
+    "TX_ID,TX_TYPE,FROM_USER_ID,TO_USER_ID,AMOUNT,TIMESTAMP,STATUS,DESCRIPTION\n";
+
+fn sample_tx(id: u64, from: u64, to: u64, amount: i64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(from),
+        to: AccountType::Numeric(to),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(1_700_000_000_000),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn violations(validator: &Validator, data: &[TxRecord]) -> Vec<(usize, String, Severity)> {
+    validator
+        .validate(data)
+        .violations
+        .into_iter()
+        .map(|violation| (violation.position, violation.rule, violation.severity))
+        .collect()
+}
+
+#[test]
+fn builtin_rules_flag_reused_ids_and_non_positive_amounts() {
+    let data = [
+        sample_tx(1, 11, 22, 100),
+        sample_tx(2, 11, 22, 0),
+        sample_tx(1, 11, 22, 100),
+        sample_tx(3, 11, 22, -5),
+    ];
+    let report = Validator::builtin().validate(&data);
+    assert!(!report.is_valid());
+    assert_eq!(
+        violations(&Validator::builtin(), &data),
+        vec![
+            (1, "positive_amounts".into(), Severity::Error),
+            (2, "unique_ids".into(), Severity::Error),
+            (3, "positive_amounts".into(), Severity::Error),
+        ]
+    );
+    assert_eq!(report.count(Severity::Error), 3);
+    assert_eq!(report.max_severity(), Some(Severity::Error));
+    assert_eq!(
+        report.violations[1].to_string(),
+        "ERROR unique_ids: record #2 (TX_ID 1): id is already used by record #0"
+    );
+    assert!(Validator::builtin().validate(&data[..1]).is_valid());
+}
+
+#[test]
+fn custom_rules_report_their_severity_in_position_order() {
+    let validator = Validator::new()
+        .with_rule(Rule::record("described", Severity::Info, |tx| {
+            tx.description
+                .is_none()
+                .then(|| "description is missing".to_string())
+        }))
+        .with_rule(Rule::expression(
+            "small",
+            Severity::Warning,
+            Filter::parse("amount < 1000").expect("filter should compile"),
+        ))
+        .with_rule(Rule::dataset("single_record", Severity::Error, |data| {
+            (1..data.len())
+                .map(|position| (position, "one record expected".to_string()))
+                .collect()
+        }));
+    let mut described = sample_tx(1, 11, 22, 100);
+    described.description = Some("rent".into());
+    let data = [described, sample_tx(2, 11, 22, 500_000)];
+    assert_eq!(
+        violations(&validator, &data),
+        vec![
+            (1, "described".into(), Severity::Info),
+            (1, "small".into(), Severity::Warning),
+            (1, "single_record".into(), Severity::Error),
+        ]
+    );
+    let report = validator.validate(&data[..1]);
+    assert!(report.is_valid());
+    assert_eq!(report.max_severity(), None);
+
+    // warnings and notes alone keep input valid
+    let report = validator.validate(&[sample_tx(2, 11, 22, 500_000)]);
+    assert!(report.is_valid());
+    assert_eq!(report.max_severity(), Some(Severity::Warning));
+    assert_eq!(
+        report.violations[1].message,
+        "does not satisfy `amount < 1000`"
+    );
+}
+
+#[test]
+fn anomalies_are_wrapped_as_a_rule() {
+    let validator = Validator::new().with_rule(Rule::anomalies(
+        Severity::Warning,
+        AnomalyOptions {
+            now: TxTimestamp::from_millis(1_700_000_000_000),
+            ..Default::default()
+        },
+    ));
+    let data = [sample_tx(1, 11, 22, 100), sample_tx(2, 33, 33, 100)];
+    let report = validator.validate(&data);
+    assert!(report.is_valid());
+    assert_eq!(
+        violations(&validator, &data),
+        vec![(1, "anomalies".into(), Severity::Warning)]
+    );
+    assert_eq!(
+        report.violations[0].message,
+        "funds move to the same account"
+    );
+}
+
+#[test]
+fn rules_are_loaded_from_config() {
+    let config = "\
+# amounts are compared as decimals
+ERROR positive: amount > 0
+WARNING small: amount < 1000 && kind == TRANSFER
+
+INFO described: description != \"\"
+";
+    let validator = Validator::from_reader(config.as_bytes()).expect("rules should load");
+    let rules: Vec<(&str, Severity)> = validator
+        .rules()
+        .iter()
+        .map(|rule| (rule.name(), rule.severity()))
+        .collect();
+    assert_eq!(
+        rules,
+        vec![
+            ("positive", Severity::Error),
+            ("small", Severity::Warning),
+            ("described", Severity::Info),
+        ]
+    );
+    assert_eq!(
+        violations(&validator, &[sample_tx(1, 11, 22, 500_000)]),
+        vec![(0, "small".into(), Severity::Warning)]
+    );
+}
+
+#[test]
+fn malformed_rules_name_their_line() {
+    for (config, line) in [
+        ("ERROR positive amount > 0", 1),
+        ("# comment\nFATAL positive: amount > 0", 2),
+        ("ERROR: amount > 0", 1),
+        ("ERROR two words: amount > 0", 1),
+        ("\nERROR positive: amount >", 2),
+    ] {
+        match Validator::from_reader(config.as_bytes()) {
+            Err(AppError::ParsingError {
+                context: ParserContext::LineNumAndLine { line_num, .. },
+                ..
+            }) => assert_eq!(line_num, line, "{:?}", config),
+            other => panic!("{:?} should be rejected, got {:?}", config, other),
+        }
+    }
+}
+
+#[test]
+fn strict_parsing_enforces_error_rules() {
+    let input = format!(
+        "{}1,TRANSFER,11,22,100,1700000000000,SUCCESS,\n2,TRANSFER,11,22,-5,1700000000000,SUCCESS,\n",
+        CSV_HEADER
+    );
+    let options = ParseOptions {
+        strictness: Strictness::Strict,
+        validation: Validator::builtin(),
+        ..Default::default()
+    };
+    match Codec::CsvCodec.parse_with(input.as_bytes(), &options) {
+        Err(AppError::ParsingError {
+            context: ParserContext::Record { number },
+            source: ParserError::RuleViolation { rule, .. },
+        }) => {
+            assert_eq!(number, 2);
+            assert_eq!(rule, "positive_amounts");
+        }
+        other => panic!("negative amount should be rejected, got {:?}", other),
+    }
+
+    // lenient parsing does not validate
+    let lenient = ParseOptions {
+        validation: Validator::builtin(),
+        ..Default::default()
+    };
+    let records = Codec::CsvCodec
+        .parse_with(input.as_bytes(), &lenient)
+        .expect("lenient parsing should accept");
+    assert_eq!(records.len(), 2);
+
+    // warnings do not reject input
+    let warning = ParseOptions {
+        strictness: Strictness::Strict,
+        validation: Validator::new().with_rule(Rule::positive_amounts(Severity::Warning)),
+        ..Default::default()
+    };
+    assert!(
+        Codec::CsvCodec
+            .parse_with(input.as_bytes(), &warning)
+            .is_ok()
+    );
+}
+
+#[test]
+fn strict_streams_enforce_record_rules() {
+    let input = format!(
+        "{}1,TRANSFER,11,22,100,1700000000000,SUCCESS,\n2,TRANSFER,11,22,-5,1700000000000,SUCCESS,\n3,TRANSFER,11,22,7,1700000000000,SUCCESS,\n",
+        CSV_HEADER
+    );
+    let options = ParseOptions {
+        strictness: Strictness::Strict,
+        validation: Validator::builtin(),
+        ..Default::default()
+    };
+    let streamed: Vec<_> = Codec::CsvCodec
+        .parse_stream(input.as_bytes(), &options)
+        .collect();
+    // stream ends with the violation
+    assert_eq!(streamed.len(), 2);
+    assert!(streamed[0].is_ok());
+    match &streamed[1] {
+        Err(AppError::ParsingError {
+            context: ParserContext::Record { number: 2 },
+            source: ParserError::RuleViolation { rule, .. },
+        }) => assert_eq!(rule, "positive_amounts"),
+        other => panic!("negative amount should be rejected, got {:?}", other),
+    }
+
+    // lenient streams are not validated
+    let lenient = ParseOptions {
+        validation: Validator::builtin(),
+        ..Default::default()
+    };
+    let streamed = Codec::CsvCodec.parse_stream(input.as_bytes(), &lenient);
+    assert_eq!(streamed.filter(Result::is_ok).count(), 3);
+}
+
+#[test]
+fn strict_streams_leave_dataset_rules_to_whole_input_parsing() {
+    let input = format!(
+        "{}1,TRANSFER,11,22,100,1700000000000,SUCCESS,\n1,TRANSFER,11,22,5,1700000000000,SUCCESS,\n",
+        CSV_HEADER
+    );
+    let options = ParseOptions {
+        strictness: Strictness::Strict,
+        validation: Validator::builtin(),
+        ..Default::default()
+    };
+    let streamed: Result<Vec<_>, _> = Codec::CsvCodec
+        .parse_stream(input.as_bytes(), &options)
+        .collect();
+    assert_eq!(streamed.unwrap().len(), 2);
+    assert!(matches!(
+        Codec::CsvCodec.parse_with(input.as_bytes(), &options),
+        Err(AppError::ParsingError {
+            source: ParserError::RuleViolation { .. },
+            ..
+        })
+    ));
+}
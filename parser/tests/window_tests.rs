@@ -0,0 +1,155 @@
+use parser::analytics::window::{
+    Bucket, COUNT_KEY, MAX_KEY, MIN_KEY, WINDOW_END_KEY, Window, bucket,
+};
+use parser::codecs::base::Codec;
+use parser::codecs::options::{AmountFormat, ParseOptions, WriteOptions};
+use parser::domain::money::AmountOverflow;
+use parser::domain::tx::{
+    AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp, UtcOffset,
+};
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+// 2024-01-31T00:00:00Z
+const JAN_31: u64 = 1_706_659_200_000;
+
+fn sample_tx(id: u64, amount: i64, ts: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(11),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(amount),
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Success,
+        description: None,
+        ..Default::default()
+    }
+}
+
+fn starts(data: &[TxRecord], window: Window, offset: UtcOffset) -> Vec<(u64, usize)> {
+    bucket(data, window, offset)
+        .expect("sums should fit")
+        .buckets
+        .iter()
+        .map(|bucket| (bucket.start.millis(), bucket.count))
+        .collect()
+}
+
+#[test]
+fn windows_follow_local_time() {
+    let ts = TxTimestamp::from_millis(JAN_31 + 23 * HOUR_MS + 30 * 60_000);
+    let (start, end) = Window::Hour.bounds(ts, UtcOffset::UTC);
+    assert_eq!(start.millis(), JAN_31 + 23 * HOUR_MS);
+    assert_eq!(end.millis(), JAN_31 + DAY_MS);
+
+    let (start, end) = Window::Day.bounds(ts, UtcOffset::UTC);
+    assert_eq!((start.millis(), end.millis()), (JAN_31, JAN_31 + DAY_MS));
+    // 00:30 of February 1st at +01:00
+    let cet: UtcOffset = "+01:00".parse().expect("offset should parse");
+    let (start, end) = Window::Day.bounds(ts, cet);
+    assert_eq!(start.millis(), JAN_31 + DAY_MS - HOUR_MS);
+    assert_eq!(end.millis(), JAN_31 + 2 * DAY_MS - HOUR_MS);
+
+    // January 2024 has 31 days, February 29
+    let (start, end) = Window::Month.bounds(ts, UtcOffset::UTC);
+    assert_eq!(start.millis(), JAN_31 - 30 * DAY_MS);
+    assert_eq!(end.millis(), JAN_31 + DAY_MS);
+    let (start, end) = Window::Month.bounds(ts, cet);
+    assert_eq!(start.millis(), JAN_31 + DAY_MS - HOUR_MS);
+    assert_eq!(end.millis(), JAN_31 + 30 * DAY_MS - HOUR_MS);
+
+    // half-hour offsets shift hours as well
+    let ist: UtcOffset = "+05:30".parse().expect("offset should parse");
+    let (start, _) = Window::Hour.bounds(ts, ist);
+    assert_eq!(start.millis(), JAN_31 + 23 * HOUR_MS + 30 * 60_000);
+
+    assert_eq!("MONTH".parse::<Window>().ok(), Some(Window::Month));
+    assert!("WEEK".parse::<Window>().is_err());
+    assert_eq!(Window::Hour.to_string(), "HOUR");
+}
+
+#[test]
+fn records_are_aggregated_per_window() {
+    let data = [
+        sample_tx(1, 100, JAN_31 + 10 * HOUR_MS),
+        sample_tx(2, 250, JAN_31 + 10 * HOUR_MS + 59 * 60_000),
+        sample_tx(3, 50, JAN_31 + 9 * HOUR_MS),
+        sample_tx(4, 700, JAN_31 + DAY_MS + 10 * HOUR_MS),
+    ];
+    let buckets = bucket(&data, Window::Hour, UtcOffset::UTC).expect("sums should fit");
+    assert_eq!(buckets.buckets.len(), 3);
+    assert_eq!(
+        buckets.get(TxTimestamp::from_millis(JAN_31 + 10 * HOUR_MS + 1)),
+        Some(&Bucket {
+            start: TxTimestamp::from_millis(JAN_31 + 10 * HOUR_MS),
+            end: TxTimestamp::from_millis(JAN_31 + 11 * HOUR_MS),
+            count: 2,
+            sum: Money::from_minor_units(350),
+            min: Money::from_minor_units(100),
+            max: Money::from_minor_units(250),
+        })
+    );
+    assert!(
+        buckets
+            .get(TxTimestamp::from_millis(JAN_31 + 11 * HOUR_MS))
+            .is_none()
+    );
+
+    assert_eq!(
+        starts(&data, Window::Day, UtcOffset::UTC),
+        vec![(JAN_31, 3), (JAN_31 + DAY_MS, 1)]
+    );
+    assert_eq!(
+        starts(&data, Window::Month, UtcOffset::UTC),
+        vec![(JAN_31 - 30 * DAY_MS, 3), (JAN_31 + DAY_MS, 1)]
+    );
+    assert!(starts(&[], Window::Day, UtcOffset::UTC).is_empty());
+
+    let max = sample_tx(5, i64::MAX, JAN_31);
+    assert_eq!(
+        bucket(&[max.clone(), max], Window::Day, UtcOffset::UTC),
+        Err(AmountOverflow)
+    );
+}
+
+#[test]
+fn buckets_are_written_as_records() {
+    let data = [
+        sample_tx(1, 100, JAN_31 + 10 * HOUR_MS),
+        sample_tx(2, 250, JAN_31 + 12 * HOUR_MS),
+    ];
+    let cet: UtcOffset = "+01:00".parse().expect("offset should parse");
+    let records = bucket(&data, Window::Day, cet)
+        .expect("sums should fit")
+        .records();
+    assert_eq!(records.len(), 1);
+    let tx = &records[0];
+    let start = JAN_31 - HOUR_MS;
+    assert_eq!(tx.id, TxIdType::Numeric(start));
+    assert_eq!(tx.ts, TxTimestamp::from_millis(start));
+    assert_eq!(tx.amount, Money::from_minor_units(350));
+    assert_eq!(tx.status, TxStatus::Success);
+    assert_eq!(
+        tx.description.as_deref(),
+        Some("2024-01-31T00:00:00.000+01:00")
+    );
+    assert_eq!(tx.extras[COUNT_KEY], "2");
+    assert_eq!(tx.extras[MIN_KEY], "1.00");
+    assert_eq!(tx.extras[MAX_KEY], "2.50");
+    assert_eq!(tx.extras[WINDOW_END_KEY], (start + DAY_MS).to_string());
+
+    let mut write_options = WriteOptions::default();
+    write_options.csv.amounts = AmountFormat::Exact;
+    let mut csv = Vec::new();
+    Codec::CsvCodec
+        .write_with(&mut csv, &records, &write_options)
+        .expect("CSV should be written");
+    let mut parse_options = ParseOptions::default();
+    parse_options.csv.amounts = AmountFormat::Exact;
+    parse_options.csv.keep_extras = true;
+    let parsed = Codec::CsvCodec
+        .parse_with(&csv[..], &parse_options)
+        .expect("CSV should parse");
+    assert_eq!(parsed, records);
+}
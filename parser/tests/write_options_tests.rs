@@ -0,0 +1,282 @@
+use parser::codecs::base::Codec;
+use parser::codecs::csv::{CsvDialect, CsvWriteOptions};
+use parser::codecs::options::{
+    DescriptionLimit, OverlongDescription, ParseOptions, RecordOrder, TrailingNewline, WriteOptions,
+};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+
+fn sample(id: u64, ts: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(1),
+        to: AccountType::Numeric(2),
+        amount: Money::from_minor_units(10 * id as i64),
+        ts: TxTimestamp::from_millis(ts),
+        status: TxStatus::Success,
+        description: Some(format!("tx {}", id)),
+        ..Default::default()
+    }
+}
+
+fn written(codec: &Codec, data: &[TxRecord], options: &WriteOptions) -> Vec<u8> {
+    let mut out = Vec::new();
+    codec
+        .write_with(&mut out, data, options)
+        .expect("write should succeed");
+    out
+}
+
+fn ids(data: &[TxRecord]) -> Vec<u64> {
+    data.iter().map(|tx| tx.id.as_numeric().unwrap()).collect()
+}
+
+#[test]
+fn records_are_written_in_requested_order() {
+    let data = vec![
+        sample(3, 200),
+        sample(1, 300),
+        sample(2, 200),
+        sample(1, 100),
+    ];
+    for (order, expected) in [
+        (RecordOrder::Input, vec![3, 1, 2, 1]),
+        (RecordOrder::ById, vec![1, 1, 2, 3]),
+        (RecordOrder::ByTimestamp, vec![1, 2, 3, 1]),
+    ] {
+        let options = WriteOptions {
+            order,
+            ..Default::default()
+        };
+        let out = written(&Codec::CsvCodec, &data, &options);
+        let parsed = Codec::CsvCodec.parse(out.as_slice()).unwrap();
+        assert_eq!(ids(&parsed), expected, "{:?}", order);
+    }
+
+    // equal ids keep their input order
+    let options = WriteOptions {
+        order: RecordOrder::ById,
+        ..Default::default()
+    };
+    let parsed = Codec::BinaryCodec
+        .parse(written(&Codec::BinaryCodec, &data, &options).as_slice())
+        .unwrap();
+    assert_eq!(parsed[0].ts, TxTimestamp::from_millis(300));
+    assert_eq!(parsed[1].ts, TxTimestamp::from_millis(100));
+}
+
+#[test]
+fn differently_ordered_inputs_give_identical_output() {
+    let data = vec![sample(2, 20), sample(1, 10), sample(3, 30)];
+    let mut shuffled = data.clone();
+    shuffled.rotate_left(1);
+    let options = WriteOptions {
+        order: RecordOrder::ById,
+        ..Default::default()
+    };
+    for codec in [Codec::CsvCodec, Codec::TextCodec, Codec::BinaryCodec] {
+        assert_eq!(
+            written(&codec, &data, &options),
+            written(&codec, &shuffled, &options)
+        );
+    }
+}
+
+#[test]
+fn csv_header_can_be_omitted() {
+    let data = vec![sample(1, 10)];
+    let options = WriteOptions {
+        csv: CsvWriteOptions {
+            header: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let out = String::from_utf8(written(&Codec::CsvCodec, &data, &options)).unwrap();
+    assert!(out.starts_with("1,TRANSFER,"));
+
+    let parse_options = ParseOptions {
+        csv: CsvDialect {
+            has_header: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let parsed = Codec::CsvCodec
+        .parse_with(out.as_bytes(), &parse_options)
+        .unwrap();
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn trailing_newline_is_trimmed_from_textual_output_only() {
+    let data = vec![sample(1, 10), sample(2, 20)];
+    let options = WriteOptions {
+        trailing_newline: TrailingNewline::Trim,
+        ..Default::default()
+    };
+    for codec in [Codec::CsvCodec, Codec::TextCodec, Codec::MarkdownCodec] {
+        let kept = written(&codec, &data, &WriteOptions::default());
+        assert!(kept.ends_with(b"\n"));
+        let trimmed = written(&codec, &data, &options);
+        assert!(!trimmed.ends_with(b"\n"), "{:?}", codec);
+        assert_eq!(&kept[..trimmed.len()], trimmed.as_slice());
+    }
+    for codec in [Codec::CsvCodec, Codec::TextCodec] {
+        let parsed = codec
+            .parse(written(&codec, &data, &options).as_slice())
+            .unwrap();
+        assert_eq!(parsed, data);
+    }
+
+    // binary output may end with line break bytes which are content
+    let mut binary = sample(1, 10);
+    binary.description = Some("\n".to_string());
+    let kept = written(&Codec::BinaryCodec, std::slice::from_ref(&binary), &options);
+    assert!(kept.ends_with(b"\n"));
+    assert_eq!(
+        Codec::BinaryCodec.parse(kept.as_slice()).unwrap(),
+        vec![binary]
+    );
+}
+
+#[test]
+fn canonical_output_is_identical_for_reordered_and_padded_records() {
+    let mut data = vec![sample(1, 20), sample(2, 10), sample(3, 30)];
+    data[0].tenant = Some("acme".to_string());
+    let mut padded: Vec<TxRecord> = data.iter().rev().cloned().collect();
+    padded[0].description = Some(format!("  {}\t", padded[0].description.as_deref().unwrap()));
+    padded[2].tenant = Some(" acme ".to_string());
+
+    let options = WriteOptions::canonical();
+    for codec in [
+        Codec::CsvCodec,
+        Codec::TextCodec,
+        Codec::BinaryCodec,
+        Codec::MarkdownCodec,
+    ] {
+        assert_eq!(
+            written(&codec, &padded, &options),
+            written(&codec, &data, &options),
+            "{:?}",
+            codec
+        );
+    }
+    let parsed = Codec::CsvCodec
+        .parse(written(&Codec::CsvCodec, &padded, &options).as_slice())
+        .unwrap();
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn canonical_output_orders_records_of_the_same_id_by_content() {
+    let mut first = sample(1, 10);
+    first.amount = Money::from_minor_units(5);
+    let mut second = sample(1, 10);
+    second.amount = Money::from_minor_units(7);
+    let options = WriteOptions::canonical();
+    assert_eq!(
+        written(
+            &Codec::TextCodec,
+            &[first.clone(), second.clone()],
+            &options
+        ),
+        written(&Codec::TextCodec, &[second, first], &options)
+    );
+}
+
+fn limited(max_chars: usize, overlong: OverlongDescription) -> WriteOptions {
+    WriteOptions {
+        description_limit: Some(DescriptionLimit {
+            max_chars,
+            overlong,
+        }),
+        ..Default::default()
+    }
+}
+
+fn with_description(id: u64, description: &str) -> TxRecord {
+    TxRecord {
+        description: Some(description.to_string()),
+        ..sample(id, 10)
+    }
+}
+
+#[test]
+fn overlong_descriptions_are_truncated_with_ellipsis() {
+    let data = vec![
+        with_description(1, "exactly 10"),
+        with_description(2, "eleven char"),
+        with_description(3, "åååååååååååå"),
+    ];
+    let out = written(
+        &Codec::CsvCodec,
+        &data,
+        &limited(10, OverlongDescription::Truncate),
+    );
+    let parsed = Codec::CsvCodec.parse(out.as_slice()).unwrap();
+    let descriptions: Vec<&str> = parsed
+        .iter()
+        .map(|tx| tx.description.as_deref().unwrap())
+        .collect();
+    assert_eq!(descriptions, ["exactly 10", "eleven ...", "ååååååå..."]);
+
+    // no room for ellipsis
+    let out = written(
+        &Codec::CsvCodec,
+        &data[1..2],
+        &limited(2, OverlongDescription::Truncate),
+    );
+    assert_eq!(
+        Codec::CsvCodec.parse(out.as_slice()).unwrap()[0].description,
+        Some("el".to_string())
+    );
+}
+
+#[test]
+fn records_of_overlong_descriptions_can_be_skipped_or_fail_writing() {
+    let data = vec![
+        with_description(1, "short"),
+        with_description(2, "much too long"),
+        with_description(3, "fine"),
+    ];
+    let out = written(
+        &Codec::TextCodec,
+        &data,
+        &limited(5, OverlongDescription::Skip),
+    );
+    let parsed = Codec::TextCodec.parse(out.as_slice()).unwrap();
+    assert_eq!(ids(&parsed), vec![1, 3]);
+
+    let mut out = Vec::new();
+    let err = Codec::CsvCodec
+        .write_with(&mut out, &data, &limited(5, OverlongDescription::Fail))
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("record #2 (id 2) is 13 characters long, at most 5 allowed")
+    );
+    assert!(out.is_empty());
+}
+
+#[test]
+fn sinks_apply_description_limit() {
+    let data = vec![with_description(1, "much too long"), sample(2, 20)];
+    let mut out = Vec::new();
+    let mut sink = Codec::CsvCodec
+        .open_sink(&mut out, &limited(8, OverlongDescription::Skip))
+        .unwrap();
+    for tx in &data {
+        sink.push(tx).unwrap();
+    }
+    sink.finish().unwrap();
+    assert_eq!(
+        ids(&Codec::CsvCodec.parse(out.as_slice()).unwrap()),
+        vec![2]
+    );
+
+    let mut sink = Codec::CsvCodec
+        .open_sink(Vec::new(), &limited(8, OverlongDescription::Fail))
+        .unwrap();
+    assert!(sink.push(&data[0]).is_err());
+}
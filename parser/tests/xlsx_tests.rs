@@ -0,0 +1,71 @@
+#![cfg(feature = "xlsx")]
+
+use parser::codecs::base::Codec;
+use parser::codecs::errors::ParserError;
+use parser::domain::tx::{
+    AccountType, ExchangeRate, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp,
+};
+use parser::errors::AppError;
+
+fn sample_tx(id: u64, tenant: Option<&str>) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(u64::MAX),
+        to: AccountType::Numeric(22),
+        amount: Money::from_minor_units(-500),
+        ts: TxTimestamp::from_millis(1_700_000_000_000),
+        status: TxStatus::Pending,
+        description: Some("rent <May> & \"utilities\"".to_string()),
+        tenant: tenant.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn xlsx_round_trip() {
+    let records = vec![
+        sample_tx(1, None),
+        TxRecord {
+            fee: Some(Money::from_minor_units(25)),
+            reference: Some("E2E-1".into()),
+            original_amount: Some(Money::new(15_000, 0)),
+            exchange_rate: Some(ExchangeRate::new(7_217, 4)),
+            ..sample_tx(2, Some("acme"))
+        },
+    ];
+    let mut bytes = Vec::new();
+    Codec::XlsxCodec
+        .write(&mut bytes, &records)
+        .expect("xlsx write should succeed");
+    assert_eq!(&bytes[..2], b"PK");
+
+    let parsed = Codec::XlsxCodec
+        .parse(bytes.as_slice())
+        .expect("xlsx read should succeed");
+    assert_eq!(parsed, records);
+}
+
+#[test]
+fn xlsx_empty_workbook_and_empty_input() {
+    let mut bytes = Vec::new();
+    Codec::XlsxCodec
+        .write(&mut bytes, &[])
+        .expect("xlsx write should succeed");
+    assert!(Codec::XlsxCodec.parse(bytes.as_slice()).unwrap().is_empty());
+    assert!(Codec::XlsxCodec.parse(&b""[..]).unwrap().is_empty());
+}
+
+#[test]
+fn xlsx_rejects_non_zip_input() {
+    let err = Codec::XlsxCodec
+        .parse(&b"TX_ID,TX_TYPE\n"[..])
+        .expect_err("csv is not a workbook");
+    assert!(matches!(
+        err,
+        AppError::ParsingError {
+            source: ParserError::UnparsableValue(_),
+            ..
+        }
+    ));
+}
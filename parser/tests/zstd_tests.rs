@@ -0,0 +1,163 @@
+#![cfg(feature = "zstd")]
+
+use parser::codecs::base::Codec;
+use parser::codecs::compression::{
+    CompressedReader, CompressedWriter, Compression, CompressionOptions,
+};
+use parser::codecs::options::{ParseOptions, WriteOptions};
+use parser::domain::tx::{AccountType, Money, TxIdType, TxKind, TxRecord, TxStatus, TxTimestamp};
+use std::io::{Read, Write};
+
+// `zstd -19` output for `reference_text()`, Huffman coded literals and FSE coded sequences
+const REFERENCE_FRAME: [u8; 217] = [
+    0x28, 0xB5, 0x2F, 0xFD, 0x64, 0x31, 0x08, 0x5D, 0x06, 0x00, 0x92, 0xC9, 0x19, 0x15, 0xA0, 0xAB,
+    0x03, 0x20, 0xE5, 0x4D, 0x5C, 0x94, 0xA9, 0x2A, 0x71, 0xB6, 0xBF, 0x4C, 0x49, 0xCA, 0x5A, 0xBE,
+    0x77, 0x15, 0x0F, 0x09, 0xD7, 0xD5, 0x85, 0xE3, 0x7F, 0xFF, 0x6E, 0xEF, 0xD3, 0x97, 0x95, 0x05,
+    0xA3, 0x6F, 0xFB, 0x6C, 0xAE, 0x93, 0xD7, 0xA9, 0x13, 0x0E, 0xEF, 0xE7, 0x3B, 0xBB, 0x5E, 0xBA,
+    0xA5, 0x4C, 0x30, 0xB4, 0x9B, 0xED, 0xCC, 0x6A, 0xC9, 0x96, 0x6A, 0x2A, 0xF6, 0x75, 0xAF, 0xD6,
+    0x36, 0x75, 0x9D, 0x7A, 0x05, 0x43, 0x75, 0xAC, 0xD7, 0xD9, 0x80, 0xA2, 0x6C, 0x90, 0xC1, 0x41,
+    0x01, 0xF3, 0x24, 0x83, 0x92, 0x48, 0x72, 0xC9, 0x22, 0x17, 0x94, 0x06, 0x83, 0x0A, 0x05, 0x15,
+    0x65, 0x72, 0x20, 0x01, 0x44, 0xA8, 0x11, 0x30, 0xF9, 0xFD, 0x3F, 0x03, 0x30, 0x25, 0x1C, 0xCD,
+    0x36, 0x12, 0x68, 0x00, 0x09, 0xFE, 0xFF, 0x98, 0x22, 0x60, 0x08, 0xFA, 0x03, 0x11, 0x19, 0x90,
+    0x0A, 0x8A, 0x4D, 0x1A, 0x50, 0x08, 0x04, 0x4A, 0x20, 0x65, 0xAF, 0x49, 0xB2, 0xC5, 0xB3, 0x1B,
+    0x46, 0x57, 0x46, 0xEE, 0x73, 0x71, 0xF1, 0x48, 0x14, 0x23, 0xBB, 0xBF, 0x48, 0x9E, 0x47, 0x5B,
+    0xF5, 0xD1, 0x47, 0x53, 0x14, 0xBF, 0x8C, 0x44, 0x89, 0xD4, 0x0F, 0xB0, 0x28, 0x76, 0x28, 0x9A,
+    0x63, 0x3C, 0xEF, 0x8E, 0xE0, 0x8E, 0x30, 0xE7, 0x67, 0xE2, 0xAE, 0x54, 0xE1, 0xEF, 0x32, 0x04,
+    0x03, 0xE1, 0x04, 0xA8, 0x55, 0xF4, 0xA3, 0xA0, 0x13,
+];
+
+fn reference_text() -> String {
+    (1..=60)
+        .map(|i| format!("line {} of the reference text, value {}\n", i, i * i % 17))
+        .collect()
+}
+
+fn sample(id: u64) -> TxRecord {
+    TxRecord {
+        id: TxIdType::Numeric(id),
+        kind: TxKind::Transfer,
+        from: AccountType::Numeric(id % 7),
+        to: AccountType::Numeric(id % 5),
+        amount: Money::from_minor_units(100 * id as i64),
+        ts: TxTimestamp::from_millis(1_700_000 + id),
+        status: TxStatus::Success,
+        description: Some(format!("payment {}", id % 3)),
+        ..Default::default()
+    }
+}
+
+fn options(level: i32, dictionary: Option<&[u8]>) -> CompressionOptions {
+    CompressionOptions {
+        level,
+        dictionary: dictionary.map(<[u8]>::to_vec),
+    }
+}
+
+fn compress(data: &[u8], options: CompressionOptions) -> Vec<u8> {
+    let mut w = CompressedWriter::with_options(Vec::new(), Compression::Zstd, options).unwrap();
+    w.write_all(data).unwrap();
+    w.finish().unwrap()
+}
+
+// corrupted input is detected while reading, after the reader is created
+fn decompress(data: &[u8], options: &CompressionOptions) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    CompressedReader::with_options(data, options)
+        .map_err(|e| e.to_string())?
+        .read_to_end(&mut out)
+        .map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+#[test]
+fn zstd_round_trip_for_every_codec_and_level() {
+    let data: Vec<TxRecord> = (1..300).map(sample).collect();
+    for codec in [Codec::CsvCodec, Codec::TextCodec, Codec::BinaryCodec] {
+        for level in [1, CompressionOptions::DEFAULT_LEVEL, 19] {
+            let write_options = WriteOptions {
+                compression: options(level, None),
+                ..Default::default()
+            };
+            let mut compressed = Vec::new();
+            codec
+                .write_compressed(&mut compressed, &data, &write_options, Compression::Zstd)
+                .expect("compressed write should succeed");
+            assert_eq!(Compression::detect(&compressed), Some(Compression::Zstd));
+            let parsed = codec
+                .parse_compressed(compressed.as_slice(), &ParseOptions::default())
+                .expect("compressed input should parse");
+            assert_eq!(parsed, data);
+        }
+    }
+}
+
+#[test]
+fn zstd_output_shrinks_binary_archives() {
+    let data: Vec<TxRecord> = (1..2000).map(sample).collect();
+    let mut plain = Vec::new();
+    Codec::BinaryCodec.write(&mut plain, &data).unwrap();
+    let compressed = compress(&plain, CompressionOptions::default());
+    assert!(compressed.len() * 6 < plain.len());
+    assert_eq!(decompress(&compressed, &Default::default()).unwrap(), plain);
+}
+
+#[test]
+fn zstd_reads_frames_of_reference_encoder() {
+    let text = decompress(&REFERENCE_FRAME, &Default::default()).unwrap();
+    assert_eq!(String::from_utf8(text).unwrap(), reference_text());
+}
+
+#[test]
+fn zstd_round_trips_edge_inputs() {
+    let incompressible: Vec<u8> = (0..200_000u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    let inputs = [
+        Vec::new(),
+        b"abc".to_vec(),
+        vec![7; 300_000],
+        incompressible,
+        reference_text().repeat(100).into_bytes(),
+    ];
+    for input in inputs {
+        let compressed = compress(&input, CompressionOptions::default());
+        assert_eq!(decompress(&compressed, &Default::default()).unwrap(), input);
+    }
+}
+
+#[test]
+fn zstd_concatenated_and_skippable_frames_are_read_as_one_stream() {
+    let mut compressed = compress(b"hello ", CompressionOptions::default());
+    // skippable frame with 3 bytes of user data
+    compressed.extend_from_slice(&[0x50, 0x2A, 0x4D, 0x18, 3, 0, 0, 0, 1, 2, 3]);
+    compressed.extend(compress(b"world", CompressionOptions::default()));
+    let read = decompress(&compressed, &Default::default()).unwrap();
+    assert_eq!(read, b"hello world");
+}
+
+#[test]
+fn zstd_corrupted_frame_is_rejected() {
+    let input = reference_text().into_bytes();
+    let mut compressed = compress(&input, CompressionOptions::default());
+    let last = compressed.len() - 1;
+    compressed[last] ^= 0xFF;
+    let err = decompress(&compressed, &Default::default()).expect_err("checksum should not match");
+    assert!(err.contains("checksum"), "{err}");
+
+    let truncated = &REFERENCE_FRAME[..100];
+    assert!(decompress(truncated, &Default::default()).is_err());
+}
+
+#[test]
+fn zstd_dictionary_is_required_to_read_output_written_with_it() {
+    let dictionary = reference_text().into_bytes();
+    let input = b"line 61 of the reference text, value 15\n".repeat(2);
+    let with_dictionary = options(CompressionOptions::DEFAULT_LEVEL, Some(&dictionary));
+    let compressed = compress(&input, with_dictionary.clone());
+    assert!(compressed.len() < compress(&input, CompressionOptions::default()).len());
+    assert_eq!(decompress(&compressed, &with_dictionary).unwrap(), input);
+    assert_ne!(
+        decompress(&compressed, &Default::default()).ok(),
+        Some(input)
+    );
+}
@@ -0,0 +1,69 @@
+use clap::Parser;
+use parser::analytics::balance::Ledger;
+use rustyapa::cli_format::Format;
+
+#[derive(Parser, Debug)]
+struct CliArgs {
+    #[arg(long)]
+    input: String,
+    /// Format of input, inferred from its extension when omitted.
+    #[arg(long)]
+    input_format: Option<Format>,
+    /// Apply records taking accounts below zero instead of rejecting them.
+    #[arg(long)]
+    allow_overdraft: bool,
+    /// Maximum number of rejected records printed.
+    #[arg(long, default_value_t = 10)]
+    max_issues: usize,
+}
+
+fn run(args: CliArgs, input_format: Format) -> Result<bool, Box<dyn std::error::Error>> {
+    let data = input_format.parse_path(&args.input)?;
+    let mut ledger = Ledger::new();
+    ledger.allow_overdraft = args.allow_overdraft;
+    let errors = ledger.apply_all(&data);
+    println!(
+        "{} records applied, {} skipped for their status, {} rejected\n",
+        ledger.applied(),
+        ledger.skipped(),
+        errors.len()
+    );
+    for (account, balance) in ledger.balances() {
+        println!("{}\t{}", account, balance);
+    }
+    if !errors.is_empty() {
+        println!();
+    }
+    for e in errors.iter().take(args.max_issues) {
+        println!("{}", e);
+    }
+    Ok(errors.is_empty())
+}
+
+fn main() {
+    // parse args
+    let args = CliArgs::parse();
+
+    let Some(input_format) = args
+        .input_format
+        .clone()
+        .or_else(|| Format::from_path(&args.input))
+    else {
+        eprintln!(
+            "Error occured during application execution: cannot infer format of '{}' from its extension, pass --input-format",
+            args.input
+        );
+        std::process::exit(1);
+    };
+
+    // run app
+    println!("Computing balances of '{}':{}", args.input, input_format);
+    match run(args, input_format) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(2),
+        Err(e) => {
+            eprintln!("Error occured during application execution: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
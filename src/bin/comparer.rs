@@ -1,65 +1,148 @@
 use clap::Parser;
-use parser::domain::tx::TxRecord;
+use parser::codecs::base::TxFieldKey;
+use parser::compare::{
+    CompareOptions, DiffReport, FuzzyReport, Tolerances, compare_with, diff, fuzzy_match,
+};
+use parser::domain::key::RecordKey;
+use parser::domain::tx::Money;
 use rustyapa::cli_format::Format;
-use std::{collections::HashMap, fs::File};
 
 #[derive(Parser, Debug)]
 struct CliArgs {
     #[arg(long)]
     file1: String,
+    /// Format of the first file, inferred from its extension when omitted.
     #[arg(long)]
-    format1: Format,
+    format1: Option<Format>,
     #[arg(long)]
     file2: String,
+    /// Format of the second file, inferred from its extension when omitted.
     #[arg(long)]
-    format2: Format,
+    format2: Option<Format>,
+    /// Record identity: `FULL`, `ID` or comma separated field names, e.g. `TX_ID,AMOUNT`.
+    #[arg(long, default_value = "FULL", value_parser = parse_record_key)]
+    key: RecordKey,
+    /// Match records by TX_ID and report fields differing between them.
+    #[arg(long)]
+    diff: bool,
+    /// Match records within tolerances and classify them as exact, fuzzy or unmatched.
+    #[arg(long, conflicts_with = "diff")]
+    fuzzy: bool,
+    /// Largest difference of matched timestamps in milliseconds.
+    #[arg(long, default_value_t = 0, requires = "fuzzy")]
+    timestamp_drift_ms: u64,
+    /// Largest difference of matched amounts, e.g. `0.01`.
+    #[arg(long, value_parser = parse_money, requires = "fuzzy")]
+    amount_tolerance: Option<Money>,
+    /// Field not compared when matching, may be repeated, e.g. `DESCRIPTION`.
+    #[arg(long, value_parser = parse_field_key, requires = "fuzzy")]
+    ignore_field: Vec<TxFieldKey>,
+}
+
+fn parse_record_key(s: &str) -> Result<RecordKey, String> {
+    s.parse().map_err(|e| format!("{}", e))
+}
+
+fn parse_money(s: &str) -> Result<Money, String> {
+    s.parse().map_err(|e| format!("{}", e))
+}
+
+fn parse_field_key(s: &str) -> Result<TxFieldKey, String> {
+    s.parse().map_err(|e| format!("{}", e))
 }
 
-fn read_records_from_file(
-    file_format: &Format,
-    filename: &str,
-) -> Result<Vec<TxRecord>, Box<dyn std::error::Error>> {
-    let f = File::open(filename).map_err(|e| {
-        std::io::Error::new(e.kind(), format!("Error opening a file {} {}", filename, e))
-    })?;
-    Ok(file_format.codec().parse(f)?)
+fn file_format(format: &Option<Format>, filename: &str, flag: &str) -> Result<Format, String> {
+    format
+        .clone()
+        .or_else(|| Format::from_path(filename))
+        .ok_or_else(|| {
+            format!(
+                "cannot infer format of '{}' from its extension, pass {}",
+                filename, flag
+            )
+        })
 }
 
-fn run(args: CliArgs) -> Result<(), Box<dyn std::error::Error>> {
-    // read and 'count' transactions
-    // count is number_of_occurences_in_file1 - number_of_occurences_in_file2 for each unique (by hash) transaction
-    let mut record_count = HashMap::new();
-    {
-        // reading first file
-        let ds1_records = read_records_from_file(&args.format1, &args.file1)?;
-        for item in ds1_records.into_iter() {
-            *record_count.entry(item).or_insert(0) += 1;
+fn print_diff(report: &DiffReport) {
+    if report.is_identical() {
+        println!("All transaction records are identical.");
+        return;
+    }
+    println!(
+        "{} transactions are identical, {} differ",
+        report.unchanged,
+        report.changed.len()
+    );
+    for record in &report.changed {
+        println!("{}", record);
+    }
+    for (records, file) in [(&report.only_in_a, "#1"), (&report.only_in_b, "#2")] {
+        for tx in records {
+            println!("Transaction {} is only in the file '{}'", tx, file);
         }
     }
-    {
-        // reading second file
-        let ds2_records = read_records_from_file(&args.format2, &args.file2)?;
-        for item in ds2_records {
-            *record_count.entry(item).or_insert(0) -= 1;
+}
+
+fn print_fuzzy(report: &FuzzyReport) {
+    println!(
+        "{} transactions match exactly, {} within tolerances, {} and {} are unmatched",
+        report.exact,
+        report.fuzzy.len(),
+        report.only_in_a.len(),
+        report.only_in_b.len()
+    );
+    for pair in &report.fuzzy {
+        let fields: Vec<String> = pair.fields.iter().map(|field| field.to_string()).collect();
+        println!("#{} ~ #{}: {}", pair.a.id, pair.b.id, fields.join(", "));
+    }
+    for (records, file) in [(&report.only_in_a, "#1"), (&report.only_in_b, "#2")] {
+        for tx in records {
+            println!("Transaction {} is only in the file '{}'", tx, file);
         }
     }
-    // cleaning up recrods with 0 counts
-    record_count.retain(|_, v| 0 != *v);
+}
+
+fn run(args: CliArgs, format1: Format, format2: Format) -> Result<(), Box<dyn std::error::Error>> {
+    let ds1_records = format1.parse_path(&args.file1)?;
+    let ds2_records = format2.parse_path(&args.file2)?;
+    if args.diff {
+        print_diff(&diff(&ds1_records, &ds2_records));
+        return Ok(());
+    }
+    if args.fuzzy {
+        let tolerances = Tolerances {
+            timestamp_drift_ms: args.timestamp_drift_ms,
+            amount: args.amount_tolerance.unwrap_or_default(),
+            ignored: args.ignore_field,
+        };
+        print_fuzzy(&fuzzy_match(&ds1_records, &ds2_records, &tolerances));
+        return Ok(());
+    }
+    let report = compare_with(
+        &ds1_records,
+        &ds2_records,
+        &CompareOptions {
+            key: args.key,
+            ..Default::default()
+        },
+    );
 
-    // 0 count mean the exact record appears same number of times in both files
-    if record_count.len() == 0 {
+    if report.is_identical() {
         println!("All transaction records are identical.");
     } else {
         println!(
             "There are {} unique transactions that don't match between the files",
-            record_count.len()
+            report.only_in_a.len() + report.only_in_b.len()
         );
-        // number of occurences is zero - means there are no
-        for (item, count) in record_count.into_iter() {
+        for (unmatched, file) in report
+            .only_in_a
+            .iter()
+            .map(|unmatched| (unmatched, "#1"))
+            .chain(report.only_in_b.iter().map(|unmatched| (unmatched, "#2")))
+        {
             println!(
                 "There is no equivivalent for transaction {} in the file '{}'",
-                item.id,
-                if count > 0 { "#1" } else { "#2" }
+                unmatched.record, file
             );
         }
     }
@@ -71,12 +154,23 @@ fn main() {
     // parse args
     let args = CliArgs::parse();
 
+    let formats = file_format(&args.format1, &args.file1, "--format1").and_then(|format1| {
+        file_format(&args.format2, &args.file2, "--format2").map(|format2| (format1, format2))
+    });
+    let (format1, format2) = match formats {
+        Ok(formats) => formats,
+        Err(e) => {
+            eprintln!("Error occured during application execution: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // run app
     println!(
         "Comparing 2 files\n\t1:'{}':{}\n\t2:'{}':{}\n",
-        args.file1, args.format1, args.file2, args.format2
+        args.file1, format1, args.file2, format2
     );
-    let app_result = run(args);
+    let app_result = run(args, format1, format2);
 
     // handle errors
     if let Err(e) = app_result {
@@ -1,31 +1,694 @@
 use clap::Parser;
-use rustyapa::cli_format::Format;
-use std::fs::File;
+use parser::analytics::aggregate::{GroupBy, GroupValue, aggregate};
+use parser::analytics::window::{Window, bucket};
+use parser::codecs::base::{Codec, TxFieldKey};
+use parser::codecs::binary::{self, BinaryVersion};
+use parser::codecs::compression::{CompressedReader, Compression};
+use parser::codecs::encryption::{EncryptedReader, EncryptedWriter, EncryptionKey, is_encrypted};
+use parser::codecs::errors::ParserError;
+use parser::codecs::options::{
+    AmountFormat, BufferSize, DescriptionLimit, ParseLimits, ParseOptions, Strictness,
+    TrailingNewline, WriteOptions,
+};
+use parser::codecs::quarantine::QuarantineWriter;
+use parser::codecs::signing::{SignedReader, SignedWriter, SigningKey};
+use parser::codecs::text::RecordSeparator;
+use parser::domain::directory::AccountDirectory;
+use parser::domain::tx::{Money, TxRecord, UtcOffset};
+use parser::errors::AppError;
+use parser::filter::Filter;
+use parser::reconcile::ControlTotals;
+use parser::transform::dedup;
+use parser::transform::join::{AccountMetadata, EnrichedRecord, join};
+use parser::transform::redact::RedactionPolicy;
+use parser::transform::sort::SortOrder;
+use parser::transform::split::split;
+use parser::transform::statement::with_running_balance;
+use parser::validation::Validator;
+use rustyapa::cli_format::{
+    ByteOrder, Dedup, Encoding, Format, Order, Overlong, Quoting, SummaryFormat, Timestamps,
+    create_file, open_file,
+};
+use std::io::{BufWriter, Cursor, Read, Write};
+use std::path::Path;
 
 #[derive(Parser, Debug)]
 
 struct CliArgs {
     #[arg(long)]
     input: String,
+    /// Format of input, inferred from its extension when omitted.
     #[arg(long)]
-    input_format: Format,
+    input_format: Option<Format>,
     #[arg(long)]
     output_format: Format,
+    /// Fields delimiter of CSV input, e.g. `;`.
+    #[arg(long)]
+    csv_delimiter: Option<char>,
+    /// Value of CSV column missing in input header, e.g. `STATUS=PENDING`; may be repeated.
+    #[arg(long = "csv-default", value_parser = parse_csv_default)]
+    csv_defaults: Vec<(TxFieldKey, String)>,
+    /// Header name of CSV column, read and written, e.g. `TX_ID=transaction_id`; may be repeated.
+    #[arg(long = "csv-column", value_parser = parse_csv_default)]
+    csv_columns: Vec<(TxFieldKey, String)>,
+    /// Match keys of text input ignoring case, underscores, dashes and spaces.
+    #[arg(long)]
+    lenient_keys: bool,
+    /// Keep unknown CSV columns and text keys of input and write them back to CSV and text output.
+    #[arg(long)]
+    keep_extras: bool,
+    /// Line ending records of text input besides blank lines and following them in text output,
+    /// e.g. `---`.
+    #[arg(long)]
+    text_separator: Option<String>,
+    /// Other name of a key of text input, e.g. `SENDER=FROM_USER_ID`; may be repeated.
+    #[arg(long = "key-alias", value_parser = parse_key_alias)]
+    key_aliases: Vec<(String, TxFieldKey)>,
+    /// Write malformed records to this file instead of failing on them.
+    #[arg(long)]
+    quarantine: Option<String>,
+    /// Skip corrupt regions of binary input up to the next record signature (with `--quarantine`).
+    #[arg(long, requires = "quarantine")]
+    resync: bool,
+    /// Reject input without records or with bytes other than whitespace after the last record.
+    #[arg(long)]
+    strict: bool,
+    /// Reject input violating error rules of this file, `SEVERITY NAME: FILTER` per line
+    /// (with `--strict`).
+    #[arg(long, requires = "strict")]
+    rules: Option<String>,
+    /// Largest accepted binary record or text line in bytes.
+    #[arg(long, default_value_t = ParseLimits::DEFAULT_MAX_RECORD_SIZE)]
+    max_record_size: usize,
+    /// Largest columnar block, Cap'n Proto segment, camt.053, BAI2 or xlsx input in bytes.
+    #[arg(long, default_value_t = ParseLimits::DEFAULT_MAX_BLOCK_SIZE)]
+    max_block_size: usize,
+    /// Longest accepted description in bytes.
+    #[arg(long, default_value_t = ParseLimits::DEFAULT_MAX_DESCRIPTION_LEN)]
+    max_description_len: usize,
+    /// Fail if input holds more records.
+    #[arg(long)]
+    max_records: Option<usize>,
+    /// Fail if number of parsed records differs.
+    #[arg(long)]
+    expect_count: Option<usize>,
+    /// Fail if sum of parsed amounts (minor units) differs.
+    #[arg(long, allow_hyphen_values = true)]
+    expect_total: Option<i128>,
+    /// Control file with `RECORD_COUNT` and `TOTAL_AMOUNT` expectations, flags take priority.
+    #[arg(long)]
+    control_file: Option<String>,
+    /// Account names, `name,account` CSV or JSON object; names are read from text input and
+    /// written to text and report output.
+    #[arg(long)]
+    accounts: Option<String>,
+    /// Owners and countries of accounts, `account,owner,country` CSV or JSON object; written
+    /// in FROM_OWNER, FROM_COUNTRY, TO_OWNER and TO_COUNTRY extra columns, keys or lines.
+    #[arg(long, conflicts_with_all = ["aggregate", "bucket"])]
+    account_metadata: Option<String>,
+    /// Redaction policy, `FIELD: KEEP|HASH|MASK|DROP` lines with `SALT` key of pseudonyms;
+    /// applied to output records before they are summarized, e.g. to share them as a test
+    /// dataset; extras are dropped, so it conflicts with flags writing extra columns and keys.
+    #[arg(
+        long,
+        conflicts_with_all = ["aggregate", "bucket", "account_metadata", "running_balance"]
+    )]
+    redact: Option<String>,
+    /// Keep only records matching the expression, e.g. `amount > 1000 && kind == TRANSFER`.
+    #[arg(long, value_parser = parse_filter)]
+    filter: Option<Filter>,
+    /// Write amounts aggregated by comma separated dimensions as CSV instead of records,
+    /// e.g. `TX_TYPE,DAY`; days are UTC ones unless `--output-utc-offset` is set.
+    #[arg(long, value_delimiter = ',', value_parser = parse_group_by, conflicts_with = "append")]
+    aggregate: Vec<GroupBy>,
+    /// Write a record per `HOUR`, `DAY` or `MONTH` window with its sum of amounts and record
+    /// count, smallest and largest amounts in extra columns or keys; windows are UTC ones
+    /// unless `--output-utc-offset` is set.
+    #[arg(long, value_parser = parse_window, conflicts_with = "aggregate")]
+    bucket: Option<Window>,
+    /// Write records to one file per value of the dimension (`TX_TYPE`, `STATUS`,
+    /// `FROM_USER_ID`, `TO_USER_ID` or `DAY`) in `--split-dir` instead of standard output.
+    #[arg(
+        long,
+        value_parser = parse_group_by,
+        requires = "split_dir",
+        conflicts_with_all = ["append", "aggregate"]
+    )]
+    split_by: Option<GroupBy>,
+    /// Directory partition files of `--split-by` are written to, named after their values.
+    #[arg(long, requires = "split_by")]
+    split_dir: Option<String>,
+    /// Write statement of the account, id or name of `--accounts`: its successful records by
+    /// timestamp with running balance in BALANCE extra column or key.
+    #[arg(long, conflicts_with_all = ["sort", "order", "canonical", "aggregate"])]
+    running_balance: Option<String>,
+    /// Balance of `--running-balance` account before the first record, e.g. `100.00`.
+    #[arg(long, requires = "running_balance", allow_hyphen_values = true)]
+    opening_balance: Option<Money>,
+    /// Print summary of output records before them: counts, amounts, timestamps and accounts.
+    #[arg(long)]
+    summary: Option<SummaryFormat>,
+    /// Leave duplicate records out of output, first occurence is kept.
+    #[arg(long)]
+    dedup: Option<Dedup>,
+    /// Precede binary output with file header holding layout version and records count.
+    #[arg(long)]
+    binary_header: bool,
+    /// Store integers of binary output as varints, file header marks the layout.
+    #[arg(long)]
+    binary_varint: bool,
+    /// Append CRC32 checksum to every record of binary output.
+    #[arg(long)]
+    binary_checksum: bool,
+    /// Follow binary output with footer index of record offsets for random access.
+    #[arg(long)]
+    binary_index: bool,
+    /// Byte order of binary input.
+    #[arg(long, default_value = "big")]
+    input_endianness: ByteOrder,
+    /// Byte order of binary output.
+    #[arg(long, default_value = "big")]
+    output_endianness: ByteOrder,
+    /// Character encoding of text and CSV input.
+    #[arg(long, default_value = "utf8")]
+    input_encoding: Encoding,
+    /// Form of timestamps of text and CSV input.
+    #[arg(long, default_value = "millis")]
+    input_timestamps: Timestamps,
+    /// Form of timestamps of text and CSV output.
+    #[arg(long, default_value = "millis")]
+    output_timestamps: Timestamps,
+    /// UTC offset of RFC 3339 input timestamps without one, e.g. `+02:00`.
+    #[arg(long, allow_hyphen_values = true)]
+    input_utc_offset: Option<UtcOffset>,
+    /// Write RFC 3339 output timestamps in local time at this UTC offset, e.g. `+02:00`.
+    #[arg(long, allow_hyphen_values = true)]
+    output_utc_offset: Option<UtcOffset>,
+    /// Read AMOUNT of text and CSV input as decimal with this many fraction digits, e.g. 2.
+    #[arg(long)]
+    input_amount_scale: Option<u8>,
+    /// Write AMOUNT of text and CSV output as decimal with this many fraction digits.
+    #[arg(long)]
+    output_amount_scale: Option<u8>,
+    /// Read and write AMOUNT of text and CSV as decimals of their own scale, e.g. `500` of JPY.
+    #[arg(long, conflicts_with_all = ["input_amount_scale", "output_amount_scale"])]
+    exact_amounts: bool,
+    /// Store repeating descriptions once in binary v2 output.
+    #[arg(long)]
+    dedup_descriptions: bool,
+    /// Compress output with gzip, compressed input is detected automatically.
+    #[arg(long)]
+    gzip: bool,
+    /// Compress output with zstd.
+    #[arg(long, conflicts_with = "gzip")]
+    zstd: bool,
+    /// Level of zstd output from 1 (fastest) to 19 (smallest).
+    #[arg(long, requires = "zstd", value_parser = clap::value_parser!(i32).range(1..=19))]
+    compression_level: Option<i32>,
+    /// Zstd dictionary to read input and write output with.
+    #[arg(long)]
+    dictionary: Option<String>,
+    /// Decrypt input with passphrase read from this file.
+    #[arg(long)]
+    decrypt_with: Option<String>,
+    /// Encrypt output with passphrase read from this file.
+    #[arg(long)]
+    encrypt_with: Option<String>,
+    /// Verify HMAC-SHA256 trailer of input with key read from this file.
+    #[arg(long)]
+    verify_with: Option<String>,
+    /// Append HMAC-SHA256 trailer to output signed with key read from this file.
+    #[arg(long)]
+    sign_with: Option<String>,
+    /// Append records to this binary file instead of writing them to stdout.
+    #[arg(
+        long,
+        conflicts_with_all = ["gzip", "zstd", "encrypt_with", "sign_with"]
+    )]
+    append: Option<String>,
+    /// Quoting of CSV output, DESCRIPTION included; it is always quoted by default.
+    #[arg(long)]
+    csv_quoting: Option<Quoting>,
+    /// Omit header line of CSV and TSV output.
+    #[arg(long)]
+    no_csv_header: bool,
+    /// Order of output records, stable so regenerated files diff cleanly.
+    #[arg(long, default_value = "input")]
+    order: Order,
+    /// Sort output by comma separated keys, e.g. `TIMESTAMP:DESC,TX_ID`; stable like `--order`.
+    #[arg(long, value_parser = parse_sort_order, conflicts_with = "order")]
+    sort: Option<SortOrder>,
+    /// Write canonical output: records sorted by id with trimmed descriptions.
+    #[arg(long, conflicts_with_all = ["order", "sort"])]
+    canonical: bool,
+    /// Omit line break at the end of textual output.
+    #[arg(long)]
+    no_trailing_newline: bool,
+    /// Longest description of output in characters.
+    #[arg(long)]
+    max_description_chars: Option<usize>,
+    /// Handling of descriptions longer than `--max-description-chars`.
+    #[arg(long, default_value = "truncate", requires = "max_description_chars")]
+    overlong_descriptions: Overlong,
+}
+
+fn parse_csv_default(s: &str) -> Result<(TxFieldKey, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("`{}` is not KEY=VALUE", s))?;
+    let key = key.parse().map_err(|e: ParserError| e.to_string())?;
+    Ok((key, value.to_string()))
+}
+
+fn parse_key_alias(s: &str) -> Result<(String, TxFieldKey), String> {
+    let (alias, key) = s
+        .split_once('=')
+        .ok_or_else(|| format!("`{}` is not ALIAS=KEY", s))?;
+    let key = key.parse().map_err(|e: ParserError| e.to_string())?;
+    Ok((alias.to_string(), key))
+}
+
+fn parse_filter(s: &str) -> Result<Filter, String> {
+    s.parse().map_err(|e: AppError| e.to_string())
+}
+
+fn parse_group_by(s: &str) -> Result<GroupBy, String> {
+    s.parse().map_err(|e: ParserError| e.to_string())
+}
+
+fn parse_window(s: &str) -> Result<Window, String> {
+    s.parse().map_err(|e: ParserError| e.to_string())
+}
+
+fn parse_sort_order(s: &str) -> Result<SortOrder, String> {
+    s.parse().map_err(|e: ParserError| e.to_string())
+}
+
+fn expected_totals(args: &CliArgs) -> Result<ControlTotals, Box<dyn std::error::Error>> {
+    let from_file = match &args.control_file {
+        Some(path) => {
+            let f = open_file(path)?;
+            ControlTotals::from_reader(f)?
+        }
+        None => ControlTotals::default(),
+    };
+    Ok(from_file.overridden_by(ControlTotals {
+        record_count: args.expect_count,
+        total_amount: args.expect_total,
+    }))
+}
+
+// `.json` files hold an object of names, others are CSV
+fn account_directory(
+    path: &Option<String>,
+) -> Result<Option<AccountDirectory>, Box<dyn std::error::Error>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let f = open_file(path)?;
+    let directory = if path.to_ascii_lowercase().ends_with(".json") {
+        AccountDirectory::read_json(f)?
+    } else {
+        AccountDirectory::read_csv(f)?
+    };
+    Ok(Some(directory))
 }
 
-fn run(args: CliArgs) -> Result<(), Box<dyn std::error::Error>> {
-    let f = File::open(&args.input).map_err(|e| {
-        std::io::Error::new(
-            e.kind(),
-            format!("Error opening a file {} {}", args.input, e),
-        )
+fn account_metadata(
+    path: &Option<String>,
+) -> Result<Option<AccountMetadata>, Box<dyn std::error::Error>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let f = open_file(path)?;
+    let metadata = if path.to_ascii_lowercase().ends_with(".json") {
+        AccountMetadata::read_json(f)?
+    } else {
+        AccountMetadata::read_csv(f)?
+    };
+    Ok(Some(metadata))
+}
+
+// key is the file content without trailing line break
+fn read_secret(path: &Option<String>) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let secret = std::fs::read(path).map_err(|e| {
+        std::io::Error::new(e.kind(), format!("Error reading a file {} {}", path, e))
     })?;
+    let len = secret.len()
+        - secret
+            .iter()
+            .rev()
+            .take_while(|&&b| b == b'\n' || b == b'\r')
+            .count();
+    Ok(Some(secret[..len].to_vec()))
+}
+
+fn write_output<W: Write>(
+    codec: &Codec,
+    w: &mut W,
+    data: &[TxRecord],
+    options: &WriteOptions,
+    compression: Option<Compression>,
+) -> Result<(), AppError> {
+    match compression {
+        Some(compression) => codec.write_compressed(w, data, options, compression),
+        None => codec.write_with(w, data, options),
+    }
+}
+
+// content is compressed before encryption, ciphertext does not compress
+fn write_sealed<W: Write>(
+    codec: &Codec,
+    mut w: W,
+    data: &[TxRecord],
+    options: &WriteOptions,
+    compression: Option<Compression>,
+    key: Option<EncryptionKey>,
+) -> Result<W, AppError> {
+    match key {
+        Some(key) => {
+            let mut w = EncryptedWriter::new(w, &key);
+            write_output(codec, &mut w, data, options, compression)?;
+            w.finish()
+        }
+        None => {
+            write_output(codec, &mut w, data, options, compression)?;
+            Ok(w)
+        }
+    }
+}
+
+// signature covers the output as it is stored
+fn write_signed<W: Write>(
+    codec: &Codec,
+    w: W,
+    data: &[TxRecord],
+    options: &WriteOptions,
+    compression: Option<Compression>,
+    encryption: Option<EncryptionKey>,
+    signing: Option<&SigningKey>,
+) -> Result<(), AppError> {
+    match signing {
+        Some(key) => {
+            let w = SignedWriter::new(w, key);
+            write_sealed(codec, w, data, options, compression, encryption)?.finish()?;
+        }
+        None => {
+            write_sealed(codec, w, data, options, compression, encryption)?;
+        }
+    }
+    Ok(())
+}
+
+// partition values may hold characters not allowed in file names
+fn partition_file_stem(value: &GroupValue) -> String {
+    value
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn run(args: CliArgs, input_format: Format) -> Result<(), Box<dyn std::error::Error>> {
+    let f = open_file(&args.input)?;
+
+    let expected = expected_totals(&args)?;
+    let accounts = account_directory(&args.accounts)?;
+    let metadata = account_metadata(&args.account_metadata)?;
+    let redaction = match &args.redact {
+        Some(path) => Some(RedactionPolicy::from_reader(open_file(path)?)?),
+        None => None,
+    };
 
     let stdout = &mut std::io::stdout().lock();
-    let data = args.input_format.codec().parse(f)?;
+    let codec = input_format.codec();
+    let mut options = ParseOptions {
+        buffer_size: BufferSize::adaptive_for(&f),
+        ..Default::default()
+    };
+    if let Some(delimiter) = args.csv_delimiter {
+        options.csv.delimiter = delimiter;
+    }
+    options.csv.defaults = args.csv_defaults.clone();
+    options.csv.column_names = args.csv_columns.clone();
+    options.text.lenient_keys = args.lenient_keys;
+    options.text.keep_extras = args.keep_extras;
+    if let Some(separator) = &args.text_separator {
+        options.text.separator = RecordSeparator::Line(separator.clone());
+    }
+    options.csv.keep_extras = args.keep_extras;
+    options.text.aliases = args.key_aliases.clone();
+    options.text.accounts = accounts.clone();
+    options.binary.resync = args.resync;
+    if args.strict {
+        options.strictness = Strictness::Strict;
+    }
+    if let Some(path) = &args.rules {
+        options.validation = Validator::from_reader(open_file(path)?)?;
+    }
+    options.binary.endianness = args.input_endianness.endianness();
+    options.encoding = args.input_encoding.text_encoding();
+    options.csv.timestamps = args.input_timestamps.format_at(args.input_utc_offset);
+    options.text.timestamps = args.input_timestamps.format_at(args.input_utc_offset);
+    if let Some(scale) = args.input_amount_scale {
+        options.csv.amounts = AmountFormat::Decimal { scale };
+        options.text.amounts = AmountFormat::Decimal { scale };
+    }
+    if args.exact_amounts {
+        options.csv.amounts = AmountFormat::Exact;
+        options.text.amounts = AmountFormat::Exact;
+    }
+    options.limits = ParseLimits {
+        max_record_size: args.max_record_size,
+        max_block_size: args.max_block_size,
+        max_description_len: args.max_description_len,
+        max_records: args.max_records.unwrap_or(usize::MAX),
+    };
+    if let Some(path) = &args.dictionary {
+        options.compression.dictionary = Some(std::fs::read(path).map_err(|e| {
+            std::io::Error::new(e.kind(), format!("Error reading a file {} {}", path, e))
+        })?);
+    }
+    // signature covers the input as it is stored
+    let mut f: Box<dyn Read> = match read_secret(&args.verify_with)? {
+        Some(secret) => Box::new(SignedReader::new(f, &SigningKey::new(secret))?),
+        None => Box::new(f),
+    };
+    let f: Box<dyn Read> = match read_secret(&args.decrypt_with)? {
+        Some(secret) => Box::new(EncryptedReader::new(f, &EncryptionKey::new(secret))?),
+        None => {
+            let mut prefix = Vec::new();
+            (&mut f).take(4).read_to_end(&mut prefix)?;
+            if is_encrypted(&prefix) {
+                return Err("input is encrypted, pass --decrypt-with".into());
+            }
+            Box::new(Cursor::new(prefix).chain(f))
+        }
+    };
+    let f = CompressedReader::with_limits(f, &options.compression, options.limits)?;
+    let data = match &args.quarantine {
+        Some(path) => {
+            let q = create_file(path)?;
+            let mut quarantine = QuarantineWriter::new(BufWriter::new(q));
+            let data = codec.parse_quarantined(f, &options, &mut quarantine)?;
+            println!("{} records quarantined to '{}'", quarantine.count(), path);
+            quarantine.into_inner()?;
+            data
+        }
+        None => codec.parse_with(f, &options)?,
+    };
     println!("{} records successfully ingested\n", data.len());
 
-    args.output_format.codec().write(stdout, &data)?;
+    let mismatches = expected.check(&data);
+    if !mismatches.is_empty() {
+        let details: Vec<String> = mismatches.iter().map(|m| m.to_string()).collect();
+        return Err(format!("reconciliation failed: {}", details.join("; ")).into());
+    }
+    let data = match &args.filter {
+        Some(filter) => {
+            let matching = filter.apply(&data);
+            println!("{} records filtered out\n", data.len() - matching.len());
+            matching
+        }
+        None => data,
+    };
+    let mut data = match args.dedup {
+        Some(kind) => {
+            let deduplicated = dedup::dedup(&data, &kind.record_key());
+            println!("{} duplicate records removed\n", deduplicated.removed);
+            deduplicated.records
+        }
+        None => data,
+    };
+    if let Some(order) = &args.sort {
+        order.sort(&mut data);
+    }
+    if let Some(account) = &args.running_balance {
+        let account = match &accounts {
+            Some(directory) => directory.parse_account(account)?,
+            None => account.parse()?,
+        };
+        let opening = args.opening_balance.unwrap_or_default();
+        data = with_running_balance(&data, &account, opening)?;
+    }
+    if let Some(metadata) = &metadata {
+        data = join(&data, metadata)
+            .into_iter()
+            .map(EnrichedRecord::into_record)
+            .collect();
+    }
+    if let Some(policy) = &redaction {
+        data = policy.redact_all(&data);
+    }
+    if let Some(format) = args.summary {
+        println!("{}", format.render(&data));
+    }
+    if !args.aggregate.is_empty() {
+        let dimensions: Vec<GroupBy> = args
+            .aggregate
+            .iter()
+            .map(|dimension| match (dimension, args.output_utc_offset) {
+                (GroupBy::Day(_), Some(offset)) => GroupBy::Day(offset),
+                _ => *dimension,
+            })
+            .collect();
+        aggregate(&data, &dimensions)?.write_csv(stdout)?;
+        return Ok(());
+    }
+    if let Some(window) = args.bucket {
+        let offset = args.output_utc_offset.unwrap_or(UtcOffset::UTC);
+        data = bucket(&data, window, offset)?.records();
+    }
+
+    let mut write_options = WriteOptions {
+        compression: options.compression.clone(),
+        ..Default::default()
+    };
+    if let Some(level) = args.compression_level {
+        write_options.compression.level = level;
+    }
+    if args.binary_header {
+        write_options.binary.version = BinaryVersion::V2;
+    }
+    if args.binary_varint {
+        write_options.binary.version = BinaryVersion::V3;
+    }
+    write_options.binary.checksum = args.binary_checksum;
+    write_options.binary.index = args.binary_index;
+    write_options.binary.endianness = args.output_endianness.endianness();
+    write_options.binary_v2.dedup_descriptions = args.dedup_descriptions;
+    if let Some(quoting) = args.csv_quoting {
+        write_options.csv.quoting = quoting.policy();
+        write_options.csv.quote_description = false;
+    }
+    write_options.csv.header = !args.no_csv_header;
+    write_options.csv.column_names = args.csv_columns.clone();
+    write_options.csv.timestamps = args.output_timestamps.format_at(args.output_utc_offset);
+    write_options.text.timestamps = args.output_timestamps.format_at(args.output_utc_offset);
+    write_options.text.separator = options.text.separator.clone();
+    write_options.text.accounts = accounts.clone();
+    write_options.report.accounts = accounts;
+    if let Some(scale) = args.output_amount_scale {
+        write_options.csv.amounts = AmountFormat::Decimal { scale };
+        write_options.text.amounts = AmountFormat::Decimal { scale };
+    }
+    if args.exact_amounts {
+        write_options.csv.amounts = AmountFormat::Exact;
+        write_options.text.amounts = AmountFormat::Exact;
+    }
+    write_options.order = args.order.record_order();
+    write_options.canonical = args.canonical;
+    if args.no_trailing_newline {
+        write_options.trailing_newline = TrailingNewline::Trim;
+    }
+    if let Some(max_chars) = args.max_description_chars {
+        let limit = DescriptionLimit {
+            max_chars,
+            overlong: args.overlong_descriptions.handling(),
+        };
+        if matches!(args.overlong_descriptions, Overlong::Skip) {
+            let overlong = data.iter().filter(|tx| limit.exceeds(tx)).count();
+            println!(
+                "{} records with descriptions over {} characters left out",
+                overlong, max_chars
+            );
+        }
+        write_options.description_limit = Some(limit);
+    }
+    if let Some(path) = &args.append {
+        if !matches!(args.output_format, Format::Binary) {
+            return Err("--append needs binary output format".into());
+        }
+        let f = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|e| {
+                std::io::Error::new(e.kind(), format!("Error opening a file {} {}", path, e))
+            })?;
+        let records = binary::append(f, &data, &write_options.binary)?;
+        println!(
+            "{} records appended, '{}' holds {}",
+            data.len(),
+            path,
+            records
+        );
+        return Ok(());
+    }
+    let codec = args.output_format.codec();
+    let compression = if args.gzip {
+        Some(Compression::Gzip)
+    } else if args.zstd {
+        Some(Compression::Zstd)
+    } else {
+        None
+    };
+    let encryption = read_secret(&args.encrypt_with)?.map(EncryptionKey::new);
+    let signing = read_secret(&args.sign_with)?.map(SigningKey::new);
+    if let (Some(by), Some(dir)) = (args.split_by, &args.split_dir) {
+        let by = match (by, args.output_utc_offset) {
+            (GroupBy::Day(_), Some(offset)) => GroupBy::Day(offset),
+            _ => by,
+        };
+        let mut partitions: Vec<_> = split(&data, by).into_iter().collect();
+        partitions.sort_by(|a, b| a.0.cmp(&b.0));
+        for (value, records) in partitions {
+            let path = Path::new(dir).join(format!(
+                "{}.{}",
+                partition_file_stem(&value),
+                args.output_format.extension()
+            ));
+            let f = create_file(&path)?;
+            write_signed(
+                &codec,
+                f,
+                &records,
+                &write_options,
+                compression,
+                encryption.clone(),
+                signing.as_ref(),
+            )?;
+            println!("{} records written to '{}'", records.len(), path.display());
+        }
+        return Ok(());
+    }
+    write_signed(
+        &codec,
+        stdout,
+        &data,
+        &write_options,
+        compression,
+        encryption,
+        signing.as_ref(),
+    )?;
     Ok(())
 }
 
@@ -33,12 +696,24 @@ fn main() {
     // parse args
     let args = CliArgs::parse();
 
+    let Some(input_format) = args
+        .input_format
+        .clone()
+        .or_else(|| Format::from_path(&args.input))
+    else {
+        eprintln!(
+            "Error occured during application execution: cannot infer format of '{}' from its extension, pass --input-format",
+            args.input
+        );
+        std::process::exit(1);
+    };
+
     // run app
     println!(
         "Converting from '{}':{} to :{}",
-        args.input, args.input_format, args.output_format
+        args.input, input_format, args.output_format
     );
-    let app_result = run(args);
+    let app_result = run(args, input_format);
 
     // handle errors
     if let Err(e) = app_result {
@@ -0,0 +1,82 @@
+use clap::Parser;
+use parser::domain::tx::TxRecord;
+use parser::transform::merge::merge;
+use rustyapa::cli_format::{Format, OnConflict};
+
+#[derive(Parser, Debug)]
+struct CliArgs {
+    /// Source file, may be repeated; sources are merged in order.
+    #[arg(long = "input", required = true)]
+    inputs: Vec<String>,
+    /// Format of all inputs, inferred from their extensions when omitted.
+    #[arg(long)]
+    input_format: Option<Format>,
+    #[arg(long)]
+    output_format: Format,
+    /// Resolution of records sharing an id but differing in other fields.
+    #[arg(long, default_value = "keep-first")]
+    on_conflict: OnConflict,
+    /// Maximum number of conflicts printed.
+    #[arg(long, default_value_t = 10)]
+    max_issues: usize,
+}
+
+fn run(args: CliArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut sources: Vec<Vec<TxRecord>> = Vec::new();
+    for input in &args.inputs {
+        let Some(format) = args
+            .input_format
+            .clone()
+            .or_else(|| Format::from_path(input))
+        else {
+            return Err(format!(
+                "cannot infer format of '{}' from its extension, pass --input-format",
+                input
+            )
+            .into());
+        };
+        sources.push(format.parse_path(input)?);
+    }
+    let sources: Vec<&[TxRecord]> = sources.iter().map(Vec::as_slice).collect();
+    let merged = merge(&sources, args.on_conflict.policy())?;
+
+    let kept = merged.kept();
+    for (i, input) in args.inputs.iter().enumerate() {
+        println!(
+            "'{}': {} records read, {} kept",
+            input, merged.read[i], kept[i]
+        );
+    }
+    println!(
+        "{} records merged, {} duplicates dropped, {} conflicts resolved\n",
+        merged.records.len(),
+        merged.duplicates,
+        merged.conflicts.len()
+    );
+    for conflict in merged.conflicts.iter().take(args.max_issues) {
+        println!("{}", conflict);
+    }
+    if !merged.conflicts.is_empty() {
+        println!();
+    }
+
+    let stdout = &mut std::io::stdout().lock();
+    args.output_format.codec().write(stdout, &merged.records)?;
+    Ok(())
+}
+
+fn main() {
+    // parse args
+    let args = CliArgs::parse();
+
+    // run app
+    println!(
+        "Merging {} sources to :{}",
+        args.inputs.len(),
+        args.output_format
+    );
+    if let Err(e) = run(args) {
+        eprintln!("Error occured during application execution: {}", e);
+        std::process::exit(1);
+    }
+}
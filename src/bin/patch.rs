@@ -0,0 +1,92 @@
+use std::io::{BufWriter, Write};
+
+use clap::Parser;
+use parser::transform::patch::{Amendment, apply_patch};
+use rustyapa::cli_format::{Format, create_file, open_file};
+
+#[derive(Parser, Debug)]
+struct CliArgs {
+    /// Base dataset.
+    #[arg(long)]
+    input: String,
+    /// Format of input, inferred from its extension when omitted.
+    #[arg(long)]
+    input_format: Option<Format>,
+    /// Corrections, blocks of `KEY: VALUE` lines with `TX_ID` and fields to change.
+    #[arg(long)]
+    amendments: String,
+    #[arg(long)]
+    output_format: Format,
+    /// Write every applied change, a line per amended record, to the file.
+    #[arg(long)]
+    audit: Option<String>,
+    /// Fail if an amendment matches no record of the base dataset.
+    #[arg(long)]
+    fail_on_unmatched: bool,
+    /// Maximum number of changes and unmatched amendments printed.
+    #[arg(long, default_value_t = 10)]
+    max_issues: usize,
+}
+
+fn run(args: CliArgs, input_format: Format) -> Result<(), Box<dyn std::error::Error>> {
+    let base = input_format.parse_path(&args.input)?;
+    let amendments = Amendment::read(open_file(&args.amendments)?)?;
+    let patched = apply_patch(&base, &amendments);
+
+    println!(
+        "{} amendments read, {} records changed, {} already up to date, {} unmatched\n",
+        amendments.len(),
+        patched.applied.len(),
+        patched.unchanged,
+        patched.unmatched.len()
+    );
+    for change in patched.applied.iter().take(args.max_issues) {
+        println!("{}", change);
+    }
+    for id in patched.unmatched.iter().take(args.max_issues) {
+        println!("#{} matches no record", id);
+    }
+    if !patched.applied.is_empty() || !patched.unmatched.is_empty() {
+        println!();
+    }
+    if args.fail_on_unmatched && !patched.unmatched.is_empty() {
+        return Err(format!("{} amendments match no record", patched.unmatched.len()).into());
+    }
+    if let Some(path) = &args.audit {
+        let mut audit = BufWriter::new(create_file(path)?);
+        for change in &patched.applied {
+            writeln!(audit, "{}", change)?;
+        }
+        audit.flush()?;
+    }
+
+    let stdout = &mut std::io::stdout().lock();
+    args.output_format.codec().write(stdout, &patched.records)?;
+    Ok(())
+}
+
+fn main() {
+    // parse args
+    let args = CliArgs::parse();
+    let Some(input_format) = args
+        .input_format
+        .clone()
+        .or_else(|| Format::from_path(&args.input))
+    else {
+        eprintln!(
+            "Error occured during application execution: cannot infer format of '{}' from its extension, pass --input-format",
+            args.input
+        );
+        std::process::exit(1);
+    };
+
+    // run app
+    println!(
+        "Patching '{}' :{} to :{}",
+        args.input, input_format, args.output_format
+    );
+    if let Err(e) = run(args, input_format) {
+        eprintln!("Error occured during application execution: {}", e);
+        std::process::exit(1);
+    }
+}
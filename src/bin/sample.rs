@@ -0,0 +1,92 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use clap::Parser;
+use parser::codecs::options::{BufferSize, ParseOptions};
+use parser::transform::pipeline::Pipeline;
+use parser::transform::sample::Sampling;
+use rustyapa::cli_format::{Format, open_file};
+
+#[derive(Parser, Debug)]
+#[command(group(clap::ArgGroup::new("sampling").required(true).args(["first", "every", "reservoir"])))]
+struct CliArgs {
+    #[arg(long)]
+    input: String,
+    /// Format of input, inferred from its extension when omitted.
+    #[arg(long)]
+    input_format: Option<Format>,
+    #[arg(long)]
+    output_format: Format,
+    /// Write the first N records, the rest of input is not read.
+    #[arg(long, value_name = "N")]
+    first: Option<usize>,
+    /// Write every N-th record starting from the first one.
+    #[arg(long, value_name = "N")]
+    every: Option<usize>,
+    /// Write N records picked uniformly at random, holding only them in memory.
+    #[arg(long, value_name = "N")]
+    reservoir: Option<usize>,
+    /// Seed of `--reservoir` picks, the same seed picks the same records; random when omitted.
+    #[arg(long, requires = "reservoir")]
+    seed: Option<u64>,
+}
+
+fn sampling(args: &CliArgs) -> Sampling {
+    match (args.first, args.every, args.reservoir) {
+        (Some(n), _, _) => Sampling::First(n),
+        (_, Some(n), _) => Sampling::EveryNth(n),
+        (_, _, size) => Sampling::Reservoir {
+            size: size.unwrap_or_default(),
+            // printed, so the sample can be picked again
+            seed: args
+                .seed
+                .unwrap_or_else(|| RandomState::new().build_hasher().finish()),
+        },
+    }
+}
+
+fn run(args: CliArgs, input_format: Format) -> Result<(), Box<dyn std::error::Error>> {
+    let sampling = sampling(&args);
+    if let Sampling::Reservoir { seed, .. } = sampling {
+        println!("seed {}\n", seed);
+    }
+    let f = open_file(&args.input)?;
+    let options = ParseOptions {
+        buffer_size: BufferSize::adaptive_for(&f),
+        ..Default::default()
+    };
+    let records = input_format.codec().parse_stream(f, &options);
+    let stdout = std::io::stdout().lock();
+    let sink = args
+        .output_format
+        .codec()
+        .open_sink(stdout, &Default::default())?;
+    Pipeline::new().sample(sampling).run_into(records, sink)?;
+    Ok(())
+}
+
+fn main() {
+    // parse args
+    let args = CliArgs::parse();
+    let Some(input_format) = args
+        .input_format
+        .clone()
+        .or_else(|| Format::from_path(&args.input))
+    else {
+        eprintln!(
+            "Error occured during application execution: cannot infer format of '{}' from its extension, pass --input-format",
+            args.input
+        );
+        std::process::exit(1);
+    };
+
+    // run app
+    println!(
+        "Sampling '{}' :{} to :{}",
+        args.input, input_format, args.output_format
+    );
+    if let Err(e) = run(args, input_format) {
+        eprintln!("Error occured during application execution: {}", e);
+        std::process::exit(1);
+    }
+}
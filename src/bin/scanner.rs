@@ -0,0 +1,67 @@
+use clap::Parser;
+use parser::codecs::binary::{self, BinaryParseOptions};
+use rustyapa::cli_format::ByteOrder;
+use std::{fs::File, io::BufReader};
+
+#[derive(Parser, Debug)]
+struct CliArgs {
+    #[arg(long)]
+    input: String,
+    /// Maximum number of issues printed, the whole file is scanned anyway.
+    #[arg(long, default_value_t = 10)]
+    max_issues: usize,
+    /// Byte order of the file.
+    #[arg(long, default_value = "big")]
+    endianness: ByteOrder,
+}
+
+fn run(args: CliArgs) -> Result<bool, Box<dyn std::error::Error>> {
+    let f = File::open(&args.input).map_err(|e| {
+        std::io::Error::new(
+            e.kind(),
+            format!("Error opening a file {} {}", args.input, e),
+        )
+    })?;
+
+    let options = BinaryParseOptions {
+        endianness: args.endianness.endianness(),
+        ..Default::default()
+    };
+    let report = binary::scan(BufReader::new(f), &options, args.max_issues)?;
+    println!(
+        "{} records, {} bytes scanned in {:?} ({:.1} MB/s)",
+        report.records,
+        report.bytes,
+        report.elapsed,
+        report.bytes_per_second() / 1_000_000.0
+    );
+    for issue in &report.issues {
+        println!("offset #{}: {}", issue.offset, issue.error);
+    }
+    if report.issues.len() < report.issues_found {
+        println!(
+            "{} more issues not printed",
+            report.issues_found - report.issues.len()
+        );
+    }
+    if report.aborted {
+        println!("framing is lost, rest of the file was not scanned");
+    }
+    Ok(report.is_healthy())
+}
+
+fn main() {
+    // parse args
+    let args = CliArgs::parse();
+
+    // run app
+    println!("Scanning binary file '{}'", args.input);
+    match run(args) {
+        Ok(true) => println!("No structural issues found."),
+        Ok(false) => std::process::exit(2),
+        Err(e) => {
+            eprintln!("Error occured during application execution: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use clap::Parser;
+use parser::validation::{Severity, Validator};
+use rustyapa::cli_format::{Format, open_file};
+
+#[derive(Parser, Debug)]
+struct CliArgs {
+    #[arg(long)]
+    input: String,
+    /// Format of input, inferred from its extension when omitted.
+    #[arg(long)]
+    input_format: Option<Format>,
+    /// Rules to check besides built-in ones, `SEVERITY NAME: FILTER` per line.
+    #[arg(long)]
+    rules: Option<String>,
+    /// Skip built-in rules (unique ids, positive amounts).
+    #[arg(long)]
+    no_builtin: bool,
+    /// Maximum number of violations printed.
+    #[arg(long, default_value_t = 10)]
+    max_issues: usize,
+}
+
+fn run(args: CliArgs, input_format: Format) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut validator = match args.no_builtin {
+        true => Validator::new(),
+        false => Validator::builtin(),
+    };
+    if let Some(path) = &args.rules {
+        for rule in Validator::from_reader(open_file(path)?)?.rules() {
+            validator = validator.with_rule(rule.clone());
+        }
+    }
+    let data = input_format.parse_path(&args.input)?;
+    let report = validator.validate(&data);
+    println!(
+        "{} records checked by {} rules: {} errors, {} warnings, {} notes\n",
+        data.len(),
+        validator.rules().len(),
+        report.count(Severity::Error),
+        report.count(Severity::Warning),
+        report.count(Severity::Info)
+    );
+    for violation in report.violations.iter().take(args.max_issues) {
+        println!("{}", violation);
+    }
+    Ok(report.is_valid())
+}
+
+fn main() {
+    // parse args
+    let args = CliArgs::parse();
+
+    let Some(input_format) = args
+        .input_format
+        .clone()
+        .or_else(|| Format::from_path(&args.input))
+    else {
+        eprintln!(
+            "Error occured during application execution: cannot infer format of '{}' from its extension, pass --input-format",
+            args.input
+        );
+        std::process::exit(1);
+    };
+
+    // run app
+    println!("Validating '{}':{}", args.input, input_format);
+    match run(args, input_format) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(2),
+        Err(e) => {
+            eprintln!("Error occured during application execution: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,35 +1,389 @@
 use std::fmt::Display;
+use std::fs::File;
+use std::io;
+use std::path::Path;
 
 use clap::ValueEnum;
+use parser::analytics::summarize;
 use parser::codecs::base::Codec;
+use parser::codecs::binary::Endianness;
+use parser::codecs::csv::CsvQuoting;
+use parser::codecs::options::{
+    BufferSize, OverlongDescription, ParseOptions, RecordOrder, TextEncoding, TimestampFormat,
+    WriteOptions,
+};
+use parser::domain::key::RecordKey;
+use parser::domain::tx::{TxRecord, UtcOffset};
+use parser::errors::AppError;
+use parser::transform::dedup;
+use parser::transform::merge::ConflictPolicy;
 
 /// Supported formats
 #[derive(Clone, Debug, ValueEnum)]
 pub enum Format {
     /// Binary file format.
     Binary,
+    /// Delta-encoded binary file format (YPB2).
+    BinaryV2,
+    /// Column-wise binary file format (YPBN-C).
+    Columnar,
     /// Text file format.
     Text,
+    /// Compact bincode layout dump, for intermediate results.
+    Bincode,
+    /// Cap'n Proto messages, see bundled schema.
+    Capnp,
     /// CSV file format.
     Csv,
+    /// Tab-separated file format.
+    Tsv,
+    /// ISO 20022 camt.053 XML statement (read only).
+    Camt053,
+    /// FIX execution reports log (read only).
+    Fix,
+    /// BAI2 cash management file (read only).
+    Bai2,
+    /// Excel workbook, records on the first sheet.
+    Xlsx,
+    /// GitHub-flavored markdown table (write only).
+    Markdown,
+    /// ledger-cli journal (write only).
+    Ledger,
+    /// Human-readable report for terminals (write only).
+    Report,
 }
 impl Format {
     /// Returns format-specific codec.
     pub fn codec(&self) -> Codec {
         match &self {
             Format::Binary => Codec::BinaryCodec,
+            Format::BinaryV2 => Codec::BinaryV2Codec,
+            Format::Columnar => Codec::ColumnarBinaryCodec,
             Format::Text => Codec::TextCodec,
+            Format::Bincode => Codec::BincodeCodec,
+            Format::Capnp => Codec::CapnpCodec,
             Format::Csv => Codec::CsvCodec,
+            Format::Tsv => Codec::TsvCodec,
+            Format::Camt053 => Codec::CamtCodec,
+            Format::Fix => Codec::FixCodec,
+            Format::Bai2 => Codec::Bai2Codec,
+            Format::Xlsx => Codec::XlsxCodec,
+            Format::Markdown => Codec::MarkdownCodec,
+            Format::Ledger => Codec::LedgerCodec,
+            Format::Report => Codec::ReportCodec,
         }
     }
+
+    /// Extension of files in the format, inferred back by [`Format::from_path`] where the
+    /// format is readable.
+    pub fn extension(&self) -> &'static str {
+        match &self {
+            Format::Binary | Format::BinaryV2 | Format::Columnar => "bin",
+            Format::Text | Format::Report => "txt",
+            Format::Bincode => "bincode",
+            Format::Capnp => "capnp",
+            Format::Csv => "csv",
+            Format::Tsv => "tsv",
+            Format::Camt053 => "xml",
+            Format::Fix => "fix",
+            Format::Bai2 => "bai2",
+            Format::Xlsx => "xlsx",
+            Format::Markdown => "md",
+            Format::Ledger => "ledger",
+        }
+    }
+
+    /// Infers format of file from its extension, e.g. `csv` of `data.csv`;
+    /// extensions are matched ignoring case.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Format> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "bin" => Some(Format::Binary),
+            "txt" => Some(Format::Text),
+            "bincode" => Some(Format::Bincode),
+            "capnp" => Some(Format::Capnp),
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            "xml" => Some(Format::Camt053),
+            "fix" => Some(Format::Fix),
+            "bai" | "bai2" => Some(Format::Bai2),
+            "xlsx" => Some(Format::Xlsx),
+            "md" => Some(Format::Markdown),
+            "ledger" | "journal" => Some(Format::Ledger),
+            _ => None,
+        }
+    }
+
+    /// Reads records from file at `path`, IO errors name the file.
+    pub fn parse_path(&self, path: impl AsRef<Path>) -> Result<Vec<TxRecord>, AppError> {
+        let path = path.as_ref();
+        let f = open_file(path).map_err(AppError::ReadError)?;
+        let options = ParseOptions {
+            buffer_size: BufferSize::adaptive_for(&f),
+            ..Default::default()
+        };
+        self.codec()
+            .parse_with(f, &options)
+            .map_err(|e| naming_file(e, path))
+    }
+
+    /// Writes records to file at `path`, replacing its content; IO errors name the file.
+    pub fn write_path(&self, path: impl AsRef<Path>, data: &[TxRecord]) -> Result<(), AppError> {
+        let path = path.as_ref();
+        let mut f = create_file(path).map_err(AppError::WriteError)?;
+        let options = WriteOptions {
+            buffer_size: BufferSize::adaptive_for(&f),
+            ..Default::default()
+        };
+        self.codec()
+            .write_with(&mut f, data, &options)
+            .map_err(|e| naming_file(e, path))
+    }
+}
+
+/// Opens file for reading, error names the file.
+pub fn open_file(path: impl AsRef<Path>) -> io::Result<File> {
+    let path = path.as_ref();
+    File::open(path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Error opening a file {} {}", path.display(), e),
+        )
+    })
+}
+
+/// Creates or truncates file for writing, error names the file.
+pub fn create_file(path: impl AsRef<Path>) -> io::Result<File> {
+    let path = path.as_ref();
+    File::create(path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Error creating a file {} {}", path.display(), e),
+        )
+    })
+}
+
+fn naming_file(e: AppError, path: &Path) -> AppError {
+    let named = |e: io::Error, action: &str| {
+        io::Error::new(
+            e.kind(),
+            format!("Error {} a file {} {}", action, path.display(), e),
+        )
+    };
+    match e {
+        AppError::ReadError(e) => AppError::ReadError(named(e, "reading")),
+        AppError::WriteError(e) => AppError::WriteError(named(e, "writing")),
+        e => e,
+    }
 }
 
 impl Display for Format {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Format::Binary => write!(f, "binary"),
+            Format::BinaryV2 => write!(f, "binary-v2"),
+            Format::Columnar => write!(f, "columnar"),
             Format::Text => write!(f, "text"),
+            Format::Bincode => write!(f, "bincode"),
+            Format::Capnp => write!(f, "capnp"),
             Format::Csv => write!(f, "csv"),
+            Format::Tsv => write!(f, "tsv"),
+            Format::Camt053 => write!(f, "camt053"),
+            Format::Fix => write!(f, "fix"),
+            Format::Bai2 => write!(f, "bai2"),
+            Format::Xlsx => write!(f, "xlsx"),
+            Format::Markdown => write!(f, "markdown"),
+            Format::Ledger => write!(f, "ledger"),
+            Format::Report => write!(f, "report"),
+        }
+    }
+}
+
+/// Quoting policies of written CSV
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Quoting {
+    /// Quote every field.
+    Always,
+    /// Quote fields with delimiters, quotes, line breaks or surrounding spaces.
+    Minimal,
+    /// Never quote, fail on fields which need quoting.
+    Never,
+}
+impl Quoting {
+    /// Returns matching CSV quoting policy.
+    pub fn policy(&self) -> CsvQuoting {
+        match self {
+            Quoting::Always => CsvQuoting::Always,
+            Quoting::Minimal => CsvQuoting::Minimal,
+            Quoting::Never => CsvQuoting::Never,
+        }
+    }
+}
+
+/// Orders of written records
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Order {
+    /// Keep order of the input.
+    Input,
+    /// Ascending ids.
+    Id,
+    /// Ascending timestamps, then ids.
+    Timestamp,
+}
+impl Order {
+    /// Returns matching record order.
+    pub fn record_order(&self) -> RecordOrder {
+        match self {
+            Order::Input => RecordOrder::Input,
+            Order::Id => RecordOrder::ById,
+            Order::Timestamp => RecordOrder::ByTimestamp,
+        }
+    }
+}
+
+/// Kinds of duplicate records
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Dedup {
+    /// Records equal in all fields.
+    Exact,
+    /// Records of the same id.
+    Id,
+    /// Records equal in all fields but timestamp, e.g. re-sent batches.
+    Resent,
+}
+impl Dedup {
+    /// Returns key records are deduplicated by.
+    pub fn record_key(&self) -> RecordKey {
+        match self {
+            Dedup::Exact => RecordKey::FullRecord,
+            Dedup::Id => RecordKey::IdOnly,
+            Dedup::Resent => dedup::resent_key(),
+        }
+    }
+}
+
+/// Resolutions of records sharing an id across merged sources
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum OnConflict {
+    /// Keep the record of the earliest source.
+    KeepFirst,
+    /// Keep the record of the latest timestamp.
+    KeepLatest,
+    /// Fail on the first conflict.
+    Error,
+}
+impl OnConflict {
+    /// Returns policy of the resolution.
+    pub fn policy(&self) -> ConflictPolicy {
+        match self {
+            OnConflict::KeepFirst => ConflictPolicy::KeepFirst,
+            OnConflict::KeepLatest => ConflictPolicy::KeepLatest,
+            OnConflict::Error => ConflictPolicy::Error,
+        }
+    }
+}
+
+/// Forms of dataset summary
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SummaryFormat {
+    /// Lines of text.
+    Text,
+    /// JSON object on one line.
+    Json,
+}
+impl SummaryFormat {
+    /// Returns summary of records in the form.
+    pub fn render(&self, data: &[TxRecord]) -> String {
+        let summary = summarize(data);
+        match self {
+            SummaryFormat::Text => summary.to_string(),
+            SummaryFormat::Json => format!("{}\n", summary.to_json()),
+        }
+    }
+}
+
+/// Handlings of descriptions over length limit
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Overlong {
+    /// Cut description ending it with ellipsis.
+    Truncate,
+    /// Leave the record out.
+    Skip,
+    /// Fail before anything is written.
+    Fail,
+}
+impl Overlong {
+    /// Returns matching overlong description handling.
+    pub fn handling(&self) -> OverlongDescription {
+        match self {
+            Overlong::Truncate => OverlongDescription::Truncate,
+            Overlong::Skip => OverlongDescription::Skip,
+            Overlong::Fail => OverlongDescription::Fail,
+        }
+    }
+}
+
+/// Forms of timestamps in text and CSV formats
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Timestamps {
+    /// Milliseconds since Unix epoch.
+    Millis,
+    /// RFC 3339 date-time in UTC.
+    Rfc3339,
+}
+impl Timestamps {
+    /// Returns matching timestamp format.
+    pub fn format(&self) -> TimestampFormat {
+        self.format_at(None)
+    }
+    /// Returns matching timestamp format, RFC 3339 date-times are in local time at `offset`.
+    pub fn format_at(&self, offset: Option<UtcOffset>) -> TimestampFormat {
+        match (self, offset) {
+            (Timestamps::Millis, _) => TimestampFormat::EpochMillis,
+            (Timestamps::Rfc3339, None) => TimestampFormat::Rfc3339,
+            (Timestamps::Rfc3339, Some(offset)) => TimestampFormat::Local(offset),
+        }
+    }
+}
+
+/// Character encodings of text and CSV input
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Encoding {
+    /// UTF-8, byte order mark is skipped.
+    Utf8,
+    /// Encoding of byte order mark, UTF-8 without it.
+    Detect,
+    /// UTF-16 little endian.
+    Utf16le,
+    /// UTF-16 big endian.
+    Utf16be,
+}
+impl Encoding {
+    /// Returns matching text encoding.
+    pub fn text_encoding(&self) -> TextEncoding {
+        match self {
+            Encoding::Utf8 => TextEncoding::Utf8,
+            Encoding::Detect => TextEncoding::Detect,
+            Encoding::Utf16le => TextEncoding::Utf16Le,
+            Encoding::Utf16be => TextEncoding::Utf16Be,
+        }
+    }
+}
+
+/// Byte orders of binary formats
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ByteOrder {
+    /// Most significant byte first.
+    Big,
+    /// Least significant byte first.
+    Little,
+}
+impl ByteOrder {
+    /// Returns matching binary endianness.
+    pub fn endianness(&self) -> Endianness {
+        match self {
+            ByteOrder::Big => Endianness::Big,
+            ByteOrder::Little => Endianness::Little,
         }
     }
 }